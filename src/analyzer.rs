@@ -0,0 +1,202 @@
+// Text analysis for the search index - splitting notes into terms
+//
+// Words are lowercased and stemmed for the configured language, so "interviews",
+// "interviewed" and "interviewing" all index as "interview". Chinese, Japanese and
+// Korean have no spaces between words; runs of those characters are cut into
+// overlapping two-character terms (研究方法 -> 研究 究方 方法), which finds words
+// without needing a dictionary. Queries go through the same analyzer.
+
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    None,  // Lowercase only
+    #[default]
+    English,
+    German,
+    French,
+    Spanish,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CjkMode {
+    #[default]
+    Bigram,   // Overlapping character pairs
+    Unigram,  // Single characters: more matches, less precise
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Analyzer {
+    pub language: Language,
+    pub cjk: CjkMode,
+}
+
+impl Analyzer {
+    /// Stored with the index; a different value means the index has to be rebuilt
+    pub fn signature(&self) -> String {
+        format!("v1 {:?} {:?}", self.language, self.cjk).to_lowercase()
+    }
+
+    /// Terms in text order (a term's position is its index)
+    pub fn terms(&self, text: &str) -> Vec<String> {
+        let mut terms = Vec::new();
+        let mut word = String::new();
+        let mut cjk: Vec<char> = Vec::new();
+
+        for c in text.chars() {
+            if is_cjk(c) {
+                self.flush_word(&mut word, &mut terms);
+                cjk.push(c);
+            } else if c.is_alphanumeric() {
+                self.flush_cjk(&mut cjk, &mut terms);
+                word.extend(c.to_lowercase());
+            } else {
+                self.flush_word(&mut word, &mut terms);
+                self.flush_cjk(&mut cjk, &mut terms);
+            }
+        }
+        self.flush_word(&mut word, &mut terms);
+        self.flush_cjk(&mut cjk, &mut terms);
+        terms
+    }
+
+    fn flush_word(&self, word: &mut String, terms: &mut Vec<String>) {
+        if !word.is_empty() {
+            terms.push(stem(self.language, word));
+            word.clear();
+        }
+    }
+
+    fn flush_cjk(&self, run: &mut Vec<char>, terms: &mut Vec<String>) {
+        match (self.cjk, run.len()) {
+            (_, 0) => {}
+            (CjkMode::Bigram, 1) | (CjkMode::Unigram, _) => terms.extend(run.iter().map(|c| c.to_string())),
+            (CjkMode::Bigram, _) => terms.extend(run.windows(2).map(|pair| pair.iter().collect::<String>())),
+        }
+        run.clear();
+    }
+}
+
+/// Han, kana and Hangul - scripts written without spaces between words
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF      // Hiragana, Katakana
+        | 0x3400..=0x4DBF    // CJK Extension A
+        | 0x4E00..=0x9FFF    // CJK Unified Ideographs
+        | 0xAC00..=0xD7AF    // Hangul syllables
+        | 0xF900..=0xFAFF    // CJK Compatibility Ideographs
+        | 0xFF66..=0xFF9F    // Half-width Katakana
+        | 0x20000..=0x2FA1F  // Extensions B-F, compatibility supplement
+    )
+}
+
+fn stem(language: Language, word: &str) -> String {
+    match language {
+        Language::None => word.to_string(),
+        Language::English => english(word),
+        Language::German => german(word),
+        Language::French => french(word),
+        Language::Spanish => spanish(word),
+    }
+}
+
+fn has_vowel(s: &str) -> bool {
+    s.chars().any(|c| "aeiouy".contains(c))
+}
+
+/// Light English stemmer: plurals, -ed, -ing and -ly
+fn english(word: &str) -> String {
+    let mut w = word.to_string();
+    if w.chars().count() <= 3 {
+        return w;
+    }
+
+    if let Some(stem) = w.strip_suffix("ies").filter(|s| s.len() > 1) {
+        w = format!("{}y", stem);
+    } else if let Some(stem) = w.strip_suffix("es").filter(|s| ["ss", "x", "z", "ch", "sh"].iter().any(|e| s.ends_with(*e))) {
+        w = stem.to_string();
+    } else if w.ends_with('s') && !["ss", "us", "is"].iter().any(|e| w.ends_with(*e)) {
+        w.pop();
+    }
+
+    for suffix in ["ing", "ed"] {
+        if let Some(stem) = w.strip_suffix(suffix).filter(|s| s.len() >= 3 && has_vowel(s)) {
+            w = undouble(stem);
+            break;
+        }
+    }
+    if let Some(stem) = w.strip_suffix("ly").filter(|s| s.len() >= 4) {
+        w = stem.to_string();
+    }
+    w
+}
+
+/// "runn" -> "run", but keep "fall", "miss", "buzz"
+fn undouble(stem: &str) -> String {
+    let chars: Vec<char> = stem.chars().collect();
+    match chars.as_slice() {
+        [.., a, b] if a == b && !"aeiouylsz".contains(*a) => chars[..chars.len() - 1].iter().collect(),
+        _ => stem.to_string(),
+    }
+}
+
+/// Replace accented vowels with plain ones (umlauts too)
+fn fold_accents(word: &str) -> String {
+    word.chars()
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ä' | 'ã' => 'a',
+            'è' | 'é' | 'ê' | 'ë' => 'e',
+            'ì' | 'í' | 'î' | 'ï' => 'i',
+            'ò' | 'ó' | 'ô' | 'ö' | 'õ' => 'o',
+            'ù' | 'ú' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            'ñ' => 'n',
+            other => other,
+        })
+        .collect()
+}
+
+/// Strip the first matching suffix, keeping at least `min` characters
+fn strip_any(word: &str, suffixes: &[&str], min: usize) -> Option<String> {
+    suffixes.iter()
+        .find_map(|suffix| word.strip_suffix(*suffix))
+        .filter(|stem| stem.chars().count() >= min)
+        .map(str::to_string)
+}
+
+/// Light German stemmer (inflection endings, then -est / -er / -en)
+fn german(word: &str) -> String {
+    let w = fold_accents(word).replace('ß', "ss");
+    let w = strip_any(&w, &["ern", "em", "en", "er", "es", "e"], 3).unwrap_or(w);
+    // Genitive / plural -s only after consonants that can end a German stem
+    let stripped = w.strip_suffix('s')
+        .filter(|stem| stem.chars().count() >= 3 && stem.ends_with(|c| "bdfghklmnrt".contains(c)))
+        .map(str::to_string);
+    let w = stripped.unwrap_or(w);
+    strip_any(&w, &["est", "er", "en"], 4).unwrap_or(w)
+}
+
+/// Light French stemmer (plurals, feminine -e, -ement adverbs)
+fn french(word: &str) -> String {
+    let w = fold_accents(word);
+    if w.chars().count() <= 4 {
+        return w;
+    }
+    let singular = w.strip_suffix("aux").map(|stem| format!("{}al", stem))
+        .or_else(|| strip_any(&w, &["s", "x"], 4));
+    let w = singular.unwrap_or(w);
+    let w = strip_any(&w, &["ement"], 4).unwrap_or(w);
+    strip_any(&w, &["e"], 4).unwrap_or(w)
+}
+
+/// Light Spanish stemmer (plurals and gender endings)
+fn spanish(word: &str) -> String {
+    let w = fold_accents(word);
+    if w.chars().count() < 5 {
+        return w;
+    }
+    strip_any(&w, &["es", "os", "as", "o", "a", "e", "s"], 4).unwrap_or(w)
+}
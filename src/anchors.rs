@@ -0,0 +1,70 @@
+// Line anchors - coded segments held by line numbers instead of char offsets
+//
+// With "segment_anchors": "lines" in config.json a new code application covers whole
+// lines and remembers them (start_line / end_line in codes.db). Each time the note is
+// saved, line-anchored segments are put back over their lines, so normalizing
+// whitespace - trailing spaces, tabs, a sentence re-wrapped within its line - doesn't
+// leave them hanging off by a few chars the way char offsets would. Reports cite every
+// segment by its lines ("lines 45-52") whichever way it is anchored. Lines are numbered
+// from 1 over the whole note, front matter included, like the editor's "Ln".
+
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Anchor {
+    #[default]
+    Chars,  // Segments are exactly the selected chars
+    Lines,  // Segments are whole lines
+}
+
+/// First and last line (1-based, inclusive) of the chars `start..end`
+pub fn line_range(text: &str, start: usize, end: usize) -> (usize, usize) {
+    let last = end.saturating_sub(1).max(start);
+    let (mut first_line, mut last_line) = (1, 1);
+    for (i, c) in text.chars().enumerate().take(last) {
+        if c == '\n' {
+            if i < start {
+                first_line += 1;
+            }
+            last_line += 1;
+        }
+    }
+    (first_line, last_line)
+}
+
+/// The chars of lines `first..=last`, without the whitespace around them; None when the
+/// lines are gone or blank
+pub fn lines_span(text: &str, first: usize, last: usize) -> Option<(usize, usize)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut line_starts = vec![0];
+    line_starts.extend(chars.iter().enumerate().filter(|(_, &c)| c == '\n').map(|(i, _)| i + 1));
+    if first == 0 || first > last || last > line_starts.len() {
+        return None;
+    }
+    let mut start = line_starts[first - 1];
+    let mut end = line_starts.get(last).map_or(chars.len(), |&next| next - 1);
+    while start < end && chars[start].is_whitespace() {
+        start += 1;
+    }
+    while end > start && chars[end - 1].is_whitespace() {
+        end -= 1;
+    }
+    (start < end).then_some((start, end))
+}
+
+/// `start..end` grown to the whole lines it touches, with those lines
+pub fn snap(text: &str, start: usize, end: usize) -> (usize, usize, (usize, usize)) {
+    let lines = line_range(text, start, end);
+    let (start, end) = lines_span(text, lines.0, lines.1).unwrap_or((start, end));
+    (start, end, lines)
+}
+
+/// "line 45" or "lines 45–52"
+pub fn label((first, last): (usize, usize)) -> String {
+    if first == last {
+        format!("line {}", first)
+    } else {
+        format!("lines {}–{}", first, last)
+    }
+}
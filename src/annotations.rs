@@ -0,0 +1,137 @@
+// Annotations - sticky-note comments on a text range, apart from analytic codes
+//
+// A comment is personal scaffolding ("check this against interview 3"), not a coding
+// decision, so like highlights annotations live in their own table in notes.db and stay
+// out of exports, reports and the audit log. The editor underlines annotated text and
+// marks the rows in the gutter; Ctrl+G N lists a note's annotations, and `ann:` in the
+// search bar finds notes by what their annotations say.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::db;
+use crate::theme::Rgb;
+
+/// Underline and gutter marker color - sticky-note yellow
+pub const COLOR: Rgb = Rgb::new(220, 190, 60);
+
+#[derive(Debug, Clone)]
+pub struct Annotation {
+    pub id: i64,
+    pub note_id: String,
+    pub start_offset: usize,  // Char offsets, like code applications
+    pub end_offset: usize,
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A comment being typed: a new one on a span, or an edit of an existing one
+#[derive(Debug, Clone)]
+pub struct AnnotationEdit {
+    pub id: Option<i64>,
+    pub note_id: String,
+    pub start_offset: usize,
+    pub end_offset: usize,
+    pub text: String,
+}
+
+pub struct AnnotationStore {
+    conn: Connection,
+}
+
+impl AnnotationStore {
+    pub fn new(notes_dir: &Path) -> Result<Self> {
+        // The annotations table is created by the notes.db migrations
+        let conn = db::open_migrated(&notes_dir.join("notes.db"), db::NOTES)?;
+        Ok(AnnotationStore { conn })
+    }
+
+    /// Save a typed comment; an empty one removes the annotation being edited
+    pub fn save(&mut self, edit: &AnnotationEdit) -> Result<()> {
+        let text = edit.text.trim();
+        match edit.id {
+            Some(id) if text.is_empty() => self.remove(id)?,
+            Some(id) => {
+                self.conn.execute(
+                    "UPDATE annotations SET text = ?1 WHERE id = ?2",
+                    params![text, id],
+                )?;
+            }
+            None if text.is_empty() => {}
+            None => {
+                self.conn.execute(
+                    "INSERT INTO annotations (note_id, start_offset, end_offset, text, created_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![edit.note_id, edit.start_offset as i64, edit.end_offset as i64, text, Utc::now().to_rfc3339()],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Annotations of one note in text order
+    pub fn for_note(&self, note_id: &str) -> Result<Vec<Annotation>> {
+        self.query("WHERE note_id = ?1", params![note_id])
+    }
+
+    /// Every annotation, grouped by note id (for `ann:` searches)
+    pub fn all(&self) -> Result<HashMap<String, Vec<Annotation>>> {
+        let mut by_note: HashMap<String, Vec<Annotation>> = HashMap::new();
+        for annotation in self.query("", [])? {
+            by_note.entry(annotation.note_id.clone()).or_default().push(annotation);
+        }
+        Ok(by_note)
+    }
+
+    fn query(&self, filter: &str, args: impl rusqlite::Params) -> Result<Vec<Annotation>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, note_id, start_offset, end_offset, text, created_at
+             FROM annotations {} ORDER BY note_id, start_offset, end_offset",
+            filter
+        ))?;
+        let rows = stmt.query_map(args, |row| {
+            let created_at: String = row.get(5)?;
+            Ok(Annotation {
+                id: row.get(0)?,
+                note_id: row.get(1)?,
+                start_offset: row.get::<_, i64>(2)? as usize,
+                end_offset: row.get::<_, i64>(3)? as usize,
+                text: row.get(4)?,
+                created_at: DateTime::parse_from_rfc3339(&created_at).map_or_else(|_| Utc::now(), |t| t.with_timezone(&Utc)),
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn remove(&mut self, id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM annotations WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Move annotations at or after char `from` to another note, shifted so `from` lands on
+    /// `base` (merge / split); one running across `from` is cut there and kept on both sides
+    pub fn move_to(&mut self, from_note: &str, to_note: &str, from: usize, base: usize) -> Result<()> {
+        let (from, base) = (from as i64, base as i64);
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO annotations (note_id, start_offset, end_offset, text, created_at)
+             SELECT ?2, ?4, end_offset - ?3 + ?4, text, created_at FROM annotations
+             WHERE note_id = ?1 AND start_offset < ?3 AND end_offset > ?3",
+            params![from_note, to_note, from, base],
+        )?;
+        tx.execute(
+            "UPDATE annotations SET end_offset = ?2 WHERE note_id = ?1 AND start_offset < ?2 AND end_offset > ?2",
+            params![from_note, from],
+        )?;
+        tx.execute(
+            "UPDATE annotations SET note_id = ?2, start_offset = start_offset - ?3 + ?4, end_offset = end_offset - ?3 + ?4
+             WHERE note_id = ?1 AND start_offset >= ?3",
+            params![from_note, to_note, from, base],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+// Attachments - files copied into the notes directory and referenced from note text
+//
+//   ![interview-3.m4a](attachment://5c1e0b9a4f2d)
+//
+// Files live in attachments/<id>/<original name>, where the id is taken from a hash
+// of the contents, so attaching the same file twice stores it once. A note's
+// attachments are the references in its text; nothing else tracks them. Dropping
+// files onto the terminal pastes their paths, which the editor turns into
+// attachments instead of text.
+
+use anyhow::{anyhow, Context, Result};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+pub const SCHEME: &str = "attachment://";
+
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    pub id: String,
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl Attachment {
+    /// Markdown reference to put in a note
+    pub fn reference(&self) -> String {
+        format!("![{}]({}{})", self.name, SCHEME, self.id)
+    }
+
+    pub fn size(&self) -> u64 {
+        std::fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0)
+    }
+}
+
+/// "812 B", "4.2 KB", "13.0 MB"
+pub fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+fn dir(notes_dir: &Path) -> PathBuf {
+    notes_dir.join("attachments")
+}
+
+/// Copy a file into the attachments directory (a no-op if the same contents are already there)
+pub fn add(notes_dir: &Path, source: &Path) -> Result<Attachment> {
+    let bytes = std::fs::read(source).with_context(|| format!("reading {}", source.display()))?;
+    let id = format!("{:x}", Sha256::digest(&bytes))[..12].to_string();
+    let name = source.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| anyhow!("{} is not a file", source.display()))?;
+
+    if let Some(existing) = resolve(notes_dir, &id) {
+        return Ok(existing);
+    }
+    let folder = dir(notes_dir).join(&id);
+    std::fs::create_dir_all(&folder)?;
+    let path = folder.join(&name);
+    std::fs::write(&path, &bytes)?;
+    tracing::info!(id = %id, name = %name, bytes = bytes.len(), "attachment added");
+    Ok(Attachment { id, name, path })
+}
+
+/// The stored file for an attachment id
+pub fn resolve(notes_dir: &Path, id: &str) -> Option<Attachment> {
+    // Ids are hex; anything else could walk out of the attachments directory
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let entry = std::fs::read_dir(dir(notes_dir).join(id)).ok()?
+        .filter_map(|e| e.ok())
+        .find(|e| e.path().is_file())?;
+    Some(Attachment {
+        id: id.to_string(),
+        name: entry.file_name().to_string_lossy().to_string(),
+        path: entry.path(),
+    })
+}
+
+/// Every stored attachment, by name
+pub fn all(notes_dir: &Path) -> Vec<Attachment> {
+    let Ok(entries) = std::fs::read_dir(dir(notes_dir)) else {
+        return Vec::new();
+    };
+    let mut found: Vec<Attachment> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| resolve(notes_dir, &e.file_name().to_string_lossy()))
+        .collect();
+    found.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.id.cmp(&b.id)));
+    found
+}
+
+/// Delete an attachment's stored file for good
+pub fn remove(notes_dir: &Path, attachment: &Attachment) -> Result<()> {
+    let folder = dir(notes_dir).join(&attachment.id);
+    std::fs::remove_dir_all(&folder).with_context(|| format!("deleting {}", folder.display()))?;
+    tracing::info!(id = %attachment.id, name = %attachment.name, "attachment deleted");
+    Ok(())
+}
+
+/// Attachment ids referenced in a note, in text order without repeats
+pub fn referenced_ids(text: &str) -> Vec<String> {
+    let mut ids: Vec<String> = Vec::new();
+    for (start, _) in text.match_indices(SCHEME) {
+        let id: String = text[start + SCHEME.len()..].chars()
+            .take_while(|c| c.is_ascii_hexdigit())
+            .collect();
+        if !id.is_empty() && !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    ids
+}
+
+/// Open a file with the desktop's default application, without waiting for it
+pub fn open(path: &Path) -> Result<()> {
+    let (program, args): (&str, &[&str]) = if cfg!(target_os = "macos") {
+        ("open", &[])
+    } else if cfg!(windows) {
+        ("cmd", &["/C", "start", ""])
+    } else {
+        ("xdg-open", &[])
+    };
+    std::process::Command::new(program)
+        .args(args)
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow!("could not run {}: {}", program, e))?;
+    Ok(())
+}
+
+/// Paths a terminal pasted for files dropped on it, or None if the text is anything else.
+/// Terminals quote ('/a b.png'), backslash-escape (/a\ b.png) or send file:// URLs.
+pub fn dropped_paths(text: &str) -> Option<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for token in split_shell_words(text.trim()) {
+        let token = match token.strip_prefix("file://") {
+            Some(url) => percent_decode(url.strip_prefix("localhost").unwrap_or(url)),
+            None => token,
+        };
+        let path = PathBuf::from(&token);
+        if !path.is_absolute() || !path.is_file() {
+            return None;
+        }
+        paths.push(path);
+    }
+    (!paths.is_empty()).then_some(paths)
+}
+
+fn split_shell_words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '\\') => current.extend(chars.next()),
+            (None, c) if c.is_whitespace() => {
+                if !current.is_empty() {
+                    words.push(std::mem::take(&mut current));
+                }
+            }
+            (None, c) => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+/// "%20" -> " " in file URLs
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok()).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
@@ -0,0 +1,52 @@
+// Append-only audit log of coding decisions (audit.jsonl in the notes directory)
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+
+use crate::qda_codes::CodeApplication;
+
+#[derive(Debug, Serialize)]
+pub struct AuditEntry<'a> {
+    pub at: DateTime<Utc>,
+    pub action: &'a str,  // e.g. "suggestion_accepted", "suggestion_rejected"
+    pub coder: &'a str,
+    pub note_id: &'a str,
+    pub code_id: &'a str,
+    pub start: usize,
+    pub end: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,  // Free-form extra context (previous code, confidence, ...)
+}
+
+impl<'a> AuditEntry<'a> {
+    pub fn for_application(action: &'a str, coder: &'a str, app: &'a CodeApplication) -> Self {
+        AuditEntry {
+            at: Utc::now(),
+            action,
+            coder,
+            note_id: &app.note_id,
+            code_id: &app.code_id,
+            start: app.start_offset,
+            end: app.end_offset,
+            detail: None,
+        }
+    }
+
+    pub fn with_detail(mut self, detail: String) -> Self {
+        self.detail = Some(detail);
+        self
+    }
+}
+
+/// Append one entry; the file is never rewritten
+pub fn record(notes_dir: &Path, entry: &AuditEntry) -> Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(notes_dir.join("audit.jsonl"))?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
@@ -0,0 +1,134 @@
+// Auto-coding - apply a code to every match of a phrase or regex across notes
+//
+// Ctrl+G F asks for a pattern in the editor header (a plain phrase, matched case-
+// insensitively with any run of whitespace between its words, or /a regex/), then for
+// the code, then shows every match in the marked notes (or the listed ones when none are
+// marked) before anything is coded. Each match is coded as the exact span, its sentence
+// or its paragraph (`u` cycles; config "auto_code_unit" sets the default); several matches
+// in one sentence or paragraph become one segment. Spans the code already covers are
+// skipped, and each application goes to the audit log as "auto_coded".
+
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+
+use crate::front_matter::parse_front_matter;
+use crate::note_store::Note;
+use crate::qda_codes::CodeApplication;
+use crate::text_units::Unit;
+
+/// Matches shown and coded at most - a pattern that hits more is too broad to review
+pub const MAX_MATCHES: usize = 2000;
+
+/// The pattern as typed: "/.../" is a regex, anything else a phrase
+pub fn compile(pattern: &str) -> Result<Regex> {
+    let pattern = pattern.trim();
+    let source = match pattern.strip_prefix('/').and_then(|p| p.strip_suffix('/')).filter(|p| !p.is_empty()) {
+        Some(regex) => regex.to_string(),
+        None => pattern.split_whitespace().map(regex::escape).collect::<Vec<_>>().join(r"\s+"),
+    };
+    if source.is_empty() {
+        anyhow::bail!("empty pattern");
+    }
+    RegexBuilder::new(&source)
+        .case_insensitive(true)
+        .build()
+        .with_context(|| format!("bad pattern {}", pattern))
+}
+
+/// One span to code, found by the pattern
+#[derive(Debug, Clone)]
+pub struct Match {
+    pub note_id: String,
+    pub note_title: String,
+    pub start: usize,  // Char offsets, after growing to the unit
+    pub end: usize,
+    pub excerpt: String,
+    pub checked: bool,
+    pub already: bool,  // The code already covers exactly this span
+}
+
+/// An auto-coding run being set up through the prompt, the code picker and the preview
+#[derive(Debug, Clone)]
+pub struct AutoCode {
+    pub pattern: String,
+    pub note_ids: Vec<String>,
+    pub choices: Vec<String>,  // Code ids as the picker lists them
+    pub code_id: Option<String>,
+    pub unit: Unit,
+    pub matches: Vec<Match>,
+}
+
+impl AutoCode {
+    pub fn new(pattern: &str, note_ids: Vec<String>, unit: Unit) -> Self {
+        AutoCode { pattern: pattern.to_string(), note_ids, choices: Vec::new(), code_id: None, unit, matches: Vec::new() }
+    }
+
+    /// Collect the matches in `notes` (front matter left out) grown to `self.unit`
+    pub fn find(&mut self, regex: &Regex, notes: &[&Note], applications: &[CodeApplication]) {
+        let code_id = self.code_id.clone().unwrap_or_default();
+        let mut matches = Vec::new();
+        for note in notes {
+            let body_start = parse_front_matter(&note.content).map_or(0, |fm| fm.body_start);
+            let chars: Vec<char> = note.content.chars().collect();
+            let mut byte_to_char = note.content.char_indices().map(|(b, _)| b).collect::<Vec<_>>();
+            byte_to_char.push(note.content.len());
+            let to_char = |byte: usize| byte_to_char.partition_point(|&b| b < byte);
+
+            let mut last: Option<(usize, usize)> = None;
+            for m in regex.find_iter(&note.content[body_start..]) {
+                if m.start() == m.end() {
+                    continue;
+                }
+                let (start, end) = (to_char(body_start + m.start()), to_char(body_start + m.end()));
+                let (start, end) = self.unit.expand(&chars, start, end);
+                // Matches in the same sentence / paragraph merge into one segment
+                if let Some(previous) = last.as_mut().filter(|(_, e)| start < *e) {
+                    previous.1 = previous.1.max(end);
+                    continue;
+                }
+                if let Some(previous) = last.replace((start, end)) {
+                    matches.push(self.make_match(note, &chars, previous, &code_id, applications));
+                }
+            }
+            if let Some(previous) = last {
+                matches.push(self.make_match(note, &chars, previous, &code_id, applications));
+            }
+            if matches.len() >= MAX_MATCHES {
+                matches.truncate(MAX_MATCHES);
+                break;
+            }
+        }
+        self.matches = matches;
+    }
+
+    fn make_match(&self, note: &Note, chars: &[char], (start, end): (usize, usize), code_id: &str, applications: &[CodeApplication]) -> Match {
+        let already = note.codes.iter().any(|s| s.code_id == code_id && s.start_offset == start && s.end_offset == end)
+            || applications.iter().any(|a| a.note_id == note.id && a.code_id == code_id && a.start_offset == start && a.end_offset == end);
+        let text: String = chars[start..end].iter().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ");
+        let excerpt: String = text.chars().take(70).collect();
+        let ellipsis = if text.chars().count() > 70 { "..." } else { "" };
+        Match {
+            note_id: note.id.clone(),
+            note_title: note.title.clone(),
+            start,
+            end,
+            excerpt: format!("{}{}", excerpt, ellipsis),
+            checked: !already,
+            already,
+        }
+    }
+
+    pub fn checked(&self) -> impl Iterator<Item = &Match> {
+        self.matches.iter().filter(|m| m.checked && !m.already)
+    }
+
+    pub fn preview_lines(&self) -> Vec<String> {
+        self.matches.iter()
+            .map(|m| {
+                let tick = if m.already { "[=]" } else if m.checked { "[x]" } else { "[ ]" };
+                let title: String = m.note_title.chars().take(20).collect();
+                format!("{} {:<20}  \"{}\"", tick, title, m.excerpt)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,197 @@
+// Backups - timed snapshots of notes.db and codes.db in backups/
+//
+//   snyfter3 backup              take one now
+//   snyfter3 restore             list them, newest first
+//   snyfter3 restore <name>      put one back (after snapshotting the current state)
+//
+// The app takes a snapshot every config "backup_interval_hours" while it runs, and
+// one is taken before the steps that rewrite a lot at once: imports, sync and schema
+// migrations. Each snapshot is a directory named after its time and reason
+// (20261014-093000-import) holding consistent copies made with VACUUM INTO, so they
+// are safe to take while the daemon or the app has the databases open. Only the
+// newest "backup_keep" are kept.
+//
+// The search index and the markdown mirror are rebuilt from notes.db, and attachments
+// are never rewritten, so none of them are copied.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::db;
+
+pub const DATABASES: [&str; 2] = ["notes.db", "codes.db"];
+const STAMP: &str = "%Y%m%d-%H%M%S";
+
+#[derive(Debug, Clone)]
+pub struct Backup {
+    pub name: String,
+    pub path: PathBuf,
+    pub created: DateTime<Local>,
+    pub reason: String,
+}
+
+impl Backup {
+    /// Total size of the copied databases
+    pub fn size(&self) -> u64 {
+        DATABASES.iter()
+            .filter_map(|db| std::fs::metadata(self.path.join(db)).ok())
+            .map(|m| m.len())
+            .sum()
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{}\t{}\t{}\t{}", self.name, self.created.format("%Y-%m-%d %H:%M"), self.reason,
+            crate::attachments::format_size(self.size()))
+    }
+}
+
+fn dir(notes_dir: &Path) -> PathBuf {
+    notes_dir.join("backups")
+}
+
+/// Snapshot every database
+pub fn create(notes_dir: &Path, reason: &str) -> Result<Backup> {
+    snapshot(notes_dir, reason, |partial| {
+        for name in DATABASES {
+            let path = notes_dir.join(name);
+            if path.exists() {
+                vacuum_into(&db::open(&path)?, &partial.join(name))?;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Snapshot one open database (before migrating it)
+pub fn create_for(conn: &Connection, db_path: &Path, reason: &str) -> Result<Backup> {
+    let notes_dir = db_path.parent().context("database path has no directory")?;
+    let name = db_path.file_name().context("database path has no file name")?;
+    snapshot(notes_dir, reason, |partial| vacuum_into(conn, &partial.join(name)))
+}
+
+/// Fill a hidden directory and rename it into place (so a half-written snapshot is never
+/// listed), then drop the snapshots beyond the retention limit
+fn snapshot(notes_dir: &Path, reason: &str, fill: impl FnOnce(&Path) -> Result<()>) -> Result<Backup> {
+    let created = Local::now();
+    let stamp = created.format(STAMP).to_string();
+    let mut name = format!("{}-{}", stamp, reason);
+    let mut n = 2;
+    while dir(notes_dir).join(&name).exists() {
+        name = format!("{}-{}-{}", stamp, reason, n);
+        n += 1;
+    }
+
+    let partial = dir(notes_dir).join(format!(".{}", name));
+    std::fs::create_dir_all(&partial)?;
+    if let Err(e) = fill(&partial) {
+        let _ = std::fs::remove_dir_all(&partial);
+        return Err(e.context("writing backup"));
+    }
+    let path = dir(notes_dir).join(&name);
+    std::fs::rename(&partial, &path)?;
+    tracing::info!(backup = %name, "backup created");
+
+    let keep = Config::load(notes_dir).map(|c| c.backup_keep).unwrap_or(0);
+    if let Err(e) = prune(notes_dir, keep) {
+        tracing::warn!(error = %e, "pruning backups failed");
+    }
+    Ok(Backup { name, path, created, reason: reason.to_string() })
+}
+
+fn vacuum_into(conn: &Connection, target: &Path) -> Result<()> {
+    let target = target.to_str().context("backup path is not UTF-8")?;
+    conn.execute("VACUUM INTO ?1", [target])?;
+    Ok(())
+}
+
+/// Snapshots, newest first
+pub fn list(notes_dir: &Path) -> Result<Vec<Backup>> {
+    let Ok(entries) = std::fs::read_dir(dir(notes_dir)) else {
+        return Ok(Vec::new());
+    };
+    let mut backups = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') || !entry.file_type()?.is_dir() {
+            continue;
+        }
+        // 20261014-093000-reason
+        let Some(stamp) = name.get(..15) else {
+            continue;
+        };
+        let Some(created) = NaiveDateTime::parse_from_str(stamp, STAMP).ok()
+            .and_then(|t| Local.from_local_datetime(&t).earliest()) else {
+            continue;
+        };
+        let reason = name.get(16..).unwrap_or("").to_string();
+        backups.push(Backup { path: entry.path(), name, created, reason });
+    }
+    backups.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(backups)
+}
+
+/// Delete all but the newest `keep` snapshots (0 keeps everything); returns how many went
+pub fn prune(notes_dir: &Path, keep: usize) -> Result<usize> {
+    if keep == 0 {
+        return Ok(0);
+    }
+    let old = list(notes_dir)?.into_iter().skip(keep).collect::<Vec<_>>();
+    for backup in &old {
+        std::fs::remove_dir_all(&backup.path)?;
+    }
+    Ok(old.len())
+}
+
+/// Time left until the next timed snapshot (zero when one is due now); None: timed backups off
+pub fn next_due(notes_dir: &Path, interval_hours: u64) -> Option<std::time::Duration> {
+    if interval_hours == 0 {
+        return None;
+    }
+    let interval = chrono::Duration::hours(interval_hours as i64);
+    let newest = list(notes_dir).ok()?.into_iter().next();
+    let elapsed = newest.map_or(interval, |b| Local::now() - b.created);
+    Some((interval - elapsed).to_std().unwrap_or_default())
+}
+
+/// Put a snapshot's databases back, keeping the current ones as a "before-restore" snapshot.
+/// The app and the daemon must not be running.
+pub fn restore(notes_dir: &Path, name: &str) -> Result<Backup> {
+    let backups = list(notes_dir)?;
+    let Some(backup) = backups.iter().find(|b| b.name == name).cloned() else {
+        bail!("no backup named {} (run `snyfter3 restore` to list them)", name);
+    };
+    let present: Vec<&str> = DATABASES.into_iter().filter(|db| backup.path.join(db).exists()).collect();
+    if present.is_empty() {
+        bail!("backup {} holds no databases", name);
+    }
+
+    // Copy out first: the safety snapshot below may prune the one being restored
+    for db in &present {
+        std::fs::copy(backup.path.join(db), notes_dir.join(format!("{}.restore", db)))?;
+    }
+    create(notes_dir, "before-restore")?;
+    for db in &present {
+        let target = notes_dir.join(db);
+        // The write-ahead log belongs to the database being replaced
+        remove_with_sidecars(&target)?;
+        std::fs::rename(notes_dir.join(format!("{}.restore", db)), &target)?;
+    }
+    // Indexed against the replaced notes: start it over
+    remove_with_sidecars(&notes_dir.join("search.db"))?;
+    tracing::info!(backup = %backup.name, "backup restored");
+    Ok(backup)
+}
+
+fn remove_with_sidecars(path: &Path) -> Result<()> {
+    for suffix in ["", "-journal", "-wal", "-shm"] {
+        let file = PathBuf::from(format!("{}{}", path.display(), suffix));
+        if file.exists() {
+            std::fs::remove_file(&file)?;
+        }
+    }
+    Ok(())
+}
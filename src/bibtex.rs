@@ -0,0 +1,298 @@
+// BibTeX references - a literature note per entry, cited as @citekey
+//
+//   snyfter3 import-bib library.bib
+//
+// Every entry without a note yet gets one from the literature template, with the
+// citekey, authors, year, title and venue in its front matter. Those notes are the
+// reference library: `@` plus the start of a key completes against them in the
+// editor (Ctrl+Space), and following a citation (Ctrl+G l) opens its note. Importing
+// the same file again only adds the new entries.
+
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use crate::config::Config;
+use crate::front_matter::parse_front_matter;
+use crate::note_store::{Note, NoteStore};
+use crate::templates::{TemplateContext, LITERATURE};
+
+#[derive(Debug, Clone)]
+pub struct BibEntry {
+    pub key: String,
+    pub kind: String,  // article, book, inproceedings, ... (lowercase)
+    pub fields: BTreeMap<String, String>,  // Lowercase names, values with the TeX markup removed
+}
+
+impl BibEntry {
+    fn field(&self, name: &str) -> &str {
+        self.fields.get(name).map(String::as_str).unwrap_or("")
+    }
+
+    pub fn title(&self) -> &str {
+        self.field("title")
+    }
+
+    pub fn year(&self) -> &str {
+        let year = self.field("year");
+        if year.is_empty() { self.field("date").get(..4).unwrap_or("") } else { year }
+    }
+
+    /// Journal, proceedings or publisher
+    pub fn venue(&self) -> &str {
+        ["journal", "journaltitle", "booktitle", "publisher", "school", "institution"].iter()
+            .map(|name| self.field(name))
+            .find(|v| !v.is_empty())
+            .unwrap_or("")
+    }
+
+    pub fn doi(&self) -> &str {
+        self.field("doi")
+    }
+
+    pub fn url(&self) -> &str {
+        self.field("url")
+    }
+
+    /// Authors (editors if there are none) as written, "Last, First" or "First Last"
+    fn names(&self) -> Vec<&str> {
+        let names = if self.field("author").is_empty() { self.field("editor") } else { self.field("author") };
+        names.split(" and ").map(str::trim).filter(|n| !n.is_empty()).collect()
+    }
+
+    /// "Smith, Jane; Doe, John"
+    pub fn authors(&self) -> String {
+        self.names().join("; ")
+    }
+
+    /// "Smith", "Smith & Doe" or "Smith et al."
+    pub fn short_authors(&self) -> String {
+        let last: Vec<&str> = self.names().iter().map(|n| last_name(n)).collect();
+        match last.as_slice() {
+            [] => String::new(),
+            [one] => one.to_string(),
+            [a, b] => format!("{} & {}", a, b),
+            [first, ..] => format!("{} et al.", first),
+        }
+    }
+}
+
+fn last_name(name: &str) -> &str {
+    match name.split_once(',') {
+        Some((last, _)) => last.trim(),
+        None => name.split_whitespace().last().unwrap_or(name),
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct BibSummary {
+    pub created: usize,
+    pub existing: usize,  // Entries that already had a literature note
+}
+
+impl BibSummary {
+    pub fn describe(&self) -> String {
+        let mut text = format!("{} literature notes created", self.created);
+        if self.existing > 0 {
+            text.push_str(&format!(", {} already there", self.existing));
+        }
+        text
+    }
+}
+
+/// Create a literature note for every entry of a .bib file that doesn't have one
+pub fn import_bib(notes_dir: &Path, path: &Path) -> Result<BibSummary> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let entries = parse(&text);
+    let mut store = NoteStore::new(notes_dir)?;
+    let mut known: HashSet<String> = store.get_all_notes()?.iter().filter_map(citekey).collect();
+    let coder = Config::load(notes_dir)?.coder_name();
+    let mut summary = BibSummary::default();
+    let mut drafts = Vec::new();
+
+    for entry in &entries {
+        if !known.insert(entry.key.clone()) {
+            summary.existing += 1;
+            continue;
+        }
+        let ctx = TemplateContext {
+            date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+            coder: coder.clone(),
+            case: None,
+            case_codes: Vec::new(),
+            code: None,
+            code_example: None,
+            reference: Some(entry),
+        };
+        drafts.push(LITERATURE.render(&ctx));
+        summary.created += 1;
+    }
+    let notes = store.create_notes(&drafts)?;
+    crate::cli::index_imported(notes_dir, &notes);
+
+    tracing::info!(file = %path.display(), entries = entries.len(), created = summary.created, "bibtex import");
+    Ok(summary)
+}
+
+/// Citekey of a literature note
+pub fn citekey(note: &Note) -> Option<String> {
+    parse_front_matter(&note.content)?
+        .get("citekey")
+        .filter(|k| !k.is_empty())
+        .map(str::to_string)
+}
+
+fn is_key_char(c: char) -> bool {
+    c.is_alphanumeric() || "_-:./+".contains(c)
+}
+
+/// The citation being typed at `cursor` (a char offset): the offset of its `@` and the
+/// part of the key before the cursor. An `@` inside a word (an email address) doesn't count.
+pub fn citation_before(text: &str, cursor: usize) -> Option<(usize, String)> {
+    let chars: Vec<char> = text.chars().take(cursor).collect();
+    let start = chars.iter().rposition(|c| !is_key_char(*c)).map_or(0, |i| i + 1);
+    let at = start.checked_sub(1).filter(|&i| chars[i] == '@')?;
+    if at > 0 && chars[at - 1].is_alphanumeric() {
+        return None;
+    }
+    Some((at, chars[start..].iter().collect()))
+}
+
+/// The whole citekey of an @citation under the cursor
+pub fn citation_at(text: &str, cursor: usize) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    // On the @ itself: the key starts right after it
+    let cursor = if chars.get(cursor) == Some(&'@') { cursor + 1 } else { cursor };
+    let end = chars[cursor.min(chars.len())..].iter().position(|c| !is_key_char(*c)).map_or(chars.len(), |i| cursor + i);
+    let (_, prefix) = citation_before(text, cursor)?;
+    let key: String = prefix.chars().chain(chars[cursor.min(chars.len())..end].iter().copied()).collect();
+    // Keys don't end in punctuation ("see @smith2020.")
+    let key = key.trim_end_matches(|c: char| !c.is_alphanumeric()).to_string();
+    (!key.is_empty()).then_some(key)
+}
+
+/// Entries of a .bib file; @string, @preamble and @comment blocks are skipped
+pub fn parse(text: &str) -> Vec<BibEntry> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut entries = Vec::new();
+    let mut i = 0;
+
+    while let Some(at) = chars.get(i..).and_then(|rest| rest.iter().position(|&c| c == '@')).map(|p| i + p) {
+        let Some(open) = chars[at..].iter().position(|&c| c == '{' || c == '(').map(|p| at + p) else {
+            break;
+        };
+        let kind: String = chars[at + 1..open].iter().collect::<String>().trim().to_lowercase();
+        let close = matching(&chars, open);
+        i = close + 1;
+        if matches!(kind.as_str(), "string" | "preamble" | "comment") || kind.is_empty() {
+            continue;
+        }
+        if let Some(entry) = parse_entry(kind, &chars[open + 1..close.min(chars.len())]) {
+            entries.push(entry);
+        }
+    }
+    entries
+}
+
+/// Index of the bracket closing the `{` or `(` at `open` (or the end of the text)
+fn matching(chars: &[char], open: usize) -> usize {
+    let (opening, closing) = if chars[open] == '(' { ('(', ')') } else { ('{', '}') };
+    let mut depth = 0;
+    for (i, &c) in chars.iter().enumerate().skip(open) {
+        if c == opening {
+            depth += 1;
+        } else if c == closing {
+            depth -= 1;
+            if depth == 0 {
+                return i;
+            }
+        }
+    }
+    chars.len()
+}
+
+/// `key, name = {value}, name = "value", year = 2020`
+fn parse_entry(kind: String, body: &[char]) -> Option<BibEntry> {
+    let comma = body.iter().position(|&c| c == ',')?;
+    let key: String = body[..comma].iter().collect::<String>().trim().to_string();
+    if key.is_empty() {
+        return None;
+    }
+    let mut fields = BTreeMap::new();
+    let mut i = comma + 1;
+
+    while i < body.len() {
+        let Some(eq) = body[i..].iter().position(|&c| c == '=').map(|p| i + p) else {
+            break;
+        };
+        let name: String = body[i..eq].iter().collect::<String>().trim().trim_start_matches(',').trim().to_lowercase();
+        let mut value = String::new();
+        i = eq + 1;
+        // Parts joined with # ("Vol. " # 3)
+        loop {
+            while body.get(i).is_some_and(|c| c.is_whitespace()) {
+                i += 1;
+            }
+            match body.get(i) {
+                Some('{') => {
+                    let close = matching(body, i);
+                    value.extend(&body[i + 1..close.min(body.len())]);
+                    i = close + 1;
+                }
+                Some('"') => {
+                    let close = body[i + 1..].iter().position(|&c| c == '"').map_or(body.len(), |p| i + 1 + p);
+                    value.extend(&body[i + 1..close]);
+                    i = close + 1;
+                }
+                Some(_) => {
+                    let end = body[i..].iter().position(|&c| c == ',' || c == '#').map_or(body.len(), |p| i + p);
+                    value.push_str(body[i..end].iter().collect::<String>().trim());
+                    i = end;
+                }
+                None => break,
+            }
+            while body.get(i).is_some_and(|c| c.is_whitespace()) {
+                i += 1;
+            }
+            if body.get(i) == Some(&'#') {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+        if !name.is_empty() {
+            fields.insert(name, clean(&value));
+        }
+    }
+    Some(BibEntry { key, kind, fields })
+}
+
+/// TeX markup out: braces, accent commands ({\"o} -> o), \& -> &, ~ -> space, one line
+fn clean(value: &str) -> String {
+    let mut out = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' => {}
+            '~' => out.push(' '),
+            '\\' => match chars.peek() {
+                Some(&next) if next.is_alphabetic() => {
+                    // \emph, \textit ... : drop the command, keep its argument
+                    while chars.peek().is_some_and(|c| c.is_alphabetic()) {
+                        chars.next();
+                    }
+                }
+                Some(&next) if "&%$#_".contains(next) => {
+                    out.push(next);
+                    chars.next();
+                }
+                Some(_) => {
+                    chars.next();  // Accent (\" \' \` \^ \~): the letter follows
+                }
+                None => {}
+            },
+            c => out.push(c),
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
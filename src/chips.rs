@@ -0,0 +1,155 @@
+// Quick filter chips - the row under the search bar
+//
+//   [1 Notebook: all] [2 Tag: all] [3 Has code] [4 Has task] [5 Any date]
+//
+// Number keys in the note list (or a click) toggle or cycle a chip. Active
+// chips narrow the notes before the text query is matched, so each one is a
+// shortcut for a filter you would otherwise have to know the syntax for.
+// Notebooks come from the `notebook:` front matter field. The date chip is the
+// `updated:today` / `updated:7d` / `updated:30d` search term (see date_filter.rs).
+
+use crate::date_filter::DateFilter;
+use crate::front_matter::parse_front_matter;
+use crate::note_store::Note;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DateRange {
+    #[default]
+    Any,
+    Today,
+    Week,
+    Month,
+}
+
+impl DateRange {
+    fn next(self) -> Self {
+        match self {
+            DateRange::Any => DateRange::Today,
+            DateRange::Today => DateRange::Week,
+            DateRange::Week => DateRange::Month,
+            DateRange::Month => DateRange::Any,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            DateRange::Any => "Any date",
+            DateRange::Today => "Today",
+            DateRange::Week => "Last 7 days",
+            DateRange::Month => "Last 30 days",
+        }
+    }
+
+    /// The `updated:` term it stands for
+    fn filter(self) -> Option<DateFilter> {
+        let value = match self {
+            DateRange::Any => return None,
+            DateRange::Today => "today",
+            DateRange::Week => "7d",
+            DateRange::Month => "30d",
+        };
+        DateFilter::parse("updated", value)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FilterChips {
+    pub notebook: Option<String>,
+    pub tag: Option<String>,
+    pub has_code: bool,
+    pub has_task: bool,
+    pub date: DateRange,
+}
+
+impl FilterChips {
+    pub fn is_active(&self) -> bool {
+        self.notebook.is_some() || self.tag.is_some() || self.has_code || self.has_task || self.date != DateRange::Any
+    }
+
+    /// Chip labels with whether each is active, in key order (1-5)
+    pub fn labels(&self) -> Vec<(String, bool)> {
+        vec![
+            (format!("1 Notebook: {}", self.notebook.as_deref().unwrap_or("all")), self.notebook.is_some()),
+            (format!("2 Tag: {}", self.tag.as_ref().map(|t| format!("#{}", t)).unwrap_or_else(|| "all".to_string())), self.tag.is_some()),
+            ("3 Has code".to_string(), self.has_code),
+            ("4 Has task".to_string(), self.has_task),
+            (format!("5 {}", self.date.label()), self.date != DateRange::Any),
+        ]
+    }
+
+    /// Toggle or advance chip `index` (0-based); notebook and tag cycle through the values in `notes`
+    pub fn activate(&mut self, index: usize, notes: &[Note]) {
+        match index {
+            0 => self.notebook = next_value(&self.notebook, &notebooks(notes)),
+            1 => self.tag = next_value(&self.tag, &tags_by_use(notes)),
+            2 => self.has_code = !self.has_code,
+            3 => self.has_task = !self.has_task,
+            4 => self.date = self.date.next(),
+            _ => {}
+        }
+    }
+
+    /// `has_codes` says whether the note has any code applications (looked up by the caller)
+    pub fn matches(&self, note: &Note, has_codes: bool) -> bool {
+        if let Some(notebook) = &self.notebook {
+            if notebook_of(note).as_deref() != Some(notebook.as_str()) {
+                return false;
+            }
+        }
+        if let Some(tag) = &self.tag {
+            if !note.tags.iter().any(|t| t == tag) {
+                return false;
+            }
+        }
+        if self.has_code && !has_codes && note.codes.is_empty() {
+            return false;
+        }
+        if self.has_task && !has_open_task(&note.content) {
+            return false;
+        }
+        self.date.filter().map_or(true, |filter| filter.matches(note))
+    }
+}
+
+fn notebook_of(note: &Note) -> Option<String> {
+    parse_front_matter(&note.content)
+        .and_then(|fm| fm.get("notebook").map(str::to_string))
+        .filter(|n| !n.is_empty())
+}
+
+fn notebooks(notes: &[Note]) -> Vec<String> {
+    let mut names: Vec<String> = notes.iter().filter_map(notebook_of).collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Tags ordered by how many notes carry them, most used first
+fn tags_by_use(notes: &[Note]) -> Vec<String> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for tag in notes.iter().flat_map(|n| n.tags.iter()) {
+        match counts.iter_mut().find(|(t, _)| t == tag) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((tag.clone(), 1)),
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts.into_iter().map(|(tag, _)| tag).collect()
+}
+
+/// all -> first -> second -> ... -> all
+fn next_value(current: &Option<String>, values: &[String]) -> Option<String> {
+    let next = match current {
+        None => 0,
+        Some(value) => values.iter().position(|v| v == value).map_or(values.len(), |i| i + 1),
+    };
+    values.get(next).cloned()
+}
+
+/// An unchecked markdown task ("- [ ] ...") anywhere in the note
+fn has_open_task(content: &str) -> bool {
+    content.lines().any(|line| {
+        let line = line.trim_start();
+        line.starts_with("- [ ]") || line.starts_with("* [ ]")
+    })
+}
@@ -0,0 +1,420 @@
+// Headless subcommands - list, search, show and export without opening the TUI
+//
+//   snyfter3 list                      id <TAB> updated <TAB> title, newest first
+//   snyfter3 search "interview memo:x" same query syntax as the search bar
+//   snyfter3 show 3fa9                 note text (ids can be shortened to a unique prefix)
+//   snyfter3 export --format md        files under <notes_dir>/exports/
+//   snyfter3 reindex                   rebuild the search index from the note store
+//   snyfter3 attach 3fa9 scan.pdf      copy files in and reference them from the note
+//   snyfter3 export-codebook / import-codebook   codebook files (see codebook.rs)
+//   snyfter3 report --code cost --format docx    coded segments for a paper (see report.rs)
+//   snyfter3 import-pdf a.pdf b.pdf    one note per PDF (see pdf_import.rs)
+//   snyfter3 import-transcript a.vtt   one note per transcript (see transcript.rs)
+//   snyfter3 journal "text" / --search x   add to or print the project journal
+//   snyfter3 sync                      push and pull notes (see sync.rs)
+//   snyfter3 backup / restore [name]   snapshots of the databases (see backup.rs)
+//
+// --json prints the same data as JSON, so the note store can be scripted.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::annotations::AnnotationStore;
+use crate::attachments;
+use crate::backup;
+use crate::codebook;
+use crate::config::Config;
+use crate::export;
+use crate::note_store::{Note, NoteStore};
+use crate::pdf_import;
+use crate::project_journal::{self, JournalEdit, ProjectJournal};
+use crate::qda_codes::CodeManager;
+use crate::query::{self, coded_text, SearchQuery};
+use crate::report;
+use crate::search_index::SearchIndex;
+use crate::sync;
+use crate::transcript;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ExportFormat {
+    Md,
+    Html,
+    Jsonl,  // Coded segments (one corpus file for all the notes)
+}
+
+/// A note without its text, for list and search results
+#[derive(Serialize)]
+struct NoteSummary<'a> {
+    id: &'a str,
+    title: &'a str,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    tags: &'a [String],
+}
+
+impl<'a> NoteSummary<'a> {
+    fn of(note: &'a Note) -> Self {
+        NoteSummary {
+            id: &note.id,
+            title: &note.title,
+            created_at: note.created_at,
+            updated_at: note.updated_at,
+            tags: &note.tags,
+        }
+    }
+}
+
+/// One code applied in a note, for `show --json`
+#[derive(Serialize)]
+struct AppliedCode<'a> {
+    code: &'a str,
+    start: usize,
+    end: usize,
+    text: String,
+    coder: Option<&'a str>,
+    suggested: bool,
+}
+
+#[derive(Serialize)]
+struct NoteDetail<'a> {
+    #[serde(flatten)]
+    summary: NoteSummary<'a>,
+    content: &'a str,
+    codes: Vec<AppliedCode<'a>>,
+}
+
+pub fn list(notes_dir: &Path, json: bool) -> Result<()> {
+    let notes = NoteStore::new(notes_dir)?.get_all_notes()?;
+    print_notes(&notes.iter().collect::<Vec<_>>(), json)
+}
+
+pub fn search(notes_dir: &Path, query: &str, json: bool) -> Result<()> {
+    let notes = NoteStore::new(notes_dir)?.get_all_notes()?;
+    let query = SearchQuery::parse(query);
+
+    let candidates: Vec<&Note> = if query.has_segment_terms() {
+        let applications = CodeManager::new(notes_dir)?.get_all_code_applications()?;
+        let annotations = AnnotationStore::new(notes_dir)?.all()?;
+        notes.iter()
+            .filter(|note| {
+                let apps = applications.get(&note.id).map(|a| a.as_slice()).unwrap_or(&[]);
+                let anns = annotations.get(&note.id).map(|a| a.as_slice()).unwrap_or(&[]);
+                query.matches_segments(note, apps) && query.matches_annotations(anns)
+            })
+            .collect()
+    } else {
+        notes.iter().collect()
+    };
+    let candidates: Vec<&Note> = candidates.into_iter().filter(|note| query.matches_fields(note)).collect();
+
+    let config = Config::load(notes_dir)?;
+    let mut index = SearchIndex::open(notes_dir, config.search)?;
+    index.sync(&notes)?;
+    let term_scores = index.search(&query.text, config.ranking.title_weight)?;
+    let candidates = index.filter_proximity(candidates, &query.proximity)?;
+    let candidates = index.filter_excluded(candidates, &query.exclude)?;
+    print_notes(&query::rank(candidates, &query.text, &term_scores, &config.ranking), json)
+}
+
+pub fn show(notes_dir: &Path, id: &str, json: bool) -> Result<()> {
+    let notes = NoteStore::new(notes_dir)?.get_all_notes()?;
+    let note = find_note(&notes, id)?;
+    if !json {
+        print!("{}", note.content);
+        if !note.content.ends_with('\n') {
+            println!();
+        }
+        return Ok(());
+    }
+
+    let codes = CodeManager::new(notes_dir)?;
+    let applications = codes.get_code_applications(&note.id)?;
+    let applied = note.codes.iter()
+        .map(|s| AppliedCode {
+            code: code_name(&codes, &s.code_id),
+            start: s.start_offset,
+            end: s.end_offset,
+            text: coded_text(&note.content, s.start_offset, s.end_offset),
+            coder: None,
+            suggested: false,
+        })
+        .chain(applications.iter().map(|a| AppliedCode {
+            code: code_name(&codes, &a.code_id),
+            start: a.start_offset,
+            end: a.end_offset,
+            text: coded_text(&note.content, a.start_offset, a.end_offset),
+            coder: a.coder.as_deref(),
+            suggested: a.suggested,
+        }))
+        .collect();
+
+    let detail = NoteDetail { summary: NoteSummary::of(note), content: &note.content, codes: applied };
+    println!("{}", serde_json::to_string_pretty(&detail)?);
+    Ok(())
+}
+
+/// Export the given notes (all of them when `ids` is empty) and print the written paths
+pub fn export(notes_dir: &Path, format: ExportFormat, ids: &[String], json: bool) -> Result<()> {
+    let all = NoteStore::new(notes_dir)?.get_all_notes()?;
+    let notes: Vec<Note> = if ids.is_empty() {
+        all
+    } else {
+        ids.iter().map(|id| find_note(&all, id).cloned()).collect::<Result<_>>()?
+    };
+    let mut codes = CodeManager::new(notes_dir)?;
+    codes.set_inherit_colors(Config::load(notes_dir)?.inherit_code_colors);
+
+    let paths = match format {
+        ExportFormat::Md => notes.iter().map(|n| export::export_note_md(notes_dir, n)).collect::<Result<Vec<_>>>()?,
+        ExportFormat::Html => notes.iter().map(|n| export::export_note_html(notes_dir, n, &codes)).collect::<Result<Vec<_>>>()?,
+        ExportFormat::Jsonl => vec![export::export_segments_jsonl(notes_dir, &notes, &codes)?.0],
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&paths)?);
+    } else {
+        for path in &paths {
+            println!("{}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Write the codebook and print where it went
+pub fn export_codebook(notes_dir: &Path, format: codebook::Format, output: Option<&Path>) -> Result<()> {
+    let codes = CodeManager::new(notes_dir)?;
+    let path = output.map_or_else(|| codebook::export_path(notes_dir, format), Path::to_path_buf);
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, codes.export_codebook(format)?)?;
+    println!("{}", path.display());
+    Ok(())
+}
+
+pub fn import_codebook(notes_dir: &Path, file: &Path, format: Option<codebook::Format>) -> Result<()> {
+    let Some(format) = format.or_else(|| codebook::Format::from_path(file)) else {
+        bail!("can't tell the codebook format from {} (use --format)", file.display());
+    };
+    let text = std::fs::read_to_string(file)?;
+    let summary = CodeManager::new(notes_dir)?.import_codebook(&text, format)?;
+    println!("{}", summary.describe());
+    Ok(())
+}
+
+/// Write a report of the segments of `codes` (names or ids; all when empty) in `ids`
+/// (all notes when empty) and print where it went
+pub fn report(notes_dir: &Path, codes: &[String], ids: &[String], format: report::Format, output: Option<&Path>) -> Result<()> {
+    let all = NoteStore::new(notes_dir)?.get_all_notes()?;
+    let notes: Vec<&Note> = if ids.is_empty() {
+        all.iter().collect()
+    } else {
+        ids.iter().map(|id| find_note(&all, id)).collect::<Result<_>>()?
+    };
+    let mut manager = CodeManager::new(notes_dir)?;
+    manager.set_inherit_colors(Config::load(notes_dir)?.inherit_code_colors);
+
+    let code_ids: Vec<String> = if codes.is_empty() {
+        manager.tree().iter().filter(|(_, depth)| *depth == 0).map(|(c, _)| c.id.clone()).collect()
+    } else {
+        codes.iter()
+            .map(|wanted| match manager.get_code(wanted).or_else(|| manager.get_code_by_name(wanted)) {
+                Some(code) => Ok(code.id.clone()),
+                None => bail!("no code named {}", wanted),
+            })
+            .collect::<Result<_>>()?
+    };
+
+    let applications = manager.get_all_code_applications()?;
+    let rows = report::retrieve(&notes, &manager, &code_ids, &applications);
+    let path = output.map_or_else(|| report::report_path(notes_dir, format), Path::to_path_buf);
+    report::write(&path, &rows, format, &report::Scope { codes: code_ids.len(), cases: notes.len() })?;
+    println!("{}", path.display());
+    Ok(())
+}
+
+/// Add an entry to the project journal, or print (or export) the entries
+pub fn journal(notes_dir: &Path, text: Option<&str>, search: Option<&str>, export: bool, json: bool) -> Result<()> {
+    let mut journal = ProjectJournal::new(notes_dir)?;
+    if let Some(text) = text {
+        let text = match text {
+            "-" => {
+                let mut text = String::new();
+                std::io::Read::read_to_string(&mut std::io::stdin(), &mut text)?;
+                text
+            }
+            text => text.to_string(),
+        };
+        match journal.save(&JournalEdit { id: None, text })? {
+            Some(id) => println!("{}", id),
+            None => bail!("empty journal entry"),
+        }
+        return Ok(());
+    }
+
+    let entries = journal.search(search.unwrap_or(""))?;
+    if export {
+        println!("{}", project_journal::export(notes_dir, &entries)?.display());
+    } else if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    } else {
+        for entry in &entries {
+            println!("## {}\n\n{}\n", entry.date(), entry.text.trim_end());
+        }
+    }
+    Ok(())
+}
+
+/// Copy files into the attachments directory and append their references to a note
+pub fn attach(notes_dir: &Path, id: &str, files: &[PathBuf]) -> Result<()> {
+    let mut store = NoteStore::new(notes_dir)?;
+    let notes = store.get_all_notes()?;
+    let mut note = find_note(&notes, id)?.clone();
+    if !note.content.is_empty() && !note.content.ends_with('\n') {
+        note.content.push('\n');
+    }
+    for file in files {
+        let attachment = attachments::add(notes_dir, file)?;
+        note.content.push_str(&attachment.reference());
+        note.content.push('\n');
+        println!("{}\t{}", attachment.id, attachment.name);
+    }
+    store.update_note(&note)?;
+    Ok(())
+}
+
+/// Add just-imported notes to the search index in a few large commits, so neither the next
+/// search nor the next launch has to. A failure only leaves that to them
+pub fn index_imported(notes_dir: &Path, notes: &[Note]) {
+    let result = Config::load(notes_dir)
+        .and_then(|config| SearchIndex::open(notes_dir, config.search))
+        .and_then(|mut index| index.add_notes(notes));
+    if let Err(e) = result {
+        tracing::warn!(error = %e, "indexing imported notes failed");
+    }
+}
+
+/// Create a note per PDF and print each note's id and title
+pub fn import_pdf(notes_dir: &Path, files: &[PathBuf]) -> Result<()> {
+    let mut store = NoteStore::new(notes_dir)?;
+    let mut imported = Vec::new();
+    let mut failed = 0;
+    for file in files {
+        match pdf_import::import_pdf(notes_dir, &mut store, file) {
+            Ok(note) => {
+                println!("{}\t{}", note.id, note.title);
+                imported.push(note);
+            }
+            Err(e) => {
+                eprintln!("{}: {:#}", file.display(), e);
+                failed += 1;
+            }
+        }
+    }
+    index_imported(notes_dir, &imported);
+    if failed > 0 {
+        bail!("{} of {} PDFs could not be imported", failed, files.len());
+    }
+    Ok(())
+}
+
+/// Create a note per transcript and print each note's id and title
+pub fn import_transcripts(notes_dir: &Path, files: &[PathBuf]) -> Result<()> {
+    let mut store = NoteStore::new(notes_dir)?;
+    let mut imported = Vec::new();
+    let mut failed = 0;
+    for file in files {
+        match transcript::import_transcript(&mut store, file) {
+            Ok(note) => {
+                println!("{}\t{}", note.id, note.title);
+                imported.push(note);
+            }
+            Err(e) => {
+                eprintln!("{}: {:#}", file.display(), e);
+                failed += 1;
+            }
+        }
+    }
+    index_imported(notes_dir, &imported);
+    if failed > 0 {
+        bail!("{} of {} transcripts could not be imported", failed, files.len());
+    }
+    Ok(())
+}
+
+pub fn sync(notes_dir: &Path) -> Result<()> {
+    let Some(config) = Config::load(notes_dir)?.sync else {
+        bail!("sync is not set up (add \"sync\" to config.json)");
+    };
+    let mut store = NoteStore::new(notes_dir)?;
+    let report = sync::sync(notes_dir, &mut store, &config)?;
+    println!("{}", report.describe());
+    Ok(())
+}
+
+pub fn backup(notes_dir: &Path) -> Result<()> {
+    let backup = backup::create(notes_dir, "manual")?;
+    println!("{}", backup.path.display());
+    Ok(())
+}
+
+/// List the backups, or put one back
+pub fn restore(notes_dir: &Path, name: Option<&str>) -> Result<()> {
+    let Some(name) = name else {
+        let backups = backup::list(notes_dir)?;
+        if backups.is_empty() {
+            println!("No backups yet");
+        }
+        for backup in backups {
+            println!("{}", backup.describe());
+        }
+        return Ok(());
+    };
+    let restored = backup::restore(notes_dir, name)?;
+    println!("Restored {} (the replaced databases are in the before-restore backup)", restored.name);
+    Ok(())
+}
+
+/// Rebuild search.db from the note store, with a progress line on stderr
+pub fn reindex(notes_dir: &Path) -> Result<()> {
+    let notes = NoteStore::new(notes_dir)?.get_all_notes()?;
+    let mut index = SearchIndex::open(notes_dir, Config::load(notes_dir)?.search)?;
+    index.reindex_all(&notes, |done, total| {
+        eprint!("\rIndexed {}/{} notes", done, total);
+    })?;
+    eprintln!();
+    println!("Search index rebuilt: {} notes", index.note_count());
+    Ok(())
+}
+
+fn print_notes(notes: &[&Note], json: bool) -> Result<()> {
+    if json {
+        let summaries: Vec<NoteSummary> = notes.iter().map(|n| NoteSummary::of(n)).collect();
+        println!("{}", serde_json::to_string_pretty(&summaries)?);
+    } else {
+        for note in notes {
+            println!("{}\t{}\t{}", note.id, note.updated_at.format("%Y-%m-%d %H:%M"), note.title);
+        }
+    }
+    Ok(())
+}
+
+fn code_name<'a>(codes: &'a CodeManager, code_id: &'a str) -> &'a str {
+    codes.get_code(code_id).map(|c| c.name.as_str()).unwrap_or(code_id)
+}
+
+/// A note by full id or unique id prefix
+fn find_note<'a>(notes: &'a [Note], id: &str) -> Result<&'a Note> {
+    if let Some(note) = notes.iter().find(|n| n.id == id) {
+        return Ok(note);
+    }
+    let matches: Vec<&Note> = notes.iter().filter(|n| n.id.starts_with(id)).collect();
+    match matches.as_slice() {
+        [note] => Ok(note),
+        [] => bail!("no note with id {}", id),
+        _ => bail!("id {} is ambiguous ({} notes start with it)", id, matches.len()),
+    }
+}
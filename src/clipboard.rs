@@ -0,0 +1,155 @@
+// System clipboard access shared by the editor and any other copy/paste path
+//
+// Uses arboard in-process (X11, Wayland, macOS, Windows) instead of spawning
+// pbcopy/xclip. Over SSH the OSC 52 escape sequence hands copies to the local
+// terminal instead. If neither is reachable, copies still land in an
+// in-process buffer so cut/paste inside the app keeps working.
+
+use anyhow::Result;
+use base64::Engine;
+use serde::{Serialize, Deserialize};
+use std::io::Write;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+// Kill ring size - older copies fall off the end
+const HISTORY_LEN: usize = 30;
+
+/// Where copies go (config.json "clipboard")
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardBackend {
+    Auto,    // OSC 52 inside an SSH session, system clipboard otherwise
+    System,  // arboard only
+    Osc52,   // Terminal escape sequence only
+}
+
+impl ClipboardBackend {
+    fn uses_osc52(self) -> bool {
+        match self {
+            ClipboardBackend::Auto => std::env::var_os("SSH_CONNECTION").is_some()
+                || std::env::var_os("SSH_TTY").is_some(),
+            ClipboardBackend::System => false,
+            ClipboardBackend::Osc52 => true,
+        }
+    }
+}
+
+struct State {
+    backend: ClipboardBackend,
+    // Kept alive for the whole session: on X11 the selection is owned by this handle
+    system: Option<arboard::Clipboard>,
+    system_failed: bool,
+    notice: Option<String>,  // Not yet shown to the user: why the system clipboard isn't used
+    local: String,  // Last copied text, used when the system clipboard is unavailable
+    history: VecDeque<String>,  // Kill ring, newest first, independent of the system clipboard
+}
+
+// Clipboard handles aren't Send on every platform, so state lives on the UI thread
+thread_local! {
+    static STATE: RefCell<State> = const { RefCell::new(State {
+        backend: ClipboardBackend::Auto,
+        system: None,
+        system_failed: false,
+        notice: None,
+        local: String::new(),
+        history: VecDeque::new(),
+    }) };
+}
+
+impl State {
+    fn remember(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        // Re-copying an older entry moves it to the front rather than duplicating it
+        self.history.retain(|entry| entry != text);
+        self.history.push_front(text.to_string());
+        self.history.truncate(HISTORY_LEN);
+    }
+
+    fn system(&mut self) -> Option<&mut arboard::Clipboard> {
+        if self.system.is_none() && !self.system_failed {
+            match arboard::Clipboard::new() {
+                Ok(clipboard) => self.system = Some(clipboard),
+                // Headless or no display - don't retry every copy
+                Err(e) => {
+                    self.system_failed = true;
+                    self.notice = Some(format!("System clipboard unavailable ({}) - copies stay inside snyfter3", e));
+                }
+            }
+        }
+        self.system.as_mut()
+    }
+}
+
+pub fn set_backend(backend: ClipboardBackend) {
+    STATE.with(|state| state.borrow_mut().backend = backend);
+}
+
+/// Copy text to the clipboard (falls back to the in-process buffer)
+pub fn copy(text: &str) -> Result<()> {
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        state.local = text.to_string();
+        state.remember(text);
+
+        if state.backend.uses_osc52() {
+            return osc52_copy(text);
+        }
+        // On failure the text is still in the in-process buffer and the history
+        if let Some(Err(e)) = state.system().map(|clipboard| clipboard.set_text(text.to_string())) {
+            state.notice = Some(format!("Copy to the system clipboard failed: {}", e));
+        }
+        Ok(())
+    })
+}
+
+/// A clipboard problem the user hasn't been told about yet (shown once)
+pub fn take_notice() -> Option<String> {
+    STATE.with(|state| state.borrow_mut().notice.take())
+}
+
+/// Read text from the system clipboard (or the in-process buffer).
+/// OSC 52 reads are disabled in most terminals, so that backend pastes the
+/// last in-app copy; the terminal's own paste still arrives as typed input.
+pub fn paste() -> Result<String> {
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        if state.backend.uses_osc52() {
+            return Ok(state.local.clone());
+        }
+
+        if let Some(clipboard) = state.system() {
+            if let Ok(text) = clipboard.get_text() {
+                return Ok(text);
+            }
+        }
+        Ok(state.local.clone())
+    })
+}
+
+/// Send the text to the terminal's clipboard with an OSC 52 escape sequence
+fn osc52_copy(text: &str) -> Result<()> {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+    let sequence = format!("\x1b]52;c;{}\x07", encoded);
+
+    // tmux and screen only forward the sequence when wrapped in a DCS passthrough
+    let sequence = if std::env::var_os("TMUX").is_some() {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else if std::env::var("TERM").is_ok_and(|t| t.starts_with("screen")) {
+        format!("\x1bP{}\x1b\\", sequence)
+    } else {
+        sequence
+    };
+
+    let mut stdout = std::io::stdout();
+    stdout.write_all(sequence.as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Recent cuts/copies, newest first
+pub fn history() -> Vec<String> {
+    STATE.with(|state| state.borrow().history.iter().cloned().collect())
+}
@@ -0,0 +1,410 @@
+// Codebook files - the code tree as JSON, CSV or a REFI-QDA codebook (.qdc)
+//
+//   Ctrl+G B                                  export, or import a codebook file found in the
+//                                             notes directory, exports/ or imports/
+//   snyfter3 export-codebook --format qdc     writes <notes_dir>/exports/codebook.qdc
+//   snyfter3 import-codebook theirs.qdc       format from the extension
+//
+// All three carry names, descriptions, colors and the hierarchy. JSON is the code records
+// as stored (shortcuts included). CSV has one row per code: id, name, parent (the parent's
+// id or name), color as #rrggbb, description, shortcut - only `name` is required, so a
+// spreadsheet list of codes imports too. QDC is the REFI-QDA exchange format read by
+// ATLAS.ti, MAXQDA, NVivo and QualCoder: nested <Code> elements with a GUID each, derived
+// from the code id so that exporting twice gives the same GUIDs and re-importing our own
+// file matches the codes already here.
+
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+use crate::csv_import;
+use crate::qda_codes::{self, Code, CodeColor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Json,
+    Csv,
+    Qdc,  // REFI-QDA codebook
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Json, Format::Csv, Format::Qdc];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Csv => "csv",
+            Format::Qdc => "qdc",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Json => "JSON",
+            Format::Csv => "CSV",
+            Format::Qdc => "REFI-QDA (.qdc)",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        Format::ALL.into_iter().find(|f| f.extension() == extension)
+    }
+}
+
+/// What a line of the codebook overlay does
+#[derive(Debug, Clone)]
+pub enum Choice {
+    Export(Format),
+    Import(PathBuf),
+}
+
+/// Where exports go
+pub fn export_path(notes_dir: &Path, format: Format) -> PathBuf {
+    notes_dir.join("exports").join(format!("codebook.{}", format.extension()))
+}
+
+/// Codebook files lying around the notes directory: any .qdc, and codebook*.json / .csv
+pub fn candidates(notes_dir: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    for dir in [notes_dir.to_path_buf(), notes_dir.join("exports"), notes_dir.join("imports")] {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|e| e.path()) {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_lowercase();
+            let wanted = match Format::from_path(&path) {
+                Some(Format::Qdc) => true,
+                Some(_) => name.starts_with("codebook"),
+                None => false,
+            };
+            if wanted && path.is_file() {
+                found.push(path);
+            }
+        }
+    }
+    found.sort();
+    found
+}
+
+/// GUID for a code in QDC files, stable for a given code id
+pub fn guid(id: &str) -> String {
+    let hash = format!("{:x}", Sha256::digest(id.as_bytes()));
+    format!("{}-{}-{}-{}-{}", &hash[0..8], &hash[8..12], &hash[12..16], &hash[16..20], &hash[20..32])
+}
+
+/// The codebook in `format`; `tree` is the codes in tree order with their depths
+pub fn write(tree: &[(&Code, usize)], format: Format) -> Result<String> {
+    Ok(match format {
+        Format::Json => {
+            let codes: Vec<&Code> = tree.iter().map(|(code, _)| *code).collect();
+            serde_json::to_string_pretty(&codes)?
+        }
+        Format::Csv => write_csv(tree),
+        Format::Qdc => write_qdc(tree),
+    })
+}
+
+/// Codes from a codebook file. Ids and parent ids are the file's own (QDC: GUIDs); the
+/// code manager maps them onto the codes it has.
+pub fn read(text: &str, format: Format) -> Result<Vec<Code>> {
+    let codes = match format {
+        Format::Json => serde_json::from_str(text).context("not a JSON codebook (a list of codes)")?,
+        Format::Csv => read_csv(text)?,
+        Format::Qdc => read_qdc(text)?,
+    };
+    if codes.is_empty() {
+        bail!("the codebook has no codes");
+    }
+    Ok(codes)
+}
+
+pub fn hex(color: &CodeColor) -> String {
+    format!("#{:02X}{:02X}{:02X}", color.r, color.g, color.b)
+}
+
+fn parse_hex(text: &str) -> Option<CodeColor> {
+    let digits = text.trim().trim_start_matches('#');
+    if digits.len() != 6 || !digits.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).ok();
+    Some(CodeColor { r: channel(0)?, g: channel(2)?, b: channel(4)? })
+}
+
+/// Codes without a color in the file
+const DEFAULT_COLOR: CodeColor = CodeColor { r: 150, g: 150, b: 150 };
+
+// CSV
+
+const CSV_HEADER: [&str; 6] = ["id", "name", "parent", "color", "description", "shortcut"];
+
+pub fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn write_csv(tree: &[(&Code, usize)]) -> String {
+    let mut out = CSV_HEADER.join(",");
+    out.push('\n');
+    for (code, _) in tree {
+        let fields = [
+            code.id.clone(),
+            code.name.clone(),
+            code.parent_id.clone().unwrap_or_default(),
+            hex(&code.color),
+            code.description.clone(),
+            code.shortcut.map(String::from).unwrap_or_default(),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+fn read_csv(text: &str) -> Result<Vec<Code>> {
+    let rows = csv_import::parse(text);
+    let Some((header, rows)) = rows.split_first() else {
+        bail!("the CSV file is empty");
+    };
+    let column = |names: &[&str]| header.iter().position(|h| names.iter().any(|n| h.trim().eq_ignore_ascii_case(n)));
+    let Some(name_col) = column(&["name", "code"]) else {
+        bail!("the CSV codebook needs a \"name\" column");
+    };
+    let id_col = column(&["id"]);
+    let parent_col = column(&["parent", "parent_id", "parent_name"]);
+    let color_col = column(&["color", "colour"]);
+    let description_col = column(&["description", "memo"]);
+    let shortcut_col = column(&["shortcut"]);
+    let cell = |row: &[String], col: Option<usize>| col.and_then(|c| row.get(c)).map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+
+    let mut codes: Vec<Code> = Vec::new();
+    let mut parents = Vec::new();
+    for row in rows {
+        let Some(name) = cell(row, Some(name_col)) else {
+            continue;
+        };
+        codes.push(Code {
+            id: cell(row, id_col).unwrap_or_else(|| qda_codes::id_for_name(&name)),
+            name,
+            description: cell(row, description_col).unwrap_or_default(),
+            color: cell(row, color_col).and_then(|c| parse_hex(&c)).unwrap_or(DEFAULT_COLOR),
+            parent_id: None,
+            shortcut: cell(row, shortcut_col).and_then(|s| s.chars().next()),
+        });
+        parents.push(cell(row, parent_col));
+    }
+
+    // A parent is named by id or by name; one that isn't in the file is left for the
+    // code manager to look up among the codes already here
+    let resolved: Vec<Option<String>> = parents.iter().map(|parent| {
+        parent.as_ref().map(|p| {
+            codes.iter()
+                .find(|c| c.id == *p)
+                .or_else(|| codes.iter().find(|c| c.name.eq_ignore_ascii_case(p)))
+                .map_or_else(|| p.clone(), |c| c.id.clone())
+        })
+    }).collect();
+    for (code, parent) in codes.iter_mut().zip(resolved) {
+        code.parent_id = parent.filter(|p| *p != code.id);
+    }
+    Ok(codes)
+}
+
+// REFI-QDA codebook (QDC)
+
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn unescape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find('&') {
+        out.push_str(&rest[..at]);
+        rest = &rest[at..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity.strip_prefix("#x").and_then(|h| u32::from_str_radix(h, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|d| d.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn write_qdc(tree: &[(&Code, usize)]) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    out.push_str("<CodeBook xmlns=\"urn:QDA-XML:codebook:1.0\" origin=\"Snyfter3\">\n  <Codes>\n");
+    let indent = |depth: usize| "  ".repeat(depth + 2);
+    let mut open = 0;  // Depth of the codes left open for their children
+    for (i, (code, depth)) in tree.iter().enumerate() {
+        while open > *depth {
+            open -= 1;
+            out.push_str(&format!("{}</Code>\n", indent(open)));
+        }
+        out.push_str(&format!(
+            "{}<Code guid=\"{}\" name=\"{}\" isCodable=\"true\" color=\"{}\"",
+            indent(*depth), guid(&code.id), escape_xml(&code.name), hex(&code.color)
+        ));
+        let has_children = tree.get(i + 1).is_some_and(|(_, next)| next > depth);
+        if code.description.is_empty() && !has_children {
+            out.push_str("/>\n");
+            continue;
+        }
+        out.push_str(">\n");
+        if !code.description.is_empty() {
+            out.push_str(&format!("{}  <Description>{}</Description>\n", indent(*depth), escape_xml(&code.description)));
+        }
+        if has_children {
+            open = depth + 1;
+        } else {
+            out.push_str(&format!("{}</Code>\n", indent(*depth)));
+        }
+    }
+    while open > 0 {
+        open -= 1;
+        out.push_str(&format!("{}</Code>\n", indent(open)));
+    }
+    out.push_str("  </Codes>\n</CodeBook>\n");
+    out
+}
+
+/// `name="value"` pairs of a start tag
+fn attributes(tag: &str) -> Vec<(&str, String)> {
+    let mut attrs = Vec::new();
+    let mut rest = tag;
+    while let Some(eq) = rest.find('=') {
+        let name = rest[..eq].trim();
+        let after = rest[eq + 1..].trim_start();
+        let Some(quote) = after.chars().next().filter(|q| *q == '"' || *q == '\'') else {
+            break;
+        };
+        let Some(len) = after[1..].find(quote) else {
+            break;
+        };
+        attrs.push((name, unescape_xml(&after[1..1 + len])));
+        rest = &after[len + 2..];
+    }
+    attrs
+}
+
+/// Element name without its namespace prefix
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+fn read_qdc(text: &str) -> Result<Vec<Code>> {
+    let mut codes: Vec<Code> = Vec::new();
+    let mut open: Vec<usize> = Vec::new();  // Code elements whose children come next
+    let mut description: Option<String> = None;  // Text of the <Description> being read
+    let mut found_codebook = false;
+    let mut rest = text;
+
+    while let Some(start) = rest.find('<') {
+        if let Some(text) = description.as_mut() {
+            text.push_str(&rest[..start]);
+        }
+        rest = &rest[start..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").context("unterminated CDATA section")?;
+            if let Some(text) = description.as_mut() {
+                // Escaped, so the unescape at </Description> gives the raw text back
+                text.push_str(&escape_xml(&cdata[..end]));
+            }
+            rest = &cdata[end + 3..];
+            continue;
+        }
+        let end = rest.find('>').context("unterminated tag in the codebook")?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+
+        if let Some(name) = tag.strip_prefix('/') {
+            match local_name(name.trim()) {
+                "Code" => {
+                    open.pop();
+                }
+                "Description" => {
+                    if let (Some(text), Some(&i)) = (description.take(), open.last()) {
+                        codes[i].description = unescape_xml(text.trim());
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let (name, attrs) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+        match local_name(name) {
+            "CodeBook" | "Project" => found_codebook = true,
+            "Code" => {
+                let attrs = attributes(attrs);
+                let get = |key: &str| attrs.iter().find(|(k, _)| *k == key).map(|(_, v)| v.clone());
+                let name = get("name").unwrap_or_default();
+                let code = Code {
+                    id: get("guid").filter(|g| !g.is_empty()).unwrap_or_else(|| qda_codes::id_for_name(&name)),
+                    name,
+                    description: String::new(),
+                    color: get("color").and_then(|c| parse_hex(&c)).unwrap_or(DEFAULT_COLOR),
+                    parent_id: open.last().map(|&i| codes[i].id.clone()),
+                    shortcut: None,
+                };
+                codes.push(code);
+                if !self_closing {
+                    open.push(codes.len() - 1);
+                }
+            }
+            "Description" if !self_closing && !open.is_empty() => description = Some(String::new()),
+            _ => {}
+        }
+    }
+
+    if !found_codebook {
+        bail!("not a REFI-QDA codebook (no <CodeBook> element)");
+    }
+    // Unnamed codes can't be told apart here; their children move up a level
+    let unnamed: Vec<(String, Option<String>)> = codes.iter()
+        .filter(|c| c.name.trim().is_empty())
+        .map(|c| (c.id.clone(), c.parent_id.clone()))
+        .collect();
+    codes.retain(|c| !c.name.trim().is_empty());
+    for code in &mut codes {
+        while let Some((_, parent)) = unnamed.iter().find(|(id, _)| code.parent_id.as_ref() == Some(id)) {
+            code.parent_id = parent.clone();
+        }
+    }
+    Ok(codes)
+}
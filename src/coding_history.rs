@@ -0,0 +1,84 @@
+// Coding history - undo / redo for coding decisions, apart from the editor's text undo
+//
+// Applying a code (converting a highlight, auto-coding), removing one (Alt+U, rejecting
+// a suggestion), accepting a suggestion, recoding and merging codes each push one step.
+// A step keeps the code applications it touched as they were before and after (by row
+// id), the segments stored with the notes it changed and, for a merge, the code it
+// deleted - so Alt+Z puts all of it back and Alt+Y does it again. Text edits keep their
+// own Ctrl+Z / Ctrl+Y history and neither clears the other. Steps last for the session.
+
+use crate::highlights::Highlight;
+use crate::note_store::CodedSegment;
+use crate::qda_codes::{Code, CodeApplication};
+
+const MAX_STEPS: usize = 100;
+
+/// A code folded into another, kept so undo can bring it back
+#[derive(Debug, Clone)]
+pub struct Merged {
+    pub code: Code,
+    pub into: String,
+    pub children: Vec<String>,  // Codes that hung under it (`into` too, if it was one)
+}
+
+/// One coding action
+#[derive(Debug, Clone, Default)]
+pub struct Step {
+    pub label: String,                  // "Auto-code as Theme", for the undo / redo message
+    pub before: Vec<CodeApplication>,   // The applications as they were (none: added by the action)
+    pub after: Vec<CodeApplication>,    // And as the action left them (none: removed)
+    pub segments: Vec<(String, Vec<CodedSegment>, Vec<CodedSegment>)>,  // Note id, its segments before and after
+    pub merged: Option<Merged>,
+    pub highlight: Option<Highlight>,   // Converted into the applied code
+}
+
+impl Step {
+    pub fn new(label: impl Into<String>) -> Self {
+        Step { label: label.into(), ..Step::default() }
+    }
+
+    /// The applications to cite in the audit log: the ones the action made, or for a
+    /// removal the ones it took away
+    pub fn touched(&self) -> &[CodeApplication] {
+        if self.after.is_empty() { &self.before } else { &self.after }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CodingHistory {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+}
+
+impl CodingHistory {
+    /// Record a new action; anything undone before it can no longer be redone
+    pub fn push(&mut self, step: Step) {
+        self.redo.clear();
+        self.push_undo(step);
+    }
+
+    fn push_undo(&mut self, step: Step) {
+        self.undo.push(step);
+        if self.undo.len() > MAX_STEPS {
+            self.undo.remove(0);
+        }
+    }
+
+    pub fn pop_undo(&mut self) -> Option<Step> {
+        self.undo.pop()
+    }
+
+    pub fn pop_redo(&mut self) -> Option<Step> {
+        self.redo.pop()
+    }
+
+    /// A step just undone, to redo later
+    pub fn undone(&mut self, step: Step) {
+        self.redo.push(step);
+    }
+
+    /// A step just redone, to undo again
+    pub fn redone(&mut self, step: Step) {
+        self.push_undo(step);
+    }
+}
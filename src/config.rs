@@ -0,0 +1,226 @@
+// User configuration stored as config.json in the notes directory
+
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::analyzer::Analyzer;
+use crate::anchors::Anchor;
+use crate::clipboard::ClipboardBackend;
+use crate::images::ImageProtocol;
+use crate::input::InputBackend;
+use crate::query::Ranking;
+use crate::reading::LineSpacing;
+use crate::sync::SyncConfig;
+use crate::text_units::Unit;
+use crate::theme::Theme;
+
+/// How much of each note the list shows
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListDensity {
+    Compact,   // Title only
+    Cozy,      // Title + updated date
+    Detailed,  // Title + 2-line snippet + tag chips
+}
+
+impl ListDensity {
+    pub fn next(self) -> Self {
+        match self {
+            ListDensity::Compact => ListDensity::Cozy,
+            ListDensity::Cozy => ListDensity::Detailed,
+            ListDensity::Detailed => ListDensity::Compact,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ListDensity::Compact => "compact",
+            ListDensity::Cozy => "cozy",
+            ListDensity::Detailed => "detailed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]  // Missing keys fall back to defaults so old config files keep working
+pub struct Config {
+    /// Name of the active theme (built-in or one of `themes`)
+    pub theme: String,
+
+    /// User-defined themes, keyed by name
+    pub themes: BTreeMap<String, Theme>,
+
+    /// Note list display mode
+    pub list_density: ListDensity,
+
+    /// Blank rows in reading mode: "single", "paragraphs" or "double"
+    pub line_spacing: LineSpacing,
+
+    /// Clipboard backend: "auto" (OSC 52 over SSH), "system" or "osc52"
+    pub clipboard: ClipboardBackend,
+
+    /// Count commands/features locally for the usage report (off unless enabled)
+    pub usage_insights: bool,
+
+    /// Wider margins, blank separators instead of thin lines, bigger mouse targets
+    pub large_ui: bool,
+
+    /// Keyboard protocol: "auto" (kitty when supported), "basic" or "kitty"
+    pub input: InputBackend,
+
+    /// Name recorded with coding decisions (defaults to the login name)
+    pub coder: Option<String>,
+
+    /// Search analyzer: {"language": "english" | "german" | "french" | "spanish" | "none",
+    /// "cjk": "bigram" | "unigram"}; changing it rebuilds the search index
+    pub search: Analyzer,
+
+    /// Search result order: {"title_weight": 3.0, "recency_boost": 0.5,
+    /// "recency_half_life_days": 30}; a boost of 0 ranks on the match alone
+    pub ranking: Ranking,
+
+    /// Images in reading mode: "auto" (detect the terminal), "kitty", "iterm" or "off"
+    pub inline_images: ImageProtocol,
+
+    /// Minutes without input before the screen locks (0: never); only with a passphrase set
+    pub auto_lock_minutes: u64,
+
+    /// WebDAV / S3 endpoint notes are synced with (see sync.rs); None: no sync
+    pub sync: Option<SyncConfig>,
+
+    /// Titles follow the first line or heading of the text until renamed by hand
+    pub auto_title: bool,
+
+    /// Moving through the list highlights notes and previews them instead of opening them
+    pub list_preview: bool,
+
+    /// Child codes are drawn (and exported) in shades of their top-level code's color
+    pub inherit_code_colors: bool,
+
+    /// Hours between timed backups while the app runs (0: only before imports, sync, migrations)
+    pub backup_interval_hours: u64,
+
+    /// Backups kept in backups/, newest first (0: keep all)
+    pub backup_keep: usize,
+
+    /// Words left out of word frequencies, on top of the built-in English list
+    pub stopwords: Vec<String>,
+
+    /// What auto-coding codes around each match: "exact", "sentence" or "paragraph"
+    pub auto_code_unit: Unit,
+
+    /// Highlights converted to codes are coded as the whole sentences they touch
+    pub code_whole_sentences: bool,
+
+    /// Percent coded at which a note counts as done on the coverage dashboard
+    pub coverage_target: usize,
+
+    /// How new coded segments are anchored: "chars" (exact offsets) or "lines" (whole lines)
+    pub segment_anchors: Anchor,
+
+    /// Rows where coded segments start end in ⟨code⟩ labels in the editor
+    pub code_labels: bool,
+
+    /// Every note gets a timestamp ID ("202403151030") that [[links]] can use
+    pub zettel_ids: bool,
+
+    /// Tags are suggested (as chips under the editor) a moment after the open note is saved
+    pub tag_suggestions: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            theme: "dark".to_string(),
+            themes: BTreeMap::new(),
+            list_density: ListDensity::Compact,
+            line_spacing: LineSpacing::Single,
+            clipboard: ClipboardBackend::Auto,
+            usage_insights: false,
+            large_ui: false,
+            input: InputBackend::Auto,
+            coder: None,
+            search: Analyzer::default(),
+            ranking: Ranking::default(),
+            inline_images: ImageProtocol::Auto,
+            auto_lock_minutes: 10,
+            sync: None,
+            auto_title: false,
+            list_preview: false,
+            inherit_code_colors: false,
+            backup_interval_hours: 24,
+            backup_keep: 20,
+            stopwords: Vec::new(),
+            auto_code_unit: Unit::Exact,
+            code_whole_sentences: false,
+            coverage_target: 90,
+            segment_anchors: Anchor::Chars,
+            code_labels: false,
+            zettel_ids: false,
+            tag_suggestions: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn path(notes_dir: &Path) -> PathBuf {
+        notes_dir.join("config.json")
+    }
+
+    pub fn load(notes_dir: &Path) -> Result<Self> {
+        let path = Self::path(notes_dir);
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+
+        let json = std::fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    pub fn save(&self, notes_dir: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(Self::path(notes_dir), json)?;
+        Ok(())
+    }
+
+    /// Who to credit for coding decisions
+    pub fn coder_name(&self) -> String {
+        self.coder.clone()
+            .or_else(|| std::env::var("USER").ok())
+            .or_else(|| std::env::var("USERNAME").ok())
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+
+    /// Resolve a theme by name - user themes shadow built-ins, unknown names fall back to dark
+    pub fn resolve_theme(&self, name: &str) -> Theme {
+        self.themes.get(name)
+            .cloned()
+            .or_else(|| Theme::builtin(name))
+            .unwrap_or_default()
+    }
+
+    pub fn active_theme(&self) -> Theme {
+        self.resolve_theme(&self.theme)
+    }
+
+    /// All selectable theme names: built-ins first, then user themes
+    pub fn theme_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = Theme::BUILTIN_NAMES.iter().map(|s| s.to_string()).collect();
+        for name in self.themes.keys() {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        names
+    }
+
+    /// Advance to the next theme and return its name
+    pub fn cycle_theme(&mut self) -> String {
+        let names = self.theme_names();
+        let current = names.iter().position(|n| *n == self.theme).unwrap_or(0);
+        self.theme = names[(current + 1) % names.len()].clone();
+        self.theme.clone()
+    }
+}
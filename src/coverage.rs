@@ -0,0 +1,107 @@
+// Coding coverage - how much of each note is coded, so no transcript is left half done
+//
+// A note's coverage is the share of its text (front matter and whitespace left out) that
+// at least one code covers; machine suggestions don't count until they are accepted. The
+// editor footer shows it for the open note with the line the last coded segment ends on,
+// and Ctrl+G O lists every note below "coverage_target" percent, least coded first, so
+// Enter picks up where coding stopped.
+
+use std::collections::HashMap;
+
+use crate::front_matter::parse_front_matter;
+use crate::note_store::Note;
+use crate::qda_codes::CodeApplication;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Coverage {
+    pub chars: usize,               // Non-whitespace chars of the body
+    pub coded: usize,               // Of those, the ones inside a segment
+    pub last_coded: Option<usize>,  // Char offset where the furthest segment ends
+    pub last_line: Option<usize>,   // 1-based line of the last coded char
+}
+
+impl Coverage {
+    /// Coverage of `text` by `spans` (char offsets into the whole text)
+    pub fn of(text: &str, spans: &[(usize, usize)]) -> Self {
+        let body_start = parse_front_matter(text).map_or(0, |fm| text[..fm.body_start].chars().count());
+        let chars: Vec<char> = text.chars().collect();
+        let mut covered = vec![false; chars.len()];
+        let mut last: Option<usize> = None;
+        for &(start, end) in spans {
+            let end = end.min(chars.len());
+            if end <= start {
+                continue;
+            }
+            covered[start..end].iter_mut().for_each(|c| *c = true);
+            last = Some(last.map_or(end, |l| l.max(end)));
+        }
+
+        let body = chars.iter().zip(&covered).skip(body_start).filter(|(c, _)| !c.is_whitespace());
+        let (total, coded) = body.fold((0, 0), |(t, k), (_, &covered)| (t + 1, k + covered as usize));
+        Coverage {
+            chars: total,
+            coded,
+            last_coded: last,
+            last_line: last.map(|end| chars[..end - 1].iter().filter(|&&c| c == '\n').count() + 1),
+        }
+    }
+
+    /// Whole percent coded, rounded down so a note only shows 100 when every char is coded
+    pub fn percent(&self) -> usize {
+        if self.chars == 0 { 0 } else { self.coded * 100 / self.chars }
+    }
+
+    /// "42% coded to ln 120", or "uncoded"
+    pub fn label(&self) -> String {
+        match self.last_line {
+            Some(line) if self.coded > 0 => format!("{}% coded to ln {}", self.percent(), line),
+            _ => "uncoded".to_string(),
+        }
+    }
+}
+
+/// The spans coded in a note: its own segments and its reviewed applications
+pub fn spans(note: &Note, applications: &[CodeApplication]) -> Vec<(usize, usize)> {
+    note.codes.iter()
+        .map(|s| (s.start_offset, s.end_offset))
+        .chain(applications.iter().filter(|a| !a.suggested).map(|a| (a.start_offset, a.end_offset)))
+        .collect()
+}
+
+/// A row of the coverage dashboard
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub note_id: String,
+    pub title: String,
+    pub coverage: Coverage,
+}
+
+/// Notes with text, least coded first (ties by title); with `target` set, only those below it
+pub fn dashboard(notes: &[Note], applications: &HashMap<String, Vec<CodeApplication>>, target: Option<usize>) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = notes.iter()
+        .map(|note| {
+            let applications = applications.get(&note.id).map(Vec::as_slice).unwrap_or(&[]);
+            Entry {
+                note_id: note.id.clone(),
+                title: note.title.clone(),
+                coverage: Coverage::of(&note.content, &spans(note, applications)),
+            }
+        })
+        .filter(|e| e.coverage.chars > 0 && target.map_or(true, |t| e.coverage.percent() < t))
+        .collect();
+    entries.sort_by(|a, b| a.coverage.percent().cmp(&b.coverage.percent()).then_with(|| a.title.cmp(&b.title)));
+    entries
+}
+
+pub fn dashboard_lines(entries: &[Entry]) -> Vec<String> {
+    entries.iter()
+        .map(|e| {
+            let title: String = e.title.chars().take(40).collect();
+            let bar_len = e.coverage.percent() / 10;
+            let bar = format!("{}{}", "█".repeat(bar_len), "░".repeat(10 - bar_len));
+            let last = e.coverage.last_line.filter(|_| e.coverage.coded > 0)
+                .map_or_else(|| "uncoded".to_string(), |line| format!("to ln {}", line));
+            format!("{} {:>3}%  {:<40}  {}", bar, e.coverage.percent(), title, last)
+        })
+        .collect()
+}
@@ -0,0 +1,282 @@
+// CSV import - one note per row, the usual way open-ended survey responses come in
+//
+//   snyfter3 import-csv responses.csv                          (asks which column is what)
+//   snyfter3 import-csv responses.csv --title id --content q5 --attr age --attr region
+//
+// Columns map to the note title, its text (several text columns become ## sections),
+// tags (values split on commas / semicolons) and case attributes, which go into the
+// front matter next to `source` and `row` so every note can be traced back to its
+// line in the file. Rows with no text in any content column are skipped.
+
+use anyhow::{bail, Context, Result};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+
+use crate::note_store::NoteStore;
+
+#[derive(Debug, Clone, Default)]
+pub struct ColumnMapping {
+    pub title: Option<usize>,
+    pub content: Vec<usize>,
+    pub tags: Vec<usize>,
+    pub attributes: Vec<usize>,
+}
+
+/// Column choices from the command line, by header name or 1-based number
+#[derive(Debug, Default)]
+pub struct MappingArgs {
+    pub title: Option<String>,
+    pub content: Vec<String>,
+    pub tags: Vec<String>,
+    pub attributes: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct CsvSummary {
+    pub created: usize,
+    pub skipped: usize,  // Rows without any content
+}
+
+impl CsvSummary {
+    pub fn describe(&self, dry_run: bool) -> String {
+        let verb = if dry_run { "would be created" } else { "created" };
+        let mut text = format!("{} notes {}", self.created, verb);
+        if self.skipped > 0 {
+            text.push_str(&format!(", {} empty rows skipped", self.skipped));
+        }
+        text
+    }
+}
+
+pub fn import_csv(notes_dir: &Path, path: &Path, args: &MappingArgs, dry_run: bool) -> Result<CsvSummary> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("reading {}", path.display()))?;
+    let mut rows = parse(&text);
+    if rows.is_empty() {
+        bail!("{} is empty", path.display());
+    }
+    let headers = rows.remove(0);
+    let source = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+
+    let mapping = if args.content.is_empty() {
+        if !io::stdin().is_terminal() {
+            bail!("no --content column given (run in a terminal to choose columns interactively)");
+        }
+        prompt_mapping(&source, &headers, &rows)?
+    } else {
+        ColumnMapping {
+            title: args.title.as_deref().map(|c| column(&headers, c)).transpose()?,
+            content: args.content.iter().map(|c| column(&headers, c)).collect::<Result<_>>()?,
+            tags: args.tags.iter().map(|c| column(&headers, c)).collect::<Result<_>>()?,
+            attributes: args.attributes.iter().map(|c| column(&headers, c)).collect::<Result<_>>()?,
+        }
+    };
+
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| "row".to_string());
+    let mut summary = CsvSummary::default();
+    let mut drafts = Vec::new();
+
+    for (i, row) in rows.iter().enumerate() {
+        let row_no = i + 1;  // Data rows, not counting the header
+        let Some(content) = note_content(&headers, row, &mapping, &source, row_no) else {
+            summary.skipped += 1;
+            continue;
+        };
+        let title = mapping.title
+            .and_then(|c| cell(row, c))
+            .map(|t| t.lines().next().unwrap_or_default().to_string())
+            .unwrap_or_else(|| format!("{} #{}", stem, row_no));
+
+        drafts.push((title, content));
+        summary.created += 1;
+    }
+
+    // All rows go in in one transaction, and into the search index right away
+    if !dry_run {
+        let notes = NoteStore::new(notes_dir)?.create_notes(&drafts)?;
+        crate::cli::index_imported(notes_dir, &notes);
+    }
+
+    tracing::info!(file = %path.display(), created = summary.created, skipped = summary.skipped, dry_run, "csv import");
+    Ok(summary)
+}
+
+/// Front matter with the case attributes, then the text columns, then the tags
+fn note_content(headers: &[String], row: &[String], mapping: &ColumnMapping, source: &str, row_no: usize) -> Option<String> {
+    let sections: Vec<(&str, &str)> = mapping.content.iter()
+        .filter_map(|&c| Some((headers[c].as_str(), cell(row, c)?)))
+        .collect();
+    if sections.is_empty() {
+        return None;
+    }
+
+    let mut content = String::from("---\n");
+    content.push_str(&format!("source: {}\nrow: {}\n", source, row_no));
+    for &c in &mapping.attributes {
+        if let Some(value) = cell(row, c) {
+            content.push_str(&format!("{}: {}\n", attribute_key(&headers[c]), value.split_whitespace().collect::<Vec<_>>().join(" ")));
+        }
+    }
+    content.push_str("---\n\n");
+
+    // A single answer column is the note; several become one section each
+    if let [(_, text)] = sections.as_slice() {
+        content.push_str(text);
+        content.push('\n');
+    } else {
+        for (i, (header, text)) in sections.iter().enumerate() {
+            if i > 0 {
+                content.push('\n');
+            }
+            content.push_str(&format!("## {}\n\n{}\n", header, text));
+        }
+    }
+
+    let tags: Vec<String> = mapping.tags.iter()
+        .filter_map(|&c| cell(row, c))
+        .flat_map(|v| v.split([',', ';']).map(tag_slug).collect::<Vec<_>>())
+        .filter(|t| !t.is_empty())
+        .collect();
+    if !tags.is_empty() {
+        let line = tags.iter().map(|t| format!("#{}", t)).collect::<Vec<_>>().join(" ");
+        content.push_str(&format!("\n{}\n", line));
+    }
+
+    Some(content)
+}
+
+/// Non-empty, trimmed cell value
+fn cell(row: &[String], column: usize) -> Option<&str> {
+    row.get(column).map(|v| v.trim()).filter(|v| !v.is_empty())
+}
+
+/// Front matter key for a header: lowercase, with runs of anything else turned into `_`
+fn attribute_key(header: &str) -> String {
+    let key: String = header.trim().to_lowercase().chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    let key = key.split('_').filter(|p| !p.is_empty()).collect::<Vec<_>>().join("_");
+    if key.is_empty() { "attribute".to_string() } else { key }
+}
+
+/// Tag value as a #tag: spaces become dashes, other punctuation goes
+fn tag_slug(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join("-")
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+        .collect()
+}
+
+/// Resolve a column given by header name (any case) or 1-based number
+fn column(headers: &[String], spec: &str) -> Result<usize> {
+    let spec = spec.trim();
+    if let Some(i) = headers.iter().position(|h| h.trim().eq_ignore_ascii_case(spec)) {
+        return Ok(i);
+    }
+    match spec.parse::<usize>() {
+        Ok(n) if (1..=headers.len()).contains(&n) => Ok(n - 1),
+        _ => bail!("no column \"{}\" (columns: {})", spec, headers.join(", ")),
+    }
+}
+
+/// Ask which column is which, showing a sample value for each
+fn prompt_mapping(source: &str, headers: &[String], rows: &[Vec<String>]) -> Result<ColumnMapping> {
+    // The column with the longest answers on average is most likely the open-ended one
+    let average_len = |c: usize| rows.iter().map(|r| cell(r, c).map_or(0, str::len)).sum::<usize>() / rows.len().max(1);
+    let longest = (0..headers.len()).max_by_key(|&c| average_len(c)).unwrap_or(0);
+
+    println!("Columns in {} ({} rows):", source, rows.len());
+    for (i, header) in headers.iter().enumerate() {
+        let sample = rows.iter().find_map(|r| cell(r, i)).unwrap_or("");
+        let sample: String = sample.split_whitespace().collect::<Vec<_>>().join(" ");
+        let short: String = sample.chars().take(40).collect();
+        let ellipsis = if sample.chars().count() > 40 { "..." } else { "" };
+        println!("  {:>2}  {:<24} e.g. \"{}{}\"", i + 1, header, short, ellipsis);
+    }
+    println!();
+
+    let mut stdin = io::stdin().lock();
+    let mut ask = |question: &str, default: &str| -> Result<Vec<usize>> {
+        loop {
+            print!("{} [{}]: ", question, if default.is_empty() { "none" } else { default });
+            io::stdout().flush()?;
+            let mut answer = String::new();
+            stdin.read_line(&mut answer)?;
+            let answer = match answer.trim() {
+                "" => default.to_string(),
+                "-" => String::new(),
+                other => other.to_string(),
+            };
+            let columns: Result<Vec<usize>> = answer.split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(|s| column(headers, s))
+                .collect();
+            match columns {
+                Ok(columns) => return Ok(columns),
+                Err(e) => println!("  {}", e),
+            }
+        }
+    };
+
+    let title = ask("Title column (empty: file name and row number)", "")?.into_iter().next();
+    let content = loop {
+        let content = ask("Content columns, comma separated", &(longest + 1).to_string())?;
+        if !content.is_empty() {
+            break content;
+        }
+        println!("  At least one content column is needed");
+    };
+    let tags = ask("Tag columns", "")?;
+
+    // Everything not used yet is a case attribute unless told otherwise ("-" for none)
+    let rest: Vec<String> = (0..headers.len())
+        .filter(|c| Some(*c) != title && !content.contains(c) && !tags.contains(c))
+        .map(|c| (c + 1).to_string())
+        .collect();
+    let attributes = ask("Case attribute columns (\"-\" for none)", &rest.join(","))?;
+
+    Ok(ColumnMapping { title, content, tags, attributes })
+}
+
+/// RFC 4180 records: quoted fields may hold commas, doubled quotes and line breaks
+pub fn parse(text: &str) -> Vec<Vec<String>> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);  // Spreadsheet exports often start with a BOM
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    // Blank lines carry no record
+    rows.retain(|r| !(r.len() == 1 && r[0].is_empty()));
+    rows
+}
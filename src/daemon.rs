@@ -0,0 +1,181 @@
+// Companion daemon for OS-level hotkeys (skhd, Hammerspoon, sxhkd)
+//
+// Listens on <notes_dir>/snyfter3.sock for one-line requests:
+//   capture <text>   - create a note (first line becomes the title)
+//   search <query>   - ask the running TUI to show a search
+//   ping             - liveness check
+// Each connection gets a single "ok ..." or "error ..." line back, and is dropped if it
+// doesn't send its line within a few seconds so one stuck client can't hold up the rest.
+// The socket is only open to the current user and is removed on Ctrl-C or SIGTERM.
+// Requests meant for the TUI are queued in an inbox file that the TUI drains.
+
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// How long a client gets to send its request line and read the answer
+#[cfg(unix)]
+const CLIENT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum InboxItem {
+    Search { query: String },
+    Captured { note_id: String },
+}
+
+pub fn socket_path(notes_dir: &Path) -> PathBuf {
+    notes_dir.join("snyfter3.sock")
+}
+
+fn inbox_path(notes_dir: &Path) -> PathBuf {
+    notes_dir.join("daemon_inbox.jsonl")
+}
+
+/// Queue an item for the TUI (also used by `snyfter3 add` so a running app picks up the note)
+pub fn push_inbox(notes_dir: &Path, item: &InboxItem) -> Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(inbox_path(notes_dir))?;
+    writeln!(file, "{}", serde_json::to_string(item)?)?;
+    Ok(())
+}
+
+/// Read and clear everything the daemon queued since the last call
+pub fn take_inbox(notes_dir: &Path) -> Vec<InboxItem> {
+    let path = inbox_path(notes_dir);
+    if !path.exists() {
+        return Vec::new();
+    }
+
+    // Rename first so a request arriving mid-read lands in a fresh file
+    let taken = notes_dir.join("daemon_inbox.taking");
+    if std::fs::rename(&path, &taken).is_err() {
+        return Vec::new();
+    }
+    let items = std::fs::read_to_string(&taken)
+        .map(|text| text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+        .unwrap_or_default();
+    let _ = std::fs::remove_file(&taken);
+    items
+}
+
+/// Handle a single request line and return the response line
+fn handle_request(notes_dir: &Path, line: &str) -> Result<String> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let (verb, rest) = line.split_once(' ').unwrap_or((line, ""));
+
+    match verb {
+        "capture" => {
+            // Allow literal "\n" so one-line hotkey scripts can send multiple lines
+            let text = rest.replace("\\n", "\n");
+            let text = text.trim();
+            if text.is_empty() {
+                anyhow::bail!("nothing to capture");
+            }
+
+            let title = text.lines().next().unwrap_or("").trim();
+            let title = if title.is_empty() {
+                format!("Capture {}", chrono::Local::now().format("%Y-%m-%d %H:%M"))
+            } else {
+                title.to_string()
+            };
+
+            let mut store = crate::note_store::NoteStore::new(notes_dir)?;
+            let note = store.create_note(&title, text)?;
+            crate::cli::index_imported(notes_dir, std::slice::from_ref(&note));
+            push_inbox(notes_dir, &InboxItem::Captured { note_id: note.id.clone() })?;
+            Ok(format!("ok {}", note.id))
+        }
+        "search" => {
+            push_inbox(notes_dir, &InboxItem::Search { query: rest.to_string() })?;
+            Ok("ok".to_string())
+        }
+        "ping" => Ok("ok pong".to_string()),
+        other => anyhow::bail!("unknown request: {}", other),
+    }
+}
+
+/// Removes the socket file when the daemon stops, however `run` returns
+#[cfg(unix)]
+struct SocketFile(PathBuf);
+
+#[cfg(unix)]
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+#[cfg(unix)]
+pub async fn run(notes_dir: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::UnixListener;
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let path = socket_path(notes_dir);
+    if path.exists() {
+        // A live daemon answers; a stale socket from a crash just gets replaced
+        if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+            anyhow::bail!("daemon already running on {}", path.display());
+        }
+        std::fs::remove_file(&path)?;
+    }
+
+    let listener = UnixListener::bind(&path)?;
+    let socket = SocketFile(path.clone());
+    // Anyone who can connect can add notes
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    println!("snyfter3 daemon listening on {}", path.display());
+    tracing::info!(socket = %path.display(), "daemon listening");
+
+    // Requests are served on their own thread; this one waits for the signal to stop
+    let dir = notes_dir.to_path_buf();
+    std::thread::spawn(move || serve(&dir, listener));
+
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
+    }
+    tracing::info!("daemon stopping");
+    drop(socket);
+    Ok(())
+}
+
+#[cfg(unix)]
+fn serve(notes_dir: &Path, listener: std::os::unix::net::UnixListener) {
+    use std::io::{BufRead, BufReader};
+
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                tracing::warn!(error = %e, "daemon connection failed");
+                continue;
+            }
+        };
+        if let Err(e) = stream.set_read_timeout(Some(CLIENT_TIMEOUT))
+            .and_then(|_| stream.set_write_timeout(Some(CLIENT_TIMEOUT)))
+        {
+            tracing::warn!(error = %e, "daemon connection failed");
+            continue;
+        }
+
+        let mut line = String::new();
+        if BufReader::new(&stream).read_line(&mut line).is_err() {
+            continue;
+        }
+
+        let response = handle_request(notes_dir, &line)
+            .unwrap_or_else(|e| format!("error {}", e));
+        let _ = writeln!(stream, "{}", response);
+    }
+}
+
+#[cfg(not(unix))]
+pub async fn run(_notes_dir: &Path) -> Result<()> {
+    anyhow::bail!("daemon mode needs Unix domain sockets and is not available on this platform")
+}
@@ -0,0 +1,79 @@
+// Daily notes - one note per day, found again by its date
+//
+// A note is a daily note if its front matter says `type: daily` with a `date:`, or if
+// its title is just the date (YYYY-MM-DD). The calendar picker marks days that have one.
+
+use chrono::{Datelike, Local, NaiveDate, Weekday};
+use std::collections::HashSet;
+
+use crate::front_matter::parse_front_matter;
+use crate::note_store::Note;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// The day a daily note is for
+pub fn daily_date(note: &Note) -> Option<NaiveDate> {
+    let from_front_matter = parse_front_matter(&note.content)
+        .filter(|fm| fm.get("type") == Some("daily"))
+        .and_then(|fm| fm.get("date").and_then(|d| NaiveDate::parse_from_str(d, DATE_FORMAT).ok()));
+    from_front_matter.or_else(|| NaiveDate::parse_from_str(note.title.trim(), DATE_FORMAT).ok())
+}
+
+pub fn find(notes: &[Note], date: NaiveDate) -> Option<&Note> {
+    notes.iter().find(|n| daily_date(n) == Some(date))
+}
+
+/// Nearest daily note strictly before (or after) `from`
+pub fn neighbor(notes: &[Note], from: NaiveDate, forward: bool) -> Option<&Note> {
+    let dated = notes.iter().filter_map(|n| Some((daily_date(n)?, n)));
+    if forward {
+        dated.filter(|(d, _)| *d > from).min_by_key(|(d, _)| *d).map(|(_, n)| n)
+    } else {
+        dated.filter(|(d, _)| *d < from).max_by_key(|(d, _)| *d).map(|(_, n)| n)
+    }
+}
+
+/// Month grid around `selected`: [day] is the selection, (day) today, a dot marks a daily note
+pub fn calendar_lines(selected: NaiveDate, notes: &[Note]) -> Vec<String> {
+    let have_notes: HashSet<NaiveDate> = notes.iter().filter_map(daily_date).collect();
+    let today = today();
+    let first = selected.with_day(1).unwrap_or(selected);
+
+    let mut lines = vec![
+        format!("{:^34}", first.format("%B %Y").to_string()),
+        " Mo   Tu   We   Th   Fr   Sa   Su".to_string(),
+    ];
+    let mut week = " ".repeat(5 * first.weekday().num_days_from_monday() as usize);
+    let mut day = first;
+    while day.month() == first.month() {
+        let (open, close) = if day == selected {
+            ('[', ']')
+        } else if day == today {
+            ('(', ')')
+        } else {
+            (' ', ' ')
+        };
+        let mark = if have_notes.contains(&day) { '•' } else { ' ' };
+        week.push_str(&format!("{}{:>2}{}{}", open, day.day(), mark, close));
+        if day.weekday() == Weekday::Sun {
+            lines.push(week.trim_end().to_string());
+            week.clear();
+        }
+        match day.succ_opt() {
+            Some(next) => day = next,
+            None => break,
+        }
+    }
+    if !week.trim().is_empty() {
+        lines.push(week.trim_end().to_string());
+    }
+
+    lines.push(String::new());
+    lines.push("• daily note  (  ) today".to_string());
+    lines.push("←→ day  ↑↓ week  PgUp/PgDn month  t today".to_string());
+    lines
+}
@@ -0,0 +1,228 @@
+// Date filters - `created:` and `updated:` in the search bar
+//
+//   updated:week  created:2024-03  updated:2024-03-01..2024-03-15  created:<2024  updated:30d
+//
+// A value is a named period (today, yesterday, week - since Monday - last-week, month,
+// last-month, year, last-year), the last N days (`30d`), a day, month or year
+// (`2024-03-15`, `2024-03`, `2024`), two of those joined by `..` for everything from the
+// start of one to the end of the other (either end may be left off: `2024-03..`), or one
+// after `>`, `>=`, `<` or `<=`. Dates are local days. They narrow the notes along with
+// the rest of the query; Ctrl+D in the search bar picks one from a list or takes a
+// range typed in. The date chip under the search bar is one of these too.
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Utc};
+
+use crate::note_store::Note;
+
+/// The named periods the picker offers, with their labels
+pub const PRESETS: &[(&str, &str)] = &[
+    ("today", "today"),
+    ("yesterday", "yesterday"),
+    ("week", "this week"),
+    ("last-week", "last week"),
+    ("month", "this month"),
+    ("last-month", "last month"),
+    ("30d", "in the last 30 days"),
+    ("year", "this year"),
+];
+
+/// The search terms taking a date
+pub const FIELDS: &[&str] = &["updated", "created"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateField {
+    Created,
+    Updated,
+}
+
+/// A `created:` / `updated:` term: notes from `from` (inclusive) up to `to` (exclusive)
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateFilter {
+    pub field: DateField,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
+impl DateFilter {
+    /// `field` is "created" or "updated" (any case); None when either part isn't understood
+    pub fn parse(field: &str, value: &str) -> Option<DateFilter> {
+        Self::parse_on(field, value, Local::now().date_naive())
+    }
+
+    /// `parse` with "today" being `today`
+    pub fn parse_on(field: &str, value: &str, today: NaiveDate) -> Option<DateFilter> {
+        let field = match field.to_lowercase().as_str() {
+            "created" => DateField::Created,
+            "updated" => DateField::Updated,
+            _ => return None,
+        };
+        let (from, to) = bounds(&value.to_lowercase(), today)?;
+        Some(DateFilter { field, from, to })
+    }
+
+    pub fn matches(&self, note: &Note) -> bool {
+        let at = match self.field {
+            DateField::Created => note.created_at,
+            DateField::Updated => note.updated_at,
+        };
+        self.from.map_or(true, |from| at >= from) && self.to.map_or(true, |to| at < to)
+    }
+}
+
+/// Whether a search bar word is a date term (to replace it with another)
+pub fn is_date_term(word: &str) -> bool {
+    word.split_once(':').is_some_and(|(key, _)| FIELDS.iter().any(|f| f.eq_ignore_ascii_case(key)))
+}
+
+fn bounds(value: &str, today: NaiveDate) -> Option<(Option<DateTime<Utc>>, Option<DateTime<Utc>>)> {
+    if let Some(days) = value.strip_suffix('d').and_then(|n| n.parse::<i64>().ok()).filter(|&n| n > 0) {
+        return Some((start_of(today - Duration::days(days - 1)), None));
+    }
+    if let Some((first, last)) = value.split_once("..") {
+        let from = if first.is_empty() { None } else { Some(period(first, today)?.0) };
+        let to = if last.is_empty() { None } else { Some(period(last, today)?.1) };
+        return Some((from.and_then(start_of), to.and_then(start_of)));
+    }
+    for op in [">=", "<=", ">", "<"] {
+        if let Some(date) = value.strip_prefix(op) {
+            let (start, end) = period(date, today)?;
+            return Some(match op {
+                ">=" => (start_of(start), None),
+                ">" => (start_of(end), None),
+                "<=" => (None, start_of(end)),
+                _ => (None, start_of(start)),
+            });
+        }
+    }
+    let (start, end) = period(value, today)?;
+    Some((start_of(start), start_of(end)))
+}
+
+/// The days a period covers, as [first, the day after the last)
+fn period(value: &str, today: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+    let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let month = today.with_day(1)?;
+    let year = NaiveDate::from_ymd_opt(today.year(), 1, 1)?;
+    let day = Duration::days(1);
+    Some(match value {
+        "today" => (today, today + day),
+        "yesterday" => (today - day, today),
+        "week" => (monday, monday + Duration::days(7)),
+        "last-week" => (monday - Duration::days(7), monday),
+        "month" => (month, next_month(month)?),
+        "last-month" => (previous_month(month)?, month),
+        "year" => (year, year.with_year(year.year() + 1)?),
+        "last-year" => (year.with_year(year.year() - 1)?, year),
+        _ => {
+            let parts: Vec<&str> = value.split('-').collect();
+            let numbers: Vec<u32> = parts.iter().map(|p| p.parse().ok()).collect::<Option<_>>()?;
+            match numbers.as_slice() {
+                [y] if parts[0].len() == 4 => {
+                    let start = NaiveDate::from_ymd_opt(*y as i32, 1, 1)?;
+                    (start, start.with_year(start.year() + 1)?)
+                }
+                [y, m] => {
+                    let start = NaiveDate::from_ymd_opt(*y as i32, *m, 1)?;
+                    (start, next_month(start)?)
+                }
+                [y, m, d] => {
+                    let date = NaiveDate::from_ymd_opt(*y as i32, *m, *d)?;
+                    (date, date + day)
+                }
+                _ => return None,
+            }
+        }
+    })
+}
+
+fn next_month(first: NaiveDate) -> Option<NaiveDate> {
+    match first.month() {
+        12 => NaiveDate::from_ymd_opt(first.year() + 1, 1, 1),
+        m => NaiveDate::from_ymd_opt(first.year(), m + 1, 1),
+    }
+}
+
+fn previous_month(first: NaiveDate) -> Option<NaiveDate> {
+    match first.month() {
+        1 => NaiveDate::from_ymd_opt(first.year() - 1, 12, 1),
+        m => NaiveDate::from_ymd_opt(first.year(), m - 1, 1),
+    }
+}
+
+/// Local midnight starting `date`
+fn start_of(date: NaiveDate) -> Option<DateTime<Utc>> {
+    date.and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+        .map(|t| t.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A Wednesday in a leap year
+    fn today() -> NaiveDate {
+        day(2024, 3, 20)
+    }
+
+    fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn at(y: i32, m: u32, d: u32) -> Option<DateTime<Utc>> {
+        start_of(day(y, m, d))
+    }
+
+    #[test]
+    fn periods() {
+        assert_eq!(period("2024", today()), Some((day(2024, 1, 1), day(2025, 1, 1))));
+        assert_eq!(period("2024-03", today()), Some((day(2024, 3, 1), day(2024, 4, 1))));
+        assert_eq!(period("2024-12", today()), Some((day(2024, 12, 1), day(2025, 1, 1))));
+        assert_eq!(period("2024-02-29", today()), Some((day(2024, 2, 29), day(2024, 3, 1))));
+        assert_eq!(period("today", today()), Some((day(2024, 3, 20), day(2024, 3, 21))));
+        assert_eq!(period("yesterday", today()), Some((day(2024, 3, 19), day(2024, 3, 20))));
+        assert_eq!(period("week", today()), Some((day(2024, 3, 18), day(2024, 3, 25))));
+        assert_eq!(period("last-week", today()), Some((day(2024, 3, 11), day(2024, 3, 18))));
+        assert_eq!(period("last-month", today()), Some((day(2024, 2, 1), day(2024, 3, 1))));
+        assert_eq!(period("last-year", today()), Some((day(2023, 1, 1), day(2024, 1, 1))));
+    }
+
+    #[test]
+    fn periods_not_understood() {
+        for value in ["24", "2024-13", "2023-02-29", "2024-03-01-02", "soon", ""] {
+            assert_eq!(period(value, today()), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn bounds_of_each_form() {
+        assert_eq!(bounds("30d", today()), Some((at(2024, 2, 20), None)));
+        assert_eq!(bounds("1d", today()), Some((at(2024, 3, 20), None)));
+        assert_eq!(bounds("2024", today()), Some((at(2024, 1, 1), at(2025, 1, 1))));
+        assert_eq!(bounds("2024-03", today()), Some((at(2024, 3, 1), at(2024, 4, 1))));
+        assert_eq!(bounds("2024-03-01..2024-03-15", today()), Some((at(2024, 3, 1), at(2024, 3, 16))));
+        assert_eq!(bounds("2024-01..2024-02", today()), Some((at(2024, 1, 1), at(2024, 3, 1))));
+        assert_eq!(bounds("2024-03..", today()), Some((at(2024, 3, 1), None)));
+        assert_eq!(bounds("..2024-03", today()), Some((None, at(2024, 4, 1))));
+        assert_eq!(bounds(">=2024-03-10", today()), Some((at(2024, 3, 10), None)));
+        assert_eq!(bounds(">2024-03-10", today()), Some((at(2024, 3, 11), None)));
+        assert_eq!(bounds("<2024", today()), Some((None, at(2024, 1, 1))));
+        assert_eq!(bounds("<=2024-03", today()), Some((None, at(2024, 4, 1))));
+    }
+
+    #[test]
+    fn bounds_not_understood() {
+        for value in ["0d", "-3d", "2024-03-01..nope", ">soon", "<"] {
+            assert_eq!(bounds(value, today()), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn parse_checks_the_field() {
+        let filter = DateFilter::parse_on("Updated", "2024", today()).unwrap();
+        assert_eq!(filter.field, DateField::Updated);
+        assert_eq!((filter.from, filter.to), (at(2024, 1, 1), at(2025, 1, 1)));
+        assert_eq!(DateFilter::parse_on("created", "Last-Month", today()).map(|f| f.from), Some(at(2024, 2, 1)));
+        assert_eq!(DateFilter::parse_on("modified", "2024", today()), None);
+    }
+}
@@ -0,0 +1,250 @@
+// SQLite connections and schema migrations
+//
+// Every database is opened through `open`: the SQLCipher key when a passphrase is set
+// (see lock.rs), write-ahead logging (the TUI, the daemon and CLI runs can read while
+// another writes), foreign keys enforced, and a busy timeout instead of an immediate
+// "database is locked" when two of them write at once.
+//
+// Schema changes are numbered migrations. A database records the last one it has
+// seen in `PRAGMA user_version`; `migrate` applies the newer ones in order, each in
+// its own transaction, so a vault from any earlier release is brought up to date on
+// open and a failed step leaves it at the previous version. A database that already
+// holds tables is snapshotted into backups/ first. Migrations are only ever
+// appended - never edit or renumber one that has shipped.
+//
+// Vaults from before this framework are at version 0 but already have some of the
+// tables, so the early steps are written to be harmless on those (IF NOT EXISTS,
+// adding a column only when it is missing).
+
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::backup;
+
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub apply: fn(&Connection) -> Result<()>,
+}
+
+/// Open a database with the pragmas every store relies on
+pub fn open(path: &Path) -> Result<Connection> {
+    open_keyed(path, crate::lock::file_key())
+}
+
+/// `open` with an explicit hex key (None: unencrypted), for re-encrypting
+pub fn open_keyed(path: &Path, key: Option<&str>) -> Result<Connection> {
+    let conn = Connection::open(path).with_context(|| format!("opening {}", path.display()))?;
+    if let Some(key) = key {
+        // Has to come before anything reads the file
+        conn.execute_batch(&format!("PRAGMA key = \"x'{}'\";", key))?;
+    }
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    // journal_mode answers with the mode in effect (it stays "memory" for in-memory databases)
+    let mode: String = conn.query_row("PRAGMA journal_mode = WAL", [], |row| row.get(0))?;
+    if !mode.eq_ignore_ascii_case("wal") {
+        tracing::warn!(path = %path.display(), mode = %mode, "WAL not available, using the rollback journal");
+    }
+    conn.execute_batch(
+        "PRAGMA synchronous = NORMAL;
+         PRAGMA foreign_keys = ON;"
+    )?;
+    Ok(conn)
+}
+
+/// Open a database and bring its schema up to the newest migration
+pub fn open_migrated(path: &Path, migrations: &[Migration]) -> Result<Connection> {
+    let mut conn = open(path)?;
+    let current: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    let tables: u32 = conn.query_row("SELECT count(*) FROM sqlite_master WHERE type = 'table'", [], |row| row.get(0))?;
+    // A vault with data in it is copied before its schema changes
+    if tables > 0 && migrations.iter().any(|m| m.version > current) {
+        backup::create_for(&conn, path, "migration")?;
+    }
+    migrate(&mut conn, migrations).with_context(|| format!("migrating {}", path.display()))?;
+    Ok(conn)
+}
+
+/// Apply the migrations newer than the database's user_version
+pub fn migrate(conn: &mut Connection, migrations: &[Migration]) -> Result<()> {
+    let current: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    let latest = migrations.last().map_or(0, |m| m.version);
+    if current > latest {
+        bail!("database schema version {} is newer than this build knows ({}); update snyfter3", current, latest);
+    }
+
+    for migration in migrations.iter().filter(|m| m.version > current) {
+        let tx = conn.transaction()?;
+        (migration.apply)(&tx).with_context(|| format!("migration {} ({})", migration.version, migration.name))?;
+        // PRAGMA doesn't take parameters; the version is a number we control
+        tx.execute_batch(&format!("PRAGMA user_version = {}", migration.version))?;
+        tx.commit()?;
+        tracing::info!(version = migration.version, name = migration.name, "schema migration applied");
+    }
+    Ok(())
+}
+
+/// Add a column to tables created before it existed
+pub fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    if conn.prepare(&format!("SELECT {} FROM {} LIMIT 0", column, table)).is_err() {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
+    Ok(())
+}
+
+/// notes.db: the note store and the highlights, annotations and journal kept next to it
+pub static NOTES: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "notes table",
+        apply: |conn| {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS notes (
+                    id TEXT PRIMARY KEY,
+                    title TEXT NOT NULL,
+                    content TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    tags TEXT,
+                    codes TEXT
+                )"
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 2,
+        name: "trash",
+        apply: |conn| ensure_column(conn, "notes", "deleted_at", "TEXT"),
+    },
+    Migration {
+        version: 3,
+        name: "highlights",
+        apply: |conn| {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS highlights (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    note_id TEXT NOT NULL,
+                    start_offset INTEGER NOT NULL,
+                    end_offset INTEGER NOT NULL,
+                    color TEXT NOT NULL,
+                    created_at TEXT NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS highlights_note ON highlights (note_id);"
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 4,
+        name: "annotations",
+        apply: |conn| {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS annotations (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    note_id TEXT NOT NULL,
+                    start_offset INTEGER NOT NULL,
+                    end_offset INTEGER NOT NULL,
+                    text TEXT NOT NULL,
+                    created_at TEXT NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS annotations_note ON annotations (note_id);"
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 5,
+        name: "project journal",
+        apply: |conn| {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS journal_entries (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    text TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                );"
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 6,
+        name: "read-only notes",
+        apply: |conn| ensure_column(conn, "notes", "read_only", "INTEGER NOT NULL DEFAULT 0"),
+    },
+    Migration {
+        version: 7,
+        name: "zettelkasten ids",
+        apply: |conn| ensure_column(conn, "notes", "zettel_id", "TEXT"),
+    },
+];
+
+/// codes.db: the codebook and where codes are applied
+pub static CODES: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "codes and applications",
+        apply: |conn| {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS codes (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL UNIQUE,
+                    description TEXT,
+                    color_r INTEGER NOT NULL,
+                    color_g INTEGER NOT NULL,
+                    color_b INTEGER NOT NULL,
+                    parent_id TEXT,
+                    shortcut TEXT,
+                    FOREIGN KEY (parent_id) REFERENCES codes(id)
+                );
+                CREATE TABLE IF NOT EXISTS code_applications (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    code_id TEXT NOT NULL,
+                    note_id TEXT NOT NULL,
+                    start_offset INTEGER NOT NULL,
+                    end_offset INTEGER NOT NULL,
+                    memo TEXT,
+                    created_at TEXT NOT NULL,
+                    FOREIGN KEY (code_id) REFERENCES codes(id)
+                )"
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 2,
+        name: "coder and model suggestions",
+        apply: |conn| {
+            ensure_column(conn, "code_applications", "coder", "TEXT")?;
+            ensure_column(conn, "code_applications", "suggested", "INTEGER NOT NULL DEFAULT 0")?;
+            ensure_column(conn, "code_applications", "confidence", "REAL")
+        },
+    },
+    Migration {
+        version: 3,
+        name: "application lookups",
+        apply: |conn| {
+            conn.execute_batch(
+                // Vaults from before foreign keys were enforced may point at deleted parents
+                "UPDATE codes SET parent_id = NULL
+                    WHERE parent_id IS NOT NULL AND parent_id NOT IN (SELECT id FROM codes);
+                 CREATE INDEX IF NOT EXISTS code_applications_note ON code_applications (note_id);
+                 CREATE INDEX IF NOT EXISTS code_applications_code ON code_applications (code_id);"
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 4,
+        name: "line anchors",
+        apply: |conn| {
+            ensure_column(conn, "code_applications", "start_line", "INTEGER")?;
+            ensure_column(conn, "code_applications", "end_line", "INTEGER")
+        },
+    },
+];
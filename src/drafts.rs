@@ -0,0 +1,145 @@
+// Crash-safe drafts - the editor's text, copied to draft.json every few seconds
+//
+// Autosave puts each edit into the database straight away, but a crash (or a kill,
+// or a power cut) between an edit and its save can still lose the latest change.
+// So while a note is open its editor text is also written here, atomically and only
+// when it changed since the last write. A clean exit removes the file; drafts still
+// in it at launch mean the last session ended abruptly, and those that differ from
+// the stored notes are offered back in a recovery prompt. Drafts the user sets
+// aside (Esc) are kept for the next launch.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::note_store::Note;
+
+const WRITE_INTERVAL: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Draft {
+    pub note_id: String,
+    pub title: String,
+    pub content: String,
+    pub written_at: DateTime<Utc>,
+}
+
+pub struct Drafts {
+    path: PathBuf,
+    recovered: BTreeMap<String, Draft>,  // Left by a session that didn't exit cleanly
+    current: Option<Draft>,  // This session's copy of the open note
+    written: Option<u64>,  // Hash of what is on disk for `current`
+    last_write: Instant,
+}
+
+impl Drafts {
+    /// Pick up what the previous session left behind
+    pub fn open(notes_dir: &Path) -> Self {
+        let path = notes_dir.join("draft.json");
+        let recovered = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Drafts { path, recovered, current: None, written: None, last_write: Instant::now() }
+    }
+
+    /// Drop recovered drafts that match what the store already holds (the crash came
+    /// after the save); returns the ones worth offering
+    pub fn worth_recovering(&mut self, notes: &[Note]) -> Vec<Draft> {
+        self.recovered.retain(|id, draft| {
+            notes.iter().find(|n| n.id == *id).map_or(true, |n| n.content != draft.content)
+        });
+        self.recovered.values().cloned().collect()
+    }
+
+    /// Take the recovered drafts off disk (to apply or discard them)
+    pub fn take_recovered(&mut self) -> Result<Vec<Draft>> {
+        let drafts = std::mem::take(&mut self.recovered).into_values().collect();
+        self.persist()?;
+        Ok(drafts)
+    }
+
+    pub fn due(&self) -> bool {
+        self.last_write.elapsed() >= WRITE_INTERVAL
+    }
+
+    /// Copy the open note's text to disk if it changed since the last write
+    pub fn write(&mut self, note_id: &str, title: &str, content: &str) -> Result<()> {
+        self.last_write = Instant::now();
+        let mut hasher = DefaultHasher::new();
+        (note_id, title, content).hash(&mut hasher);
+        let hash = hasher.finish();
+        if self.written == Some(hash) {
+            return Ok(());
+        }
+
+        self.current = Some(Draft {
+            note_id: note_id.to_string(),
+            title: title.to_string(),
+            content: content.to_string(),
+            written_at: Utc::now(),
+        });
+        self.persist()?;
+        self.written = Some(hash);
+        Ok(())
+    }
+
+    /// No note open, or a clean exit: only recovered drafts set aside for later stay on disk
+    pub fn clear(&mut self) -> Result<()> {
+        if self.current.take().is_none() {
+            return Ok(());
+        }
+        self.written = None;
+        self.persist()
+    }
+
+    fn persist(&self) -> Result<()> {
+        let mut all = self.recovered.clone();
+        if let Some(draft) = &self.current {
+            // This session's text for the note is newer than a recovered draft of it
+            all.insert(draft.note_id.clone(), draft.clone());
+        }
+        if all.is_empty() {
+            return match std::fs::remove_file(&self.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            };
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(&all)?)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+/// Lines for the recovery prompt shown at launch
+pub fn summary_lines(drafts: &[Draft], notes: &[Note]) -> Vec<String> {
+    let mut lines = vec![
+        format!("The last session ended without saving {} draft(s):", drafts.len()),
+        String::new(),
+    ];
+    for draft in drafts {
+        let note = notes.iter().find(|n| n.id == draft.note_id);
+        let state = match note {
+            None => " (note no longer exists - will be recreated)".to_string(),
+            Some(n) => {
+                let difference = draft.content.chars().count() as i64 - n.content.chars().count() as i64;
+                format!(" ({:+} chars against the saved note)", difference)
+            }
+        };
+        lines.push(format!(
+            "  {}  {}{}",
+            draft.written_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S"),
+            draft.title,
+            state
+        ));
+    }
+    lines.push(String::new());
+    lines.push("Enter: recover all   d: discard all   Esc: keep for next launch".to_string());
+    lines
+}
@@ -0,0 +1,148 @@
+// Near-duplicate notes - the same title, or mostly the same text
+//
+// Text similarity is the Jaccard overlap of word shingles (every run of SHINGLE
+// consecutive words, lowercased), so reordered paragraphs and small edits still
+// match while notes that merely share vocabulary don't. Candidate pairs come from
+// an inverted index over the shingles; shingles found in many notes (boilerplate,
+// template headings) are left out of it so they neither match nor slow things down.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::note_store::Note;
+
+const SHINGLE: usize = 4;
+const MIN_SIMILARITY: f64 = 0.7;
+const COMMON: usize = 25;  // Shingles in more notes than this don't count
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reason {
+    SameTitle,
+    Similar(f64),  // Jaccard similarity of the texts, 0..1
+}
+
+#[derive(Debug, Clone)]
+pub struct Pair {
+    pub first: String,   // Note ids; the older note first
+    pub second: String,
+    pub reason: Reason,
+}
+
+impl Pair {
+    pub fn label(&self) -> String {
+        match self.reason {
+            Reason::SameTitle => "same title".to_string(),
+            Reason::Similar(s) => format!("{:.0}% alike", s * 100.0),
+        }
+    }
+
+    pub fn involves(&self, id: &str) -> bool {
+        self.first == id || self.second == id
+    }
+}
+
+fn shingles(text: &str) -> HashSet<u64> {
+    let words: Vec<String> = text.split_whitespace()
+        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .filter(|w| !w.is_empty())
+        .collect();
+    // Too short to tell a duplicate from a coincidence
+    if words.len() < SHINGLE {
+        return HashSet::new();
+    }
+    words.windows(SHINGLE)
+        .map(|window| {
+            let mut hasher = DefaultHasher::new();
+            window.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+/// Pairs of likely duplicates, most alike first (same titles before similar texts)
+pub fn find(notes: &[Note]) -> Vec<Pair> {
+    let mut notes: Vec<&Note> = notes.iter().collect();
+    notes.sort_by_key(|n| n.created_at);
+    let mut pairs = Vec::new();
+    let mut seen: HashSet<(usize, usize)> = HashSet::new();
+
+    let mut by_title: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, note) in notes.iter().enumerate() {
+        let title = note.title.trim().to_lowercase();
+        if !title.is_empty() {
+            by_title.entry(title).or_default().push(i);
+        }
+    }
+    for group in by_title.values() {
+        for (n, &a) in group.iter().enumerate() {
+            for &b in &group[n + 1..] {
+                seen.insert((a, b));
+                pairs.push((a, b, Reason::SameTitle));
+            }
+        }
+    }
+
+    let sets: Vec<HashSet<u64>> = notes.iter().map(|n| shingles(&n.content)).collect();
+    let mut index: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, set) in sets.iter().enumerate() {
+        for &shingle in set {
+            index.entry(shingle).or_default().push(i);
+        }
+    }
+    let mut shared: HashMap<(usize, usize), usize> = HashMap::new();
+    for holders in index.values().filter(|h| h.len() > 1 && h.len() <= COMMON) {
+        for (n, &a) in holders.iter().enumerate() {
+            for &b in &holders[n + 1..] {
+                *shared.entry((a, b)).or_default() += 1;
+            }
+        }
+    }
+    for ((a, b), common) in shared {
+        let union = sets[a].len() + sets[b].len() - common;
+        let similarity = common as f64 / union as f64;
+        if similarity >= MIN_SIMILARITY && seen.insert((a, b)) {
+            pairs.push((a, b, Reason::Similar(similarity)));
+        }
+    }
+
+    let rank = |reason: &Reason| match reason {
+        Reason::SameTitle => 2.0,
+        Reason::Similar(s) => *s,
+    };
+    pairs.sort_by(|x, y| rank(&y.2).total_cmp(&rank(&x.2)).then((x.0, x.1).cmp(&(y.0, y.1))));
+    pairs.into_iter()
+        .map(|(a, b, reason)| Pair { first: notes[a].id.clone(), second: notes[b].id.clone(), reason })
+        .collect()
+}
+
+/// Two texts in columns of `width` display cells, separated by " │ "
+pub fn side_by_side(left: &str, right: &str, width: usize) -> Vec<String> {
+    let wrap = |text: &str| -> Vec<String> {
+        let mut rows = Vec::new();
+        for line in text.lines() {
+            let mut row = String::new();
+            let mut used = 0;
+            for c in line.chars() {
+                let w = c.width().unwrap_or(0);
+                if used + w > width {
+                    rows.push(std::mem::take(&mut row));
+                    used = 0;
+                }
+                row.push(c);
+                used += w;
+            }
+            rows.push(row);
+        }
+        rows
+    };
+    let (left, right) = (wrap(left), wrap(right));
+    (0..left.len().max(right.len()))
+        .map(|i| {
+            let l = left.get(i).map(String::as_str).unwrap_or("");
+            let pad = width.saturating_sub(l.width());
+            format!("{}{:pad$} │ {}", l, "", right.get(i).map(String::as_str).unwrap_or(""), pad = pad)
+        })
+        .collect()
+}
@@ -5,7 +5,44 @@
 // CROSSTERM ELIMINATED! Pure ANSI escape sequences
 use std::io::{self, Write};
 use helix_core::Rope;
+use crate::annotations;
 use crate::block_selection::BlockSelection;
+use crate::theme::{Rgb, Theme};
+
+/// A colored span under the text (read-time highlights): start and end-exclusive (row, col), and its background
+pub type Mark = ((usize, usize), (usize, usize), Rgb);
+
+/// An underlined span (annotations): start and end-exclusive (row, col)
+pub type Underline = ((usize, usize), (usize, usize));
+
+// Pre-built ANSI style prefixes derived from the active theme
+struct AnsiStyles {
+    normal: String,     // Regular text
+    cursor: String,     // Block cursor
+    selection: String,  // Regular and block selections
+    highlight: String,  // Search highlights
+    mark_fg: String,    // Text over a highlight mark (background comes from the mark)
+    underline: String,  // Annotated text, added on top of the other styles
+    label: String,      // Extraction method label
+    track: String,      // Scrollbar track
+    thumb: String,      // Scrollbar thumb
+}
+
+impl AnsiStyles {
+    fn from_theme(theme: &Theme) -> Self {
+        Self {
+            normal: format!("\x1b[0m{}{}", theme.editor_bg.ansi_bg(), theme.editor_fg.ansi_fg()),
+            cursor: format!("{}{}", theme.cursor_bg.ansi_bg(), theme.editor_fg.ansi_fg()),
+            selection: format!("{}{}", theme.selection_bg.ansi_bg(), theme.selection_fg.ansi_fg()),
+            highlight: format!("{}{}", theme.highlight_bg.ansi_bg(), theme.selection_fg.ansi_fg()),
+            mark_fg: format!("\x1b[0m{}", theme.editor_fg.ansi_fg()),
+            underline: format!("\x1b[4m\x1b[58;2;{};{};{}m", annotations::COLOR.r, annotations::COLOR.g, annotations::COLOR.b),
+            label: format!("{}{}", theme.header_bg.ansi_bg(), theme.header_fg.ansi_fg()),
+            track: theme.scrollbar_track.ansi_fg(),
+            thumb: theme.scrollbar_thumb.ansi_fg(),
+        }
+    }
+}
 
 pub struct EditPanelRenderer {
     buffer: Vec<Vec<char>>,      // The full extracted content
@@ -15,6 +52,7 @@ pub struct EditPanelRenderer {
     pub scroll_y: u16,               // Vertical scroll offset
     pub viewport_x: usize,           // Current viewport X position for mouse mapping
     pub viewport_y: usize,           // Current viewport Y position for mouse mapping
+    styles: AnsiStyles,              // Colors from the active theme
 }
 
 impl EditPanelRenderer {
@@ -27,8 +65,14 @@ impl EditPanelRenderer {
             scroll_y: 0,
             viewport_x: 0,
             viewport_y: 0,
+            styles: AnsiStyles::from_theme(&Theme::default()),
         }
     }
+
+    /// Pick up colors from the active theme
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.styles = AnsiStyles::from_theme(theme);
+    }
     
     // update_buffer eliminated - using update_from_rope with helix-core
 
@@ -191,7 +235,8 @@ impl EditPanelRenderer {
             let padding = render_width.saturating_sub(label_text.len() as u16);
 
             // Render label with subtle background color
-            print!("\x1b[48;2;40;40;40m\x1b[38;2;200;200;200m{}{}\x1b[0m",
+            print!("{}{}{}\x1b[0m",
+                   self.styles.label,
                    label_text,
                    " ".repeat(padding as usize));
 
@@ -225,10 +270,10 @@ impl EditPanelRenderer {
                 }
                 
                 // Write the entire line in one go
-                write!(stdout, "{}", screen_buffer)?;
+                write!(stdout, "{}{}", self.styles.normal, screen_buffer)?;
             } else {
                 // Clear the rest of the viewport
-                write!(stdout, "{:width$}", "", width = render_width as usize)?;
+                write!(stdout, "{}{:width$}", self.styles.normal, "", width = render_width as usize)?;
             }
         }
         
@@ -263,9 +308,9 @@ impl EditPanelRenderer {
                     
                     if is_highlighted {
                         // ANSI: Selection highlighting
-                        print!("\x1b[48;2;0;0;139m\x1b[38;2;255;255;255m{}\x1b[m", row[x]);
+                        print!("{}{}{}", self.styles.highlight, row[x], self.styles.normal);
                     } else {
-                        write!(stdout, "{}", row[x])?;
+                        write!(stdout, "{}{}", self.styles.normal, row[x])?;
                     }
                 }
                 
@@ -315,11 +360,11 @@ impl EditPanelRenderer {
             };
 
             // Draw scrollbar track
-            print!("\x1b[{};{}H\x1b[38;2;40;40;40m{}\x1b[0m",
-                scrollbar_y, start_x + 1, "─".repeat(width as usize));
+            print!("\x1b[{};{}H{}{}\x1b[0m",
+                scrollbar_y, start_x + 1, self.styles.track, "─".repeat(width as usize));
             // Draw scrollbar thumb
-            print!("\x1b[{};{}H\x1b[38;2;100;100;100m{}\x1b[0m",
-                scrollbar_y, start_x + thumb_pos + 1, "═".repeat(thumb_width as usize));
+            print!("\x1b[{};{}H{}{}\x1b[0m",
+                scrollbar_y, start_x + thumb_pos + 1, self.styles.thumb, "═".repeat(thumb_width as usize));
         }
 
         // Draw vertical scrollbar if content is taller than viewport
@@ -336,9 +381,9 @@ impl EditPanelRenderer {
             // Draw scrollbar track and thumb
             for y in 0..height {
                 if y >= thumb_pos && y < thumb_pos + thumb_height {
-                    print!("\x1b[{};{}H\x1b[38;2;100;100;100m║\x1b[0m", start_y + y + 1, scrollbar_x);
+                    print!("\x1b[{};{}H{}║\x1b[0m", start_y + y + 1, scrollbar_x, self.styles.thumb);
                 } else {
-                    print!("\x1b[{};{}H\x1b[38;2;40;40;40m│\x1b[0m", start_y + y + 1, scrollbar_x);
+                    print!("\x1b[{};{}H{}│\x1b[0m", start_y + y + 1, scrollbar_x, self.styles.track);
                 }
             }
         }
@@ -410,13 +455,13 @@ impl EditPanelRenderer {
                     
                     if is_cursor {
                         // ANSI: Cursor highlighting (light color)
-                        print!("\x1b[48;2;80;80;200m{}\x1b[m", ch);
+                        print!("{}{}", self.styles.cursor, ch);
                     } else if is_selected {
                         // ANSI: Selection highlighting (same blue as block selection)
-                        print!("\x1b[48;2;80;80;200m\x1b[38;2;255;255;255m{}\x1b[m", ch);
+                        print!("{}{}", self.styles.selection, ch);
                     } else {
                         // Normal character - explicitly reset to ensure no background
-                        print!("\x1b[0m{}", ch);
+                        print!("{}{}", self.styles.normal, ch);
                     }
                 }
                 
@@ -429,9 +474,9 @@ impl EditPanelRenderer {
                         let virtual_x = end_col + offset;
                         if cursor.1 == buffer_y && cursor.0 == virtual_x {
                             // Render cursor in virtual space
-                            print!("\x1b[48;2;80;80;200m \x1b[m");
+                            print!("{} ", self.styles.cursor);
                         } else {
-                            print!("\x1b[0m ");
+                            print!("{} ", self.styles.normal);
                         }
                     }
                 }
@@ -440,9 +485,9 @@ impl EditPanelRenderer {
                 for x in 0..render_width as usize {
                     if cursor.1 == buffer_y && cursor.0 == x {
                         // Render cursor on empty line
-                        print!("\x1b[48;2;80;80;200m \x1b[m");
+                        print!("{} ", self.styles.cursor);
                     } else {
-                        write!(stdout, " ")?;
+                        write!(stdout, "{} ", self.styles.normal)?;
                     }
                 }
             }
@@ -464,6 +509,8 @@ impl EditPanelRenderer {
         block_selection: Option<&BlockSelection>,
         selection_start: Option<(usize, usize)>,
         selection_end: Option<(usize, usize)>,
+        marks: &[Mark],
+        underlines: &[Underline],
     ) -> io::Result<()> {
         let mut stdout = io::stdout();
 
@@ -538,20 +585,33 @@ impl EditPanelRenderer {
                         false
                     };
 
+                    let mark = marks.iter().find(|((start_row, start_col), (end_row, end_col), _)| {
+                        (buffer_y > *start_row || (buffer_y == *start_row && x >= *start_col)) &&
+                        (buffer_y < *end_row || (buffer_y == *end_row && x < *end_col))
+                    });
+
                     let ch = row.get(x).copied().unwrap_or(' ');
+                    let underlined = underlines.iter().any(|((start_row, start_col), (end_row, end_col))| {
+                        (buffer_y > *start_row || (buffer_y == *start_row && x >= *start_col)) &&
+                        (buffer_y < *end_row || (buffer_y == *end_row && x < *end_col))
+                    });
+                    let underline = if underlined { self.styles.underline.as_str() } else { "" };
 
                     if is_cursor {
                         // ANSI: Cursor highlighting (light color)
-                        print!("\x1b[48;2;80;80;200m{}\x1b[m", ch);
+                        print!("{}{}", self.styles.cursor, ch);
                     } else if is_in_block {
                         // ANSI: Block selection highlighting
-                        print!("\x1b[48;2;80;80;200m\x1b[38;2;255;255;255m{}\x1b[m", ch);
+                        print!("{}{}", self.styles.selection, ch);
                     } else if is_selected {
                         // ANSI: Regular selection highlighting
-                        print!("\x1b[48;2;80;80;200m\x1b[38;2;255;255;255m{}\x1b[m", ch);
+                        print!("{}{}", self.styles.selection, ch);
+                    } else if let Some((_, _, color)) = mark {
+                        // ANSI: Read-time highlight under the text
+                        print!("{}{}{}{}", self.styles.mark_fg, color.ansi_bg(), underline, ch);
                     } else {
                         // Normal character - explicitly reset to ensure no background
-                        print!("\x1b[0m{}", ch);
+                        print!("{}{}{}", self.styles.normal, underline, ch);
                     }
                 }
 
@@ -575,12 +635,12 @@ impl EditPanelRenderer {
 
                         if is_cursor {
                             // Render cursor in virtual space
-                            print!("\x1b[48;2;80;80;200m \x1b[m");
+                            print!("{} ", self.styles.cursor);
                         } else if is_in_block {
                             // Render block selection in virtual space
-                            print!("\x1b[48;2;80;80;200m\x1b[38;2;255;255;255m \x1b[m");
+                            print!("{} ", self.styles.selection);
                         } else {
-                            print!("\x1b[0m ");
+                            print!("{} ", self.styles.normal);
                         }
                     }
                 }
@@ -601,12 +661,12 @@ impl EditPanelRenderer {
 
                     if is_cursor {
                         // Render cursor on virtual line
-                        print!("\x1b[48;2;80;80;200m \x1b[m");
+                        print!("{} ", self.styles.cursor);
                     } else if is_in_block {
                         // Render block selection on virtual line
-                        print!("\x1b[48;2;80;80;200m\x1b[38;2;255;255;255m \x1b[m");
+                        print!("{} ", self.styles.selection);
                     } else {
-                        write!(stdout, " ")?;
+                        write!(stdout, "{} ", self.styles.normal)?;
                     }
                 }
             }
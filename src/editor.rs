@@ -8,8 +8,8 @@ use helix_core::{
     movement,
 };
 use crossterm::event::{KeyCode, KeyModifiers};
-use std::process::Command;
 use crate::block_selection::BlockSelection;
+use crate::undo::{EditKind, Snapshot, UndoHistory};
 
 pub struct TextEditor {
     pub rope: Rope,
@@ -21,6 +21,10 @@ pub struct TextEditor {
     pub virtual_cursor_col: Option<usize>,  // Virtual column for up/down movement (like chonker7)
     pub block_selection: Option<BlockSelection>,  // For rectangular selection
     pub potential_block_start: Option<(usize, usize)>,  // For tracking mouse drag start
+    pub drag_anchor: Option<usize>,  // Char position where a plain mouse drag started
+    pub history: UndoHistory,  // Undo/redo for the open note (reset by set_text)
+    pub read_only: bool,  // The open note's text is locked; moving, selecting and copying still work
+    pub blocked: bool,  // An edit was refused because of read_only, until the app reports it
 }
 
 impl TextEditor {
@@ -35,6 +39,10 @@ impl TextEditor {
             virtual_cursor_col: None,
             block_selection: None,
             potential_block_start: None,
+            drag_anchor: None,
+            history: UndoHistory::default(),
+            read_only: false,
+            blocked: false,
         }
     }
 
@@ -51,6 +59,10 @@ impl TextEditor {
             virtual_cursor_col: None,
             block_selection: None,
             potential_block_start: None,
+            drag_anchor: None,
+            history: UndoHistory::default(),
+            read_only: false,
+            blocked: false,
         }
     }
 
@@ -60,6 +72,7 @@ impl TextEditor {
 
     pub fn handle_key_with_acceleration(&mut self, code: KeyCode, modifiers: KeyModifiers, acceleration: usize) -> Result<bool> {
         let mut modified = false;
+        let before = self.snapshot();
 
         match (code, modifiers) {
             // Basic movement with acceleration
@@ -200,20 +213,95 @@ impl TextEditor {
                 }
             }
 
-            // Undo/Redo (simplified - would need history tracking)
+            // Undo/Redo - handled here so they never land in the history themselves
+            (KeyCode::Char('z'), mods) if mods.contains(KeyModifiers::CONTROL) && mods.contains(KeyModifiers::SHIFT) => {
+                return Ok(self.redo());
+            }
             (KeyCode::Char('z'), mods) if mods.contains(KeyModifiers::CONTROL) => {
-                // TODO: Implement undo
+                return Ok(self.undo());
             }
             (KeyCode::Char('y'), mods) if mods.contains(KeyModifiers::CONTROL) => {
-                // TODO: Implement redo
+                return Ok(self.redo());
             }
 
             _ => {}
         }
 
+        // A locked note: whatever the key changed is put back (cut still copies)
+        if modified && self.read_only {
+            self.restore(before);
+            self.blocked = true;
+            return Ok(false);
+        }
+
+        // Paste records its own step (it is also reachable without a key press)
+        let pasted = matches!(code, KeyCode::Char('v')) && modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::SUPER);
+        if modified && !pasted {
+            let kind = match code {
+                KeyCode::Char(_) if !modifiers.contains(KeyModifiers::CONTROL) => EditKind::Typing,
+                KeyCode::Backspace | KeyCode::Delete => EditKind::Deleting,
+                _ => EditKind::Other,
+            };
+            self.history.record(before, kind);
+        }
+
         Ok(modified)
     }
 
+    fn snapshot(&self) -> Snapshot {
+        Snapshot::new(&self.rope, self.selection.primary().cursor(self.rope.slice(..)))
+    }
+
+    /// Swap in text edited elsewhere as one undo step, keeping the cursor offset where possible
+    pub fn replace_text(&mut self, text: &str) {
+        let before = self.snapshot();
+        let cursor = before.cursor;
+        self.history.record(before, EditKind::Other);
+        self.restore(Snapshot::new(&Rope::from_str(text), cursor));
+    }
+
+    /// Put a snapshot's text back and move the cursor where it was
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.rope = snapshot.text;
+        let pos = snapshot.cursor.min(self.rope.len_chars());
+        self.selection = Selection::point(pos);
+        self.selection_anchor = None;
+        self.virtual_cursor_col = None;
+        self.block_selection = None;
+        self.update_cursor_position();
+    }
+
+    /// Returns true if there was something to undo (the text changed)
+    pub fn undo(&mut self) -> bool {
+        if self.read_only {
+            self.blocked = true;
+            return false;
+        }
+        let current = self.snapshot();
+        match self.history.undo(current) {
+            Some(previous) => {
+                self.restore(previous);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        if self.read_only {
+            self.blocked = true;
+            return false;
+        }
+        let current = self.snapshot();
+        match self.history.redo(current) {
+            Some(next) => {
+                self.restore(next);
+                true
+            }
+            None => false,
+        }
+    }
+
     fn move_cursor_left(&mut self) {
         // Check if we have a virtual cursor position
         if let Some(virtual_col) = self.virtual_cursor_col {
@@ -286,8 +374,15 @@ impl TextEditor {
             pos - line_start
         };
 
+        // Inside the text step over whole graphemes; past the end, one virtual column at a time
+        let new_col = if current_col < effective_len {
+            let next = next_grapheme_boundary(text, line_start + current_col).min(line_start + effective_len);
+            next - line_start
+        } else {
+            current_col + 1
+        };
+
         // Always allow moving right, even into virtual space
-        let new_col = current_col + 1;
         self.virtual_cursor_col = Some(new_col);
 
         // Update cursor position for rendering
@@ -472,14 +567,10 @@ impl TextEditor {
                 let spaces_needed = virtual_col - effective_len;
                 let insert_pos = line_start + effective_len;
 
-                // Insert spaces to reach the virtual cursor position
-                let mut new_text = self.rope.to_string();
-                for _ in 0..spaces_needed {
-                    new_text.insert(insert_pos, ' ');
-                }
-                // Then insert the actual character
-                new_text.insert(insert_pos + spaces_needed, ch);
-                self.rope = Rope::from_str(&new_text);
+                // Insert spaces to reach the virtual cursor position, then the character
+                // (rope positions are char indices, so multi-byte text stays intact)
+                self.rope.insert(insert_pos, &" ".repeat(spaces_needed));
+                self.rope.insert_char(insert_pos + spaces_needed, ch);
 
                 // Update selection to be after the inserted character
                 let new_pos = insert_pos + spaces_needed + 1;
@@ -496,18 +587,15 @@ impl TextEditor {
         let text = self.rope.slice(..);
         let range = self.selection.primary();
         let pos = range.cursor(text);
+        self.rope.insert_char(pos, ch);
 
-        // Simple insertion without Transaction API
-        let mut new_text = self.rope.to_string();
-        new_text.insert(pos, ch);
-        self.rope = Rope::from_str(&new_text);
-
-        // Move cursor forward
-        let new_pos = pos + 1;
+        // Move cursor to the next grapheme boundary so it never lands inside a cluster
+        // (e.g. a base letter typed in front of a combining mark)
+        let new_pos = next_grapheme_boundary(self.rope.slice(..), pos).max(pos + 1);
         self.selection = Selection::single(new_pos, new_pos);
-        self.update_cursor_position();
         // Clear virtual column when editing
         self.virtual_cursor_col = None;
+        self.update_cursor_position();
     }
 
     fn insert_newline(&mut self) {
@@ -519,11 +607,9 @@ impl TextEditor {
         let range = self.selection.primary();
         let pos = range.cursor(text);
         if pos > 0 {
+            // Whole grapheme clusters go at once (emoji sequences, base + combining marks)
             let start = prev_grapheme_boundary(text, pos);
-            // Simple deletion without Transaction API
-            let mut new_text = self.rope.to_string();
-            new_text.drain(start..pos);
-            self.rope = Rope::from_str(&new_text);
+            self.rope.remove(start..pos);
             self.selection = Selection::single(start, start);
             self.update_cursor_position();
             // Clear virtual column when editing
@@ -539,10 +625,7 @@ impl TextEditor {
         let pos = range.cursor(text);
         if pos < text.len_chars() {
             let end = next_grapheme_boundary(text, pos);
-            // Simple deletion without Transaction API
-            let mut new_text = self.rope.to_string();
-            new_text.drain(pos..end);
-            self.rope = Rope::from_str(&new_text);
+            self.rope.remove(pos..end);
             self.selection = Selection::single(pos, pos);
             self.update_cursor_position();
             // Clear virtual column when editing
@@ -621,6 +704,7 @@ impl TextEditor {
 
     pub fn set_text(&mut self, text: &str) {
         self.rope = Rope::from_str(text);
+        self.history = UndoHistory::default();
         self.selection = Selection::single(0, 0);
         self.cursor_pos = Position::new(0, 0);
         self.scroll_x = 0;
@@ -675,7 +759,7 @@ impl TextEditor {
         // Allow cursor to be placed on any row, even past the end of the document
         let target_row = row;
 
-        if target_row >= line_count {
+        if target_row >= line_count && !self.read_only {
             // If clicking beyond the last line, extend the document with empty lines
             let lines_to_add = (target_row + 1).saturating_sub(line_count);
             for _ in 0..lines_to_add {
@@ -791,6 +875,110 @@ impl TextEditor {
         self.selection_anchor = Some(0);
     }
 
+    /// Extend a plain (linear) mouse selection from the drag anchor to a grid position
+    pub fn drag_selection_to(&mut self, row: usize, col: usize) {
+        let Some(anchor) = self.drag_anchor else {
+            return;
+        };
+        let row = row.min(self.rope.len_lines().saturating_sub(1));
+        let head = self.coords_to_pos(row, col);
+
+        self.selection = Selection::single(anchor, head);
+        self.selection_anchor = Some(anchor);  // Shift+arrows keep extending the same selection
+        let (row, col) = self.pos_to_coords(head);
+        self.cursor_pos = Position::new(row, col);
+        self.virtual_cursor_col = None;
+    }
+
+    /// Put the cursor at a char position (clamped to the text), clearing any selection
+    pub fn set_cursor_char(&mut self, pos: usize) {
+        let pos = pos.min(self.rope.len_chars());
+        self.selection = Selection::point(pos);
+        self.selection_anchor = None;
+        self.block_selection = None;
+        self.virtual_cursor_col = None;
+        self.update_cursor_position();
+    }
+
+    /// Double-click: select the run of word characters (or of other non-space characters) at pos
+    pub fn select_word_at(&mut self, pos: usize) {
+        let text = self.rope.slice(..);
+        let len = text.len_chars();
+        if pos >= len {
+            return;
+        }
+
+        let class = |c: char| if c.is_alphanumeric() || c == '_' { 0 } else if c.is_whitespace() { 1 } else { 2 };
+        let target = class(text.char(pos));
+        if text.char(pos) == '\n' {
+            return;
+        }
+
+        let mut start = pos;
+        while start > 0 && text.char(start - 1) != '\n' && class(text.char(start - 1)) == target {
+            start -= 1;
+        }
+        let mut end = pos + 1;
+        while end < len && text.char(end) != '\n' && class(text.char(end)) == target {
+            end += 1;
+        }
+
+        self.select_span(start, end);
+    }
+
+    /// Triple-click: select a whole line including its line break
+    pub fn select_line_at(&mut self, row: usize) {
+        if row >= self.rope.len_lines() {
+            return;
+        }
+        let start = self.rope.line_to_char(row);
+        let end = start + self.rope.line(row).len_chars();
+        self.select_span(start, end);
+    }
+
+    /// Select chars start..end, cursor at the end
+    pub fn select_span(&mut self, start: usize, end: usize) {
+        let len = self.rope.len_chars();
+        let (start, end) = (start.min(len), end.min(len));
+        self.selection = Selection::single(start, end);
+        self.selection_anchor = Some(start);
+        self.drag_anchor = None;
+        self.block_selection = None;
+        let (row, col) = self.pos_to_coords(end);
+        self.cursor_pos = Position::new(row, col);
+        self.virtual_cursor_col = None;
+    }
+
+    /// Linear selection as a char range (from, to), if any text is selected
+    pub fn selection_range(&self) -> Option<(usize, usize)> {
+        let range = self.selection.primary();
+        (range.len() > 0).then(|| (range.from(), range.to()))
+    }
+
+    /// The char at grid coordinates, if there is one (None past the end of a line)
+    pub fn char_at(&self, row: usize, col: usize) -> Option<usize> {
+        if row >= self.rope.len_lines() {
+            return None;
+        }
+        let line = self.rope.line(row);
+        let len = line.chars().take_while(|&c| c != '\n').count();
+        (col < len).then(|| self.rope.line_to_char(row) + col)
+    }
+
+    /// Grid coordinates of a char position, clamped to the text
+    pub fn char_coords(&self, pos: usize) -> (usize, usize) {
+        self.pos_to_coords(pos.min(self.rope.len_chars()))
+    }
+
+    /// Linear selection as ((row, col), (row, col)) grid coordinates, if any text is selected
+    pub fn selection_coords(&self) -> Option<((usize, usize), (usize, usize))> {
+        let range = self.selection.primary();
+        if range.len() == 0 {
+            return None;
+        }
+        Some((self.pos_to_coords(range.from()), self.pos_to_coords(range.to())))
+    }
+
     // Block selection extensions
     fn extend_block_selection_left(&mut self) {
         let pos = self.selection.primary().head;
@@ -887,7 +1075,7 @@ impl TextEditor {
     }
 
     // Clipboard operations
-    fn copy_selection(&self) -> Result<()> {
+    pub fn copy_selection(&self) -> Result<()> {
         // Handle block selection first
         if let Some(ref block_sel) = self.block_selection {
             let mut lines = Vec::new();
@@ -1010,14 +1198,15 @@ impl TextEditor {
                 let start_char = crate::block_selection::visual_col_to_char_idx(line_slice, start_col);
                 let end_char = crate::block_selection::visual_col_to_char_idx(line_slice, end_col);
 
-                // Clamp to line length
-                let start_char = start_char.min(line.len());
-                let end_char = end_char.min(line.len());
+                // Clamp to line length (in chars - the line may hold multi-byte text)
+                let line_chars = line.chars().count();
+                let start_char = start_char.min(line_chars);
+                let end_char = end_char.min(line_chars);
 
                 if start_char < end_char {
-                    let mut new_line = String::new();
-                    new_line.push_str(&line[..start_char]);
-                    new_line.push_str(&line[end_char..]);
+                    let new_line: String = line.chars().take(start_char)
+                        .chain(line.chars().skip(end_char))
+                        .collect();
                     lines[line_idx] = new_line;
                 }
             }
@@ -1050,9 +1239,7 @@ impl TextEditor {
             self.copy_to_clipboard(&selected_text)?;
 
             // Delete the selection
-            let mut new_text = self.rope.to_string();
-            new_text.drain(range.from()..range.to());
-            self.rope = Rope::from_str(&new_text);
+            self.rope.remove(range.from()..range.to());
 
             // Update selection
             self.selection = Selection::point(range.from());
@@ -1065,267 +1252,173 @@ impl TextEditor {
     }
 
     fn paste(&mut self) -> Result<bool> {
-        if let Ok(clipboard_text) = self.paste_from_clipboard() {
-            // Use cursor_pos which tracks the visual position
-            let line = self.cursor_pos.row;
-            let col = self.cursor_pos.col;
-
-            eprintln!("DEBUG PASTE: cursor_pos row={} col={}", line, col);
-            eprintln!("DEBUG PASTE: selection={:?}", self.selection.primary());
-
-            // Ensure we have the line
-            if line >= self.rope.len_lines() {
-                // Add lines if needed
-                let lines_to_add = line + 1 - self.rope.len_lines();
-                for _ in 0..lines_to_add {
-                    self.rope.insert_char(self.rope.len_chars(), '\n');
-                }
-            }
-
-            // Get line info
-            let line_slice = self.rope.line(line);
-            let line_len_raw = line_slice.len_chars();
-            let line_len = if line_len_raw > 0 && line_slice.char(line_len_raw - 1) == '\n' {
-                line_len_raw - 1
-            } else {
-                line_len_raw
-            };
-
-            // Pad with spaces if clicking past end of line
-            if col > line_len {
-                let spaces_needed = col - line_len;
-                let line_start = self.rope.line_to_char(line);
-                let insert_pos = line_start + line_len;
+        match self.paste_from_clipboard() {
+            Ok(clipboard_text) => self.paste_text(&clipboard_text),
+            Err(_) => Ok(false),
+        }
+    }
 
-                eprintln!("DEBUG PASTE: Padding {} spaces at char pos {}", spaces_needed, insert_pos);
+    /// Insert text at the cursor, replacing a selection or filling a block selection line by line
+    pub fn paste_text(&mut self, clipboard_text: &str) -> Result<bool> {
+        if self.read_only {
+            self.blocked = true;
+            return Ok(false);
+        }
+        let before = self.snapshot();
+        let pasted = self.insert_pasted(clipboard_text)?;
+        if pasted {
+            self.history.record(before, EditKind::Other);
+        }
+        Ok(pasted)
+    }
 
-                // Convert to string, insert spaces, convert back
-                let mut text_str = self.rope.to_string();
-                let mut byte_pos = 0;
-                let mut char_count = 0;
-                for ch in text_str.chars() {
-                    if char_count >= insert_pos {
-                        break;
-                    }
-                    byte_pos += ch.len_utf8();
-                    char_count += 1;
-                }
+    fn insert_pasted(&mut self, clipboard_text: &str) -> Result<bool> {
+        // Use cursor_pos which tracks the visual position
+        let line = self.cursor_pos.row;
+        let col = self.cursor_pos.col;
 
-                for _ in 0..spaces_needed {
-                    text_str.insert(byte_pos, ' ');
-                    byte_pos += 1;
-                }
+        tracing::trace!(row = line, col, selection = ?self.selection.primary(), "paste");
 
-                self.rope = Rope::from_str(&text_str);
+        // Ensure we have the line
+        if line >= self.rope.len_lines() {
+            // Add lines if needed
+            let lines_to_add = line + 1 - self.rope.len_lines();
+            for _ in 0..lines_to_add {
+                self.rope.insert_char(self.rope.len_chars(), '\n');
             }
+        }
 
-            // Now paste at the correct position
-            let line_start = self.rope.line_to_char(line);
-            let cursor_char_pos = line_start + col;
-
-            eprintln!("DEBUG PASTE: Inserting at char position {}", cursor_char_pos);
-
-            // Handle block selection paste
-            if let Some(ref block_sel) = self.block_selection {
-                // For block selection, paste at each line in the block
-                let mut new_text = self.rope.to_string();
-
-                // Get the visual columns for the block
-                let min_col = block_sel.anchor_visual_col.min(block_sel.cursor_visual_col);
-                let min_row = block_sel.anchor.line.min(block_sel.cursor.line);
-                let max_row = block_sel.anchor.line.max(block_sel.cursor.line);
-
-                // Split clipboard text into lines
-                let paste_lines: Vec<&str> = clipboard_text.lines().collect();
-
-                // Apply paste to each line in the selection
-                for (i, row) in (min_row..=max_row).enumerate() {
-                    if row < self.rope.len_lines() {
-                        let line_start = self.rope.line_to_byte(row);
-                        let line = self.rope.line(row);
-                        let line_text = line.as_str().unwrap_or("");
-
-                        // Calculate the byte position for this visual column
-                        let mut visual_col = 0;
-                        let mut byte_col = 0;
-                        for ch in line_text.chars() {
-                            if visual_col >= min_col {
-                                break;
-                            }
-                            byte_col += ch.len_utf8();
-                            visual_col += if ch == '\t' { 4 } else { 1 };
-                        }
+        // Get line info
+        let line_slice = self.rope.line(line);
+        let line_len_raw = line_slice.len_chars();
+        let line_len = if line_len_raw > 0 && line_slice.char(line_len_raw - 1) == '\n' {
+            line_len_raw - 1
+        } else {
+            line_len_raw
+        };
 
-                        // Get the paste text for this line
-                        let paste_text = if i < paste_lines.len() {
-                            paste_lines[i]
-                        } else if paste_lines.len() == 1 {
-                            paste_lines[0]  // Repeat single line
-                        } else {
-                            ""  // No more lines to paste
-                        };
+        // Pad with spaces if clicking past end of line
+        if col > line_len {
+            let spaces_needed = col - line_len;
+            let line_start = self.rope.line_to_char(line);
+            let insert_pos = line_start + line_len;
 
-                        // Insert at the calculated position
-                        let insert_pos = line_start + byte_col;
-                        if insert_pos <= new_text.len() {
-                            new_text.insert_str(insert_pos, paste_text);
-                        }
-                    }
-                }
+            tracing::trace!(spaces_needed, insert_pos, "paste past end of line, padding");
 
-                self.rope = Rope::from_str(&new_text);
-                self.block_selection = None;
-            } else if self.selection_anchor.is_some() {
-                // Handle regular selection paste - replace the selection
-                let range = self.selection.primary();
-                let mut new_text = self.rope.to_string();
-                new_text.drain(range.from()..range.to());
-                new_text.insert_str(range.from(), &clipboard_text);
-
-                self.rope = Rope::from_str(&new_text);
-                let new_pos = range.from() + clipboard_text.len();
-                self.selection = Selection::point(new_pos);
-                self.cursor_pos = self.byte_index_to_position(new_pos);
-                self.selection_anchor = None;
-            } else {
-                // No selection - insert at cursor position
-                // Convert char position to byte position for string manipulation
-                let rope_str = self.rope.to_string();
-                let mut char_count = 0;
-                let mut byte_pos = 0;
-
-                for ch in rope_str.chars() {
-                    if char_count >= cursor_char_pos {
-                        break;
-                    }
-                    byte_pos += ch.len_utf8();
-                    char_count += 1;
+            // Convert to string, insert spaces, convert back
+            let mut text_str = self.rope.to_string();
+            let mut byte_pos = 0;
+            let mut char_count = 0;
+            for ch in text_str.chars() {
+                if char_count >= insert_pos {
+                    break;
                 }
-
-                let mut new_text = rope_str;
-                new_text.insert_str(byte_pos, &clipboard_text);
-
-                self.rope = Rope::from_str(&new_text);
-
-                // Calculate new cursor position
-                let new_char_pos = cursor_char_pos + clipboard_text.chars().count();
-                self.selection = Selection::point(new_char_pos);
-
-                // Update cursor_pos to match
-                let text = self.rope.slice(..);
-                let new_line = text.char_to_line(new_char_pos);
-                let new_line_start = text.line_to_char(new_line);
-                let new_col = new_char_pos - new_line_start;
-                self.cursor_pos = Position::new(new_line, new_col);
-
-                eprintln!("DEBUG PASTE: New cursor position row={} col={}", new_line, new_col);
+                byte_pos += ch.len_utf8();
+                char_count += 1;
             }
 
-            return Ok(true);
-        }
-
-        Ok(false)
-    }
-
-    fn copy_to_clipboard(&self, text: &str) -> Result<()> {
-        #[cfg(target_os = "macos")]
-        {
-            let mut child = Command::new("pbcopy")
-                .stdin(std::process::Stdio::piped())
-                .spawn()?;
-
-            if let Some(mut stdin) = child.stdin.take() {
-                use std::io::Write;
-                stdin.write_all(text.as_bytes())?;
+            for _ in 0..spaces_needed {
+                text_str.insert(byte_pos, ' ');
+                byte_pos += 1;
             }
 
-            child.wait()?;
+            self.rope = Rope::from_str(&text_str);
         }
 
-        #[cfg(not(target_os = "macos"))]
-        {
-            // For Linux, use xclip or xsel
-            let mut child = Command::new("xclip")
-                .arg("-selection")
-                .arg("clipboard")
-                .stdin(std::process::Stdio::piped())
-                .spawn()?;
-
-            if let Some(mut stdin) = child.stdin.take() {
-                use std::io::Write;
-                stdin.write_all(text.as_bytes())?;
-            }
+        // Now paste at the correct position
+        let line_start = self.rope.line_to_char(line);
+        let cursor_char_pos = line_start + col;
 
-            child.wait()?;
-        }
+        tracing::trace!(cursor_char_pos, "paste insert position");
 
-        Ok(())
-    }
+        // Handle block selection paste
+        if let Some(ref block_sel) = self.block_selection {
+            // For block selection, paste at each line in the block
+            let mut new_text = self.rope.to_string();
 
-    // Helper method to convert a Position to a byte index in the rope
-    fn position_to_byte_index(&self, pos: Position) -> usize {
-        if pos.row >= self.rope.len_lines() {
-            return self.rope.len_bytes();
-        }
+            // Get the visual columns for the block
+            let min_col = block_sel.anchor_visual_col.min(block_sel.cursor_visual_col);
+            let min_row = block_sel.anchor.line.min(block_sel.cursor.line);
+            let max_row = block_sel.anchor.line.max(block_sel.cursor.line);
+
+            // Split clipboard text into lines
+            let paste_lines: Vec<&str> = clipboard_text.lines().collect();
+
+            // Apply paste to each line in the selection
+            for (i, row) in (min_row..=max_row).enumerate() {
+                if row < self.rope.len_lines() {
+                    let line_start = self.rope.line_to_byte(row);
+                    let line = self.rope.line(row);
+                    let line_text = line.as_str().unwrap_or("");
+
+                    // Calculate the byte position for this visual column
+                    let mut visual_col = 0;
+                    let mut byte_col = 0;
+                    for ch in line_text.chars() {
+                        if visual_col >= min_col {
+                            break;
+                        }
+                        byte_col += ch.len_utf8();
+                        visual_col += if ch == '\t' { 4 } else { 1 };
+                    }
 
-        let line_start = self.rope.line_to_byte(pos.row);
-        let line = self.rope.line(pos.row);
-        let line_str = line.as_str().unwrap_or("");
+                    // Get the paste text for this line
+                    let paste_text = if i < paste_lines.len() {
+                        paste_lines[i]
+                    } else if paste_lines.len() == 1 {
+                        paste_lines[0]  // Repeat single line
+                    } else {
+                        ""  // No more lines to paste
+                    };
 
-        // Convert visual column to byte offset
-        let mut visual_col = 0;
-        let mut byte_offset = 0;
-        for ch in line_str.chars() {
-            if visual_col >= pos.col {
-                break;
+                    // Insert at the calculated position
+                    let insert_pos = line_start + byte_col;
+                    if insert_pos <= new_text.len() {
+                        new_text.insert_str(insert_pos, paste_text);
+                    }
+                }
             }
-            byte_offset += ch.len_utf8();
-            visual_col += if ch == '\t' { 4 } else { 1 };
-        }
 
-        line_start + byte_offset.min(line.len_bytes())
-    }
+            self.rope = Rope::from_str(&new_text);
+            self.block_selection = None;
+        } else if self.selection_anchor.is_some() {
+            // Handle regular selection paste - replace the selection
+            let range = self.selection.primary();
+            self.rope.remove(range.from()..range.to());
+            self.rope.insert(range.from(), clipboard_text);
 
-    // Helper method to convert a byte index to a Position
-    fn byte_index_to_position(&self, byte_idx: usize) -> Position {
-        let byte_idx = byte_idx.min(self.rope.len_bytes());
-        let row = self.rope.byte_to_line(byte_idx);
-        let line_start = self.rope.line_to_byte(row);
-        let col_byte = byte_idx - line_start;
+            let new_pos = range.from() + clipboard_text.chars().count();
+            self.selection = Selection::point(new_pos);
+            let (row, col) = self.pos_to_coords(new_pos);
+            self.cursor_pos = Position::new(row, col);
+            self.selection_anchor = None;
+        } else {
+            // No selection - insert at cursor position
+            self.rope.insert(cursor_char_pos, clipboard_text);
 
-        // Convert byte offset to visual column
-        let line = self.rope.line(row);
-        let line_str = line.as_str().unwrap_or("");
-        let mut visual_col = 0;
-        let mut byte_count = 0;
+            // Calculate new cursor position
+            let new_char_pos = cursor_char_pos + clipboard_text.chars().count();
+            self.selection = Selection::point(new_char_pos);
 
-        for ch in line_str.chars() {
-            if byte_count >= col_byte {
-                break;
-            }
-            byte_count += ch.len_utf8();
-            visual_col += if ch == '\t' { 4 } else { 1 };
+            // Update cursor_pos to match
+            let text = self.rope.slice(..);
+            let new_line = text.char_to_line(new_char_pos);
+            let new_line_start = text.line_to_char(new_line);
+            let new_col = new_char_pos - new_line_start;
+            self.cursor_pos = Position::new(new_line, new_col);
+
+            tracing::trace!(row = new_line, col = new_col, "paste cursor moved");
         }
 
-        Position::new(row, visual_col)
+        Ok(true)
     }
 
-    fn paste_from_clipboard(&self) -> Result<String> {
-        #[cfg(target_os = "macos")]
-        {
-            let output = Command::new("pbpaste").output()?;
-            Ok(String::from_utf8_lossy(&output.stdout).to_string())
-        }
+    fn copy_to_clipboard(&self, text: &str) -> Result<()> {
+        crate::clipboard::copy(text)
+    }
 
-        #[cfg(not(target_os = "macos"))]
-        {
-            let output = Command::new("xclip")
-                .arg("-selection")
-                .arg("clipboard")
-                .arg("-o")
-                .output()?;
-            Ok(String::from_utf8_lossy(&output.stdout).to_string())
-        }
+    fn paste_from_clipboard(&self) -> Result<String> {
+        crate::clipboard::paste()
     }
 
     pub fn has_selection(&self) -> bool {
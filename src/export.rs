@@ -0,0 +1,344 @@
+// Note exporters - styling is driven by front matter classes, not exporter code
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::front_matter::{parse_front_matter, FrontMatter};
+use crate::note_store::Note;
+use crate::pdf_import;
+use crate::transcript;
+use crate::qda_codes::{CodeApplication, CodeManager};
+use crate::query::coded_text;
+
+/// Front matter keys that select export styling.
+///
+/// ```text
+/// ---
+/// layout: article            -> <body class="layout-article">
+/// highlight-codes: true      -> coded segments wrapped in colored <mark>
+/// class: wide serif          -> extra classes on <body>
+/// ---
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StyleClasses {
+    pub layout: Option<String>,
+    pub highlight_codes: bool,
+    pub extra: Vec<String>,
+}
+
+impl StyleClasses {
+    pub fn from_front_matter(front_matter: Option<&FrontMatter>) -> Self {
+        let Some(fm) = front_matter else {
+            return StyleClasses::default();
+        };
+
+        StyleClasses {
+            layout: fm.get("layout").map(css_ident).filter(|l| !l.is_empty()),
+            highlight_codes: fm.get_bool("highlight-codes"),
+            extra: fm.get_list("class").iter().map(|c| css_ident(c)).filter(|c| !c.is_empty()).collect(),
+        }
+    }
+
+    /// Classes for the <body> element
+    pub fn body_classes(&self) -> Vec<String> {
+        let mut classes = vec![format!("layout-{}", self.layout.as_deref().unwrap_or("default"))];
+        if self.highlight_codes {
+            classes.push("highlight-codes".to_string());
+        }
+        classes.extend(self.extra.iter().cloned());
+        classes
+    }
+}
+
+// Layout classes shipped with the exporter; anything else can be styled by a user stylesheet
+const BASE_CSS: &str = "\
+body { font-family: -apple-system, 'Segoe UI', sans-serif; line-height: 1.5; margin: 2em auto; max-width: 48em; padding: 0 1em; color: #222; }
+body.layout-article { font-family: Georgia, 'Times New Roman', serif; max-width: 38em; font-size: 1.1em; line-height: 1.7; }
+body.layout-article h1 { text-align: center; margin-bottom: 0.2em; }
+body.layout-article .meta { text-align: center; }
+body.layout-letter { font-family: Georgia, serif; max-width: 34em; }
+body.layout-letter .meta { display: none; }
+body.layout-wide { max-width: none; }
+body.layout-plain { font-family: ui-monospace, monospace; white-space: pre-wrap; }
+.meta { color: #777; font-size: 0.85em; margin-bottom: 2em; }
+.tags span { background: #eee; border-radius: 3px; padding: 0 0.3em; margin-right: 0.3em; }
+mark.code { border-radius: 2px; padding: 0 1px; }
+mark.suggested { outline: 1px dashed currentColor; background-image: repeating-linear-gradient(45deg, rgba(255,255,255,0.4) 0 3px, transparent 3px 6px); }
+body:not(.highlight-codes) mark.code { background: none; }
+";
+
+/// Filesystem-safe file name stem for a note (falls back to the id for symbol-only titles)
+pub fn file_stem(note: &Note) -> String {
+    let stem: String = note.title.chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if stem.trim_matches('_').is_empty() { note.id.clone() } else { stem }
+}
+
+/// Default export location: <notes_dir>/exports/<title>.<ext>
+pub fn export_path(notes_dir: &Path, note: &Note, extension: &str) -> PathBuf {
+    notes_dir.join("exports").join(format!("{}.{}", file_stem(note), extension))
+}
+
+/// Render a note as a standalone HTML document
+pub fn note_to_html(note: &Note, codes: &CodeManager, applications: &[CodeApplication]) -> String {
+    let front_matter = parse_front_matter(&note.content);
+    let styles = StyleClasses::from_front_matter(front_matter.as_ref());
+    let body_start = front_matter.as_ref().map(|fm| fm.body_start).unwrap_or(0);
+
+    // Code applications use char offsets into the full content (front matter included)
+    let char_count = note.content.chars().count();
+    let mut char_codes: Vec<Option<(&str, bool)>> = vec![None; char_count];
+    if styles.highlight_codes {
+        for app in applications {
+            let end = app.end_offset.min(char_count);
+            for slot in char_codes.iter_mut().take(end).skip(app.start_offset) {
+                *slot = Some((app.code_id.as_str(), app.suggested));
+            }
+        }
+    }
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n", escape_html(&note.title)));
+    html.push_str("<style>\n");
+    html.push_str(BASE_CSS);
+    if styles.highlight_codes {
+        for app in applications {
+            if let Some(code) = codes.get_code(&app.code_id) {
+                let color = codes.color_of(&code.id);
+                let rule = format!(
+                    "mark.code-{} {{ background: rgba({}, {}, {}, 0.35); }}\n",
+                    css_ident(&code.id), color.r, color.g, color.b
+                );
+                if !html.contains(&rule) {
+                    html.push_str(&rule);
+                }
+            }
+        }
+    }
+    html.push_str("</style>\n</head>\n");
+    html.push_str(&format!("<body class=\"{}\">\n", styles.body_classes().join(" ")));
+
+    html.push_str(&format!("<h1>{}</h1>\n", escape_html(&note.title)));
+    html.push_str(&format!(
+        "<div class=\"meta\">{}",
+        note.updated_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")
+    ));
+    if !note.tags.is_empty() {
+        html.push_str(" <span class=\"tags\">");
+        for tag in &note.tags {
+            html.push_str(&format!("<span>#{}</span>", escape_html(tag)));
+        }
+        html.push_str("</span>");
+    }
+    html.push_str("</div>\n");
+
+    // Body: blank lines separate paragraphs, "# " lines become headings
+    let mut char_offset = note.content[..body_start].chars().count();
+    let mut paragraph: Vec<String> = Vec::new();
+    let flush = |paragraph: &mut Vec<String>, html: &mut String| {
+        if !paragraph.is_empty() {
+            html.push_str(&format!("<p>{}</p>\n", paragraph.join("<br>\n")));
+            paragraph.clear();
+        }
+    };
+
+    for line in note.content[body_start..].split('\n') {
+        let line_chars = line.chars().count();
+        let rendered = render_line(line, &char_codes[char_offset.min(char_count)..(char_offset + line_chars).min(char_count)], codes);
+        char_offset += line_chars + 1;
+
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            flush(&mut paragraph, &mut html);
+        } else if let Some(level) = heading_level(trimmed) {
+            flush(&mut paragraph, &mut html);
+            // Drop the leading hashes (ASCII, so one char each) from the rendered markup
+            let skip = line.len() - trimmed.len() + level + 1;
+            let text = render_line(&line[skip.min(line.len())..], &[], codes);
+            html.push_str(&format!("<h{0}>{1}</h{0}>\n", level + 1, text));
+        } else {
+            paragraph.push(rendered);
+        }
+    }
+    flush(&mut paragraph, &mut html);
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Write a note's markdown (its text as stored, front matter included) and return the path
+pub fn export_note_md(notes_dir: &Path, note: &Note) -> Result<PathBuf> {
+    let path = export_path(notes_dir, note, "md");
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, &note.content)?;
+    Ok(path)
+}
+
+/// Write a note's HTML export to disk and return the path
+pub fn export_note_html(notes_dir: &Path, note: &Note, codes: &CodeManager) -> Result<PathBuf> {
+    let applications = codes.get_code_applications(&note.id)?;
+    let html = note_to_html(note, codes, &applications);
+
+    let path = export_path(notes_dir, note, "html");
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, html)?;
+    Ok(path)
+}
+
+/// One coded span in the NLP export - overlapping codes on the same span share a record
+#[derive(Debug, Serialize)]
+struct SegmentRecord<'a> {
+    text: String,
+    labels: Vec<&'a str>,    // Code names
+    document_id: &'a str,
+    document_title: &'a str,
+    start: usize,            // Char offsets into the note content
+    end: usize,
+    coder: Option<&'a str>,
+    memos: Vec<&'a str>,
+    source: &'static str,    // "human" or "suggested" (imported model output)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page: Option<usize>,     // PDF page the segment starts on, for imported PDFs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_time: Option<String>,  // Transcripts: "00:12:34" timestamp before the segment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_time: Option<String>,    // and the first one after it
+}
+
+/// Write every coded segment as JSONL (one record per span and coder) to
+/// <notes_dir>/exports/segments.jsonl; returns the path and record count
+pub fn export_segments_jsonl(notes_dir: &Path, notes: &[Note], codes: &CodeManager) -> Result<(PathBuf, usize)> {
+    let applications = codes.get_all_code_applications()?;
+
+    let path = notes_dir.join("exports").join("segments.jsonl");
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut out = std::io::BufWriter::new(std::fs::File::create(&path)?);
+    let mut count = 0;
+
+    for note in notes {
+        // Segments stored on the note carry no coder; application rows may
+        let segments = note.codes.iter()
+            .map(|s| (s.start_offset, s.end_offset, s.code_id.as_str(), s.memo.as_deref(), None, false))
+            .chain(applications.get(&note.id).into_iter().flatten()
+                .map(|a| (a.start_offset, a.end_offset, a.code_id.as_str(), a.memo.as_deref(), a.coder.as_deref(), a.suggested)));
+
+        let timestamps = transcript::markers(&note.content);
+
+        // Suggestions never share a record with human codes on the same span
+        let mut spans: BTreeMap<(usize, usize, Option<&str>, bool), SegmentRecord> = BTreeMap::new();
+        for (start, end, code_id, memo, coder, suggested) in segments {
+            if end <= start {
+                continue;
+            }
+            let label = codes.get_code(code_id).map(|c| c.name.as_str()).unwrap_or(code_id);
+            let times = transcript::time_range(&timestamps, start, end);
+            let record = spans.entry((start, end, coder, suggested)).or_insert_with(|| SegmentRecord {
+                text: coded_text(&note.content, start, end),
+                labels: Vec::new(),
+                document_id: &note.id,
+                document_title: &note.title,
+                start,
+                end,
+                coder,
+                memos: Vec::new(),
+                source: if suggested { "suggested" } else { "human" },
+                page: pdf_import::page_at(&note.content, start),
+                start_time: times.map(|(from, _)| transcript::format_time(from)),
+                end_time: times.and_then(|(_, to)| to).map(transcript::format_time),
+            });
+            if !record.labels.contains(&label) {
+                record.labels.push(label);
+            }
+            if let Some(memo) = memo.filter(|m| !m.trim().is_empty()) {
+                record.memos.push(memo);
+            }
+        }
+
+        for record in spans.values() {
+            writeln!(out, "{}", serde_json::to_string(record)?)?;
+            count += 1;
+        }
+    }
+
+    out.flush()?;
+    Ok((path, count))
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+    let hashes = line.chars().take_while(|&c| c == '#').count();
+    if (1..=5).contains(&hashes) && line[hashes..].starts_with(' ') {
+        Some(hashes)
+    } else {
+        None
+    }
+}
+
+/// Escape one line, wrapping runs of coded characters in <mark> elements
+/// (machine suggestions get an extra "suggested" class)
+fn render_line(line: &str, char_codes: &[Option<(&str, bool)>], codes: &CodeManager) -> String {
+    let mut out = String::new();
+    let mut current: Option<(&str, bool)> = None;
+
+    for (i, ch) in line.chars().enumerate() {
+        let code = char_codes.get(i).copied().flatten();
+        if code != current {
+            if current.is_some() {
+                out.push_str("</mark>");
+            }
+            if let Some((code_id, suggested)) = code {
+                let title = codes.get_code(code_id).map(|c| c.name.as_str()).unwrap_or(code_id);
+                out.push_str(&format!(
+                    "<mark class=\"code code-{}{}\" title=\"{}{}\">",
+                    css_ident(code_id),
+                    if suggested { " suggested" } else { "" },
+                    escape_html(title),
+                    if suggested { " (suggested)" } else { "" }
+                ));
+            }
+            current = code;
+        }
+        push_escaped(&mut out, ch);
+    }
+    if current.is_some() {
+        out.push_str("</mark>");
+    }
+    out
+}
+
+fn push_escaped(out: &mut String, ch: char) {
+    match ch {
+        '&' => out.push_str("&amp;"),
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        '"' => out.push_str("&quot;"),
+        _ => out.push(ch),
+    }
+}
+
+pub fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        push_escaped(&mut out, ch);
+    }
+    out
+}
+
+/// Restrict user-supplied class names to characters that are safe inside a class attribute
+fn css_ident(name: &str) -> String {
+    name.trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect()
+}
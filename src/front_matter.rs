@@ -0,0 +1,106 @@
+// YAML-style front matter block at the top of a note (--- key: value ---)
+
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Default)]
+pub struct FrontMatter {
+    pub fields: BTreeMap<String, String>,
+    pub body_start: usize,  // Byte offset of the first character after the closing ---
+}
+
+impl FrontMatter {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields.get(key).map(|v| v.as_str())
+    }
+
+    /// true/yes/on (any case) count as true, anything else as false
+    pub fn get_bool(&self, key: &str) -> bool {
+        matches!(
+            self.get(key).map(|v| v.to_ascii_lowercase()).as_deref(),
+            Some("true" | "yes" | "on" | "1")
+        )
+    }
+
+    /// Comma or space separated list value ("a, b c" -> ["a", "b", "c"])
+    pub fn get_list(&self, key: &str) -> Vec<String> {
+        self.get(key)
+            .map(|v| v.trim_matches(|c| c == '[' || c == ']')
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+                .collect())
+            .unwrap_or_default()
+    }
+}
+
+/// A field being typed in the editor header as "key: value" (Alt+M); `original` is the key
+/// it started as, None for a new field
+#[derive(Debug, Clone)]
+pub struct FieldEdit {
+    pub original: Option<String>,
+    pub text: String,
+}
+
+/// Parse a front matter block if the note starts with one.
+/// Only flat `key: value` pairs are supported; other lines are ignored.
+pub fn parse_front_matter(content: &str) -> Option<FrontMatter> {
+    let mut lines = content.split_inclusive('\n');
+    let first = lines.next()?;
+    if first.trim_end() != "---" {
+        return None;
+    }
+
+    let mut fields = BTreeMap::new();
+    let mut offset = first.len();
+    for line in lines {
+        offset += line.len();
+        let trimmed = line.trim_end();
+        if trimmed == "---" {
+            return Some(FrontMatter { fields, body_start: offset });
+        }
+
+        if let Some((key, value)) = trimmed.split_once(':') {
+            let key = key.trim();
+            if !key.is_empty() && !key.starts_with('#') {
+                let value = value.trim().trim_matches('"').to_string();
+                fields.insert(key.to_lowercase(), value);
+            }
+        }
+    }
+
+    // No closing delimiter - treat the whole note as body
+    None
+}
+
+/// `content` with front matter field `key` set to `value` - replaced in place, or added at
+/// the end of the block (starting one if the note has none) - or with `None` removed.
+/// Every other line is kept as written; a block left empty goes.
+pub fn set_field(content: &str, key: &str, value: Option<&str>) -> String {
+    let key = key.trim();
+    let line = value.map(|v| format!("{}: {}\n", key, v.trim()));
+    let Some(fm) = parse_front_matter(content) else {
+        return match line {
+            Some(line) => format!("---\n{}---\n{}", line, content),
+            None => content.to_string(),
+        };
+    };
+
+    let (block, body) = content.split_at(fm.body_start);
+    let mut lines: Vec<String> = block.split_inclusive('\n').map(str::to_string).collect();
+    let close = lines.len() - 1;
+    let existing = lines[1..close].iter()
+        .position(|l| l.split_once(':').is_some_and(|(k, _)| k.trim().eq_ignore_ascii_case(key)))
+        .map(|i| i + 1);
+    match (existing, line) {
+        (Some(i), Some(line)) => lines[i] = line,
+        (Some(i), None) => {
+            lines.remove(i);
+        }
+        (None, Some(line)) => lines.insert(close, line),
+        (None, None) => {}
+    }
+    if lines.iter().skip(1).take(lines.len().saturating_sub(2)).all(|l| l.trim().is_empty()) {
+        return body.to_string();
+    }
+    format!("{}{}", lines.concat(), body)
+}
@@ -0,0 +1,161 @@
+// Read-time highlights - personal color marks on a first read, before any codebook exists
+//
+// Kept in their own table (in notes.db) so they never show up as coding
+// decisions in exports, reports or the audit log; converting one into a code
+// application is an explicit step that removes the highlight.
+
+use anyhow::Result;
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use std::path::Path;
+
+use crate::db;
+use crate::theme::Rgb;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HighlightColor {
+    Yellow,
+    Green,
+    Blue,
+    Pink,
+}
+
+impl HighlightColor {
+    pub const ALL: [HighlightColor; 4] = [HighlightColor::Yellow, HighlightColor::Green, HighlightColor::Blue, HighlightColor::Pink];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HighlightColor::Yellow => "yellow",
+            HighlightColor::Green => "green",
+            HighlightColor::Blue => "blue",
+            HighlightColor::Pink => "pink",
+        }
+    }
+
+    fn from_name(name: &str) -> Self {
+        Self::ALL.into_iter().find(|c| c.name() == name).unwrap_or(HighlightColor::Yellow)
+    }
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|c| *c == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Marker background - muted enough to keep any theme's editor text readable
+    pub fn rgb(self) -> Rgb {
+        match self {
+            HighlightColor::Yellow => Rgb::new(120, 110, 30),
+            HighlightColor::Green => Rgb::new(40, 105, 60),
+            HighlightColor::Blue => Rgb::new(40, 75, 130),
+            HighlightColor::Pink => Rgb::new(125, 50, 95),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Highlight {
+    pub id: i64,
+    pub note_id: String,
+    pub start_offset: usize,  // Char offsets, like code applications
+    pub end_offset: usize,
+    pub color: HighlightColor,
+}
+
+pub struct HighlightStore {
+    conn: Connection,
+}
+
+impl HighlightStore {
+    pub fn new(notes_dir: &Path) -> Result<Self> {
+        // The highlights table is created by the notes.db migrations
+        let conn = db::open_migrated(&notes_dir.join("notes.db"), db::NOTES)?;
+        Ok(HighlightStore { conn })
+    }
+
+    /// Mark a span; marking exactly the same span again cycles its color instead
+    pub fn toggle(&mut self, note_id: &str, start: usize, end: usize) -> Result<HighlightColor> {
+        let existing = self.for_note(note_id)?.into_iter()
+            .find(|h| h.start_offset == start && h.end_offset == end);
+        if let Some(highlight) = existing {
+            let color = highlight.color.next();
+            self.conn.execute(
+                "UPDATE highlights SET color = ?1 WHERE id = ?2",
+                params![color.name(), highlight.id],
+            )?;
+            return Ok(color);
+        }
+
+        let color = HighlightColor::Yellow;
+        self.conn.execute(
+            "INSERT INTO highlights (note_id, start_offset, end_offset, color, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![note_id, start as i64, end as i64, color.name(), Utc::now().to_rfc3339()],
+        )?;
+        Ok(color)
+    }
+
+    /// Highlights of one note in text order
+    pub fn for_note(&self, note_id: &str) -> Result<Vec<Highlight>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, note_id, start_offset, end_offset, color
+             FROM highlights WHERE note_id = ?1 ORDER BY start_offset, end_offset"
+        )?;
+        let rows = stmt.query_map(params![note_id], |row| {
+            let color: String = row.get(4)?;
+            Ok(Highlight {
+                id: row.get(0)?,
+                note_id: row.get(1)?,
+                start_offset: row.get::<_, i64>(2)? as usize,
+                end_offset: row.get::<_, i64>(3)? as usize,
+                color: HighlightColor::from_name(&color),
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn remove(&mut self, id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM highlights WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Put a removed highlight back under its old id (undoing its conversion into a code)
+    pub fn restore(&mut self, highlight: &Highlight) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO highlights (id, note_id, start_offset, end_offset, color, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                highlight.id,
+                highlight.note_id,
+                highlight.start_offset as i64,
+                highlight.end_offset as i64,
+                highlight.color.name(),
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Move highlights at or after char `from` to another note, shifted so `from` lands on
+    /// `base` (merge / split); one running across `from` is cut there and kept on both sides
+    pub fn move_to(&mut self, from_note: &str, to_note: &str, from: usize, base: usize) -> Result<()> {
+        let (from, base) = (from as i64, base as i64);
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO highlights (note_id, start_offset, end_offset, color, created_at)
+             SELECT ?2, ?4, end_offset - ?3 + ?4, color, created_at FROM highlights
+             WHERE note_id = ?1 AND start_offset < ?3 AND end_offset > ?3",
+            params![from_note, to_note, from, base],
+        )?;
+        tx.execute(
+            "UPDATE highlights SET end_offset = ?2 WHERE note_id = ?1 AND start_offset < ?2 AND end_offset > ?2",
+            params![from_note, from],
+        )?;
+        tx.execute(
+            "UPDATE highlights SET note_id = ?2, start_offset = start_offset - ?3 + ?4, end_offset = end_offset - ?3 + ?4
+             WHERE note_id = ?1 AND start_offset >= ?3",
+            params![from_note, to_note, from, base],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+}
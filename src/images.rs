@@ -0,0 +1,131 @@
+// Inline images in reading mode - drawn with the terminal's graphics protocol
+//
+//   kitty - kitty graphics protocol (kitty, Ghostty); PNG files only, since
+//           anything else would need decoding here first
+//   iterm - iTerm2 inline images (also WezTerm); any format the terminal can show
+//
+// Images are `![alt](src)` lines whose source is an attachment (attachment://id) or
+// a local file, relative paths being taken from the notes directory. Terminals
+// without either protocol, and images that don't fit on screen, get a one-line
+// placeholder instead.
+
+use anyhow::Result;
+use base64::Engine;
+use serde::{Serialize, Deserialize};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::attachments;
+
+/// Rows reserved for an image in the reading layout
+pub const IMAGE_ROWS: usize = 12;
+
+/// Largest file sent through the terminal (the data crosses the pty encoded as base64)
+const MAX_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Chunk size the kitty protocol requires for direct transmission
+const KITTY_CHUNK: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageProtocol {
+    #[default]
+    Auto,   // Pick from the environment
+    Kitty,
+    Iterm,
+    Off,    // Placeholders only
+}
+
+impl ImageProtocol {
+    /// Resolve Auto from the terminal's environment variables
+    pub fn detect(self) -> Self {
+        if self != ImageProtocol::Auto {
+            return self;
+        }
+        let term = std::env::var("TERM").unwrap_or_default();
+        let program = std::env::var("TERM_PROGRAM").unwrap_or_default();
+        if std::env::var_os("KITTY_WINDOW_ID").is_some() || term == "xterm-kitty" || term == "xterm-ghostty" || program == "ghostty" {
+            ImageProtocol::Kitty
+        } else if program == "iTerm.app" || program == "WezTerm" {
+            ImageProtocol::Iterm
+        } else {
+            ImageProtocol::Off
+        }
+    }
+
+    pub fn enabled(self) -> bool {
+        matches!(self, ImageProtocol::Kitty | ImageProtocol::Iterm)
+    }
+}
+
+/// (alt text, source) of a line that is only an image reference
+pub fn image_line(line: &str) -> Option<(&str, &str)> {
+    let rest = line.trim().strip_prefix("![")?;
+    let (alt, rest) = rest.split_once("](")?;
+    let src = rest.strip_suffix(')')?;
+    (!src.is_empty() && !src.contains(char::is_whitespace)).then_some((alt, src))
+}
+
+/// Local file an image source points to
+pub fn resolve(notes_dir: &Path, src: &str) -> Option<PathBuf> {
+    if let Some(id) = src.strip_prefix(attachments::SCHEME) {
+        return attachments::resolve(notes_dir, id).map(|a| a.path);
+    }
+    if src.contains("://") {
+        return None;  // Remote images are never fetched
+    }
+    let path = Path::new(src.strip_prefix("file://").unwrap_or(src));
+    let path = if path.is_absolute() { path.to_path_buf() } else { notes_dir.join(path) };
+    path.is_file().then_some(path)
+}
+
+/// Why an image is shown as a placeholder, if it can't be drawn
+pub fn unsupported(protocol: ImageProtocol, path: &Path) -> Option<&'static str> {
+    if !protocol.enabled() {
+        return Some("no terminal graphics");
+    }
+    if std::fs::metadata(path).map(|m| m.len()).unwrap_or(u64::MAX) > MAX_BYTES {
+        return Some("too large to preview");
+    }
+    let png = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("png"));
+    if protocol == ImageProtocol::Kitty && !png {
+        return Some("only PNG can be previewed");
+    }
+    None
+}
+
+/// Remove every image placed by an earlier frame (kitty keeps them over redrawn text)
+pub fn clear(protocol: ImageProtocol) -> Result<()> {
+    if protocol == ImageProtocol::Kitty {
+        print!("\x1b_Ga=d,q=2\x1b\\");
+    }
+    Ok(())
+}
+
+/// Draw an image into the cell box at the current cursor position
+pub fn draw(protocol: ImageProtocol, path: &Path, cols: u16, rows: u16) -> Result<()> {
+    let data = base64::engine::general_purpose::STANDARD.encode(std::fs::read(path)?);
+    let mut out = io::stdout().lock();
+    match protocol {
+        ImageProtocol::Kitty => {
+            let chunks: Vec<&[u8]> = data.as_bytes().chunks(KITTY_CHUNK).collect();
+            for (i, chunk) in chunks.iter().enumerate() {
+                let more = u8::from(i + 1 < chunks.len());
+                if i == 0 {
+                    // C=1: leave the cursor where it was so the text layout is undisturbed
+                    write!(out, "\x1b_Ga=T,f=100,t=d,q=2,C=1,c={},r={},m={};", cols, rows, more)?;
+                } else {
+                    write!(out, "\x1b_Gm={};", more)?;
+                }
+                out.write_all(chunk)?;
+                write!(out, "\x1b\\")?;
+            }
+        }
+        ImageProtocol::Iterm => {
+            write!(out, "\x1b]1337;File=inline=1;width={};height={};preserveAspectRatio=1:{}\x07", cols, rows, data)?;
+        }
+        ImageProtocol::Auto | ImageProtocol::Off => {}
+    }
+    out.flush()?;
+    Ok(())
+}
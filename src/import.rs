@@ -0,0 +1,103 @@
+// Importers - bring model output back in as machine-suggested code applications
+//
+// The suggestion format mirrors the segment export, so a classifier trained on
+// exports/segments.jsonl can write its predictions straight back:
+//   {"document_id":"3f2a9c1b7d4e","start":120,"end":139,"labels":["Theme"],"confidence":0.82}
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::note_store::Note;
+use crate::qda_codes::CodeManager;
+use crate::query::coded_text;
+
+#[derive(Debug, Deserialize)]
+struct SuggestionRecord {
+    document_id: String,
+    start: usize,
+    end: usize,
+    #[serde(default)]
+    labels: Vec<String>,
+    label: Option<String>,  // Single-label models may emit "label" instead of "labels"
+    #[serde(alias = "score")]
+    confidence: Option<f64>,
+    text: Option<String>,   // If present, must still match the note (guards against edited notes)
+}
+
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub duplicates: usize,
+    pub unknown_documents: usize,
+    pub stale_spans: usize,              // Offsets out of range or text no longer matching
+    pub unknown_labels: BTreeSet<String>,
+}
+
+impl ImportSummary {
+    pub fn describe(&self) -> String {
+        let mut text = format!("{} suggestions added", self.added);
+        if self.duplicates > 0 {
+            text.push_str(&format!(", {} already present", self.duplicates));
+        }
+        if self.unknown_documents > 0 {
+            text.push_str(&format!(", {} for unknown notes", self.unknown_documents));
+        }
+        if self.stale_spans > 0 {
+            text.push_str(&format!(", {} stale spans skipped", self.stale_spans));
+        }
+        if !self.unknown_labels.is_empty() {
+            let labels: Vec<&str> = self.unknown_labels.iter().map(|l| l.as_str()).collect();
+            text.push_str(&format!(", unknown labels: {}", labels.join(", ")));
+        }
+        text
+    }
+}
+
+/// Read a JSONL file of predicted spans and store them as suggested applications
+pub fn import_suggestions_jsonl(path: &Path, notes: &[Note], codes: &mut CodeManager) -> Result<ImportSummary> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("reading {}", path.display()))?;
+    let mut summary = ImportSummary::default();
+
+    for (line_no, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: SuggestionRecord = serde_json::from_str(line)
+            .with_context(|| format!("{}:{}: invalid suggestion record", path.display(), line_no + 1))?;
+
+        let Some(note) = notes.iter().find(|n| n.id == record.document_id) else {
+            summary.unknown_documents += 1;
+            continue;
+        };
+
+        let char_count = note.content.chars().count();
+        let span_ok = record.start < record.end && record.end <= char_count
+            && !record.text.as_ref().is_some_and(|t| *t != coded_text(&note.content, record.start, record.end));
+        if !span_ok {
+            summary.stale_spans += 1;
+            continue;
+        }
+
+        for label in record.labels.iter().chain(record.label.iter()) {
+            // Labels may be code names (as exported) or code ids
+            let code_id = codes.get_all_codes().into_iter()
+                .find(|c| c.name.eq_ignore_ascii_case(label) || c.id == *label)
+                .map(|c| c.id.clone());
+            let Some(code_id) = code_id else {
+                summary.unknown_labels.insert(label.clone());
+                continue;
+            };
+
+            if codes.apply_suggestion(&code_id, &note.id, record.start, record.end, record.confidence)? {
+                summary.added += 1;
+            } else {
+                summary.duplicates += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
@@ -0,0 +1,121 @@
+// Terminal input - the single event source for keys, mouse and paste
+//
+// All decoding (keys, SGR mouse, bracketed paste) goes through crossterm so
+// every feature sees the same events. Backends differ only in the terminal
+// protocols switched on at startup:
+//   basic - legacy key encoding; Cmd/Super never reaches the app
+//   kitty - kitty keyboard protocol: reports Super (Cmd on macOS) and tells
+//           Esc apart from Alt+key
+//
+// Whatever ends the session - quitting, an error out of the event loop or a
+// panic (see install_panic_hook) - the terminal is put back the way it was.
+
+use anyhow::Result;
+use crossterm::{
+    event::{
+        self, Event, KeyEventKind, KeyboardEnhancementFlags, PushKeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
+        EnableMouseCapture, DisableMouseCapture, EnableBracketedPaste, DisableBracketedPaste,
+    },
+    cursor, execute, terminal,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use serde::{Serialize, Deserialize};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+// Raw mode is on (for the panic hook, which can't reach the Input)
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Which keyboard protocol to request (config.json "input")
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputBackend {
+    Auto,   // Kitty protocol if the terminal answers the query, basic otherwise
+    Basic,  // Legacy encoding only
+    Kitty,  // Always request the kitty protocol
+}
+
+pub struct Input {
+    kitty: bool,  // Enhancement flags were pushed and must be popped on exit
+}
+
+impl Input {
+    /// Enter raw mode and the alternate screen, and switch on mouse and paste reporting
+    pub fn start(backend: InputBackend) -> Result<Self> {
+        enable_raw_mode()?;
+        ACTIVE.store(true, Ordering::SeqCst);
+        let mut stdout = io::stdout();
+        // Bracketed paste delivers pasted/IME-committed text as one UTF-8 string
+        execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
+
+        let wanted = match backend {
+            InputBackend::Auto => terminal::supports_keyboard_enhancement().unwrap_or(false),
+            InputBackend::Basic => false,
+            InputBackend::Kitty => true,
+        };
+        // Terminals (and platforms) without the protocol reject the push - fall back to basic
+        let kitty = wanted && execute!(
+            stdout,
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+        ).is_ok();
+
+        Ok(Input { kitty })
+    }
+
+    /// Next event, or None if nothing arrived within the timeout
+    pub fn next_event(&mut self, timeout: Duration) -> Result<Option<Event>> {
+        if !event::poll(timeout)? {
+            return Ok(None);
+        }
+
+        match event::read()? {
+            // Windows and the kitty protocol can report key releases; only presses and repeats count
+            Event::Key(key) if key.kind == KeyEventKind::Release => Ok(None),
+            event => Ok(Some(event)),
+        }
+    }
+
+    /// Restore the terminal to the state it was in before `start`
+    pub fn stop(&mut self) -> Result<()> {
+        let mut stdout = io::stdout();
+        if self.kitty {
+            execute!(stdout, PopKeyboardEnhancementFlags)?;
+            self.kitty = false;
+        }
+        execute!(stdout, DisableBracketedPaste, DisableMouseCapture, LeaveAlternateScreen, cursor::Show)?;
+        disable_raw_mode()?;
+        ACTIVE.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// Best-effort restore when the Input can't be stopped normally. Popping the
+/// keyboard flags is harmless on terminals that never had them pushed.
+pub fn restore_terminal() {
+    if !ACTIVE.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    let _ = execute!(
+        io::stdout(),
+        PopKeyboardEnhancementFlags,
+        DisableBracketedPaste,
+        DisableMouseCapture,
+        LeaveAlternateScreen,
+        cursor::Show,
+    );
+    let _ = disable_raw_mode();
+}
+
+/// On a panic: restore the terminal first, so the message is readable, then record
+/// it in the log and hand over to the default hook (which prints it)
+pub fn install_panic_hook(log_path: std::path::PathBuf) {
+    let default = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        let location = info.location().map(|l| format!("{}:{}", l.file(), l.line())).unwrap_or_default();
+        tracing::error!(panic = %info, location = %location, "snyfter3 panicked");
+        default(info);
+        eprintln!("snyfter3 crashed; details are in {}", log_path.display());
+    }));
+}
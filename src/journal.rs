@@ -0,0 +1,211 @@
+// Write-ahead journal for revisions that autosave could not get into the database
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::note_store::Note;
+
+// Retry schedule after a failed save: 0.5s, 1s, 2s ... capped at 30s
+const FIRST_RETRY: Duration = Duration::from_millis(500);
+const MAX_RETRY: Duration = Duration::from_secs(30);
+// Minimum gap between journal rewrites while typing against a failing database
+const WRITE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub note_id: String,
+    pub title: String,
+    pub content: String,
+    pub tags: Vec<String>,
+    pub base_updated_at: DateTime<Utc>,  // Note's updated_at when the edit started
+    pub journaled_at: DateTime<Utc>,
+    pub error: String,
+}
+
+pub struct Journal {
+    path: PathBuf,
+    entries: BTreeMap<String, JournalEntry>,  // Latest unsaved revision per note
+    held: BTreeSet<String>,  // Entries from a previous session the user hasn't decided on yet
+    dirty: bool,
+    last_write: Option<Instant>,
+    retry_delay: Duration,
+    next_retry: Option<Instant>,
+}
+
+impl Journal {
+    pub fn open(notes_dir: &Path) -> Self {
+        let path = notes_dir.join("unsaved_journal.json");
+        let entries = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        Journal {
+            path,
+            entries,
+            held: BTreeSet::new(),
+            dirty: false,
+            last_write: None,
+            retry_delay: FIRST_RETRY,
+            next_retry: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Revisions still waiting to reach the database
+    pub fn pending(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Record a revision that failed to save and schedule a retry
+    pub fn record(&mut self, note: &Note, error: &anyhow::Error) {
+        let entry = JournalEntry {
+            note_id: note.id.clone(),
+            title: note.title.clone(),
+            content: note.content.clone(),
+            tags: note.tags.clone(),
+            base_updated_at: self.entries.get(&note.id)
+                .map(|e| e.base_updated_at)
+                .unwrap_or(note.updated_at),
+            journaled_at: Utc::now(),
+            error: error.to_string(),
+        };
+        self.entries.insert(note.id.clone(), entry);
+        self.dirty = true;
+
+        if self.next_retry.is_none() {
+            self.next_retry = Some(Instant::now() + self.retry_delay);
+        }
+        self.flush(false);
+    }
+
+    /// The note saved normally, so any older journaled revision is obsolete
+    pub fn forget(&mut self, note_id: &str) {
+        self.held.remove(note_id);
+        if self.entries.remove(note_id).is_some() {
+            self.dirty = true;
+            self.flush(true);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.held.clear();
+        self.dirty = true;
+        self.next_retry = None;
+        self.retry_delay = FIRST_RETRY;
+        self.flush(true);
+    }
+
+    /// Persist the journal; rate-limited unless forced. Best effort - if the disk
+    /// is full too there is nothing better to do than keep the entries in memory.
+    pub fn flush(&mut self, force: bool) {
+        if !self.dirty {
+            return;
+        }
+        if !force && self.last_write.is_some_and(|t| t.elapsed() < WRITE_INTERVAL) {
+            return;
+        }
+
+        let result = if self.entries.is_empty() {
+            match std::fs::remove_file(&self.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            }
+        } else {
+            self.write_atomically()
+        };
+
+        if result.is_ok() {
+            self.dirty = false;
+        }
+        self.last_write = Some(Instant::now());
+    }
+
+    fn write_atomically(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.entries)?;
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    /// True when a retry is scheduled and its backoff has elapsed
+    pub fn retry_due(&self) -> bool {
+        self.next_retry.is_some_and(|t| Instant::now() >= t)
+    }
+
+    /// Push the retry further out after another failure
+    pub fn back_off(&mut self) {
+        self.retry_delay = (self.retry_delay * 2).min(MAX_RETRY);
+        self.next_retry = Some(Instant::now() + self.retry_delay);
+        self.flush(false);
+    }
+
+    /// Retries succeeded - reset the schedule (held entries wait for the user)
+    pub fn settle(&mut self) {
+        self.retry_delay = FIRST_RETRY;
+        self.next_retry = None;
+        self.flush(true);
+    }
+
+    /// Remove the entries that retries may save (held entries stay put)
+    pub fn take_entries(&mut self) -> Vec<JournalEntry> {
+        let ids: Vec<String> = self.entries.keys()
+            .filter(|id| !self.held.contains(*id))
+            .cloned()
+            .collect();
+        self.dirty = true;
+        ids.iter().filter_map(|id| self.entries.remove(id)).collect()
+    }
+
+    /// Keep everything currently journaled out of automatic retries until the next launch
+    pub fn hold_all(&mut self) {
+        self.held = self.entries.keys().cloned().collect();
+    }
+
+    /// Make held entries eligible for saving again
+    pub fn release_all(&mut self) {
+        self.held.clear();
+    }
+
+    pub fn restore(&mut self, entry: JournalEntry) {
+        self.entries.insert(entry.note_id.clone(), entry);
+        self.dirty = true;
+    }
+
+    /// Lines for the recovery prompt shown at launch
+    pub fn summary_lines(&self, current: &[Note]) -> Vec<String> {
+        let mut lines = vec![
+            format!("{} edit(s) could not be saved last session:", self.entries.len()),
+            String::new(),
+        ];
+
+        for entry in self.entries.values() {
+            let note = current.iter().find(|n| n.id == entry.note_id);
+            let state = match note {
+                None => " (note no longer exists - will be recreated)",
+                Some(n) if n.updated_at > entry.base_updated_at => " (note changed since - journal wins)",
+                Some(_) => "",
+            };
+            lines.push(format!(
+                "  {}  {}{}",
+                entry.journaled_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+                entry.title,
+                state
+            ));
+            lines.push(format!("      {} chars, failed with: {}", entry.content.chars().count(), entry.error));
+        }
+
+        lines.push(String::new());
+        lines.push("Enter: apply all   d: discard all   Esc: keep for next launch".to_string());
+        lines
+    }
+}
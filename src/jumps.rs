@@ -0,0 +1,81 @@
+// Jump list - the places the cursor jumped from, for going back and forward again
+//
+// Whenever the editor lands somewhere new in one go - another note (a followed link, a
+// search result, a coded segment or task in another note, back / forward) or more than
+// a few lines away in the same note (a heading, the next segment, the outline) - the place
+// it left is remembered. Alt+O goes back through those places and Alt+I forward again,
+// like vim's Ctrl+O / Ctrl+I (Ctrl+O here is the external editor and terminals send
+// Ctrl+I as Tab). Jumping somewhere new after going back drops the places ahead.
+// Positions are char offsets, kept within the note as it changes.
+
+/// Lines the cursor has to move in one step to count as a jump
+pub const JUMP_LINES: usize = 10;
+/// Places kept
+const MAX_JUMPS: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Jump {
+    pub note_id: String,
+    pub pos: usize,   // Char offset of the cursor
+    pub line: usize,  // Its line, for telling a jump from ordinary movement
+}
+
+impl Jump {
+    /// Whether going from here to `to` is a jump rather than moving around
+    pub fn is_jump_to(&self, to: &Jump) -> bool {
+        self.note_id != to.note_id || self.line.abs_diff(to.line) > JUMP_LINES
+    }
+
+    fn same_place(&self, other: &Jump) -> bool {
+        self.note_id == other.note_id && self.line == other.line
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct JumpList {
+    jumps: Vec<Jump>,
+    index: usize,  // Where Alt+O / Alt+I are in `jumps`; its length while not going back
+}
+
+impl JumpList {
+    /// A jump was made from `from`
+    pub fn record(&mut self, from: Jump) {
+        self.jumps.truncate(self.index);
+        if self.jumps.last().is_some_and(|last| last.same_place(&from)) {
+            self.jumps.pop();
+        }
+        self.jumps.push(from);
+        if self.jumps.len() > MAX_JUMPS {
+            self.jumps.remove(0);
+        }
+        self.index = self.jumps.len();
+    }
+
+    /// The place jumped from before `here`; the first step back remembers `here` so
+    /// forward can return to it. `exists` skips places in notes deleted since
+    pub fn back(&mut self, here: Jump, exists: impl Fn(&str) -> bool) -> Option<Jump> {
+        if self.index == self.jumps.len() {
+            self.record(here.clone());
+            self.index = self.jumps.len() - 1;
+        }
+        while self.index > 0 {
+            self.index -= 1;
+            let jump = &self.jumps[self.index];
+            if exists(&jump.note_id) && !jump.same_place(&here) {
+                return Some(jump.clone());
+            }
+        }
+        None
+    }
+
+    pub fn forward(&mut self, here: Jump, exists: impl Fn(&str) -> bool) -> Option<Jump> {
+        while self.index + 1 < self.jumps.len() {
+            self.index += 1;
+            let jump = &self.jumps[self.index];
+            if exists(&jump.note_id) && !jump.same_place(&here) {
+                return Some(jump.clone());
+            }
+        }
+        None
+    }
+}
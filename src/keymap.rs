@@ -0,0 +1,582 @@
+// Keybinding table - key dispatch and the help overlay are both generated from here
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::FocusArea;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Command {
+    Quit,
+    FocusSearch,
+    CycleFocus,
+    CycleTheme,
+    ShowHelp,
+    NewNote,
+    NewFromTemplate,
+    OpenNote,
+    DeleteNote,
+    UndoDelete,
+    FollowLink,
+    SelectPrev,
+    SelectNext,
+    PageUp,
+    PageDown,
+    SelectFirst,
+    SelectLast,
+    ChipNotebook,
+    ChipTag,
+    ChipHasCode,
+    ChipHasTask,
+    ChipDate,
+    ShrinkList,
+    GrowList,
+    CycleDensity,
+    BackToList,
+    ExportHtml,
+    ExportSegments,
+    ShowUsage,
+    ToggleLargeUi,
+    PasteHistory,
+    MirrorCheck,
+    ReviewSuggestions,
+    HighlightSelection,
+    SelectSentence,
+    SelectParagraph,
+    ShowHighlights,
+    Annotate,
+    ShowAnnotations,
+    ShowJournal,
+    MergeCode,
+    RecodeSegments,
+    MoveCode,
+    AutoCode,
+    CaseMatrix,
+    CodingCoverage,
+    ToggleReadOnly,
+    ToggleCodeLabels,
+    NextSegment,
+    PrevSegment,
+    SegmentFilter,
+    CodeVisibility,
+    UndoCoding,
+    RedoCoding,
+    RemoveCode,
+    SelectionActions,
+    ExtractNote,
+    Metadata,
+    TagManager,
+    TagSuggestions,
+    RelatedNotes,
+    LinkCheck,
+    QuickSwitcher,
+    NoteBack,
+    NoteForward,
+    JumpBack,
+    JumpForward,
+    SearchHistory,
+    DateFilter,
+    ToggleCodeColors,
+    Codebook,
+    Report,
+    WordFrequency,
+    EditExternally,
+    ToggleReading,
+    CycleLineSpacing,
+    TodayNote,
+    PrevDailyNote,
+    NextDailyNote,
+    DailyCalendar,
+    ShowTasks,
+    ToggleCheckbox,
+    ShowOutline,
+    NextHeading,
+    PrevHeading,
+    NoteInfo,
+    Reindex,
+    ShowAttachments,
+    CompleteCitation,
+    LockScreen,
+    SyncNow,
+    ToggleMark,
+    MergeNotes,
+    SplitNote,
+    FindDuplicates,
+    RenameNote,
+    ShowMessages,
+    CycleLayout,
+    ToggleZen,
+    TogglePreview,
+    Leader,
+}
+
+impl Command {
+    pub fn description(self) -> &'static str {
+        match self {
+            Command::Quit => "Quit",
+            Command::FocusSearch => "Focus search bar",
+            Command::CycleFocus => "Switch focus (search / list / editor)",
+            Command::CycleTheme => "Cycle color theme",
+            Command::ShowHelp => "Show this help",
+            Command::NewNote => "Create new note",
+            Command::NewFromTemplate => "New note from QDA template (case summary, memo, ...)",
+            Command::OpenNote => "Edit selected note",
+            Command::DeleteNote => "Move selected note to trash",
+            Command::UndoDelete => "Undo delete (restore from trash)",
+            Command::FollowLink => "Follow wiki link or @citation",
+            Command::SelectPrev => "Previous note",
+            Command::SelectNext => "Next note",
+            Command::PageUp => "Page up in note list",
+            Command::PageDown => "Page down in note list",
+            Command::SelectFirst => "First note",
+            Command::SelectLast => "Last note",
+            Command::ChipNotebook => "Filter chip: cycle notebook",
+            Command::ChipTag => "Filter chip: cycle tag",
+            Command::ChipHasCode => "Filter chip: only notes with codes",
+            Command::ChipHasTask => "Filter chip: only notes with open tasks",
+            Command::ChipDate => "Filter chip: cycle date range",
+            Command::ShrinkList => "Make note list narrower",
+            Command::GrowList => "Make note list wider",
+            Command::CycleDensity => "Cycle list view (compact / cozy / detailed)",
+            Command::BackToList => "Back to note list",
+            Command::ExportHtml => "Export note as HTML",
+            Command::ExportSegments => "Export coded segments as JSONL (NLP corpus)",
+            Command::ShowUsage => "Usage report (local, opt-in)",
+            Command::ToggleLargeUi => "Toggle large UI (accessibility)",
+            Command::PasteHistory => "Paste from clipboard history",
+            Command::MirrorCheck => "Compare notes with markdown mirror",
+            Command::ReviewSuggestions => "Review machine-suggested codes",
+            Command::HighlightSelection => "Highlight selection (again: next color)",
+            Command::SelectSentence => "Select the sentence(s) around the cursor or selection",
+            Command::SelectParagraph => "Select the paragraph(s) around the cursor or selection",
+            Command::ShowHighlights => "List highlights in this note (convert to codes)",
+            Command::Annotate => "Comment on the selection (or edit the annotation at the cursor)",
+            Command::ShowAnnotations => "List annotations in this note",
+            Command::ShowJournal => "Project journal: dated analysis memos",
+            Command::MergeCode => "Merge a code into another (all notes; the old code is deleted)",
+            Command::RecodeSegments => "Recode a code's segments as another code in the listed notes",
+            Command::MoveCode => "Move a code under another parent in the code tree",
+            Command::AutoCode => "Auto-code: apply a code to every match of a phrase or /regex/",
+            Command::CaseMatrix => "Case matrix: segment counts per code and case (marked or listed notes)",
+            Command::CodingCoverage => "Coding coverage: notes not yet fully coded, least coded first",
+            Command::ToggleCodeLabels => "Show or hide ⟨code⟩ labels where coded segments start in the editor",
+            Command::ToggleReadOnly => "Lock or unlock the open note's text (read-only still allows selecting and coding)",
+            Command::ToggleCodeColors => "Child codes take shades of their parent's color",
+            Command::Codebook => "Export or import the codebook (JSON, CSV, REFI-QDA .qdc)",
+            Command::Report => "Report: segments of chosen codes in the listed notes (CSV, HTML, DOCX)",
+            Command::WordFrequency => "Word frequencies and keyword in context (marked or listed notes)",
+            Command::EditExternally => "Edit note in $EDITOR",
+            Command::TodayNote => "Today's daily note (created if missing)",
+            Command::PrevDailyNote => "Previous daily note",
+            Command::NextDailyNote => "Next daily note",
+            Command::DailyCalendar => "Calendar of daily notes",
+            Command::ShowTasks => "Tasks from all notes",
+            Command::ToggleCheckbox => "Tick / untick the checkbox on the cursor line",
+            Command::ShowOutline => "Outline of the open note",
+            Command::NextHeading => "Next heading",
+            Command::NextSegment => "Next coded segment (of the code chosen with the segment filter)",
+            Command::PrevSegment => "Previous coded segment (of the code chosen with the segment filter)",
+            Command::SegmentFilter => "Segment filter: choose the code next / previous segment steps through",
+            Command::CodeVisibility => "Code visibility: show or hide the editor tints of single codes or whole branches",
+            Command::UndoCoding => "Undo the last coding action (apply, remove, accept, recode, merge)",
+            Command::RedoCoding => "Redo the last undone coding action",
+            Command::RemoveCode => "Remove the innermost code at the cursor",
+            Command::SelectionActions => "Selection actions: copy, code, annotate, highlight, search, new linked note, extract",
+            Command::ExtractNote => "Extract note: move the selection into a new note and link to it in its place",
+            Command::Metadata => "Metadata: edit the open note's front matter fields",
+            Command::TagManager => "Tag manager: rename, merge or remove a #tag in every note",
+            Command::TagSuggestions => "Suggested tags for the open note: add or dismiss each",
+            Command::RelatedNotes => "Related notes: the notes most like the open one by the words they use",
+            Command::LinkCheck => "Link check: broken links, orphan notes and unused attachments, with quick fixes",
+            Command::QuickSwitcher => "Quick switcher: recent notes first, filtered by title as you type",
+            Command::NoteBack => "Back to the note visited before this one",
+            Command::NoteForward => "Forward to the next note visited",
+            Command::JumpBack => "Jump back to where the cursor was before the last jump",
+            Command::JumpForward => "Jump forward again through the jump list",
+            Command::SearchHistory => "Search history: earlier queries, filtered as you type",
+            Command::DateFilter => "Date filter: notes created or updated in a period or a typed range",
+            Command::PrevHeading => "Previous heading",
+            Command::NoteInfo => "Note info and statistics",
+            Command::Reindex => "Rebuild the search index",
+            Command::ShowAttachments => "Attachments of the open note",
+            Command::CompleteCitation => "Complete the @citekey at the cursor",
+            Command::LockScreen => "Lock the screen (needs a passphrase)",
+            Command::SyncNow => "Sync notes with the WebDAV / S3 store",
+            Command::ToggleMark => "Mark / unmark the selected note (for merging)",
+            Command::MergeNotes => "Merge the marked notes into the first one marked",
+            Command::SplitNote => "Split the open note at the cursor or heading",
+            Command::FindDuplicates => "Find duplicate notes (same title or mostly the same text)",
+            Command::RenameNote => "Rename the open note (edit its title in the header)",
+            Command::ShowMessages => "Message history (errors and warnings included)",
+            Command::CycleLayout => "Cycle pane layout (list + editor / + codes pane)",
+            Command::ToggleZen => "Zen mode: the editor alone, full screen",
+            Command::TogglePreview => "Preview mode: the list previews notes instead of opening them",
+            Command::ToggleReading => "Reading mode (wrapped, read-only)",
+            Command::CycleLineSpacing => "Cycle reading line spacing (single / paragraphs / double)",
+            Command::Leader => "Leader key (shows follow-up keys)",
+        }
+    }
+}
+
+pub struct Binding {
+    pub area: Option<FocusArea>,  // None = active everywhere
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+    pub command: Command,
+}
+
+const fn bind(area: Option<FocusArea>, code: KeyCode, modifiers: KeyModifiers, command: Command) -> Binding {
+    Binding { area, code, modifiers, command }
+}
+
+const NONE: KeyModifiers = KeyModifiers::NONE;
+const CTRL: KeyModifiers = KeyModifiers::CONTROL;
+const ALT: KeyModifiers = KeyModifiers::ALT;
+const LIST: Option<FocusArea> = Some(FocusArea::NoteList);
+const EDITOR: Option<FocusArea> = Some(FocusArea::Editor);
+const SEARCH: Option<FocusArea> = Some(FocusArea::SearchBar);
+
+pub static BINDINGS: &[Binding] = &[
+    // Search bar (ahead of the global Ctrl+R, rename)
+    bind(SEARCH, KeyCode::Char('r'), CTRL, Command::SearchHistory),
+    bind(SEARCH, KeyCode::Char('d'), CTRL, Command::DateFilter),
+
+    // Global
+    bind(None, KeyCode::Char('q'), CTRL, Command::Quit),
+    bind(None, KeyCode::Char('l'), CTRL, Command::FocusSearch),
+    bind(None, KeyCode::Tab, NONE, Command::CycleFocus),
+    bind(None, KeyCode::F(1), NONE, Command::ShowHelp),
+    bind(None, KeyCode::F(2), NONE, Command::CycleTheme),
+    bind(None, KeyCode::F(3), NONE, Command::ShowUsage),
+    bind(None, KeyCode::F(4), NONE, Command::ToggleLargeUi),
+    bind(None, KeyCode::F(5), NONE, Command::MirrorCheck),
+    bind(None, KeyCode::F(6), NONE, Command::ReviewSuggestions),
+    bind(None, KeyCode::F(7), NONE, Command::ToggleReading),
+    bind(None, KeyCode::F(8), NONE, Command::ShowTasks),
+    bind(None, KeyCode::F(9), NONE, Command::ShowOutline),
+    bind(None, KeyCode::Char('e'), CTRL, Command::ExportHtml),
+    bind(None, KeyCode::Char('g'), CTRL, Command::Leader),
+    bind(None, KeyCode::Char('u'), CTRL, Command::UndoDelete),
+    bind(None, KeyCode::Char('o'), CTRL, Command::EditExternally),
+    bind(None, KeyCode::Char('t'), CTRL, Command::TodayNote),
+    bind(None, KeyCode::Char('r'), CTRL, Command::RenameNote),
+    bind(None, KeyCode::Char('z'), ALT, Command::UndoCoding),
+    bind(None, KeyCode::Char('y'), ALT, Command::RedoCoding),
+    bind(None, KeyCode::Char('p'), CTRL, Command::QuickSwitcher),
+    bind(None, KeyCode::Char(','), ALT, Command::NoteBack),
+    bind(None, KeyCode::Char('.'), ALT, Command::NoteForward),
+    bind(None, KeyCode::Char('o'), ALT, Command::JumpBack),
+    bind(None, KeyCode::Char('i'), ALT, Command::JumpForward),
+
+    // Note list
+    bind(LIST, KeyCode::Char('f'), CTRL, Command::FocusSearch),
+    bind(LIST, KeyCode::Char('/'), CTRL, Command::FocusSearch),
+    bind(LIST, KeyCode::Enter, NONE, Command::OpenNote),
+    bind(LIST, KeyCode::Right, NONE, Command::OpenNote),
+    bind(LIST, KeyCode::Char('n'), CTRL, Command::NewNote),
+    bind(LIST, KeyCode::Up, NONE, Command::SelectPrev),
+    bind(LIST, KeyCode::Char('k'), NONE, Command::SelectPrev),
+    bind(LIST, KeyCode::Down, NONE, Command::SelectNext),
+    bind(LIST, KeyCode::Char('j'), NONE, Command::SelectNext),
+    bind(LIST, KeyCode::PageUp, NONE, Command::PageUp),
+    bind(LIST, KeyCode::PageDown, NONE, Command::PageDown),
+    bind(LIST, KeyCode::Home, NONE, Command::SelectFirst),
+    bind(LIST, KeyCode::End, NONE, Command::SelectLast),
+    bind(LIST, KeyCode::Char('1'), NONE, Command::ChipNotebook),
+    bind(LIST, KeyCode::Char('2'), NONE, Command::ChipTag),
+    bind(LIST, KeyCode::Char('3'), NONE, Command::ChipHasCode),
+    bind(LIST, KeyCode::Char('4'), NONE, Command::ChipHasTask),
+    bind(LIST, KeyCode::Char('5'), NONE, Command::ChipDate),
+    bind(LIST, KeyCode::Char('w'), CTRL, Command::FollowLink),
+    bind(LIST, KeyCode::Char('d'), CTRL, Command::DeleteNote),
+    bind(LIST, KeyCode::Char(','), CTRL, Command::ShrinkList),
+    bind(LIST, KeyCode::Char('.'), CTRL, Command::GrowList),
+    bind(LIST, KeyCode::Char('v'), NONE, Command::CycleDensity),
+    bind(LIST, KeyCode::Char('?'), NONE, Command::ShowHelp),
+    bind(LIST, KeyCode::Char(' '), NONE, Command::ToggleMark),
+    bind(LIST, KeyCode::Left, ALT, Command::NoteBack),
+    bind(LIST, KeyCode::Right, ALT, Command::NoteForward),
+
+    // Editor
+    bind(EDITOR, KeyCode::Esc, NONE, Command::BackToList),
+    bind(EDITOR, KeyCode::Char('v'), ALT, Command::PasteHistory),
+    bind(EDITOR, KeyCode::Char('h'), ALT, Command::HighlightSelection),
+    bind(EDITOR, KeyCode::Char('n'), ALT, Command::Annotate),
+    bind(EDITOR, KeyCode::Char('s'), ALT, Command::SelectSentence),
+    bind(EDITOR, KeyCode::Char('p'), ALT, Command::SelectParagraph),
+    bind(EDITOR, KeyCode::Char('u'), ALT, Command::RemoveCode),
+    bind(EDITOR, KeyCode::Char('a'), ALT, Command::SelectionActions),
+    bind(EDITOR, KeyCode::Char('e'), ALT, Command::ExtractNote),
+    bind(EDITOR, KeyCode::Char('m'), ALT, Command::Metadata),
+    bind(EDITOR, KeyCode::Char('t'), ALT, Command::TagSuggestions),
+    bind(EDITOR, KeyCode::Enter, CTRL, Command::ToggleCheckbox),
+    bind(EDITOR, KeyCode::Down, ALT, Command::NextHeading),
+    bind(EDITOR, KeyCode::Up, ALT, Command::PrevHeading),
+    bind(EDITOR, KeyCode::Right, ALT, Command::NextSegment),
+    bind(EDITOR, KeyCode::Left, ALT, Command::PrevSegment),
+    bind(EDITOR, KeyCode::Char(' '), CTRL, Command::CompleteCitation),
+
+    // After the editor's Ctrl+Enter (checkbox), which falls back to zen off a task line
+    bind(None, KeyCode::Enter, CTRL, Command::ToggleZen),
+];
+
+/// Follow-up keys after the leader (Ctrl+G), grouped for the which-key popup
+pub struct LeaderBinding {
+    pub group: &'static str,
+    pub key: char,
+    pub command: Command,
+}
+
+const fn leader(group: &'static str, key: char, command: Command) -> LeaderBinding {
+    LeaderBinding { group, key, command }
+}
+
+pub const LEADER_GROUPS: &[&str] = &["notes", "codes", "search", "export", "view"];
+
+pub static LEADER_BINDINGS: &[LeaderBinding] = &[
+    leader("notes", 'n', Command::NewNote),
+    leader("notes", 'a', Command::NewFromTemplate),
+    leader("notes", 'd', Command::DeleteNote),
+    leader("notes", 'r', Command::UndoDelete),
+    leader("notes", 'l', Command::FollowLink),
+    leader("notes", 'p', Command::PasteHistory),
+    leader("notes", 'o', Command::EditExternally),
+    leader("notes", 'y', Command::TodayNote),
+    leader("notes", '[', Command::PrevDailyNote),
+    leader("notes", ']', Command::NextDailyNote),
+    leader("notes", 'k', Command::DailyCalendar),
+    leader("notes", 'x', Command::ShowTasks),
+    leader("notes", '-', Command::ToggleCheckbox),
+    leader("notes", 'w', Command::NoteInfo),
+    leader("notes", 'A', Command::ShowAttachments),
+    leader("notes", '@', Command::CompleteCitation),
+    leader("notes", 'S', Command::SyncNow),
+    leader("notes", 'M', Command::MergeNotes),
+    leader("notes", '/', Command::SplitNote),
+    leader("notes", 'D', Command::FindDuplicates),
+    leader("notes", 'R', Command::RenameNote),
+    leader("notes", 'N', Command::ShowAnnotations),
+    leader("notes", 'J', Command::ShowJournal),
+    leader("notes", 'U', Command::ToggleReadOnly),
+    leader("notes", '#', Command::TagManager),
+    leader("notes", '=', Command::RelatedNotes),
+    leader("notes", '!', Command::LinkCheck),
+    leader("codes", 'c', Command::ReviewSuggestions),
+    leader("codes", 'h', Command::ShowHighlights),
+    leader("codes", 'C', Command::MergeCode),
+    leader("codes", 'K', Command::RecodeSegments),
+    leader("codes", 'T', Command::MoveCode),
+    leader("codes", 'F', Command::AutoCode),
+    leader("codes", 'X', Command::CaseMatrix),
+    leader("codes", 'O', Command::CodingCoverage),
+    leader("codes", 'I', Command::ToggleCodeColors),
+    leader("codes", 'H', Command::ToggleCodeLabels),
+    leader("codes", 'G', Command::SegmentFilter),
+    leader("codes", 'Y', Command::CodeVisibility),
+    leader("search", 's', Command::FocusSearch),
+    leader("search", 'f', Command::Reindex),
+    leader("search", 'W', Command::WordFrequency),
+    leader("export", 'e', Command::ExportHtml),
+    leader("export", 'j', Command::ExportSegments),
+    leader("export", 'm', Command::MirrorCheck),
+    leader("export", 'B', Command::Codebook),
+    leader("export", 'E', Command::Report),
+    leader("view", 't', Command::CycleTheme),
+    leader("view", 'v', Command::CycleDensity),
+    leader("view", 'b', Command::CycleLineSpacing),
+    leader("view", 'i', Command::ShowOutline),
+    leader("view", 'z', Command::ToggleLargeUi),
+    leader("view", 'u', Command::ShowUsage),
+    leader("view", 'g', Command::ShowMessages),
+    leader("view", 'P', Command::CycleLayout),
+    leader("view", 'Z', Command::ToggleZen),
+    leader("view", 'V', Command::TogglePreview),
+    leader("view", 'L', Command::LockScreen),
+    leader("view", '?', Command::ShowHelp),
+];
+
+pub fn lookup_leader(key: &KeyEvent) -> Option<Command> {
+    match key.code {
+        KeyCode::Char(c) => LEADER_BINDINGS.iter().find(|b| b.key == c).map(|b| b.command),
+        _ => None,
+    }
+}
+
+/// Which-key popup contents: one section per non-empty group
+pub fn leader_lines() -> Vec<String> {
+    let mut lines = Vec::new();
+    for group in LEADER_GROUPS {
+        let bindings: Vec<&LeaderBinding> = LEADER_BINDINGS.iter().filter(|b| b.group == *group).collect();
+        if bindings.is_empty() {
+            continue;
+        }
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(format!("── {} ──", group));
+        for binding in bindings {
+            lines.push(format!("  {}   {}", binding.key, binding.command.description()));
+        }
+    }
+    lines
+}
+
+/// Keys handled directly by the text inputs rather than through commands (listed in help only)
+pub static TEXT_INPUT_KEYS: &[(FocusArea, &str, &str)] = &[
+    (FocusArea::SearchBar, "Type", "Filter notes as you type"),
+    (FocusArea::SearchBar, "memo:x / quote:x", "Match code memos / coded text"),
+    (FocusArea::SearchBar, "Esc / Enter / ↓", "Back to note list (Enter opens first match)"),
+    (FocusArea::Editor, "Arrows", "Move cursor (accelerates when held)"),
+    (FocusArea::Editor, "Shift+Arrows", "Extend selection"),
+    (FocusArea::Editor, "Alt+Shift+Arrows", "Extend block selection"),
+    (FocusArea::Editor, "Ctrl+← / Ctrl+→", "Move by word"),
+    (FocusArea::Editor, "Home / End", "Line start / end"),
+    (FocusArea::Editor, "PgUp / PgDn", "Page up / down"),
+    (FocusArea::Editor, "Ctrl+A", "Select all"),
+    (FocusArea::Editor, "Ctrl+X / Ctrl+C / Ctrl+V", "Cut / copy / paste"),
+    (FocusArea::Editor, "Ctrl+Z / Ctrl+Y", "Undo / redo (kept across sessions)"),
+    (FocusArea::Editor, "Mouse drag", "Select text"),
+    (FocusArea::Editor, "Alt+mouse drag", "Block selection"),
+    (FocusArea::Editor, "Double / triple click", "Select word / line"),
+];
+
+impl Binding {
+    fn matches(&self, area: FocusArea, key: &KeyEvent) -> bool {
+        if let Some(binding_area) = self.area {
+            if binding_area != area {
+                return false;
+            }
+        }
+
+        // Shift is implied by the character itself ('?' arrives with SHIFT on most terminals)
+        let modifiers = if matches!(key.code, KeyCode::Char(_)) {
+            key.modifiers.difference(KeyModifiers::SHIFT)
+        } else {
+            key.modifiers
+        };
+
+        self.code == key.code && self.modifiers == modifiers
+    }
+}
+
+/// Find the command bound to a key in the given focus area
+pub fn lookup(area: FocusArea, key: &KeyEvent) -> Option<Command> {
+    BINDINGS.iter()
+        .find(|binding| binding.matches(area, key))
+        .map(|binding| binding.command)
+}
+
+/// Human-readable label for a key combination ("Ctrl+N", "F1", "↑")
+pub fn key_label(code: KeyCode, modifiers: KeyModifiers) -> String {
+    let mut label = String::new();
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        label.push_str("Ctrl+");
+    }
+    if modifiers.contains(KeyModifiers::ALT) {
+        label.push_str("Alt+");
+    }
+    if modifiers.contains(KeyModifiers::SHIFT) {
+        label.push_str("Shift+");
+    }
+
+    let key = match code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => c.to_ascii_uppercase().to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        KeyCode::Enter => "Enter".to_string(),
+        KeyCode::Esc => "Esc".to_string(),
+        KeyCode::Tab => "Tab".to_string(),
+        KeyCode::Backspace => "Backspace".to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::Home => "Home".to_string(),
+        KeyCode::End => "End".to_string(),
+        KeyCode::PageUp => "PgUp".to_string(),
+        KeyCode::PageDown => "PgDn".to_string(),
+        other => format!("{:?}", other),
+    };
+
+    label.push_str(&key);
+    label
+}
+
+/// Every command reachable from the binding table, in table order
+pub fn bound_commands() -> Vec<Command> {
+    let mut commands = Vec::new();
+    for binding in BINDINGS {
+        if !commands.contains(&binding.command) {
+            commands.push(binding.command);
+        }
+    }
+    commands
+}
+
+/// Labels of all keys bound to a command
+pub fn keys_for(command: Command) -> Vec<String> {
+    BINDINGS.iter()
+        .filter(|b| b.command == command)
+        .map(|b| key_label(b.code, b.modifiers))
+        .collect()
+}
+
+fn area_title(area: Option<FocusArea>) -> &'static str {
+    match area {
+        None => "Anywhere",
+        Some(FocusArea::SearchBar) => "Search bar",
+        Some(FocusArea::NoteList) => "Note list",
+        Some(FocusArea::Editor) => "Editor",
+    }
+}
+
+/// Help text generated from the binding table, one section per focus area
+pub fn help_lines() -> Vec<String> {
+    let mut lines = Vec::new();
+    let areas = [None, Some(FocusArea::SearchBar), Some(FocusArea::NoteList), Some(FocusArea::Editor)];
+
+    for area in areas {
+        // Collect keys per command, keeping table order
+        let mut entries: Vec<(Vec<String>, &str)> = Vec::new();
+        for binding in BINDINGS.iter().filter(|b| b.area == area) {
+            let label = key_label(binding.code, binding.modifiers);
+            let description = binding.command.description();
+            match entries.iter_mut().find(|(_, d)| *d == description) {
+                Some((keys, _)) => keys.push(label),
+                None => entries.push((vec![label], description)),
+            }
+        }
+
+        let mut rows: Vec<(String, &str)> = entries.into_iter()
+            .map(|(keys, description)| (keys.join(" / "), description))
+            .collect();
+        if let Some(area) = area {
+            rows.extend(TEXT_INPUT_KEYS.iter()
+                .filter(|(a, _, _)| *a == area)
+                .map(|(_, keys, description)| (keys.to_string(), *description)));
+        }
+
+        if rows.is_empty() {
+            continue;
+        }
+
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(format!("── {} ──", area_title(area)));
+        for (keys, description) in rows {
+            lines.push(format!("  {:<26} {}", keys, description));
+        }
+    }
+
+    // Leader chords, listed as "Ctrl+G n"
+    let leader_key = keys_for(Command::Leader).join(" / ");
+    lines.push(String::new());
+    lines.push("── Leader ──".to_string());
+    for binding in LEADER_BINDINGS {
+        let keys = format!("{} {}", leader_key, binding.key);
+        lines.push(format!("  {:<26} {} ({})", keys, binding.command.description(), binding.group));
+    }
+
+    lines
+}
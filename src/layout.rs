@@ -0,0 +1,215 @@
+// Screen layout - where every widget sits for a terminal size
+//
+// Rendering and mouse handling both work from the same Layout, so a click is
+// tested against exactly what was drawn instead of re-deriving rows and columns.
+// `hit` names the widget under a cell, and `Widget::focus` says which focus area a
+// click there moves to: any click inside a pane - header, body or footer - focuses it.
+//
+// Presets pick the panes: list + editor, or list + editor + a codes pane on the right.
+// Zen mode gives the whole screen to the editor; it applies while the editor has the
+// focus, so leaving the editor brings the other panes back.
+
+use serde::{Serialize, Deserialize};
+
+use crate::ui::UI;
+use crate::FocusArea;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    #[default]
+    ListEditor,
+    ListEditorCodes,
+}
+
+impl Preset {
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::ListEditor => "list + editor",
+            Preset::ListEditorCodes => "list + editor + codes",
+        }
+    }
+
+    pub fn next(self) -> Preset {
+        match self {
+            Preset::ListEditor => Preset::ListEditorCodes,
+            Preset::ListEditorCodes => Preset::ListEditor,
+        }
+    }
+}
+
+// The codes pane takes a fifth of the screen within these bounds, and is left out
+// when the editor would get narrower than EDITOR_MIN
+const CODES_MIN: u16 = 20;
+const CODES_MAX: u16 = 32;
+const EDITOR_MIN: u16 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Rect {
+    pub fn contains(&self, column: u16, row: u16) -> bool {
+        column >= self.x && column < self.x + self.width && row >= self.y && row < self.y + self.height
+    }
+
+    /// The rows between a pane's header row and its footer row
+    pub fn body(&self) -> Rect {
+        Rect { y: self.y + 1, height: self.height.saturating_sub(2), ..*self }
+    }
+
+    fn first_row(&self) -> u16 {
+        self.y
+    }
+
+    fn last_row(&self) -> u16 {
+        self.y + self.height.saturating_sub(1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Widget {
+    Header,
+    SearchBar,
+    Chips,
+    ListHeader,
+    List,
+    ListFooter,
+    Divider,
+    EditorHeader,
+    Editor,
+    EditorFooter,
+    Codes,
+    StatusBar,
+}
+
+impl Widget {
+    /// Focus area a click on this widget moves to (None: focus stays where it is)
+    pub fn focus(self) -> Option<FocusArea> {
+        match self {
+            Widget::SearchBar => Some(FocusArea::SearchBar),
+            Widget::ListHeader | Widget::List | Widget::ListFooter => Some(FocusArea::NoteList),
+            Widget::EditorHeader | Widget::Editor | Widget::EditorFooter => Some(FocusArea::Editor),
+            Widget::Header | Widget::Chips | Widget::Divider | Widget::Codes | Widget::StatusBar => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Layout {
+    pub header: Rect,
+    pub search: Rect,
+    pub chips: Rect,
+    pub list: Rect,     // Whole pane: header row, entries, footer row
+    pub divider: Rect,
+    pub editor: Rect,   // Whole pane: header row, text, footer row
+    pub codes_divider: Rect,
+    pub codes: Rect,    // Empty unless the preset has a codes pane
+    pub status: Rect,
+    grab_slop: u16,     // Extra columns either side of the divider that still grab it
+}
+
+impl Layout {
+    pub fn compute(width: u16, height: u16, split_ratio: f32, large_ui: bool, preset: Preset, zen: bool) -> Layout {
+        // Large UI widens the grab zone around the divider as well
+        let grab_slop = if large_ui { 2 } else { 1 };
+        if zen {
+            let editor = Rect { x: 0, y: 0, width, height };
+            return Layout {
+                header: Rect::default(),
+                search: Rect::default(),
+                chips: Rect::default(),
+                list: Rect::default(),
+                divider: Rect::default(),
+                editor,
+                codes_divider: Rect::default(),
+                codes: Rect::default(),
+                status: Rect::default(),
+                grab_slop,
+            };
+        }
+
+        let split_x = (width as f32 * split_ratio) as u16;
+        let divider_width = UI::divider_width(large_ui);
+        let row = |y: u16| Rect { x: 0, y, width, height: 1 };
+        // Panes fill the space between the chips row and the status bar
+        let pane_height = height.saturating_sub(UI::PANES_TOP + 1);
+        let pane = |x: u16, width: u16| Rect { x, y: UI::PANES_TOP, width, height: pane_height };
+
+        let editor_x = split_x + divider_width;
+        let mut editor_width = width.saturating_sub(editor_x);
+        let mut codes_width = 0;
+        if preset == Preset::ListEditorCodes {
+            let wanted = (width / 5).clamp(CODES_MIN, CODES_MAX);
+            if editor_width >= wanted + 1 + EDITOR_MIN {
+                codes_width = wanted;
+                editor_width -= wanted + 1;
+            }
+        }
+        let (codes_divider, codes) = if codes_width > 0 {
+            (pane(editor_x + editor_width, 1), pane(editor_x + editor_width + 1, codes_width))
+        } else {
+            (Rect::default(), Rect::default())
+        };
+
+        Layout {
+            header: row(0),
+            search: row(1),
+            chips: row(UI::PANES_TOP - 1),
+            list: pane(0, split_x),
+            divider: pane(split_x, divider_width),
+            editor: pane(editor_x, editor_width),
+            codes_divider,
+            codes,
+            status: row(height.saturating_sub(1)),
+            grab_slop,
+        }
+    }
+
+    pub fn has_codes(&self) -> bool {
+        self.codes.width > 0
+    }
+
+    /// Widget under a cell
+    pub fn hit(&self, column: u16, row: u16) -> Option<Widget> {
+        let grab = Rect {
+            x: self.divider.x.saturating_sub(self.grab_slop),
+            width: if self.divider.width > 0 { self.divider.width + 2 * self.grab_slop } else { 0 },
+            ..self.divider
+        };
+        // The divider's grab zone overlaps the panes' edges and wins over them
+        let pane = |rect: &Rect, header, body, footer| {
+            if row == rect.first_row() {
+                header
+            } else if row == rect.last_row() {
+                footer
+            } else {
+                body
+            }
+        };
+
+        if self.header.contains(column, row) {
+            Some(Widget::Header)
+        } else if self.search.contains(column, row) {
+            Some(Widget::SearchBar)
+        } else if self.chips.contains(column, row) {
+            Some(Widget::Chips)
+        } else if self.status.contains(column, row) {
+            Some(Widget::StatusBar)
+        } else if grab.contains(column, row) {
+            Some(Widget::Divider)
+        } else if self.list.contains(column, row) {
+            Some(pane(&self.list, Widget::ListHeader, Widget::List, Widget::ListFooter))
+        } else if self.editor.contains(column, row) {
+            Some(pane(&self.editor, Widget::EditorHeader, Widget::Editor, Widget::EditorFooter))
+        } else if self.codes_divider.contains(column, row) || self.codes.contains(column, row) {
+            Some(Widget::Codes)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,95 @@
+// Link check - broken [[links]], orphan notes and unreferenced attachments, vault-wide
+//
+// Ctrl+G ! lists three kinds of loose ends: [[links]] whose target no note has as its
+// title (or Zettelkasten ID), notes that link nowhere and that no note links to, and
+// files in attachments/ that no note references. Each has its quick fix: Enter creates
+// the missing note, opens the orphan or opens the attachment; `o` opens the note holding
+// a broken link; `d` deletes an unreferenced attachment (pressed twice, it can't be
+// undone). Attachments referenced from notes in the trash count as used, so restoring a
+// note never brings back a dangling reference.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::attachments::{self, Attachment};
+use crate::note_store::Note;
+
+#[derive(Debug, Clone)]
+pub enum Problem {
+    BrokenLink { note_id: String, note_title: String, target: String },
+    Orphan { note_id: String, title: String },
+    UnusedAttachment(Attachment),
+}
+
+impl Problem {
+    pub fn line(&self) -> String {
+        match self {
+            Problem::BrokenLink { note_title, target, .. } => {
+                format!("broken      [[{}]] in {}", target, note_title)
+            }
+            Problem::Orphan { title, .. } => format!("orphan      {}", title),
+            Problem::UnusedAttachment(a) => {
+                format!("unused      {} ({})", a.name, attachments::format_size(a.size()))
+            }
+        }
+    }
+}
+
+/// Everything loose in `notes` and the attachments directory: broken links first (by
+/// note), then orphans (by title), then unused attachments (by name)
+pub fn check(
+    notes: &[Note],
+    zettel_ids: &HashMap<String, String>,
+    notes_dir: &Path,
+    trashed: &[String],
+) -> Vec<Problem> {
+    let mut by_target: HashMap<&str, &str> = notes.iter().map(|n| (n.title.as_str(), n.id.as_str())).collect();
+    by_target.extend(zettel_ids.iter().map(|(note_id, id)| (id.as_str(), note_id.as_str())));
+
+    let mut problems = Vec::new();
+    let mut linked: HashSet<&str> = HashSet::new();  // Notes with a link in or out
+    for note in notes {
+        let mut broken: Vec<String> = Vec::new();
+        for target in crate::App::extract_wiki_links(&note.content) {
+            match by_target.get(target.as_str()) {
+                Some(&id) if id != note.id => {
+                    linked.insert(id);
+                    linked.insert(&note.id);
+                }
+                Some(_) => {}
+                None if !broken.contains(&target) => broken.push(target),
+                None => {}
+            }
+        }
+        problems.extend(broken.into_iter().map(|target| Problem::BrokenLink {
+            note_id: note.id.clone(),
+            note_title: note.title.clone(),
+            target,
+        }));
+    }
+
+    let mut orphans: Vec<&Note> = notes.iter().filter(|n| !linked.contains(n.id.as_str())).collect();
+    orphans.sort_by(|a, b| a.title.to_lowercase().cmp(&b.title.to_lowercase()));
+    problems.extend(orphans.into_iter().map(|n| Problem::Orphan { note_id: n.id.clone(), title: n.title.clone() }));
+
+    let referenced: HashSet<String> = notes.iter().map(|n| n.content.as_str())
+        .chain(trashed.iter().map(String::as_str))
+        .flat_map(attachments::referenced_ids)
+        .collect();
+    problems.extend(attachments::all(notes_dir).into_iter()
+        .filter(|a| !referenced.contains(&a.id))
+        .map(Problem::UnusedAttachment));
+    problems
+}
+
+/// "2 broken links, 5 orphans, 1 unused attachment"
+pub fn summary(problems: &[Problem]) -> String {
+    let count = |f: fn(&Problem) -> bool| problems.iter().filter(|p| f(p)).count();
+    let plural = |n: usize, word: &str| if n == 1 { format!("1 {}", word) } else { format!("{} {}s", n, word) };
+    format!(
+        "{}, {}, {}",
+        plural(count(|p| matches!(p, Problem::BrokenLink { .. })), "broken link"),
+        plural(count(|p| matches!(p, Problem::Orphan { .. })), "orphan"),
+        plural(count(|p| matches!(p, Problem::UnusedAttachment(_))), "unused attachment"),
+    )
+}
@@ -0,0 +1,393 @@
+// Screen lock - the UI blanks itself after a spell of inactivity and asks for a passphrase
+//
+//   snyfter3 passphrase            set or change it (asks for the current one first)
+//   snyfter3 passphrase --remove   turn the lock off
+//
+// With a passphrase set, the databases are encrypted at rest with SQLCipher: every run
+// asks for the passphrase first (or takes $SNYFTER3_PASSPHRASE, for the daemon and
+// scripts), and the key derived from it opens notes.db, codes.db, search.db and the
+// backups. The app then locks its screen again after config "auto_lock_minutes"
+// without a key press (Ctrl+G L locks at once). lock.json holds only a salted
+// PBKDF2-HMAC-SHA256 hash to check the passphrase against, and a second salt for the
+// key, which is never stored.
+//
+// Setting, changing or removing the passphrase re-encrypts every database (close the
+// app and the daemon first): each is copied under the new key, the copies replace the
+// originals, and lock.json is written last - if any step fails, the originals go back and
+// lock.json is left as it was. The salts come from the OS random source. The markdown mirror, exports and attachments are files
+// meant to be opened elsewhere and stay as they are.
+
+use anyhow::{anyhow, bail, Context, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use serde::{Serialize, Deserialize};
+use rusqlite::params;
+use sha2::{Digest, Sha256};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::{backup, db};
+
+const ITERATIONS: u32 = 200_000;
+const BLOCK: usize = 64;  // SHA-256 block size, for HMAC
+const PASSPHRASE_VAR: &str = "SNYFTER3_PASSPHRASE";
+
+/// The databases' key for this run, once `unlock` has checked the passphrase
+static FILE_KEY: OnceLock<String> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Passphrase {
+    salt: String,  // Hex
+    iterations: u32,
+    hash: String,  // Hex PBKDF2 output
+    #[serde(default)]
+    key_salt: Option<String>,  // Hex; None for a lock set before the databases were encrypted
+}
+
+impl Passphrase {
+    fn path(notes_dir: &Path) -> PathBuf {
+        notes_dir.join("lock.json")
+    }
+
+    /// The stored passphrase, if the lock is turned on
+    pub fn load(notes_dir: &Path) -> Option<Self> {
+        let json = std::fs::read_to_string(Self::path(notes_dir)).ok()?;
+        match serde_json::from_str(&json) {
+            Ok(passphrase) => Some(passphrase),
+            Err(e) => {
+                tracing::warn!(error = %e, "lock.json unreadable, lock disabled");
+                None
+            }
+        }
+    }
+
+    pub fn new(passphrase: &str) -> Result<Self> {
+        let salt = random_salt()?;
+        let hash = hex(&pbkdf2(passphrase.as_bytes(), salt.as_bytes(), ITERATIONS));
+        Ok(Passphrase { salt, iterations: ITERATIONS, hash, key_salt: Some(random_salt()?) })
+    }
+
+    /// Written to a temporary file and renamed over lock.json, so it is never half there
+    pub fn save(&self, notes_dir: &Path) -> Result<()> {
+        let path = Self::path(notes_dir);
+        let partial = path.with_extension("json.partial");
+        std::fs::write(&partial, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&partial, &path)?;
+        Ok(())
+    }
+
+    pub fn remove(notes_dir: &Path) -> Result<()> {
+        std::fs::remove_file(Self::path(notes_dir))?;
+        Ok(())
+    }
+
+    pub fn verify(&self, passphrase: &str) -> bool {
+        let hash = hex(&pbkdf2(passphrase.as_bytes(), self.salt.as_bytes(), self.iterations));
+        // Compare every byte so the time taken doesn't say how much matched
+        hash.len() == self.hash.len()
+            && hash.bytes().zip(self.hash.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+
+    /// The hex SQLCipher key `passphrase` gives, if the databases are encrypted
+    fn file_key(&self, passphrase: &str) -> Option<String> {
+        let salt = self.key_salt.as_ref()?;
+        Some(hex(&pbkdf2(passphrase.as_bytes(), salt.as_bytes(), self.iterations)))
+    }
+}
+
+/// The key `db::open` applies to every database (None when there's no passphrase)
+pub fn file_key() -> Option<&'static str> {
+    FILE_KEY.get().map(String::as_str)
+}
+
+/// Ask for the passphrase, if one is set, and keep its key for the databases opened
+/// after this. A lock set before encryption existed encrypts them now.
+pub fn unlock(notes_dir: &Path) -> Result<()> {
+    let Some(mut lock) = Passphrase::load(notes_dir) else {
+        return Ok(());
+    };
+    let passphrase = match std::env::var(PASSPHRASE_VAR) {
+        Ok(passphrase) => passphrase,
+        Err(_) => read_hidden("Passphrase: ")?,
+    };
+    if !lock.verify(&passphrase) {
+        bail!("wrong passphrase");
+    }
+    if lock.key_salt.is_none() {
+        let encrypted = Passphrase::new(&passphrase)?;
+        let staged = rekey_all(notes_dir, None, encrypted.file_key(&passphrase).as_deref())?;
+        swap_in(staged, || encrypted.save(notes_dir))?;
+        tracing::info!("databases encrypted under the existing passphrase");
+        lock = encrypted;
+    }
+    if let Some(key) = lock.file_key(&passphrase) {
+        let _ = FILE_KEY.set(key);
+    }
+    Ok(())
+}
+
+/// Copy every database re-encrypted from `from` to `to` (None: unencrypted), next to the
+/// original, as (copy, original); `swap_in` then puts the copies in place. The search
+/// index is dropped instead (it is rebuilt on the next start).
+fn rekey_all(notes_dir: &Path, from: Option<&str>, to: Option<&str>) -> Result<Vec<(PathBuf, PathBuf)>> {
+    remove_with_sidecars(&notes_dir.join("search.db"))?;
+    let mut dirs = vec![notes_dir.to_path_buf()];
+    dirs.extend(backup::list(notes_dir)?.into_iter().map(|b| b.path));
+    let mut staged = Vec::new();
+    for dir in dirs {
+        for name in backup::DATABASES {
+            let path = dir.join(name);
+            if !path.exists() {
+                continue;
+            }
+            match rekey(&path, from, to).with_context(|| format!("re-encrypting {}", path.display())) {
+                Ok(copy) => staged.push((copy, path)),
+                Err(e) => {
+                    for (copy, _) in &staged {
+                        let _ = remove_with_sidecars(copy);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+    }
+    Ok(staged)
+}
+
+fn rekey(path: &Path, from: Option<&str>, to: Option<&str>) -> Result<PathBuf> {
+    let copy = path.with_extension("rekey");
+    remove_with_sidecars(&copy)?;
+    let conn = db::open_keyed(path, from)?;
+    let version: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    let key = to.map(|key| format!("x'{}'", key)).unwrap_or_default();
+    conn.execute("ATTACH DATABASE ?1 AS rekeyed KEY ?2", params![copy.to_string_lossy(), key])?;
+    conn.query_row("SELECT sqlcipher_export('rekeyed')", [], |_| Ok(()))?;
+    // The export copies tables and rows but not the version the migrations go by
+    conn.execute_batch(&format!("PRAGMA rekeyed.user_version = {}; DETACH DATABASE rekeyed;", version))?;
+    Ok(copy)
+}
+
+/// Put the staged copies in place of the originals, then `commit` (lock.json). The
+/// originals are kept aside until then and put back if any of it fails, so the databases
+/// and lock.json always agree.
+fn swap_in(staged: Vec<(PathBuf, PathBuf)>, commit: impl FnOnce() -> Result<()>) -> Result<()> {
+    let mut aside: Vec<(&Path, PathBuf)> = Vec::new();
+    let mut result = Ok(());
+    for (copy, path) in &staged {
+        // A write-ahead log left behind means something still has the database open
+        if sidecar(path, "-wal").exists() {
+            result = Err(anyhow!("{} is in use - close the app and the daemon first", path.display()));
+            break;
+        }
+        let kept = path.with_extension("previous");
+        let step = remove_with_sidecars(&kept)
+            .and_then(|_| std::fs::rename(path, &kept).map_err(Into::into))
+            .and_then(|_| {
+                aside.push((path.as_path(), kept));
+                std::fs::rename(copy, path).map_err(Into::into)
+            });
+        if let Err(e) = step {
+            result = Err(e.context(format!("replacing {}", path.display())));
+            break;
+        }
+    }
+    if result.is_ok() {
+        result = commit();
+    }
+
+    match result {
+        Ok(()) => {
+            for (_, kept) in aside {
+                remove_with_sidecars(&kept)?;
+            }
+            Ok(())
+        }
+        Err(e) => {
+            for (path, kept) in aside.into_iter().rev() {
+                if let Err(back) = std::fs::rename(&kept, path) {
+                    tracing::error!(path = %path.display(), kept = %kept.display(), error = %back, "could not put a database back");
+                }
+            }
+            for (copy, _) in &staged {
+                let _ = remove_with_sidecars(copy);
+            }
+            Err(e)
+        }
+    }
+}
+
+fn sidecar(path: &Path, suffix: &str) -> PathBuf {
+    PathBuf::from(format!("{}{}", path.display(), suffix))
+}
+
+fn remove_with_sidecars(path: &Path) -> Result<()> {
+    for suffix in ["", "-wal", "-shm"] {
+        let file = sidecar(path, suffix);
+        if file.exists() {
+            std::fs::remove_file(&file)?;
+        }
+    }
+    Ok(())
+}
+
+/// 32 bytes from the OS random source, in hex
+fn random_salt() -> Result<String> {
+    let mut salt = [0u8; 32];
+    getrandom::getrandom(&mut salt).map_err(|e| anyhow!("no random source for the salt: {}", e))?;
+    Ok(hex(&salt))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// HMAC-SHA256 with the padded key already hashed into both halves, so PBKDF2's many
+/// rounds under one key only hash their messages
+struct Hmac {
+    inner: Sha256,
+    outer: Sha256,
+}
+
+impl Hmac {
+    fn new(key: &[u8]) -> Self {
+        let mut block = [0u8; BLOCK];
+        if key.len() > BLOCK {
+            block[..32].copy_from_slice(&Sha256::digest(key));
+        } else {
+            block[..key.len()].copy_from_slice(key);
+        }
+        let pad = |byte: u8| block.map(|k| k ^ byte);
+        Hmac { inner: Sha256::new().chain_update(pad(0x36)), outer: Sha256::new().chain_update(pad(0x5c)) }
+    }
+
+    fn mac(&self, message: &[u8]) -> [u8; 32] {
+        let inner = self.inner.clone().chain_update(message).finalize();
+        self.outer.clone().chain_update(inner).finalize().into()
+    }
+}
+
+#[cfg(test)]
+fn hmac(key: &[u8], message: &[u8]) -> [u8; 32] {
+    Hmac::new(key).mac(message)
+}
+
+/// PBKDF2-HMAC-SHA256 with a single 32-byte output block
+fn pbkdf2(passphrase: &[u8], salt: &[u8], iterations: u32) -> [u8; 32] {
+    let prf = Hmac::new(passphrase);
+    let mut u = prf.mac(&[salt, &1u32.to_be_bytes()[..]].concat());
+    let mut out = u;
+    for _ in 1..iterations {
+        u = prf.mac(&u);
+        for (o, x) in out.iter_mut().zip(u) {
+            *o ^= x;
+        }
+    }
+    out
+}
+
+/// `snyfter3 passphrase [--remove]`
+pub fn set_passphrase(notes_dir: &Path, remove: bool) -> Result<()> {
+    let mut current_key = None;
+    if let Some(current) = Passphrase::load(notes_dir) {
+        let typed = read_hidden("Current passphrase: ")?;
+        if !current.verify(&typed) {
+            bail!("wrong passphrase");
+        }
+        current_key = current.file_key(&typed);
+    } else if remove {
+        println!("No passphrase is set");
+        return Ok(());
+    }
+
+    if remove {
+        let staged = rekey_all(notes_dir, current_key.as_deref(), None)?;
+        swap_in(staged, || Passphrase::remove(notes_dir))?;
+        println!("Passphrase removed; the databases are decrypted and the app no longer locks");
+        return Ok(());
+    }
+    let passphrase = read_hidden("New passphrase: ")?;
+    if passphrase.is_empty() {
+        bail!("the passphrase can't be empty (use --remove to turn the lock off)");
+    }
+    if read_hidden("Repeat it: ")? != passphrase {
+        bail!("the passphrases don't match");
+    }
+    let lock = Passphrase::new(&passphrase)?;
+    let staged = rekey_all(notes_dir, current_key.as_deref(), lock.file_key(&passphrase).as_deref())?;
+    swap_in(staged, || lock.save(notes_dir))?;
+    println!("Passphrase set; the databases are encrypted, every start asks for it and the app locks itself when idle");
+    Ok(())
+}
+
+/// Read a line from the terminal without echoing it
+fn read_hidden(prompt: &str) -> Result<String> {
+    print!("{}", prompt);
+    io::stdout().flush()?;
+    terminal::enable_raw_mode()?;
+    let result = read_raw_line();
+    terminal::disable_raw_mode()?;
+    println!();
+    result
+}
+
+fn read_raw_line() -> Result<String> {
+    let mut line = String::new();
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter => return Ok(line),
+            KeyCode::Backspace => {
+                line.pop();
+            }
+            KeyCode::Esc => bail!("cancelled"),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => bail!("cancelled"),
+            KeyCode::Char(c) => line.push(c),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 4231 test cases 1, 2 and 6 (a key longer than the block is hashed first)
+    #[test]
+    fn hmac_sha256_vectors() {
+        assert_eq!(hex(&hmac(&[0x0b; 20], b"Hi There")),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7");
+        assert_eq!(hex(&hmac(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+        assert_eq!(hex(&hmac(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First")),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54");
+    }
+
+    // The published PBKDF2-HMAC-SHA256 vectors for "password" / "salt", 32 bytes out
+    #[test]
+    fn pbkdf2_sha256_vectors() {
+        assert_eq!(hex(&pbkdf2(b"password", b"salt", 1)),
+            "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b");
+        assert_eq!(hex(&pbkdf2(b"password", b"salt", 2)),
+            "ae4d0c95af6b46d32d0adff928f06dd02a303f8ef3c251dfd6e2d85a95474c43");
+        assert_eq!(hex(&pbkdf2(b"password", b"salt", 4096)),
+            "c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a");
+    }
+
+    #[test]
+    fn verify_and_key() {
+        let lock = Passphrase { iterations: 2, ..Passphrase::new("correct horse").unwrap() };
+        let lock = Passphrase { hash: hex(&pbkdf2(b"correct horse", lock.salt.as_bytes(), 2)), ..lock };
+        assert!(lock.verify("correct horse"));
+        assert!(!lock.verify("correct hors"));
+        let key = lock.file_key("correct horse").unwrap();
+        assert_eq!(key.len(), 64);
+        assert_ne!(key, lock.hash);
+        assert_eq!(Passphrase { key_salt: None, ..lock }.file_key("correct horse"), None);
+    }
+}
@@ -0,0 +1,45 @@
+// Diagnostics via tracing, written to a per-user log file (never the terminal the UI draws on)
+//
+// Level comes from --log-level, then SNYFTER3_LOG, then "info". Both accept
+// EnvFilter directives, e.g. "debug" or "snyfter3::note_store=trace,info".
+
+use anyhow::Result;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing_subscriber::EnvFilter;
+
+pub const LOG_ENV: &str = "SNYFTER3_LOG";
+const DEFAULT_LEVEL: &str = "info";
+
+/// Per-user log location: the platform cache dir (~/.cache/snyfter3 on Linux,
+/// ~/Library/Caches/snyfter3 on macOS)
+pub fn log_path() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("snyfter3")
+        .join("snyfter3.log")
+}
+
+/// Install the global subscriber; returns the log file path
+pub fn init(level: Option<&str>) -> Result<PathBuf> {
+    let directives = level
+        .map(str::to_string)
+        .or_else(|| std::env::var(LOG_ENV).ok())
+        .unwrap_or_else(|| DEFAULT_LEVEL.to_string());
+    let filter = EnvFilter::try_new(&directives)
+        .map_err(|e| anyhow::anyhow!("invalid log level '{}': {}", directives, e))?;
+
+    let path = log_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = std::fs::OpenOptions::new().create(true).append(true).open(&path)?;
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(Mutex::new(file))
+        .with_ansi(false)
+        .init();
+
+    Ok(path)
+}
@@ -1,47 +1,341 @@
 // Snyfter3 - Fast note-taking and qualitative data analysis app
 // NValt-like interface with QualCoder-style highlighting
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind, MouseButton, EnableMouseCapture, DisableMouseCapture},
-    execute, terminal,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind, MouseButton},
+    terminal,
 };
+use std::collections::{HashMap, HashSet};
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 use chrono;
-use nucleo::{Matcher, Utf32Str, pattern::{Pattern, CaseMatching, Normalization}};
+use serde::{Serialize, Deserialize};
 
 mod note_store;
+mod db;  // SQLite connection pragmas and schema migrations
+mod backup;  // Timed and pre-import snapshots of the databases, and restore
 mod ui;
 mod qda_codes;  // Qualitative data analysis codes/tags
 mod editor;
 mod edit_renderer;
 mod block_selection;
 mod syntax;
+mod theme;
+mod config;  // User configuration (config.json)
+mod keymap;
+mod overlay;
+mod front_matter;
+mod export;  // HTML export styled by front matter classes
+mod daemon;  // Socket listener for global hotkeys
+mod journal;  // Unsaved revisions when autosave fails
+mod drafts;  // Editor text copied to draft.json, recovered after a crash
+mod clipboard;
+mod usage;  // Opt-in local usage counters
+mod mirror;  // Markdown mirror files and drift checking
+mod toast;
+mod messages;  // Status bar messages with levels, expiry and a log
+mod query;  // Search operators (memo:, quote:)
+mod input;  // Terminal event source (basic / kitty keyboard protocol)
+mod logging;  // tracing subscriber writing to the per-user log file
+mod import;  // Model predictions -> suggested code applications
+mod audit;  // Append-only log of coding decisions
+mod undo;  // Per-note undo history, persisted across sessions
+mod templates;  // Built-in QDA note templates
+mod session;  // Last note, search and layout, restored on startup
+mod chips;  // Quick filter chips under the search bar
+mod highlights;  // Read-time highlights, kept apart from codes
+mod cli;  // Headless list/search/show/export subcommands
+mod csv_import;  // One note per CSV row, with column mapping
+mod reading;  // Reading mode layout (wrapping, line spacing)
+mod daily;  // Daily notes and the calendar picker
+mod tasks;  // Checkbox tasks collected from all notes
+mod outline;  // Heading tree of the open note
+mod stats;  // Word counts, reading time and the note info popup
+mod analyzer;  // Stemming and CJK segmentation for the search index
+mod search_index;  // Word index of all notes in search.db
+mod attachments;  // Files stored with the notes and referenced as attachment://
+mod images;  // Inline images in reading mode (kitty / iTerm2 graphics)
+mod pdf_import;  // PDF text as a note, with page markers
+mod transcript;  // Timestamped transcripts and subtitle import
+mod bibtex;  // BibTeX import, literature notes and @citekey completion
+mod lock;  // Idle screen lock with a passphrase
+mod sync;  // Push / pull notes to WebDAV or S3, with conflicted copies
+mod merge;  // Merging marked notes and splitting one at the cursor
+mod duplicates;  // Near-duplicate detection by title and word shingles
+mod titles;  // Titles derived from the first line (config auto_title)
+mod layout;  // Widget positions shared by rendering and mouse hit-testing
+mod preview;  // Read-only note preview popup for the list
+mod recode;  // Merging codes and recoding segments in bulk
+mod codebook;  // Codebook files: JSON, CSV and REFI-QDA .qdc
+mod annotations;  // Sticky-note comments on text ranges, apart from codes
+mod project_journal;  // Dated analysis memos about the project, not any one note
+mod report;  // Coded segment retrievals as CSV, HTML or DOCX
+mod words;  // Word frequencies and keyword-in-context concordances
+mod text_units;  // Sentence and paragraph spans around an offset
+mod autocode;  // Applying a code to every match of a phrase or regex
+mod matrix;  // Codes x cases matrix of segment counts
+mod coverage;  // How much of each note is coded
+mod anchors;  // Coded segments anchored to line numbers
+mod coding_history;  // Undo / redo of coding actions, apart from text undo
+mod zettel;  // Timestamp IDs for notes, usable in [[links]]
+mod tags;  // Renaming, merging and removing #tags in every note
+mod similarity;  // TF-IDF cosine similarity between notes
+mod tag_suggest;  // Tags offered for the open note from similar notes and its words
+mod link_check;  // Broken links, orphan notes and unused attachments
+mod recent;  // Recently opened notes, the quick switcher and back / forward
+mod jumps;  // Places the cursor jumped from, for Alt+O / Alt+I
+mod search_history;  // Past search queries, for Up / Down and Ctrl+R in the search bar
+mod date_filter;  // created: / updated: dates and periods in search queries
 
 use note_store::{Note, NoteStore};
-use ui::UI;
-use qda_codes::CodeManager;
+use ui::{ResizeDebounce, UI};
+use layout::{Layout, Preset, Widget};
+use preview::Preview;
+use recode::Recode;
+use autocode::AutoCode;
+use coding_history::{CodingHistory, Merged, Step};
+use qda_codes::{CodeApplication, CodeManager};
 use editor::TextEditor;
+use config::Config;
+use theme::Theme;
+use keymap::Command;
+use overlay::{Overlay, OverlayAction};
+use journal::{Journal, JournalEntry};
+use drafts::{Draft, Drafts};
+use usage::Usage;
+use toast::Toast;
+use messages::Messages;
+use query::SearchQuery;
+use input::Input;
+use audit::AuditEntry;
+use undo::UndoHistory;
+use highlights::{Highlight, HighlightStore};
+use annotations::{Annotation, AnnotationEdit, AnnotationStore};
+use project_journal::{JournalEdit, ProjectJournal};
+use search_index::{IndexJob, SearchIndex};
+use images::ImageProtocol;
+use lock::Passphrase;
+use templates::{TemplateContext, DAILY, TEMPLATES};
+use session::Session;
+use front_matter::{parse_front_matter, FieldEdit, FrontMatter};
+use tags::TagChange;
+use similarity::Similarity;
+use tag_suggest::Suggestion;
+use recent::NoteHistory;
+use jumps::{Jump, JumpList};
+use search_history::SearchHistory;
+use date_filter::DateFilter;
+use chips::FilterChips;
+
+// Event loop steps failing in a row before the session gives up
+const MAX_STEP_FAILURES: u32 = 3;
+
+// Pause after a save before related notes and tag suggestions are worked out again
+const SIMILARITY_DELAY: Duration = Duration::from_secs(2);
+// Notes listed as related to the open one
+const RELATED_NOTES: usize = 10;
+
+// How long the "moved to trash" toast stays up
+const UNDO_TOAST: Duration = Duration::from_secs(5);
+// Trashed notes are purged for good after this many days
+const TRASH_RETENTION_DAYS: i64 = 30;
+// Clicks on the same cell closer together than this count as double/triple clicks
+const MULTI_CLICK: Duration = Duration::from_millis(400);
+// Quick actions on an editor selection (Alt+A), with the key that runs each
+const SELECTION_ACTIONS: &[(char, &str)] = &[
+    ('c', "Copy"),
+    ('o', "Code..."),
+    ('n', "Annotate"),
+    ('h', "Highlight"),
+    ('/', "Search all notes for it"),
+    ('l', "New note from it, linked back here"),
+    ('x', "Extract into a new note, leaving a link to it"),
+];
 
 #[derive(Parser, Debug)]
 #[command(name = "snyfter3", author, version, about)]
 struct Args {
     /// Directory to store notes (defaults to ~/Documents/Snyfter3)
-    #[arg(long)]
+    #[arg(long, global = true)]
     notes_dir: Option<PathBuf>,
 
     /// Open with a search query
     #[arg(short, long)]
     search: Option<String>,
+
+    /// Log level or filter directives (overrides SNYFTER3_LOG, default "info")
+    #[arg(long, global = true)]
+    log_level: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Listen on a local socket for capture/search requests from OS hotkeys
+    Daemon,
+    /// Import model predictions (JSONL) as machine-suggested code applications
+    ImportSuggestions {
+        /// JSONL file with document_id, start, end and labels per line
+        file: PathBuf,
+    },
+    /// Create a note without opening the UI and print its id
+    Add {
+        title: String,
+        /// Note text, or "-" to read it from stdin
+        #[arg(long)]
+        body: Option<String>,
+        /// Tag to add (repeatable)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+    },
+    /// Create one note per CSV row (e.g. open-ended survey responses)
+    ImportCsv {
+        file: PathBuf,
+        /// Column for the note title (header name or 1-based number)
+        #[arg(long)]
+        title: Option<String>,
+        /// Column(s) with the note text (repeatable); without any, asks interactively
+        #[arg(long)]
+        content: Vec<String>,
+        /// Column(s) whose values become tags (repeatable)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// Column(s) kept as case attributes in the front matter (repeatable)
+        #[arg(long = "attr")]
+        attributes: Vec<String>,
+        /// Count the notes without creating them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// List notes, newest first
+    List {
+        #[arg(long)]
+        json: bool,
+    },
+    /// Search notes with the search bar's query syntax, best match first
+    Search {
+        query: String,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print a note (by id or unique id prefix)
+    Show {
+        id: String,
+        /// Include metadata and applied codes
+        #[arg(long)]
+        json: bool,
+    },
+    /// Export notes (all, or the given ids) to the exports directory
+    Export {
+        #[arg(long, value_enum, default_value = "md")]
+        format: cli::ExportFormat,
+        ids: Vec<String>,
+        /// Print the written paths as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Rebuild the search index (search.db) from the note store
+    Reindex,
+    /// Attach files to a note (by id or unique id prefix)
+    Attach {
+        id: String,
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Create one note per PDF from its text (needs pdftotext), keeping page markers
+    ImportPdf {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Create one note per transcript (.srt, .vtt, or text with [hh:mm:ss] timestamps)
+    ImportTranscript {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Create a literature note per BibTeX entry that doesn't have one yet
+    ImportBib {
+        file: PathBuf,
+    },
+    /// Push local changes to the sync store and pull the other devices' changes
+    Sync,
+    /// Set or change the passphrase that locks the screen when idle
+    Passphrase {
+        /// Turn the lock off
+        #[arg(long)]
+        remove: bool,
+    },
+    /// Write the codebook (codes, hierarchy, colors) to <notes_dir>/exports/ or a given file
+    ExportCodebook {
+        #[arg(long, value_enum, default_value = "json")]
+        format: codebook::Format,
+        /// Where to write it instead of exports/codebook.<format>
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Add the codes of a codebook file (.json, .csv or REFI-QDA .qdc) that aren't here yet
+    ImportCodebook {
+        file: PathBuf,
+        /// Format, when the file's extension doesn't say
+        #[arg(long, value_enum)]
+        format: Option<codebook::Format>,
+    },
+    /// Write the segments of some codes (all, without --code) in some notes to a report
+    Report {
+        /// Code name or id (repeatable); codes below it are included
+        #[arg(long = "code")]
+        codes: Vec<String>,
+        /// Note id or unique prefix (repeatable); without any, every note
+        #[arg(long = "note")]
+        notes: Vec<String>,
+        #[arg(long, value_enum, default_value = "html")]
+        format: report::Format,
+        /// Where to write it instead of exports/report.<format>
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Add a project journal entry, or print the journal newest first
+    Journal {
+        /// Entry text, or "-" to read it from stdin
+        text: Option<String>,
+        /// Print only the entries containing every word
+        #[arg(long)]
+        search: Option<String>,
+        /// Write the journal to exports/journal.md instead of printing it
+        #[arg(long)]
+        export: bool,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Snapshot notes.db and codes.db into backups/
+    Backup,
+    /// Restore a backup by name; without one, list them
+    Restore {
+        name: Option<String>,
+    },
+}
+
+impl Commands {
+    /// Commands that write many notes or codes at once get a backup first
+    fn backup_reason(&self) -> Option<&'static str> {
+        match self {
+            Commands::ImportSuggestions { .. } | Commands::ImportPdf { .. }
+            | Commands::ImportTranscript { .. } | Commands::ImportBib { .. }
+            | Commands::ImportCodebook { .. } => Some("import"),
+            Commands::ImportCsv { dry_run, .. } => (!dry_run).then_some("import"),
+            _ => None,
+        }
+    }
 }
 
 // Single unified mode - no mode switching needed
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum FocusArea {
     SearchBar,
     NoteList,
@@ -52,8 +346,23 @@ pub struct App {
     // Core components
     notes: NoteStore,
     codes: CodeManager,
+    coding_history: CodingHistory,  // Alt+Z / Alt+Y over applying, removing and recoding codes
+    highlights: HighlightStore,
+    annotations: AnnotationStore,
+    project_journal: ProjectJournal,
+    search_index: SearchIndex,
+    index_job: Option<IndexJob>,  // Startup indexing still under way
     ui: UI,
     editor: TextEditor,
+    journal: Journal,
+    drafts: Drafts,
+    recovered_drafts: Vec<Draft>,  // Offered once the journal prompt is out of the way
+    usage: Usage,
+
+    // Configuration
+    notes_dir: PathBuf,
+    config: Config,
+    theme: Theme,  // Resolved from config.theme
 
     // All notes and filtering
     all_notes: Vec<Note>,  // All notes to search through
@@ -61,18 +370,114 @@ pub struct App {
     // Current state
     selected_note: Option<Note>,
     selected_note_index: usize,
+    list_scroll: usize,  // First note shown in the list (scrolls independently of the selection)
     search_query: String,
+    chips: FilterChips,  // Quick filters combined with the search query
     filtered_notes: Vec<Note>,  // Notes matching current search
     focus_area: FocusArea,  // Which area currently has focus
+    reading: bool,  // Reading mode: the editor pane shows the note wrapped and read-only
+    reading_scroll: usize,  // First display row shown in reading mode
+    image_protocol: ImageProtocol,  // Resolved from config.inline_images
+
+    // Screen lock
+    passphrase: Option<Passphrase>,  // None: the lock is off
+    locked: bool,
+    lock_input: String,  // Passphrase typed so far on the lock screen
+    lock_failed: bool,  // The last attempt was wrong
+    last_activity: Instant,  // Last key, click or paste, for the idle lock
+    next_backup: Option<Instant>,  // When the next timed backup is due; None: timed backups off
+    overlay: Option<Overlay>,  // Modal panel (help etc.) drawn above everything
+    mirror_report: Vec<mirror::MirrorEntry>,  // Rows of the mirror sync overlay
+    review_queue: Vec<CodeApplication>,  // Rows of the suggestion review overlay
+    review_relabel: Option<usize>,  // Queue index being relabeled in the code picker
+    template_pending: Option<usize>,  // Template waiting for its code in the code picker
+    highlight_list: Vec<Highlight>,  // Rows of the highlights overlay
+    highlight_converting: Option<usize>,  // Highlight being converted in the code picker
+    annotation_list: Vec<Annotation>,  // Rows of the annotations overlay
+    journal_list: Vec<project_journal::Entry>,  // Rows of the project journal overlay
+    journal_viewing: Option<usize>,  // Journal entry shown in full
+    recode: Option<Recode>,  // Code merge / recode being set up in the pickers
+    code_moving: Option<String>,  // Code being moved in the code tree pickers
+    code_move_choices: Vec<Option<String>>,  // Parent picker rows (None: top level)
+    dragging_code: Option<String>,  // Code picked up in the codes pane, dropped on its new parent
+    codebook_choices: Vec<codebook::Choice>,  // Lines of the codebook overlay
+    report_codes: Vec<String>,  // Code ids as the report's code checklist lists them
+    report_chosen: HashSet<String>,  // Codes ticked for the report
+    word_notes: Vec<String>,  // Notes the word frequencies were counted over
+    word_counts: Vec<words::WordCount>,  // Rows of the word frequency overlay
+    word_hits: Vec<words::Hit>,  // Rows of the concordance overlay
+    word_selected: usize,  // Word whose concordance is open, to return to
+    matrix: Option<matrix::Matrix>,  // Codes x cases matrix while its overlay is open
+    matrix_case: usize,  // Case column selected in the matrix
+    matrix_code: usize,  // Code row whose cell is being drilled into
+    coverage: Vec<coverage::Entry>,  // Rows of the coverage dashboard while it is open
+    coverage_all: bool,  // The dashboard lists every note, not just those below the target
+    calendar_day: chrono::NaiveDate,  // Day selected in the daily note calendar
+    task_list: Vec<tasks::Task>,  // Rows of the tasks overlay
+    task_filter: tasks::TaskFilter,
+    outline: Vec<outline::Heading>,  // Headings of the open note while the outline is up
+    outline_collapsed: HashSet<usize>,  // Lines of collapsed headings
+    outline_note: Option<String>,  // Note the collapsed headings belong to
+    attachment_list: Vec<attachments::Attachment>,  // Rows of the attachments overlay
+    citation_list: Vec<String>,  // Citekeys in the completion overlay
+    duplicate_pairs: Vec<duplicates::Pair>,  // Rows of the duplicates overlay
+    duplicate_open: usize,  // Pair shown side by side
+    citation_span: (usize, usize),  // Chars replaced by the chosen citation (the @ up to the cursor)
+    outline_rows: Vec<usize>,  // Heading index of each outline overlay row
+    toast: Option<Toast>,  // Transient notice above the status bar
+    trashed: Vec<String>,  // Ids trashed this session, newest last (Ctrl+U restores)
+    marked: Vec<String>,  // Note ids marked in the list for merging, in the order marked
+    read_only: HashSet<String>,  // Notes whose text is locked against editing
+    zettel_ids: HashMap<String, String>,  // Zettelkasten ID by note id (config zettel_ids)
+    title_edit: Option<String>,  // Title being typed in the editor header (Some while renaming)
+    annotation_edit: Option<AnnotationEdit>,  // Comment being typed in the editor header
+    field_edit: Option<FieldEdit>,  // Front matter field being typed in the editor header
+    tag_list: Vec<String>,  // Tags in the order the tag manager lists them
+    tag_rename: Option<(String, String)>,  // Tag being renamed and the new name typed so far
+    tag_change: Option<TagChange>,  // Rename / merge / removal waiting on its preview
+    similarity: Similarity,  // Word counts for note similarity, synced as it is used
+    tag_suggestions: Option<(String, Vec<Suggestion>)>,  // Tags offered for a note, by note id
+    related: Option<(String, Vec<(String, f64)>)>,  // Notes most like a note (id, cosine), by note id
+    similarity_due: Option<Instant>,  // When to work out related notes and suggestions after a save
+    dismissed_tags: HashMap<String, HashSet<String>>,  // Suggestions turned down this session, by note id
+    link_problems: Vec<link_check::Problem>,  // Rows of the link check overlay
+    attachment_delete: Option<String>,  // Unused attachment waiting on a second `d`
+    note_history: NoteHistory,  // Recently opened notes and the back / forward steps
+    switcher: Option<String>,  // Filter typed into the quick switcher (Some while it is open)
+    switcher_rows: Vec<String>,  // Note ids the quick switcher lists
+    search_history: SearchHistory,
+    history_filter: Option<String>,  // Filter typed into the search history (Some while it is open)
+    history_rows: Vec<String>,  // Queries the search history lists
+    date_range: Option<String>,  // Range typed into the date filter (Some while it is open)
+    date_rows: Vec<String>,  // The created: / updated: term of each date filter row ("" for any date)
+    jump_list: JumpList,
+    last_location: Option<Jump>,  // Where the editor cursor was after the previous event
+    jump_navigating: bool,  // The current event is Alt+O / Alt+I, which mustn't record a jump
+    journal_edit: Option<JournalEdit>,  // Project journal entry being typed in the editor header
+    autocode_pattern: Option<String>,  // Auto-coding pattern being typed in the editor header
+    autocode: Option<AutoCode>,  // Auto-coding run between the code picker and the preview
+    last_inbox_check: Instant,  // Last poll of the daemon inbox
 
     // Display state
     needs_redraw: bool,
+    resize: ResizeDebounce,  // Holds rendering back while a window drag is still resizing
     exit_requested: bool,
-    status_message: String,
+    messages: Messages,  // Status bar message and the log behind it (Ctrl+G g)
 
     // Split pane position (percentage of screen width for note list)
     split_ratio: f32,  // 0.2 = 20% width for list, 80% for editor
+    layout_preset: Preset,
+    zen: bool,  // Editor alone on screen while it has the focus
+    preview: Option<Preview>,  // Note waiting to be (or being) previewed over the editor
+    code_hover: Option<(u16, u16, Vec<(String, bool)>)>,  // Codes under the mouse in the editor, and where it is
+    segment_filter: Option<String>,  // Code (with its subcodes) that next / previous segment keeps to
+    hidden_codes: HashSet<String>,   // Codes whose segments aren't tinted in the editor (Ctrl+G Y)
     dragging_divider: bool,  // Whether we're currently dragging the divider
+    external_edit_requested: bool,  // Open the note in $EDITOR once the current event is handled
+
+    // Mouse multi-click detection
+    last_click: Option<(Instant, u16, u16)>,  // Time and cell of the previous left click
+    click_count: u8,  // 1 = single, 2 = double, 3 = triple
 
     // Cursor acceleration
     last_arrow_key: Option<KeyCode>,
@@ -119,30 +524,160 @@ fn update_arrow_acceleration(app: &mut App, key: KeyCode) -> usize {
 
 impl App {
     pub fn new(notes_dir: PathBuf) -> Result<Self> {
-        let notes = NoteStore::new(&notes_dir)?;
-        let codes = CodeManager::new(&notes_dir)?;
+        let mut notes = NoteStore::new(&notes_dir)?;
+        notes.purge_trash(chrono::Utc::now() - chrono::Duration::days(TRASH_RETENTION_DAYS))?;
+        if let Err(e) = undo::prune(&notes_dir) {
+            tracing::warn!(error = %e, "pruning undo history failed");
+        }
+        let mut codes = CodeManager::new(&notes_dir)?;
+        let highlights = HighlightStore::new(&notes_dir)?;
+        let annotations = AnnotationStore::new(&notes_dir)?;
+        let read_only = notes.read_only_ids()?;
+        let zettel_ids = notes.zettel_ids()?;
+        let project_journal = ProjectJournal::new(&notes_dir)?;
         let ui = UI::new()?;
+        let journal = Journal::open(&notes_dir);
+        let config = Config::load(&notes_dir)?;
+        codes.set_inherit_colors(config.inherit_code_colors);
+        let theme = config.active_theme();
+        clipboard::set_backend(config.clipboard);
+        let usage = Usage::open(&notes_dir, config.usage_insights);
+        let image_protocol = config.inline_images.detect();
+        let passphrase = Passphrase::load(&notes_dir);
+        let next_backup = backup::next_due(&notes_dir, config.backup_interval_hours).map(|wait| Instant::now() + wait);
 
         // Load initial notes
         let all_notes = notes.get_all_notes()?;
         let filtered_notes = all_notes.clone();
+        // The UI comes up at once; new and changed notes are indexed between events
+        let search_index = SearchIndex::open(&notes_dir, config.search)?;
+        let index_job = search_index.start_sync(&all_notes);
 
         Ok(App {
             notes,
             codes,
+            coding_history: CodingHistory::default(),
+            highlights,
+            annotations,
+            project_journal,
+            search_index,
+            index_job,
             ui,
             editor: TextEditor::new(),
+            journal,
+            drafts: Drafts::open(&notes_dir),
+            recovered_drafts: Vec::new(),
+            usage,
+            notes_dir,
+            config,
+            theme,
             all_notes,
             selected_note: None,
             selected_note_index: 0,
+            list_scroll: 0,
             search_query: String::new(),
+            chips: FilterChips::default(),
             filtered_notes,
             focus_area: FocusArea::NoteList,  // Start with note list focused
+            reading: false,
+            reading_scroll: 0,
+            image_protocol,
+            locked: false,  // The passphrase was asked for before the databases opened
+            passphrase,
+            lock_input: String::new(),
+            lock_failed: false,
+            last_activity: Instant::now(),
+            next_backup,
+            overlay: None,
+            mirror_report: Vec::new(),
+            review_queue: Vec::new(),
+            review_relabel: None,
+            template_pending: None,
+            highlight_list: Vec::new(),
+            highlight_converting: None,
+            annotation_list: Vec::new(),
+            journal_list: Vec::new(),
+            journal_viewing: None,
+            recode: None,
+            code_moving: None,
+            code_move_choices: Vec::new(),
+            dragging_code: None,
+            codebook_choices: Vec::new(),
+            report_codes: Vec::new(),
+            report_chosen: HashSet::new(),
+            word_notes: Vec::new(),
+            word_counts: Vec::new(),
+            word_hits: Vec::new(),
+            word_selected: 0,
+            matrix: None,
+            matrix_case: 0,
+            matrix_code: 0,
+            coverage: Vec::new(),
+            coverage_all: false,
+            calendar_day: daily::today(),
+            task_list: Vec::new(),
+            task_filter: tasks::TaskFilter::default(),
+            outline: Vec::new(),
+            outline_collapsed: HashSet::new(),
+            outline_note: None,
+            attachment_list: Vec::new(),
+            citation_list: Vec::new(),
+            duplicate_pairs: Vec::new(),
+            duplicate_open: 0,
+            citation_span: (0, 0),
+            outline_rows: Vec::new(),
+            toast: None,
+            trashed: Vec::new(),
+            marked: Vec::new(),
+            read_only,
+            zettel_ids,
+            title_edit: None,
+            annotation_edit: None,
+            field_edit: None,
+            tag_list: Vec::new(),
+            tag_rename: None,
+            tag_change: None,
+            similarity: Similarity::default(),
+            tag_suggestions: None,
+            related: None,
+            similarity_due: None,
+            dismissed_tags: HashMap::new(),
+            link_problems: Vec::new(),
+            attachment_delete: None,
+            note_history: NoteHistory::default(),
+            switcher: None,
+            switcher_rows: Vec::new(),
+            search_history: SearchHistory::default(),
+            history_filter: None,
+            history_rows: Vec::new(),
+            date_range: None,
+            date_rows: Vec::new(),
+            jump_list: JumpList::default(),
+            last_location: None,
+            jump_navigating: false,
+            journal_edit: None,
+            autocode_pattern: None,
+            autocode: None,
+            last_inbox_check: Instant::now(),
             needs_redraw: true,
+            resize: ResizeDebounce::new(),
             exit_requested: false,
-            status_message: String::from("Welcome to Snyfter3!"),
+            messages: {
+                let mut messages = Messages::default();
+                messages.info("Welcome to Snyfter3!");
+                messages
+            },
             split_ratio: 0.2,  // Start with narrower notes list
+            layout_preset: Preset::default(),
+            zen: false,
+            preview: None,
+            code_hover: None,
+            segment_filter: None,
+            hidden_codes: HashSet::new(),
             dragging_divider: false,
+            external_edit_requested: false,
+            last_click: None,
+            click_count: 0,
             last_arrow_key: None,
             arrow_key_count: 0,
             last_arrow_time: None,
@@ -151,74 +686,241 @@ impl App {
 
     pub async fn run(&mut self) -> Result<()> {
         // Setup terminal
-        enable_raw_mode()?;
-        let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+        let mut input = Input::start(self.config.input)?;
 
-        // Main event loop
+        // Main event loop. A step that fails is reported and the session carries on;
+        // only failures step after step (a terminal that is gone, a database that can't
+        // be read) end it.
+        let mut failures = 0;
         while !self.exit_requested {
-            // Render
-            if self.needs_redraw {
-                self.render()?;
-                self.needs_redraw = false;
-            }
-
-            // Handle input
-            if event::poll(std::time::Duration::from_millis(50))? {
-                match event::read()? {
-                    Event::Key(key) => self.handle_key(key).await?,
-                    Event::Mouse(mouse) => self.handle_mouse(mouse)?,
-                    Event::Resize(_, _) => self.needs_redraw = true,
-                    _ => {}
+            match self.step(&mut input).await {
+                Ok(()) => failures = 0,
+                Err(e) => {
+                    failures += 1;
+                    tracing::error!(error = %format!("{:#}", e), failures, "event loop step failed");
+                    if failures >= MAX_STEP_FAILURES {
+                        self.shut_down(&mut input);
+                        return Err(e.context("giving up after repeated errors"));
+                    }
+                    self.messages.error(format!("{:#}", e));
+                    self.needs_redraw = true;
                 }
             }
         }
 
-        // Cleanup
-        execute!(stdout, DisableMouseCapture, LeaveAlternateScreen)?;
-        disable_raw_mode()?;
+        // A clean exit: nothing left to recover (an error exit keeps the draft)
+        if let Err(e) = self.drafts.clear() {
+            tracing::warn!(error = %e, "removing draft failed");
+        }
+        self.shut_down(&mut input);
+        Ok(())
+    }
+
+    /// One pass of the event loop: background work, a redraw if needed, one input event
+    async fn step(&mut self, input: &mut Input) -> Result<()> {
+        // Pick up captures/searches sent through the daemon (held back while locked)
+        if !self.locked && self.last_inbox_check.elapsed() >= Duration::from_millis(500) {
+            self.last_inbox_check = Instant::now();
+            self.process_daemon_inbox()?;
+        }
+
+        // Retry journaled saves once their backoff has elapsed
+        if self.journal.retry_due() {
+            self.retry_journal()?;
+        }
+
+        if self.toast.as_ref().is_some_and(|t| t.is_expired()) {
+            self.toast = None;
+            self.needs_redraw = true;
+        }
+
+        if self.messages.expire() {
+            self.needs_redraw = true;
+        }
+
+        // The preview pops up once the dwell time is over
+        if self.preview.as_mut().is_some_and(|p| p.became_ready()) {
+            self.needs_redraw = true;
+        }
+
+        if self.drafts.due() {
+            self.write_draft();
+        }
+
+        if self.similarity_due.is_some_and(|at| Instant::now() >= at) && !self.locked {
+            self.similarity_due = None;
+            self.refresh_related();
+            if self.config.tag_suggestions {
+                self.suggest_tags();
+            }
+            self.needs_redraw = true;
+        }
+
+        // The codes pane's related notes follow the note being edited
+        if self.layout_preset == Preset::ListEditorCodes && self.related_stale() {
+            self.refresh_related();
+            self.needs_redraw = true;
+        }
+
+        if !self.recovered_drafts.is_empty() && self.overlay.is_none() && !self.locked {
+            self.offer_draft_recovery();
+        }
+
+        if self.resize.settled() {
+            self.needs_redraw = true;
+        }
+
+        if self.idle_lock_due() {
+            self.lock_screen()?;
+        }
+
+        if self.next_backup.is_some_and(|at| Instant::now() >= at) {
+            self.timed_backup();
+        }
+
+        if self.index_job.is_some() {
+            self.step_index_job();
+        }
+
+        // Render
+        if self.needs_redraw && !self.resize.pending() {
+            self.render()?;
+            self.needs_redraw = false;
+        }
+
+        // Handle input
+        // Startup indexing goes on between events, so only look for input while it runs
+        let wait = if self.index_job.is_some() { Duration::ZERO } else { Duration::from_millis(50) };
+        let event = input.next_event(wait)?;
+        if matches!(event, Some(Event::Key(_) | Event::Mouse(_) | Event::Paste(_))) {
+            self.last_activity = Instant::now();
+        }
+        // Arrows and paging move the cursor a long way without it being a jump
+        let moving = matches!(&event, Some(Event::Key(key))
+            if matches!(key.code, KeyCode::Up | KeyCode::Down | KeyCode::PageUp | KeyCode::PageDown));
+        let handled = match event {
+            Some(Event::Key(key)) => self.handle_key(key).await,
+            Some(Event::Mouse(mouse)) => self.handle_mouse(mouse),
+            Some(Event::Paste(text)) => self.handle_paste(&text),
+            Some(Event::Resize(_, _)) => {
+                self.resize.record();
+                Ok(())
+            }
+            _ => Ok(()),
+        };
+        // A failed command is reported and logged rather than ending the session
+        if let Err(e) = handled {
+            tracing::error!(error = %e, "command failed");
+            self.messages.error(format!("{:#}", e));
+            self.needs_redraw = true;
+        }
+        self.record_visit();
+        self.track_jumps(moving);
+        if let Some(notice) = clipboard::take_notice() {
+            self.messages.warn(notice);
+            self.needs_redraw = true;
+        }
 
+        // $EDITOR needs the real terminal, so the TUI steps aside while it runs
+        if self.external_edit_requested {
+            self.external_edit_requested = false;
+            input.stop()?;
+            let result = self.edit_externally();
+            input = Input::start(self.config.input)?;
+            if let Err(e) = result {
+                tracing::warn!(error = %e, "external editor failed");
+                self.messages.error(format!("External editor failed: {}", e));
+            }
+            self.needs_redraw = true;
+        }
         Ok(())
     }
 
+    /// Save what the next session needs and give the terminal back
+    fn shut_down(&mut self, input: &mut Input) {
+        self.persist_undo_history();
+        if let Err(e) = self.session().save(&self.notes_dir) {
+            tracing::warn!(error = %e, "saving session state failed");
+        }
+        let _ = self.usage.save();
+        if let Err(e) = input.stop() {
+            tracing::warn!(error = %e, "restoring the terminal failed");
+            input::restore_terminal();
+        }
+    }
+
+    #[tracing::instrument(level = "trace", skip_all)]
     fn render(&mut self) -> Result<()> {
         // Extract necessary references before calling render
         let mut ui = std::mem::replace(&mut self.ui, UI::new()?);
-        ui.render(self)?;
+        let result = ui.render(self);
+        // Put the UI back even when drawing failed, so the next frame keeps its state
         self.ui = ui;
+        result?;
         io::stdout().flush()?;
         Ok(())
     }
 
+    #[tracing::instrument(level = "debug", skip(self), fields(focus = ?self.focus_area))]
     async fn handle_key(&mut self, key: KeyEvent) -> Result<()> {
-        // Handle Ctrl+Q to quit from anywhere
-        if key.code == KeyCode::Char('q') && key.modifiers.contains(KeyModifiers::CONTROL) {
-            self.exit_requested = true;
+        if self.locked {
+            self.handle_lock_key(key)?;
+            self.needs_redraw = true;
+            return Ok(());
+        }
+        self.code_hover = None;
+
+        // So does the title field while renaming
+        if self.title_edit.is_some() {
+            self.handle_title_key(key)?;
+            self.needs_redraw = true;
+            return Ok(());
+        }
+        if self.annotation_edit.is_some() {
+            self.handle_annotation_key(key)?;
+            self.needs_redraw = true;
+            return Ok(());
+        }
+        if self.journal_edit.is_some() {
+            self.handle_journal_key(key)?;
+            self.needs_redraw = true;
+            return Ok(());
+        }
+        if self.field_edit.is_some() {
+            self.handle_field_key(key)?;
+            self.needs_redraw = true;
+            return Ok(());
+        }
+        if self.tag_rename.is_some() {
+            self.handle_tag_rename_key(key)?;
+            self.needs_redraw = true;
+            return Ok(());
+        }
+        if self.autocode_pattern.is_some() {
+            self.handle_autocode_key(key)?;
+            self.needs_redraw = true;
             return Ok(());
         }
 
-        // Handle Ctrl+L or Cmd+L to focus search bar (NValt style)
-        if key.code == KeyCode::Char('l') && key.modifiers.contains(KeyModifiers::CONTROL) {
-            self.focus_area = FocusArea::SearchBar;
+        // An open overlay captures all input until dismissed
+        if self.overlay.is_some() {
+            self.handle_overlay_key(key)?;
             self.needs_redraw = true;
             return Ok(());
         }
 
-        // Tab cycles through focus areas
-        if key.code == KeyCode::Tab {
-            self.focus_area = match self.focus_area {
-                FocusArea::SearchBar => FocusArea::NoteList,
-                FocusArea::NoteList => FocusArea::Editor,
-                FocusArea::Editor => FocusArea::SearchBar,
-            };
+        // Keymap commands take priority over text input
+        if let Some(command) = keymap::lookup(self.focus_area, &key) {
+            self.run_command(command)?;
             self.needs_redraw = true;
             return Ok(());
         }
 
-        // Handle input based on focus area
+        // Everything else goes to the focused text input
         match self.focus_area {
             FocusArea::SearchBar => self.handle_search_key(key).await?,
-            FocusArea::NoteList => self.handle_list_key(key).await?,
+            FocusArea::NoteList => {}
+            FocusArea::Editor if self.reading => self.handle_reading_key(key),
             FocusArea::Editor => self.handle_editor_key(key).await?,
         }
 
@@ -226,175 +928,4884 @@ impl App {
         Ok(())
     }
 
-    async fn handle_search_key(&mut self, key: KeyEvent) -> Result<()> {
-        match key.code {
-            KeyCode::Esc | KeyCode::Enter | KeyCode::Down => {
-                // Exit search focus, move to notes list
-                self.focus_area = FocusArea::NoteList;
-                if !self.filtered_notes.is_empty() && key.code == KeyCode::Enter {
-                    self.selected_note_index = 0;
-                    self.load_selected_note()?;
-                }
-            }
-            KeyCode::Backspace => {
-                self.search_query.pop();
-                self.update_search()?;
+    fn run_command(&mut self, command: Command) -> Result<()> {
+        tracing::debug!(?command, "command");
+        self.usage.record_command(command);
+        match command {
+            Command::Quit => self.exit_requested = true,
+            Command::FocusSearch => {
+                // Don't clear - allow incremental search
+                self.focus_area = FocusArea::SearchBar;
             }
-            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.search_query.push(c);
-                self.update_search()?;
+            Command::CycleFocus => {
+                self.focus_area = match self.focus_area {
+                    FocusArea::SearchBar => FocusArea::NoteList,
+                    FocusArea::NoteList => FocusArea::Editor,
+                    FocusArea::Editor => FocusArea::SearchBar,
+                };
             }
-            _ => {}
-        }
-        Ok(())
-    }
-
-    async fn handle_list_key(&mut self, key: KeyEvent) -> Result<()> {
-        match key.code {
-            KeyCode::Char('/') | KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                // Focus search bar
-                self.focus_area = FocusArea::SearchBar;
-                // Don't clear - allow incremental search
+            Command::CycleTheme => self.cycle_theme()?,
+            Command::ShowHelp => {
+                self.overlay = Some(Overlay::new("Keyboard shortcuts", keymap::help_lines()));
             }
-            KeyCode::Enter | KeyCode::Right => {
-                // Move focus to editor
+            Command::NewNote => self.create_new_note()?,
+            Command::OpenNote => {
+                // In preview mode the highlighted note is only opened now
+                if self.config.list_preview && self.highlighted_is_unopened() {
+                    self.load_selected_note()?;
+                }
+                self.preview = None;
                 if self.selected_note.is_some() {
                     self.focus_area = FocusArea::Editor;
                 }
             }
-            KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                // Create new note
-                self.create_new_note()?;
-            }
-            KeyCode::Up | KeyCode::Char('k') => {
+            Command::DeleteNote => self.delete_selected_note()?,
+            Command::UndoDelete => self.undo_delete()?,
+            Command::FollowLink => self.follow_wiki_link()?,
+            Command::SelectPrev => {
                 if self.selected_note_index > 0 {
                     self.selected_note_index -= 1;
-                    self.load_selected_note()?;
+                    self.follow_list_selection()?;
                 }
             }
-            KeyCode::Down | KeyCode::Char('j') => {
+            Command::SelectNext => {
                 if self.selected_note_index < self.filtered_notes.len().saturating_sub(1) {
                     self.selected_note_index += 1;
-                    self.load_selected_note()?;
+                    self.follow_list_selection()?;
                 }
             }
-            KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                // Follow wiki link under cursor
-                self.follow_wiki_link()?;
+            Command::PageUp | Command::PageDown => {
+                if !self.filtered_notes.is_empty() {
+                    let page = UI::visible_note_count(self, self.list_scroll, self.list_rows());
+                    let last = self.filtered_notes.len() - 1;
+                    self.selected_note_index = if command == Command::PageUp {
+                        self.selected_note_index.saturating_sub(page)
+                    } else {
+                        (self.selected_note_index + page).min(last)
+                    };
+                    self.follow_list_selection()?;
+                }
             }
-            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                // Delete selected note
-                self.delete_selected_note()?;
+            Command::SelectFirst | Command::SelectLast => {
+                if !self.filtered_notes.is_empty() {
+                    self.selected_note_index = if command == Command::SelectFirst {
+                        0
+                    } else {
+                        self.filtered_notes.len() - 1
+                    };
+                    self.follow_list_selection()?;
+                }
             }
-            // Resize panes with keyboard
-            KeyCode::Char(',') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                // Make notes pane smaller
+            Command::ShrinkList => {
                 self.split_ratio = (self.split_ratio - 0.05).max(0.1);  // Allow down to 10%
-                self.needs_redraw = true;
             }
-            KeyCode::Char('.') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                // Make notes pane larger
+            Command::GrowList => {
                 self.split_ratio = (self.split_ratio + 0.05).min(0.7);
-                self.needs_redraw = true;
             }
-            _ => {}
+            Command::CycleDensity => {
+                self.config.list_density = self.config.list_density.next();
+                self.config.save(&self.notes_dir)?;
+                self.messages.info(format!("List view: {}", self.config.list_density.name()));
+            }
+            Command::BackToList => {
+                // Leaving the editor ends zen mode rather than only hiding it
+                self.zen = false;
+                self.focus_area = FocusArea::NoteList;
+            }
+            Command::ExportHtml => self.export_selected_note()?,
+            Command::ExportSegments => self.export_segments()?,
+            Command::ShowUsage => self.show_usage_report(),
+            Command::PasteHistory => self.show_paste_history(),
+            Command::MirrorCheck => self.show_mirror_report()?,
+            Command::ReviewSuggestions => self.show_review_queue(None)?,
+            Command::MergeCode => {
+                let all = self.all_notes.iter().map(|n| n.id.clone()).collect();
+                self.start_recode(Recode::new(recode::Kind::Merge, all));
+            }
+            Command::MoveCode => self.show_move_code_picker(),
+            Command::CaseMatrix => self.show_case_matrix()?,
+            Command::CodingCoverage => self.show_coverage()?,
+            Command::ToggleReadOnly => self.toggle_read_only()?,
+            Command::AutoCode => {
+                if self.codes.get_all_codes().is_empty() {
+                    self.messages.info("No codes yet - create a code to auto-code with");
+                } else {
+                    self.autocode_pattern = Some(String::new());
+                }
+            }
+            Command::Codebook => self.show_codebook_menu(),
+            Command::Report => self.show_report_codes(None),
+            Command::WordFrequency => self.show_word_frequencies(),
+            Command::ToggleCodeLabels => {
+                self.config.code_labels = !self.config.code_labels;
+                self.config.save(&self.notes_dir)?;
+                self.messages.info(if self.config.code_labels {
+                    "Coded segments are labelled ⟨code⟩ at the end of the row they start on"
+                } else {
+                    "Code labels hidden (hover coded text to see its codes)"
+                });
+            }
+            Command::ToggleCodeColors => {
+                self.config.inherit_code_colors = !self.config.inherit_code_colors;
+                self.codes.set_inherit_colors(self.config.inherit_code_colors);
+                self.config.save(&self.notes_dir)?;
+                self.messages.info(if self.config.inherit_code_colors {
+                    "Child codes are drawn in shades of their parent's color"
+                } else {
+                    "Every code is drawn in its own color"
+                });
+            }
+            Command::RecodeSegments => {
+                let listed = self.filtered_notes.iter().map(|n| n.id.clone()).collect();
+                self.start_recode(Recode::new(recode::Kind::Recode, listed));
+            }
+            Command::NewFromTemplate => self.show_template_picker(),
+            Command::HighlightSelection => self.highlight_selection()?,
+            Command::Annotate => self.annotate()?,
+            Command::SelectionActions => self.show_selection_actions(),
+            Command::ExtractNote => self.extract_note()?,
+            Command::Metadata => self.show_metadata(None),
+            Command::TagManager => self.show_tags(None),
+            Command::TagSuggestions => self.show_tag_suggestions(None),
+            Command::RelatedNotes => self.show_related(),
+            Command::LinkCheck => self.show_link_check(None)?,
+            Command::QuickSwitcher => {
+                self.switcher = Some(String::new());
+                self.show_switcher();
+            }
+            Command::SearchHistory => {
+                self.history_filter = Some(String::new());
+                self.show_search_history();
+            }
+            Command::DateFilter => {
+                self.date_range = Some(String::new());
+                self.show_date_filter();
+            }
+            Command::NoteBack => self.step_note_history(false)?,
+            Command::NoteForward => self.step_note_history(true)?,
+            Command::JumpBack => self.step_jump(false)?,
+            Command::JumpForward => self.step_jump(true)?,
+            Command::SelectSentence => self.select_unit(text_units::Unit::Sentence),
+            Command::SelectParagraph => self.select_unit(text_units::Unit::Paragraph),
+            Command::TodayNote => self.open_daily(daily::today())?,
+            Command::PrevDailyNote => self.step_daily(false)?,
+            Command::NextDailyNote => self.step_daily(true)?,
+            Command::DailyCalendar => {
+                self.calendar_day = self.open_daily_date().unwrap_or_else(daily::today);
+                self.show_calendar();
+            }
+            Command::ShowTasks => self.show_tasks(None),
+            Command::ToggleCheckbox => self.toggle_checkbox_at_cursor()?,
+            Command::ShowOutline => self.show_outline(),
+            Command::NextSegment => self.step_segment(true),
+            Command::PrevSegment => self.step_segment(false),
+            Command::SegmentFilter => self.show_segment_filter(),
+            Command::CodeVisibility => self.show_code_visibility(None),
+            Command::UndoCoding => self.step_coding_history(false)?,
+            Command::RedoCoding => self.step_coding_history(true)?,
+            Command::RemoveCode => self.remove_code_at_cursor()?,
+            Command::NextHeading => self.step_heading(true),
+            Command::PrevHeading => self.step_heading(false),
+            Command::NoteInfo => self.show_note_info()?,
+            Command::Reindex => self.reindex()?,
+            Command::ShowAttachments => self.show_attachments(),
+            Command::CompleteCitation => self.complete_citation()?,
+            Command::SyncNow => self.sync_now()?,
+            Command::ToggleMark => self.toggle_mark(),
+            Command::MergeNotes => self.merge_marked_notes()?,
+            Command::SplitNote => self.split_note()?,
+            Command::FindDuplicates => self.find_duplicates(None)?,
+            Command::RenameNote => match &self.selected_note {
+                Some(note) => self.title_edit = Some(note.title.clone()),
+                None => self.messages.info("No note selected"),
+            },
+            Command::ShowMessages => self.show_messages(),
+            Command::CycleLayout => {
+                self.layout_preset = self.layout_preset.next();
+                self.messages.info(format!("Layout: {}", self.layout_preset.name()));
+            }
+            Command::ToggleZen => self.toggle_zen(),
+            Command::TogglePreview => {
+                self.config.list_preview = !self.config.list_preview;
+                self.config.save(&self.notes_dir)?;
+                self.preview = None;
+                self.messages.info(if self.config.list_preview {
+                    "Preview mode: the list previews notes, Enter opens one"
+                } else {
+                    "Preview mode off: the list opens notes as you move"
+                });
+            }
+            Command::LockScreen => {
+                if self.passphrase.is_some() {
+                    self.lock_screen()?;
+                } else {
+                    self.messages.info("No passphrase set (run snyfter3 passphrase to set one)");
+                }
+            }
+            Command::ToggleReading => self.toggle_reading(),
+            Command::CycleLineSpacing => {
+                // Keep the same note line at the top while the rows around it move
+                let top = self.reading_rows().iter().skip(self.reading_scroll).find_map(|r| r.line);
+                self.config.line_spacing = self.config.line_spacing.next();
+                self.config.save(&self.notes_dir)?;
+                if let Some(line) = top {
+                    self.reading_scroll = self.reading_rows().iter().position(|r| r.line == Some(line)).unwrap_or(0);
+                }
+                self.messages.info(format!("Reading mode: {}", self.config.line_spacing.name()));
+            }
+            Command::EditExternally => {
+                if self.selected_note.is_some() {
+                    self.external_edit_requested = true;
+                } else {
+                    self.messages.info("No note selected");
+                }
+            }
+            Command::ShowHighlights => self.show_highlights(None)?,
+            Command::ShowAnnotations => self.show_annotations(None)?,
+            Command::ShowJournal => self.show_journal(None)?,
+            Command::ChipNotebook => self.toggle_chip(0)?,
+            Command::ChipTag => self.toggle_chip(1)?,
+            Command::ChipHasCode => self.toggle_chip(2)?,
+            Command::ChipHasTask => self.toggle_chip(3)?,
+            Command::ChipDate => self.toggle_chip(4)?,
+            Command::Leader => {
+                self.overlay = Some(Overlay::new("Leader - press a key (Esc: cancel)", keymap::leader_lines())
+                    .with_action(OverlayAction::Leader));
+            }
+            Command::ToggleLargeUi => {
+                self.config.large_ui = !self.config.large_ui;
+                self.config.save(&self.notes_dir)?;
+                self.messages.info(format!("Large UI {}", if self.config.large_ui { "on" } else { "off" }));
+            }
         }
         Ok(())
     }
 
-    async fn handle_editor_key(&mut self, key: KeyEvent) -> Result<()> {
-        match key.code {
-            KeyCode::Esc => {
-                // Go back to note list
-                self.focus_area = FocusArea::NoteList;
+    fn handle_overlay_key(&mut self, key: KeyEvent) -> Result<()> {
+        let (_, term_height) = terminal::size()?;
+        let visible = Overlay::visible_rows(term_height);
+
+        // Leader popup: any key closes it; a bound key runs its command
+        if let Some(OverlayAction::Leader) = self.overlay.as_ref().and_then(|o| o.action) {
+            self.overlay = None;
+            match keymap::lookup_leader(&key) {
+                Some(command) => self.run_command(command)?,
+                None if key.code != KeyCode::Esc => {
+                    self.messages.info(format!("No leader binding for {}", keymap::key_label(key.code, key.modifiers)));
+                }
+                None => {}
             }
-            // Arrow keys with acceleration
-            KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down
-                if key.modifiers.is_empty() => {
-                // Calculate acceleration for arrow keys
-                let acceleration = update_arrow_acceleration(self, key.code);
+            return Ok(());
+        }
 
-                if self.selected_note.is_some() {
-                    if self.editor.handle_key_with_acceleration(key.code, key.modifiers, acceleration)? {
-                        // Auto-save immediately after any modification
-                        self.auto_save_current_note()?;
+        if let Some(OverlayAction::RecoverJournal) = self.overlay.as_ref().and_then(|o| o.action) {
+            match key.code {
+                KeyCode::Enter => {
+                    self.overlay = None;
+                    self.journal.release_all();
+                    self.retry_journal()?;
+                    self.all_notes = self.notes.get_all_notes()?;
+                    self.update_search()?;
+                    if self.journal.is_empty() {
+                        self.messages.info("Recovered journaled edits");
                     }
+                    return Ok(());
                 }
+                KeyCode::Char('d') => {
+                    self.overlay = None;
+                    self.journal.clear();
+                    self.messages.info("Discarded journaled edits");
+                    return Ok(());
+                }
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    self.journal.hold_all();
+                }
+                _ => {}
             }
-            // All other keys are passed to the editor for text editing
-            _ => {
-                if self.selected_note.is_some() {
-                    if self.editor.handle_key(key.code, key.modifiers)? {
-                        // Auto-save immediately after any modification
-                        self.auto_save_current_note()?;
+        }
+
+        if let Some(OverlayAction::RecoverDrafts) = self.overlay.as_ref().and_then(|o| o.action) {
+            match key.code {
+                KeyCode::Enter => {
+                    self.overlay = None;
+                    return self.recover_drafts();
+                }
+                KeyCode::Char('d') => {
+                    self.overlay = None;
+                    self.drafts.take_recovered()?;
+                    self.messages.info("Discarded the drafts");
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(OverlayAction::UsageReport) = self.overlay.as_ref().and_then(|o| o.action) {
+            match key.code {
+                KeyCode::Char('e') => {
+                    self.config.usage_insights = !self.config.usage_insights;
+                    self.usage.enabled = self.config.usage_insights;
+                    self.config.save(&self.notes_dir)?;
+                    self.show_usage_report();
+                    return Ok(());
+                }
+                KeyCode::Char('r') if self.usage.enabled => {
+                    self.usage.reset()?;
+                    self.show_usage_report();
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(OverlayAction::ReviewRelabel) = self.overlay.as_ref().and_then(|o| o.action) {
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    // Back to the queue rather than out of review entirely
+                    let index = self.review_relabel.take();
+                    return self.show_review_queue(index);
+                }
+                KeyCode::Enter => {
+                    let chosen = self.overlay.as_ref()
+                        .and_then(|o| o.selected)
+                        .and_then(|i| self.code_ids_by_name().into_iter().nth(i));
+                    if let (Some(index), Some(code_id)) = (self.review_relabel.take(), chosen) {
+                        self.review_suggestion(index, Some(code_id))?;
+                    }
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(OverlayAction::TemplateCode) = self.overlay.as_ref().and_then(|o| o.action) {
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    self.template_pending = None;
+                    self.show_template_picker();
+                    return Ok(());
+                }
+                KeyCode::Enter => {
+                    let chosen = self.overlay.take()
+                        .and_then(|o| o.selected)
+                        .and_then(|i| self.code_ids_by_name().into_iter().nth(i));
+                    if let (Some(index), Some(code_id)) = (self.template_pending.take(), chosen) {
+                        self.create_from_template(index, Some(&code_id))?;
+                    }
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(OverlayAction::SegmentFilter) = self.overlay.as_ref().and_then(|o| o.action) {
+            if key.code == KeyCode::Enter {
+                // Line 0 is "All codes"
+                let selected = self.overlay.take().and_then(|o| o.selected).unwrap_or(0);
+                self.segment_filter = selected.checked_sub(1).and_then(|i| self.code_ids_by_name().into_iter().nth(i));
+                let keys = format!("{}/{}", keymap::keys_for(Command::PrevSegment).join("/"), keymap::keys_for(Command::NextSegment).join("/"));
+                let message = match &self.segment_filter {
+                    Some(id) => format!("{} steps through {} segments", keys, self.code_name(id)),
+                    None => format!("{} steps through all coded segments", keys),
+                };
+                self.messages.info(message);
+                return Ok(());
+            }
+        }
+
+        if let Some(OverlayAction::TagManager) = self.overlay.as_ref().and_then(|o| o.action) {
+            let selected = self.overlay.as_ref().and_then(|o| o.selected).and_then(|i| self.tag_list.get(i)).cloned();
+            if let Some(tag) = selected {
+                match key.code {
+                    KeyCode::Char('r') | KeyCode::Enter => {
+                        self.overlay = None;
+                        self.tag_rename = Some((tag.clone(), tag));
+                        return Ok(());
+                    }
+                    KeyCode::Char('m') => {
+                        self.show_tag_merge_picker(tag);
+                        return Ok(());
+                    }
+                    KeyCode::Char('d') | KeyCode::Delete => {
+                        self.preview_tag_change(tag, None);
+                        return Ok(());
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if let Some(OverlayAction::TagMergeInto) = self.overlay.as_ref().and_then(|o| o.action) {
+            let from = self.tag_change.as_ref().map(|c| c.from.clone()).unwrap_or_default();
+            match key.code {
+                KeyCode::Esc | KeyCode::Backspace => {
+                    self.tag_change = None;
+                    let keep = self.tag_list.iter().position(|t| *t == from);
+                    self.show_tags(keep);
+                    return Ok(());
+                }
+                KeyCode::Enter => {
+                    let into = self.overlay.take()
+                        .and_then(|o| o.selected)
+                        .and_then(|i| self.tag_list.iter().filter(|t| **t != from).nth(i).cloned());
+                    if let Some(into) = into {
+                        self.preview_tag_change(from, Some(into));
+                    }
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(OverlayAction::TagPreview) = self.overlay.as_ref().and_then(|o| o.action) {
+            match key.code {
+                KeyCode::Esc | KeyCode::Backspace => {
+                    let from = self.tag_change.take().map(|c| c.from);
+                    let keep = from.and_then(|from| self.tag_list.iter().position(|t| *t == from));
+                    self.show_tags(keep);
+                    return Ok(());
+                }
+                KeyCode::Enter => {
+                    self.overlay = None;
+                    if let Some(change) = self.tag_change.take() {
+                        self.apply_tag_change(change)?;
+                    }
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(OverlayAction::QuickSwitcher) = self.overlay.as_ref().and_then(|o| o.action) {
+            let Some(filter) = self.switcher.as_mut() else {
+                return Ok(());
+            };
+            match key.code {
+                KeyCode::Esc => {
+                    self.switcher = None;
+                    self.overlay = None;
+                }
+                KeyCode::Enter => {
+                    let selected = self.overlay.take().and_then(|o| o.selected);
+                    self.switcher = None;
+                    let note = selected.and_then(|i| self.switcher_rows.get(i))
+                        .and_then(|id| self.all_notes.iter().find(|n| n.id == *id))
+                        .cloned();
+                    if let Some(note) = note {
+                        self.open_note(note)?;
+                        self.focus_area = FocusArea::Editor;
+                        self.usage.record_feature("quick switcher");
+                    }
+                }
+                KeyCode::Backspace => {
+                    filter.pop();
+                    self.show_switcher();
+                }
+                KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    filter.clear();
+                    self.show_switcher();
+                }
+                KeyCode::Char(c) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                    filter.push(c);
+                    self.show_switcher();
+                }
+                // Arrows and paging move through the rows as in any picker
+                _ => {}
+            }
+            if !matches!(key.code, KeyCode::Up | KeyCode::Down | KeyCode::PageUp | KeyCode::PageDown | KeyCode::Home | KeyCode::End) {
+                return Ok(());
+            }
+        }
+
+        if let Some(OverlayAction::SearchHistory) = self.overlay.as_ref().and_then(|o| o.action) {
+            let Some(filter) = self.history_filter.as_mut() else {
+                return Ok(());
+            };
+            match key.code {
+                KeyCode::Esc => {
+                    self.history_filter = None;
+                    self.overlay = None;
+                }
+                KeyCode::Enter => {
+                    let selected = self.overlay.take().and_then(|o| o.selected);
+                    self.history_filter = None;
+                    if let Some(query) = selected.and_then(|i| self.history_rows.get(i)).cloned() {
+                        self.search_history.stop();
+                        self.search_query = query;
+                        self.focus_area = FocusArea::SearchBar;
+                        self.update_search()?;
+                        self.usage.record_feature("search history");
                     }
                 }
+                KeyCode::Backspace => {
+                    filter.pop();
+                    self.show_search_history();
+                }
+                KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    filter.clear();
+                    self.show_search_history();
+                }
+                KeyCode::Char(c) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                    filter.push(c);
+                    self.show_search_history();
+                }
+                // Arrows and paging move through the rows as in any picker
+                _ => {}
+            }
+            if !matches!(key.code, KeyCode::Up | KeyCode::Down | KeyCode::PageUp | KeyCode::PageDown | KeyCode::Home | KeyCode::End) {
+                return Ok(());
             }
         }
-        Ok(())
+
+        if let Some(OverlayAction::DateFilter) = self.overlay.as_ref().and_then(|o| o.action) {
+            let Some(range) = self.date_range.as_mut() else {
+                return Ok(());
+            };
+            match key.code {
+                KeyCode::Esc => {
+                    self.date_range = None;
+                    self.overlay = None;
+                }
+                KeyCode::Enter => {
+                    let selected = self.overlay.take().and_then(|o| o.selected);
+                    self.date_range = None;
+                    if let Some(term) = selected.and_then(|i| self.date_rows.get(i)).cloned() {
+                        // The chosen term replaces any date terms already in the query
+                        let mut words: Vec<&str> = self.search_query.split_whitespace()
+                            .filter(|w| !date_filter::is_date_term(w))
+                            .collect();
+                        if !term.is_empty() {
+                            words.push(&term);
+                        }
+                        self.search_query = words.join(" ");
+                        self.search_history.stop();
+                        self.focus_area = FocusArea::SearchBar;
+                        self.update_search()?;
+                        self.usage.record_feature("date filter");
+                    }
+                }
+                KeyCode::Backspace => {
+                    range.pop();
+                    self.show_date_filter();
+                }
+                KeyCode::Char(c) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                    range.push(c);
+                    self.show_date_filter();
+                }
+                // Arrows and paging move through the rows as in any picker
+                _ => {}
+            }
+            if !matches!(key.code, KeyCode::Up | KeyCode::Down | KeyCode::PageUp | KeyCode::PageDown | KeyCode::Home | KeyCode::End) {
+                return Ok(());
+            }
+        }
+
+        if let Some(OverlayAction::LinkCheck) = self.overlay.as_ref().and_then(|o| o.action) {
+            let selected = self.overlay.as_ref().and_then(|o| o.selected).unwrap_or(0);
+            if let Some(problem) = self.link_problems.get(selected).cloned() {
+                match key.code {
+                    KeyCode::Enter | KeyCode::Char('o') => {
+                        self.fix_link_problem(problem, key.code == KeyCode::Enter)?;
+                        return Ok(());
+                    }
+                    KeyCode::Char('d') => {
+                        if let link_check::Problem::UnusedAttachment(attachment) = problem {
+                            if self.attachment_delete.as_deref() == Some(attachment.id.as_str()) {
+                                self.attachment_delete = None;
+                                attachments::remove(&self.notes_dir, &attachment)?;
+                                self.messages.info(format!("Deleted {}", attachment.name));
+                                self.show_link_check(Some(selected))?;
+                            } else {
+                                self.messages.warn(format!("Press d again to delete {} for good", attachment.name));
+                                self.attachment_delete = Some(attachment.id);
+                            }
+                        }
+                        return Ok(());
+                    }
+                    _ => self.attachment_delete = None,
+                }
+            }
+        }
+
+        if let Some(OverlayAction::Related) = self.overlay.as_ref().and_then(|o| o.action) {
+            if key.code == KeyCode::Enter {
+                let index = self.overlay.as_ref().and_then(|o| o.selected);
+                let related: Vec<&(String, f64)> = self.open_related().iter()
+                    .filter(|(id, _)| self.all_notes.iter().any(|n| n.id == *id))
+                    .collect();
+                if let Some(id) = index.and_then(|i| related.get(i)).map(|(id, _)| id.clone()) {
+                    self.open_related_note(&id)?;
+                }
+                return Ok(());
+            }
+        }
+
+        if let Some(OverlayAction::TagSuggestions) = self.overlay.as_ref().and_then(|o| o.action) {
+            let selected = self.overlay.as_ref().and_then(|o| o.selected).unwrap_or(0);
+            match key.code {
+                KeyCode::Enter => {
+                    self.accept_tag_suggestions(Some(selected))?;
+                    return Ok(());
+                }
+                KeyCode::Char('a') => {
+                    self.accept_tag_suggestions(None)?;
+                    return Ok(());
+                }
+                KeyCode::Char('d') | KeyCode::Delete => {
+                    self.dismiss_tag_suggestion(selected);
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(OverlayAction::Metadata) = self.overlay.as_ref().and_then(|o| o.action) {
+            let selected = self.overlay.as_ref().and_then(|o| o.selected).unwrap_or(0);
+            let keys: Vec<String> = self.open_front_matter().fields.into_keys().collect();
+            match key.code {
+                // The last line adds a field
+                KeyCode::Enter => {
+                    self.overlay = None;
+                    self.start_field_edit(keys.get(selected).cloned());
+                    return Ok(());
+                }
+                KeyCode::Char('a') => {
+                    self.overlay = None;
+                    self.start_field_edit(None);
+                    return Ok(());
+                }
+                KeyCode::Char('d') | KeyCode::Delete => {
+                    if let Some(key) = keys.get(selected) {
+                        if self.check_editable() {
+                            let text = front_matter::set_field(&self.editor.get_text(), key, None);
+                            self.editor.replace_text(&text);
+                            self.auto_save_current_note()?;
+                            self.messages.info(format!("Removed {}", key));
+                        }
+                        self.show_metadata(Some(selected));
+                    }
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(OverlayAction::SelectionActions) = self.overlay.as_ref().and_then(|o| o.action) {
+            let chosen = match key.code {
+                KeyCode::Enter => self.overlay.as_ref().and_then(|o| o.selected),
+                KeyCode::Char(c) => SELECTION_ACTIONS.iter().position(|(k, _)| *k == c),
+                _ => None,
+            };
+            if let Some(index) = chosen {
+                self.overlay = None;
+                return self.run_selection_action(index);
+            }
+        }
+
+        if let Some(OverlayAction::SelectionCode) = self.overlay.as_ref().and_then(|o| o.action) {
+            if key.code == KeyCode::Enter {
+                let chosen = self.overlay.take()
+                    .and_then(|o| o.selected)
+                    .and_then(|i| self.code_ids_by_name().into_iter().nth(i));
+                if let Some(code_id) = chosen {
+                    self.code_selection(&code_id)?;
+                }
+                return Ok(());
+            }
+        }
+
+        if let Some(OverlayAction::CodeVisibility) = self.overlay.as_ref().and_then(|o| o.action) {
+            let selected = self.overlay.as_ref().and_then(|o| o.selected).unwrap_or(0);
+            let ids: Vec<String> = self.codes.tree().iter().map(|(c, _)| c.id.clone()).collect();
+            match key.code {
+                KeyCode::Char(' ') => {
+                    if let Some(id) = ids.get(selected) {
+                        if !self.hidden_codes.remove(id) {
+                            self.hidden_codes.insert(id.clone());
+                        }
+                    }
+                }
+                KeyCode::Char('b') => {
+                    // The whole branch follows the selected code
+                    if let Some(root) = ids.get(selected) {
+                        let hide = !self.hidden_codes.contains(root);
+                        for id in ids.iter().filter(|id| self.codes.is_within(id, root)) {
+                            if hide {
+                                self.hidden_codes.insert(id.clone());
+                            } else {
+                                self.hidden_codes.remove(id);
+                            }
+                        }
+                    }
+                }
+                KeyCode::Char('a') => {
+                    if self.hidden_codes.is_empty() {
+                        self.hidden_codes.extend(ids);
+                    } else {
+                        self.hidden_codes.clear();
+                    }
+                }
+                _ => {}
+            }
+            if matches!(key.code, KeyCode::Char(' ') | KeyCode::Char('b') | KeyCode::Char('a')) {
+                self.show_code_visibility(Some(selected));
+                return Ok(());
+            }
+        }
+
+        if let Some(OverlayAction::HighlightCode) = self.overlay.as_ref().and_then(|o| o.action) {
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    let index = self.highlight_converting.take();
+                    return self.show_highlights(index);
+                }
+                KeyCode::Enter => {
+                    let chosen = self.overlay.as_ref()
+                        .and_then(|o| o.selected)
+                        .and_then(|i| self.code_ids_by_name().into_iter().nth(i));
+                    if let (Some(index), Some(code_id)) = (self.highlight_converting.take(), chosen) {
+                        self.convert_highlight(index, &code_id)?;
+                    }
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(action @ (OverlayAction::RecodeFrom | OverlayAction::RecodeInto | OverlayAction::RecodeConfirm)) =
+            self.overlay.as_ref().and_then(|o| o.action)
+        {
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    self.overlay = None;
+                    self.recode = None;
+                    return Ok(());
+                }
+                KeyCode::Enter => {
+                    let chosen = self.overlay.take().and_then(|o| o.selected);
+                    let Some(mut recode) = self.recode.take() else {
+                        return Ok(());
+                    };
+                    let code = chosen.and_then(|i| recode.choices.get(i).cloned());
+                    match action {
+                        OverlayAction::RecodeFrom => {
+                            recode.from = code;
+                            self.show_recode_into(recode);
+                        }
+                        OverlayAction::RecodeInto => {
+                            recode.into = code;
+                            self.confirm_recode(recode)?;
+                        }
+                        _ => self.apply_recode(recode)?,
+                    }
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(action @ (OverlayAction::MoveCodePick | OverlayAction::MoveCodeParent)) =
+            self.overlay.as_ref().and_then(|o| o.action)
+        {
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    self.overlay = None;
+                    self.code_moving = None;
+                    return Ok(());
+                }
+                KeyCode::Enter => {
+                    let Some(index) = self.overlay.take().and_then(|o| o.selected) else {
+                        return Ok(());
+                    };
+                    if action == OverlayAction::MoveCodePick {
+                        self.code_moving = self.codes.tree().get(index).map(|(c, _)| c.id.clone());
+                        self.show_move_code_parent_picker();
+                    } else if let (Some(id), Some(parent)) = (self.code_moving.take(), self.code_move_choices.get(index).cloned()) {
+                        self.move_code(&id, parent.as_deref())?;
+                    }
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(OverlayAction::Codebook) = self.overlay.as_ref().and_then(|o| o.action) {
+            if key.code == KeyCode::Enter {
+                let choice = self.overlay.take()
+                    .and_then(|o| o.selected)
+                    .and_then(|i| self.codebook_choices.get(i).cloned());
+                match choice {
+                    Some(codebook::Choice::Export(format)) => self.export_codebook(format)?,
+                    Some(codebook::Choice::Import(path)) => self.import_codebook(&path)?,
+                    None => {}
+                }
+                return Ok(());
+            }
+        }
+
+        if let Some(OverlayAction::ReportCodes) = self.overlay.as_ref().and_then(|o| o.action) {
+            let selected = self.overlay.as_ref().and_then(|o| o.selected);
+            match key.code {
+                KeyCode::Char(' ') => {
+                    if let Some(id) = selected.and_then(|i| self.report_codes.get(i)).cloned() {
+                        if !self.report_chosen.remove(&id) {
+                            self.report_chosen.insert(id);
+                        }
+                        self.show_report_codes(selected);
+                    }
+                    return Ok(());
+                }
+                KeyCode::Char('a') => {
+                    if self.report_chosen.len() == self.report_codes.len() {
+                        self.report_chosen.clear();
+                    } else {
+                        self.report_chosen = self.report_codes.iter().cloned().collect();
+                    }
+                    self.show_report_codes(selected);
+                    return Ok(());
+                }
+                KeyCode::Enter => {
+                    if self.report_chosen.is_empty() {
+                        self.messages.info("Tick at least one code (Space)");
+                    } else {
+                        self.show_report_formats();
+                    }
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(OverlayAction::ReportFormat) = self.overlay.as_ref().and_then(|o| o.action) {
+            if key.code == KeyCode::Enter {
+                let format = self.overlay.take()
+                    .and_then(|o| o.selected)
+                    .and_then(|i| report::Format::ALL.get(i).copied());
+                if let Some(format) = format {
+                    self.write_report(format)?;
+                }
+                return Ok(());
+            }
+        }
+
+        if let Some(OverlayAction::AutoCodePick) = self.overlay.as_ref().and_then(|o| o.action) {
+            if key.code == KeyCode::Enter {
+                let selected = self.overlay.take().and_then(|o| o.selected);
+                if let (Some(mut run), Some(index)) = (self.autocode.take(), selected) {
+                    run.code_id = run.choices.get(index).cloned();
+                    self.find_autocode_matches(run)?;
+                }
+                return Ok(());
+            }
+        }
+
+        if let Some(OverlayAction::AutoCodePreview) = self.overlay.as_ref().and_then(|o| o.action) {
+            let selected = self.overlay.as_ref().and_then(|o| o.selected);
+            match key.code {
+                KeyCode::Char(' ') => {
+                    if let Some(m) = selected.and_then(|i| self.autocode.as_mut().and_then(|run| run.matches.get_mut(i))) {
+                        m.checked = !m.checked;
+                    }
+                    self.show_autocode_preview(selected);
+                    return Ok(());
+                }
+                KeyCode::Char('a') => {
+                    if let Some(run) = self.autocode.as_mut() {
+                        let all = run.matches.iter().all(|m| m.checked || m.already);
+                        for m in run.matches.iter_mut() {
+                            m.checked = !all;
+                        }
+                    }
+                    self.show_autocode_preview(selected);
+                    return Ok(());
+                }
+                KeyCode::Char('u') => {
+                    if let Some(mut run) = self.autocode.take() {
+                        run.unit = run.unit.next();
+                        self.find_autocode_matches(run)?;
+                    }
+                    return Ok(());
+                }
+                KeyCode::Enter => {
+                    self.overlay = None;
+                    if let Some(run) = self.autocode.take() {
+                        self.apply_autocode(&run)?;
+                    }
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(OverlayAction::Coverage) = self.overlay.as_ref().and_then(|o| o.action) {
+            match key.code {
+                KeyCode::Enter => {
+                    let index = self.overlay.as_ref().and_then(|o| o.selected);
+                    if let Some(entry) = index.and_then(|i| self.coverage.get(i)).cloned() {
+                        if let Some(note) = self.all_notes.iter().find(|n| n.id == entry.note_id).cloned() {
+                            self.overlay = None;
+                            self.open_note(note)?;
+                            // Pick up where coding stopped
+                            if let Some(pos) = entry.coverage.last_coded {
+                                self.editor.set_cursor_char(pos);
+                            }
+                            self.focus_area = FocusArea::Editor;
+                        }
+                    }
+                    return Ok(());
+                }
+                KeyCode::Char('a') => {
+                    self.coverage_all = !self.coverage_all;
+                    self.show_coverage()?;
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(OverlayAction::Calendar) = self.overlay.as_ref().and_then(|o| o.action) {
+            let day = self.calendar_day;
+            let moved = match key.code {
+                KeyCode::Left | KeyCode::Char('h') => day.pred_opt(),
+                KeyCode::Right | KeyCode::Char('l') => day.succ_opt(),
+                KeyCode::Up | KeyCode::Char('k') => day.checked_sub_signed(chrono::Duration::days(7)),
+                KeyCode::Down | KeyCode::Char('j') => day.checked_add_signed(chrono::Duration::days(7)),
+                KeyCode::PageUp => day.checked_sub_months(chrono::Months::new(1)),
+                KeyCode::PageDown => day.checked_add_months(chrono::Months::new(1)),
+                KeyCode::Char('t') => Some(daily::today()),
+                KeyCode::Enter => {
+                    self.overlay = None;
+                    return self.open_daily(day);
+                }
+                _ => None,
+            };
+            if let Some(day) = moved {
+                self.calendar_day = day;
+                self.show_calendar();
+                return Ok(());
+            }
+        }
+
+        if let Some(OverlayAction::WordFrequency) = self.overlay.as_ref().and_then(|o| o.action) {
+            if key.code == KeyCode::Enter {
+                if let Some(index) = self.overlay.as_ref().and_then(|o| o.selected) {
+                    self.show_concordance(index);
+                }
+                return Ok(());
+            }
+        }
+
+        if let Some(OverlayAction::Concordance) = self.overlay.as_ref().and_then(|o| o.action) {
+            match key.code {
+                KeyCode::Enter => {
+                    let hit = self.overlay.as_ref().and_then(|o| o.selected).and_then(|i| self.word_hits.get(i)).cloned();
+                    if let Some(hit) = hit {
+                        if let Some(note) = self.all_notes.iter().find(|n| n.id == hit.note_id).cloned() {
+                            self.overlay = None;
+                            self.open_note(note)?;
+                            self.editor.select_span(hit.start, hit.end);
+                            self.focus_area = FocusArea::Editor;
+                        }
+                    }
+                    return Ok(());
+                }
+                KeyCode::Esc | KeyCode::Backspace => {
+                    self.show_word_list(Some(self.word_selected));
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(OverlayAction::Matrix) = self.overlay.as_ref().and_then(|o| o.action) {
+            let selected = self.overlay.as_ref().and_then(|o| o.selected);
+            let cases = self.matrix.as_ref().map_or(0, |m| m.cases.len());
+            match key.code {
+                KeyCode::Left | KeyCode::Char('h') => {
+                    self.matrix_case = self.matrix_case.saturating_sub(1);
+                    self.render_matrix(selected)?;
+                    return Ok(());
+                }
+                KeyCode::Right | KeyCode::Char('l') => {
+                    self.matrix_case = (self.matrix_case + 1).min(cases.saturating_sub(1));
+                    self.render_matrix(selected)?;
+                    return Ok(());
+                }
+                KeyCode::Enter => {
+                    // Line 0 is the header of case numbers
+                    if let Some(code) = selected.and_then(|i| i.checked_sub(1)) {
+                        self.show_matrix_cell(code);
+                    }
+                    return Ok(());
+                }
+                KeyCode::Char('x') => {
+                    if let Some(matrix) = &self.matrix {
+                        let path = matrix::export(&self.notes_dir, matrix)?;
+                        self.messages.info(format!("Wrote the matrix to {}", path.display()));
+                    }
+                    return Ok(());
+                }
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    self.matrix = None;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(OverlayAction::MatrixCell) = self.overlay.as_ref().and_then(|o| o.action) {
+            match key.code {
+                KeyCode::Enter => {
+                    let index = self.overlay.as_ref().and_then(|o| o.selected);
+                    let row = self.matrix.as_ref()
+                        .and_then(|m| index.and_then(|i| m.cell(self.matrix_code, self.matrix_case).get(i)))
+                        .cloned();
+                    if let Some(row) = row {
+                        if let Some(note) = self.all_notes.iter().find(|n| n.id == row.note_id).cloned() {
+                            self.overlay = None;
+                            self.matrix = None;
+                            self.open_note(note)?;
+                            self.editor.select_span(row.start, row.end);
+                            self.focus_area = FocusArea::Editor;
+                        }
+                    }
+                    return Ok(());
+                }
+                KeyCode::Esc | KeyCode::Backspace => {
+                    self.render_matrix(Some(self.matrix_code + 1))?;
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(OverlayAction::JournalEntry) = self.overlay.as_ref().and_then(|o| o.action) {
+            match (key.code, self.journal_viewing) {
+                (KeyCode::Char('e'), Some(index)) => {
+                    self.edit_journal_entry(index);
+                    return Ok(());
+                }
+                (KeyCode::Esc | KeyCode::Backspace, Some(index)) => return self.show_journal(Some(index)),
+                _ => {}
+            }
+        }
+
+        if matches!(key.code, KeyCode::Esc | KeyCode::Char('q') | KeyCode::F(1)) {
+            self.overlay = None;
+            return Ok(());
+        }
+
+        if let Some(OverlayAction::TemplatePicker) = self.overlay.as_ref().and_then(|o| o.action) {
+            if key.code == KeyCode::Enter {
+                let selected = self.overlay.take().and_then(|o| o.selected);
+                if let Some(index) = selected {
+                    if TEMPLATES[index].needs_code {
+                        self.show_template_code_picker(index);
+                    } else {
+                        self.create_from_template(index, None)?;
+                    }
+                }
+                return Ok(());
+            }
+        }
+
+        if let Some(OverlayAction::ReviewSuggestions) = self.overlay.as_ref().and_then(|o| o.action) {
+            let selected = self.overlay.as_ref().and_then(|o| o.selected);
+            if let Some(index) = selected {
+                match key.code {
+                    KeyCode::Enter | KeyCode::Char('a') => {
+                        let code_id = self.review_queue.get(index).map(|app| app.code_id.clone());
+                        if code_id.is_some() {
+                            self.review_suggestion(index, code_id)?;
+                        }
+                        return Ok(());
+                    }
+                    KeyCode::Char('r') => {
+                        self.review_suggestion(index, None)?;
+                        return Ok(());
+                    }
+                    KeyCode::Char('e') => {
+                        self.show_relabel_picker(index);
+                        return Ok(());
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if let Some(OverlayAction::Highlights) = self.overlay.as_ref().and_then(|o| o.action) {
+            let selected = self.overlay.as_ref().and_then(|o| o.selected);
+            if let Some(index) = selected {
+                match key.code {
+                    KeyCode::Enter => {
+                        self.overlay = None;
+                        if let Some(h) = self.highlight_list.get(index) {
+                            self.editor.select_span(h.start_offset, h.end_offset);
+                            self.focus_area = FocusArea::Editor;
+                        }
+                        return Ok(());
+                    }
+                    KeyCode::Char('d') => {
+                        if let Some(h) = self.highlight_list.get(index) {
+                            self.highlights.remove(h.id)?;
+                        }
+                        return self.show_highlights(Some(index));
+                    }
+                    KeyCode::Char('c') => {
+                        self.show_highlight_code_picker(index);
+                        return Ok(());
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if let Some(OverlayAction::Annotations) = self.overlay.as_ref().and_then(|o| o.action) {
+            let selected = self.overlay.as_ref().and_then(|o| o.selected);
+            if let Some(index) = selected {
+                match key.code {
+                    KeyCode::Enter => {
+                        self.overlay = None;
+                        if let Some(a) = self.annotation_list.get(index) {
+                            self.editor.select_span(a.start_offset, a.end_offset);
+                            self.focus_area = FocusArea::Editor;
+                        }
+                        return Ok(());
+                    }
+                    KeyCode::Char('e') => {
+                        if let Some(a) = self.annotation_list.get(index) {
+                            self.annotation_edit = Some(AnnotationEdit {
+                                id: Some(a.id),
+                                note_id: a.note_id.clone(),
+                                start_offset: a.start_offset,
+                                end_offset: a.end_offset,
+                                text: a.text.clone(),
+                            });
+                            self.overlay = None;
+                        }
+                        return Ok(());
+                    }
+                    KeyCode::Char('d') => {
+                        if let Some(a) = self.annotation_list.get(index) {
+                            self.annotations.remove(a.id)?;
+                        }
+                        return self.show_annotations(Some(index));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if let Some(OverlayAction::Journal) = self.overlay.as_ref().and_then(|o| o.action) {
+            let selected = self.overlay.as_ref().and_then(|o| o.selected);
+            match key.code {
+                KeyCode::Char('n') => {
+                    self.overlay = None;
+                    self.journal_edit = Some(JournalEdit::default());
+                    return Ok(());
+                }
+                KeyCode::Char('x') => {
+                    let entries = self.project_journal.entries()?;
+                    let path = project_journal::export(&self.notes_dir, &entries)?;
+                    self.messages.info(format!("Exported {} journal entries to {}", entries.len(), path.display()));
+                    return Ok(());
+                }
+                _ => {}
+            }
+            if let Some(index) = selected {
+                match key.code {
+                    KeyCode::Enter => {
+                        self.show_journal_entry(index);
+                        return Ok(());
+                    }
+                    KeyCode::Char('e') => {
+                        self.edit_journal_entry(index);
+                        return Ok(());
+                    }
+                    KeyCode::Char('d') => {
+                        if let Some(entry) = self.journal_list.get(index) {
+                            self.project_journal.remove(entry.id)?;
+                        }
+                        return self.show_journal(Some(index));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if let Some(OverlayAction::Outline) = self.overlay.as_ref().and_then(|o| o.action) {
+            let Some(index) = self.overlay.as_ref().and_then(|o| o.selected).and_then(|i| self.outline_rows.get(i).copied()) else {
+                return Ok(());
+            };
+            let line = self.outline[index].line;
+            let has_children = outline::has_children(&self.outline, index);
+            match key.code {
+                KeyCode::Enter => {
+                    self.overlay = None;
+                    self.jump_to_line(line);
+                    return Ok(());
+                }
+                KeyCode::Left | KeyCode::Char('h') => {
+                    // Collapse, or on a collapsed / leaf heading move up to its parent
+                    if has_children && !self.outline_collapsed.contains(&line) {
+                        self.outline_collapsed.insert(line);
+                        self.refresh_outline(index);
+                    } else if let Some(parent) = outline::parent(&self.outline, index) {
+                        self.refresh_outline(parent);
+                    }
+                    return Ok(());
+                }
+                KeyCode::Right | KeyCode::Char('l') => {
+                    self.outline_collapsed.remove(&line);
+                    self.refresh_outline(index);
+                    return Ok(());
+                }
+                KeyCode::Char(' ') if has_children => {
+                    if !self.outline_collapsed.remove(&line) {
+                        self.outline_collapsed.insert(line);
+                    }
+                    self.refresh_outline(index);
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(OverlayAction::Attachments) = self.overlay.as_ref().and_then(|o| o.action) {
+            if key.code == KeyCode::Enter {
+                let selected = self.overlay.as_ref().and_then(|o| o.selected);
+                if let Some(attachment) = selected.and_then(|i| self.attachment_list.get(i)) {
+                    match attachments::open(&attachment.path) {
+                        Ok(()) => self.messages.info(format!("Opened {}", attachment.name)),
+                        Err(e) => self.messages.error(format!("Could not open {}: {}", attachment.name, e)),
+                    }
+                }
+                self.overlay = None;
+                return Ok(());
+            }
+        }
+
+        if let Some(OverlayAction::Citations) = self.overlay.as_ref().and_then(|o| o.action) {
+            if key.code == KeyCode::Enter {
+                let selected = self.overlay.as_ref().and_then(|o| o.selected);
+                if let Some(key) = selected.and_then(|i| self.citation_list.get(i)).cloned() {
+                    self.insert_citation(&key)?;
+                }
+                self.overlay = None;
+                return Ok(());
+            }
+        }
+
+        if let Some(OverlayAction::Duplicates) = self.overlay.as_ref().and_then(|o| o.action) {
+            if key.code == KeyCode::Enter {
+                if let Some(i) = self.overlay.as_ref().and_then(|o| o.selected) {
+                    self.show_duplicate_pair(i)?;
+                }
+                return Ok(());
+            }
+        }
+
+        if let Some(OverlayAction::DuplicatePair) = self.overlay.as_ref().and_then(|o| o.action) {
+            let Some(pair) = self.duplicate_pairs.get(self.duplicate_open).cloned() else {
+                self.overlay = None;
+                return Ok(());
+            };
+            match key.code {
+                KeyCode::Char('m') => {
+                    self.merge_notes(&[pair.first, pair.second])?;
+                    self.find_duplicates(Some(self.duplicate_open))?;
+                }
+                KeyCode::Char('l') => {
+                    self.trash_note(&pair.first)?;
+                    self.find_duplicates(Some(self.duplicate_open))?;
+                }
+                KeyCode::Char('r') => {
+                    self.trash_note(&pair.second)?;
+                    self.find_duplicates(Some(self.duplicate_open))?;
+                }
+                KeyCode::Esc | KeyCode::Char('q') => self.show_duplicates(Some(self.duplicate_open)),
+                _ => {}
+            }
+            if !matches!(key.code, KeyCode::Up | KeyCode::Down | KeyCode::PageUp | KeyCode::PageDown | KeyCode::Home | KeyCode::End) {
+                return Ok(());
+            }
+        }
+
+        if let Some(OverlayAction::Tasks) = self.overlay.as_ref().and_then(|o| o.action) {
+            let selected = self.overlay.as_ref().and_then(|o| o.selected);
+            match key.code {
+                KeyCode::Char(' ') | KeyCode::Char('x') => {
+                    if let Some(task) = selected.and_then(|i| self.task_list.get(i)).cloned() {
+                        self.toggle_task_line(&task.note_id, task.line)?;
+                        self.show_tasks(selected);
+                    }
+                    return Ok(());
+                }
+                KeyCode::Enter => {
+                    if let Some(task) = selected.and_then(|i| self.task_list.get(i)).cloned() {
+                        self.overlay = None;
+                        if let Some(note) = self.all_notes.iter().find(|n| n.id == task.note_id).cloned() {
+                            self.open_note(note)?;
+                            let pos = self.editor.rope.line_to_char(task.line.min(self.editor.rope.len_lines().saturating_sub(1)));
+                            self.editor.set_cursor_char(pos);
+                            self.focus_area = FocusArea::Editor;
+                        }
+                    }
+                    return Ok(());
+                }
+                KeyCode::Char('t') => {
+                    // all -> each tag in turn -> all
+                    let tags = tasks::tags(&self.all_notes);
+                    let next = match &self.task_filter.tag {
+                        None => 0,
+                        Some(tag) => tags.iter().position(|t| t == tag).map_or(tags.len(), |i| i + 1),
+                    };
+                    self.task_filter.tag = tags.get(next).cloned();
+                    self.show_tasks(None);
+                    return Ok(());
+                }
+                KeyCode::Char('d') => {
+                    self.task_filter.due = self.task_filter.due.next();
+                    self.show_tasks(None);
+                    return Ok(());
+                }
+                KeyCode::Char('a') => {
+                    self.task_filter.show_done = !self.task_filter.show_done;
+                    self.show_tasks(None);
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(OverlayAction::MirrorSync) = self.overlay.as_ref().and_then(|o| o.action) {
+            let selected = self.overlay.as_ref().and_then(|o| o.selected);
+            let handled = match key.code {
+                KeyCode::Char('d') => selected.map(|i| self.sync_mirror_entry(i, true)).transpose()?.is_some(),
+                KeyCode::Char('f') => selected.map(|i| self.sync_mirror_entry(i, false)).transpose()?.is_some(),
+                KeyCode::Char('a') => {
+                    let notes = self.notes.get_all_notes()?;
+                    for note in &notes {
+                        mirror::write_note(&self.notes_dir, note)?;
+                    }
+                    self.messages.info(format!("Wrote {} notes to the mirror", notes.len()));
+                    true
+                }
+                _ => false,
+            };
+            if handled {
+                // Refresh the report, keeping the cursor near where it was
+                self.show_mirror_report()?;
+                if let (Some(overlay), Some(i)) = (&mut self.overlay, selected) {
+                    overlay.selected = Some(i.min(overlay.lines.len().saturating_sub(1)));
+                }
+                return Ok(());
+            }
+        }
+
+        if let Some(OverlayAction::PasteHistory) = self.overlay.as_ref().and_then(|o| o.action) {
+            if key.code == KeyCode::Enter {
+                let selected = self.overlay.take().and_then(|o| o.selected);
+                if let Some(text) = selected.and_then(|i| clipboard::history().into_iter().nth(i)) {
+                    if self.editor.paste_text(&text)? {
+                        self.auto_save_current_note()?;
+                    }
+                    self.report_blocked_edit();
+                }
+                return Ok(());
+            }
+        }
+
+        if let Some(overlay) = &mut self.overlay {
+            let picker = overlay.selected.is_some();
+            match key.code {
+                KeyCode::Up | KeyCode::Char('k') if picker => overlay.select_prev(),
+                KeyCode::Down | KeyCode::Char('j') if picker => overlay.select_next(visible),
+                KeyCode::Up | KeyCode::Char('k') => overlay.scroll_up(1),
+                KeyCode::Down | KeyCode::Char('j') => overlay.scroll_down(1, visible),
+                KeyCode::PageUp => overlay.scroll_up(visible),
+                KeyCode::PageDown | KeyCode::Char(' ') => overlay.scroll_down(visible, visible),
+                KeyCode::Home => overlay.scroll = 0,
+                KeyCode::End => overlay.scroll_to_end(visible),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(chars = text.chars().count()))]
+    fn handle_paste(&mut self, text: &str) -> Result<()> {
+        if self.locked {
+            // A passphrase from a password manager
+            self.lock_input.push_str(text.trim_end_matches(['\r', '\n']));
+            self.needs_redraw = true;
+            return Ok(());
+        }
+        if let Some(title) = &mut self.title_edit {
+            title.push_str(&text.replace(['\r', '\n'], " "));
+            self.needs_redraw = true;
+            return Ok(());
+        }
+        if let Some(edit) = &mut self.annotation_edit {
+            edit.text.push_str(&text.replace(['\r', '\n'], " "));
+            self.needs_redraw = true;
+            return Ok(());
+        }
+        if let Some(edit) = &mut self.journal_edit {
+            edit.text.push_str(&text.replace(['\r', '\n'], " "));
+            self.needs_redraw = true;
+            return Ok(());
+        }
+        if let Some(pattern) = &mut self.autocode_pattern {
+            pattern.push_str(&text.replace(['\r', '\n'], " "));
+            self.needs_redraw = true;
+            return Ok(());
+        }
+        if let Some(edit) = &mut self.field_edit {
+            edit.text.push_str(&text.replace(['\r', '\n'], " "));
+            self.needs_redraw = true;
+            return Ok(());
+        }
+        if let Some((_, name)) = &mut self.tag_rename {
+            name.push_str(text.trim());
+            self.needs_redraw = true;
+            return Ok(());
+        }
+        if self.overlay.is_some() {
+            return Ok(());
+        }
+
+        match self.focus_area {
+            FocusArea::SearchBar => {
+                self.search_query.push_str(&text.replace(['\r', '\n'], " "));
+                self.update_search()?;
+            }
+            FocusArea::Editor if self.selected_note.is_some() => {
+                // Files dropped on the terminal arrive as their paths
+                if let Some(paths) = attachments::dropped_paths(text) {
+                    return self.attach_files(&paths);
+                }
+                // Terminals send CR line endings inside bracketed paste
+                let text = text.replace("\r\n", "\n").replace('\r', "\n");
+                if self.editor.paste_text(&text)? {
+                    self.auto_save_current_note()?;
+                }
+                self.report_blocked_edit();
+            }
+            _ => {}
+        }
+        self.needs_redraw = true;
+        Ok(())
+    }
+
+    /// Keys for the title field in the editor header: Enter saves, Esc cancels
+    fn handle_title_key(&mut self, key: KeyEvent) -> Result<()> {
+        let Some(title) = &mut self.title_edit else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Enter => {
+                let title = title.clone();
+                self.title_edit = None;
+                self.rename_note(&title)?;
+            }
+            KeyCode::Esc => self.title_edit = None,
+            KeyCode::Backspace => {
+                title.pop();
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => title.clear(),
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => title.push(c),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Keys for the front matter field in the editor header: Enter saves, Esc goes back to the panel
+    fn handle_field_key(&mut self, key: KeyEvent) -> Result<()> {
+        let Some(edit) = &mut self.field_edit else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Enter => {
+                let edit = edit.clone();
+                self.field_edit = None;
+                self.save_field(edit)?;
+            }
+            KeyCode::Esc => {
+                self.field_edit = None;
+                self.show_metadata(None);
+            }
+            KeyCode::Backspace => {
+                edit.text.pop();
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => edit.text.clear(),
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => edit.text.push(c),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Keys for the new tag name in the editor header: Enter previews, Esc goes back to the tags
+    fn handle_tag_rename_key(&mut self, key: KeyEvent) -> Result<()> {
+        let Some((from, name)) = &mut self.tag_rename else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Enter => {
+                let Some(into) = tags::normalize(name) else {
+                    self.messages.warn("A tag has no spaces and doesn't start with a digit");
+                    return Ok(());
+                };
+                let from = from.clone();
+                self.tag_rename = None;
+                if into == from {
+                    self.show_tags(None);
+                } else {
+                    self.preview_tag_change(from, Some(into));
+                }
+            }
+            KeyCode::Esc => {
+                let from = from.clone();
+                self.tag_rename = None;
+                let keep = self.tag_list.iter().position(|t| *t == from);
+                self.show_tags(keep);
+            }
+            KeyCode::Backspace => {
+                name.pop();
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => name.clear(),
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) && !c.is_whitespace() => name.push(c),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Keys for the comment field in the editor header: Enter saves, Esc cancels
+    fn handle_annotation_key(&mut self, key: KeyEvent) -> Result<()> {
+        let Some(edit) = &mut self.annotation_edit else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Enter => {
+                let edit = edit.clone();
+                self.annotation_edit = None;
+                self.annotations.save(&edit)?;
+                if edit.text.trim().is_empty() {
+                    if edit.id.is_some() {
+                        self.messages.info("Annotation removed");
+                    }
+                } else {
+                    self.usage.record_feature("annotation");
+                    self.messages.info("Annotation saved (Ctrl+G N: list)");
+                }
+            }
+            KeyCode::Esc => self.annotation_edit = None,
+            KeyCode::Backspace => {
+                edit.text.pop();
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => edit.text.clear(),
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => edit.text.push(c),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Keys for the journal entry field in the editor header: Enter saves and goes back to
+    /// the journal, Esc cancels
+    fn handle_journal_key(&mut self, key: KeyEvent) -> Result<()> {
+        let Some(edit) = &mut self.journal_edit else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Enter => {
+                let edit = edit.clone();
+                self.journal_edit = None;
+                let saved = self.project_journal.save(&edit)?;
+                if saved.is_some() {
+                    self.usage.record_feature("project_journal");
+                }
+                // Back to the list, on the entry just written
+                self.show_journal(None)?;
+                let index = saved.and_then(|id| self.journal_list.iter().position(|e| e.id == id));
+                if let (Some(overlay), Some(index)) = (self.overlay.as_mut(), index) {
+                    overlay.selected = Some(index);
+                }
+            }
+            KeyCode::Esc => self.journal_edit = None,
+            KeyCode::Backspace => {
+                edit.text.pop();
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => edit.text.clear(),
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => edit.text.push(c),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Keys for the auto-coding pattern in the editor header: Enter goes on to the code
+    /// picker, Esc cancels
+    fn handle_autocode_key(&mut self, key: KeyEvent) -> Result<()> {
+        let Some(pattern) = &mut self.autocode_pattern else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Enter => {
+                let pattern = pattern.clone();
+                if let Err(e) = autocode::compile(&pattern) {
+                    // Stay in the field so the pattern can be fixed
+                    self.messages.warn(format!("{:#}", e));
+                    return Ok(());
+                }
+                self.autocode_pattern = None;
+                self.show_autocode_picker(&pattern);
+            }
+            KeyCode::Esc => self.autocode_pattern = None,
+            KeyCode::Backspace => {
+                pattern.pop();
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => pattern.clear(),
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => pattern.push(c),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Give the open note a new title; the list and the search index follow
+    fn rename_note(&mut self, title: &str) -> Result<()> {
+        let title = title.trim();
+        if title.is_empty() {
+            self.messages.info("A note needs a title");
+            return Ok(());
+        }
+        self.auto_save_current_note()?;
+        let Some(mut note) = self.selected_note.take() else {
+            return Ok(());
+        };
+        let old = std::mem::replace(&mut note.title, title.to_string());
+        if old == note.title {
+            self.set_open_note(Some(note));
+            return Ok(());
+        }
+        // Moved on in memory too, so the search index picks up the new title
+        note.updated_at = chrono::Utc::now();
+        let result = self.notes.update_note(&note);
+        for list in [&mut self.all_notes, &mut self.filtered_notes] {
+            if let Some(slot) = list.iter_mut().find(|n| n.id == note.id) {
+                *slot = note.clone();
+            }
+        }
+        self.set_open_note(Some(note));
+        result?;
+        self.update_search()?;
+
+        // Wiki links go by title, so links to the old one now lead nowhere
+        let linking = self.all_notes.iter()
+            .filter(|n| Self::extract_wiki_links(&n.content).contains(&old))
+            .count();
+        self.messages.info(match linking {
+            0 => format!("Renamed \"{}\" to \"{}\"", old, title),
+            n => format!("Renamed to \"{}\" - {} note(s) still link to [[{}]]", title, n, old),
+        });
+        Ok(())
+    }
+
+    async fn handle_search_key(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            // Nothing matches: Enter makes the note being looked for (nvALT style)
+            KeyCode::Enter if self.filtered_notes.is_empty() && !SearchQuery::parse(&self.search_query).text.trim().is_empty() => {
+                self.create_note_from_search()?;
+            }
+            // Earlier (and later) queries; Down past the latest is back to what was typed
+            KeyCode::Up => {
+                if let Some(query) = self.search_history.older(&self.search_query) {
+                    self.search_query = query.to_string();
+                    self.update_search()?;
+                }
+            }
+            KeyCode::Down if self.search_history.is_stepping() => {
+                if let Some(query) = self.search_history.newer() {
+                    self.search_query = query;
+                    self.update_search()?;
+                }
+            }
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Down => {
+                // Exit search focus, move to notes list
+                if !self.search_query.is_empty() {
+                    self.usage.record_feature("search");
+                }
+                self.search_history.record(&self.search_query);
+                self.focus_area = FocusArea::NoteList;
+                if !self.filtered_notes.is_empty() && key.code == KeyCode::Enter {
+                    self.selected_note_index = 0;
+                    self.load_selected_note()?;
+                }
+            }
+            KeyCode::Backspace => {
+                self.search_history.stop();
+                self.search_query.pop();
+                self.update_search()?;
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.search_history.stop();
+                self.search_query.push(c);
+                self.update_search()?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_editor_key(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            // Arrow keys with acceleration
+            KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down
+                if key.modifiers.is_empty() => {
+                // Calculate acceleration for arrow keys
+                let acceleration = update_arrow_acceleration(self, key.code);
+
+                if self.selected_note.is_some() {
+                    if self.editor.handle_key_with_acceleration(key.code, key.modifiers, acceleration)? {
+                        // Auto-save immediately after any modification
+                        self.auto_save_current_note()?;
+                    }
+                }
+            }
+            // All other keys are passed to the editor for text editing
+            _ => {
+                if self.selected_note.is_some() {
+                    if self.editor.handle_key(key.code, key.modifiers)? {
+                        // Auto-save immediately after any modification
+                        self.auto_save_current_note()?;
+                    }
+                    self.report_blocked_edit();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Make `note` the open one; the editor takes on its lock (toggle_read_only keeps it
+    /// in step after)
+    fn set_open_note(&mut self, note: Option<Note>) {
+        self.editor.read_only = note.as_ref().is_some_and(|n| self.read_only.contains(&n.id));
+        self.selected_note = note;
+    }
+
+    /// Whether the open note's text is locked
+    fn open_note_read_only(&self) -> bool {
+        self.selected_note.as_ref().is_some_and(|n| self.read_only.contains(&n.id))
+    }
+
+    fn read_only_hint(&self, title: &str) -> String {
+        format!("\"{}\" is read-only ({} unlocks it)", title, keymap::keys_for(Command::ToggleReadOnly).join("/"))
+    }
+
+    /// Tell the user when the editor refused an edit to a read-only note
+    fn report_blocked_edit(&mut self) {
+        if std::mem::take(&mut self.editor.blocked) {
+            let title = self.selected_note.as_ref().map(|n| n.title.clone()).unwrap_or_default();
+            let hint = self.read_only_hint(&title);
+            self.messages.warn(hint);
+        }
+    }
+
+    /// False, with a warning, when the open note is read-only - for commands that rewrite its text
+    fn check_editable(&mut self) -> bool {
+        if !self.open_note_read_only() {
+            return true;
+        }
+        let title = self.selected_note.as_ref().map(|n| n.title.clone()).unwrap_or_default();
+        let hint = self.read_only_hint(&title);
+        self.messages.warn(hint);
+        false
+    }
+
+    /// Lock the open note's text against editing, or unlock it; selecting and coding still work
+    fn toggle_read_only(&mut self) -> Result<()> {
+        let Some(note) = self.selected_note.clone() else {
+            self.messages.info("Open a note to lock or unlock it");
+            return Ok(());
+        };
+        self.auto_save_current_note()?;
+        let locked = !self.read_only.contains(&note.id);
+        self.notes.set_read_only(&note.id, locked)?;
+        if locked {
+            self.read_only.insert(note.id.clone());
+        } else {
+            self.read_only.remove(&note.id);
+        }
+        self.editor.read_only = locked;
+        tracing::info!(note = %note.id, locked, "read-only toggled");
+        if locked {
+            self.usage.record_feature("note locked");
+            self.messages.info(format!("\"{}\" is read-only: select and code, but no edits", note.title));
+        } else {
+            self.messages.info(format!("\"{}\" can be edited again", note.title));
+        }
+        Ok(())
+    }
+
+
+
+    fn process_daemon_inbox(&mut self) -> Result<()> {
+        for item in daemon::take_inbox(&self.notes_dir) {
+            match item {
+                daemon::InboxItem::Captured { note_id } => {
+                    self.usage.record_feature("hotkey capture");
+                    if let Some(note) = self.notes.get_note(&note_id)? {
+                        let title = note.title.clone();
+                        self.all_notes.insert(0, note);
+                        self.update_search()?;
+                        self.messages.info(format!("Captured: {}", title));
+                    }
+                }
+                daemon::InboxItem::Search { query } => {
+                    self.search_query = query;
+                    self.focus_area = FocusArea::SearchBar;
+                    self.update_search()?;
+                }
+            }
+            self.needs_redraw = true;
+        }
+        Ok(())
+    }
+
+    fn show_paste_history(&mut self) {
+        if self.selected_note.is_none() {
+            return;
+        }
+
+        // One line per entry; line breaks shown as ⏎ so each entry stays on its row
+        let lines: Vec<String> = clipboard::history().iter()
+            .map(|entry| entry.trim_end().replace('\n', " ⏎ "))
+            .collect();
+        if lines.is_empty() {
+            self.messages.info("Clipboard history is empty");
+            return;
+        }
+        self.overlay = Some(Overlay::new("Clipboard history (Enter: paste)", lines)
+            .with_selection()
+            .with_action(OverlayAction::PasteHistory));
+    }
+
+    fn show_mirror_report(&mut self) -> Result<()> {
+        // Compare against the database itself - the in-memory lists can lag behind autosave
+        let notes = self.notes.get_all_notes()?;
+        self.mirror_report = mirror::check(&self.notes_dir, &notes)?;
+        if self.mirror_report.is_empty() {
+            self.overlay = None;
+            self.messages.info(format!("Markdown mirror in sync ({} notes)", notes.len()));
+            return Ok(());
+        }
+
+        let lines = self.mirror_report.iter()
+            .map(|entry| format!("[{:<7}] {}", entry.drift.label(), entry.title))
+            .collect();
+        let title = format!(
+            "Mirror drift: {} (d: DB→file  f: file→DB  a: write all)",
+            self.mirror_report.len()
+        );
+        self.overlay = Some(Overlay::new(&title, lines)
+            .with_selection()
+            .with_action(OverlayAction::MirrorSync));
+        Ok(())
+    }
+
+    /// Resolve one drifted mirror entry in the given direction
+    fn sync_mirror_entry(&mut self, index: usize, db_to_file: bool) -> Result<()> {
+        let Some(entry) = self.mirror_report.get(index).cloned() else {
+            return Ok(());
+        };
+        let note = match entry.note_id {
+            Some(ref id) => self.notes.get_note(id)?,
+            None => None,
+        };
+
+        match (db_to_file, note) {
+            (true, Some(note)) => {
+                mirror::write_note(&self.notes_dir, &note)?;
+                self.messages.info(format!("Mirror updated: {}", note.title));
+            }
+            (true, None) => {
+                // The database has no such note - DB wins, so the stray file goes
+                std::fs::remove_file(&entry.path)?;
+                self.messages.info(format!("Removed orphan file {}", entry.path.display()));
+            }
+            (false, Some(mut note)) => {
+                if entry.drift == mirror::Drift::MissingFile {
+                    self.messages.info("No mirror file to import");
+                    return Ok(());
+                }
+                note.content = mirror::read_content(&entry.path)?;
+                note.tags = Self::extract_tags(&note.content);
+                self.notes.update_note(&note)?;
+                self.replace_note_in_memory(&note);
+                self.messages.info(format!("Imported from mirror: {}", note.title));
+            }
+            (false, None) => {
+                let content = mirror::read_content(&entry.path)?;
+                let note = self.notes.create_note(&entry.title, &content)?;
+                mirror::adopt(&entry.path, &note)?;
+                self.all_notes.insert(0, note);
+                self.update_search()?;
+                self.messages.info(format!("Created note from {}", entry.path.display()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Swap an updated note into the in-memory lists (and the editor if it is open)
+    fn replace_note_in_memory(&mut self, note: &Note) {
+        for list in [&mut self.all_notes, &mut self.filtered_notes] {
+            if let Some(slot) = list.iter_mut().find(|n| n.id == note.id) {
+                *slot = note.clone();
+            }
+        }
+        if self.selected_note.as_ref().is_some_and(|n| n.id == note.id) {
+            self.set_open_note(Some(note.clone()));
+            self.editor.set_text(&note.content);
+        }
+    }
+
+    /// List pending machine suggestions; `keep_selected` restores the cursor after a decision
+    fn show_review_queue(&mut self, keep_selected: Option<usize>) -> Result<()> {
+        // Suggestions on trashed notes wait until the note comes back
+        let notes = self.notes.get_all_notes()?;
+        self.review_queue = self.codes.get_suggestions()?;
+        self.review_queue.retain(|app| notes.iter().any(|n| n.id == app.note_id));
+        if self.review_queue.is_empty() {
+            self.overlay = None;
+            self.messages.info("No machine-suggested codes to review");
+            return Ok(());
+        }
+
+        let lines = self.review_queue.iter()
+            .map(|app| {
+                let code = self.codes.get_code(&app.code_id).map(|c| c.name.as_str()).unwrap_or(&app.code_id);
+                let confidence = app.confidence
+                    .map(|c| format!("{:>3.0}%", c * 100.0))
+                    .unwrap_or_else(|| "   -".to_string());
+                let note = notes.iter().find(|n| n.id == app.note_id);
+                let title = note.map(|n| n.title.as_str()).unwrap_or("");
+                let text = note
+                    .map(|n| query::coded_text(&n.content, app.start_offset, app.end_offset).replace('\n', " ⏎ "))
+                    .unwrap_or_default();
+                format!("{:<12} {}  {} - \"{}\"", code, confidence, title, text)
+            })
+            .collect();
+        let title = format!(
+            "Review {} suggestions (a/Enter: accept  r: reject  e: relabel)",
+            self.review_queue.len()
+        );
+
+        let mut overlay = Overlay::new(&title, lines)
+            .with_selection()
+            .with_action(OverlayAction::ReviewSuggestions);
+        if let Some(index) = keep_selected {
+            overlay.selected = Some(index.min(overlay.lines.len().saturating_sub(1)));
+        }
+        self.overlay = Some(overlay);
+        Ok(())
+    }
+
+    /// Accept (optionally under another code) or, with None, reject one queued suggestion
+    fn review_suggestion(&mut self, index: usize, accept_as: Option<String>) -> Result<()> {
+        let Some(app) = self.review_queue.get(index).cloned() else {
+            return Ok(());
+        };
+        let coder = self.config.coder_name();
+
+        let entry = match accept_as {
+            Some(code_id) => {
+                self.codes.accept_suggestion(app.id, &code_id, &coder)?;
+                let accepted = CodeApplication { code_id: code_id.clone(), coder: Some(coder.clone()), suggested: false, ..app.clone() };
+                self.coding_history.push(Step {
+                    before: vec![app.clone()],
+                    after: vec![accepted],
+                    ..Step::new(format!("Accept suggestion as {}", self.code_name(&code_id)))
+                });
+                if code_id == app.code_id {
+                    AuditEntry::for_application("suggestion_accepted", &coder, &app)
+                } else {
+                    AuditEntry::for_application("suggestion_relabeled", &coder, &app)
+                        .with_detail(format!("{} -> {}", app.code_id, code_id))
+                }
+            }
+            None => {
+                self.codes.reject_suggestion(app.id)?;
+                self.coding_history.push(Step {
+                    before: vec![app.clone()],
+                    ..Step::new(format!("Reject {} suggestion", self.code_name(&app.code_id)))
+                });
+                AuditEntry::for_application("suggestion_rejected", &coder, &app)
+            }
+        };
+        audit::record(&self.notes_dir, &entry)?;
+
+        self.show_review_queue(Some(index))
+    }
+
+    fn show_relabel_picker(&mut self, index: usize) {
+        let Some(app) = self.review_queue.get(index) else {
+            return;
+        };
+        let ids = self.code_ids_by_name();
+        let current = ids.iter().position(|id| *id == app.code_id);
+        let lines = ids.iter()
+            .map(|id| self.codes.get_code(id).map(|c| c.name.clone()).unwrap_or_else(|| id.clone()))
+            .collect();
+
+        let mut overlay = Overlay::new("Relabel as (Enter: accept with this code  Esc: back)", lines)
+            .with_selection()
+            .with_action(OverlayAction::ReviewRelabel);
+        if current.is_some() {
+            overlay.selected = current;
+        }
+        self.review_relabel = Some(index);
+        self.overlay = Some(overlay);
+    }
+
+    fn show_template_picker(&mut self) {
+        let lines = TEMPLATES.iter()
+            .map(|t| format!("{:<20} {}", t.name, t.description))
+            .collect();
+        self.overlay = Some(Overlay::new("New from template (Enter: create)", lines)
+            .with_selection()
+            .with_action(OverlayAction::TemplatePicker));
+    }
+
+    /// Ask which code the memo / definition is about, starting on the code at the cursor
+    fn show_template_code_picker(&mut self, index: usize) {
+        let ids = self.code_ids_by_name();
+        let at_cursor = self.selected_note.as_ref()
+            .and_then(|note| self.codes_at_cursor(note).into_iter().next())
+            .and_then(|(code_id, _)| ids.iter().position(|id| *id == code_id));
+        let lines = ids.iter()
+            .map(|id| self.codes.get_code(id).map(|c| c.name.clone()).unwrap_or_else(|| id.clone()))
+            .collect();
+
+        let title = format!("{} for code (Enter: create  Esc: back)", TEMPLATES[index].name);
+        let mut overlay = Overlay::new(&title, lines)
+            .with_selection()
+            .with_action(OverlayAction::TemplateCode);
+        if at_cursor.is_some() {
+            overlay.selected = at_cursor;
+        }
+        self.template_pending = Some(index);
+        self.overlay = Some(overlay);
+    }
+
+    /// Create a note from a template, filled in from the open note (the case) and the chosen code
+    fn create_from_template(&mut self, index: usize, code_id: Option<&str>) -> Result<()> {
+        let template = &TEMPLATES[index];
+        let case = self.selected_note.clone();
+        if template.needs_case && case.is_none() {
+            self.messages.info(format!("Open the case's note first - {} summarizes it", template.name));
+            return Ok(());
+        }
+
+        let applications = self.codes.get_all_code_applications()?;
+
+        // Codes applied in the case, most frequent first (suggestions don't count until accepted)
+        let mut case_codes: Vec<(String, usize)> = Vec::new();
+        if let Some(case) = &case {
+            let ids = case.codes.iter().map(|s| s.code_id.as_str())
+                .chain(applications.get(&case.id).into_iter().flatten()
+                    .filter(|a| !a.suggested)
+                    .map(|a| a.code_id.as_str()));
+            for id in ids {
+                let name = self.codes.get_code(id).map(|c| c.name.clone()).unwrap_or_else(|| id.to_string());
+                match case_codes.iter_mut().find(|(n, _)| *n == name) {
+                    Some((_, count)) => *count += 1,
+                    None => case_codes.push((name, 1)),
+                }
+            }
+            case_codes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        }
+
+        // First accepted segment coded with the code, quoted as its example
+        let code_example = code_id.and_then(|code_id| {
+            applications.values().flatten()
+                .filter(|a| a.code_id == code_id && !a.suggested)
+                .find_map(|a| {
+                    let note = self.all_notes.iter().find(|n| n.id == a.note_id)?;
+                    Some(query::coded_text(&note.content, a.start_offset, a.end_offset))
+                })
+        });
+
+        let ctx = TemplateContext {
+            date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+            coder: self.config.coder_name(),
+            case: case.as_ref(),
+            case_codes,
+            code: code_id.and_then(|id| self.codes.get_code(id)),
+            code_example,
+            reference: None,
+        };
+        let (title, content) = template.render(&ctx);
+        self.open_new_note(&title, &content)?;
+        self.usage.record_feature("note from template");
+        Ok(())
+    }
+
+    /// Store a new note and open it in the editor
+    fn open_new_note(&mut self, title: &str, content: &str) -> Result<()> {
+        self.persist_undo_history();
+        let mut note = self.notes.create_note(title, content)?;
+        note.tags = Self::extract_tags(&note.content);
+        self.notes.update_note(&note)?;
+
+        self.all_notes.push(note.clone());
+        self.update_search()?;
+        self.set_open_note(Some(note));
+        self.editor.set_text(content);
+        self.reading_scroll = 0;
+        self.focus_area = FocusArea::Editor;
+        self.messages.info(format!("Created \"{}\"", title));
+        Ok(())
+    }
+
+    /// Open a note in the editor, selecting it in the list when the current search shows it
+    fn open_note(&mut self, note: Note) -> Result<()> {
+        if let Some(index) = self.filtered_notes.iter().position(|n| n.id == note.id) {
+            self.selected_note_index = index;
+            self.load_selected_note()?;
+        } else if !self.selected_note.as_ref().is_some_and(|n| n.id == note.id) {
+            self.persist_undo_history();
+            self.editor.set_text(&note.content);
+            self.editor.history = UndoHistory::load(&self.notes_dir, &note.id, &note.content);
+            self.reading_scroll = 0;
+            self.set_open_note(Some(note));
+        }
+        Ok(())
+    }
+
+    /// The day of the open note, if it is a daily note
+    fn open_daily_date(&self) -> Option<chrono::NaiveDate> {
+        self.selected_note.as_ref().and_then(daily::daily_date)
+    }
+
+    /// Open the daily note for a day, creating it from the daily template if there is none
+    fn open_daily(&mut self, date: chrono::NaiveDate) -> Result<()> {
+        let label = date.format("%A %-d %B %Y").to_string();
+        if let Some(note) = daily::find(&self.all_notes, date).cloned() {
+            self.open_note(note)?;
+            self.focus_area = FocusArea::Editor;
+            self.messages.info(format!("Daily note for {}", label));
+            return Ok(());
+        }
+
+        let ctx = TemplateContext {
+            date: date.format("%Y-%m-%d").to_string(),
+            coder: self.config.coder_name(),
+            case: None,
+            case_codes: Vec::new(),
+            code: None,
+            code_example: None,
+            reference: None,
+        };
+        let (title, content) = DAILY.render(&ctx);
+        self.open_new_note(&title, &content)?;
+        self.usage.record_feature("daily note");
+        self.messages.info(format!("Created the daily note for {}", label));
+        Ok(())
+    }
+
+    /// Move to the nearest existing daily note before / after the open one (or today)
+    fn step_daily(&mut self, forward: bool) -> Result<()> {
+        let from = self.open_daily_date().unwrap_or_else(daily::today);
+        match daily::neighbor(&self.all_notes, from, forward).cloned() {
+            Some(note) => {
+                let label = daily::daily_date(&note).map(|d| d.format("%A %-d %B %Y").to_string()).unwrap_or_default();
+                self.open_note(note)?;
+                self.messages.info(format!("Daily note for {}", label));
+            }
+            None => {
+                self.messages.info(format!("No {} daily note", if forward { "later" } else { "earlier" }));
+            }
+        }
+        Ok(())
+    }
+
+    fn show_tasks(&mut self, keep_selected: Option<usize>) {
+        let today = daily::today();
+        self.task_list = tasks::collect(&self.all_notes, &self.task_filter, today);
+
+        let lines = if self.task_list.is_empty() {
+            vec!["No tasks match - t: tag  d: due date  a: include done".to_string()]
+        } else {
+            self.task_list.iter()
+                .map(|task| {
+                    let due = match task.due {
+                        Some(d) if d < today && !task.done => format!("overdue {}", d.format("%b %-d")),
+                        Some(d) => format!("due {}", d.format("%b %-d")),
+                        None => String::new(),
+                    };
+                    let text: String = task.text.chars().take(48).collect();
+                    format!("{} {:<48} {:<14} {}", if task.done { "[x]" } else { "[ ]" }, text, due, task.note_title)
+                })
+                .collect()
+        };
+
+        let title = format!(
+            "Tasks: {} - {} (Space: toggle  Enter: open  t: tag  d: due  a: done)",
+            self.task_filter.describe(),
+            self.task_list.len()
+        );
+        let mut overlay = Overlay::new(&title, lines)
+            .with_selection()
+            .with_action(OverlayAction::Tasks);
+        if let Some(index) = keep_selected {
+            overlay.selected = Some(index.min(overlay.lines.len().saturating_sub(1)));
+        }
+        self.overlay = Some(overlay);
+    }
+
+    /// Index the next batch of the startup job; once done, pick up edits made meanwhile
+    /// and rank the search again with the whole index
+    fn step_index_job(&mut self) {
+        let Some(job) = self.index_job.as_mut() else {
+            return;
+        };
+        let result = self.search_index.step(job).and_then(|()| {
+            if job.is_finished() {
+                self.search_index.sync(&self.all_notes)?;
+            }
+            Ok(job.is_finished())
+        });
+        match result {
+            Ok(false) => {}
+            Ok(true) => {
+                self.index_job = None;
+                if let Err(e) = self.update_search() {
+                    tracing::warn!(error = %e, "search after indexing failed");
+                }
+            }
+            Err(e) => {
+                // The index stays behind; the next search's sync catches it up
+                tracing::warn!(error = %e, "startup indexing failed");
+                self.messages.error(format!("Indexing failed: {:#}", e));
+                self.index_job = None;
+            }
+        }
+        self.needs_redraw = true;
+    }
+
+    /// Rebuild the search index, drawing the progress in the status bar between batches
+    fn reindex(&mut self) -> Result<()> {
+        self.auto_save_current_note()?;
+        self.index_job = None;
+        let notes = self.all_notes.clone();
+        self.search_index.clear()?;
+        for (i, batch) in notes.chunks(search_index::REINDEX_BATCH).enumerate() {
+            self.search_index.index_batch(batch)?;
+            let done = i * search_index::REINDEX_BATCH + batch.len();
+            self.messages.info(format!("Reindexing... {}/{}", done, notes.len()));
+            self.render()?;
+        }
+
+        self.update_search()?;
+        self.messages.info(format!("Search index rebuilt ({} notes)", self.search_index.note_count()));
+        Ok(())
+    }
+
+    /// Copy files in as attachments and insert their references at the cursor
+    fn attach_files(&mut self, paths: &[PathBuf]) -> Result<()> {
+        if !self.check_editable() {
+            return Ok(());
+        }
+        let mut references = Vec::new();
+        for path in paths {
+            match attachments::add(&self.notes_dir, path) {
+                Ok(attachment) => references.push(attachment.reference()),
+                Err(e) => {
+                    tracing::warn!(error = %e, path = %path.display(), "attaching file failed");
+                    self.messages.error(format!("Could not attach {}: {}", path.display(), e));
+                }
+            }
+        }
+        if references.is_empty() {
+            return Ok(());
+        }
+        if self.editor.paste_text(&references.join("\n"))? {
+            self.auto_save_current_note()?;
+        }
+        self.usage.record_feature("attachments");
+        self.messages.info(format!("Attached {} file{}", references.len(), if references.len() == 1 { "" } else { "s" }));
+        self.needs_redraw = true;
+        Ok(())
+    }
+
+    /// Look for duplicates among all notes and list the pairs found
+    fn find_duplicates(&mut self, selected: Option<usize>) -> Result<()> {
+        self.auto_save_current_note()?;
+        self.duplicate_pairs = duplicates::find(&self.all_notes);
+        if self.duplicate_pairs.is_empty() {
+            self.overlay = None;
+            self.messages.info("No duplicate notes found");
+            return Ok(());
+        }
+        self.show_duplicates(selected);
+        Ok(())
+    }
+
+    fn note_title(&self, id: &str) -> &str {
+        self.all_notes.iter().find(|n| n.id == id).map_or("?", |n| n.title.as_str())
+    }
+
+    fn show_duplicates(&mut self, selected: Option<usize>) {
+        let lines: Vec<String> = self.duplicate_pairs.iter()
+            .map(|p| format!("{:<11} {}  ↔  {}", p.label(), self.note_title(&p.first), self.note_title(&p.second)))
+            .collect();
+        let (_, term_height) = terminal::size().unwrap_or((80, 24));
+        let mut overlay = Overlay::new(&format!("Possible duplicates: {} (Enter: compare)", lines.len()), lines)
+            .with_selection()
+            .with_action(OverlayAction::Duplicates);
+        if let Some(i) = selected {
+            overlay.select(i, Overlay::visible_rows(term_height));
+        }
+        self.overlay = Some(overlay);
+    }
+
+    /// The two notes of a pair in columns, older on the left
+    fn show_duplicate_pair(&mut self, index: usize) -> Result<()> {
+        let Some(pair) = self.duplicate_pairs.get(index) else {
+            return Ok(());
+        };
+        let find = |id: &str| self.all_notes.iter().find(|n| n.id == id);
+        let (Some(left), Some(right)) = (find(&pair.first), find(&pair.second)) else {
+            return Ok(());
+        };
+        let (term_width, term_height) = terminal::size()?;
+        let (_, _, box_width, _) = Overlay::frame(term_width, term_height);
+        let column = (box_width as usize).saturating_sub(7) / 2;
+
+        let header = |note: &Note| format!("{}\n{}, {} words", note.title,
+            note.updated_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"), note.content.split_whitespace().count());
+        let mut lines = duplicates::side_by_side(&header(left), &header(right), column);
+        lines.push(format!("{} ┼ {}", "─".repeat(column), "─".repeat(column)));
+        lines.extend(duplicates::side_by_side(&left.content, &right.content, column));
+
+        self.duplicate_open = index;
+        self.overlay = Some(Overlay::new(&format!("{} - m: merge right into left  l / r: trash left / right  Esc: back", pair.label()), lines)
+            .with_action(OverlayAction::DuplicatePair));
+        Ok(())
+    }
+
+    fn show_attachments(&mut self) {
+        if self.selected_note.is_none() {
+            self.messages.info("No note selected");
+            return;
+        }
+        self.attachment_list = attachments::referenced_ids(&self.editor.get_text()).iter()
+            .filter_map(|id| attachments::resolve(&self.notes_dir, id))
+            .collect();
+        if self.attachment_list.is_empty() {
+            self.messages.info("No attachments (drop files onto the editor to attach them)");
+            return;
+        }
+        let lines = self.attachment_list.iter()
+            .map(|a| format!("{:<40} {:>9}  {}", a.name, attachments::format_size(a.size()), a.id))
+            .collect();
+        self.overlay = Some(Overlay::new("Attachments (Enter: open)", lines)
+            .with_selection()
+            .with_action(OverlayAction::Attachments));
+    }
+
+    /// Sync with the configured store, then reload whatever it changed
+    fn sync_now(&mut self) -> Result<()> {
+        let Some(config) = self.config.sync.clone() else {
+            self.messages.info("Sync is not set up (add \"sync\" to config.json)");
+            return Ok(());
+        };
+        self.auto_save_current_note()?;
+        self.messages.info("Syncing...");
+        self.render()?;
+
+        let report = match sync::sync(&self.notes_dir, &mut self.notes, &config) {
+            Ok(report) => report,
+            Err(e) => {
+                tracing::warn!(error = %e, "sync failed");
+                self.messages.error(format!("Sync failed: {:#}", e));
+                return Ok(());
+            }
+        };
+        self.all_notes = self.notes.get_all_notes()?;
+        self.update_search()?;
+        // The open note came in from another device (or went to the trash there)
+        if let Some(open) = self.selected_note.as_ref().filter(|n| report.changed.contains(&n.id)).map(|n| n.id.clone()) {
+            match self.all_notes.iter().find(|n| n.id == open).cloned() {
+                Some(note) => {
+                    self.editor.set_text(&note.content);
+                    self.set_open_note(Some(note));
+                }
+                None => {
+                    self.set_open_note(None);
+                    self.editor.set_text("");
+                }
+            }
+        }
+        self.usage.record_feature("sync");
+        self.messages.info(report.describe());
+        Ok(())
+    }
+
+    /// Snapshot the databases; a failure is reported and tried again next interval
+    fn timed_backup(&mut self) {
+        if let Err(e) = backup::create(&self.notes_dir, "scheduled") {
+            tracing::warn!(error = %e, "timed backup failed");
+            self.messages.error(format!("Backup failed: {:#}", e));
+            self.needs_redraw = true;
+        }
+        let hours = self.config.backup_interval_hours;
+        self.next_backup = (hours > 0).then(|| Instant::now() + Duration::from_secs(hours * 3600));
+    }
+
+    fn toggle_mark(&mut self) {
+        let Some(id) = self.filtered_notes.get(self.selected_note_index).map(|n| n.id.clone()) else {
+            return;
+        };
+        if let Some(i) = self.marked.iter().position(|m| *m == id) {
+            self.marked.remove(i);
+        } else {
+            self.marked.push(id);
+        }
+        self.messages.info(match self.marked.len() {
+            0 => "No notes marked".to_string(),
+            1 => "1 note marked - mark more to merge them into it".to_string(),
+            n => format!("{} notes marked - Ctrl+G M merges them", n),
+        });
+    }
+
+    fn merge_marked_notes(&mut self) -> Result<()> {
+        self.marked.retain(|id| self.all_notes.iter().any(|n| n.id == *id));
+        if self.marked.len() < 2 {
+            self.messages.info(format!("Mark at least two notes to merge ({} in the list)", keymap::keys_for(Command::ToggleMark).join("/")));
+            return Ok(());
+        }
+        let ids = std::mem::take(&mut self.marked);
+        self.merge_notes(&ids)
+    }
+
+    /// Append the other notes to the first, moving their codes and highlights along;
+    /// the merged notes go to the trash
+    fn merge_notes(&mut self, ids: &[String]) -> Result<()> {
+        if let Some(note) = self.all_notes.iter().find(|n| ids.contains(&n.id) && self.read_only.contains(&n.id)) {
+            let hint = self.read_only_hint(&note.title);
+            self.messages.warn(hint);
+            return Ok(());
+        }
+        self.auto_save_current_note()?;
+        self.persist_undo_history();
+
+        let find = |id: &String| self.all_notes.iter().find(|n| n.id == *id).cloned();
+        let Some(mut target) = ids.first().and_then(find) else {
+            return Ok(());
+        };
+        let sources: Vec<Note> = ids[1..].iter().filter_map(find).collect();
+        let (content, moved) = merge::merged_content(&target, &sources);
+        target.content = content;
+        target.tags = Self::extract_tags(&target.content);
+
+        for (source, moved) in sources.iter().zip(&moved) {
+            self.codes.move_applications(&source.id, &target.id, moved.from, moved.base)?;
+            self.highlights.move_to(&source.id, &target.id, moved.from, moved.base)?;
+            self.annotations.move_to(&source.id, &target.id, moved.from, moved.base)?;
+            let mut segments = source.codes.clone();
+            merge::move_segments(&mut segments, &mut target.codes, moved.from, moved.base);
+            self.notes.trash_note(&source.id)?;
+            self.trashed.push(source.id.clone());
+        }
+        self.notes.update_note(&target)?;
+        tracing::info!(target = %target.id, merged = sources.len(), "notes merged");
+
+        // Reopen the merged note fresh: the old editor text and undo history are stale
+        self.set_open_note(None);
+        self.editor.set_text("");
+        self.all_notes = self.notes.get_all_notes()?;
+        self.update_search()?;
+        if let Some(note) = self.all_notes.iter().find(|n| n.id == target.id).cloned() {
+            self.open_note(note)?;
+        }
+        self.marked.retain(|id| !ids.contains(id));
+        self.usage.record_feature("notes merged");
+        self.messages.info(format!("Merged {} notes into \"{}\" (the others are in the trash)", sources.len(), target.title));
+        Ok(())
+    }
+
+    /// Move everything from the cursor (or the heading it is on) into a new note, leaving
+    /// a wiki link to it behind
+    fn split_note(&mut self) -> Result<()> {
+        if self.selected_note.is_none() {
+            self.messages.info("Open a note to split it");
+            return Ok(());
+        }
+        if !self.check_editable() {
+            return Ok(());
+        }
+        let text = self.editor.get_text();
+        let Some((at, title)) = merge::split_point(&text, self.editor.get_cursor_position()) else {
+            self.messages.info("Nothing to split off here (move the cursor into the body)");
+            return Ok(());
+        };
+        self.auto_save_current_note()?;
+        let Some(mut original) = self.selected_note.clone() else {
+            return Ok(());
+        };
+        let title = if title.is_empty() { format!("{} (part 2)", original.title) } else { title };
+        let title = merge::unique_title(&title, &self.all_notes);
+
+        let tail: String = text.chars().skip(at).collect();
+        let mut head: String = text.chars().take(at).collect();
+        if !head.is_empty() && !head.ends_with('\n') {
+            head.push('\n');
+        }
+        head.push_str(&format!("[[{}]]\n", title));
+
+        let mut note = self.notes.create_note(&title, &tail)?;
+        note.tags = Self::extract_tags(&tail);
+        merge::move_segments(&mut original.codes, &mut note.codes, at, 0);
+        self.notes.update_note(&note)?;
+        self.codes.move_applications(&original.id, &note.id, at, 0)?;
+        self.highlights.move_to(&original.id, &note.id, at, 0)?;
+        self.annotations.move_to(&original.id, &note.id, at, 0)?;
+        tracing::info!(note = %original.id, new = %note.id, at, "note split");
+
+        // One undo step in the original, like an external edit
+        self.set_open_note(Some(original));
+        self.editor.replace_text(&head);
+        self.auto_save_current_note()?;
+        self.all_notes.push(note);
+        self.update_search()?;
+        self.usage.record_feature("note split");
+        self.messages.info(format!("Split off \"{}\" (linked at the end of this note)", title));
+        Ok(())
+    }
+
+    fn idle_lock_due(&self) -> bool {
+        let minutes = self.config.auto_lock_minutes;
+        !self.locked && self.passphrase.is_some() && minutes > 0
+            && self.last_activity.elapsed() >= Duration::from_secs(minutes * 60)
+    }
+
+    /// Save, close any popup and blank the screen until the passphrase is entered
+    fn lock_screen(&mut self) -> Result<()> {
+        self.auto_save_current_note()?;
+        self.overlay = None;
+        self.locked = true;
+        self.lock_input.clear();
+        self.lock_failed = false;
+        self.needs_redraw = true;
+        tracing::info!("screen locked");
+        Ok(())
+    }
+
+    /// Keys on the lock screen type the passphrase; Ctrl+Q still quits
+    fn handle_lock_key(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Char('q') if key.modifiers.contains(KeyModifiers::CONTROL) => self.exit_requested = true,
+            KeyCode::Enter => {
+                let unlocked = match &self.passphrase {
+                    Some(passphrase) => passphrase.verify(&self.lock_input),
+                    None => true,
+                };
+                self.lock_input.clear();
+                self.lock_failed = !unlocked;
+                if unlocked {
+                    self.locked = false;
+                    self.last_activity = Instant::now();
+                    self.messages.info("Unlocked");
+                    tracing::info!("screen unlocked");
+                } else {
+                    tracing::warn!("wrong passphrase on the lock screen");
+                }
+            }
+            KeyCode::Backspace => {
+                self.lock_input.pop();
+            }
+            KeyCode::Esc => self.lock_input.clear(),
+            KeyCode::Char(c) => self.lock_input.push(c),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Complete the @citekey before the cursor from the literature notes: a single match is
+    /// filled in, several are offered in a picker
+    fn complete_citation(&mut self) -> Result<()> {
+        if self.selected_note.is_none() || self.reading {
+            return Ok(());
+        }
+        let cursor = self.editor.get_cursor_position();
+        let Some((at, prefix)) = bibtex::citation_before(&self.editor.get_text(), cursor) else {
+            self.messages.info("Type @ and the start of a citekey to complete it");
+            return Ok(());
+        };
+        let prefix = prefix.to_lowercase();
+        let mut matches: Vec<(String, String)> = self.all_notes.iter()
+            .filter_map(|n| Some((bibtex::citekey(n)?, n.title.clone())))
+            .filter(|(key, _)| key.to_lowercase().starts_with(&prefix))
+            .collect();
+        matches.sort();
+
+        self.citation_span = (at, cursor);
+        match matches.as_slice() {
+            [] => self.messages.info(format!("No reference matches @{} (import a .bib with snyfter3 import-bib)", prefix)),
+            [(key, _)] => {
+                let key = key.clone();
+                self.insert_citation(&key)?;
+            }
+            _ => {
+                let lines = matches.iter().map(|(key, title)| format!("@{:<24} {}", key, title)).collect();
+                self.citation_list = matches.into_iter().map(|(key, _)| key).collect();
+                self.overlay = Some(Overlay::new("Cite (Enter: insert)", lines)
+                    .with_selection()
+                    .with_action(OverlayAction::Citations));
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace the citation being typed (citation_span) with @key
+    fn insert_citation(&mut self, key: &str) -> Result<()> {
+        if !self.check_editable() {
+            return Ok(());
+        }
+        let (start, end) = self.citation_span;
+        let text: Vec<char> = self.editor.get_text().chars().collect();
+        let (start, end) = (start.min(text.len()), end.min(text.len()));
+        let citation = format!("@{}", key);
+        let before: String = text[..start].iter().collect();
+        let after: String = text[end..].iter().collect();
+        self.editor.replace_text(&format!("{}{}{}", before, citation, after));
+        self.editor.set_cursor_char(start + citation.chars().count());
+        self.auto_save_current_note()?;
+        self.usage.record_feature("cite");
+        self.messages.info(format!("Cited @{}", key));
+        Ok(())
+    }
+
+    fn show_note_info(&mut self) -> Result<()> {
+        let Some(note) = self.selected_note.clone() else {
+            self.messages.info("No note selected");
+            return Ok(());
+        };
+        let applications = self.codes.get_code_applications(&note.id)?;
+        let suggested = applications.iter().filter(|a| a.suggested).count();
+        let coded = (note.codes.len() + applications.len(), suggested);
+        let lines = stats::info_lines(&note, &self.editor.get_text(), &self.all_notes, coded);
+        self.overlay = Some(Overlay::new(&format!("Info: {}", note.title), lines));
+        Ok(())
+    }
+
+    fn show_outline(&mut self) {
+        if self.selected_note.is_none() {
+            self.messages.info("No note selected");
+            return;
+        }
+        self.outline = outline::headings(&self.editor.get_text());
+        if self.outline.is_empty() {
+            self.messages.info("This note has no headings");
+            return;
+        }
+        // Forget collapsed headings that no longer exist; start on the section holding the cursor
+        let note_id = self.selected_note.as_ref().map(|n| n.id.clone());
+        if self.outline_note != note_id {
+            self.outline_collapsed.clear();
+            self.outline_note = note_id;
+        }
+        let lines: HashSet<usize> = self.outline.iter().map(|h| h.line).collect();
+        self.outline_collapsed.retain(|line| lines.contains(line));
+        let current = outline::section_at(&self.outline, self.editor.cursor_pos.row).unwrap_or(0);
+        self.overlay = None;
+        self.refresh_outline(current);
+    }
+
+    /// Rebuild the outline overlay, selecting heading `index` (or its nearest visible ancestor)
+    fn refresh_outline(&mut self, index: usize) {
+        self.outline_rows = outline::visible(&self.outline, &self.outline_collapsed);
+        let mut target = Some(index);
+        let selected = loop {
+            match target {
+                Some(i) => match self.outline_rows.iter().position(|&r| r == i) {
+                    Some(row) => break row,
+                    None => target = outline::parent(&self.outline, i),
+                },
+                None => break 0,
+            }
+        };
+
+        let lines: Vec<String> = self.outline_rows.iter()
+            .map(|&i| outline::row_label(&self.outline, i, &self.outline_collapsed))
+            .collect();
+        let title = format!("Outline - {} headings (Enter: jump  ←→: collapse / expand)", self.outline.len());
+        let scroll = self.overlay.as_ref().map_or(0, |o| o.scroll);
+        let mut overlay = Overlay::new(&title, lines)
+            .with_selection()
+            .with_action(OverlayAction::Outline);
+        overlay.scroll = scroll;
+        let (_, height) = terminal::size().unwrap_or((80, 24));
+        overlay.select(selected, Overlay::visible_rows(height));
+        self.overlay = Some(overlay);
+    }
+
+    /// Put the cursor at the start of `line` and bring that line to the top of the editor
+    fn jump_to_line(&mut self, line: usize) {
+        let line = line.min(self.editor.rope.len_lines().saturating_sub(1));
+        let pos = self.editor.rope.line_to_char(line);
+        self.editor.set_cursor_char(pos);
+        self.ui.scroll_editor_to(line);
+        if self.reading {
+            self.reading_scroll = self.reading_rows().iter().position(|r| r.line == Some(line)).unwrap_or(0);
+        }
+        self.focus_area = FocusArea::Editor;
+    }
+
+    fn step_heading(&mut self, forward: bool) {
+        if self.selected_note.is_none() {
+            return;
+        }
+        let headings = outline::headings(&self.editor.get_text());
+        match outline::neighbor(&headings, self.editor.cursor_pos.row, forward) {
+            Some(line) => {
+                let pos = self.editor.rope.line_to_char(line);
+                self.editor.set_cursor_char(pos);
+            }
+            None => self.messages.info(format!("No {} heading", if forward { "next" } else { "previous" })),
+        }
+    }
+
+    /// Select the next (or previous) coded segment of the open note, keeping to the code
+    /// chosen with the segment filter
+    fn step_segment(&mut self, forward: bool) {
+        let Some(note) = self.selected_note.clone() else {
+            return;
+        };
+        let mut spans: Vec<(usize, usize)> = self.coded_spans(&note).into_iter()
+            .filter(|(id, start, end, _)| {
+                end > start && self.segment_filter.as_ref().map_or(true, |f| self.codes.is_within(id, f))
+            })
+            .map(|(_, start, end, _)| (start, end))
+            .collect();
+        spans.sort_unstable();
+        spans.dedup();
+
+        // From the segment already selected, or the cursor
+        let here = self.editor.selection_range().map_or(self.editor.get_cursor_position(), |(start, _)| start);
+        let found = if forward {
+            spans.iter().position(|&(start, _)| start > here)
+        } else {
+            spans.iter().rposition(|&(start, _)| start < here)
+        };
+        let which = self.segment_filter.as_ref().map_or_else(|| "coded".to_string(), |id| self.code_name(id));
+        let Some(index) = found else {
+            let message = format!("No {} {} segment in this note", if forward { "next" } else { "previous" }, which);
+            self.messages.info(message);
+            return;
+        };
+
+        let (start, end) = spans[index];
+        self.editor.select_span(start, end);
+        let mut names: Vec<String> = Vec::new();
+        for (id, suggested) in self.codes_at(&note, start) {
+            let name = if suggested { format!("{}?", self.code_name(&id)) } else { self.code_name(&id) };
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        self.messages.info(format!("Segment {} of {}: {}", index + 1, spans.len(), names.join(", ")));
+    }
+
+    /// Pick the code next / previous segment keeps to, or all codes
+    fn show_segment_filter(&mut self) {
+        let ids = self.code_ids_by_name();
+        if ids.is_empty() {
+            self.messages.info("No codes yet");
+            return;
+        }
+        let lines: Vec<String> = std::iter::once("All codes".to_string())
+            .chain(ids.iter().map(|id| self.code_name(id)))
+            .collect();
+        let current = self.segment_filter.as_ref().and_then(|f| ids.iter().position(|id| id == f)).map_or(0, |i| i + 1);
+        let mut overlay = Overlay::new("Step through the segments of (Enter: choose  Esc: cancel)", lines)
+            .with_selection()
+            .with_action(OverlayAction::SegmentFilter);
+        overlay.selected = Some(current);
+        self.overlay = Some(overlay);
+    }
+
+    /// The codebook with a checkbox per code: whether its segments are tinted in the editor
+    fn show_code_visibility(&mut self, keep: Option<usize>) {
+        let tree = self.codes.tree();
+        if tree.is_empty() {
+            self.messages.info("No codes yet");
+            return;
+        }
+        let lines: Vec<String> = tree.iter()
+            .map(|(code, depth)| {
+                let tick = if self.hidden_codes.contains(&code.id) { "[ ]" } else { "[x]" };
+                format!("{}{} {}", "  ".repeat(*depth), tick, code.name)
+            })
+            .collect();
+        let len = lines.len();
+        let scroll = self.overlay.as_ref().filter(|_| keep.is_some()).map_or(0, |o| o.scroll);
+        let mut overlay = Overlay::new("Tinted codes (Space: toggle  b: branch  a: all  Esc: close)", lines)
+            .with_selection()
+            .with_action(OverlayAction::CodeVisibility);
+        if let Some(i) = keep {
+            overlay.selected = Some(i.min(len - 1));
+            overlay.scroll = scroll;
+        }
+        self.overlay = Some(overlay);
+    }
+
+    fn toggle_checkbox_at_cursor(&mut self) -> Result<()> {
+        if self.selected_note.is_none() || self.reading {
+            return Ok(());
+        }
+        let line = self.editor.rope.char_to_line(self.editor.get_cursor_position());
+        // The box keeps its width, so the cursor stays put
+        match tasks::toggle(&self.editor.get_text(), line) {
+            Some(content) => {
+                if self.check_editable() {
+                    self.editor.replace_text(&content);
+                    self.auto_save_current_note()?;
+                }
+            }
+            // Ctrl+Enter does double duty: off a task line it is the zen toggle
+            None => self.toggle_zen(),
+        }
+        Ok(())
+    }
+
+    /// Tick or untick the task on `line` of a note - through the editor if it is the open note
+    fn toggle_task_line(&mut self, note_id: &str, line: usize) -> Result<bool> {
+        if let Some(note) = self.all_notes.iter().find(|n| n.id == note_id && self.read_only.contains(&n.id)) {
+            let hint = self.read_only_hint(&note.title);
+            self.messages.warn(hint);
+            return Ok(false);
+        }
+        if self.selected_note.as_ref().is_some_and(|n| n.id == note_id) {
+            let Some(content) = tasks::toggle(&self.editor.get_text(), line) else {
+                return Ok(false);
+            };
+            self.editor.replace_text(&content);
+            self.auto_save_current_note()?;
+            return Ok(true);
+        }
+
+        let Some(mut note) = self.all_notes.iter().find(|n| n.id == note_id).cloned() else {
+            return Ok(false);
+        };
+        let Some(content) = tasks::toggle(&note.content, line) else {
+            return Ok(false);
+        };
+        note.content = content;
+        self.notes.update_note(&note)?;
+        for list in [&mut self.all_notes, &mut self.filtered_notes] {
+            if let Some(slot) = list.iter_mut().find(|n| n.id == note.id) {
+                *slot = note.clone();
+            }
+        }
+        Ok(true)
+    }
+
+    fn show_calendar(&mut self) {
+        let lines = daily::calendar_lines(self.calendar_day, &self.all_notes);
+        self.overlay = Some(Overlay::new("Daily notes (Enter: open or create  Esc: close)", lines)
+            .with_action(OverlayAction::Calendar));
+    }
+
+    /// A note's coded spans as (code id, start, end, machine suggestion): its own segments
+    /// and the applications table
+    fn coded_spans(&self, note: &Note) -> Vec<(String, usize, usize, bool)> {
+        let applications = self.codes.get_code_applications(&note.id).unwrap_or_default();
+        note.codes.iter()
+            .map(|s| (s.code_id.clone(), s.start_offset, s.end_offset, false))
+            .chain(applications.into_iter().map(|a| (a.code_id, a.start_offset, a.end_offset, a.suggested)))
+            .collect()
+    }
+
+    /// Codes covering char `pos` of a note, with whether each is a machine suggestion
+    fn codes_at(&self, note: &Note, pos: usize) -> Vec<(String, bool)> {
+        self.coded_spans(note).into_iter()
+            .filter(|&(_, start, end, _)| pos >= start && pos < end)
+            .map(|(code_id, _, _, suggested)| (code_id, suggested))
+            .collect()
+    }
+
+    /// Codes covering the cursor in the open note
+    fn codes_at_cursor(&self, note: &Note) -> Vec<(String, bool)> {
+        self.codes_at(note, self.editor.get_cursor_position())
+    }
+
+    /// Mark the selection in the open note (or recolor it if exactly that span is already marked)
+    fn highlight_selection(&mut self) -> Result<()> {
+        let Some(note_id) = self.selected_note.as_ref().map(|n| n.id.clone()) else {
+            return Ok(());
+        };
+        let Some((start, end)) = self.editor.selection_range() else {
+            self.messages.info("Select some text to highlight");
+            return Ok(());
+        };
+        let color = self.highlights.toggle(&note_id, start, end)?;
+        self.usage.record_feature("highlight");
+        self.messages.info(format!("Highlighted in {} (Ctrl+G h: list, convert to codes)", color.name()));
+        Ok(())
+    }
+
+    fn show_highlights(&mut self, keep_selected: Option<usize>) -> Result<()> {
+        let Some(note) = self.selected_note.clone() else {
+            self.messages.info("Open a note to see its highlights");
+            return Ok(());
+        };
+        self.highlight_list = self.highlights.for_note(&note.id)?;
+        if self.highlight_list.is_empty() {
+            self.overlay = None;
+            self.messages.info("No highlights in this note (select text, Alt+H)");
+            return Ok(());
+        }
+
+        let content = self.editor.rope.to_string();
+        let lines = self.highlight_list.iter()
+            .map(|h| {
+                let text: String = query::coded_text(&content, h.start_offset, h.end_offset)
+                    .split_whitespace().collect::<Vec<_>>().join(" ");
+                let excerpt: String = text.chars().take(60).collect();
+                let ellipsis = if text.chars().count() > 60 { "..." } else { "" };
+                format!("{:<7} \"{}{}\"", h.color.name(), excerpt, ellipsis)
+            })
+            .collect();
+
+        let title = format!(
+            "{} highlights (Enter: jump  d: delete  c: convert to code)",
+            self.highlight_list.len()
+        );
+        let mut overlay = Overlay::new(&title, lines)
+            .with_selection()
+            .with_action(OverlayAction::Highlights);
+        if let Some(index) = keep_selected {
+            overlay.selected = Some(index.min(overlay.lines.len().saturating_sub(1)));
+        }
+        self.overlay = Some(overlay);
+        Ok(())
+    }
+
+    /// Grow the selection (or select from the cursor) to whole sentences or paragraphs
+    fn select_unit(&mut self, unit: text_units::Unit) {
+        if self.selected_note.is_none() {
+            return;
+        }
+        let chars: Vec<char> = self.editor.rope.chars().collect();
+        let (start, end) = self.editor.selection_range().unwrap_or_else(|| {
+            let cursor = self.editor.get_cursor_position();
+            (cursor, cursor)
+        });
+        let (start, end) = unit.expand(&chars, start, end);
+        if start < end {
+            self.editor.select_span(start, end);
+        } else {
+            self.messages.info(format!("No {} here", unit.name()));
+        }
+    }
+
+    /// Comment on the selection, or edit the annotation under the cursor
+    fn annotate(&mut self) -> Result<()> {
+        let Some(note_id) = self.selected_note.as_ref().map(|n| n.id.clone()) else {
+            return Ok(());
+        };
+        if let Some((start, end)) = self.editor.selection_range() {
+            self.annotation_edit = Some(AnnotationEdit { id: None, note_id, start_offset: start, end_offset: end, text: String::new() });
+            return Ok(());
+        }
+        let cursor = self.editor.get_cursor_position();
+        let existing = self.annotations.for_note(&note_id)?.into_iter()
+            .find(|a| a.start_offset <= cursor && cursor <= a.end_offset);
+        match existing {
+            Some(a) => {
+                self.annotation_edit = Some(AnnotationEdit {
+                    id: Some(a.id),
+                    note_id,
+                    start_offset: a.start_offset,
+                    end_offset: a.end_offset,
+                    text: a.text,
+                });
+            }
+            None => self.messages.info("Select some text to annotate"),
+        }
+        Ok(())
+    }
+
+    fn show_annotations(&mut self, keep_selected: Option<usize>) -> Result<()> {
+        let Some(note) = self.selected_note.clone() else {
+            self.messages.info("Open a note to see its annotations");
+            return Ok(());
+        };
+        self.annotation_list = self.annotations.for_note(&note.id)?;
+        if self.annotation_list.is_empty() {
+            self.overlay = None;
+            self.messages.info("No annotations in this note (select text, Alt+N)");
+            return Ok(());
+        }
+
+        let content = self.editor.rope.to_string();
+        let lines = self.annotation_list.iter()
+            .map(|a| {
+                let text: String = query::coded_text(&content, a.start_offset, a.end_offset)
+                    .split_whitespace().collect::<Vec<_>>().join(" ");
+                let excerpt: String = text.chars().take(30).collect();
+                let ellipsis = if text.chars().count() > 30 { "..." } else { "" };
+                format!("\"{}{}\"  {}", excerpt, ellipsis, a.text)
+            })
+            .collect();
+
+        let title = format!(
+            "{} annotations (Enter: jump  e: edit  d: delete)",
+            self.annotation_list.len()
+        );
+        let mut overlay = Overlay::new(&title, lines)
+            .with_selection()
+            .with_action(OverlayAction::Annotations);
+        if let Some(index) = keep_selected {
+            overlay.selected = Some(index.min(overlay.lines.len().saturating_sub(1)));
+        }
+        self.overlay = Some(overlay);
+        Ok(())
+    }
+
+    /// The project journal, newest first; text in the search bar narrows it to the
+    /// entries containing every word
+    fn show_journal(&mut self, keep_selected: Option<usize>) -> Result<()> {
+        let filter = SearchQuery::parse(&self.search_query).text;
+        self.journal_list = self.project_journal.search(&filter)?;
+        self.journal_viewing = None;
+
+        let title = if filter.is_empty() {
+            format!("Journal: {} entries (Enter: read  n: new  e: edit  d: delete  x: export)", self.journal_list.len())
+        } else {
+            format!("Journal: {} entries with \"{}\" (Enter: read  n: new  e: edit  d: delete  x: export)", self.journal_list.len(), filter)
+        };
+        let mut overlay = if self.journal_list.is_empty() {
+            let line = if filter.is_empty() { "No entries yet - press n to write one" } else { "No entries match the search text" };
+            Overlay::new(&title, vec![line.to_string()])
+        } else {
+            Overlay::new(&title, project_journal::list_lines(&self.journal_list)).with_selection()
+        };
+        overlay = overlay.with_action(OverlayAction::Journal);
+        if let (Some(index), Some(_)) = (keep_selected, overlay.selected) {
+            overlay.selected = Some(index.min(overlay.lines.len().saturating_sub(1)));
+        }
+        self.overlay = Some(overlay);
+        Ok(())
+    }
+
+    fn show_journal_entry(&mut self, index: usize) {
+        let Some(entry) = self.journal_list.get(index) else {
+            return;
+        };
+        let lines = project_journal::entry_lines(entry, 72);
+        self.overlay = Some(Overlay::new("Journal entry (e: edit  Esc: back)", lines).with_action(OverlayAction::JournalEntry));
+        self.journal_viewing = Some(index);
+    }
+
+    fn edit_journal_entry(&mut self, index: usize) {
+        if let Some(entry) = self.journal_list.get(index) {
+            self.journal_edit = Some(JournalEdit { id: Some(entry.id), text: entry.text.clone() });
+            self.overlay = None;
+        }
+    }
+
+    /// Every tag with the number of notes carrying it
+    fn show_tags(&mut self, keep: Option<usize>) {
+        let usage = tags::usage(&self.all_notes);
+        if usage.is_empty() {
+            self.messages.info("No #tags in any note yet");
+            return;
+        }
+        let width = usage.iter().map(|(t, _)| t.chars().count()).max().unwrap_or(0) + 1;
+        let lines = usage.iter()
+            .map(|(tag, count)| format!("{:<width$}  {} note(s)", format!("#{}", tag), count, width = width))
+            .collect();
+        self.tag_list = usage.into_iter().map(|(tag, _)| tag).collect();
+        let mut overlay = Overlay::new("Tags (r: rename  m: merge into another  d: remove  Esc: close)", lines)
+            .with_selection()
+            .with_action(OverlayAction::TagManager);
+        if let Some(i) = keep {
+            overlay.selected = Some(i.min(self.tag_list.len() - 1));
+        }
+        self.overlay = Some(overlay);
+    }
+
+    /// Which tag `from` is merged into
+    fn show_tag_merge_picker(&mut self, from: String) {
+        let lines: Vec<String> = self.tag_list.iter().filter(|t| **t != from).map(|t| format!("#{}", t)).collect();
+        if lines.is_empty() {
+            self.messages.info("There is no other tag to merge into");
+            return;
+        }
+        let title = format!("Merge #{} into (Enter: preview  Esc: back)", from);
+        self.tag_change = Some(TagChange { from, into: None, note_ids: Vec::new() });
+        self.overlay = Some(Overlay::new(&title, lines)
+            .with_selection()
+            .with_action(OverlayAction::TagMergeInto));
+    }
+
+    /// The notes a tag rename / merge / removal would change, before anything is written
+    fn preview_tag_change(&mut self, from: String, into: Option<String>) {
+        let note_ids: Vec<String> = self.all_notes.iter()
+            .filter(|n| n.tags.contains(&from))
+            .map(|n| n.id.clone())
+            .collect();
+        let lines = note_ids.iter()
+            .filter_map(|id| self.all_notes.iter().find(|n| n.id == *id))
+            .map(|n| {
+                let skipped = if self.read_only.contains(&n.id) { "  (read-only, left alone)" } else { "" };
+                format!("{}{}", n.title, skipped)
+            })
+            .collect();
+        let change = TagChange { from, into, note_ids };
+        let existing = change.into.as_ref().is_some_and(|into| self.tag_list.contains(into));
+        let what = match &change.into {
+            Some(into) => format!("#{} into #{}", change.from, into),
+            None => format!("#{}", change.from),
+        };
+        let title = format!("{} {} in {} note(s) (Enter: apply  Esc: back)", change.verb(existing), what, change.note_ids.len());
+        self.tag_change = Some(change);
+        self.overlay = Some(Overlay::new(&title, lines).with_action(OverlayAction::TagPreview));
+    }
+
+    /// Rewrite the #tag in every note that carries it and reindex them
+    fn apply_tag_change(&mut self, change: TagChange) -> Result<()> {
+        self.auto_save_current_note()?;
+        let (mut changed, mut skipped) = (0, 0);
+        for note in self.all_notes.iter_mut().filter(|n| change.note_ids.contains(&n.id)) {
+            if self.read_only.contains(&note.id) {
+                skipped += 1;
+                continue;
+            }
+            let (content, count) = tags::rewrite(&note.content, &change.from, change.into.as_deref());
+            if count == 0 {
+                continue;
+            }
+            note.content = content;
+            note.tags = Self::extract_tags(&note.content);
+            self.notes.update_note(note)?;
+            changed += 1;
+        }
+
+        // The open note shows the rewritten text
+        if let Some(open) = self.selected_note.as_ref().map(|n| n.id.clone()) {
+            if let Some(note) = self.all_notes.iter().find(|n| n.id == open).cloned() {
+                if note.content != self.editor.get_text() {
+                    self.editor.replace_text(&note.content);
+                }
+                self.set_open_note(Some(note));
+            }
+        }
+        if let Err(e) = self.search_index.sync(&self.all_notes) {
+            tracing::warn!(error = %e, "indexing failed");
+        }
+        self.update_search()?;
+        tracing::info!(from = %change.from, into = ?change.into, changed, skipped, "tag changed");
+        self.usage.record_feature("tag manager");
+
+        let done = match &change.into {
+            Some(into) => format!("#{} is now #{} in {} note(s)", change.from, into, changed),
+            None => format!("Removed #{} from {} note(s)", change.from, changed),
+        };
+        let skipped = if skipped > 0 { format!(" ({} read-only note(s) left alone)", skipped) } else { String::new() };
+        self.messages.info(format!("{}{}", done, skipped));
+        Ok(())
+    }
+
+    /// Work out the tags to offer the open note (see tag_suggest.rs)
+    fn suggest_tags(&mut self) {
+        let Some(note) = self.selected_note.as_ref() else {
+            return;
+        };
+        self.similarity.sync(&self.all_notes, &self.config.stopwords);
+        let dismissed = self.dismissed_tags.get(&note.id).cloned().unwrap_or_default();
+        let suggestions = tag_suggest::suggest(note, &self.all_notes, &self.similarity, &dismissed);
+        self.tag_suggestions = Some((note.id.clone(), suggestions));
+    }
+
+    /// Work out the notes most like the open one
+    fn refresh_related(&mut self) {
+        let Some(note) = self.selected_note.as_ref() else {
+            return;
+        };
+        self.similarity.sync(&self.all_notes, &self.config.stopwords);
+        self.related = Some((note.id.clone(), self.similarity.similar(&note.id, RELATED_NOTES)));
+    }
+
+    /// Whether the related notes are for some other note than the open one
+    fn related_stale(&self) -> bool {
+        let open = self.selected_note.as_ref().map(|n| n.id.as_str());
+        open.is_some() && self.related.as_ref().map(|(id, _)| id.as_str()) != open
+    }
+
+    /// The related notes of the open note, if they have been worked out
+    fn open_related(&self) -> &[(String, f64)] {
+        match (&self.related, &self.selected_note) {
+            (Some((id, related)), Some(note)) if *id == note.id => related,
+            _ => &[],
+        }
+    }
+
+    /// Notes most like the open one, best first; Enter opens one
+    fn show_related(&mut self) {
+        if self.selected_note.is_none() {
+            self.messages.info("Open a note to see the notes related to it");
+            return;
+        }
+        if self.related_stale() {
+            self.refresh_related();
+        }
+        let lines: Vec<String> = self.open_related().iter()
+            .filter_map(|(id, score)| self.all_notes.iter().find(|n| n.id == *id).map(|n| (n, score)))
+            .map(|(note, score)| format!("{:>3.0}%  {}", score * 100.0, note.title))
+            .collect();
+        if lines.is_empty() {
+            self.messages.info("No note shares enough words with this one");
+            return;
+        }
+        let title = format!("Related to \"{}\" (Enter: open  Esc: close)", self.selected_note.as_ref().map_or("", |n| n.title.as_str()));
+        self.overlay = Some(Overlay::new(&title, lines)
+            .with_selection()
+            .with_action(OverlayAction::Related));
+    }
+
+    /// A note open with the editor focused counts as visited (see recent.rs)
+    fn record_visit(&mut self) {
+        if self.focus_area != FocusArea::Editor {
+            return;
+        }
+        if let Some(note) = &self.selected_note {
+            self.note_history.visit(&note.id);
+        }
+    }
+
+    /// Where the editor cursor is, when a note is open
+    fn location(&self) -> Option<Jump> {
+        self.selected_note.as_ref().map(|note| Jump {
+            note_id: note.id.clone(),
+            pos: self.editor.get_cursor_position(),
+            line: self.editor.cursor_pos.row,
+        })
+    }
+
+    /// Remember the place the editor left if the last event jumped away from it (see jumps.rs)
+    fn track_jumps(&mut self, moving: bool) {
+        let navigating = std::mem::take(&mut self.jump_navigating);
+        if self.focus_area != FocusArea::Editor {
+            return;
+        }
+        let Some(here) = self.location() else {
+            return;
+        };
+        if let Some(last) = self.last_location.take() {
+            if !moving && !navigating && last.is_jump_to(&here) {
+                self.jump_list.record(last);
+            }
+        }
+        self.last_location = Some(here);
+    }
+
+    /// Go back (or forward) through the jump list
+    fn step_jump(&mut self, forward: bool) -> Result<()> {
+        let Some(here) = self.location() else {
+            self.messages.info("Open a note to move through the jump list");
+            return Ok(());
+        };
+        let exists = |id: &str| self.all_notes.iter().any(|n| n.id == id);
+        let target = if forward { self.jump_list.forward(here.clone(), exists) } else { self.jump_list.back(here.clone(), exists) };
+        let Some(target) = target else {
+            self.messages.info(if forward { "No later place in the jump list" } else { "No earlier place in the jump list" });
+            return Ok(());
+        };
+        if target.note_id != here.note_id {
+            if let Some(note) = self.all_notes.iter().find(|n| n.id == target.note_id).cloned() {
+                self.open_note(note)?;
+            }
+        }
+        self.editor.set_cursor_char(target.pos);
+        self.focus_area = FocusArea::Editor;
+        self.jump_navigating = true;
+        Ok(())
+    }
+
+    /// Quick switcher: recent notes first, then the rest, narrowed by the typed filter
+    fn show_switcher(&mut self) {
+        let filter = self.switcher.clone().unwrap_or_default();
+        let rows = recent::switcher(&self.all_notes, self.note_history.recent(), &filter);
+        let recent = self.note_history.recent();
+        let lines: Vec<String> = if rows.is_empty() {
+            vec!["No note title matches".to_string()]
+        } else {
+            rows.iter()
+                .map(|n| format!("{} {}", if recent.contains(&n.id) { "◷" } else { " " }, n.title))
+                .collect()
+        };
+        self.switcher_rows = rows.into_iter().map(|n| n.id.clone()).collect();
+        let title = format!("Switch to: {}▏ (Enter: open  Esc: close)", filter);
+        self.overlay = Some(Overlay::new(&title, lines)
+            .with_selection()
+            .with_action(OverlayAction::QuickSwitcher));
+    }
+
+    /// Search history: past queries, most recent first, narrowed by the typed filter
+    fn show_search_history(&mut self) {
+        let filter = self.history_filter.clone().unwrap_or_default();
+        self.history_rows = self.search_history.matching(&filter).into_iter().map(str::to_string).collect();
+        let lines = if self.history_rows.is_empty() {
+            vec![if self.search_history.queries().is_empty() { "No searches yet" } else { "No earlier search matches" }.to_string()]
+        } else {
+            self.history_rows.clone()
+        };
+        let title = format!("Earlier searches: {}▏ (Enter: search  Esc: close)", filter);
+        self.overlay = Some(Overlay::new(&title, lines)
+            .with_selection()
+            .with_action(OverlayAction::SearchHistory));
+    }
+
+    /// Date filter: a typed range first (once it reads as one), then any date and the named
+    /// periods, for when notes were updated and when they were created
+    fn show_date_filter(&mut self) {
+        let range = self.date_range.clone().unwrap_or_default();
+        let mut rows: Vec<(String, String)> = Vec::new();
+        let range = range.trim();
+        if !range.is_empty() {
+            for field in date_filter::FIELDS {
+                if DateFilter::parse(field, range).is_some() {
+                    rows.push((format!("{} {}", field, range), format!("{}:{}", field, range)));
+                }
+            }
+        }
+        rows.push(("any date".to_string(), String::new()));
+        for field in date_filter::FIELDS {
+            for (value, label) in date_filter::PRESETS {
+                rows.push((format!("{} {}", field, label), format!("{}:{}", field, value)));
+            }
+        }
+        let lines = rows.iter().map(|(label, term)| format!("{:<30} {}", label, term)).collect();
+        self.date_rows = rows.into_iter().map(|(_, term)| term).collect();
+        let title = format!("Dates: {}▏ (type a range like 2024-03..2024-05  Enter: filter  Esc: close)", range);
+        self.overlay = Some(Overlay::new(&title, lines)
+            .with_selection()
+            .with_action(OverlayAction::DateFilter));
+    }
+
+    /// Go back (or forward) to the note visited before (after) the current one
+    fn step_note_history(&mut self, forward: bool) -> Result<()> {
+        self.record_visit();
+        let exists = |id: &str| self.all_notes.iter().any(|n| n.id == id);
+        let id = if forward { self.note_history.forward(exists) } else { self.note_history.back(exists) };
+        let Some(note) = id.and_then(|id| self.all_notes.iter().find(|n| n.id == id)).cloned() else {
+            self.messages.info(if forward { "No note to go forward to" } else { "No earlier note to go back to" });
+            return Ok(());
+        };
+        self.open_note(note.clone())?;
+        self.focus_area = FocusArea::Editor;
+        self.messages.info(format!("{}: {}", if forward { "Forward" } else { "Back" }, note.title));
+        Ok(())
+    }
+
+    /// Broken links, orphan notes and unused attachments across the vault
+    fn show_link_check(&mut self, keep: Option<usize>) -> Result<()> {
+        self.auto_save_current_note()?;
+        let trashed = self.notes.trashed_contents()?;
+        self.link_problems = link_check::check(&self.all_notes, &self.zettel_ids, &self.notes_dir, &trashed);
+        self.attachment_delete = None;
+        if self.link_problems.is_empty() {
+            self.overlay = None;
+            self.messages.info("No broken links, orphan notes or unused attachments");
+            return Ok(());
+        }
+        let lines: Vec<String> = self.link_problems.iter().map(|p| p.line()).collect();
+        let title = format!(
+            "Link check: {} (Enter: create / open  o: open the linking note  d: delete attachment)",
+            link_check::summary(&self.link_problems)
+        );
+        let len = lines.len();
+        let mut overlay = Overlay::new(&title, lines)
+            .with_selection()
+            .with_action(OverlayAction::LinkCheck);
+        if let Some(i) = keep {
+            overlay.selected = Some(i.min(len - 1));
+        }
+        self.overlay = Some(overlay);
+        Ok(())
+    }
+
+    /// The quick fix for a link check row: Enter creates a missing target, `o` opens the
+    /// note with the broken link; either opens an orphan or an attachment
+    fn fix_link_problem(&mut self, problem: link_check::Problem, create: bool) -> Result<()> {
+        match problem {
+            link_check::Problem::BrokenLink { target, .. } if create => {
+                self.overlay = None;
+                self.open_new_note(&target, "")?;
+                self.usage.record_feature("link check");
+            }
+            link_check::Problem::BrokenLink { note_id: id, .. } | link_check::Problem::Orphan { note_id: id, .. } => {
+                if let Some(note) = self.all_notes.iter().find(|n| n.id == id).cloned() {
+                    self.overlay = None;
+                    self.open_note(note)?;
+                    self.focus_area = FocusArea::Editor;
+                }
+            }
+            link_check::Problem::UnusedAttachment(attachment) => {
+                match attachments::open(&attachment.path) {
+                    Ok(()) => self.messages.info(format!("Opened {}", attachment.name)),
+                    Err(e) => self.messages.error(format!("Could not open {}: {}", attachment.name, e)),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Open a related note (by id) in the editor
+    fn open_related_note(&mut self, note_id: &str) -> Result<()> {
+        let Some(note) = self.all_notes.iter().find(|n| n.id == note_id).cloned() else {
+            return Ok(());
+        };
+        self.overlay = None;
+        self.open_note(note)?;
+        self.focus_area = FocusArea::Editor;
+        self.usage.record_feature("related notes");
+        Ok(())
+    }
+
+    /// The suggestions for the open note, if they have been worked out
+    fn open_tag_suggestions(&self) -> &[Suggestion] {
+        match (&self.tag_suggestions, &self.selected_note) {
+            (Some((id, suggestions)), Some(note)) if *id == note.id => suggestions,
+            _ => &[],
+        }
+    }
+
+    /// Suggested tags for the open note, worked out now if the chips aren't showing
+    fn show_tag_suggestions(&mut self, keep: Option<usize>) {
+        if self.selected_note.is_none() {
+            self.messages.info("Open a note to get tag suggestions");
+            return;
+        }
+        if keep.is_none() && self.open_tag_suggestions().is_empty() {
+            self.suggest_tags();
+        }
+        let suggestions = self.open_tag_suggestions();
+        if suggestions.is_empty() {
+            self.overlay = None;
+            if keep.is_none() {
+                self.messages.info("No tag suggestions for this note");
+            }
+            return;
+        }
+        let width = suggestions.iter().map(|s| s.tag.chars().count()).max().unwrap_or(0) + 1;
+        let lines: Vec<String> = suggestions.iter()
+            .map(|s| format!("{:<width$}  {}", format!("#{}", s.tag), s.describe(), width = width))
+            .collect();
+        let len = lines.len();
+        let mut overlay = Overlay::new("Suggested tags (Enter: add  a: add all  d: dismiss  Esc: close)", lines)
+            .with_selection()
+            .with_action(OverlayAction::TagSuggestions);
+        if let Some(i) = keep {
+            overlay.selected = Some(i.min(len - 1));
+        }
+        self.overlay = Some(overlay);
+    }
+
+    /// Add one suggested tag (None: all of them) to the open note
+    fn accept_tag_suggestions(&mut self, index: Option<usize>) -> Result<()> {
+        if !self.check_editable() {
+            return Ok(());
+        }
+        let suggestions = self.open_tag_suggestions().to_vec();
+        let accepted: Vec<Suggestion> = match index {
+            Some(i) => suggestions.get(i).cloned().into_iter().collect(),
+            None => suggestions.clone(),
+        };
+        if accepted.is_empty() {
+            return Ok(());
+        }
+        let mut text = self.editor.get_text();
+        for suggestion in &accepted {
+            text = tag_suggest::add_tag(&text, &suggestion.tag);
+        }
+        self.editor.replace_text(&text);
+        self.auto_save_current_note()?;
+        self.update_search()?;
+        if let Some((_, list)) = self.tag_suggestions.as_mut() {
+            list.retain(|s| !accepted.contains(s));
+        }
+        self.usage.record_feature("tag suggestion");
+        let names: Vec<String> = accepted.iter().map(|s| format!("#{}", s.tag)).collect();
+        self.messages.info(format!("Tagged {}", names.join(" ")));
+        // Adding a tag is a save; the rest stay on offer without waiting for a new round
+        self.refresh_related();
+        self.similarity_due = None;
+        self.show_tag_suggestions(Some(index.unwrap_or(0)));
+        Ok(())
+    }
+
+    /// Turn down a suggestion for the open note until the session ends
+    fn dismiss_tag_suggestion(&mut self, index: usize) {
+        let Some(note_id) = self.selected_note.as_ref().map(|n| n.id.clone()) else {
+            return;
+        };
+        let Some(suggestion) = self.open_tag_suggestions().get(index).cloned() else {
+            return;
+        };
+        self.dismissed_tags.entry(note_id).or_default().insert(suggestion.tag.clone());
+        if let Some((_, list)) = self.tag_suggestions.as_mut() {
+            list.retain(|s| *s != suggestion);
+        }
+        self.messages.info(format!("Won't suggest #{} for this note again this session", suggestion.tag));
+        self.show_tag_suggestions(Some(index));
+    }
+
+    /// Front matter of the open note as the editor has it
+    fn open_front_matter(&self) -> FrontMatter {
+        parse_front_matter(&self.editor.get_text()).unwrap_or_default()
+    }
+
+    /// The open note's front matter as a key / value table, with a line for adding a field
+    fn show_metadata(&mut self, keep: Option<usize>) {
+        if self.selected_note.is_none() {
+            self.messages.info("Open a note to edit its metadata");
+            return;
+        }
+        let fields = self.open_front_matter().fields;
+        let width = fields.keys().map(|k| k.chars().count()).max().unwrap_or(0);
+        let mut lines: Vec<String> = fields.iter()
+            .map(|(key, value)| format!("{:<width$}  {}", key, value, width = width))
+            .collect();
+        lines.push("+ add a field".to_string());
+        let len = lines.len();
+        let mut overlay = Overlay::new("Metadata (Enter: edit  a: add  d: remove  Esc: close)", lines)
+            .with_selection()
+            .with_action(OverlayAction::Metadata);
+        if let Some(i) = keep {
+            overlay.selected = Some(i.min(len - 1));
+        }
+        self.overlay = Some(overlay);
+    }
+
+    /// Type a field as "key: value" in the editor header; None starts a new one
+    fn start_field_edit(&mut self, key: Option<String>) {
+        if !self.check_editable() {
+            return;
+        }
+        let text = match &key {
+            Some(key) => format!("{}: {}", key, self.open_front_matter().get(key).unwrap_or("")),
+            None => String::new(),
+        };
+        self.field_edit = Some(FieldEdit { original: key, text });
+    }
+
+    /// Write a typed field into the note's front matter; an empty value removes it, a new
+    /// key renames the field
+    fn save_field(&mut self, edit: FieldEdit) -> Result<()> {
+        let Some((key, value)) = edit.text.split_once(':').map(|(k, v)| (k.trim().to_lowercase(), v.trim().to_string())) else {
+            self.messages.warn("Type the field as key: value");
+            self.field_edit = Some(edit);
+            return Ok(());
+        };
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            self.messages.warn("A field name can't be empty or have spaces");
+            self.field_edit = Some(edit);
+            return Ok(());
+        }
+        let mut text = self.editor.get_text();
+        if let Some(original) = edit.original.as_deref().filter(|o| *o != key) {
+            text = front_matter::set_field(&text, original, None);
+        }
+        text = front_matter::set_field(&text, &key, (!value.is_empty()).then_some(value.as_str()));
+        self.editor.replace_text(&text);
+        self.auto_save_current_note()?;
+        self.usage.record_feature("metadata edited");
+        self.messages.info(if value.is_empty() { format!("Removed {}", key) } else { format!("Set {}: {}", key, value) });
+
+        let keys: Vec<String> = self.open_front_matter().fields.into_keys().collect();
+        self.show_metadata(keys.iter().position(|k| *k == key));
+        Ok(())
+    }
+
+    /// The quick actions for the editor selection
+    fn show_selection_actions(&mut self) {
+        if self.selected_note.is_none() || !self.editor.has_selection() {
+            self.messages.info("Select some text first");
+            return;
+        }
+        let lines = SELECTION_ACTIONS.iter().map(|(key, label)| format!("{}  {}", key, label)).collect();
+        self.overlay = Some(Overlay::new("Selection (key or Enter: run  Esc: cancel)", lines)
+            .with_selection()
+            .with_action(OverlayAction::SelectionActions));
+    }
+
+    fn run_selection_action(&mut self, index: usize) -> Result<()> {
+        match SELECTION_ACTIONS.get(index).map(|(key, _)| *key) {
+            Some('c') => {
+                self.editor.copy_selection()?;
+                self.messages.info("Copied");
+            }
+            Some('o') => self.show_selection_code_picker(),
+            Some('n') => self.annotate()?,
+            Some('h') => self.highlight_selection()?,
+            Some('/') => self.search_for_selection()?,
+            Some('l') => self.linked_note_from_selection()?,
+            Some('x') => self.extract_note()?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn show_selection_code_picker(&mut self) {
+        let ids = self.code_ids_by_name();
+        if ids.is_empty() {
+            self.messages.info("No codes yet");
+            return;
+        }
+        let lines = ids.iter().map(|id| self.code_name(id)).collect();
+        self.overlay = Some(Overlay::new("Code the selection as (Enter: apply  Esc: cancel)", lines)
+            .with_selection()
+            .with_action(OverlayAction::SelectionCode));
+    }
+
+    /// Apply a code to the editor selection (grown to whole lines with line anchors)
+    fn code_selection(&mut self, code_id: &str) -> Result<()> {
+        let (Some(note_id), Some((start, end))) = (self.selected_note.as_ref().map(|n| n.id.clone()), self.editor.selection_range()) else {
+            return Ok(());
+        };
+        let coder = self.config.coder_name();
+        let (start, end, lines) = self.anchor(&self.editor.get_text(), start, end);
+        let id = self.codes.apply_code(code_id, &note_id, start, end, None, Some(&coder), lines)?;
+        audit::record(&self.notes_dir, &AuditEntry {
+            at: chrono::Utc::now(),
+            action: "code_applied",
+            coder: &coder,
+            note_id: &note_id,
+            code_id,
+            start,
+            end,
+            detail: None,
+        })?;
+        self.coding_history.push(Step {
+            after: self.codes.get_applications(&[id])?,
+            ..Step::new(format!("Code selection as {}", self.code_name(code_id)))
+        });
+        self.usage.record_feature("code selection");
+        let message = format!("Coded as {} ({} undoes)", self.code_name(code_id), keymap::keys_for(Command::UndoCoding).join("/"));
+        self.messages.info(message);
+        self.needs_redraw = true;
+        Ok(())
+    }
+
+    /// Search every note for the selected text (as a phrase when it has several words)
+    fn search_for_selection(&mut self) -> Result<()> {
+        let Some(text) = self.editor.get_selection() else {
+            return Ok(());
+        };
+        let words = text.replace('"', " ").split_whitespace().collect::<Vec<_>>().join(" ");
+        if words.is_empty() {
+            return Ok(());
+        }
+        self.auto_save_current_note()?;
+        self.search_query = if words.contains(' ') { format!("\"{}\"", words) } else { words };
+        self.update_search()?;
+        self.focus_area = FocusArea::SearchBar;
+        Ok(())
+    }
+
+    /// New note holding the selected text, ending with a link back to the note it came from
+    fn linked_note_from_selection(&mut self) -> Result<()> {
+        let (Some(source), Some(text)) = (self.selected_note.clone(), self.editor.get_selection()) else {
+            return Ok(());
+        };
+        let title = titles::derive(&text).unwrap_or_else(|| format!("From {}", source.title));
+        let content = format!("{}\n\nFrom [[{}]]\n", text.trim_end(), source.title);
+        self.auto_save_current_note()?;
+        self.persist_undo_history();
+        let note = self.notes.create_note(&title, &content)?;
+        self.usage.record_feature("note from selection");
+
+        self.all_notes.push(note.clone());
+        self.update_search()?;
+        if let Some(index) = self.filtered_notes.iter().position(|n| n.id == note.id) {
+            self.selected_note_index = index;
+        }
+        self.editor.set_text(&content);
+        self.editor.history = UndoHistory::default();
+        self.set_open_note(Some(note));
+        self.reading = false;
+        self.focus_area = FocusArea::Editor;
+        self.messages.info(format!("Created \"{}\", linked back to \"{}\"", title, source.title));
+        Ok(())
+    }
+
+    /// Move the selection into a new note, leave a `[[link]]` to it in its place and open it
+    fn extract_note(&mut self) -> Result<()> {
+        let (Some(source), Some((start, end)), Some(text)) =
+            (self.selected_note.clone(), self.editor.selection_range(), self.editor.get_selection()) else {
+            self.messages.info("Select the text to extract first");
+            return Ok(());
+        };
+        if !self.check_editable() {
+            return Ok(());
+        }
+
+        // Links go by title, so the new note needs one no other note has
+        let base = titles::derive(&text).unwrap_or_else(|| format!("From {}", source.title)).replace(['[', ']'], "");
+        let mut title = base.clone();
+        let mut n = 1;
+        while self.all_notes.iter().any(|note| note.title == title) {
+            n += 1;
+            title = format!("{} ({})", base, n);
+        }
+
+        let link = format!("[[{}]]", title);
+        let chars: Vec<char> = self.editor.get_text().chars().collect();
+        let mut remaining: String = chars[..start].iter().collect();
+        remaining.push_str(&link);
+        remaining.extend(&chars[end..]);
+        self.editor.replace_text(&remaining);
+        self.editor.set_cursor_char(start + link.chars().count());
+        self.auto_save_current_note()?;
+        self.persist_undo_history();
+
+        let content = format!("{}\n", text.trim());
+        let note = self.notes.create_note(&title, &content)?;
+        self.usage.record_feature("note extracted");
+        tracing::info!(from = %source.id, to = %note.id, chars = end - start, "note extracted");
+
+        self.all_notes.push(note.clone());
+        self.update_search()?;
+        if let Some(index) = self.filtered_notes.iter().position(|n| n.id == note.id) {
+            self.selected_note_index = index;
+        }
+        self.editor.set_text(&content);
+        self.editor.history = UndoHistory::default();
+        self.set_open_note(Some(note));
+        self.reading = false;
+        self.focus_area = FocusArea::Editor;
+        self.messages.info(format!("Extracted \"{}\" - \"{}\" links to it", title, source.title));
+        Ok(())
+    }
+
+    fn show_highlight_code_picker(&mut self, index: usize) {
+        let ids = self.code_ids_by_name();
+        if ids.is_empty() {
+            self.messages.info("No codes yet - create codes before converting highlights");
+            return;
+        }
+        let lines = ids.iter()
+            .map(|id| self.codes.get_code(id).map(|c| c.name.clone()).unwrap_or_else(|| id.clone()))
+            .collect();
+
+        self.overlay = Some(Overlay::new("Convert highlight to code (Enter: apply  Esc: back)", lines)
+            .with_selection()
+            .with_action(OverlayAction::HighlightCode));
+        self.highlight_converting = Some(index);
+    }
+
+    /// A span about to be coded, as config segment_anchors stores it: as it is, or grown to
+    /// whole lines along with the line numbers it is anchored to
+    fn anchor(&self, text: &str, start: usize, end: usize) -> (usize, usize, Option<(usize, usize)>) {
+        match self.config.segment_anchors {
+            anchors::Anchor::Chars => (start, end, None),
+            anchors::Anchor::Lines => {
+                let (start, end, lines) = anchors::snap(text, start, end);
+                (start, end, Some(lines))
+            }
+        }
+    }
+
+    /// Apply a code to a highlighted span (grown to whole sentences with config
+    /// code_whole_sentences); the highlight has done its job and goes
+    fn convert_highlight(&mut self, index: usize, code_id: &str) -> Result<()> {
+        let Some(highlight) = self.highlight_list.get(index).cloned() else {
+            return Ok(());
+        };
+        let coder = self.config.coder_name();
+        let (start, end) = if self.config.code_whole_sentences {
+            let chars: Vec<char> = self.editor.rope.chars().collect();
+            text_units::Unit::Sentence.expand(&chars, highlight.start_offset, highlight.end_offset)
+        } else {
+            (highlight.start_offset, highlight.end_offset)
+        };
+        let (start, end, lines) = self.anchor(&self.editor.get_text(), start, end);
+        let id = self.codes.apply_code(code_id, &highlight.note_id, start, end, None, Some(&coder), lines)?;
+        self.highlights.remove(highlight.id)?;
+        self.coding_history.push(Step {
+            after: self.codes.get_applications(&[id])?,
+            highlight: Some(highlight.clone()),
+            ..Step::new(format!("Code highlight as {}", self.code_name(code_id)))
+        });
+
+        let entry = AuditEntry {
+            at: chrono::Utc::now(),
+            action: "highlight_converted",
+            coder: &coder,
+            note_id: &highlight.note_id,
+            code_id,
+            start,
+            end,
+            detail: Some(highlight.color.name().to_string()),
+        };
+        audit::record(&self.notes_dir, &entry)?;
+
+        self.show_highlights(Some(index))?;
+        let name = self.codes.get_code(code_id).map(|c| c.name.clone()).unwrap_or_else(|| code_id.to_string());
+        self.messages.info(format!("Coded highlight as {}", name));
+        Ok(())
+    }
+
+    /// First step of a code merge / recode: pick the code segments move away from
+    fn start_recode(&mut self, mut recode: Recode) {
+        if self.codes.get_all_codes().len() < 2 {
+            self.messages.info(format!("{} needs at least two codes", recode.kind.verb()));
+            return;
+        }
+        if recode.note_ids.is_empty() {
+            self.messages.info("No notes listed - nothing to recode");
+            return;
+        }
+        recode.choices = self.code_ids_by_name();
+        let lines = self.code_names(&recode.choices);
+        let title = match recode.kind {
+            recode::Kind::Merge => "Merge which code away? (Enter: pick  Esc: cancel)".to_string(),
+            recode::Kind::Recode => format!("Recode which code in {} listed note(s)? (Enter: pick  Esc: cancel)", recode.note_ids.len()),
+        };
+        self.overlay = Some(Overlay::new(&title, lines)
+            .with_selection()
+            .with_action(OverlayAction::RecodeFrom));
+        self.recode = Some(recode);
+    }
+
+    fn show_recode_into(&mut self, mut recode: Recode) {
+        let Some(from) = recode.from.clone() else {
+            return;
+        };
+        recode.choices = self.code_ids_by_name().into_iter().filter(|id| *id != from).collect();
+        let lines = self.code_names(&recode.choices);
+        let title = format!("{} \"{}\" into which code? (Enter: pick  Esc: cancel)", recode.kind.verb(), self.code_name(&from));
+        self.overlay = Some(Overlay::new(&title, lines)
+            .with_selection()
+            .with_action(OverlayAction::RecodeInto));
+        self.recode = Some(recode);
+    }
+
+    /// Summary of what the merge / recode will change, before anything does
+    fn confirm_recode(&mut self, recode: Recode) -> Result<()> {
+        let (Some(from), Some(into)) = (recode.from.as_deref(), recode.into.as_deref()) else {
+            return Ok(());
+        };
+        // The open note's segments are saved first so the counts (and the change) see them
+        self.auto_save_current_note()?;
+        let applications = self.codes.get_all_code_applications()?;
+        let (segments, notes) = recode::count(from, &recode.note_ids, &self.all_notes, &applications);
+        if segments == 0 && recode.kind == recode::Kind::Recode {
+            self.messages.info(format!("\"{}\" isn't applied in the listed notes", self.code_name(from)));
+            return Ok(());
+        }
+        let lines = recode::summary_lines(
+            recode.kind,
+            &self.code_name(from),
+            &self.code_name(into),
+            segments,
+            notes,
+            self.codes.child_count(from),
+        );
+        self.overlay = Some(Overlay::new(&format!("{} codes", recode.kind.verb()), lines)
+            .with_action(OverlayAction::RecodeConfirm));
+        self.recode = Some(recode);
+        Ok(())
+    }
+
+    /// Move the segments over (and for a merge, delete the old code), logging each one
+    fn apply_recode(&mut self, recode: Recode) -> Result<()> {
+        let (Some(from), Some(into)) = (recode.from.as_deref(), recode.into.as_deref()) else {
+            return Ok(());
+        };
+        let (from_name, into_name) = (self.code_name(from), self.code_name(into));
+        let coder = self.config.coder_name();
+        let action = match recode.kind {
+            recode::Kind::Merge => "code_merged",
+            recode::Kind::Recode => "segment_recoded",
+        };
+        let detail = format!("{} -> {}", from, into);
+
+        let mut step = Step::new(format!("{} \"{}\" into \"{}\"", recode.kind.verb(), from_name, into_name));
+        if recode.kind == recode::Kind::Merge {
+            step.merged = self.codes.get_code(from).cloned().map(|code| Merged {
+                code,
+                into: into.to_string(),
+                children: self.codes.get_all_codes().iter()
+                    .filter(|c| c.parent_id.as_deref() == Some(from))
+                    .map(|c| c.id.clone())
+                    .collect(),
+            });
+        }
+
+        // Segments stored with the notes
+        let mut moved = 0;
+        for note in self.all_notes.iter_mut().filter(|n| recode.note_ids.contains(&n.id)) {
+            let before: Vec<_> = note.codes.iter().filter(|s| s.code_id == from).cloned().collect();
+            let segments = note.codes.clone();
+            if recode::recode_segments(&mut note.codes, from, into) == 0 {
+                continue;
+            }
+            self.notes.update_note(note)?;
+            step.segments.push((note.id.clone(), segments, note.codes.clone()));
+            for segment in &before {
+                let entry = AuditEntry {
+                    at: chrono::Utc::now(),
+                    action,
+                    coder: &coder,
+                    note_id: &note.id,
+                    code_id: into,
+                    start: segment.start_offset,
+                    end: segment.end_offset,
+                    detail: Some(detail.clone()),
+                };
+                audit::record(&self.notes_dir, &entry)?;
+            }
+            moved += before.len();
+        }
+
+        // Applications in the codes database
+        let applications = match recode.kind {
+            recode::Kind::Merge => self.codes.merge_code(from, into)?,
+            recode::Kind::Recode => self.codes.recode(from, into, &recode.note_ids)?,
+        };
+        for app in &applications {
+            let entry = AuditEntry::for_application(action, &coder, app).with_detail(detail.clone());
+            audit::record(&self.notes_dir, &entry)?;
+        }
+        moved += applications.len();
+        step.after = applications.iter().map(|app| CodeApplication { code_id: into.to_string(), ..app.clone() }).collect();
+        step.before = applications;
+        self.coding_history.push(step);
+
+        // The open note keeps its text; only its segments changed
+        if let Some(open) = self.selected_note.as_ref().map(|n| n.id.clone()) {
+            self.set_open_note(self.all_notes.iter().find(|n| n.id == open).cloned());
+        }
+        self.update_search()?;
+        tracing::info!(kind = ?recode.kind, from, into, moved, "codes recoded");
+        self.messages.info(match recode.kind {
+            recode::Kind::Merge => format!("Merged \"{}\" into \"{}\" ({} segment(s) moved)", from_name, into_name, moved),
+            recode::Kind::Recode => format!("Recoded {} segment(s) from \"{}\" to \"{}\"", moved, from_name, into_name),
+        });
+        Ok(())
+    }
+
+    /// Put the state a coding step left (`undo` false) or found (`undo` true) back in place
+    fn replay_coding(&mut self, step: &Step, undo: bool) -> Result<()> {
+        let (from, to) = if undo { (&step.after, &step.before) } else { (&step.before, &step.after) };
+        // A merged code has to exist again before its applications can point at it
+        if let (true, Some(merged)) = (undo, &step.merged) {
+            self.codes.restore_code(&merged.code, &merged.children)?;
+        }
+        let remove: Vec<i64> = from.iter().map(|app| app.id).collect();
+        self.codes.replace_applications(&remove, to)?;
+        if let (false, Some(merged)) = (undo, &step.merged) {
+            self.codes.merge_code(&merged.code.id, &merged.into)?;
+        }
+
+        for (note_id, before, after) in &step.segments {
+            if let Some(note) = self.all_notes.iter_mut().find(|n| n.id == *note_id) {
+                note.codes = if undo { before.clone() } else { after.clone() };
+                self.notes.update_note(note)?;
+            }
+        }
+        if let Some(highlight) = &step.highlight {
+            if undo {
+                self.highlights.restore(highlight)?;
+            } else {
+                self.highlights.remove(highlight.id)?;
+            }
+        }
+
+        if let Some(open) = self.selected_note.as_ref().map(|n| n.id.clone()) {
+            self.set_open_note(self.all_notes.iter().find(|n| n.id == open).cloned());
+        }
+        self.update_search()?;
+        self.needs_redraw = true;
+        Ok(())
+    }
+
+    /// Undo (or with `redo`, redo) the last coding action, logging each application it touches
+    fn step_coding_history(&mut self, redo: bool) -> Result<()> {
+        let step = if redo { self.coding_history.pop_redo() } else { self.coding_history.pop_undo() };
+        let Some(step) = step else {
+            self.messages.info(if redo { "No coding action to redo" } else { "No coding action to undo" });
+            return Ok(());
+        };
+        self.replay_coding(&step, !redo)?;
+
+        let coder = self.config.coder_name();
+        let action = if redo { "coding_redone" } else { "coding_undone" };
+        for app in step.touched() {
+            let entry = AuditEntry::for_application(action, &coder, app).with_detail(step.label.clone());
+            audit::record(&self.notes_dir, &entry)?;
+        }
+        tracing::info!(label = %step.label, redo, "coding history step");
+        let message = if redo {
+            format!("Redid: {} ({} undoes)", step.label, keymap::keys_for(Command::UndoCoding).join("/"))
+        } else {
+            format!("Undid: {} ({} redoes)", step.label, keymap::keys_for(Command::RedoCoding).join("/"))
+        };
+        self.messages.info(message);
+        if redo {
+            self.coding_history.redone(step);
+        } else {
+            self.coding_history.undone(step);
+        }
+        Ok(())
+    }
+
+    /// Take the innermost reviewed code at the cursor off its segment
+    fn remove_code_at_cursor(&mut self) -> Result<()> {
+        let Some(note) = self.selected_note.clone() else {
+            return Ok(());
+        };
+        let pos = self.editor.get_cursor_position();
+        let covers = |start: usize, end: usize| pos >= start && pos < end;
+
+        // (length, code, start, end, step) for the shortest segment of each kind
+        let mut candidates: Vec<(usize, String, usize, usize, Step)> = Vec::new();
+        let segment = note.codes.iter().enumerate()
+            .filter(|(_, s)| covers(s.start_offset, s.end_offset))
+            .min_by_key(|(_, s)| s.end_offset - s.start_offset);
+        if let Some((i, segment)) = segment {
+            let mut after = note.codes.clone();
+            after.remove(i);
+            let step = Step {
+                segments: vec![(note.id.clone(), note.codes.clone(), after)],
+                ..Step::new(format!("Remove {}", self.code_name(&segment.code_id)))
+            };
+            candidates.push((segment.end_offset - segment.start_offset, segment.code_id.clone(), segment.start_offset, segment.end_offset, step));
+        }
+        let application = self.codes.get_code_applications(&note.id)?.into_iter()
+            .filter(|a| !a.suggested && covers(a.start_offset, a.end_offset))
+            .min_by_key(|a| a.end_offset - a.start_offset);
+        if let Some(app) = application {
+            let (code_id, start, end) = (app.code_id.clone(), app.start_offset, app.end_offset);
+            let step = Step { before: vec![app], ..Step::new(format!("Remove {}", self.code_name(&code_id))) };
+            candidates.push((end - start, code_id, start, end, step));
+        }
+        let Some((_, code_id, start, end, step)) = candidates.into_iter().min_by_key(|c| c.0) else {
+            self.messages.info("No code at the cursor");
+            return Ok(());
+        };
+
+        self.replay_coding(&step, false)?;
+        let coder = self.config.coder_name();
+        audit::record(&self.notes_dir, &AuditEntry {
+            at: chrono::Utc::now(),
+            action: "code_removed",
+            coder: &coder,
+            note_id: &note.id,
+            code_id: &code_id,
+            start,
+            end,
+            detail: None,
+        })?;
+        self.coding_history.push(step);
+        let message = format!("Removed {} here ({} undoes)", self.code_name(&code_id), keymap::keys_for(Command::UndoCoding).join("/"));
+        self.messages.info(message);
+        Ok(())
+    }
+
+    /// Code tree lines, indented by depth
+    fn code_tree_lines(&self) -> Vec<String> {
+        self.codes.tree().iter().map(|(code, depth)| format!("{}{}", "  ".repeat(*depth), code.name)).collect()
+    }
+
+    /// Which code the pattern's matches get; they come from the marked notes, or the
+    /// listed ones when none are marked
+    fn show_autocode_picker(&mut self, pattern: &str) {
+        let note_ids = if self.marked.is_empty() {
+            self.filtered_notes.iter().map(|n| n.id.clone()).collect()
+        } else {
+            self.marked.clone()
+        };
+        let mut run = AutoCode::new(pattern, note_ids, self.config.auto_code_unit);
+        run.choices = self.code_ids_by_name();
+        let lines = self.code_names(&run.choices);
+        let title = format!("Code the matches of \"{}\" as... (Enter: pick  Esc: cancel)", pattern);
+        self.overlay = Some(Overlay::new(&title, lines)
+            .with_selection()
+            .with_action(OverlayAction::AutoCodePick));
+        self.autocode = Some(run);
+    }
+
+    fn find_autocode_matches(&mut self, mut run: AutoCode) -> Result<()> {
+        let regex = autocode::compile(&run.pattern)?;
+        let notes: Vec<&Note> = run.note_ids.iter().filter_map(|id| self.all_notes.iter().find(|n| n.id == *id)).collect();
+        let applications: Vec<CodeApplication> = self.codes.get_all_code_applications()?.into_values().flatten().collect();
+        run.find(&regex, &notes, &applications);
+        if run.matches.is_empty() {
+            self.overlay = None;
+            self.messages.info(format!("No matches for \"{}\" in {} note(s)", run.pattern, run.note_ids.len()));
+            return Ok(());
+        }
+        self.autocode = Some(run);
+        self.show_autocode_preview(None);
+        Ok(())
+    }
+
+    fn show_autocode_preview(&mut self, keep_selected: Option<usize>) {
+        let Some(run) = &self.autocode else {
+            return;
+        };
+        let code = run.code_id.as_deref().map(|id| self.code_name(id)).unwrap_or_default();
+        let capped = if run.matches.len() >= autocode::MAX_MATCHES { " (first ones only)" } else { "" };
+        let title = format!(
+            "Code {} of {} match(es){} as \"{}\" by {} (Space: tick  a: all  u: unit  Enter: code  Esc: cancel)",
+            run.checked().count(), run.matches.len(), capped, code, run.unit.name()
+        );
+        let mut overlay = Overlay::new(&title, run.preview_lines())
+            .with_selection()
+            .with_action(OverlayAction::AutoCodePreview);
+        if let Some(index) = keep_selected {
+            overlay.selected = Some(index.min(overlay.lines.len().saturating_sub(1)));
+            overlay.scroll = self.overlay.as_ref().map_or(0, |o| o.scroll);
+        }
+        self.overlay = Some(overlay);
+    }
+
+    fn apply_autocode(&mut self, run: &AutoCode) -> Result<()> {
+        let Some(code_id) = run.code_id.as_deref() else {
+            return Ok(());
+        };
+        let spans: Vec<(String, usize, usize, Option<(usize, usize)>)> = run.checked()
+            .map(|m| {
+                let content = self.all_notes.iter().find(|n| n.id == m.note_id).map_or("", |n| n.content.as_str());
+                let (start, end, lines) = self.anchor(content, m.start, m.end);
+                (m.note_id.clone(), start, end, lines)
+            })
+            .collect();
+        if spans.is_empty() {
+            self.messages.info("Nothing ticked - nothing coded");
+            return Ok(());
+        }
+        let coder = self.config.coder_name();
+        let ids = self.codes.apply_code_to_spans(code_id, &spans, Some(&coder))?;
+        self.coding_history.push(Step {
+            after: self.codes.get_applications(&ids)?,
+            ..Step::new(format!("Auto-code as {}", self.code_name(code_id)))
+        });
+        let detail = format!("{} ({})", run.pattern, run.unit.name());
+        for (note_id, start, end, _) in &spans {
+            audit::record(&self.notes_dir, &AuditEntry {
+                at: chrono::Utc::now(),
+                action: "auto_coded",
+                coder: &coder,
+                note_id,
+                code_id,
+                start: *start,
+                end: *end,
+                detail: Some(detail.clone()),
+            })?;
+        }
+        let notes = spans.iter().map(|(id, _, _, _)| id.as_str()).collect::<HashSet<_>>().len();
+        tracing::info!(code = code_id, segments = spans.len(), notes, "auto-coded");
+        self.usage.record_feature("auto-code");
+        let message = format!("Coded {} segment(s) in {} note(s) as {}", spans.len(), notes, self.code_name(code_id));
+        self.messages.info(message);
+        self.needs_redraw = true;
+        Ok(())
+    }
+
+    fn show_move_code_picker(&mut self) {
+        let lines = self.code_tree_lines();
+        if lines.is_empty() {
+            self.messages.info("No codes yet");
+            return;
+        }
+        self.overlay = Some(Overlay::new("Move which code? (Enter: pick  Esc: cancel)", lines)
+            .with_selection()
+            .with_action(OverlayAction::MoveCodePick));
+    }
+
+    /// Where the code goes: the top level or any code outside its own subtree
+    fn show_move_code_parent_picker(&mut self) {
+        let Some(id) = self.code_moving.clone() else {
+            return;
+        };
+        let current = self.codes.get_code(&id).and_then(|c| c.parent_id.clone());
+        let mut choices = vec![None];
+        let mut lines = vec!["(top level)".to_string()];
+        for (code, depth) in self.codes.tree() {
+            if !self.codes.is_within(&code.id, &id) {
+                choices.push(Some(code.id.clone()));
+                lines.push(format!("  {}{}", "  ".repeat(depth), code.name));
+            }
+        }
+
+        let title = format!("Move \"{}\" under... (Enter: move  Esc: cancel)", self.code_name(&id));
+        let mut overlay = Overlay::new(&title, lines)
+            .with_selection()
+            .with_action(OverlayAction::MoveCodeParent);
+        overlay.selected = choices.iter().position(|c| *c == current).or(Some(0));
+        self.code_move_choices = choices;
+        self.overlay = Some(overlay);
+    }
+
+    fn move_code(&mut self, id: &str, parent: Option<&str>) -> Result<()> {
+        if self.codes.get_code(id).and_then(|c| c.parent_id.as_deref()) == parent {
+            return Ok(());
+        }
+        self.codes.move_code(id, parent)?;
+        tracing::info!(code = id, parent = parent.unwrap_or("-"), "code moved");
+        let message = match parent {
+            Some(parent) => format!("Moved \"{}\" under \"{}\"", self.code_name(id), self.code_name(parent)),
+            None => format!("Moved \"{}\" to the top level", self.code_name(id)),
+        };
+        self.messages.info(message);
+        self.needs_redraw = true;
+        Ok(())
+    }
+
+    fn code_name(&self, id: &str) -> String {
+        self.codes.get_code(id).map(|c| c.name.clone()).unwrap_or_else(|| id.to_string())
+    }
+
+    fn code_names(&self, ids: &[String]) -> Vec<String> {
+        ids.iter().map(|id| self.code_name(id)).collect()
+    }
+
+    /// Code ids in the order the relabel picker lists them
+    fn code_ids_by_name(&self) -> Vec<String> {
+        let mut codes = self.codes.get_all_codes();
+        codes.sort_by(|a, b| a.name.cmp(&b.name));
+        codes.into_iter().map(|c| c.id.clone()).collect()
+    }
+
+    fn show_usage_report(&mut self) {
+        let lines = self.usage.report_lines();
+        self.overlay = Some(Overlay::new("Usage report", lines).with_action(OverlayAction::UsageReport));
+    }
+
+    fn show_messages(&mut self) {
+        let lines: Vec<String> = self.messages.log().map(|m| m.describe()).collect();
+        if lines.is_empty() {
+            self.messages.info("No messages yet");
+            return;
+        }
+        self.overlay = Some(Overlay::new(&format!("Messages, newest first ({})", lines.len()), lines));
+    }
+
+    fn cycle_theme(&mut self) -> Result<()> {
+        let name = self.config.cycle_theme();
+        self.theme = self.config.active_theme();
+        self.config.save(&self.notes_dir)?;
+        self.messages.info(format!("Theme: {}", name));
+        Ok(())
+    }
+
+    fn export_selected_note(&mut self) -> Result<()> {
+        if let Some(ref note) = self.selected_note {
+            let path = export::export_note_html(&self.notes_dir, note, &self.codes)?;
+            self.messages.info(format!("Exported to {}", path.display()));
+        }
+        Ok(())
+    }
+
+    /// Export formats first, then the codebook files found in the notes directory
+    fn show_codebook_menu(&mut self) {
+        let mut choices: Vec<codebook::Choice> = codebook::Format::ALL.into_iter().map(codebook::Choice::Export).collect();
+        choices.extend(codebook::candidates(&self.notes_dir).into_iter().map(codebook::Choice::Import));
+        let relative = |path: &Path| path.strip_prefix(&self.notes_dir).unwrap_or(path).display().to_string();
+        let lines = choices.iter().map(|choice| match choice {
+            codebook::Choice::Export(format) => format!(
+                "Export as {:<16} -> {}",
+                format.name(),
+                relative(&codebook::export_path(&self.notes_dir, *format))
+            ),
+            codebook::Choice::Import(path) => format!("Import {}", relative(path)),
+        }).collect();
+        self.overlay = Some(Overlay::new("Codebook (Enter: run  Esc: close)", lines)
+            .with_selection()
+            .with_action(OverlayAction::Codebook));
+        self.codebook_choices = choices;
+    }
+
+    fn export_codebook(&mut self, format: codebook::Format) -> Result<()> {
+        let path = codebook::export_path(&self.notes_dir, format);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, self.codes.export_codebook(format)?)?;
+        self.messages.info(format!("Exported {} codes to {}", self.codes.get_all_codes().len(), path.display()));
+        Ok(())
+    }
+
+    fn import_codebook(&mut self, path: &Path) -> Result<()> {
+        let Some(format) = codebook::Format::from_path(path) else {
+            return Ok(());
+        };
+        let text = std::fs::read_to_string(path)?;
+        let summary = self.codes.import_codebook(&text, format)
+            .with_context(|| format!("importing {}", path.display()))?;
+        tracing::info!(path = %path.display(), added = summary.added, "codebook imported");
+        self.messages.info(summary.describe());
+        Ok(())
+    }
+
+    /// Checklist of codes for a report; the listed notes are its cases
+    fn show_report_codes(&mut self, keep_selected: Option<usize>) {
+        let tree = self.codes.tree();
+        if tree.is_empty() {
+            self.messages.info("No codes yet");
+            return;
+        }
+        self.report_codes = tree.iter().map(|(code, _)| code.id.clone()).collect();
+        let lines = tree.iter()
+            .map(|(code, depth)| {
+                let tick = if self.report_chosen.contains(&code.id) { "[x]" } else { "[ ]" };
+                format!("{} {}{}", tick, "  ".repeat(*depth), code.name)
+            })
+            .collect();
+        let title = format!(
+            "Report on which codes, in {} listed note(s)? (Space: tick  a: all  Enter: next  Esc: cancel)",
+            self.filtered_notes.len()
+        );
+        let mut overlay = Overlay::new(&title, lines)
+            .with_selection()
+            .with_action(OverlayAction::ReportCodes);
+        if let Some(index) = keep_selected {
+            overlay.selected = Some(index.min(overlay.lines.len().saturating_sub(1)));
+            overlay.scroll = self.overlay.as_ref().map_or(0, |o| o.scroll);
+        }
+        self.overlay = Some(overlay);
+    }
+
+    fn show_report_formats(&mut self) {
+        let lines = report::Format::ALL.iter()
+            .map(|format| {
+                let path = report::report_path(&self.notes_dir, *format);
+                let relative = path.strip_prefix(&self.notes_dir).unwrap_or(&path).display().to_string();
+                format!("{:<14} -> {}", format.name(), relative)
+            })
+            .collect();
+        self.overlay = Some(Overlay::new("Write the report as... (Enter: write  Esc: cancel)", lines)
+            .with_selection()
+            .with_action(OverlayAction::ReportFormat));
+    }
+
+    fn write_report(&mut self, format: report::Format) -> Result<()> {
+        // Keep the codebook order, not the order they were ticked in
+        let code_ids: Vec<String> = self.report_codes.iter().filter(|id| self.report_chosen.contains(*id)).cloned().collect();
+        let notes: Vec<&Note> = self.filtered_notes.iter().collect();
+        let applications = self.codes.get_all_code_applications()?;
+        let rows = report::retrieve(&notes, &self.codes, &code_ids, &applications);
+        let scope = report::Scope { codes: code_ids.len(), cases: notes.len() };
+        let path = report::report_path(&self.notes_dir, format);
+        report::write(&path, &rows, format, &scope)?;
+        let cases = rows.iter().map(|r| r.note_id.as_str()).collect::<HashSet<_>>().len();
+        self.usage.record_feature("report");
+        self.messages.info(format!("Wrote {} segment(s) from {} note(s) to {}", rows.len(), cases, path.display()));
+        Ok(())
+    }
+
+    /// Notes below the coverage target (or all of them), least coded first
+    fn show_coverage(&mut self) -> Result<()> {
+        let applications = self.codes.get_all_code_applications()?;
+        let target = self.config.coverage_target;
+        let entries = coverage::dashboard(&self.all_notes, &applications, (!self.coverage_all).then_some(target));
+        self.coverage = entries;
+        self.usage.record_feature("coverage dashboard");
+
+        let lines = if self.coverage.is_empty() {
+            vec![format!("Every note is at least {}% coded.", target)]
+        } else {
+            coverage::dashboard_lines(&self.coverage)
+        };
+        let which = if self.coverage_all { "all notes".to_string() } else { format!("below {}%", target) };
+        let title = format!(
+            "Coding coverage: {} note(s), {} (Enter: continue coding  a: {}  Esc: close)",
+            self.coverage.len(), which, if self.coverage_all { "only unfinished" } else { "show all" }
+        );
+        let mut overlay = Overlay::new(&title, lines).with_action(OverlayAction::Coverage);
+        if !self.coverage.is_empty() {
+            overlay = overlay.with_selection();
+        }
+        self.overlay = Some(overlay);
+        Ok(())
+    }
+
+    /// Coverage of the open note as it is in the editor, once the project has codes
+    fn open_note_coverage(&self, note: &Note) -> Option<coverage::Coverage> {
+        if self.codes.get_all_codes().is_empty() {
+            return None;
+        }
+        let applications = self.codes.get_code_applications(&note.id).unwrap_or_default();
+        Some(coverage::Coverage::of(&self.editor.rope.to_string(), &coverage::spans(note, &applications)))
+    }
+
+    /// Segment counts per code and case; the cases are the marked notes, or the listed ones
+    fn show_case_matrix(&mut self) -> Result<()> {
+        if self.codes.get_all_codes().is_empty() {
+            self.messages.info("No codes yet");
+            return Ok(());
+        }
+        let ids: Vec<String> = if self.marked.is_empty() {
+            self.filtered_notes.iter().map(|n| n.id.clone()).collect()
+        } else {
+            self.marked.clone()
+        };
+        let notes: Vec<&Note> = ids.iter().filter_map(|id| self.all_notes.iter().find(|n| n.id == *id)).collect();
+        if notes.is_empty() {
+            self.messages.info("No notes listed - no cases to compare");
+            return Ok(());
+        }
+        let applications = self.codes.get_all_code_applications()?;
+        let matrix = matrix::Matrix::build(&notes, &self.codes, &applications);
+        self.matrix = Some(matrix);
+        self.matrix_case = 0;
+        self.usage.record_feature("case matrix");
+        self.render_matrix(Some(1))
+    }
+
+    fn render_matrix(&mut self, keep_selected: Option<usize>) -> Result<()> {
+        let Some(matrix) = &self.matrix else {
+            return Ok(());
+        };
+        let (term_width, term_height) = terminal::size()?;
+        let (_, _, width, _) = Overlay::frame(term_width, term_height);
+        let lines = matrix.lines(self.matrix_case, width.saturating_sub(4) as usize);
+        let (_, case) = &matrix.cases[self.matrix_case.min(matrix.cases.len() - 1)];
+        let title = format!(
+            "Case {}/{}: {} (←→: case  Enter: segments  x: CSV  Esc: close)",
+            self.matrix_case + 1, matrix.cases.len(), case
+        );
+        let scroll = self.overlay.as_ref().map_or(0, |o| o.scroll);
+        let mut overlay = Overlay::new(&title, lines).with_selection().with_action(OverlayAction::Matrix);
+        overlay.selected = Some(keep_selected.unwrap_or(1).min(overlay.lines.len().saturating_sub(1)));
+        overlay.scroll = scroll;
+        self.overlay = Some(overlay);
+        Ok(())
+    }
+
+    fn show_matrix_cell(&mut self, code: usize) {
+        let Some(matrix) = &self.matrix else {
+            return;
+        };
+        let lines = matrix.cell_lines(code, self.matrix_case);
+        if lines.is_empty() {
+            self.messages.info("No segments in this cell");
+            return;
+        }
+        let title = format!(
+            "{} in {}: {} segment(s) (Enter: go to  Esc: back)",
+            matrix.codes[code].1, matrix.cases[self.matrix_case].1, lines.len()
+        );
+        self.overlay = Some(Overlay::new(&title, lines).with_selection().with_action(OverlayAction::MatrixCell));
+        self.matrix_code = code;
+    }
+
+    /// Count the words of the marked notes, or of the listed ones when none are marked
+    fn show_word_frequencies(&mut self) {
+        self.word_notes = if self.marked.is_empty() {
+            self.filtered_notes.iter().map(|n| n.id.clone()).collect()
+        } else {
+            self.marked.clone()
+        };
+        let counts = words::frequencies(&self.word_scope(), &self.config.stopwords);
+        self.word_counts = counts;
+        if self.word_counts.is_empty() {
+            self.messages.info("No words to count in these notes");
+            return;
+        }
+        self.usage.record_feature("word frequencies");
+        self.show_word_list(None);
+    }
+
+    fn word_scope(&self) -> Vec<&Note> {
+        self.word_notes.iter().filter_map(|id| self.all_notes.iter().find(|n| n.id == *id)).collect()
+    }
+
+    fn show_word_list(&mut self, keep_selected: Option<usize>) {
+        let what = if self.marked.is_empty() { "listed" } else { "marked" };
+        let title = format!(
+            "{} words in {} {} note(s) (Enter: keyword in context  Esc: close)",
+            self.word_counts.len(), self.word_notes.len(), what
+        );
+        let mut overlay = Overlay::new(&title, words::frequency_lines(&self.word_counts))
+            .with_selection()
+            .with_action(OverlayAction::WordFrequency);
+        if let Some(index) = keep_selected {
+            overlay.selected = Some(index.min(overlay.lines.len().saturating_sub(1)));
+            overlay.scroll = index.saturating_sub(5);
+        }
+        self.overlay = Some(overlay);
     }
 
+    fn show_concordance(&mut self, index: usize) {
+        let Some(word) = self.word_counts.get(index).map(|w| w.word.clone()) else {
+            return;
+        };
+        let hits = words::concordance(&self.word_scope(), &word);
+        self.word_hits = hits;
+        self.word_selected = index;
+        let title = format!("\"{}\": {} occurrence(s) (Enter: go to  Esc: back)", word, self.word_hits.len());
+        self.overlay = Some(Overlay::new(&title, words::concordance_lines(&self.word_hits))
+            .with_selection()
+            .with_action(OverlayAction::Concordance));
+    }
 
+    fn export_segments(&mut self) -> Result<()> {
+        let notes = self.notes.get_all_notes()?;
+        let (path, count) = export::export_segments_jsonl(&self.notes_dir, &notes, &self.codes)?;
+        self.messages.info(format!("Exported {} coded segments to {}", count, path.display()));
+        Ok(())
+    }
 
     fn create_new_note(&mut self) -> Result<()> {
         // Auto-save handles saving
 
+        self.persist_undo_history();
         let title = format!("Note {}", chrono::Utc::now().format("%Y-%m-%d %H:%M"));
         let note = self.notes.create_note(&title, "")?;
-
+        self.usage.record_feature("note created");
 
         // Add to all_notes and update filtered
         self.all_notes.push(note.clone());
         self.update_search()?;
 
         // Select the new note
-        self.selected_note = Some(note);
+        self.set_open_note(Some(note));
+        self.editor.set_text("");
+        self.messages.info("New note created");
+        Ok(())
+    }
+
+    /// New note titled with the search text, opened in the editor
+    fn create_note_from_search(&mut self) -> Result<()> {
+        let title = SearchQuery::parse(&self.search_query).text.trim().to_string();
+        self.auto_save_current_note()?;
+        self.persist_undo_history();
+        let note = self.notes.create_note(&title, "")?;
+        self.usage.record_feature("note created from search");
+
+        // The query now matches the new note, so it stays in the list
+        self.all_notes.push(note.clone());
+        self.update_search()?;
+        if let Some(index) = self.filtered_notes.iter().position(|n| n.id == note.id) {
+            self.selected_note_index = index;
+        }
         self.editor.set_text("");
-        self.status_message = "New note created".to_string();
+        self.editor.history = UndoHistory::default();
+        self.set_open_note(Some(note));
+        self.reading = false;
+        self.focus_area = FocusArea::Editor;
+        self.messages.info(format!("Created \"{}\"", title));
+        Ok(())
+    }
+
+    /// The list selection moved: open the note, or in preview mode only highlight it
+    /// (its preview shows once the selection rests on it)
+    fn follow_list_selection(&mut self) -> Result<()> {
+        if !self.config.list_preview {
+            return self.load_selected_note();
+        }
+        self.scroll_selection_into_view();
+        self.preview = self.filtered_notes.get(self.selected_note_index)
+            .filter(|_| self.highlighted_is_unopened())
+            .map(|note| Preview::new(&note.id));
         Ok(())
     }
 
+    fn highlighted_is_unopened(&self) -> bool {
+        self.filtered_notes.get(self.selected_note_index)
+            .is_some_and(|n| self.selected_note.as_ref().map_or(true, |open| open.id != n.id))
+    }
+
+    /// Popup lines for the note being previewed, once it has been highlighted long enough
+    fn preview_lines(&self) -> Option<(String, Vec<String>)> {
+        let preview = self.preview.as_ref().filter(|p| p.ready())?;
+        // A keyboard highlight previews only while the list has the focus; the open
+        // note is on screen already
+        if self.overlay.is_some() || self.zen || (!preview.hovered && self.focus_area == FocusArea::Editor)
+            || self.selected_note.as_ref().is_some_and(|n| n.id == preview.note_id) {
+            return None;
+        }
+        let note = self.all_notes.iter().find(|n| n.id == preview.note_id)?;
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        let applications = self.codes.get_code_applications(&note.id).unwrap_or_default();
+        for code_id in note.codes.iter().map(|s| &s.code_id).chain(applications.iter().map(|a| &a.code_id)) {
+            let name = self.codes.get_code(code_id).map_or(code_id.as_str(), |c| c.name.as_str());
+            match counts.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((name.to_string(), 1)),
+            }
+        }
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Some((note.title.clone(), preview::lines(note, &counts)))
+    }
+
     fn load_selected_note(&mut self) -> Result<()> {
         // Auto-save handles saving
 
         // Get note from filtered results
         if self.selected_note_index < self.filtered_notes.len() {
             let note = self.filtered_notes[self.selected_note_index].clone();
-            self.selected_note = Some(note.clone());
+            // Reselecting the open note (e.g. while the search refilters) keeps its history in memory
+            let history = if self.selected_note.as_ref().is_some_and(|n| n.id == note.id) {
+                std::mem::take(&mut self.editor.history)
+            } else {
+                self.persist_undo_history();
+                self.reading_scroll = 0;
+                UndoHistory::load(&self.notes_dir, &note.id, &note.content)
+            };
+            self.set_open_note(Some(note.clone()));
             self.editor.set_text(&note.content);
+            self.editor.history = history;
         }
+        self.scroll_selection_into_view();
         Ok(())
     }
 
-    fn update_search(&mut self) -> Result<()> {
-        if self.search_query.is_empty() {
-            // Show all notes when search is empty
-            self.filtered_notes = self.all_notes.clone();
-        } else {
-            // Use nucleo for fuzzy search
-            let pattern = Pattern::parse(
-                &self.search_query,
-                CaseMatching::Ignore,
-                Normalization::Smart,
-            );
+    /// Display rows of the open note in reading mode, laid out for the current terminal size
+    fn reading_rows(&self) -> Vec<reading::Row> {
+        let (width, height) = terminal::size().unwrap_or((80, 24));
+        let text_width = UI::reading_text_width(self.layout(width, height).editor.width);
+        reading::layout(&self.editor.get_text(), text_width, self.config.line_spacing, self.image_rows())
+    }
 
-            let mut matcher = Matcher::default();
-            let mut matches = Vec::new();
-            let mut buf = Vec::new();
+    /// Rows the reading layout leaves for each image
+    fn image_rows(&self) -> usize {
+        if self.image_protocol.enabled() { images::IMAGE_ROWS } else { 0 }
+    }
+
+    fn reading_page(&self) -> usize {
+        let (_, height) = terminal::size().unwrap_or((80, 24));
+        UI::list_rows(height).max(1)
+    }
 
-            for note in &self.all_notes {
-                let haystack = format!("{} {} {}", note.title, note.content, note.tags.join(" "));
-                buf.clear();
-                let score = pattern.score(Utf32Str::new(&haystack, &mut buf), &mut matcher);
-                if let Some(score) = score {
-                    matches.push((score, note.clone()));
+    fn toggle_reading(&mut self) {
+        if self.selected_note.is_none() {
+            self.messages.info("No note selected");
+            return;
+        }
+        self.reading = !self.reading;
+        let rows = self.reading_rows();
+        if self.reading {
+            // Open at the line the cursor is on
+            let line = self.editor.cursor_pos.row;
+            self.reading_scroll = rows.iter().position(|r| r.line == Some(line)).unwrap_or(0);
+            self.focus_area = FocusArea::Editor;
+            self.usage.record_feature("reading mode");
+            self.messages.info(format!("Reading mode, {} (F7: edit  Ctrl+G b: spacing)", self.config.line_spacing.name()));
+        } else {
+            // Resume editing where the reader was, unless the cursor is still on screen
+            let visible: Vec<usize> = rows.iter().skip(self.reading_scroll).take(self.reading_page())
+                .filter_map(|r| r.line)
+                .collect();
+            if !visible.contains(&self.editor.cursor_pos.row) {
+                if let Some(&line) = visible.first() {
+                    let pos = self.editor.rope.line_to_char(line);
+                    self.editor.set_cursor_char(pos);
                 }
             }
+            self.messages.info("Editing");
+        }
+    }
+
+    fn scroll_reading(&mut self, delta: isize) {
+        let max = self.reading_rows().len().saturating_sub(self.reading_page());
+        self.reading_scroll = self.reading_scroll.saturating_add_signed(delta).min(max);
+        self.needs_redraw = true;
+    }
+
+    /// Keys in reading mode only move through the text
+    fn handle_reading_key(&mut self, key: KeyEvent) {
+        let page = self.reading_page() as isize;
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.scroll_reading(-1),
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Enter => self.scroll_reading(1),
+            KeyCode::PageUp | KeyCode::Char('b') => self.scroll_reading(-page),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.scroll_reading(page),
+            KeyCode::Home | KeyCode::Char('g') => self.scroll_reading(isize::MIN),
+            KeyCode::End | KeyCode::Char('G') => self.scroll_reading(isize::MAX),
+            _ => self.messages.info("Reading mode - F7 to edit"),
+        }
+    }
+
+    /// Toggle or cycle a filter chip and refilter
+    fn toggle_chip(&mut self, index: usize) -> Result<()> {
+        self.chips.activate(index, &self.all_notes);
+        self.update_search()?;
+        let (label, active) = self.chips.labels().swap_remove(index);
+        let label = label.split_once(' ').map(|(_, l)| l).unwrap_or(&label).to_string();
+        self.messages.info(format!("{} {} - {} notes", label, if active { "on" } else { "off" }, self.filtered_notes.len()));
+        Ok(())
+    }
+
+    /// Where everything sits on a terminal of this size
+    fn layout(&self, width: u16, height: u16) -> Layout {
+        // Zen only holds while editing; focusing the list or search brings the panes back
+        let zen = self.zen && self.focus_area == FocusArea::Editor && self.selected_note.is_some();
+        Layout::compute(width, height, self.split_ratio, self.config.large_ui, self.layout_preset, zen)
+    }
+
+    fn toggle_zen(&mut self) {
+        if self.selected_note.is_none() {
+            self.messages.info("Open a note to write in zen mode");
+            return;
+        }
+        self.zen = !(self.zen && self.focus_area == FocusArea::Editor);
+        if self.zen {
+            self.focus_area = FocusArea::Editor;
+            self.messages.info(format!("Zen mode ({} or Esc to leave)", keymap::keys_for(Command::ToggleZen).join("/")));
+        } else {
+            self.messages.info("Zen mode off");
+        }
+    }
+
+    /// Snapshot of where the user is, written on quit
+    fn session(&self) -> Session {
+        Session {
+            note_id: self.selected_note.as_ref().map(|n| n.id.clone()),
+            search_query: self.search_query.clone(),
+            focus: self.focus_area,
+            split_ratio: self.split_ratio,
+            layout: self.layout_preset,
+            zen: self.zen,
+            list_scroll: self.list_scroll,
+            cursor: self.editor.get_cursor_position(),
+            hidden_codes: self.hidden_codes.iter().cloned().collect(),
+            recent: self.note_history.recent().to_vec(),
+            searches: self.search_history.queries().to_vec(),
+        }
+    }
+
+    /// Put the last session's note, search, focus and layout back
+    fn restore_session(&mut self, session: Session) -> Result<()> {
+        self.split_ratio = session.split_ratio.clamp(0.1, 0.7);
+        self.layout_preset = session.layout;
+        self.zen = session.zen;
+        self.hidden_codes = session.hidden_codes.into_iter().collect();
+        self.note_history = NoteHistory::with_recent(session.recent);
+        self.search_history = SearchHistory::with_queries(session.searches);
+        self.search_query = session.search_query;
+        self.update_search()?;
+
+        let index = session.note_id.as_ref()
+            .and_then(|id| self.filtered_notes.iter().position(|n| n.id == *id));
+        if let Some(index) = index {
+            self.selected_note_index = index;
+            self.list_scroll = session.list_scroll.min(index);
+            self.load_selected_note()?;
+            self.editor.set_cursor_char(session.cursor);
+        }
+
+        // Only return to the editor if there is a note to edit
+        self.focus_area = match session.focus {
+            FocusArea::Editor if self.selected_note.is_none() => FocusArea::NoteList,
+            focus => focus,
+        };
+        Ok(())
+    }
+
+    /// Write the open note's undo history to disk (on switching notes and on quit)
+    fn persist_undo_history(&self) {
+        if let Some(note) = &self.selected_note {
+            if let Err(e) = self.editor.history.save(&self.notes_dir, &note.id, &self.editor.get_text()) {
+                tracing::warn!(note_id = %note.id, error = %e, "saving undo history failed");
+            }
+        }
+    }
+
+    /// Rows the note list has for entries at the current terminal size
+    fn list_rows(&self) -> usize {
+        let (_, height) = terminal::size().unwrap_or((80, 24));
+        UI::list_rows(height)
+    }
+
+    /// Scroll the list just enough that the selected note is fully visible
+    fn scroll_selection_into_view(&mut self) {
+        self.list_scroll = self.list_scroll.min(self.filtered_notes.len().saturating_sub(1));
+        self.list_scroll = UI::scroll_to_show(self, self.selected_note_index, self.list_scroll, self.list_rows());
+    }
+
+    /// Mouse wheel over the list: move the view, leave the selection alone
+    fn scroll_list(&mut self, down: bool) {
+        if down {
+            let rows = self.list_rows();
+            let visible = UI::visible_note_count(self, self.list_scroll, rows);
+            if self.list_scroll + visible < self.filtered_notes.len() {
+                self.list_scroll += 1;
+            }
+        } else {
+            self.list_scroll = self.list_scroll.saturating_sub(1);
+        }
+        self.needs_redraw = true;
+    }
+
+    fn update_search(&mut self) -> Result<()> {
+        if self.config.zettel_ids {
+            self.assign_zettel_ids()?;
+        }
+        let query = SearchQuery::parse(&self.search_query);
+
+        // memo:/quote:/ann: terms, front matter fields and the filter chips narrow the
+        // candidates before fuzzy matching
+        let candidates: Vec<&Note> = if query.has_segment_terms() || query.has_note_filters() || self.chips.is_active() {
+            let applications = if query.has_segment_terms() || self.chips.has_code {
+                self.codes.get_all_code_applications()?
+            } else {
+                Default::default()
+            };
+            let annotations = if query.annotation.is_empty() {
+                Default::default()
+            } else {
+                self.annotations.all()?
+            };
+            self.all_notes.iter()
+                .filter(|note| {
+                    let apps = applications.get(&note.id).map(|a| a.as_slice()).unwrap_or(&[]);
+                    let anns = annotations.get(&note.id).map(|a| a.as_slice()).unwrap_or(&[]);
+                    query.matches_segments(note, apps)
+                        && query.matches_annotations(anns)
+                        && query.matches_fields(note)
+                        && self.chips.matches(note, !apps.is_empty())
+                })
+                .collect()
+        } else {
+            self.all_notes.iter().collect()
+        };
 
-            // Sort by score (highest first)
-            matches.sort_by(|a, b| b.0.cmp(&a.0));
-            self.filtered_notes = matches.into_iter().map(|(_, note)| note).collect();
+        // Whole-word hits from the index rank first; edits since the last search are indexed now.
+        // A failed sync only leaves the index behind; the search stays usable. While the
+        // startup job runs it does the indexing, and a sync would do it all at once
+        if (!query.text.is_empty() || !query.exclude.is_empty()) && self.index_job.is_none() {
+            if let Err(e) = self.search_index.sync(&self.all_notes) {
+                tracing::warn!(error = %e, "indexing failed");
+                self.messages.error(format!("Indexing failed: {:#}", e));
+            }
         }
+        let term_scores = if query.text.is_empty() {
+            Default::default()
+        } else {
+            self.search_index.search(&query.text, self.config.ranking.title_weight)?
+        };
+        let candidates = self.search_index.filter_proximity(candidates, &query.proximity)?;
+        let candidates = self.search_index.filter_excluded(candidates, &query.exclude)?;
+        // A Zettelkasten ID (or its start) brings up the notes it names ahead of text matches
+        let by_id: Vec<&Note> = if zettel::is_id_prefix(&query.text) {
+            candidates.iter().copied()
+                .filter(|n| self.zettel_ids.get(&n.id).is_some_and(|id| id.starts_with(query.text.trim())))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let ranked = query::rank(candidates, &query.text, &term_scores, &self.config.ranking);
+        self.filtered_notes = by_id.iter()
+            .chain(ranked.iter().filter(|n| !by_id.iter().any(|m| m.id == n.id)))
+            .map(|n| (*n).clone())
+            .collect();
 
         // Reset selection if needed
         if self.selected_note_index >= self.filtered_notes.len() {
@@ -406,22 +5817,226 @@ impl App {
             self.load_selected_note()?;
         }
 
-        self.status_message = format!("{} notes", self.filtered_notes.len());
+        self.messages.info(format!("{} notes", self.filtered_notes.len()));
+        Ok(())
+    }
+
+    /// Give every note without a Zettelkasten ID one from its creation time
+    fn assign_zettel_ids(&mut self) -> Result<()> {
+        let mut missing: Vec<&Note> = self.all_notes.iter().filter(|n| !self.zettel_ids.contains_key(&n.id)).collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        // Oldest first, so the earlier of two notes from one minute keeps the short ID
+        missing.sort_by_key(|n| n.created_at);
+        let mut taken: HashSet<String> = self.zettel_ids.values().cloned().collect();
+        let mut assigned = Vec::new();
+        for note in missing {
+            let id = zettel::generate(note.created_at, &taken.iter().map(String::as_str).collect());
+            taken.insert(id.clone());
+            assigned.push((note.id.clone(), id));
+        }
+        for (note_id, id) in assigned {
+            self.notes.set_zettel_id(&note_id, &id)?;
+            self.zettel_ids.insert(note_id, id);
+        }
         Ok(())
     }
 
     fn auto_save_current_note(&mut self) -> Result<()> {
+        let _span = tracing::debug_span!("autosave").entered();
         if let Some(mut note) = self.selected_note.take() {
-            note.content = self.editor.get_text();
+            let text = self.editor.get_text();
+            if self.config.auto_title {
+                if let Some(title) = titles::follow(&note.title, &note.content, &text) {
+                    note.title = title;
+                    // Moved on in memory too, so the search index picks up the new title
+                    note.updated_at = chrono::Utc::now();
+                }
+            }
+            note.content = text;
 
             // Extract tags from content (simplified without markdown module)
             note.tags = Self::extract_tags(&note.content);
 
-            self.notes.update_note(&note)?;
+            // A failed save must not lose the edit - journal it and retry in the background
+            match self.notes.update_note(&note) {
+                Ok(()) => {
+                    self.journal.forget(&note.id);
+                    // Line-anchored segments follow their lines through whitespace changes
+                    if let Err(e) = self.codes.reanchor(&note.id, &note.content) {
+                        tracing::warn!(note_id = %note.id, error = %e, "re-anchoring segments failed");
+                    }
+                    self.similarity_due = Some(Instant::now() + SIMILARITY_DELAY);
+                }
+                Err(e) => {
+                    tracing::warn!(note_id = %note.id, error = %e, "autosave failed, journaling");
+                    self.messages.warn(format!("Save failed ({}) - kept in journal, retrying", e));
+                    self.journal.record(&note, &e);
+                }
+            }
+
+            // Keep the list copies current, so reopening the note shows (and matches) the saved text
+            for list in [&mut self.all_notes, &mut self.filtered_notes] {
+                if let Some(slot) = list.iter_mut().find(|n| n.id == note.id) {
+                    *slot = note.clone();
+                }
+            }
+
+            self.set_open_note(Some(note));
+        }
+        Ok(())
+    }
+
+    /// Round-trip the open note through $VISUAL / $EDITOR (the terminal is already handed over)
+    fn edit_externally(&mut self) -> Result<()> {
+        if !self.check_editable() {
+            return Ok(());
+        }
+        self.auto_save_current_note()?;
+        let Some(note) = self.selected_note.clone() else {
+            return Ok(());
+        };
+
+        // A .md name lets the editor pick markdown highlighting
+        let path = std::env::temp_dir().join(format!("snyfter3-{}.md", note.id));
+        std::fs::write(&path, &note.content)?;
+
+        // "code --wait" style values carry their own arguments
+        let command = std::env::var("VISUAL").or_else(|_| std::env::var("EDITOR"))
+            .ok()
+            .filter(|c| !c.trim().is_empty())
+            .unwrap_or_else(|| "vi".to_string());
+        let mut parts = command.split_whitespace();
+        let program = parts.next().unwrap_or("vi");
+        let status = std::process::Command::new(program).args(parts).arg(&path).status();
+
+        let edited = std::fs::read_to_string(&path);
+        let _ = std::fs::remove_file(&path);
+        let status = status.map_err(|e| anyhow::anyhow!("could not run {}: {}", program, e))?;
+        if !status.success() {
+            anyhow::bail!("{} exited with {}", program, status);
+        }
+
+        let edited = edited?;
+        if edited == note.content {
+            self.messages.info("No changes from the external editor");
+            return Ok(());
+        }
+
+        // One undo step for the whole external session, then save and re-run the search
+        self.editor.replace_text(&edited);
+        self.auto_save_current_note()?;
+        let cursor = self.editor.get_cursor_position();
+        self.update_search()?;
+        if self.selected_note.as_ref().is_some_and(|n| n.id == note.id) {
+            self.editor.set_cursor_char(cursor);
+        }
+        self.messages.info(format!("Updated \"{}\" from {}", note.title, program));
+        Ok(())
+    }
+
+    /// Copy the editor's text to draft.json (only when it changed since the last copy)
+    fn write_draft(&mut self) {
+        let result = match &self.selected_note {
+            Some(note) => self.drafts.write(&note.id, &note.title, &self.editor.get_text()),
+            None => self.drafts.clear(),
+        };
+        if let Err(e) = result {
+            tracing::warn!(error = %e, "writing draft failed");
+        }
+    }
+
+    fn offer_draft_recovery(&mut self) {
+        let lines = drafts::summary_lines(&self.recovered_drafts, &self.all_notes);
+        self.recovered_drafts.clear();
+        self.overlay = Some(Overlay::new("Recover drafts", lines).with_action(OverlayAction::RecoverDrafts));
+    }
+
+    /// Put recovered drafts back: the open note through the editor (one undo step),
+    /// others straight into the store, deleted ones as new notes
+    fn recover_drafts(&mut self) -> Result<()> {
+        let drafts = self.drafts.take_recovered()?;
+        for draft in &drafts {
+            if self.selected_note.as_ref().is_some_and(|n| n.id == draft.note_id) {
+                self.editor.replace_text(&draft.content);
+                self.auto_save_current_note()?;
+                continue;
+            }
+            match self.notes.get_note(&draft.note_id)? {
+                Some(mut note) => {
+                    note.content = draft.content.clone();
+                    note.tags = Self::extract_tags(&note.content);
+                    self.notes.update_note(&note)?;
+                }
+                None => {
+                    self.notes.create_note(&draft.title, &draft.content)?;
+                }
+            }
+        }
+        self.all_notes = self.notes.get_all_notes()?;
+        self.update_search()?;
+        self.messages.info(format!("Recovered {} draft(s)", drafts.len()));
+        Ok(())
+    }
+
+    /// Show the recovery prompt if the last session left unsaved revisions behind
+    fn offer_journal_recovery(&mut self) {
+        if !self.journal.is_empty() {
+            let lines = self.journal.summary_lines(&self.all_notes);
+            self.overlay = Some(Overlay::new("Unsaved edits", lines).with_action(OverlayAction::RecoverJournal));
+        }
+    }
+
+    /// Try to save every journaled revision; failures go back in with a longer backoff
+    #[tracing::instrument(level = "debug", skip_all)]
+    fn retry_journal(&mut self) -> Result<()> {
+        let entries = self.journal.take_entries();
+        if entries.is_empty() {
+            self.journal.settle();
+            return Ok(());
+        }
+
+        let mut failed = None;
+        for entry in entries {
+            if failed.is_some() {
+                self.journal.restore(entry);
+                continue;
+            }
+            if let Err(e) = self.save_journal_entry(&entry) {
+                failed = Some(e);
+                self.journal.restore(entry);
+            }
+        }
 
-            // Update search index
+        match failed {
+            Some(e) => {
+                tracing::warn!(error = %e, "journal retry failed");
+                self.journal.back_off();
+                self.messages.error(format!("Save still failing ({}) - edits kept in journal", e));
+            }
+            None => {
+                self.journal.settle();
+                self.messages.info("Journaled edits saved");
+            }
+        }
+        self.needs_redraw = true;
+        Ok(())
+    }
 
-            self.selected_note = Some(note);
+    fn save_journal_entry(&mut self, entry: &JournalEntry) -> Result<()> {
+        match self.notes.get_note(&entry.note_id)? {
+            Some(mut note) => {
+                note.title = entry.title.clone();
+                note.content = entry.content.clone();
+                note.tags = entry.tags.clone();
+                self.notes.update_note(&note)?;
+            }
+            None => {
+                // Deleted in the meantime - bring the text back as a new note
+                let note = self.notes.create_note(&entry.title, &entry.content)?;
+                self.all_notes.insert(0, note);
+            }
         }
         Ok(())
     }
@@ -432,6 +6047,19 @@ impl App {
             let text = self.editor.get_text();
             let _cursor_pos = self.editor.get_cursor_position();
 
+            // An @citation under the cursor opens its literature note
+            if let Some(key) = bibtex::citation_at(&text, self.editor.get_cursor_position()) {
+                let note = self.all_notes.iter().find(|n| bibtex::citekey(n).as_deref() == Some(key.as_str())).cloned();
+                match note {
+                    Some(note) => {
+                        self.open_note(note)?;
+                        self.messages.info(format!("Navigated to: @{}", key));
+                    }
+                    None => self.messages.info(format!("No literature note for @{}", key)),
+                }
+                return Ok(());
+            }
+
             // Simple approach: find all wiki links and check if cursor is within one
             let links = Self::extract_wiki_links(&text);
 
@@ -442,83 +6070,106 @@ impl App {
                 // Search for a note with this title
                 let all_notes = self.notes.get_all_notes()?;
                 for (idx, note) in all_notes.iter().enumerate() {
-                    if &note.title == link_title {
+                    // [[202403151030]] links by Zettelkasten ID and survives renames
+                    if &note.title == link_title || self.zettel_ids.get(&note.id) == Some(link_title) {
                         self.selected_note_index = idx;
                         self.load_selected_note()?;
-                        self.status_message = format!("Navigated to: {}", link_title);
+                        self.messages.info(format!("Navigated to: {}", link_title));
                         return Ok(());
                     }
                 }
 
                 // If not found, create a new note with this title
+                self.persist_undo_history();
                 let new_note = self.notes.create_note(&link_title, "")?;
-                self.selected_note = Some(new_note);
+                self.set_open_note(Some(new_note));
                 self.editor.set_text("");
-                self.status_message = format!("Created new note: {}", link_title);
+                self.messages.info(format!("Created new note: {}", link_title));
                 return Ok(());
             }
         }
         Ok(())
     }
 
+    #[tracing::instrument(level = "trace", skip(self))]
     fn handle_mouse(&mut self, mouse: MouseEvent) -> Result<()> {
-        let (term_width, _term_height) = terminal::size()?;
-        let divider_x = (term_width as f32 * self.split_ratio) as u16;
+        let (term_width, term_height) = terminal::size()?;
+        // Nothing to hit while the placeholder or the lock screen is up (or a layout is still pending)
+        if UI::too_small(term_width, term_height) || self.resize.pending() || self.locked {
+            return Ok(());
+        }
+        let layout = self.layout(term_width, term_height);
+        let hit = layout.hit(mouse.column, mouse.row);
+        let text = layout.editor.body();
 
         // Check if Alt is being held for block selection
         let is_alt_held = mouse.modifiers.contains(crossterm::event::KeyModifiers::ALT);
 
         match mouse.kind {
             MouseEventKind::Down(MouseButton::Left) => {
-                // Check if clicking on search bar (line 2)
-                if mouse.row == 1 {
-                    self.focus_area = FocusArea::SearchBar;
+                // Focus follows the click into whichever pane was hit, before the widget acts on it
+                let focus = hit.and_then(Widget::focus)
+                    .filter(|&focus| focus != FocusArea::Editor || self.selected_note.is_some());
+                if let Some(focus) = focus {
+                    self.focus_area = focus;
                     self.needs_redraw = true;
                 }
-                // Check if clicking on divider (within 2 pixels)
-                else if mouse.column >= divider_x.saturating_sub(1) && mouse.column <= divider_x + 1 {
-                    self.dragging_divider = true;
-                } else if mouse.column < divider_x {
-                    // Clicking in notes list area
-                    if mouse.row == 2 {
-                        // Clicking on notes header
-                        self.focus_area = FocusArea::NoteList;
-                        self.needs_redraw = true;
-                    } else if mouse.row > 2 {  // Skip header and search bar
-                        let index = (mouse.row - 3) as usize;
-                        if index < self.filtered_notes.len() {
+
+                match hit {
+                    Some(Widget::Chips) => {
+                        if let Some(index) = UI::chip_at(self, mouse.row, mouse.column) {
+                            self.toggle_chip(index)?;
+                            self.needs_redraw = true;
+                        }
+                    }
+                    Some(Widget::Divider) => self.dragging_divider = true,
+                    Some(Widget::Codes) => {
+                        // A related note opens; a code is picked up, and dropping it on another code hangs it there
+                        if let Some(note_id) = UI::related_at_row(self, &layout, mouse.row) {
+                            self.open_related_note(&note_id)?;
+                        } else {
+                            self.dragging_code = UI::code_at_row(self, &layout, mouse.row);
+                        }
+                    }
+                    Some(Widget::List) => {
+                        let row = (mouse.row - layout.list.body().y) as usize;
+                        if let Some(index) = UI::note_index_at_row(self, row) {
                             self.selected_note_index = index;
+                            self.preview = None;
                             self.load_selected_note()?;
-                            self.focus_area = FocusArea::NoteList;
-                            self.needs_redraw = true;
                         }
                     }
-                } else {
-                    // Clicking in editor area
-                    if self.selected_note.is_some() {
-                        self.focus_area = FocusArea::Editor;
-
-                        // Calculate the click position relative to the editor panel
-                        let editor_start_x = divider_x + 1;
-                        let editor_start_y = 3; // After search bar and editor header
+                    Some(Widget::Editor) if self.selected_note.is_some() => {
+                        // Convert screen coordinates to document coordinates
+                        let (scroll_x, scroll_y) = self.ui.editor_scroll();
+                        let click_col = (mouse.column - text.x) as usize + scroll_x;
+                        let click_row = (mouse.row - text.y) as usize + scroll_y;
 
-                        if mouse.column >= editor_start_x && mouse.row >= editor_start_y {
-                            // Convert screen coordinates to editor coordinates
-                            let click_col = (mouse.column - editor_start_x) as usize;
-                            let click_row = (mouse.row - editor_start_y) as usize;
-
-                            // Clear any existing block selection on new click
-                            self.editor.block_selection = None;
-
-                            // Set cursor position
-                            self.editor.set_cursor_position(click_row, click_col);
+                        // Clear any existing block selection on new click
+                        self.editor.block_selection = None;
+                        self.editor.potential_block_start = None;
+                        self.editor.drag_anchor = None;
 
-                            // Store the click position for potential block selection on drag
-                            self.editor.potential_block_start = Some((click_row, click_col));
+                        match self.register_click(mouse.column, mouse.row) {
+                            2 => {
+                                self.editor.set_cursor_position(click_row, click_col);
+                                let pos = self.editor.get_cursor_position();
+                                self.editor.select_word_at(pos);
+                            }
+                            3 => self.editor.select_line_at(click_row),
+                            _ => {
+                                self.editor.set_cursor_position(click_row, click_col);
+                                if is_alt_held {
+                                    // Alt+drag makes a rectangular block selection
+                                    self.editor.potential_block_start = Some((click_row, click_col));
+                                } else {
+                                    // Plain drag extends a regular selection from here
+                                    self.editor.drag_anchor = Some(self.editor.get_cursor_position());
+                                }
+                            }
                         }
-
-                        self.needs_redraw = true;
                     }
+                    _ => {}
                 }
             }
             MouseEventKind::Drag(MouseButton::Left) => {
@@ -528,14 +6179,19 @@ impl App {
                         .max(0.1)  // Allow down to 10%
                         .min(0.7);
                     self.needs_redraw = true;
-                } else if mouse.column > divider_x {
-                    // Handle dragging in editor area
-                    let editor_start_x = divider_x + 1;
-                    let editor_start_y = 3;
+                } else if self.dragging_code.is_some() {
+                    // Carrying a code over the codes pane; it lands on release
+                    self.needs_redraw = true;
+                } else if mouse.column >= text.x {
+                    // Handle dragging in editor area (past its edges too, so a drag can run off the text)
+                    if mouse.row >= text.y {
+                        let (scroll_x, scroll_y) = self.ui.editor_scroll();
+                        let drag_col = (mouse.column - text.x) as usize + scroll_x;
+                        let drag_row = (mouse.row - text.y) as usize + scroll_y;
 
-                    if mouse.column >= editor_start_x && mouse.row >= editor_start_y {
-                        let drag_col = (mouse.column - editor_start_x) as usize;
-                        let drag_row = (mouse.row - editor_start_y) as usize;
+                        if self.editor.drag_anchor.is_some() {
+                            self.editor.drag_selection_to(drag_row, drag_col);
+                        }
 
                         // Create block selection on first drag if we have a start position
                         if self.editor.block_selection.is_none() {
@@ -563,8 +6219,20 @@ impl App {
             MouseEventKind::Up(MouseButton::Left) => {
                 self.dragging_divider = false;
 
+                // A code dropped on another code moves under it; dropped on the pane's header, to the top level
+                if let Some(id) = self.dragging_code.take() {
+                    if hit == Some(Widget::Codes) {
+                        if mouse.row == layout.codes.y {
+                            self.move_code(&id, None)?;
+                        } else if let Some(target) = UI::code_at_row(self, &layout, mouse.row).filter(|t| *t != id) {
+                            self.move_code(&id, Some(&target))?;
+                        }
+                    }
+                }
+
                 // Clear the potential block start since mouse is released
                 self.editor.potential_block_start = None;
+                self.editor.drag_anchor = None;
 
                 // Check if block selection exists and hasn't been extended (just a click, no drag)
                 if let Some(ref block_sel) = self.editor.block_selection {
@@ -576,6 +6244,51 @@ impl App {
                     }
                 }
             }
+            MouseEventKind::Moved => {
+                // Hovering a list entry previews it; moving off the list drops the hover preview
+                let hovered = match hit {
+                    Some(Widget::List) => {
+                        let row = (mouse.row - layout.list.body().y) as usize;
+                        UI::note_index_at_row(self, row).and_then(|i| self.filtered_notes.get(i))
+                    }
+                    _ => None,
+                };
+                match hovered {
+                    Some(note) => {
+                        if self.preview.as_ref().map_or(true, |p| p.note_id != note.id) {
+                            self.preview = Some(Preview::hovered(&note.id));
+                            self.needs_redraw = true;
+                        }
+                    }
+                    None if self.preview.as_ref().is_some_and(|p| p.hovered) => {
+                        self.preview = None;
+                        self.needs_redraw = true;
+                    }
+                    None => {}
+                }
+
+                // Hovering coded text pops up the codes on it
+                let code_hover = match (hit, &self.selected_note) {
+                    (Some(Widget::Editor), Some(note)) if mouse.row >= text.y && mouse.column >= text.x && !self.reading => {
+                        let (scroll_x, scroll_y) = self.ui.editor_scroll();
+                        let row = (mouse.row - text.y) as usize + scroll_y;
+                        let col = (mouse.column - text.x) as usize + scroll_x;
+                        self.editor.char_at(row, col)
+                            .map(|pos| self.codes_at(note, pos))
+                            .filter(|codes| !codes.is_empty())
+                            .map(|codes| (mouse.column, mouse.row, codes))
+                    }
+                    _ => None,
+                };
+                if code_hover != self.code_hover {
+                    self.code_hover = code_hover;
+                    self.needs_redraw = true;
+                }
+            }
+            MouseEventKind::ScrollDown if mouse.column < layout.divider.x => self.scroll_list(true),
+            MouseEventKind::ScrollUp if mouse.column < layout.divider.x => self.scroll_list(false),
+            MouseEventKind::ScrollDown if self.reading => self.scroll_reading(3),
+            MouseEventKind::ScrollUp if self.reading => self.scroll_reading(-3),
             MouseEventKind::ScrollDown => {
                 // Scroll notes list down
                 if self.selected_note_index < self.filtered_notes.len().saturating_sub(1) {
@@ -598,36 +6311,74 @@ impl App {
         Ok(())
     }
 
+    /// Count consecutive clicks on the same cell (1 = single, 2 = double, 3 = triple)
+    fn register_click(&mut self, column: u16, row: u16) -> u8 {
+        let now = Instant::now();
+        let repeat = self.last_click.is_some_and(|(time, c, r)| {
+            c == column && r == row && now.duration_since(time) < MULTI_CLICK
+        });
+        self.click_count = if repeat { self.click_count % 3 + 1 } else { 1 };
+        self.last_click = Some((now, column, row));
+        self.click_count
+    }
+
     fn delete_selected_note(&mut self) -> Result<()> {
-        if self.selected_note_index < self.filtered_notes.len() {
-            let note = &self.filtered_notes[self.selected_note_index];
-            let id = note.id.clone();
+        if let Some(id) = self.filtered_notes.get(self.selected_note_index).map(|n| n.id.clone()) {
+            self.trash_note(&id)?;
+        }
+        Ok(())
+    }
 
-            // Delete from storage
-            self.notes.delete_note(&id)?;
-            // Delete from search index
+    fn trash_note(&mut self, id: &str) -> Result<()> {
+        let title = self.all_notes.iter().find(|n| n.id == id).map(|n| n.title.clone()).unwrap_or_default();
 
-            // Remove from all_notes and filtered_notes
-            self.all_notes.retain(|n| n.id != id);
-            self.filtered_notes.retain(|n| n.id != id);
+        // Soft delete - no confirmation, the toast offers the undo instead
+        self.notes.trash_note(id)?;
+        self.trashed.push(id.to_string());
 
-            // Adjust selected index if needed
-            if self.selected_note_index >= self.filtered_notes.len() && self.selected_note_index > 0 {
-                self.selected_note_index -= 1;
-            }
+        // Remove from all_notes and filtered_notes
+        self.all_notes.retain(|n| n.id != id);
+        self.filtered_notes.retain(|n| n.id != id);
 
-            // Clear selected note if it was the deleted one
-            if let Some(ref selected) = self.selected_note {
-                if selected.id == id {
-                    self.selected_note = None;
-                    self.editor.set_text("");
-                }
+        // Adjust selected index if needed
+        if self.selected_note_index >= self.filtered_notes.len() && self.selected_note_index > 0 {
+            self.selected_note_index -= 1;
+        }
+
+        // Clear selected note if it was the deleted one
+        if let Some(ref selected) = self.selected_note {
+            if selected.id == id {
+                self.set_open_note(None);
+                self.editor.set_text("");
             }
+        }
 
-            // Update search results
-            self.update_search()?;
+        // Update search results
+        self.update_search()?;
+
+        let undo_keys = keymap::keys_for(Command::UndoDelete).join("/");
+        self.toast = Some(Toast::new(&format!("Moved \"{}\" to trash - Undo ({})", title, undo_keys), UNDO_TOAST));
+        self.messages.info("Note moved to trash");
+        Ok(())
+    }
+
+    /// Restore the most recently trashed note and select it
+    fn undo_delete(&mut self) -> Result<()> {
+        let Some(id) = self.trashed.pop() else {
+            self.messages.info("Nothing to undo");
+            return Ok(());
+        };
 
-            self.status_message = "Note deleted".to_string();
+        self.toast = None;
+        if let Some(note) = self.notes.restore_note(&id)? {
+            let title = note.title.clone();
+            self.all_notes.insert(0, note);
+            self.update_search()?;
+            if let Some(index) = self.filtered_notes.iter().position(|n| n.id == id) {
+                self.selected_note_index = index;
+                self.load_selected_note()?;
+            }
+            self.messages.info(format!("Restored: {}", title));
         }
         Ok(())
     }
@@ -637,7 +6388,9 @@ impl App {
         let mut tags = Vec::new();
         for line in content.lines() {
             for word in line.split_whitespace() {
-                if word.starts_with('#') && word.len() > 1 && !word.chars().nth(1).unwrap().is_ascii_digit() {
+                // "##" and friends are markdown headings, not tags
+                if word.starts_with('#') && word.len() > 1 && !word.chars().nth(1).unwrap().is_ascii_digit()
+                    && !word[1..].starts_with('#') {
                     tags.push(word[1..].to_string());
                 }
             }
@@ -690,8 +6443,72 @@ async fn main() -> Result<()> {
     // Create directory if it doesn't exist
     std::fs::create_dir_all(&notes_dir)?;
 
+    let log_path = logging::init(args.log_level.as_deref())?;
+    tracing::info!(notes_dir = %notes_dir.display(), log = %log_path.display(), "starting");
+    input::install_panic_hook(log_path.clone());
+
+    // The databases can't be read without the passphrase's key (the command that sets
+    // it asks for the current one itself)
+    if !matches!(args.command, Some(Commands::Passphrase { .. })) {
+        lock::unlock(&notes_dir)?;
+    }
+
+    if let Some(reason) = args.command.as_ref().and_then(Commands::backup_reason) {
+        backup::create(&notes_dir, reason)?;
+    }
+
+    match args.command {
+        Some(Commands::Daemon) => return daemon::run(&notes_dir).await,
+        Some(Commands::ImportSuggestions { file }) => {
+            let notes = NoteStore::new(&notes_dir)?.get_all_notes()?;
+            let mut codes = CodeManager::new(&notes_dir)?;
+            let summary = import::import_suggestions_jsonl(&file, &notes, &mut codes)?;
+            println!("{}", summary.describe());
+            return Ok(());
+        }
+        Some(Commands::Add { title, body, tags }) => {
+            let note = add_note(&notes_dir, &title, body.as_deref(), &tags)?;
+            println!("{}", note.id);
+            return Ok(());
+        }
+        Some(Commands::ImportCsv { file, title, content, tags, attributes, dry_run }) => {
+            let mapping = csv_import::MappingArgs { title, content, tags, attributes };
+            let summary = csv_import::import_csv(&notes_dir, &file, &mapping, dry_run)?;
+            println!("{}", summary.describe(dry_run));
+            return Ok(());
+        }
+        Some(Commands::Passphrase { remove }) => return lock::set_passphrase(&notes_dir, remove),
+        Some(Commands::Sync) => return cli::sync(&notes_dir),
+        Some(Commands::Backup) => return cli::backup(&notes_dir),
+        Some(Commands::Restore { name }) => return cli::restore(&notes_dir, name.as_deref()),
+        Some(Commands::List { json }) => return cli::list(&notes_dir, json),
+        Some(Commands::Search { query, json }) => return cli::search(&notes_dir, &query, json),
+        Some(Commands::Show { id, json }) => return cli::show(&notes_dir, &id, json),
+        Some(Commands::Export { format, ids, json }) => return cli::export(&notes_dir, format, &ids, json),
+        Some(Commands::Reindex) => return cli::reindex(&notes_dir),
+        Some(Commands::Report { codes, notes, format, output }) => {
+            return cli::report(&notes_dir, &codes, &notes, format, output.as_deref());
+        }
+        Some(Commands::Journal { text, search, export, json }) => {
+            return cli::journal(&notes_dir, text.as_deref(), search.as_deref(), export, json);
+        }
+        Some(Commands::ExportCodebook { format, output }) => return cli::export_codebook(&notes_dir, format, output.as_deref()),
+        Some(Commands::ImportCodebook { file, format }) => return cli::import_codebook(&notes_dir, &file, format),
+        Some(Commands::Attach { id, files }) => return cli::attach(&notes_dir, &id, &files),
+        Some(Commands::ImportPdf { files }) => return cli::import_pdf(&notes_dir, &files),
+        Some(Commands::ImportTranscript { files }) => return cli::import_transcripts(&notes_dir, &files),
+        Some(Commands::ImportBib { file }) => {
+            let summary = bibtex::import_bib(&notes_dir, &file)?;
+            println!("{}", summary.describe());
+            return Ok(());
+        }
+        None => {}
+    }
+
     // Initialize and run app
+    let session = Session::load(&notes_dir);
     let mut app = App::new(notes_dir)?;
+    app.restore_session(session)?;
 
     // If search query provided, start with search
     if let Some(query) = args.search {
@@ -700,12 +6517,56 @@ async fn main() -> Result<()> {
         app.update_search()?;
     }
 
-    // Load first note if any
-    if !app.filtered_notes.is_empty() {
+    // Load first note if any (and the session didn't already open one)
+    if app.selected_note.is_none() && !app.filtered_notes.is_empty() {
         app.load_selected_note()?;
     }
 
+    app.offer_journal_recovery();
+    app.recovered_drafts = app.drafts.worth_recovering(&app.all_notes);
+
     app.run().await?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// `snyfter3 add`: store and index a note and let a running TUI know about it
+fn add_note(notes_dir: &std::path::Path, title: &str, body: Option<&str>, tags: &[String]) -> Result<Note> {
+    let mut content = match body {
+        Some("-") => {
+            let mut text = String::new();
+            io::Read::read_to_string(&mut io::stdin(), &mut text)?;
+            text
+        }
+        Some(text) => text.to_string(),
+        None => String::new(),
+    };
+
+    // Tags live in the text (autosave re-extracts them), so --tag appends any that are missing
+    let existing = App::extract_tags(&content);
+    let missing: Vec<String> = tags.iter()
+        .map(|t| t.trim_start_matches('#').to_string())
+        .filter(|t| !t.is_empty() && !existing.contains(t))
+        .collect();
+    if !missing.is_empty() {
+        let line = missing.iter().map(|t| format!("#{}", t)).collect::<Vec<_>>().join(" ");
+        let trimmed = content.trim_end().len();
+        content.truncate(trimmed);
+        if !content.is_empty() {
+            content.push_str("\n\n");
+        }
+        content.push_str(&line);
+        content.push('\n');
+    }
+
+    let mut store = NoteStore::new(notes_dir)?;
+    let mut note = store.create_note(title, &content)?;
+    note.tags = App::extract_tags(&content);
+    store.update_note(&note)?;
+    tracing::info!(note_id = %note.id, "note added from the command line");
+    // Found by `snyfter3 search` at once, not only after the app next syncs its index
+    crate::cli::index_imported(notes_dir, std::slice::from_ref(&note));
+
+    daemon::push_inbox(notes_dir, &daemon::InboxItem::Captured { note_id: note.id.clone() })?;
+    Ok(note)
+}
@@ -0,0 +1,132 @@
+// Case comparison matrix - coded segments counted per code and case
+//
+// The framework-analysis matrix: one row per code (codebook order), one column per case
+// (the marked notes, or the listed ones when none are marked), each cell the number of
+// segments of that code in that case. ←/→ move between cases, Enter on a cell lists its
+// segments and Enter there jumps to one; `x` writes the counts to exports/matrix.csv.
+// Counts are per code - a parent's row doesn't include its children's segments.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::codebook::csv_field;
+use crate::note_store::Note;
+use crate::qda_codes::{CodeApplication, CodeManager};
+use crate::report::{self, Row};
+
+/// Width of the code name column and of each case column
+const LABEL_WIDTH: usize = 24;
+const CELL_WIDTH: usize = 6;
+
+pub struct Matrix {
+    pub codes: Vec<(String, String, usize)>,  // Code id, name, depth in the tree
+    pub cases: Vec<(String, String)>,         // Note id, title
+    segments: HashMap<(usize, usize), Vec<Row>>,  // (code row, case column) -> segments
+}
+
+impl Matrix {
+    pub fn build(notes: &[&Note], codes: &CodeManager, applications: &HashMap<String, Vec<CodeApplication>>) -> Self {
+        let tree = codes.tree();
+        let roots: Vec<String> = tree.iter().filter(|(_, depth)| *depth == 0).map(|(c, _)| c.id.clone()).collect();
+        let code_row: HashMap<&str, usize> = tree.iter().enumerate().map(|(i, (c, _))| (c.id.as_str(), i)).collect();
+        let case_column: HashMap<&str, usize> = notes.iter().enumerate().map(|(i, n)| (n.id.as_str(), i)).collect();
+
+        let mut segments: HashMap<(usize, usize), Vec<Row>> = HashMap::new();
+        for row in report::retrieve(notes, codes, &roots, applications) {
+            if let (Some(&r), Some(&c)) = (code_row.get(row.code_id.as_str()), case_column.get(row.note_id.as_str())) {
+                segments.entry((r, c)).or_default().push(row);
+            }
+        }
+        Matrix {
+            codes: tree.iter().map(|(c, depth)| (c.id.clone(), c.name.clone(), *depth)).collect(),
+            cases: notes.iter().map(|n| (n.id.clone(), n.title.clone())).collect(),
+            segments,
+        }
+    }
+
+    pub fn count(&self, code: usize, case: usize) -> usize {
+        self.segments.get(&(code, case)).map_or(0, Vec::len)
+    }
+
+    pub fn cell(&self, code: usize, case: usize) -> &[Row] {
+        self.segments.get(&(code, case)).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// First case column to show so that `selected` is in view with `visible` columns
+    fn first_column(&self, selected: usize, visible: usize) -> usize {
+        selected.saturating_sub(visible.saturating_sub(1)).min(self.cases.len().saturating_sub(visible))
+    }
+
+    /// Overlay lines: a header of case numbers, then one row per code. The selected case's
+    /// column is bracketed; `width` is the room inside the overlay.
+    pub fn lines(&self, selected_case: usize, width: usize) -> Vec<String> {
+        let visible = (width.saturating_sub(LABEL_WIDTH) / CELL_WIDTH).max(1).min(self.cases.len());
+        let first = self.first_column(selected_case, visible);
+        let columns = first..first + visible;
+
+        let mut header = format!("{:<width$}", "", width = LABEL_WIDTH);
+        for c in columns.clone() {
+            let label = if c == selected_case { format!("[{}]", c + 1) } else { (c + 1).to_string() };
+            header.push_str(&format!("{:>width$}", label, width = CELL_WIDTH));
+        }
+        let mut lines = vec![header];
+
+        for (r, (_, name, depth)) in self.codes.iter().enumerate() {
+            let label: String = format!("{}{}", "  ".repeat(*depth), name).chars().take(LABEL_WIDTH - 1).collect();
+            let mut line = format!("{:<width$}", label, width = LABEL_WIDTH);
+            for c in columns.clone() {
+                let count = self.count(r, c);
+                let cell = match (count, c == selected_case) {
+                    (0, true) => "[·]".to_string(),
+                    (0, false) => "·".to_string(),
+                    (n, true) => format!("[{}]", n),
+                    (n, false) => n.to_string(),
+                };
+                line.push_str(&format!("{:>width$}", cell, width = CELL_WIDTH));
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// Overlay lines for one cell's segments
+    pub fn cell_lines(&self, code: usize, case: usize) -> Vec<String> {
+        self.cell(code, case).iter()
+            .map(|row| {
+                let text: String = row.text.split_whitespace().collect::<Vec<_>>().join(" ");
+                let excerpt: String = text.chars().take(70).collect();
+                let ellipsis = if text.chars().count() > 70 { "..." } else { "" };
+                format!("\"{}{}\"", excerpt, ellipsis)
+            })
+            .collect()
+    }
+
+    /// Counts as CSV: a row per code, a column per case
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("code");
+        for (_, title) in &self.cases {
+            out.push(',');
+            out.push_str(&csv_field(title));
+        }
+        out.push('\n');
+        for (r, (_, name, _)) in self.codes.iter().enumerate() {
+            out.push_str(&csv_field(name));
+            for c in 0..self.cases.len() {
+                out.push_str(&format!(",{}", self.count(r, c)));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Write exports/matrix.csv and return its path
+pub fn export(notes_dir: &Path, matrix: &Matrix) -> Result<PathBuf> {
+    let dir = notes_dir.join("exports");
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("matrix.csv");
+    std::fs::write(&path, matrix.to_csv())?;
+    Ok(path)
+}
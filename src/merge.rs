@@ -0,0 +1,111 @@
+// Merging notes into one and splitting one in two
+//
+// Merge appends each marked note's body (front matter dropped) to the first one marked,
+// under a heading with its title. Split cuts the open note at the cursor - or at the start
+// of the heading the cursor is on - moves the rest into a new note and leaves a
+// [[wiki link]] to it behind. Either way the coded segments and highlights travel with
+// their text: offsets are shifted to where the text lands, and a segment cut in two by a
+// split is kept on both sides.
+
+use crate::front_matter::parse_front_matter;
+use crate::note_store::{CodedSegment, Note};
+use crate::outline;
+
+/// Where a source note's text went in a merge: chars from `from` on moved to `base` onward
+#[derive(Debug, Clone)]
+pub struct Moved {
+    pub note_id: String,
+    pub from: usize,
+    pub base: usize,
+}
+
+/// Char offset where a note's body starts (after its front matter, if any)
+pub fn body_start(content: &str) -> usize {
+    parse_front_matter(content).map_or(0, |fm| content[..fm.body_start].chars().count())
+}
+
+/// The target's text with every source appended under a "## Title" heading
+pub fn merged_content(target: &Note, sources: &[Note]) -> (String, Vec<Moved>) {
+    let mut content = target.content.clone();
+    let mut moved = Vec::new();
+    for source in sources {
+        content.push_str(if content.is_empty() || content.ends_with("\n\n") {
+            ""
+        } else if content.ends_with('\n') {
+            "\n"
+        } else {
+            "\n\n"
+        });
+        content.push_str(&format!("## {}\n\n", source.title));
+        let from = body_start(&source.content);
+        let base = content.chars().count();
+        content.extend(source.content.chars().skip(from));
+        moved.push(Moved { note_id: source.id.clone(), from, base });
+    }
+    (content, moved)
+}
+
+/// Where to split and the new note's title: the start of the cursor's line if it is a
+/// heading (titled after it), otherwise the cursor itself (titled after the first line
+/// split off). None when there would be nothing on one side, or inside the front matter.
+pub fn split_point(content: &str, cursor: usize) -> Option<(usize, String)> {
+    let total = content.chars().count();
+    let cursor = cursor.min(total);
+    let line = content.chars().take(cursor).filter(|&c| c == '\n').count();
+    let line_start = content.chars().take(cursor).collect::<String>().rfind('\n')
+        .map_or(0, |byte| content[..byte + 1].chars().count());
+
+    let (at, title) = match outline::headings(content).into_iter().find(|h| h.line == line) {
+        Some(heading) => (line_start, heading.title),
+        None => {
+            let title = content.chars().skip(cursor).collect::<String>()
+                .lines()
+                .map(|l| l.trim().trim_start_matches('#').trim())
+                .find(|l| !l.is_empty())
+                .map(|l| l.chars().take(60).collect::<String>())
+                .unwrap_or_default();
+            (cursor, title)
+        }
+    };
+    let start = body_start(content);
+    let before: String = content.chars().take(at).skip(start).collect();
+    let after: String = content.chars().skip(at).collect();
+    if at < start || before.trim().is_empty() || after.trim().is_empty() {
+        return None;
+    }
+    Some((at, title))
+}
+
+/// `title`, or "title (2)", "title (3)" ... if a note already has it (wiki links go by title)
+pub fn unique_title(title: &str, notes: &[Note]) -> String {
+    let taken = |t: &str| notes.iter().any(|n| n.title == t);
+    if !taken(title) {
+        return title.to_string();
+    }
+    (2..).map(|n| format!("{} ({})", title, n)).find(|t| !taken(t)).unwrap_or_default()
+}
+
+/// Move the segments at or after `from` into `to`, shifted so `from` lands on `base`.
+/// A segment running across `from` is cut there and kept on both sides.
+pub fn move_segments(from_note: &mut Vec<CodedSegment>, to_note: &mut Vec<CodedSegment>, from: usize, base: usize) {
+    let shift = |offset: usize| offset - from + base;
+    let mut kept = Vec::new();
+    for mut segment in from_note.drain(..) {
+        if segment.start_offset >= from {
+            segment.start_offset = shift(segment.start_offset);
+            segment.end_offset = shift(segment.end_offset);
+            to_note.push(segment);
+        } else if segment.end_offset > from {
+            to_note.push(CodedSegment {
+                start_offset: base,
+                end_offset: shift(segment.end_offset),
+                ..segment.clone()
+            });
+            segment.end_offset = from;
+            kept.push(segment);
+        } else {
+            kept.push(segment);
+        }
+    }
+    *from_note = kept;
+}
@@ -0,0 +1,114 @@
+// Status bar messages - the current one, and a log of what was said
+//
+// Every message has a level. Info fades from the status bar after a few seconds,
+// warnings and errors stay longer and are colored (theme status_warn_fg /
+// status_error_fg). All of them go into the log, which the message history overlay
+// (Ctrl+G g) shows newest first - errors from background work such as indexing or
+// the clipboard end up there instead of vanishing with the next message.
+
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const LOG_LEN: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+        }
+    }
+
+    /// How long a message of this level stays in the status bar
+    fn lifetime(self) -> Duration {
+        match self {
+            Level::Info => Duration::from_secs(5),
+            Level::Warn => Duration::from_secs(10),
+            Level::Error => Duration::from_secs(20),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub text: String,
+    pub level: Level,
+    pub at: DateTime<Local>,
+    shown: Instant,
+}
+
+impl Message {
+    pub fn describe(&self) -> String {
+        format!("{}  {:5}  {}", self.at.format("%H:%M:%S"), self.level.name(), self.text)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Messages {
+    current: Option<Message>,
+    log: VecDeque<Message>,  // Newest first
+}
+
+impl Messages {
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.push(Level::Info, text.into());
+    }
+
+    pub fn warn(&mut self, text: impl Into<String>) {
+        self.push(Level::Warn, text.into());
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(Level::Error, text.into());
+    }
+
+    fn push(&mut self, level: Level, text: String) {
+        let message = Message { text, level, at: Local::now(), shown: Instant::now() };
+        // Progress updates ("Reindexing... 40/120") replace each other in the log
+        if self.log.front().is_some_and(|m| m.level == level && same_progress(&m.text, &message.text)) {
+            self.log.pop_front();
+        }
+        self.log.push_front(message.clone());
+        self.log.truncate(LOG_LEN);
+        // An info message doesn't hide an error that is still on screen
+        if level == Level::Info && self.visible().is_some_and(|m| m.level == Level::Error) {
+            return;
+        }
+        self.current = Some(message);
+    }
+
+    /// The message for the status bar, if it hasn't expired
+    pub fn visible(&self) -> Option<&Message> {
+        self.current.as_ref().filter(|m| m.shown.elapsed() < m.level.lifetime())
+    }
+
+    /// Drop the current message once its time is up; true if the status bar changed
+    pub fn expire(&mut self) -> bool {
+        if self.current.is_some() && self.visible().is_none() {
+            self.current = None;
+            return true;
+        }
+        false
+    }
+
+    pub fn log(&self) -> impl Iterator<Item = &Message> {
+        self.log.iter()
+    }
+}
+
+/// Both texts are "<label>... <progress>" with the same label
+fn same_progress(a: &str, b: &str) -> bool {
+    match (a.split_once("... "), b.split_once("... ")) {
+        (Some((x, _)), Some((y, _))) => x == y,
+        _ => false,
+    }
+}
@@ -0,0 +1,153 @@
+// Markdown mirror - one .md file per note under <notes_dir>/mirror, plus a drift checker
+//
+// Each mirror file starts with a marker line carrying the note id, so files can be
+// renamed freely and still match their note:
+//   <!-- snyfter3:id=abc123def456 -->
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::export::file_stem;
+use crate::note_store::Note;
+
+const MARKER_PREFIX: &str = "<!-- snyfter3:id=";
+const MARKER_SUFFIX: &str = " -->";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Drift {
+    MissingFile,  // Note has no mirror file
+    Differs,      // File content doesn't match the database
+    OrphanFile,   // File whose note no longer exists (or a new hand-written file)
+}
+
+impl Drift {
+    pub fn label(self) -> &'static str {
+        match self {
+            Drift::MissingFile => "missing",
+            Drift::Differs => "differs",
+            Drift::OrphanFile => "orphan",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MirrorEntry {
+    pub note_id: Option<String>,
+    pub title: String,
+    pub path: PathBuf,
+    pub drift: Drift,
+}
+
+pub fn mirror_dir(notes_dir: &Path) -> PathBuf {
+    notes_dir.join("mirror")
+}
+
+fn render(note: &Note) -> String {
+    format!("{}{}{}\n{}", MARKER_PREFIX, note.id, MARKER_SUFFIX, note.content)
+}
+
+/// Split a mirror file into (note id, content); files without a marker have no id
+fn parse(text: &str) -> (Option<String>, String) {
+    let (first, rest) = text.split_once('\n').unwrap_or((text, ""));
+    let id = first.trim_end()
+        .strip_prefix(MARKER_PREFIX)
+        .and_then(|s| s.strip_suffix(MARKER_SUFFIX))
+        .map(|s| s.trim().to_string());
+
+    match id {
+        Some(id) => (Some(id), rest.to_string()),
+        None => (None, text.to_string()),
+    }
+}
+
+/// Mirror files currently on disk, keyed by the note id in their marker
+fn scan(notes_dir: &Path) -> Result<(HashMap<String, (PathBuf, String)>, Vec<(PathBuf, String)>)> {
+    let mut by_id = HashMap::new();
+    let mut unmarked = Vec::new();
+
+    let dir = mirror_dir(notes_dir);
+    if !dir.exists() {
+        return Ok((by_id, unmarked));
+    }
+
+    for entry in std::fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("md") {
+            continue;
+        }
+        let text = std::fs::read_to_string(&path)?;
+        match parse(&text) {
+            (Some(id), content) => { by_id.insert(id, (path, content)); }
+            (None, content) => unmarked.push((path, content)),
+        }
+    }
+
+    Ok((by_id, unmarked))
+}
+
+/// Write (or overwrite) the mirror file for one note, keeping its existing file name
+pub fn write_note(notes_dir: &Path, note: &Note) -> Result<PathBuf> {
+    let (by_id, _) = scan(notes_dir)?;
+    let dir = mirror_dir(notes_dir);
+    std::fs::create_dir_all(&dir)?;
+
+    let path = match by_id.get(&note.id) {
+        Some((path, _)) => path.clone(),
+        None => {
+            // Two notes with the same title get the id appended
+            let path = dir.join(format!("{}.md", file_stem(note)));
+            if path.exists() {
+                dir.join(format!("{}-{}.md", file_stem(note), note.id))
+            } else {
+                path
+            }
+        }
+    };
+
+    std::fs::write(&path, render(note))?;
+    Ok(path)
+}
+
+/// Compare every note against its mirror file
+pub fn check(notes_dir: &Path, notes: &[Note]) -> Result<Vec<MirrorEntry>> {
+    let (mut by_id, unmarked) = scan(notes_dir)?;
+    let mut report = Vec::new();
+
+    for note in notes {
+        match by_id.remove(&note.id) {
+            None => report.push(MirrorEntry {
+                note_id: Some(note.id.clone()),
+                title: note.title.clone(),
+                path: mirror_dir(notes_dir).join(format!("{}.md", file_stem(note))),
+                drift: Drift::MissingFile,
+            }),
+            Some((path, content)) if content != note.content => report.push(MirrorEntry {
+                note_id: Some(note.id.clone()),
+                title: note.title.clone(),
+                path,
+                drift: Drift::Differs,
+            }),
+            Some(_) => {}
+        }
+    }
+
+    // Whatever is left on disk has no matching note
+    for (path, _) in by_id.into_values().chain(unmarked) {
+        let title = path.file_stem().and_then(|s| s.to_str()).unwrap_or("untitled").to_string();
+        report.push(MirrorEntry { note_id: None, title, path, drift: Drift::OrphanFile });
+    }
+
+    Ok(report)
+}
+
+/// Link an existing (orphan) file to a note by rewriting it with the note's marker
+pub fn adopt(path: &Path, note: &Note) -> Result<()> {
+    std::fs::write(path, render(note))?;
+    Ok(())
+}
+
+/// Content of a mirror file without its marker line
+pub fn read_content(path: &Path) -> Result<String> {
+    Ok(parse(&std::fs::read_to_string(path)?).1)
+}
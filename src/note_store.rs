@@ -1,12 +1,15 @@
-// Note storage and management using SQLite with FTS5
+// Note storage and management using SQLite (schema in db.rs)
 
 use anyhow::Result;
 use rusqlite::{Connection, params, OptionalExtension};
 use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use sha2::{Sha256, Digest};
 
+use crate::db;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Note {
     pub id: String,
@@ -31,35 +34,19 @@ pub struct NoteStore {
 }
 
 impl NoteStore {
+    #[tracing::instrument(level = "debug", skip_all)]
     pub fn new(notes_dir: &Path) -> Result<Self> {
-        let db_path = notes_dir.join("notes.db");
-        let conn = Connection::open(&db_path)?;
-
-        // Create tables if they don't exist
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS notes (
-                id TEXT PRIMARY KEY,
-                title TEXT NOT NULL,
-                content TEXT NOT NULL,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL,
-                tags TEXT,
-                codes TEXT
-            )",
-            [],
-        )?;
-
-        // Note: FTS5 removed since rusqlite doesn't support it directly
-        // We'll rely on Tantivy for full-text search instead
+        let conn = db::open_migrated(&notes_dir.join("notes.db"), db::NOTES)?;
 
         Ok(NoteStore {
             conn,
         })
     }
 
+    #[tracing::instrument(level = "debug", skip(self, content))]
     pub fn create_note(&mut self, title: &str, content: &str) -> Result<Note> {
         let now = Utc::now();
-        let id = self.generate_id(title, &now);
+        let id = Self::generate_id(title, &now);
 
         let note = Note {
             id: id.clone(),
@@ -71,23 +58,35 @@ impl NoteStore {
             codes: Vec::new(),
         };
 
-        self.conn.execute(
-            "INSERT INTO notes (id, title, content, created_at, updated_at, tags, codes)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![
-                &note.id,
-                &note.title,
-                &note.content,
-                &note.created_at.to_rfc3339(),
-                &note.updated_at.to_rfc3339(),
-                serde_json::to_string(&note.tags)?,
-                serde_json::to_string(&note.codes)?,
-            ],
-        )?;
-
+        insert_note(&self.conn, &note)?;
         Ok(note)
     }
 
+    /// Create many notes from (title, content) in one transaction - one disk flush instead
+    /// of one per note, for imports. Their tags are taken from the text
+    #[tracing::instrument(level = "debug", skip_all, fields(count = drafts.len()))]
+    pub fn create_notes(&mut self, drafts: &[(String, String)]) -> Result<Vec<Note>> {
+        let tx = self.conn.transaction()?;
+        let mut notes = Vec::with_capacity(drafts.len());
+        for (title, content) in drafts {
+            let now = Utc::now();
+            let note = Note {
+                id: Self::generate_id(title, &now),
+                title: title.clone(),
+                content: content.clone(),
+                created_at: now,
+                updated_at: now,
+                tags: crate::App::extract_tags(content),
+                codes: Vec::new(),
+            };
+            insert_note(&tx, &note)?;
+            notes.push(note);
+        }
+        tx.commit()?;
+        Ok(notes)
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(note_id = %note.id))]
     pub fn update_note(&mut self, note: &Note) -> Result<()> {
         let updated = Note {
             updated_at: Utc::now(),
@@ -110,11 +109,57 @@ impl NoteStore {
         Ok(())
     }
 
-    pub fn delete_note(&mut self, id: &str) -> Result<()> {
-        self.conn.execute("DELETE FROM notes WHERE id = ?1", params![id])?;
+    /// Store a note exactly as given, timestamps included, replacing (and untrashing) any
+    /// note with its id - for notes that come from another device
+    #[tracing::instrument(level = "debug", skip_all, fields(note_id = %note.id))]
+    pub fn put_note(&mut self, note: &Note) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO notes (id, title, content, created_at, updated_at, tags, codes)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(id) DO UPDATE SET title = ?2, content = ?3, created_at = ?4,
+                 updated_at = ?5, tags = ?6, codes = ?7, deleted_at = NULL",
+            params![
+                &note.id,
+                &note.title,
+                &note.content,
+                &note.created_at.to_rfc3339(),
+                &note.updated_at.to_rfc3339(),
+                serde_json::to_string(&note.tags)?,
+                serde_json::to_string(&note.codes)?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Move a note to the trash (soft delete) - it stays restorable until purged
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub fn trash_note(&mut self, id: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE notes SET deleted_at = ?1 WHERE id = ?2",
+            params![Utc::now().to_rfc3339(), id],
+        )?;
         Ok(())
     }
 
+    /// Take a note back out of the trash
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub fn restore_note(&mut self, id: &str) -> Result<Option<Note>> {
+        self.conn.execute("UPDATE notes SET deleted_at = NULL WHERE id = ?1", params![id])?;
+        self.get_note(id)
+    }
+
+    /// Permanently delete notes that were trashed before the cutoff
+    pub fn purge_trash(&mut self, trashed_before: DateTime<Utc>) -> Result<usize> {
+        let purged = self.conn.execute(
+            "DELETE FROM notes WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+            params![trashed_before.to_rfc3339()],
+        )?;
+        if purged > 0 {
+            tracing::info!(purged, "emptied old notes from the trash");
+        }
+        Ok(purged)
+    }
+
     pub fn get_note(&mut self, id: &str) -> Result<Option<Note>> {
         let mut stmt = self.conn.prepare(
             "SELECT id, title, content, created_at, updated_at, tags, codes
@@ -154,7 +199,8 @@ impl NoteStore {
     pub fn get_note_by_index(&self, index: usize) -> Result<Option<Note>> {
         let mut stmt = self.conn.prepare(
             "SELECT id, title, content, created_at, updated_at, tags, codes
-             FROM notes ORDER BY updated_at DESC LIMIT 1 OFFSET ?1"
+             FROM notes WHERE deleted_at IS NULL
+             ORDER BY updated_at DESC LIMIT 1 OFFSET ?1"
         )?;
 
         let note = stmt.query_row(params![index], |row| {
@@ -194,7 +240,7 @@ impl NoteStore {
         let mut stmt = self.conn.prepare(
             "SELECT id, title, content, created_at, updated_at, tags, codes
              FROM notes
-             WHERE title LIKE ?1 OR content LIKE ?1 OR tags LIKE ?1
+             WHERE deleted_at IS NULL AND (title LIKE ?1 OR content LIKE ?1 OR tags LIKE ?1)
              ORDER BY updated_at DESC"
         )?;
 
@@ -233,10 +279,12 @@ impl NoteStore {
         Ok(notes)
     }
 
+    #[tracing::instrument(level = "trace", skip_all)]
     pub fn get_all_notes(&self) -> Result<Vec<Note>> {
         let mut stmt = self.conn.prepare(
             "SELECT id, title, content, created_at, updated_at, tags, codes
-             FROM notes ORDER BY updated_at DESC"
+             FROM notes WHERE deleted_at IS NULL
+             ORDER BY updated_at DESC"
         )?;
 
         let notes_iter = stmt.query_map([], |row| {
@@ -276,13 +324,13 @@ impl NoteStore {
 
     pub fn get_note_count(&self) -> usize {
         self.conn.query_row(
-            "SELECT COUNT(*) FROM notes",
+            "SELECT COUNT(*) FROM notes WHERE deleted_at IS NULL",
             [],
             |row| row.get(0)
         ).unwrap_or(0)
     }
 
-    fn generate_id(&self, title: &str, created_at: &DateTime<Utc>) -> String {
+    fn generate_id(title: &str, created_at: &DateTime<Utc>) -> String {
         let mut hasher = Sha256::new();
         hasher.update(title.as_bytes());
         hasher.update(created_at.to_rfc3339().as_bytes());
@@ -290,6 +338,39 @@ impl NoteStore {
         format!("{:x}", result)[..12].to_string()
     }
 
+    /// Text of the notes in the trash, which can still be restored
+    pub fn trashed_contents(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare("SELECT content FROM notes WHERE deleted_at IS NOT NULL")?;
+        let contents = stmt.query_map([], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+        Ok(contents)
+    }
+
+    /// Ids of the notes whose text is locked against editing
+    pub fn read_only_ids(&self) -> Result<HashSet<String>> {
+        let mut stmt = self.conn.prepare("SELECT id FROM notes WHERE read_only = 1 AND deleted_at IS NULL")?;
+        let ids = stmt.query_map([], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+        Ok(ids)
+    }
+
+    /// Zettelkasten IDs (config zettel_ids) by note id, for the notes that have one
+    pub fn zettel_ids(&self) -> Result<HashMap<String, String>> {
+        let mut stmt = self.conn.prepare("SELECT id, zettel_id FROM notes WHERE zettel_id IS NOT NULL AND deleted_at IS NULL")?;
+        let ids = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?.collect::<rusqlite::Result<_>>()?;
+        Ok(ids)
+    }
+
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub fn set_zettel_id(&mut self, id: &str, zettel_id: &str) -> Result<()> {
+        self.conn.execute("UPDATE notes SET zettel_id = ?1 WHERE id = ?2", params![zettel_id, id])?;
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub fn set_read_only(&mut self, id: &str, read_only: bool) -> Result<()> {
+        self.conn.execute("UPDATE notes SET read_only = ?1 WHERE id = ?2", params![read_only, id])?;
+        Ok(())
+    }
+
     #[allow(dead_code)]
     pub fn add_code_to_note(&mut self, note_id: &str, segment: CodedSegment) -> Result<()> {
         if let Some(mut note) = self.get_note(note_id)? {
@@ -307,4 +388,19 @@ impl NoteStore {
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+fn insert_note(conn: &Connection, note: &Note) -> Result<()> {
+    conn.prepare_cached(
+        "INSERT INTO notes (id, title, content, created_at, updated_at, tags, codes)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"
+    )?.execute(params![
+        &note.id,
+        &note.title,
+        &note.content,
+        &note.created_at.to_rfc3339(),
+        &note.updated_at.to_rfc3339(),
+        serde_json::to_string(&note.tags)?,
+        serde_json::to_string(&note.codes)?,
+    ])?;
+    Ok(())
+}
@@ -0,0 +1,112 @@
+// Outline of the open note - its markdown headings as a collapsible tree
+//
+// Headings are ATX style ("## Section"); lines inside front matter and fenced code
+// blocks don't count. Collapsing a heading hides everything nested below it until
+// the next heading of the same or a higher level.
+
+use std::collections::HashSet;
+
+#[derive(Debug, Clone)]
+pub struct Heading {
+    pub level: usize,   // 1 for "#", up to 6
+    pub title: String,
+    pub line: usize,    // Line in the note
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+    let hashes = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[hashes..];
+    if (1..=6).contains(&hashes) && (rest.is_empty() || rest.starts_with(' ')) {
+        Some(hashes)
+    } else {
+        None
+    }
+}
+
+pub fn headings(text: &str) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut in_front_matter = false;
+    let mut in_fence = false;
+
+    for (i, line) in text.lines().enumerate() {
+        let trimmed = line.trim_end();
+        if i == 0 && trimmed == "---" {
+            in_front_matter = true;
+            continue;
+        }
+        if in_front_matter {
+            in_front_matter = trimmed != "---";
+            continue;
+        }
+        if trimmed.trim_start().starts_with("```") || trimmed.trim_start().starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        if let Some(level) = heading_level(trimmed) {
+            let title = trimmed[level..].trim().trim_end_matches('#').trim_end().to_string();
+            headings.push(Heading { level, title, line: i });
+        }
+    }
+    headings
+}
+
+/// Whether the heading at `index` has nested headings under it
+pub fn has_children(headings: &[Heading], index: usize) -> bool {
+    headings.get(index + 1).is_some_and(|next| next.level > headings[index].level)
+}
+
+/// Index of the nearest heading above `index` with a lower level
+pub fn parent(headings: &[Heading], index: usize) -> Option<usize> {
+    let level = headings.get(index)?.level;
+    (0..index).rev().find(|&i| headings[i].level < level)
+}
+
+/// Indices of the headings not hidden under a collapsed one; `collapsed` holds heading lines
+pub fn visible(headings: &[Heading], collapsed: &HashSet<usize>) -> Vec<usize> {
+    let mut rows = Vec::new();
+    let mut hidden_below: Option<usize> = None;  // Level of the collapsed heading being skipped
+    for (i, heading) in headings.iter().enumerate() {
+        if let Some(level) = hidden_below {
+            if heading.level > level {
+                continue;
+            }
+            hidden_below = None;
+        }
+        rows.push(i);
+        if collapsed.contains(&heading.line) && has_children(headings, i) {
+            hidden_below = Some(heading.level);
+        }
+    }
+    rows
+}
+
+/// Heading the line belongs to: the last one at or above it
+pub fn section_at(headings: &[Heading], line: usize) -> Option<usize> {
+    headings.iter().rposition(|h| h.line <= line)
+}
+
+/// Line of the next heading after (or the previous one before) `line`
+pub fn neighbor(headings: &[Heading], line: usize, forward: bool) -> Option<usize> {
+    if forward {
+        headings.iter().find(|h| h.line > line).map(|h| h.line)
+    } else {
+        headings.iter().rev().find(|h| h.line < line).map(|h| h.line)
+    }
+}
+
+/// Overlay row for a heading: indented by level, with ▸ / ▾ on headings that have children
+pub fn row_label(headings: &[Heading], index: usize, collapsed: &HashSet<usize>) -> String {
+    let heading = &headings[index];
+    let marker = if !has_children(headings, index) {
+        "  "
+    } else if collapsed.contains(&heading.line) {
+        "▸ "
+    } else {
+        "▾ "
+    };
+    let title = if heading.title.is_empty() { "(untitled)" } else { heading.title.as_str() };
+    format!("{}{}{}", "  ".repeat(heading.level - 1), marker, title)
+}
@@ -0,0 +1,144 @@
+// Modal overlay panel drawn on top of the panes (help, reports, pickers)
+
+/// What Enter (and other action keys) do in an overlay; plain overlays only scroll
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverlayAction {
+    RecoverJournal,
+    RecoverDrafts,      // Editor text left behind by a session that ended abruptly
+    UsageReport,
+    PasteHistory,  // Lines are kill-ring entries; Enter pastes the selected one
+    MirrorSync,    // Lines are drifted mirror entries; d/f sync the selected one
+    Leader,        // Which-key popup; the next key picks a leader command
+    ReviewSuggestions,  // Lines are machine-suggested codes; a/r/e accept, reject, relabel
+    ReviewRelabel,      // Code picker for relabeling the suggestion being reviewed
+    TemplatePicker,     // Lines are the built-in QDA templates; Enter creates a note from one
+    TemplateCode,       // Code picker for a template that is about a code
+    Highlights,         // Lines are the open note's read-time highlights; Enter jumps, d deletes, c converts
+    HighlightCode,      // Code picker for converting a highlight into a code application
+    Annotations,        // Lines are the open note's annotations; Enter jumps, e edits, d deletes
+    Journal,            // Lines are project journal entries; Enter reads one, n / e / d / x
+    JournalEntry,       // One journal entry in full; e edits it, Esc goes back to the list
+    Calendar,           // Month grid of daily notes; arrows move the day, Enter opens it
+    Tasks,              // Lines are checkbox tasks from all notes; Space toggles, Enter opens
+    Outline,            // Lines are the open note's headings; Enter jumps, ←→ collapse / expand
+    Attachments,        // Lines are the open note's attachments; Enter opens one
+    Citations,          // Lines are references matching the @citekey being typed; Enter inserts one
+    Duplicates,         // Lines are pairs of likely duplicate notes; Enter compares one
+    DuplicatePair,      // Two notes side by side; m merges them, l / r trash one
+    RecodeFrom,         // Code picker: the code a merge / recode moves segments away from
+    RecodeInto,         // Code picker: the code they move to
+    RecodeConfirm,      // Summary of the merge / recode; Enter carries it out
+    MoveCodePick,       // Code tree: the code to move
+    MoveCodeParent,     // Code tree plus "top level": where the code goes
+    Codebook,           // Codebook export formats and importable files; Enter runs one
+    ReportCodes,        // Code checklist for a report; Space ticks, Enter goes on to the format
+    ReportFormat,       // Report formats; Enter writes the report
+    WordFrequency,      // Lines are word counts; Enter opens a word's concordance
+    Concordance,        // Lines are a word's occurrences in context; Enter jumps to one, Esc goes back
+    AutoCodePick,       // Code picker for auto-coding a pattern's matches
+    AutoCodePreview,    // Lines are the matches; Space ticks, u cycles the unit, Enter codes them
+    Matrix,             // Codes x cases counts; ←→ pick the case, Enter lists a cell's segments
+    MatrixCell,         // Segments of one code in one case; Enter jumps to one, Esc goes back
+    Coverage,           // Lines are notes by coverage; Enter opens one at its last coded position
+    SegmentFilter,      // Line 0 is "All codes", then codes by name; Enter sets what Alt+←/→ step through
+    CodeVisibility,     // Codebook tree with a box per code; Space / b / a toggle the editor tints
+    SelectionActions,   // Quick actions on the editor selection; their keys or Enter run them
+    SelectionCode,      // Codes by name; Enter codes the editor selection
+    Metadata,           // Front matter fields of the open note, then "+ add a field"; Enter / a / d edit
+    TagManager,         // Tags by use; r / m / d rename, merge or remove one
+    TagMergeInto,       // The other tags; Enter previews merging into the selected one
+    TagPreview,         // Notes a tag change touches; Enter applies it
+    TagSuggestions,     // Tags suggested for the open note; Enter / a add, d dismisses
+    Related,            // Notes most like the open one, best first; Enter opens one
+    LinkCheck,          // Broken links, orphans, unused attachments; Enter / o fix, d d deletes
+    QuickSwitcher,      // Notes, recent first, filtered by the typed text; Enter opens one
+    SearchHistory,      // Past queries, filtered by the typed text; Enter runs one
+    DateFilter,         // Date periods and the typed range; Enter puts one in the query
+}
+
+pub struct Overlay {
+    pub title: String,
+    pub lines: Vec<String>,
+    pub scroll: usize,
+    pub action: Option<OverlayAction>,
+    pub selected: Option<usize>,  // Highlighted line for picker overlays
+}
+
+impl Overlay {
+    pub fn new(title: &str, lines: Vec<String>) -> Self {
+        Self {
+            title: title.to_string(),
+            lines,
+            scroll: 0,
+            action: None,
+            selected: None,
+        }
+    }
+
+    /// Turn the overlay into a picker with the first line highlighted
+    pub fn with_selection(mut self) -> Self {
+        self.selected = if self.lines.is_empty() { None } else { Some(0) };
+        self
+    }
+
+    pub fn select_prev(&mut self) {
+        if let Some(selected) = self.selected {
+            let selected = selected.saturating_sub(1);
+            self.selected = Some(selected);
+            self.scroll = self.scroll.min(selected);
+        }
+    }
+
+    pub fn select_next(&mut self, visible: usize) {
+        if let Some(selected) = self.selected {
+            let selected = (selected + 1).min(self.lines.len().saturating_sub(1));
+            self.selected = Some(selected);
+            if selected >= self.scroll + visible {
+                self.scroll = selected + 1 - visible;
+            }
+        }
+    }
+
+    /// Select a line and scroll just enough to show it
+    pub fn select(&mut self, index: usize, visible: usize) {
+        let index = index.min(self.lines.len().saturating_sub(1));
+        self.selected = Some(index);
+        self.scroll = self.scroll.min(index).max((index + 1).saturating_sub(visible));
+    }
+
+    pub fn with_action(mut self, action: OverlayAction) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    /// Outer box (x, y, width, height) for a terminal of the given size
+    pub fn frame(term_width: u16, term_height: u16) -> (u16, u16, u16, u16) {
+        let width = term_width.saturating_sub(8).clamp(20, 90).min(term_width);
+        let height = term_height.saturating_sub(4).max(5).min(term_height);
+        let x = (term_width - width) / 2;
+        let y = (term_height - height) / 2;
+        (x, y, width, height)
+    }
+
+    /// Number of content rows inside the frame (minus border, title and footer)
+    pub fn visible_rows(term_height: u16) -> usize {
+        let (_, _, _, height) = Self::frame(80, term_height);
+        height.saturating_sub(4) as usize
+    }
+
+    fn max_scroll(&self, visible: usize) -> usize {
+        self.lines.len().saturating_sub(visible)
+    }
+
+    pub fn scroll_up(&mut self, amount: usize) {
+        self.scroll = self.scroll.saturating_sub(amount);
+    }
+
+    pub fn scroll_down(&mut self, amount: usize, visible: usize) {
+        self.scroll = (self.scroll + amount).min(self.max_scroll(visible));
+    }
+
+    pub fn scroll_to_end(&mut self, visible: usize) {
+        self.scroll = self.max_scroll(visible);
+    }
+}
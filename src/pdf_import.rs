@@ -0,0 +1,115 @@
+// PDF import - one note per document, with the page breaks kept as marker lines
+//
+//   snyfter3 import-pdf interview-07.pdf
+//
+// Text comes from `pdftotext` (poppler-utils), which has to be installed. Each page
+// starts with a marker line, so an offset in the note - say a coded segment - maps
+// back to the PDF page it came from even after the text around it is edited:
+//
+//   <!-- page 3 -->
+//
+// The PDF itself is stored as an attachment and referenced from the front matter.
+// Scanned PDFs without a text layer are refused; they need OCR first.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::attachments;
+use crate::note_store::{Note, NoteStore};
+
+const MARKER_START: &str = "<!-- page ";
+const MARKER_END: &str = " -->";
+
+/// Create a note from a PDF's text
+pub fn import_pdf(notes_dir: &Path, store: &mut NoteStore, path: &Path) -> Result<Note> {
+    let pages = extract_pages(path)?;
+    if pages.iter().all(|p| p.trim().is_empty()) {
+        bail!("{} has no text layer (scanned? run OCR on it first)", path.display());
+    }
+    let attachment = attachments::add(notes_dir, path)?;
+    let title = path.file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| attachment.name.clone());
+
+    let mut content = String::from("---\n");
+    content.push_str(&format!("source: {}\npages: {}\npdf: {}{}\n", attachment.name, pages.len(), attachments::SCHEME, attachment.id));
+    content.push_str("---\n");
+    for (i, page) in pages.iter().enumerate() {
+        content.push_str(&format!("\n{}\n\n", marker(i + 1)));
+        content.push_str(&tidy(page));
+    }
+
+    let note = store.create_note(&title, &content)?;
+    tracing::info!(file = %path.display(), pages = pages.len(), note = %note.id, "pdf import");
+    Ok(note)
+}
+
+/// Page texts in order (pdftotext ends every page with a form feed)
+fn extract_pages(path: &Path) -> Result<Vec<String>> {
+    let output = Command::new("pdftotext")
+        .args(["-enc", "UTF-8"])
+        .arg(path)
+        .arg("-")
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| anyhow!("could not run pdftotext ({}); install poppler-utils", e))?;
+    if !output.status.success() {
+        bail!("pdftotext failed on {}: {}", path.display(), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    let text = String::from_utf8(output.stdout).context("pdftotext output is not UTF-8")?;
+
+    let mut pages: Vec<String> = text.split('\u{c}').map(str::to_string).collect();
+    if pages.len() > 1 && pages.last().is_some_and(|p| p.trim().is_empty()) {
+        pages.pop();
+    }
+    Ok(pages)
+}
+
+/// Trailing spaces off every line, runs of blank lines cut to one, ending in a newline
+fn tidy(page: &str) -> String {
+    let mut out = String::new();
+    let mut blank = true;  // No blank lines at the top of a page
+    for line in page.lines().map(str::trim_end) {
+        if line.is_empty() {
+            if !blank {
+                out.push('\n');
+            }
+            blank = true;
+        } else {
+            out.push_str(line);
+            out.push('\n');
+            blank = false;
+        }
+    }
+    while out.ends_with("\n\n") {
+        out.pop();
+    }
+    out
+}
+
+fn marker(page: usize) -> String {
+    format!("{}{}{}", MARKER_START, page, MARKER_END)
+}
+
+/// Page number of a marker line
+pub fn marker_page(line: &str) -> Option<usize> {
+    line.trim().strip_prefix(MARKER_START)?.strip_suffix(MARKER_END)?.trim().parse().ok()
+}
+
+/// PDF page a char offset in an imported note falls on (None before the first marker,
+/// and for notes that didn't come from a PDF)
+pub fn page_at(content: &str, offset: usize) -> Option<usize> {
+    let mut page = None;
+    let mut position = 0;
+    for line in content.split_inclusive('\n') {
+        if position > offset {
+            break;
+        }
+        if let Some(n) = marker_page(line) {
+            page = Some(n);
+        }
+        position += line.chars().count();
+    }
+    page
+}
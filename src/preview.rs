@@ -0,0 +1,90 @@
+// Note preview - a read-only popup over the editor for the note under the mouse, or
+// the highlighted one in preview mode
+//
+// nvALT-style lists open every note they pass over. With preview mode on (Ctrl+G V,
+// config "list_preview") moving through the list only highlights, and a note that
+// stays highlighted for a moment is shown in a popup: title, tags, codes and its
+// first lines. The open note, its scroll position and undo history stay as they were
+// until Enter opens the highlighted one. Hovering the mouse over a list entry shows
+// the same popup in either mode.
+
+use std::time::{Duration, Instant};
+
+use crate::front_matter::parse_front_matter;
+use crate::note_store::Note;
+
+/// How long a note has to stay highlighted (or hovered) before its preview shows
+const DWELL: Duration = Duration::from_millis(350);
+/// Body lines shown at most
+pub const MAX_LINES: usize = 12;
+
+#[derive(Debug, Clone)]
+pub struct Preview {
+    pub note_id: String,
+    pub hovered: bool,  // Under the mouse, rather than highlighted from the keyboard
+    since: Instant,
+    announced: bool,  // A redraw was asked for when the dwell ended
+}
+
+impl Preview {
+    pub fn new(note_id: &str) -> Self {
+        Preview { note_id: note_id.to_string(), hovered: false, since: Instant::now(), announced: false }
+    }
+
+    pub fn hovered(note_id: &str) -> Self {
+        Preview { hovered: true, ..Preview::new(note_id) }
+    }
+
+    pub fn ready(&self) -> bool {
+        self.since.elapsed() >= DWELL
+    }
+
+    /// True once, when the dwell time has just run out (the screen needs a redraw)
+    pub fn became_ready(&mut self) -> bool {
+        if self.announced || !self.ready() {
+            return false;
+        }
+        self.announced = true;
+        true
+    }
+}
+
+/// Popup lines: tags and codes (name and count, most used first), a blank line, then the
+/// start of the body with blank lines squeezed
+pub fn lines(note: &Note, codes: &[(String, usize)]) -> Vec<String> {
+    let mut lines = Vec::new();
+    if !note.tags.is_empty() {
+        lines.push(format!("Tags: {}", note.tags.iter().map(|t| format!("#{}", t)).collect::<Vec<_>>().join(" ")));
+    }
+    if !codes.is_empty() {
+        let list: Vec<String> = codes.iter().map(|(name, n)| format!("{} ({})", name, n)).collect();
+        lines.push(format!("Codes: {}", list.join(", ")));
+    }
+    lines.push(format!("Edited {}", note.updated_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")));
+    lines.push(String::new());
+
+    let body = parse_front_matter(&note.content).map_or(note.content.as_str(), |fm| &note.content[fm.body_start..]);
+    let mut blank = true;  // Skips leading blank lines too
+    let mut shown = 0;
+    for line in body.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            if blank {
+                continue;
+            }
+            blank = true;
+        } else {
+            blank = false;
+        }
+        if shown == MAX_LINES {
+            lines.push("…".to_string());
+            break;
+        }
+        lines.push(line.to_string());
+        shown += 1;
+    }
+    if shown == 0 {
+        lines.push("(empty note)".to_string());
+    }
+    lines
+}
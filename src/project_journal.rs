@@ -0,0 +1,168 @@
+// Project journal - dated analysis memos about the project as a whole
+//
+// QualCoder-style journal: "merged 'cost' into 'money', the two kept overlapping",
+// "stopped coding tone after interview 6". Entries belong to the project rather than to a
+// note, so they live in their own table in notes.db (journal.rs is something else: the
+// unsaved-revision recovery). Ctrl+G J lists them newest first, filtered by the search bar
+// text; the list exports them to exports/journal.md, and `snyfter3 journal` adds, searches
+// and prints entries from the shell.
+
+use anyhow::Result;
+use chrono::{DateTime, Local, Utc};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::db;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Entry {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub text: String,
+}
+
+impl Entry {
+    /// "2026-03-02 14:05", local time of writing
+    pub fn date(&self) -> String {
+        self.created_at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()
+    }
+
+    /// Every word of `query` appears in the entry (case-insensitive); empty matches all
+    pub fn matches(&self, query: &str) -> bool {
+        let text = self.text.to_lowercase();
+        query.split_whitespace().all(|word| text.contains(&word.to_lowercase()))
+    }
+}
+
+/// An entry being typed in the editor header: a new one, or an edit of an existing one
+#[derive(Debug, Clone, Default)]
+pub struct JournalEdit {
+    pub id: Option<i64>,
+    pub text: String,
+}
+
+pub struct ProjectJournal {
+    conn: Connection,
+}
+
+impl ProjectJournal {
+    pub fn new(notes_dir: &Path) -> Result<Self> {
+        // The journal_entries table is created by the notes.db migrations
+        let conn = db::open_migrated(&notes_dir.join("notes.db"), db::NOTES)?;
+        Ok(ProjectJournal { conn })
+    }
+
+    /// Save a typed entry; emptying an existing one deletes it. Returns the entry's id.
+    pub fn save(&mut self, edit: &JournalEdit) -> Result<Option<i64>> {
+        let text = edit.text.trim();
+        let now = Utc::now().to_rfc3339();
+        match edit.id {
+            Some(id) if text.is_empty() => {
+                self.remove(id)?;
+                Ok(None)
+            }
+            Some(id) => {
+                self.conn.execute(
+                    "UPDATE journal_entries SET text = ?1, updated_at = ?2 WHERE id = ?3",
+                    params![text, now, id],
+                )?;
+                Ok(Some(id))
+            }
+            None if text.is_empty() => Ok(None),
+            None => {
+                self.conn.execute(
+                    "INSERT INTO journal_entries (text, created_at, updated_at) VALUES (?1, ?2, ?2)",
+                    params![text, now],
+                )?;
+                Ok(Some(self.conn.last_insert_rowid()))
+            }
+        }
+    }
+
+    pub fn remove(&mut self, id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM journal_entries WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// All entries, newest first
+    pub fn entries(&self) -> Result<Vec<Entry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, created_at, updated_at, text FROM journal_entries ORDER BY created_at DESC, id DESC"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let created_at: String = row.get(1)?;
+            let updated_at: String = row.get(2)?;
+            Ok(Entry {
+                id: row.get(0)?,
+                created_at: parse_time(&created_at),
+                updated_at: parse_time(&updated_at),
+                text: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Entries matching every word of `query`, newest first
+    pub fn search(&self, query: &str) -> Result<Vec<Entry>> {
+        Ok(self.entries()?.into_iter().filter(|e| e.matches(query)).collect())
+    }
+}
+
+fn parse_time(text: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(text).map_or_else(|_| Utc::now(), |t| t.with_timezone(&Utc))
+}
+
+/// The journal as Markdown, oldest entry first so it reads as a history of the analysis
+pub fn to_markdown(entries: &[Entry]) -> String {
+    let mut out = String::from("# Project journal\n");
+    for entry in entries.iter().rev() {
+        out.push_str(&format!("\n## {}\n\n{}\n", entry.date(), entry.text.trim_end()));
+    }
+    out
+}
+
+/// Write exports/journal.md and return its path
+pub fn export(notes_dir: &Path, entries: &[Entry]) -> Result<PathBuf> {
+    let dir = notes_dir.join("exports");
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("journal.md");
+    std::fs::write(&path, to_markdown(entries))?;
+    Ok(path)
+}
+
+/// Rows for the journal list: date and the start of the entry on one line
+pub fn list_lines(entries: &[Entry]) -> Vec<String> {
+    entries.iter()
+        .map(|e| {
+            let text: String = e.text.split_whitespace().collect::<Vec<_>>().join(" ");
+            let excerpt: String = text.chars().take(70).collect();
+            let ellipsis = if text.chars().count() > 70 { "..." } else { "" };
+            format!("{}  {}{}", e.date(), excerpt, ellipsis)
+        })
+        .collect()
+}
+
+/// One entry in full, wrapped to `width` columns, for the read-only view
+pub fn entry_lines(entry: &Entry, width: usize) -> Vec<String> {
+    let mut lines = vec![format!("Written {}", entry.date())];
+    if entry.updated_at.signed_duration_since(entry.created_at).num_seconds() > 60 {
+        lines[0].push_str(&format!(", edited {}", entry.updated_at.with_timezone(&Local).format("%Y-%m-%d %H:%M")));
+    }
+    lines.push(String::new());
+    for paragraph in entry.text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
@@ -1,12 +1,21 @@
 // Qualitative Data Analysis codes/tags system (QualCoder-style)
+//
+// Codes form a tree through parent_id. With color inheritance on (config
+// "inherit_code_colors") a child code is drawn in its top-level ancestor's hue, lighter
+// the deeper it sits and stepped between siblings so they stay apart; each code keeps
+// its own stored color for when inheritance is off.
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use serde::{Serialize, Deserialize};
 use std::path::Path;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use rusqlite::{Connection, params};
 use crossterm::style::Color;
 
+use crate::anchors;
+use crate::codebook;
+use crate::db;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Code {
     pub id: String,
@@ -25,7 +34,6 @@ pub struct CodeColor {
 }
 
 impl CodeColor {
-    #[allow(dead_code)]
     pub fn to_crossterm_color(&self) -> Color {
         Color::Rgb {
             r: self.r,
@@ -33,54 +41,71 @@ impl CodeColor {
             b: self.b,
         }
     }
+
+    /// A descendant's color: the same hue and saturation, lighter with depth, and nudged
+    /// lighter or darker by sibling position
+    fn shade(&self, depth: usize, sibling: usize) -> CodeColor {
+        let (h, s, l) = self.to_hsl();
+        let deeper = l + (0.9 - l).max(0.0) * (0.3 * depth as f32).min(0.8);
+        let step = (sibling % 3) as f32 - 1.0;
+        CodeColor::from_hsl(h, s, (deeper + 0.07 * step).clamp(0.2, 0.88))
+    }
+
+    fn to_hsl(&self) -> (f32, f32, f32) {
+        let (r, g, b) = (self.r as f32 / 255.0, self.g as f32 / 255.0, self.b as f32 / 255.0);
+        let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+        let l = (max + min) / 2.0;
+        if max == min {
+            return (0.0, 0.0, l);
+        }
+        let d = max - min;
+        let s = if l > 0.5 { d / (2.0 - max - min) } else { d / (max + min) };
+        let h = if max == r {
+            (g - b) / d + if g < b { 6.0 } else { 0.0 }
+        } else if max == g {
+            (b - r) / d + 2.0
+        } else {
+            (r - g) / d + 4.0
+        };
+        (h / 6.0, s, l)
+    }
+
+    fn from_hsl(h: f32, s: f32, l: f32) -> CodeColor {
+        let q = if l < 0.5 { l * (1.0 + s) } else { l + s - l * s };
+        let p = 2.0 * l - q;
+        let channel = |t: f32| {
+            let t = t.rem_euclid(1.0);
+            let v = if t < 1.0 / 6.0 {
+                p + (q - p) * 6.0 * t
+            } else if t < 0.5 {
+                q
+            } else if t < 2.0 / 3.0 {
+                p + (q - p) * (2.0 / 3.0 - t) * 6.0
+            } else {
+                p
+            };
+            (v * 255.0).round().clamp(0.0, 255.0) as u8
+        };
+        CodeColor { r: channel(h + 1.0 / 3.0), g: channel(h), b: channel(h - 1.0 / 3.0) }
+    }
 }
 
 pub struct CodeManager {
     conn: Connection,
     codes: HashMap<String, Code>,
+    inherit_colors: bool,  // Children drawn in shades of their top-level code's color
 }
 
 impl CodeManager {
     pub fn new(notes_dir: &Path) -> Result<Self> {
         let db_path = notes_dir.join("codes.db");
-        let conn = Connection::open(&db_path)?;
-
-        // Create codes table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS codes (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL UNIQUE,
-                description TEXT,
-                color_r INTEGER NOT NULL,
-                color_g INTEGER NOT NULL,
-                color_b INTEGER NOT NULL,
-                parent_id TEXT,
-                shortcut TEXT,
-                FOREIGN KEY (parent_id) REFERENCES codes(id)
-            )",
-            [],
-        )?;
-
-        // Create code applications table (links codes to text segments)
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS code_applications (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                code_id TEXT NOT NULL,
-                note_id TEXT NOT NULL,
-                start_offset INTEGER NOT NULL,
-                end_offset INTEGER NOT NULL,
-                memo TEXT,
-                created_at TEXT NOT NULL,
-                FOREIGN KEY (code_id) REFERENCES codes(id)
-            )",
-            [],
-        )?;
+        let conn = db::open_migrated(&db_path, db::CODES)?;
 
         // Load all codes into memory
         let codes = Self::load_codes(&conn)?;
 
         // Create default codes if none exist
-        let mut manager = CodeManager { conn, codes };
+        let mut manager = CodeManager { conn, codes, inherit_colors: false };
         if manager.codes.is_empty() {
             manager.create_default_codes()?;
         }
@@ -198,6 +223,16 @@ impl CodeManager {
     }
 
     pub fn delete_code(&mut self, id: &str) -> Result<()> {
+        // Children move up to the deleted code's parent (foreign keys are enforced)
+        let parent = self.codes.get(id).and_then(|c| c.parent_id.clone());
+        self.conn.execute(
+            "UPDATE codes SET parent_id = ?1 WHERE parent_id = ?2",
+            params![parent, id],
+        )?;
+        for code in self.codes.values_mut().filter(|c| c.parent_id.as_deref() == Some(id)) {
+            code.parent_id = parent.clone();
+        }
+
         // Delete all applications of this code first
         self.conn.execute(
             "DELETE FROM code_applications WHERE code_id = ?1",
@@ -214,6 +249,179 @@ impl CodeManager {
         Ok(())
     }
 
+    /// Fold `from` into `into`: its applications and child codes move over and it is deleted.
+    /// Returns the applications moved (with their old code id, for the audit log).
+    pub fn merge_code(&mut self, from: &str, into: &str) -> Result<Vec<CodeApplication>> {
+        let moved: Vec<CodeApplication> = self.get_all_code_applications()?
+            .into_values()
+            .flatten()
+            .filter(|app| app.code_id == from)
+            .collect();
+        // `into` may be one of the children; it takes `from`'s place rather than its own child's
+        let parent = self.codes.get(from).and_then(|c| c.parent_id.clone());
+
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "UPDATE code_applications SET code_id = ?2 WHERE code_id = ?1",
+            params![from, into],
+        )?;
+        tx.execute(
+            "UPDATE codes SET parent_id = ?3 WHERE id = ?2 AND parent_id = ?1",
+            params![from, into, parent],
+        )?;
+        tx.execute(
+            "UPDATE codes SET parent_id = ?2 WHERE parent_id = ?1",
+            params![from, into],
+        )?;
+        tx.execute("DELETE FROM codes WHERE id = ?1", params![from])?;
+        tx.commit()?;
+
+        for code in self.codes.values_mut() {
+            if code.id == into && code.parent_id.as_deref() == Some(from) {
+                code.parent_id = parent.clone();
+            } else if code.parent_id.as_deref() == Some(from) {
+                code.parent_id = Some(into.to_string());
+            }
+        }
+        self.codes.remove(from);
+        Ok(moved)
+    }
+
+    /// Move the applications of `from` in the given notes to `into`; returns the ones moved
+    pub fn recode(&mut self, from: &str, into: &str, note_ids: &[String]) -> Result<Vec<CodeApplication>> {
+        let mut all = self.get_all_code_applications()?;
+        let moved: Vec<CodeApplication> = note_ids.iter()
+            .filter_map(|id| all.remove(id))
+            .flatten()
+            .filter(|app| app.code_id == from)
+            .collect();
+
+        let tx = self.conn.transaction()?;
+        for app in &moved {
+            tx.execute(
+                "UPDATE code_applications SET code_id = ?2 WHERE id = ?1",
+                params![app.id, into],
+            )?;
+        }
+        tx.commit()?;
+        Ok(moved)
+    }
+
+    /// Codes directly under `id`
+    pub fn child_count(&self, id: &str) -> usize {
+        self.codes.values().filter(|c| c.parent_id.as_deref() == Some(id)).count()
+    }
+
+    /// Codes under `parent` (None: the top level), by name
+    fn children(&self, parent: Option<&str>) -> Vec<&Code> {
+        let mut children: Vec<&Code> = self.codes.values().filter(|c| c.parent_id.as_deref() == parent).collect();
+        children.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()).then_with(|| a.id.cmp(&b.id)));
+        children
+    }
+
+    /// Every code in tree order (parents before their children, siblings by name) with its depth
+    pub fn tree(&self) -> Vec<(&Code, usize)> {
+        fn walk<'a>(manager: &'a CodeManager, parent: Option<&str>, depth: usize, out: &mut Vec<(&'a Code, usize)>) {
+            for code in manager.children(parent) {
+                out.push((code, depth));
+                walk(manager, Some(&code.id), depth + 1, out);
+            }
+        }
+        let mut out = Vec::new();
+        walk(self, None, 0, &mut out);
+        out
+    }
+
+    /// The names from the top of the tree down to `id` ("Barriers > Cost")
+    pub fn path(&self, id: &str) -> String {
+        let mut names = Vec::new();
+        let mut current = self.codes.get(id);
+        while let Some(code) = current {
+            names.push(code.name.as_str());
+            if names.len() > self.codes.len() {
+                break;  // A cycle left by hand-edited data
+            }
+            current = code.parent_id.as_deref().and_then(|p| self.codes.get(p));
+        }
+        if names.is_empty() {
+            return id.to_string();
+        }
+        names.reverse();
+        names.join(" > ")
+    }
+
+    /// `id` is `ancestor` or somewhere below it
+    pub fn is_within(&self, id: &str, ancestor: &str) -> bool {
+        let mut current = Some(id);
+        let mut steps = 0;
+        while let Some(code) = current {
+            if code == ancestor {
+                return true;
+            }
+            current = self.codes.get(code).and_then(|c| c.parent_id.as_deref());
+            steps += 1;
+            if steps > self.codes.len() {
+                break;  // A cycle left by hand-edited data - don't spin on it
+            }
+        }
+        false
+    }
+
+    /// Hang a code under another parent (None: make it top level)
+    pub fn move_code(&mut self, id: &str, parent: Option<&str>) -> Result<()> {
+        let Some(code) = self.codes.get(id) else {
+            bail!("no code {}", id);
+        };
+        if let Some(parent) = parent {
+            if !self.codes.contains_key(parent) {
+                bail!("no code {}", parent);
+            }
+            if self.is_within(parent, id) {
+                bail!("can't move \"{}\" under itself or one of its own children", code.name);
+            }
+        }
+        self.conn.execute(
+            "UPDATE codes SET parent_id = ?1 WHERE id = ?2",
+            params![parent, id],
+        )?;
+        if let Some(code) = self.codes.get_mut(id) {
+            code.parent_id = parent.map(str::to_string);
+        }
+        Ok(())
+    }
+
+    pub fn set_inherit_colors(&mut self, inherit: bool) {
+        self.inherit_colors = inherit;
+    }
+
+    pub fn inherits_colors(&self) -> bool {
+        self.inherit_colors
+    }
+
+    /// The color a code is drawn and exported in: its own, or with inheritance on a shade
+    /// of its top-level ancestor's
+    pub fn color_of(&self, id: &str) -> CodeColor {
+        let fallback = CodeColor { r: 128, g: 128, b: 128 };
+        let Some(code) = self.codes.get(id) else {
+            return fallback;
+        };
+        if !self.inherit_colors || code.parent_id.is_none() {
+            return code.color.clone();
+        }
+
+        let mut root = code;
+        let mut depth = 0;
+        while let Some(parent) = root.parent_id.as_deref().and_then(|p| self.codes.get(p)) {
+            root = parent;
+            depth += 1;
+            if depth > self.codes.len() {
+                return code.color.clone();
+            }
+        }
+        let sibling = self.children(code.parent_id.as_deref()).iter().position(|c| c.id == code.id).unwrap_or(0);
+        root.color.shade(depth, sibling)
+    }
+
     pub fn get_code(&self, id: &str) -> Option<&Code> {
         self.codes.get(id)
     }
@@ -230,7 +438,6 @@ impl CodeManager {
         self.codes.values().collect()
     }
 
-    #[allow(dead_code)]
     pub fn apply_code(
         &mut self,
         code_id: &str,
@@ -238,12 +445,14 @@ impl CodeManager {
         start_offset: usize,
         end_offset: usize,
         memo: Option<String>,
-    ) -> Result<()> {
+        coder: Option<&str>,
+        lines: Option<(usize, usize)>,
+    ) -> Result<i64> {
         let created_at = chrono::Utc::now().to_rfc3339();
 
         self.conn.execute(
-            "INSERT INTO code_applications (code_id, note_id, start_offset, end_offset, memo, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            "INSERT INTO code_applications (code_id, note_id, start_offset, end_offset, memo, created_at, coder, start_line, end_line)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
             params![
                 code_id,
                 note_id,
@@ -251,9 +460,102 @@ impl CodeManager {
                 end_offset as i64,
                 memo,
                 created_at,
+                coder,
+                lines.map(|(first, _)| first as i64),
+                lines.map(|(_, last)| last as i64),
+            ],
+        )?;
+
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Apply one code to many (note id, start, end, line anchor) spans in a single transaction;
+    /// returns the new applications' row ids
+    pub fn apply_code_to_spans(&mut self, code_id: &str, spans: &[(String, usize, usize, Option<(usize, usize)>)], coder: Option<&str>) -> Result<Vec<i64>> {
+        let created_at = chrono::Utc::now().to_rfc3339();
+        let mut ids = Vec::new();
+        let tx = self.conn.transaction()?;
+        for (note_id, start, end, lines) in spans {
+            tx.execute(
+                "INSERT INTO code_applications (code_id, note_id, start_offset, end_offset, memo, created_at, coder, start_line, end_line)
+                 VALUES (?1, ?2, ?3, ?4, NULL, ?5, ?6, ?7, ?8)",
+                params![
+                    code_id, note_id, *start as i64, *end as i64, created_at, coder,
+                    lines.map(|(first, _)| first as i64),
+                    lines.map(|(_, last)| last as i64),
+                ],
+            )?;
+            ids.push(tx.last_insert_rowid());
+        }
+        tx.commit()?;
+        Ok(ids)
+    }
+
+    /// Record a machine-suggested application (model output awaiting human review).
+    /// Returns false if the same code already covers exactly this span.
+    pub fn apply_suggestion(
+        &mut self,
+        code_id: &str,
+        note_id: &str,
+        start_offset: usize,
+        end_offset: usize,
+        confidence: Option<f64>,
+    ) -> Result<bool> {
+        let exists: bool = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM code_applications
+             WHERE code_id = ?1 AND note_id = ?2 AND start_offset = ?3 AND end_offset = ?4)",
+            params![code_id, note_id, start_offset as i64, end_offset as i64],
+            |row| row.get(0),
+        )?;
+        if exists {
+            return Ok(false);
+        }
+
+        self.conn.execute(
+            "INSERT INTO code_applications (code_id, note_id, start_offset, end_offset, created_at, suggested, confidence)
+             VALUES (?1, ?2, ?3, ?4, ?5, 1, ?6)",
+            params![
+                code_id,
+                note_id,
+                start_offset as i64,
+                end_offset as i64,
+                chrono::Utc::now().to_rfc3339(),
+                confidence,
             ],
         )?;
 
+        Ok(true)
+    }
+
+    /// Suggestions still awaiting review, most confident first
+    pub fn get_suggestions(&self) -> Result<Vec<CodeApplication>> {
+        let mut suggestions: Vec<CodeApplication> = self.get_all_code_applications()?
+            .into_values()
+            .flatten()
+            .filter(|app| app.suggested)
+            .collect();
+        suggestions.sort_by(|a, b| {
+            b.confidence.unwrap_or(0.0).total_cmp(&a.confidence.unwrap_or(0.0))
+                .then_with(|| a.note_id.cmp(&b.note_id))
+                .then_with(|| a.start_offset.cmp(&b.start_offset))
+        });
+        Ok(suggestions)
+    }
+
+    /// Turn a suggestion into a normal application, optionally under a different code
+    pub fn accept_suggestion(&mut self, id: i64, code_id: &str, coder: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE code_applications SET suggested = 0, code_id = ?1, coder = ?2 WHERE id = ?3",
+            params![code_id, coder, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn reject_suggestion(&mut self, id: i64) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM code_applications WHERE id = ?1 AND suggested = 1",
+            params![id],
+        )?;
         Ok(())
     }
 
@@ -273,10 +575,10 @@ impl CodeManager {
         Ok(())
     }
 
-    #[allow(dead_code)]
     pub fn get_code_applications(&self, note_id: &str) -> Result<Vec<CodeApplication>> {
         let mut stmt = self.conn.prepare(
-            "SELECT code_id, start_offset, end_offset, memo, created_at
+            "SELECT code_id, start_offset, end_offset, memo, created_at, coder, suggested, confidence, id,
+                    start_line, end_line
              FROM code_applications WHERE note_id = ?1
              ORDER BY start_offset"
         )?;
@@ -289,6 +591,11 @@ impl CodeManager {
                 end_offset: row.get::<_, i64>(2)? as usize,
                 memo: row.get(3)?,
                 created_at: row.get(4)?,
+                coder: row.get(5)?,
+                suggested: row.get(6)?,
+                confidence: row.get(7)?,
+                id: row.get(8)?,
+                lines: line_anchor(row.get(9)?, row.get(10)?),
             })
         })?;
 
@@ -300,30 +607,241 @@ impl CodeManager {
         Ok(apps)
     }
 
+    /// Put a note's line-anchored applications back over their lines in `text` (the note as
+    /// just saved); returns how many moved. Ones whose lines are gone keep their offsets.
+    pub fn reanchor(&mut self, note_id: &str, text: &str) -> Result<usize> {
+        let anchored: Vec<CodeApplication> = self.get_code_applications(note_id)?
+            .into_iter()
+            .filter(|app| app.lines.is_some())
+            .collect();
+        if anchored.is_empty() {
+            return Ok(0);
+        }
+        let mut moved = 0;
+        let tx = self.conn.transaction()?;
+        for app in &anchored {
+            let Some((first, last)) = app.lines else { continue };
+            let Some((start, end)) = anchors::lines_span(text, first, last) else { continue };
+            if (start, end) != (app.start_offset, app.end_offset) {
+                tx.execute(
+                    "UPDATE code_applications SET start_offset = ?2, end_offset = ?3 WHERE id = ?1",
+                    params![app.id, start as i64, end as i64],
+                )?;
+                moved += 1;
+            }
+        }
+        tx.commit()?;
+        Ok(moved)
+    }
+
+    /// Move applications at or after char `from` to another note, shifted so `from` lands on
+    /// `base` (merge / split); one running across `from` is cut there and kept on both sides.
+    /// Line anchors don't survive the move - the lines are numbered differently there.
+    pub fn move_applications(&mut self, from_note: &str, to_note: &str, from: usize, base: usize) -> Result<()> {
+        let (from, base) = (from as i64, base as i64);
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO code_applications
+                (code_id, note_id, start_offset, end_offset, memo, created_at, coder, suggested, confidence)
+             SELECT code_id, ?2, ?4, end_offset - ?3 + ?4, memo, created_at, coder, suggested, confidence
+             FROM code_applications
+             WHERE note_id = ?1 AND start_offset < ?3 AND end_offset > ?3",
+            params![from_note, to_note, from, base],
+        )?;
+        tx.execute(
+            "UPDATE code_applications SET end_offset = ?2, start_line = NULL, end_line = NULL
+             WHERE note_id = ?1 AND start_offset < ?2 AND end_offset > ?2",
+            params![from_note, from],
+        )?;
+        tx.execute(
+            "UPDATE code_applications
+             SET note_id = ?2, start_offset = start_offset - ?3 + ?4, end_offset = end_offset - ?3 + ?4,
+                 start_line = NULL, end_line = NULL
+             WHERE note_id = ?1 AND start_offset >= ?3",
+            params![from_note, to_note, from, base],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// The applications with these row ids (the ones still there)
+    pub fn get_applications(&self, ids: &[i64]) -> Result<Vec<CodeApplication>> {
+        let ids: HashSet<i64> = ids.iter().copied().collect();
+        Ok(self.get_all_code_applications()?.into_values().flatten().filter(|app| ids.contains(&app.id)).collect())
+    }
+
+    /// Delete the applications `remove` and write `rows` back under their own row ids - how
+    /// the coding history puts applications as they were (undo) or as an action left them (redo)
+    pub fn replace_applications(&mut self, remove: &[i64], rows: &[CodeApplication]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        for id in remove {
+            tx.execute("DELETE FROM code_applications WHERE id = ?1", params![id])?;
+        }
+        for app in rows {
+            tx.execute(
+                "INSERT OR REPLACE INTO code_applications
+                    (id, code_id, note_id, start_offset, end_offset, memo, created_at, coder, suggested, confidence, start_line, end_line)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                params![
+                    app.id, app.code_id, app.note_id, app.start_offset as i64, app.end_offset as i64,
+                    app.memo, app.created_at, app.coder, app.suggested, app.confidence,
+                    app.lines.map(|(first, _)| first as i64),
+                    app.lines.map(|(_, last)| last as i64),
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Bring back a code deleted by a merge, with the child codes it had
+    pub fn restore_code(&mut self, code: &Code, children: &[String]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO codes (id, name, description, color_r, color_g, color_b, parent_id, shortcut)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                &code.id,
+                &code.name,
+                &code.description,
+                code.color.r,
+                code.color.g,
+                code.color.b,
+                &code.parent_id,
+                code.shortcut.map(|c| c.to_string()),
+            ],
+        )?;
+        for child in children {
+            tx.execute("UPDATE codes SET parent_id = ?1 WHERE id = ?2", params![&code.id, child])?;
+        }
+        tx.commit()?;
+
+        self.codes.insert(code.id.clone(), code.clone());
+        for child in children {
+            if let Some(c) = self.codes.get_mut(child) {
+                c.parent_id = Some(code.id.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Every code application, grouped by note id (for searching memos and coded text)
+    pub fn get_all_code_applications(&self) -> Result<HashMap<String, Vec<CodeApplication>>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT code_id, note_id, start_offset, end_offset, memo, created_at, coder, suggested, confidence, id,
+                    start_line, end_line
+             FROM code_applications
+             ORDER BY note_id, start_offset"
+        )?;
+
+        let apps_iter = stmt.query_map([], |row| {
+            Ok(CodeApplication {
+                code_id: row.get(0)?,
+                note_id: row.get(1)?,
+                start_offset: row.get::<_, i64>(2)? as usize,
+                end_offset: row.get::<_, i64>(3)? as usize,
+                memo: row.get(4)?,
+                created_at: row.get(5)?,
+                coder: row.get(6)?,
+                suggested: row.get(7)?,
+                confidence: row.get(8)?,
+                id: row.get(9)?,
+                lines: line_anchor(row.get(10)?, row.get(11)?),
+            })
+        })?;
+
+        let mut by_note: HashMap<String, Vec<CodeApplication>> = HashMap::new();
+        for app in apps_iter {
+            let app = app?;
+            by_note.entry(app.note_id.clone()).or_default().push(app);
+        }
+
+        Ok(by_note)
+    }
+
     pub fn create_code_interactive(&mut self) -> Result<()> {
         // This would be called from UI to create a new code interactively
         // For now, just a placeholder
-        println!("Creating new code...");
+        tracing::debug!("interactive code creation requested");
         // TODO: Implement interactive code creation
         Ok(())
     }
 
     fn generate_id(&self, name: &str) -> String {
-        name.to_lowercase().replace(' ', "_")
+        id_for_name(name)
     }
 
-    pub fn export_codebook(&self) -> Result<String> {
-        // Export all codes as JSON for sharing/backup
-        let codes: Vec<&Code> = self.codes.values().collect();
-        Ok(serde_json::to_string_pretty(&codes)?)
+    /// The codebook as a file in `format` (see codebook.rs), parents before children
+    pub fn export_codebook(&self, format: codebook::Format) -> Result<String> {
+        codebook::write(&self.tree(), format)
     }
 
-    pub fn import_codebook(&mut self, json: &str) -> Result<()> {
-        let codes: Vec<Code> = serde_json::from_str(json)?;
+    /// Add the codes of a codebook file that aren't here yet. A code is already here when
+    /// its id, its QDC GUID or its name matches one of ours; those are left as they are.
+    pub fn import_codebook(&mut self, text: &str, format: codebook::Format) -> Result<CodebookImport> {
+        let incoming = codebook::read(text, format)?;
+
+        // Where each of the file's ids lands: an existing code or a newly added one
+        let mut ids: HashMap<String, String> = HashMap::new();
+        let mut taken: HashSet<String> = self.codes.keys().cloned().collect();
+        let mut shortcuts: HashSet<char> = self.codes.values().filter_map(|c| c.shortcut).collect();
+        let mut codes: Vec<Code> = Vec::new();
+        let mut existing = 0;
+        for code in &incoming {
+            let here = self.codes.values()
+                .chain(codes.iter())
+                .find(|c| c.id == code.id || codebook::guid(&c.id) == code.id || c.name.eq_ignore_ascii_case(&code.name))
+                .map(|c| c.id.clone());
+            if let Some(id) = here {
+                ids.insert(code.id.clone(), id);
+                existing += 1;
+                continue;
+            }
+
+            let base = id_for_name(&code.name);
+            let mut id = base.clone();
+            let mut n = 2;
+            while taken.contains(&id) {
+                id = format!("{}_{}", base, n);
+                n += 1;
+            }
+            taken.insert(id.clone());
+            ids.insert(code.id.clone(), id.clone());
+            // A shortcut already in use stays with the code that has it
+            let shortcut = code.shortcut.filter(|s| shortcuts.insert(*s));
+            codes.push(Code { id, shortcut, ..code.clone() });
+        }
 
-        for code in codes {
+        // Parents through the same mapping; one outside the file that exists here is kept,
+        // and anything else (or a loop in a hand-edited file) leaves the code at the top level
+        for code in &mut codes {
+            code.parent_id = code.parent_id.as_ref()
+                .and_then(|p| ids.get(p).cloned().or_else(|| self.codes.contains_key(p).then(|| p.clone())))
+                .filter(|p| *p != code.id);
+        }
+        let parent_of: HashMap<String, Option<String>> = codes.iter()
+            .map(|c| (c.id.clone(), c.parent_id.clone()))
+            .collect();
+        for code in &mut codes {
+            let mut current = code.parent_id.clone();
+            for _ in 0..=parent_of.len() {
+                current = match current.as_deref() {
+                    Some(p) if p == code.id => {
+                        code.parent_id = None;
+                        break;
+                    }
+                    Some(p) => parent_of.get(p).cloned().flatten(),
+                    None => break,
+                };
+            }
+        }
+
+        // Parents may come after their children in the file: check the keys at commit
+        let tx = self.conn.transaction()?;
+        tx.execute_batch("PRAGMA defer_foreign_keys = ON")?;
+        for code in &codes {
             // Try to insert, ignore if already exists
-            self.conn.execute(
+            tx.execute(
                 "INSERT OR IGNORE INTO codes (id, name, description, color_r, color_g, color_b, parent_id, shortcut)
                  VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
                 params![
@@ -337,21 +855,51 @@ impl CodeManager {
                     code.shortcut.map(|c| c.to_string()),
                 ],
             )?;
+        }
+        tx.commit()?;
 
+        let added = codes.len();
+        for code in codes {
             self.codes.insert(code.id.clone(), code);
         }
 
-        Ok(())
+        Ok(CodebookImport { added, existing })
+    }
+}
+
+/// Code ids are derived from their names
+pub fn id_for_name(name: &str) -> String {
+    name.to_lowercase().replace(' ', "_")
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CodebookImport {
+    pub added: usize,
+    pub existing: usize,  // Matched codes already in the codebook, left unchanged
+}
+
+impl CodebookImport {
+    pub fn describe(&self) -> String {
+        format!("Imported {} new code(s) ({} already in the codebook)", self.added, self.existing)
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct CodeApplication {
+    pub id: i64,  // Row id in code_applications
     pub code_id: String,
     pub note_id: String,
     pub start_offset: usize,
     pub end_offset: usize,
     pub memo: Option<String>,
     pub created_at: String,
+    pub coder: Option<String>,  // Who applied the code (None for applications made before coders were tracked)
+    pub suggested: bool,        // Machine-suggested (imported model output), not yet reviewed
+    pub confidence: Option<f64>,  // Model score for suggestions
+    pub lines: Option<(usize, usize)>,  // First and last line it is anchored to (config segment_anchors)
+}
+
+fn line_anchor(first: Option<i64>, last: Option<i64>) -> Option<(usize, usize)> {
+    Some((first? as usize, last? as usize))
 }
 
@@ -0,0 +1,471 @@
+// Search query parsing - field operators on top of the fuzzy text search
+//
+//   interview memo:"follow up" quote:money ann:check
+//
+// `memo:` matches the memos written on code applications, `quote:` matches the
+// coded text itself, `ann:` matches the comments of annotations. Field values are case-insensitive substrings; everything
+// else is handed to the fuzzy matcher as before. `is:suggested` keeps notes
+// that carry imported machine suggestions. `status:`, `project:` and `rating:` match
+// the note's front matter (`rating:>=4` compares numbers). Whole-word matches from the search
+// index (stemmed, CJK-aware - see analyzer.rs) rank ahead of fuzzy ones. Within each,
+// config.json "ranking" weights title matches over matches in the text and lifts recently
+// updated notes: by up to `recency_boost` for a note updated now, half that after
+// `recency_half_life_days`, and so on.
+//
+//   "action research" interview NEAR/5 consent
+//
+// A quoted phrase only matches those words in that order, side by side; `a NEAR/5 b` needs
+// both words at most five words apart, in either order (`NEAR` alone: ten). Both are
+// checked on the indexed words, so "actions researched" counts as the phrase too. A
+// `NEAR` without a word on each side, or with a distance that isn't a whole number above
+// zero (`NEAR/x`, `NEAR/0`), is no operator and is searched for as the word it is.
+//
+//   interview -pilot -"focus group" -tag:archive -status:draft
+//
+// A leading `-` turns a term around: `-word` (or a quoted phrase) leaves out the notes
+// using it, in any inflection; `-tag:name` those tagged #name; `-status:`, `-project:`
+// and `-rating:` those whose field matches. Only a `-` before a letter or a quote is
+// one, so `-5`, `--` or a lone `-` are searched for as typed. A query of exclusions
+// alone lists every other note. `created:` and `updated:` take dates and periods (see
+// date_filter.rs).
+//
+// A term that starts with a quote is always searched for as typed: `"-pilot"`, `"NEAR"`
+// and `"status:draft"` are words, not operators.
+
+use chrono::Utc;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+
+use nucleo::{Matcher, Utf32Str, pattern::{Pattern, CaseMatching, Normalization}};
+
+use crate::annotations::Annotation;
+use crate::date_filter::DateFilter;
+use crate::front_matter::parse_front_matter;
+use crate::note_store::Note;
+use crate::qda_codes::CodeApplication;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchQuery {
+    pub text: String,        // Free text for the fuzzy matcher
+    pub memo: Vec<String>,   // Lowercased memo: terms (all must match)
+    pub quote: Vec<String>,  // Lowercased quote: terms (all must match)
+    pub annotation: Vec<String>,  // Lowercased ann: terms (all must match)
+    pub suggested: bool,     // is:suggested - note has machine-suggested applications
+    pub fields: Vec<(String, String)>,  // Front matter field and lowercased value (all must match)
+    pub proximity: Vec<Proximity>,  // Phrases and NEAR pairs (all must hold); their words are in `text` too
+    pub exclude: Vec<String>,       // -word / -"phrase": notes using any of these are left out
+    pub exclude_tags: Vec<String>,  // -tag: names, lowercased and without the #
+    pub exclude_fields: Vec<(String, String)>,  // -status: etc.: front matter that rules a note out
+    pub dates: Vec<DateFilter>,     // created: / updated: (all must match)
+}
+
+/// A quoted phrase or `word NEAR/n word`: words that have to stand close together
+#[derive(Debug, Clone, PartialEq)]
+pub enum Proximity {
+    Phrase(String),               // These words in this order, next to each other
+    Near(String, String, usize),  // Both words, at most this many words apart, either order
+}
+
+/// Words apart that a bare `NEAR` allows
+const NEAR_DISTANCE: usize = 10;
+
+/// `NEAR` or `NEAR/5`, as the distance it allows
+fn near_operator(token: &str) -> Option<usize> {
+    match token.strip_prefix("NEAR")? {
+        "" => Some(NEAR_DISTANCE),
+        rest => rest.strip_prefix('/')?.parse().ok().filter(|&n| n > 0),
+    }
+}
+
+/// How search results are ordered (config.json "ranking")
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Ranking {
+    pub title_weight: f64,            // A title match counts as much as this many in the text
+    pub recency_boost: f64,           // Score lift for a note updated just now (0.5: +50%)
+    pub recency_half_life_days: f64,  // Days over which that lift halves
+}
+
+impl Default for Ranking {
+    fn default() -> Self {
+        Ranking { title_weight: 3.0, recency_boost: 0.5, recency_half_life_days: 30.0 }
+    }
+}
+
+impl Ranking {
+    /// Multiplier for a note's score by how recently it was updated
+    fn recency(&self, note: &Note) -> f64 {
+        if self.recency_boost <= 0.0 || self.recency_half_life_days <= 0.0 {
+            return 1.0;
+        }
+        let days = (Utc::now() - note.updated_at).num_seconds().max(0) as f64 / 86_400.0;
+        1.0 + self.recency_boost * 0.5f64.powf(days / self.recency_half_life_days)
+    }
+}
+
+/// Front matter fields the search bar filters on
+pub const FIELDS: &[&str] = &["status", "project", "rating"];
+
+impl SearchQuery {
+    pub fn parse(input: &str) -> Self {
+        let mut query = SearchQuery::default();
+        let mut text_terms = Vec::new();
+
+        for Token { text: token, quote } in tokenize(input) {
+            let negated = token.strip_prefix('-')
+                .filter(|rest| !rest.is_empty() && (quote == Some(1) || rest.starts_with(char::is_alphabetic)));
+            if quote == Some(0) {
+                text_terms.push((token, true));
+            } else if let Some(negated) = negated {
+                if let Some(tag) = negated.strip_prefix("tag:").map(|t| t.trim_start_matches('#')).filter(|t| !t.is_empty()) {
+                    query.exclude_tags.push(tag.to_lowercase());
+                } else if let Some((key, value)) = negated.split_once(':')
+                    .filter(|(k, v)| !v.is_empty() && FIELDS.iter().any(|f| f.eq_ignore_ascii_case(k)))
+                {
+                    query.exclude_fields.push((key.to_lowercase(), value.to_lowercase()));
+                } else {
+                    query.exclude.push(negated.to_string());
+                }
+            } else if let Some(value) = token.strip_prefix("memo:").filter(|v| !v.is_empty()) {
+                query.memo.push(value.to_lowercase());
+            } else if let Some(value) = token.strip_prefix("quote:").filter(|v| !v.is_empty()) {
+                query.quote.push(value.to_lowercase());
+            } else if let Some(value) = token.strip_prefix("ann:").filter(|v| !v.is_empty()) {
+                query.annotation.push(value.to_lowercase());
+            } else if let Some(date) = token.split_once(':').and_then(|(key, value)| DateFilter::parse(key, value)) {
+                query.dates.push(date);
+            } else if token.eq_ignore_ascii_case("is:suggested") {
+                query.suggested = true;
+            } else if let Some((key, value)) = token.split_once(':')
+                .filter(|(k, v)| !v.is_empty() && FIELDS.iter().any(|f| f.eq_ignore_ascii_case(k)))
+            {
+                query.fields.push((key.to_lowercase(), value.to_lowercase()));
+            } else {
+                text_terms.push((token, false));
+            }
+        }
+
+        // Phrases and NEAR pairs become conditions; their words still go to the text search
+        let mut words: Vec<String> = Vec::new();
+        let mut tokens = text_terms.into_iter().peekable();
+        while let Some((token, quoted)) = tokens.next() {
+            if let Some(distance) = near_operator(&token).filter(|_| !quoted) {
+                let right = tokens.peek().filter(|(t, quoted)| *quoted || near_operator(t).is_none()).map(|(t, _)| t);
+                if let (Some(left), Some(right)) = (words.last(), right) {
+                    query.proximity.push(Proximity::Near(left.clone(), right.clone(), distance));
+                    continue;
+                }
+            }
+            if token.contains(char::is_whitespace) {
+                query.proximity.push(Proximity::Phrase(token.clone()));
+            }
+            words.push(token);
+        }
+        query.text = words.join(" ");
+        query
+    }
+
+    /// The quoted phrases, as typed (for highlighting)
+    pub fn phrases(&self) -> Vec<&str> {
+        self.proximity.iter()
+            .filter_map(|p| match p {
+                Proximity::Phrase(text) => Some(text.as_str()),
+                Proximity::Near(..) => None,
+            })
+            .collect()
+    }
+
+    pub fn has_segment_terms(&self) -> bool {
+        !self.memo.is_empty() || !self.quote.is_empty() || !self.annotation.is_empty() || self.suggested
+    }
+
+    /// Whether there are terms for `matches_fields`: front matter fields, dates, and -tag: or
+    /// -field: exclusions (the -word ones go by the search index)
+    pub fn has_note_filters(&self) -> bool {
+        !self.fields.is_empty() || !self.dates.is_empty() || !self.exclude_tags.is_empty() || !self.exclude_fields.is_empty()
+    }
+
+    /// Check the status:/project:/rating: terms against the note's front matter, the
+    /// created:/updated: ones against its dates, and the -tag: / -field: exclusions
+    pub fn matches_fields(&self, note: &Note) -> bool {
+        if !self.dates.iter().all(|date| date.matches(note)) {
+            return false;
+        }
+        if self.exclude_tags.iter().any(|tag| note.tags.iter().any(|t| t.to_lowercase() == *tag)) {
+            return false;
+        }
+        if self.fields.is_empty() && self.exclude_fields.is_empty() {
+            return true;
+        }
+        let Some(fm) = parse_front_matter(&note.content) else {
+            return self.fields.is_empty();
+        };
+        let has = |key: &String, want: &str| fm.get(key).is_some_and(|have| field_matches(have, want));
+        self.fields.iter().all(|(key, want)| has(key, want))
+            && !self.exclude_fields.iter().any(|(key, want)| has(key, want))
+    }
+
+    /// Check the ann: terms against a note's annotations; each may match a different one
+    pub fn matches_annotations(&self, annotations: &[Annotation]) -> bool {
+        self.annotation.iter().all(|term| {
+            annotations.iter().any(|a| a.text.to_lowercase().contains(term.as_str()))
+        })
+    }
+
+    /// Check the memo:/quote: terms against a note's coded segments.
+    /// Each term may be satisfied by a different segment.
+    pub fn matches_segments(&self, note: &Note, applications: &[CodeApplication]) -> bool {
+        if self.suggested && !applications.iter().any(|a| a.suggested) {
+            return false;
+        }
+
+        // Segments live both on the note itself and in the code application table
+        let segments: Vec<(usize, usize, Option<&str>)> = note.codes.iter()
+            .map(|s| (s.start_offset, s.end_offset, s.memo.as_deref()))
+            .chain(applications.iter().map(|a| (a.start_offset, a.end_offset, a.memo.as_deref())))
+            .collect();
+
+        let memo_ok = self.memo.iter().all(|term| {
+            segments.iter().any(|(_, _, memo)| memo.is_some_and(|m| m.to_lowercase().contains(term.as_str())))
+        });
+        if !memo_ok {
+            return false;
+        }
+
+        self.quote.iter().all(|term| {
+            segments.iter().any(|&(start, end, _)| {
+                coded_text(&note.content, start, end).to_lowercase().contains(term.as_str())
+            })
+        })
+    }
+}
+
+/// `>=4`, `<3`... compare as numbers; anything else is a case-insensitive substring
+fn field_matches(have: &str, want: &str) -> bool {
+    let ops: [(&str, fn(f64, f64) -> bool); 4] = [(">=", |a, b| a >= b), ("<=", |a, b| a <= b), (">", |a, b| a > b), ("<", |a, b| a < b)];
+    for (op, compare) in ops {
+        if let Some(number) = want.strip_prefix(op) {
+            return match (have.trim().parse::<f64>(), number.parse::<f64>()) {
+                (Ok(a), Ok(b)) => compare(a, b),
+                _ => false,
+            };
+        }
+    }
+    have.to_lowercase().contains(want)
+}
+
+/// Rank notes for the free text, best first: notes the search index matched on
+/// every word (`term_scores`, by note id) lead, then fuzzy matches against title,
+/// content and tags. Empty text keeps every candidate in its original order.
+pub fn rank<'a>(candidates: Vec<&'a Note>, text: &str, term_scores: &HashMap<String, f64>, ranking: &Ranking) -> Vec<&'a Note> {
+    if text.is_empty() {
+        return candidates;
+    }
+
+    let pattern = Pattern::parse(text, CaseMatching::Ignore, Normalization::Smart);
+    let mut matcher = Matcher::default();
+    let mut matches = Vec::new();
+    let mut buf = Vec::new();
+
+    let mut word_matches = Vec::new();
+    for note in candidates {
+        if let Some(&score) = term_scores.get(&note.id) {
+            word_matches.push((score * ranking.recency(note), note));
+            continue;
+        }
+        let haystack = format!("{} {} {}", note.title, note.content, note.tags.join(" "));
+        buf.clear();
+        let Some(score) = pattern.score(Utf32Str::new(&haystack, &mut buf), &mut matcher) else {
+            continue;
+        };
+        // The title alone, weighted, when that beats the whole note
+        buf.clear();
+        let title = pattern.score(Utf32Str::new(&note.title, &mut buf), &mut matcher)
+            .map_or(0.0, |s| s as f64 * ranking.title_weight);
+        matches.push((title.max(score as f64) * ranking.recency(note), note));
+    }
+
+    // Sort by score (highest first)
+    word_matches.sort_by(|a, b| b.0.total_cmp(&a.0));
+    matches.sort_by(|a, b| b.0.total_cmp(&a.0));
+    word_matches.into_iter().map(|(_, note)| note)
+        .chain(matches.into_iter().map(|(_, note)| note))
+        .collect()
+}
+
+/// Char positions in a title that the fuzzy matcher matched, in order (for highlighting
+/// in the note list). Empty when the text doesn't match the title itself.
+pub fn title_matches(title: &str, text: &str) -> Vec<usize> {
+    if text.is_empty() {
+        return Vec::new();
+    }
+    let pattern = Pattern::parse(text, CaseMatching::Ignore, Normalization::Smart);
+    let mut matcher = Matcher::default();
+    let mut buf = Vec::new();
+    let mut indices = Vec::new();
+    if pattern.indices(Utf32Str::new(title, &mut buf), &mut matcher, &mut indices).is_none() {
+        return Vec::new();
+    }
+    // One run per word of the pattern: merge them
+    indices.sort_unstable();
+    indices.dedup();
+    indices.into_iter().map(|i| i as usize).collect()
+}
+
+/// Char positions in `text` covered by any of `phrases`, ignoring case (for highlighting a
+/// whole phrase rather than the letters the fuzzy matcher picked)
+pub fn phrase_matches(text: &str, phrases: &[&str]) -> Vec<usize> {
+    let chars: Vec<char> = text.chars().collect();
+    let same = |a: char, b: char| a == b || a.to_lowercase().eq(b.to_lowercase());
+    let mut matched = Vec::new();
+    for phrase in phrases {
+        let phrase: Vec<char> = phrase.chars().collect();
+        if phrase.is_empty() || phrase.len() > chars.len() {
+            continue;
+        }
+        for start in 0..=chars.len() - phrase.len() {
+            if phrase.iter().enumerate().all(|(i, &c)| same(chars[start + i], c)) {
+                matched.extend(start..start + phrase.len());
+            }
+        }
+    }
+    matched.sort_unstable();
+    matched.dedup();
+    matched
+}
+
+/// Text covered by a segment (offsets are in chars)
+pub fn coded_text(content: &str, start: usize, end: usize) -> String {
+    content.chars().skip(start).take(end.saturating_sub(start)).collect()
+}
+
+/// A term of the query, without its quotes
+struct Token {
+    text: String,
+    quote: Option<usize>,  // Where its first quote opened, in chars (`-"a b"`: 1)
+}
+
+/// Split on whitespace, keeping double-quoted runs together (`memo:"two words"`)
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let mut in_quotes = false;
+
+    for ch in input.chars() {
+        match ch {
+            '"' => {
+                in_quotes = !in_quotes;
+                quote.get_or_insert(current.chars().count());
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(Token { text: std::mem::take(&mut current), quote: quote.take() });
+                }
+                quote = None;
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(Token { text: current, quote });
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn near(left: &str, right: &str, distance: usize) -> Proximity {
+        Proximity::Near(left.to_string(), right.to_string(), distance)
+    }
+
+    #[test]
+    fn near_pairs() {
+        let query = SearchQuery::parse("consent NEAR/5 recording");
+        assert_eq!(query.proximity, [near("consent", "recording", 5)]);
+        assert_eq!(query.text, "consent recording");
+        assert_eq!(SearchQuery::parse("a NEAR b").proximity, [near("a", "b", NEAR_DISTANCE)]);
+        assert_eq!(SearchQuery::parse("a NEAR/2 b NEAR/3 c").proximity, [near("a", "b", 2), near("b", "c", 3)]);
+        // A quoted side keeps its words together
+        assert_eq!(SearchQuery::parse("\"focus group\" NEAR/4 consent").proximity,
+            [Proximity::Phrase("focus group".into()), near("focus group", "consent", 4)]);
+    }
+
+    #[test]
+    fn dangling_near_is_a_word() {
+        for input in ["NEAR consent", "consent NEAR", "NEAR"] {
+            let query = SearchQuery::parse(input);
+            assert!(query.proximity.is_empty(), "{}", input);
+            assert_eq!(query.text, input, "{}", input);
+        }
+        // The first has no word after it (the next is an operator); the second has the
+        // first, kept as a word, before it
+        let query = SearchQuery::parse("a NEAR NEAR b");
+        assert_eq!(query.proximity, [near("NEAR", "b", NEAR_DISTANCE)]);
+        assert_eq!(query.text, "a NEAR b");
+    }
+
+    #[test]
+    fn malformed_near_is_a_word() {
+        for input in ["a NEAR/x b", "a NEAR/0 b", "a NEAR/ b", "a NEAR/-2 b", "a near b", "a NEARBY b"] {
+            let query = SearchQuery::parse(input);
+            assert!(query.proximity.is_empty(), "{}", input);
+            assert_eq!(query.text, input, "{}", input);
+        }
+    }
+
+    #[test]
+    fn phrases() {
+        let query = SearchQuery::parse("\"action research\" interview");
+        assert_eq!(query.proximity, [Proximity::Phrase("action research".into())]);
+        assert_eq!(query.phrases(), ["action research"]);
+        assert_eq!(query.text, "action research interview");
+    }
+
+    #[test]
+    fn exclusions() {
+        let query = SearchQuery::parse("interview -pilot");
+        assert_eq!(query.text, "interview");
+        assert_eq!(query.exclude, ["pilot"]);
+        assert_eq!(SearchQuery::parse("-\"focus group\"").exclude, ["focus group"]);
+        assert_eq!(SearchQuery::parse("-\"5 stars\"").exclude, ["5 stars"]);
+        assert_eq!(SearchQuery::parse("-tag:#Archive").exclude_tags, ["archive"]);
+        assert_eq!(SearchQuery::parse("-tag:archive").exclude_tags, ["archive"]);
+        assert_eq!(SearchQuery::parse("-Status:Draft").exclude_fields, [("status".to_string(), "draft".to_string())]);
+        // Not a field the search bar knows: an ordinary excluded word
+        assert_eq!(SearchQuery::parse("-colour:red").exclude, ["colour:red"]);
+    }
+
+    #[test]
+    fn exclusions_alone() {
+        let query = SearchQuery::parse("-pilot -tag:old -status:draft");
+        assert!(query.text.is_empty());
+        assert_eq!(query.exclude, ["pilot"]);
+        assert_eq!(query.exclude_tags, ["old"]);
+        assert_eq!(query.exclude_fields, [("status".to_string(), "draft".to_string())]);
+    }
+
+    #[test]
+    fn hyphens_that_arent_exclusions() {
+        for input in ["-", "-5", "--verbose", "-\"\"", "well-being"] {
+            let query = SearchQuery::parse(input);
+            assert!(query.exclude.is_empty() && query.exclude_tags.is_empty() && query.exclude_fields.is_empty(), "{}", input);
+        }
+        assert_eq!(SearchQuery::parse("-").text, "-");
+        assert_eq!(SearchQuery::parse("score -5").text, "score -5");
+    }
+
+    #[test]
+    fn quoted_terms_are_searched_as_typed() {
+        let query = SearchQuery::parse("\"-pilot\" \"status:draft\"");
+        assert!(query.exclude.is_empty() && query.fields.is_empty());
+        assert_eq!(query.text, "-pilot status:draft");
+        let query = SearchQuery::parse("a \"NEAR\" b");
+        assert!(query.proximity.is_empty());
+        assert_eq!(query.text, "a NEAR b");
+        // A quoted word can still be a side of a NEAR
+        assert_eq!(SearchQuery::parse("a NEAR \"NEAR\"").proximity, [near("a", "NEAR", NEAR_DISTANCE)]);
+    }
+}
@@ -0,0 +1,113 @@
+// Reading mode layout - the open note soft-wrapped to the pane, with optional blank rows
+//
+// Close reading of dense transcripts is easier with air between the lines:
+//   single     - rows as written
+//   paragraphs - a blank row after every line of the note (existing blank lines aren't doubled)
+//   double     - a blank row after every displayed row, wrapped continuations included
+//
+// On terminals that can draw images, a line that is only an image reference becomes a
+// caption row followed by blank rows the image is drawn over (see images.rs).
+
+use serde::{Serialize, Deserialize};
+use unicode_width::UnicodeWidthChar;
+
+use crate::images;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineSpacing {
+    #[default]
+    Single,
+    Paragraphs,
+    Double,
+}
+
+impl LineSpacing {
+    pub fn next(self) -> Self {
+        match self {
+            LineSpacing::Single => LineSpacing::Paragraphs,
+            LineSpacing::Paragraphs => LineSpacing::Double,
+            LineSpacing::Double => LineSpacing::Single,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LineSpacing::Single => "single spacing",
+            LineSpacing::Paragraphs => "space between paragraphs",
+            LineSpacing::Double => "double spacing",
+        }
+    }
+}
+
+/// One displayed row and the note line it comes from (None for inserted blank rows)
+pub struct Row {
+    pub text: String,
+    pub line: Option<usize>,
+    pub image: Option<String>,  // Source of the image drawn below this caption row
+}
+
+impl Row {
+    fn text(text: String, line: Option<usize>) -> Self {
+        Row { text, line, image: None }
+    }
+}
+
+/// Lay a note out into display rows `width` columns wide, leaving `image_rows` rows
+/// under each image (0 when the terminal can't draw them)
+pub fn layout(text: &str, width: usize, spacing: LineSpacing, image_rows: usize) -> Vec<Row> {
+    let width = width.max(1);
+    let lines: Vec<&str> = text.lines().collect();
+    let mut rows = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        if let Some((alt, src)) = images::image_line(line).filter(|_| image_rows > 0) {
+            let caption = wrap(&format!("[image: {}]", if alt.is_empty() { src } else { alt }), width).remove(0);
+            rows.push(Row { text: caption, line: Some(i), image: Some(src.to_string()) });
+            rows.extend((0..image_rows).map(|_| Row::text(String::new(), Some(i))));
+            continue;
+        }
+        for piece in wrap(line, width) {
+            rows.push(Row::text(piece, Some(i)));
+            if spacing == LineSpacing::Double {
+                rows.push(Row::text(String::new(), None));
+            }
+        }
+        let next_has_text = lines.get(i + 1).is_some_and(|l| !l.trim().is_empty());
+        if spacing == LineSpacing::Paragraphs && !line.trim().is_empty() && next_has_text {
+            rows.push(Row::text(String::new(), None));
+        }
+    }
+    rows
+}
+
+/// Break one line at spaces so no row is wider than `width`; over-long words are split
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    let mut rows = Vec::new();
+    let mut current = String::new();
+    let mut current_width = 0;
+
+    for word in line.split_inclusive(' ') {
+        let word_width: usize = word.chars().map(|c| c.width().unwrap_or(0)).sum();
+        if current_width + word_width.saturating_sub(trailing_space(word)) > width && !current.is_empty() {
+            rows.push(current.trim_end().to_string());
+            current.clear();
+            current_width = 0;
+        }
+        for c in word.chars() {
+            let w = c.width().unwrap_or(0);
+            if current_width + w > width && c != ' ' {
+                rows.push(std::mem::take(&mut current));
+                current_width = 0;
+            }
+            current.push(c);
+            current_width += w;
+        }
+    }
+    rows.push(current.trim_end().to_string());
+    rows
+}
+
+fn trailing_space(word: &str) -> usize {
+    usize::from(word.ends_with(' '))
+}
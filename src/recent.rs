@@ -0,0 +1,112 @@
+// Recent notes - the most-recently-used list and browser-style back / forward
+//
+// A note counts as visited once it is open with the editor focused, however it got there
+// (the list, a link, a search, a jump), so stepping through the list with the arrows
+// doesn't fill the history. Ctrl+P is the quick switcher: recent notes first, then the
+// rest, narrowed by a fuzzy match on the title as you type. Alt+, / Alt+. (and Alt+← /
+// Alt+→ in the note list) go back and forward through the notes visited, like a
+// browser; visiting a note after going back drops the forward steps. The recent list is
+// kept with the session.
+
+use nucleo::{Matcher, Utf32Str, pattern::{Pattern, CaseMatching, Normalization}};
+
+use crate::note_store::Note;
+
+/// Notes kept in the recent list, and steps kept either way of the current note
+const MAX_RECENT: usize = 50;
+const MAX_STEPS: usize = 100;
+
+#[derive(Debug, Default)]
+pub struct NoteHistory {
+    recent: Vec<String>,  // Note ids, most recent first
+    back: Vec<String>,
+    forward: Vec<String>,
+    current: Option<String>,
+}
+
+impl NoteHistory {
+    /// Start from the last session's recent list
+    pub fn with_recent(recent: Vec<String>) -> Self {
+        NoteHistory { recent, ..NoteHistory::default() }
+    }
+
+    pub fn recent(&self) -> &[String] {
+        &self.recent
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    /// `note_id` was opened; does nothing while it is already the current note
+    pub fn visit(&mut self, note_id: &str) {
+        if self.current.as_deref() == Some(note_id) {
+            return;
+        }
+        if let Some(previous) = self.current.replace(note_id.to_string()) {
+            push(&mut self.back, previous);
+        }
+        self.forward.clear();
+        self.recent.retain(|id| id != note_id);
+        self.recent.insert(0, note_id.to_string());
+        self.recent.truncate(MAX_RECENT);
+    }
+
+    /// The note before the current one (which forward then returns to); `exists` skips
+    /// notes deleted since
+    pub fn back(&mut self, exists: impl Fn(&str) -> bool) -> Option<String> {
+        step(&mut self.back, &mut self.forward, &mut self.current, exists)
+    }
+
+    pub fn forward(&mut self, exists: impl Fn(&str) -> bool) -> Option<String> {
+        step(&mut self.forward, &mut self.back, &mut self.current, exists)
+    }
+}
+
+fn push(steps: &mut Vec<String>, id: String) {
+    steps.push(id);
+    if steps.len() > MAX_STEPS {
+        steps.remove(0);
+    }
+}
+
+fn step(from: &mut Vec<String>, to: &mut Vec<String>, current: &mut Option<String>, exists: impl Fn(&str) -> bool) -> Option<String> {
+    while let Some(id) = from.pop() {
+        if !exists(&id) || current.as_deref() == Some(id.as_str()) {
+            continue;
+        }
+        if let Some(previous) = current.replace(id.clone()) {
+            push(to, previous);
+        }
+        return Some(id);
+    }
+    None
+}
+
+/// The quick switcher's rows for `filter`: recent notes in the order last opened, then the
+/// rest in list order; with a filter, only titles it fuzzily matches, best first within each
+pub fn switcher<'a>(notes: &'a [Note], recent: &[String], filter: &str) -> Vec<&'a Note> {
+    let recent_rank = |note: &Note| recent.iter().position(|id| *id == note.id).unwrap_or(usize::MAX);
+    let mut rows: Vec<(usize, u32, usize, &Note)> = Vec::new();
+    let pattern = Pattern::parse(filter, CaseMatching::Ignore, Normalization::Smart);
+    let mut matcher = Matcher::default();
+    let mut buf = Vec::new();
+    for (order, note) in notes.iter().enumerate() {
+        let score = if filter.trim().is_empty() {
+            0
+        } else {
+            buf.clear();
+            match pattern.score(Utf32Str::new(&note.title, &mut buf), &mut matcher) {
+                Some(score) => score,
+                None => continue,
+            }
+        };
+        let rank = recent_rank(note);
+        // Ties on the match: recent notes by when they were last opened, the rest by list order
+        let group = usize::from(rank == usize::MAX);
+        let order = if group == 0 { rank } else { order };
+        rows.push((group, score, order, note));
+    }
+    rows.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.cmp(&a.1)).then_with(|| a.2.cmp(&b.2)));
+    rows.into_iter().map(|(_, _, _, note)| note).collect()
+}
@@ -0,0 +1,99 @@
+// Codebook maintenance - merging one code into another, and recoding segments in bulk
+//
+// Merge moves every segment of code A (in all notes) to code B, hangs A's child codes
+// under B and deletes A. Recode moves A's segments to B only in the notes the list
+// currently shows (search text and filter chips), and keeps A. Both go through the
+// same three steps: pick the code to move from, pick the code to move to, then confirm
+// a summary of what will change. Every moved segment is written to the audit log.
+
+use std::collections::HashMap;
+
+use crate::note_store::{CodedSegment, Note};
+use crate::qda_codes::CodeApplication;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Merge,   // Every note; the old code is deleted
+    Recode,  // Only the listed notes; the old code stays
+}
+
+impl Kind {
+    pub fn verb(self) -> &'static str {
+        match self {
+            Kind::Merge => "Merge",
+            Kind::Recode => "Recode",
+        }
+    }
+}
+
+/// A merge or recode being set up through the pickers
+#[derive(Debug, Clone)]
+pub struct Recode {
+    pub kind: Kind,
+    pub note_ids: Vec<String>,  // Notes a recode applies to (all notes for a merge)
+    pub choices: Vec<String>,   // Code ids in the order the open picker lists them
+    pub from: Option<String>,
+    pub into: Option<String>,
+}
+
+impl Recode {
+    pub fn new(kind: Kind, note_ids: Vec<String>) -> Self {
+        Recode { kind, note_ids, choices: Vec::new(), from: None, into: None }
+    }
+}
+
+/// Segments (in the notes and in the codes database) of `code_id` among `note_ids`,
+/// and how many notes they are in
+pub fn count(
+    code_id: &str,
+    note_ids: &[String],
+    notes: &[Note],
+    applications: &HashMap<String, Vec<CodeApplication>>,
+) -> (usize, usize) {
+    let mut segments = 0;
+    let mut in_notes = 0;
+    for id in note_ids {
+        let in_note = notes.iter().find(|n| n.id == *id)
+            .map_or(0, |n| n.codes.iter().filter(|s| s.code_id == code_id).count())
+            + applications.get(id).map_or(0, |apps| apps.iter().filter(|a| a.code_id == code_id).count());
+        if in_note > 0 {
+            segments += in_note;
+            in_notes += 1;
+        }
+    }
+    (segments, in_notes)
+}
+
+/// Point a note's segments of `from` at `into`; returns the segments changed
+pub fn recode_segments(segments: &mut [CodedSegment], from: &str, into: &str) -> usize {
+    let mut changed = 0;
+    for segment in segments.iter_mut().filter(|s| s.code_id == from) {
+        segment.code_id = into.to_string();
+        changed += 1;
+    }
+    changed
+}
+
+/// Lines for the confirmation prompt
+pub fn summary_lines(kind: Kind, from: &str, into: &str, segments: usize, notes: usize, children: usize) -> Vec<String> {
+    let mut lines = match kind {
+        Kind::Merge => vec![
+            format!("Merge \"{}\" into \"{}\":", from, into),
+            String::new(),
+            format!("  {} segment(s) in {} note(s) move to \"{}\"", segments, notes, into),
+            format!("  \"{}\" is deleted from the codebook", from),
+        ],
+        Kind::Recode => vec![
+            format!("Recode \"{}\" as \"{}\" in the listed notes:", from, into),
+            String::new(),
+            format!("  {} segment(s) in {} note(s) move to \"{}\"", segments, notes, into),
+            format!("  \"{}\" stays in the codebook and keeps its segments in other notes", from),
+        ],
+    };
+    if kind == Kind::Merge && children > 0 {
+        lines.push(format!("  its {} child code(s) move under \"{}\"", children, into));
+    }
+    lines.push(String::new());
+    lines.push("Enter: go ahead   Esc: cancel".to_string());
+    lines
+}
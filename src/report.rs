@@ -0,0 +1,429 @@
+// Reports - retrievals of coded segments as CSV, styled HTML or Word (.docx)
+//
+// A retrieval is every segment of the chosen codes (codes below them included) in the
+// chosen cases, grouped by code in codebook order and headed by the code's place in the
+// tree (so two codes of the same name stay apart): the coded text, the note and lines it
+// comes from, its memo and the code's color. CSV is for spreadsheets and R, HTML and DOCX for
+// dropping quotes into a paper. Machine suggestions that nobody has accepted yet are left
+// out. The .docx is written by hand - a stored (uncompressed) zip with the three parts
+// Word needs - so it takes no extra dependencies.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::anchors;
+use crate::codebook::{csv_field, escape_xml, hex};
+use crate::export::escape_html;
+use crate::note_store::Note;
+use crate::qda_codes::{CodeApplication, CodeColor, CodeManager};
+use crate::query::coded_text;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Csv,
+    Html,
+    Docx,
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Csv, Format::Html, Format::Docx];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Html => "html",
+            Format::Docx => "docx",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Csv => "CSV",
+            Format::Html => "HTML",
+            Format::Docx => "Word (.docx)",
+        }
+    }
+}
+
+/// One retrieved segment
+#[derive(Debug, Clone)]
+pub struct Row {
+    pub code_id: String,
+    pub code: String,
+    pub path: String,  // "Parent > Code"
+    pub color: CodeColor,
+    pub note_id: String,
+    pub note_title: String,
+    pub start: usize,
+    pub end: usize,
+    pub lines: (usize, usize),  // First and last line, to cite it by
+    pub text: String,
+    pub memo: Option<String>,
+    pub coder: Option<String>,
+}
+
+/// What a report covers, for its heading
+pub struct Scope {
+    pub codes: usize,
+    pub cases: usize,
+}
+
+pub fn report_path(notes_dir: &Path, format: Format) -> PathBuf {
+    notes_dir.join("exports").join(format!("report.{}", format.extension()))
+}
+
+/// Segments of `code_ids` and their subcodes in `notes`, by code (tree order), then note
+/// title, then position. A segment stored both on the note and in the applications table
+/// is listed once.
+pub fn retrieve(
+    notes: &[&Note],
+    codes: &CodeManager,
+    code_ids: &[String],
+    applications: &HashMap<String, Vec<CodeApplication>>,
+) -> Vec<Row> {
+    let order: HashMap<&str, usize> = codes.tree().iter().enumerate()
+        .filter(|(_, (code, _))| code_ids.iter().any(|chosen| codes.is_within(&code.id, chosen)))
+        .map(|(i, (code, _))| (code.id.as_str(), i))
+        .collect();
+
+    let mut rows = Vec::new();
+    for note in notes {
+        let mut seen = HashSet::new();
+        let segments = note.codes.iter()
+            .map(|s| (s.code_id.as_str(), s.start_offset, s.end_offset, s.memo.as_deref(), None, None))
+            .chain(applications.get(&note.id).into_iter().flatten()
+                .filter(|a| !a.suggested)
+                .map(|a| (a.code_id.as_str(), a.start_offset, a.end_offset, a.memo.as_deref(), a.coder.as_deref(), a.lines)));
+        for (code_id, start, end, memo, coder, lines) in segments {
+            if end <= start || !order.contains_key(code_id) || !seen.insert((code_id, start, end)) {
+                continue;
+            }
+            rows.push((order[code_id], Row {
+                code_id: code_id.to_string(),
+                code: codes.get_code(code_id).map_or_else(|| code_id.to_string(), |c| c.name.clone()),
+                path: codes.path(code_id),
+                color: codes.color_of(code_id),
+                note_id: note.id.clone(),
+                note_title: note.title.clone(),
+                start,
+                end,
+                lines: lines.unwrap_or_else(|| anchors::line_range(&note.content, start, end)),
+                text: coded_text(&note.content, start, end),
+                memo: memo.filter(|m| !m.trim().is_empty()).map(str::to_string),
+                coder: coder.map(str::to_string),
+            }));
+        }
+    }
+    rows.sort_by(|(a, ra), (b, rb)| {
+        a.cmp(b).then_with(|| ra.note_title.cmp(&rb.note_title)).then(ra.start.cmp(&rb.start))
+    });
+    rows.into_iter().map(|(_, row)| row).collect()
+}
+
+/// The report file's contents
+pub fn render(rows: &[Row], format: Format, scope: &Scope) -> Vec<u8> {
+    match format {
+        Format::Csv => to_csv(rows).into_bytes(),
+        Format::Html => to_html(rows, scope).into_bytes(),
+        Format::Docx => to_docx(rows, scope),
+    }
+}
+
+fn heading(scope: &Scope) -> String {
+    format!(
+        "{} code(s) in {} case(s), {}",
+        scope.codes,
+        scope.cases,
+        chrono::Local::now().format("%Y-%m-%d %H:%M")
+    )
+}
+
+/// Rows grouped by code, keeping their order
+fn by_code(rows: &[Row]) -> Vec<&[Row]> {
+    rows.chunk_by(|a, b| a.code_id == b.code_id).collect()
+}
+
+fn to_csv(rows: &[Row]) -> String {
+    let mut out = String::from("code,code_path,color,note_id,note_title,start,end,first_line,last_line,text,memo,coder\n");
+    for row in rows {
+        let fields = [
+            csv_field(&row.code),
+            csv_field(&row.path),
+            hex(&row.color),
+            csv_field(&row.note_id),
+            csv_field(&row.note_title),
+            row.start.to_string(),
+            row.end.to_string(),
+            row.lines.0.to_string(),
+            row.lines.1.to_string(),
+            csv_field(&row.text),
+            csv_field(row.memo.as_deref().unwrap_or("")),
+            csv_field(row.coder.as_deref().unwrap_or("")),
+        ];
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+const REPORT_CSS: &str = "\
+body { font-family: Georgia, serif; max-width: 46em; margin: 2em auto; line-height: 1.5; color: #222; }
+h1 { font-size: 1.6em; margin-bottom: 0.2em; }
+.meta { color: #777; font-size: 0.9em; margin-bottom: 2em; }
+h2 { font-size: 1.2em; margin-top: 2em; padding-bottom: 0.2em; border-bottom: 3px solid; }
+h2 .count { color: #777; font-weight: normal; font-size: 0.8em; }
+blockquote { margin: 1em 0; padding: 0.2em 1em; border-left: 4px solid; white-space: pre-wrap; }
+.source { color: #555; font-size: 0.85em; margin-top: -0.6em; }
+.memo { font-style: italic; color: #555; font-size: 0.9em; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+td { padding: 0.1em 1em 0.1em 0; }
+";
+
+fn to_html(rows: &[Row], scope: &Scope) -> String {
+    let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Coded segments</title>\n");
+    html.push_str(&format!("<style>\n{}</style>\n</head>\n<body>\n", REPORT_CSS));
+    html.push_str("<h1>Coded segments</h1>\n");
+    html.push_str(&format!("<div class=\"meta\">{}</div>\n", escape_html(&heading(scope))));
+
+    let groups = by_code(rows);
+    html.push_str("<table>\n");
+    for group in &groups {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{} segment(s)</td></tr>\n",
+            escape_html(&group[0].path),
+            group.len()
+        ));
+    }
+    html.push_str("</table>\n");
+
+    for group in groups {
+        let color = &group[0].color;
+        html.push_str(&format!(
+            "<h2 style=\"border-color: {}\">{} <span class=\"count\">({})</span></h2>\n",
+            hex(color),
+            escape_html(&group[0].path),
+            group.len()
+        ));
+        for row in group {
+            html.push_str(&format!(
+                "<blockquote style=\"border-color: {}; background: rgba({}, {}, {}, 0.12)\">{}</blockquote>\n",
+                hex(color), color.r, color.g, color.b, escape_html(row.text.trim())
+            ));
+            let coder = row.coder.as_deref().map(|c| format!(", coded by {}", escape_html(c))).unwrap_or_default();
+            html.push_str(&format!(
+                "<div class=\"source\">{}, {}{}</div>\n",
+                escape_html(&row.note_title), anchors::label(row.lines), coder
+            ));
+            if let Some(memo) = &row.memo {
+                html.push_str(&format!("<p class=\"memo\">Memo: {}</p>\n", escape_html(memo)));
+            }
+        }
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+// Word
+
+/// XML 1.0 has no room for most control characters (pdftotext leaves form feeds behind)
+fn xml_text(text: &str) -> String {
+    let clean: String = text.chars().filter(|c| !c.is_control() || matches!(c, '\t' | '\n')).collect();
+    escape_xml(&clean)
+}
+
+/// A paragraph of runs; newlines in the text become line breaks
+fn docx_paragraph(text: &str, paragraph_props: &str, run_props: &str) -> String {
+    let runs: Vec<String> = text.split('\n')
+        .map(|line| format!("<w:r><w:rPr>{}</w:rPr><w:t xml:space=\"preserve\">{}</w:t></w:r>", run_props, xml_text(line)))
+        .collect();
+    format!("<w:p><w:pPr>{}</w:pPr>{}</w:p>", paragraph_props, runs.join("<w:r><w:br/></w:r>"))
+}
+
+fn document_xml(rows: &[Row], scope: &Scope) -> String {
+    let mut body = String::new();
+    body.push_str(&docx_paragraph("Coded segments", "<w:spacing w:after=\"60\"/>", "<w:b/><w:sz w:val=\"36\"/>"));
+    body.push_str(&docx_paragraph(&heading(scope), "<w:spacing w:after=\"240\"/>", "<w:color w:val=\"777777\"/><w:sz w:val=\"20\"/>"));
+
+    for group in by_code(rows) {
+        let color = hex(&group[0].color).trim_start_matches('#').to_string();
+        body.push_str(&docx_paragraph(
+            &format!("{} ({})", group[0].path, group.len()),
+            &format!("<w:keepNext/><w:spacing w:before=\"360\" w:after=\"120\"/><w:pBdr><w:bottom w:val=\"single\" w:sz=\"18\" w:space=\"2\" w:color=\"{}\"/></w:pBdr>", color),
+            "<w:b/><w:sz w:val=\"28\"/>",
+        ));
+        for row in group {
+            body.push_str(&docx_paragraph(
+                row.text.trim(),
+                &format!("<w:keepNext/><w:ind w:left=\"360\"/><w:spacing w:before=\"120\" w:after=\"40\"/><w:pBdr><w:left w:val=\"single\" w:sz=\"24\" w:space=\"8\" w:color=\"{}\"/></w:pBdr>", color),
+                "",
+            ));
+            let coder = row.coder.as_deref().map(|c| format!(", coded by {}", c)).unwrap_or_default();
+            body.push_str(&docx_paragraph(
+                &format!("{}, {}{}", row.note_title, anchors::label(row.lines), coder),
+                "<w:ind w:left=\"360\"/><w:spacing w:after=\"60\"/>",
+                "<w:color w:val=\"555555\"/><w:sz w:val=\"18\"/>",
+            ));
+            if let Some(memo) = &row.memo {
+                body.push_str(&docx_paragraph(
+                    &format!("Memo: {}", memo),
+                    "<w:ind w:left=\"360\"/><w:spacing w:after=\"120\"/>",
+                    "<w:i/><w:color w:val=\"555555\"/><w:sz w:val=\"20\"/>",
+                ));
+            }
+        }
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\"><w:body>{}<w:sectPr/></w:body></w:document>",
+        body
+    )
+}
+
+const CONTENT_TYPES: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+<Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+<Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+<Override PartName=\"/word/document.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml\"/>\
+</Types>";
+
+const PACKAGE_RELS: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+<Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument\" Target=\"word/document.xml\"/>\
+</Relationships>";
+
+fn to_docx(rows: &[Row], scope: &Scope) -> Vec<u8> {
+    let document = document_xml(rows, scope);
+    zip_stored(&[
+        ("[Content_Types].xml", CONTENT_TYPES.as_bytes()),
+        ("_rels/.rels", PACKAGE_RELS.as_bytes()),
+        ("word/document.xml", document.as_bytes()),
+    ])
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// A zip archive with every file stored as is (method 0), dated 1980-01-01
+fn zip_stored(files: &[(&str, &[u8])]) -> Vec<u8> {
+    const DOS_DATE: u16 = (1 << 5) | 1;
+    let mut out = Vec::new();
+    let mut central = Vec::new();
+    for (name, data) in files {
+        let offset = out.len() as u32;
+        let crc = crc32(data);
+        let size = data.len() as u32;
+
+        out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        for field in [20u16, 0, 0, 0, DOS_DATE] {
+            out.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [crc, size, size] {
+            out.extend_from_slice(&field.to_le_bytes());
+        }
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(data);
+
+        central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        for field in [20u16, 20, 0, 0, 0, DOS_DATE] {
+            central.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [crc, size, size] {
+            central.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [name.len() as u16, 0, 0, 0, 0] {
+            central.extend_from_slice(&field.to_le_bytes());
+        }
+        central.extend_from_slice(&0u32.to_le_bytes());  // External attributes
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+    }
+
+    let central_offset = out.len() as u32;
+    let central_size = central.len() as u32;
+    out.extend_from_slice(&central);
+    out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    for field in [0u16, 0, files.len() as u16, files.len() as u16] {
+        out.extend_from_slice(&field.to_le_bytes());
+    }
+    out.extend_from_slice(&central_size.to_le_bytes());
+    out.extend_from_slice(&central_offset.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out
+}
+
+/// Write the report file, creating the directory it goes in
+pub fn write(path: &Path, rows: &[Row], format: Format, scope: &Scope) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, render(rows, format, scope))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u16_at(bytes: &[u8], at: usize) -> u16 {
+        u16::from_le_bytes(bytes[at..at + 2].try_into().unwrap())
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn zip_stored_headers_match_the_data() {
+        let files: [(&str, &[u8]); 2] = [("a.txt", b"hello"), ("dir/b.xml", b"<x>two</x>")];
+        let zip = zip_stored(&files);
+
+        // End of central directory: the last 22 bytes, as there is no comment
+        let eocd = zip.len() - 22;
+        assert_eq!(u32_at(&zip, eocd), 0x0605_4b50);
+        assert_eq!(u16_at(&zip, eocd + 8), 2);   // Entries on this disk
+        assert_eq!(u16_at(&zip, eocd + 10), 2);  // Entries in total
+        let central_size = u32_at(&zip, eocd + 12) as usize;
+        let central_offset = u32_at(&zip, eocd + 16) as usize;
+        assert_eq!(central_offset + central_size, eocd);
+
+        let mut local = 0;
+        let mut entry = central_offset;
+        for (name, data) in files {
+            assert_eq!(u32_at(&zip, local), 0x0403_4b50);
+            assert_eq!(u32_at(&zip, local + 14), crc32(data));
+            assert_eq!(u32_at(&zip, local + 18) as usize, data.len());
+            assert_eq!(u32_at(&zip, local + 22) as usize, data.len());
+            let name_len = u16_at(&zip, local + 26) as usize;
+            let start = local + 30 + name_len;
+            assert_eq!(&zip[local + 30..start], name.as_bytes());
+            assert_eq!(&zip[start..start + data.len()], data);
+
+            assert_eq!(u32_at(&zip, entry), 0x0201_4b50);
+            assert_eq!(u32_at(&zip, entry + 16), crc32(data));
+            assert_eq!(u32_at(&zip, entry + 42) as usize, local);
+            entry += 46 + u16_at(&zip, entry + 28) as usize;
+            local = start + data.len();
+        }
+        assert_eq!(local, central_offset);
+        assert_eq!(entry, eocd);
+    }
+}
@@ -0,0 +1,101 @@
+// Search history - past queries, recalled in the search bar
+//
+// A query is remembered when Enter, Esc or Down leave the search bar with it. Up in the
+// search bar steps back through earlier queries and Down forward again, back to what
+// was being typed; Down with no step taken still moves on to the list. Ctrl+R there
+// lists the history, narrowed by a fuzzy match as you type, and Enter runs the one
+// picked. The history is kept with the session.
+
+use nucleo::{Matcher, Utf32Str, pattern::{Pattern, CaseMatching, Normalization}};
+
+/// Queries kept
+const MAX_QUERIES: usize = 100;
+
+#[derive(Debug, Default)]
+pub struct SearchHistory {
+    queries: Vec<String>,   // Most recent first
+    step: Option<usize>,    // The query Up / Down have brought up, while stepping
+    draft: String,          // What was typed before the first step
+}
+
+impl SearchHistory {
+    /// Start from the last session's queries
+    pub fn with_queries(queries: Vec<String>) -> Self {
+        SearchHistory { queries, ..SearchHistory::default() }
+    }
+
+    pub fn queries(&self) -> &[String] {
+        &self.queries
+    }
+
+    pub fn is_stepping(&self) -> bool {
+        self.step.is_some()
+    }
+
+    /// Remember `query` as the latest (once, however often it was run)
+    pub fn record(&mut self, query: &str) {
+        self.step = None;
+        let query = query.trim();
+        if query.is_empty() {
+            return;
+        }
+        self.queries.retain(|q| q != query);
+        self.queries.insert(0, query.to_string());
+        self.queries.truncate(MAX_QUERIES);
+    }
+
+    /// The query before the one shown; the first step keeps `typed` for coming back to
+    pub fn older(&mut self, typed: &str) -> Option<&str> {
+        let next = match self.step {
+            None => 0,
+            Some(i) => i + 1,
+        };
+        if next >= self.queries.len() {
+            return None;
+        }
+        if self.step.is_none() {
+            self.draft = typed.to_string();
+        }
+        self.step = Some(next);
+        Some(&self.queries[next])
+    }
+
+    /// The query after the one shown, or what was typed once past the newest; None when
+    /// not stepping
+    pub fn newer(&mut self) -> Option<String> {
+        match self.step? {
+            0 => {
+                self.step = None;
+                Some(std::mem::take(&mut self.draft))
+            }
+            i => {
+                self.step = Some(i - 1);
+                Some(self.queries[i - 1].clone())
+            }
+        }
+    }
+
+    /// Typing ends the stepping; the query shown becomes the one being edited
+    pub fn stop(&mut self) {
+        self.step = None;
+    }
+
+    /// The queries fuzzily matching `filter`, best first (most recent first without one)
+    pub fn matching(&self, filter: &str) -> Vec<&str> {
+        if filter.trim().is_empty() {
+            return self.queries.iter().map(String::as_str).collect();
+        }
+        let pattern = Pattern::parse(filter, CaseMatching::Ignore, Normalization::Smart);
+        let mut matcher = Matcher::default();
+        let mut buf = Vec::new();
+        let mut rows: Vec<(u32, usize, &str)> = self.queries.iter().enumerate()
+            .filter_map(|(order, query)| {
+                buf.clear();
+                let score = pattern.score(Utf32Str::new(query, &mut buf), &mut matcher)?;
+                Some((score, order, query.as_str()))
+            })
+            .collect();
+        rows.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        rows.into_iter().map(|(_, _, query)| query).collect()
+    }
+}
@@ -0,0 +1,639 @@
+// Full-text search index - analyzed terms of every note, kept in search.db
+//
+// The index sits next to notes.db but holds nothing that can't be rebuilt from
+// it: deleting search.db only costs a reindex. Each row is one term occurrence
+// (note, field, position), so word matches can be scored by how often and where
+// a term appears. Each note's title and text are fingerprinted when indexed, and a
+// note is only indexed again once they change - a save that changed nothing, or a
+// restart, costs no reindexing. Changing the analyzer (config.json "search") empties
+// the index so it is rebuilt with the new terms. A corrupt search.db is replaced on
+// open; `snyfter3 reindex` (or Ctrl+G f) rebuilds it by hand. At startup the app
+// doesn't wait for the index: an IndexJob brings it up to date a batch at a time
+// between events, and searches made meanwhile rank fuzzy matches (and whatever is
+// indexed already). Query terms also match indexed terms a typo or two away (one edit
+// from 4 letters on, two from 8), each edit halving the score, so "intervew" still
+// finds "interview" - though below the notes that spell it right. The distinct terms
+// are read once, on the first such lookup, then kept up to date as notes are written and
+// grouped by length, so a lookup only measures the terms short or long enough to be
+// within reach (a deleted note's last use of a term lingers, matching nothing, until the
+// next start). Positions make
+// phrases and NEAR (see query.rs) a check on where the terms stand in the same field.
+
+use anyhow::{bail, Result};
+use rusqlite::{params, Connection};
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::analyzer::Analyzer;
+use crate::db;
+use crate::note_store::Note;
+use crate::query::Proximity;
+
+/// Which part of a note a term came from
+const TITLE: i64 = 0;
+const CONTENT: i64 = 1;
+
+/// What `indexed` records; a search.db from before a change is emptied and rebuilt.
+/// 2: a fingerprint of each note's text instead of its updated_at
+const FORMAT: &str = "2";
+
+pub struct SearchIndex {
+    conn: Connection,
+    analyzer: Analyzer,
+    indexed: HashMap<String, String>,  // note id -> fingerprint of the text it was indexed with
+    vocabulary: RefCell<Option<Vocabulary>>,  // Loaded on the first fuzzy lookup
+}
+
+/// Every distinct indexed term, by length in characters
+#[derive(Debug, Default)]
+struct Vocabulary {
+    by_length: HashMap<usize, HashSet<String>>,
+}
+
+impl Vocabulary {
+    fn insert(&mut self, term: &str) {
+        let terms = self.by_length.entry(term.chars().count()).or_default();
+        if !terms.contains(term) {
+            terms.insert(term.to_string());
+        }
+    }
+
+    /// The terms no more than `edits` characters shorter or longer than `length`
+    fn within(&self, length: usize, edits: usize) -> impl Iterator<Item = &String> {
+        (length.saturating_sub(edits)..=length + edits)
+            .filter_map(|length| self.by_length.get(&length))
+            .flatten()
+    }
+}
+
+/// A fuzzy match scores this much of an exact one, per edit
+const FUZZY_FACTOR: f64 = 0.5;
+
+/// Notes written per transaction - when rebuilding, syncing or adding many at once - and
+/// between progress reports. Each commit is a disk flush, so one per note would make an
+/// import crawl, and one for thousands would hold a single huge transaction open
+pub const REINDEX_BATCH: usize = 200;
+
+/// Notes an IndexJob writes per step: small enough that keys still feel immediate
+const JOB_BATCH: usize = 25;
+
+/// Bringing the index up to date in steps, so the UI can draw and take keys in between
+pub struct IndexJob {
+    notes: Vec<Note>,  // Still to index, as they were when the job started
+    gone: Vec<String>,
+    done: usize,
+    total: usize,
+}
+
+impl IndexJob {
+    pub fn progress(&self) -> (usize, usize) {
+        (self.done, self.total)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.notes.is_empty() && self.gone.is_empty()
+    }
+}
+
+impl SearchIndex {
+    /// Open the index; an unreadable or corrupt search.db is deleted and started over empty,
+    /// and the next sync fills it again from the note store
+    pub fn open(notes_dir: &Path, analyzer: Analyzer) -> Result<Self> {
+        let path = notes_dir.join("search.db");
+        match Self::open_at(&path, analyzer) {
+            Ok(index) => Ok(index),
+            Err(e) => {
+                tracing::warn!(error = %e, path = %path.display(), "search index unusable, recreating it");
+                for suffix in ["", "-journal", "-wal", "-shm"] {
+                    let file = PathBuf::from(format!("{}{}", path.display(), suffix));
+                    if file.exists() {
+                        std::fs::remove_file(&file)?;
+                    }
+                }
+                Self::open_at(&path, analyzer)
+            }
+        }
+    }
+
+    fn open_at(path: &Path, analyzer: Analyzer) -> Result<Self> {
+        let conn = db::open(path)?;
+        let check: String = conn.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+        if check != "ok" {
+            bail!("integrity check failed: {}", check);
+        }
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS postings (
+                term TEXT NOT NULL,
+                note_id TEXT NOT NULL,
+                field INTEGER NOT NULL,
+                position INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS postings_term ON postings (term);
+            CREATE INDEX IF NOT EXISTS postings_note ON postings (note_id);
+            CREATE TABLE IF NOT EXISTS meta (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );"
+        )?;
+        let format: Option<String> = conn
+            .query_row("SELECT value FROM meta WHERE key = 'format'", [], |row| row.get(0))
+            .ok();
+        if format.as_deref() != Some(FORMAT) {
+            tracing::info!(old = ?format, new = FORMAT, "search index format changed, rebuilding it");
+            conn.execute_batch("DROP TABLE IF EXISTS indexed; DELETE FROM postings;")?;
+            conn.execute("INSERT OR REPLACE INTO meta (key, value) VALUES ('format', ?1)", params![FORMAT])?;
+        }
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS indexed (
+                note_id TEXT PRIMARY KEY,
+                fingerprint TEXT NOT NULL
+            );"
+        )?;
+
+        let mut index = SearchIndex { conn, analyzer, indexed: HashMap::new(), vocabulary: RefCell::new(None) };
+        let stored: Option<String> = index.conn
+            .query_row("SELECT value FROM meta WHERE key = 'analyzer'", [], |row| row.get(0))
+            .ok();
+        if stored.as_deref() != Some(analyzer.signature().as_str()) {
+            tracing::info!(old = ?stored, new = %analyzer.signature(), "analyzer changed, search index cleared");
+            index.clear()?;
+        } else {
+            let mut stmt = index.conn.prepare("SELECT note_id, fingerprint FROM indexed")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            index.indexed = rows.collect::<rusqlite::Result<_>>()?;
+        }
+        Ok(index)
+    }
+
+    pub fn note_count(&self) -> usize {
+        self.indexed.len()
+    }
+
+    /// Throw the whole index away and index `notes` from scratch, reporting (done, total)
+    /// after each batch
+    pub fn reindex_all(&mut self, notes: &[Note], mut progress: impl FnMut(usize, usize)) -> Result<()> {
+        self.clear()?;
+        let mut done = 0;
+        for batch in notes.chunks(REINDEX_BATCH) {
+            self.index_batch(batch)?;
+            done += batch.len();
+            progress(done, notes.len());
+        }
+        tracing::info!(notes = notes.len(), "search index rebuilt");
+        Ok(())
+    }
+
+    /// Empty the index (before indexing everything again)
+    pub fn clear(&mut self) -> Result<()> {
+        self.conn.execute_batch("DELETE FROM postings; DELETE FROM indexed;")?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES ('analyzer', ?1)",
+            params![self.analyzer.signature()],
+        )?;
+        self.indexed.clear();
+        *self.vocabulary.get_mut() = Some(Vocabulary::default());
+        Ok(())
+    }
+
+    /// Whether `note` is new or changed since it was indexed
+    fn is_stale(&self, note: &Note) -> bool {
+        self.indexed.get(&note.id) != Some(&fingerprint(note))
+    }
+
+    /// Notes new or changed since they were indexed, and the ids of indexed notes that are gone
+    fn stale<'a>(&self, notes: &'a [Note]) -> (Vec<&'a Note>, Vec<String>) {
+        let stale: Vec<&Note> = notes.iter()
+            .filter(|n| self.is_stale(n))
+            .collect();
+        let live: HashSet<&str> = notes.iter().map(|n| n.id.as_str()).collect();
+        let gone: Vec<String> = self.indexed.keys()
+            .filter(|id| !live.contains(id.as_str()))
+            .cloned()
+            .collect();
+        (stale, gone)
+    }
+
+    /// Index new and changed notes and drop deleted ones; returns how many were (re)indexed
+    pub fn sync(&mut self, notes: &[Note]) -> Result<usize> {
+        let (stale, gone) = self.stale(notes);
+        self.sync_batch(&[], &gone)?;
+        for batch in stale.chunks(REINDEX_BATCH) {
+            self.sync_batch(batch, &[])?;
+        }
+        tracing::debug!(indexed = stale.len(), removed = gone.len(), "search index sync");
+        Ok(stale.len())
+    }
+
+    /// Index many new or changed notes (say, just imported), committing every
+    /// REINDEX_BATCH; notes already indexed as they are are skipped. Returns how many were
+    /// written
+    pub fn add_notes(&mut self, notes: &[Note]) -> Result<usize> {
+        let stale: Vec<&Note> = notes.iter().filter(|n| self.is_stale(n)).collect();
+        for batch in stale.chunks(REINDEX_BATCH) {
+            self.sync_batch(batch, &[])?;
+        }
+        tracing::debug!(indexed = stale.len(), given = notes.len(), "notes added to search index");
+        Ok(stale.len())
+    }
+
+    /// What `sync` would do, as a job to run in steps; None when the index is up to date
+    pub fn start_sync(&self, notes: &[Note]) -> Option<IndexJob> {
+        let (stale, gone) = self.stale(notes);
+        if stale.is_empty() && gone.is_empty() {
+            return None;
+        }
+        let total = stale.len();
+        // Taken from the end, so reversed to index the newest notes first
+        let notes = stale.into_iter().rev().cloned().collect();
+        Some(IndexJob { notes, gone, done: 0, total })
+    }
+
+    /// Write the next batch of a job (the deletions go with the first)
+    pub fn step(&mut self, job: &mut IndexJob) -> Result<()> {
+        let batch = job.notes.split_off(job.notes.len().saturating_sub(JOB_BATCH));
+        let gone = std::mem::take(&mut job.gone);
+        self.sync_batch(&batch.iter().rev().collect::<Vec<_>>(), &gone)?;
+        job.done += batch.len();
+        if job.is_finished() {
+            tracing::info!(indexed = job.total, "search index caught up");
+        }
+        Ok(())
+    }
+
+    /// (Re)index `notes` in one transaction
+    pub fn index_batch(&mut self, notes: &[Note]) -> Result<()> {
+        self.sync_batch(&notes.iter().collect::<Vec<_>>(), &[])
+    }
+
+    /// Write `notes` and drop `gone` in one transaction
+    fn sync_batch(&mut self, notes: &[&Note], gone: &[String]) -> Result<()> {
+        if notes.is_empty() && gone.is_empty() {
+            return Ok(());
+        }
+        let tx = self.conn.transaction()?;
+        for id in gone {
+            tx.prepare_cached("DELETE FROM postings WHERE note_id = ?1")?.execute(params![id])?;
+            tx.prepare_cached("DELETE FROM indexed WHERE note_id = ?1")?.execute(params![id])?;
+        }
+        let mut terms = HashSet::new();
+        for note in notes {
+            terms.extend(write_note(&tx, &self.analyzer, note)?);
+        }
+        tx.commit()?;
+        if let Some(vocabulary) = self.vocabulary.get_mut() {
+            for term in &terms {
+                vocabulary.insert(term);
+            }
+        }
+
+        for id in gone {
+            self.indexed.remove(id);
+        }
+        for note in notes {
+            self.indexed.insert(note.id.clone(), fingerprint(note));
+        }
+        Ok(())
+    }
+
+    /// Notes containing every term of the query (or a term a typo away), with a tf-idf score
+    /// (higher is better); a title match counts as much as `title_weight` matches in the text
+    pub fn search(&self, text: &str, title_weight: f64) -> Result<HashMap<String, f64>> {
+        let mut terms = self.analyzer.terms(text);
+        terms.sort();
+        terms.dedup();
+        if terms.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let total = self.indexed.len().max(1) as f64;
+        let mut stmt = self.conn.prepare(
+            "SELECT note_id, field, COUNT(*) FROM postings WHERE term = ?1 GROUP BY note_id, field"
+        )?;
+        let mut scores: Option<HashMap<String, f64>> = None;
+        for term in &terms {
+            // A note scores by the best of the spellings it has
+            let mut best: HashMap<String, f64> = HashMap::new();
+            for (variant, edits) in self.variants(term)? {
+                let mut weights: HashMap<String, f64> = HashMap::new();
+                let rows = stmt.query_map(params![variant], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?))
+                })?;
+                for row in rows {
+                    let (note_id, field, count) = row?;
+                    let weight = if field == TITLE { title_weight } else { 1.0 };
+                    *weights.entry(note_id).or_default() += weight * (1.0 + (count as f64).ln());
+                }
+                let idf = (1.0 + total / weights.len().max(1) as f64).ln();
+                let factor = FUZZY_FACTOR.powi(edits as i32);
+                for (id, w) in weights {
+                    let score = best.entry(id).or_default();
+                    *score = score.max(w * idf * factor);
+                }
+            }
+
+            // Every term has to match: keep only notes that matched all terms so far
+            scores = Some(match scores {
+                None => best,
+                Some(previous) => previous.into_iter()
+                    .filter_map(|(id, score)| {
+                        let extra = *best.get(&id)?;
+                        Some((id, score + extra))
+                    })
+                    .collect(),
+            });
+        }
+        Ok(scores.unwrap_or_default())
+    }
+
+    /// Keep the notes where every proximity condition holds: looked up in the index, or
+    /// worked out from the text for notes it doesn't hold yet
+    pub fn filter_proximity<'a>(&self, mut notes: Vec<&'a Note>, proximity: &[Proximity]) -> Result<Vec<&'a Note>> {
+        for condition in proximity {
+            let Some(pattern) = Pattern::compile(&self.analyzer, condition) else {
+                continue;
+            };
+            let matching = self.matching(&pattern)?;
+            notes.retain(|note| self.holds(note, &pattern, &matching));
+        }
+        Ok(notes)
+    }
+
+    /// Drop the notes using any of `excluded` - words or phrases, matched as search words
+    /// are, in any inflection
+    pub fn filter_excluded<'a>(&self, mut notes: Vec<&'a Note>, excluded: &[String]) -> Result<Vec<&'a Note>> {
+        for text in excluded {
+            let terms = self.analyzer.terms(text);
+            if terms.is_empty() {
+                continue;
+            }
+            let pattern = Pattern::Phrase(terms);
+            let matching = self.matching(&pattern)?;
+            notes.retain(|note| !self.holds(note, &pattern, &matching));
+        }
+        Ok(notes)
+    }
+
+    /// Whether `pattern` holds in `note`: by `matching` (from the index) for indexed notes,
+    /// worked out from the text for the rest
+    fn holds(&self, note: &Note, pattern: &Pattern, matching: &HashSet<String>) -> bool {
+        if self.indexed.contains_key(&note.id) {
+            matching.contains(&note.id)
+        } else {
+            [&note.title, &note.content].iter().any(|text| pattern.holds(&positions(&self.analyzer, text)))
+        }
+    }
+
+    /// Indexed notes with a field where `pattern` holds
+    fn matching(&self, pattern: &Pattern) -> Result<HashSet<String>> {
+        let mut stmt = self.conn.prepare_cached("SELECT note_id, field, position FROM postings WHERE term = ?1")?;
+        let mut fields: HashMap<(String, i64), HashMap<String, Vec<usize>>> = HashMap::new();
+        for term in pattern.terms() {
+            let rows = stmt.query_map(params![term], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?))
+            })?;
+            for row in rows {
+                let (note_id, field, position) = row?;
+                fields.entry((note_id, field)).or_default()
+                    .entry(term.to_string()).or_default()
+                    .push(position as usize);
+            }
+        }
+        Ok(fields.into_iter()
+            .filter(|(_, positions)| pattern.holds(positions))
+            .map(|((note_id, _), _)| note_id)
+            .collect())
+    }
+
+    /// `term` itself, then the indexed terms within the edits its length allows, with how
+    /// many edits away each is
+    fn variants(&self, term: &str) -> Result<Vec<(String, usize)>> {
+        let mut variants = vec![(term.to_string(), 0)];
+        let max_edits = match term.chars().count() {
+            0..=3 => return Ok(variants),
+            4..=7 => 1,
+            _ => 2,
+        };
+        let mut vocabulary = self.vocabulary.borrow_mut();
+        if vocabulary.is_none() {
+            let mut loaded = Vocabulary::default();
+            let mut stmt = self.conn.prepare("SELECT DISTINCT term FROM postings")?;
+            for term in stmt.query_map([], |row| row.get::<_, String>(0))? {
+                loaded.insert(&term?);
+            }
+            *vocabulary = Some(loaded);
+        }
+        let term: Vec<char> = term.chars().collect();
+        for other in vocabulary.iter().flat_map(|v| v.within(term.len(), max_edits)) {
+            let chars: Vec<char> = other.chars().collect();
+            if let Some(edits) = edit_distance(&term, &chars, max_edits).filter(|&e| e > 0) {
+                variants.push((other.clone(), edits));
+            }
+        }
+        Ok(variants)
+    }
+}
+
+/// A proximity condition in analyzed terms
+enum Pattern {
+    Phrase(Vec<String>),
+    Near(String, String, usize),
+}
+
+impl Pattern {
+    /// None when there's nothing to check, such as a "phrase" of one word
+    fn compile(analyzer: &Analyzer, proximity: &Proximity) -> Option<Pattern> {
+        match proximity {
+            Proximity::Phrase(text) => {
+                let terms = analyzer.terms(text);
+                (terms.len() > 1).then_some(Pattern::Phrase(terms))
+            }
+            // A side of several words (a quoted phrase) counts from its inner end
+            Proximity::Near(left, right, distance) => {
+                let left = analyzer.terms(left).pop()?;
+                let right = analyzer.terms(right).into_iter().next()?;
+                Some(Pattern::Near(left, right, *distance))
+            }
+        }
+    }
+
+    fn terms(&self) -> Vec<&str> {
+        let mut terms: Vec<&str> = match self {
+            Pattern::Phrase(terms) => terms.iter().map(String::as_str).collect(),
+            Pattern::Near(left, right, _) => vec![left.as_str(), right.as_str()],
+        };
+        terms.sort_unstable();
+        terms.dedup();
+        terms
+    }
+
+    /// Whether it holds in one field, given where each of its terms stands there
+    fn holds(&self, positions: &HashMap<String, Vec<usize>>) -> bool {
+        let at = |term: &str| positions.get(term).map_or(&[][..], Vec::as_slice);
+        match self {
+            Pattern::Phrase(terms) => at(&terms[0]).iter().any(|&start| {
+                terms.iter().enumerate().skip(1).all(|(i, term)| at(term).contains(&(start + i)))
+            }),
+            Pattern::Near(left, right, distance) => at(left).iter().any(|&p| {
+                at(right).iter().any(|&q| p != q && p.abs_diff(q) <= *distance)
+            }),
+        }
+    }
+}
+
+/// Where each term of `text` stands, as the index would record it
+fn positions(analyzer: &Analyzer, text: &str) -> HashMap<String, Vec<usize>> {
+    let mut positions: HashMap<String, Vec<usize>> = HashMap::new();
+    for (position, term) in analyzer.terms(text).into_iter().enumerate() {
+        positions.entry(term).or_default().push(position);
+    }
+    positions
+}
+
+/// Edits (insertions, deletions, substitutions, swaps of neighbours) turning `a` into `b`,
+/// or None when that takes more than `max`
+fn edit_distance(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    // Three rows of the usual table: two back (for swaps), the last and the current
+    let mut before: Vec<usize> = Vec::new();
+    let mut last: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (last[j] + 1).min(row[j - 1] + 1).min(last[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(before[j - 2] + 1);
+            }
+        }
+        if row.iter().all(|&d| d > max) {
+            return None;
+        }
+        before = std::mem::replace(&mut last, row);
+    }
+    Some(last[b.len()]).filter(|&d| d <= max)
+}
+
+/// Hash of what gets indexed from a note: its title and text
+fn fingerprint(note: &Note) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(note.title.as_bytes());
+    hasher.update([0]);
+    hasher.update(note.content.as_bytes());
+    format!("{:x}", hasher.finalize())[..16].to_string()
+}
+
+/// Statements are prepared once per connection and reused for every note after. Returns
+/// the terms written
+fn write_note(conn: &Connection, analyzer: &Analyzer, note: &Note) -> Result<Vec<String>> {
+    conn.prepare_cached("DELETE FROM postings WHERE note_id = ?1")?.execute(params![note.id])?;
+    let mut insert = conn.prepare_cached(
+        "INSERT INTO postings (term, note_id, field, position) VALUES (?1, ?2, ?3, ?4)"
+    )?;
+    let mut written = Vec::new();
+    for (field, text) in [(TITLE, note.title.as_str()), (CONTENT, note.content.as_str())] {
+        let terms = analyzer.terms(text);
+        for (position, term) in terms.iter().enumerate() {
+            insert.execute(params![term, note.id, field, position as i64])?;
+        }
+        written.extend(terms);
+    }
+    conn.prepare_cached("INSERT OR REPLACE INTO indexed (note_id, fingerprint) VALUES (?1, ?2)")?
+        .execute(params![note.id, fingerprint(note)])?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distance(a: &str, b: &str, max: usize) -> Option<usize> {
+        let a: Vec<char> = a.chars().collect();
+        let b: Vec<char> = b.chars().collect();
+        edit_distance(&a, &b, max)
+    }
+
+    #[test]
+    fn edit_distance_counts_each_kind_of_edit() {
+        assert_eq!(distance("interview", "interview", 2), Some(0));
+        assert_eq!(distance("interview", "intervew", 2), Some(1));   // Deletion
+        assert_eq!(distance("coding", "codding", 2), Some(1));       // Insertion
+        assert_eq!(distance("theme", "thyme", 2), Some(1));          // Substitution
+        assert_eq!(distance("from", "form", 2), Some(1));            // Swap of neighbours
+        assert_eq!(distance("qualitative", "qualitaitve", 2), Some(1));
+        assert_eq!(distance("kitten", "sitting", 3), Some(3));
+        assert_eq!(distance("", "abc", 3), Some(3));
+    }
+
+    #[test]
+    fn edit_distance_stops_past_max() {
+        assert_eq!(distance("kitten", "sitting", 2), None);
+        assert_eq!(distance("code", "coding", 1), None);              // Lengths alone too far apart
+        assert_eq!(distance("abcdefgh", "zyxwvuts", 1), None);        // Every row past max
+        assert_eq!(distance("from", "form", 0), None);
+    }
+
+    #[test]
+    fn vocabulary_by_length() {
+        let mut vocabulary = Vocabulary::default();
+        for term in ["form", "from", "forms", "fo", "formation", "form"] {
+            vocabulary.insert(term);
+        }
+        let mut within: Vec<&str> = vocabulary.within(4, 1).map(String::as_str).collect();
+        within.sort_unstable();
+        assert_eq!(within, ["form", "forms", "from"]);
+        assert_eq!(vocabulary.within(4, 2).count(), 4);
+    }
+
+    fn at(terms: &[(&str, &[usize])]) -> HashMap<String, Vec<usize>> {
+        terms.iter().map(|(term, positions)| (term.to_string(), positions.to_vec())).collect()
+    }
+
+    fn phrase(words: &[&str]) -> Pattern {
+        Pattern::Phrase(words.iter().map(|w| w.to_string()).collect())
+    }
+
+    #[test]
+    fn phrase_needs_the_words_in_order_side_by_side() {
+        let pattern = phrase(&["action", "research"]);
+        assert!(pattern.holds(&at(&[("action", &[3]), ("research", &[4])])));
+        assert!(pattern.holds(&at(&[("action", &[0, 7]), ("research", &[2, 8])])));
+        assert!(!pattern.holds(&at(&[("action", &[4]), ("research", &[3])])));  // Reversed
+        assert!(!pattern.holds(&at(&[("action", &[3]), ("research", &[5])])));  // A word between
+        assert!(!pattern.holds(&at(&[("action", &[3])])));
+        let three = phrase(&["focus", "group", "focus"]);
+        assert!(three.holds(&at(&[("focus", &[1, 3]), ("group", &[2])])));
+        assert!(!three.holds(&at(&[("focus", &[1]), ("group", &[2])])));
+    }
+
+    #[test]
+    fn near_holds_either_way_round() {
+        let pattern = Pattern::Near("consent".into(), "recording".into(), 5);
+        assert!(pattern.holds(&at(&[("consent", &[10]), ("recording", &[15])])));
+        assert!(pattern.holds(&at(&[("consent", &[15]), ("recording", &[10])])));
+        assert!(pattern.holds(&at(&[("consent", &[10]), ("recording", &[11])])));
+        assert!(!pattern.holds(&at(&[("consent", &[10]), ("recording", &[16])])));
+        assert!(!pattern.holds(&at(&[("consent", &[16]), ("recording", &[10])])));
+        assert!(!pattern.holds(&at(&[("consent", &[10])])));
+        // A word near itself needs a second occurrence
+        let itself = Pattern::Near("consent".into(), "consent".into(), 3);
+        assert!(!itself.holds(&at(&[("consent", &[4])])));
+        assert!(itself.holds(&at(&[("consent", &[4, 6])])));
+    }
+
+    #[test]
+    fn compile_skips_one_word_phrases() {
+        let analyzer = Analyzer::default();
+        assert!(Pattern::compile(&analyzer, &Proximity::Phrase("interview".into())).is_none());
+        assert!(Pattern::compile(&analyzer, &Proximity::Phrase("the, interview!".into())).is_some());
+        assert!(Pattern::compile(&analyzer, &Proximity::Near("".into(), "consent".into(), 5)).is_none());
+        // Analyzed the way the index is
+        let Some(Pattern::Phrase(terms)) = Pattern::compile(&analyzer, &Proximity::Phrase("Interviews, recorded".into())) else {
+            panic!("a two-word phrase compiles to a phrase");
+        };
+        assert_eq!(terms, analyzer.terms("interviews recorded"));
+    }
+}
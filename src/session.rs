@@ -0,0 +1,64 @@
+// Session state (session.json in the notes directory) - where the last run left off
+//
+// Unlike config.json this is rewritten on every quit and never edited by hand;
+// a missing or unreadable file just means a fresh start.
+
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use std::path::{Path, PathBuf};
+
+use crate::FocusArea;
+use crate::layout::Preset;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub note_id: Option<String>,  // Selected note
+    pub search_query: String,
+    pub focus: FocusArea,
+    pub split_ratio: f32,
+    pub layout: Preset,
+    pub zen: bool,                // Zen mode was on (it shows again once the editor has focus)
+    pub list_scroll: usize,       // First note shown in the list
+    pub cursor: usize,            // Char position of the editor cursor in the selected note
+    pub hidden_codes: Vec<String>,  // Codes whose segments aren't tinted in the editor
+    pub recent: Vec<String>,      // Recently opened note ids, most recent first
+    pub searches: Vec<String>,    // Search history, most recent first
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            note_id: None,
+            search_query: String::new(),
+            focus: FocusArea::NoteList,
+            split_ratio: 0.2,
+            layout: Preset::default(),
+            zen: false,
+            list_scroll: 0,
+            cursor: 0,
+            hidden_codes: Vec::new(),
+            recent: Vec::new(),
+            searches: Vec::new(),
+        }
+    }
+}
+
+impl Session {
+    fn path(notes_dir: &Path) -> PathBuf {
+        notes_dir.join("session.json")
+    }
+
+    pub fn load(notes_dir: &Path) -> Self {
+        std::fs::read_to_string(Self::path(notes_dir))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, notes_dir: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(Self::path(notes_dir), json)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+// Note similarity - how alike notes are by the words they use
+//
+// Each note is a TF-IDF vector over its words, counted as for word frequencies (front
+// matter, markup and stopwords left out), and two notes are as alike as the cosine of
+// their vectors: 1 for the same words in the same proportions, 0 for none in common.
+// Words most notes use weigh little, words only a few notes share weigh most. Counts
+// are kept between uses; `sync` only counts again the notes whose text changed.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use crate::note_store::Note;
+use crate::words;
+
+#[derive(Debug, Default)]
+pub struct Similarity {
+    counts: HashMap<String, (u64, HashMap<String, usize>)>,  // Note id -> content hash, word counts
+    notes_with: HashMap<String, usize>,  // Word -> notes using it
+}
+
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl Similarity {
+    /// Bring the counts up to date with `notes`: changed notes counted again, deleted ones dropped
+    pub fn sync(&mut self, notes: &[Note], stopwords: &[String]) {
+        let live: HashSet<&str> = notes.iter().map(|n| n.id.as_str()).collect();
+        let gone: Vec<String> = self.counts.keys().filter(|id| !live.contains(id.as_str())).cloned().collect();
+        for id in gone {
+            self.forget(&id);
+        }
+        for note in notes {
+            let hash = content_hash(&note.content);
+            if self.counts.get(&note.id).is_some_and(|(h, _)| *h == hash) {
+                continue;
+            }
+            self.forget(&note.id);
+            let counts = words::counts(note, stopwords);
+            for word in counts.keys() {
+                *self.notes_with.entry(word.clone()).or_default() += 1;
+            }
+            self.counts.insert(note.id.clone(), (hash, counts));
+        }
+    }
+
+    fn forget(&mut self, note_id: &str) {
+        let Some((_, counts)) = self.counts.remove(note_id) else {
+            return;
+        };
+        for word in counts.keys() {
+            if let Some(n) = self.notes_with.get_mut(word) {
+                *n -= 1;
+                if *n == 0 {
+                    self.notes_with.remove(word);
+                }
+            }
+        }
+    }
+
+    /// Smoothed inverse document frequency, never below 1
+    fn idf(&self, word: &str) -> f64 {
+        let total = self.counts.len() as f64;
+        let with = self.notes_with.get(word).copied().unwrap_or(0) as f64;
+        ((1.0 + total) / (1.0 + with)).ln() + 1.0
+    }
+
+    /// A note's TF-IDF weights, scaled to unit length
+    fn vector(&self, note_id: &str) -> HashMap<&str, f64> {
+        let Some((_, counts)) = self.counts.get(note_id) else {
+            return HashMap::new();
+        };
+        let mut vector: HashMap<&str, f64> = counts.iter()
+            .map(|(word, &count)| (word.as_str(), (1.0 + (count as f64).ln()) * self.idf(word)))
+            .collect();
+        let length = vector.values().map(|w| w * w).sum::<f64>().sqrt();
+        if length > 0.0 {
+            vector.values_mut().for_each(|w| *w /= length);
+        }
+        vector
+    }
+
+    /// The `limit` notes most like `note_id` with their cosine, most alike first; notes
+    /// sharing no words are left out
+    pub fn similar(&self, note_id: &str, limit: usize) -> Vec<(String, f64)> {
+        let target = self.vector(note_id);
+        if target.is_empty() {
+            return Vec::new();
+        }
+        let mut scores: Vec<(String, f64)> = self.counts.keys()
+            .filter(|id| id.as_str() != note_id)
+            .filter_map(|id| {
+                let score: f64 = self.vector(id).iter()
+                    .filter_map(|(word, w)| target.get(word).map(|t| t * w))
+                    .sum();
+                (score > 0.0).then(|| (id.clone(), score))
+            })
+            .collect();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        scores.truncate(limit);
+        scores
+    }
+
+    /// The `limit` words that most set `note_id` apart, with how often it uses each
+    pub fn distinctive(&self, note_id: &str, limit: usize) -> Vec<(String, usize)> {
+        let Some((_, counts)) = self.counts.get(note_id) else {
+            return Vec::new();
+        };
+        let vector = self.vector(note_id);
+        let mut ranked: Vec<(&str, f64)> = vector.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        ranked.into_iter()
+            .take(limit)
+            .map(|(word, _)| (word.to_string(), counts[word]))
+            .collect()
+    }
+}
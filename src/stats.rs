@@ -0,0 +1,72 @@
+// Note statistics - counts for the editor footer and the note info popup
+
+use chrono::Local;
+
+use crate::note_store::Note;
+
+/// Reading speed used for the estimate (words per minute, typical for prose)
+const WORDS_PER_MINUTE: usize = 230;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TextStats {
+    pub words: usize,  // Runs of non-whitespace
+    pub chars: usize,
+    pub lines: usize,
+}
+
+impl TextStats {
+    pub fn of(text: impl Iterator<Item = char>) -> Self {
+        let mut stats = TextStats { lines: 1, ..Default::default() };
+        let mut in_word = false;
+        for ch in text {
+            let word_char = !ch.is_whitespace();
+            if word_char && !in_word {
+                stats.words += 1;
+            }
+            if ch == '\n' {
+                stats.lines += 1;
+            }
+            in_word = word_char;
+            stats.chars += 1;
+        }
+        stats
+    }
+
+    /// "4 min read", rounded up; "under a minute" for short notes
+    pub fn reading_time(&self) -> String {
+        if self.words < WORDS_PER_MINUTE {
+            return "under a minute".to_string();
+        }
+        format!("{} min read", self.words.div_ceil(WORDS_PER_MINUTE))
+    }
+}
+
+/// Popup lines describing one note; `codes` is (coded segments, of which suggested)
+pub fn info_lines(note: &Note, text: &str, all_notes: &[Note], codes: (usize, usize)) -> Vec<String> {
+    let stats = TextStats::of(text.chars());
+    let links = crate::App::extract_wiki_links(text);
+    let missing = links.iter().filter(|l| !all_notes.iter().any(|n| &n.title == *l)).count();
+    let backlinks = all_notes.iter()
+        .filter(|n| n.id != note.id && crate::App::extract_wiki_links(&n.content).contains(&note.title))
+        .count();
+    let when = |t: chrono::DateTime<chrono::Utc>| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string();
+
+    vec![
+        format!("Words       {}", stats.words),
+        format!("Characters  {}", stats.chars),
+        format!("Lines       {}", stats.lines),
+        format!("Reading     {}", stats.reading_time()),
+        String::new(),
+        format!("Tags        {}", if note.tags.is_empty() {
+            "none".to_string()
+        } else {
+            note.tags.iter().map(|t| format!("#{}", t)).collect::<Vec<_>>().join(" ")
+        }),
+        format!("Coded       {} segments{}", codes.0, if codes.1 > 0 { format!(" ({} suggested)", codes.1) } else { String::new() }),
+        format!("Links       {} out{}, {} in", links.len(), if missing > 0 { format!(" ({} to missing notes)", missing) } else { String::new() }, backlinks),
+        String::new(),
+        format!("Created     {}", when(note.created_at)),
+        format!("Updated     {}", when(note.updated_at)),
+        format!("Id          {}", note.id),
+    ]
+}
@@ -0,0 +1,518 @@
+// Sync - notes shared between devices through a WebDAV or S3-compatible store
+//
+//   config.json   "sync": { "url": "https://dav.example.org/snyfter/", "user": "jo" }
+//                 "sync": { "backend": "s3", "url": "https://s3.eu-west-1.amazonaws.com/bucket/notes/",
+//                           "region": "eu-west-1", "user": "<access key id>" }
+//   password      $SNYFTER_SYNC_PASSWORD (or "password" in the config; for S3 the secret key)
+//   run           snyfter3 sync, or Ctrl+G S in the app
+//
+// The remote holds notes/<id>.json and a manifest.json of {id: updated_at, device}.
+// sync_state.json records, per note, the updated_at both sides agreed on at the last
+// sync - the common base. Comparing local and remote against it tells which side
+// changed: one side -> copy it over (deletions included), both sides -> conflict.
+// A conflict never overwrites: the local note stays as it is and the remote version
+// arrives as a separate "conflicted copy" note to merge by hand. An edit beats a
+// deletion on the other device. The manifest is written back only if it hasn't changed
+// since it was read (If-Match on its ETag); when another device got there first, it is
+// read again, what this run wrote is laid over it and the notes are compared once more.
+// sync_state.json only moves on once the manifest is written, so a note that device
+// changed meanwhile still compares against the old base (and conflicts if edited here).
+// Requests go through curl (S3 signing needs 7.75+).
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::note_store::{Note, NoteStore};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncBackend {
+    #[default]
+    WebDav,
+    S3,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncConfig {
+    pub backend: SyncBackend,
+    pub url: String,  // Folder (WebDAV) or bucket URL with an optional prefix (S3)
+    pub user: Option<String>,
+    pub password: Option<String>,  // $SNYFTER_SYNC_PASSWORD takes precedence
+    pub region: Option<String>,    // S3 only (default us-east-1)
+    pub device: Option<String>,    // Name in conflicted copies (default: the host name)
+}
+
+impl SyncConfig {
+    fn device_name(&self) -> String {
+        self.device.clone()
+            .or_else(|| std::env::var("HOSTNAME").ok())
+            .or_else(|| std::env::var("COMPUTERNAME").ok())
+            .unwrap_or_else(|| "another device".to_string())
+    }
+}
+
+/// Rounds of re-reading the manifest when other devices keep writing it
+const MAX_ATTEMPTS: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct RemoteEntry {
+    updated_at: String,
+    device: String,
+    #[serde(default)]
+    deleted: bool,  // Tombstone, so other devices delete their copy too
+}
+
+type Manifest = BTreeMap<String, RemoteEntry>;
+
+/// Manifest entries this run wrote: id -> (the entry it replaced, the new one)
+type Written = BTreeMap<String, (Option<RemoteEntry>, RemoteEntry)>;
+
+/// What to do with one note, from its updated_at here, at the last sync and there
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Action {
+    InSync,
+    Push,          // Changed only here, or edited here what the other side deleted
+    DeleteRemote,  // Deleted here, untouched there
+    Pull,          // Changed only there, or edited there what this side deleted
+    TrashLocal,    // Deleted there, untouched here
+    Conflict,      // Edited on both
+}
+
+/// The three-way comparison against the common base (None: no note / deleted)
+fn decide(mine: Option<&str>, base: Option<&str>, theirs: Option<&str>) -> Action {
+    if mine == theirs {
+        return Action::InSync;
+    }
+    let local_changed = mine != base;
+    let remote_changed = theirs != base;
+    // Both unchanged can't happen here: then mine == base == theirs
+    match (mine, theirs) {
+        (Some(_), _) if !remote_changed || (theirs.is_none() && local_changed) => Action::Push,
+        (None, Some(_)) if !remote_changed => Action::DeleteRemote,
+        (None, Some(_)) => Action::Pull,
+        (Some(_), Some(_)) if !local_changed => Action::Pull,
+        (Some(_), None) => Action::TrashLocal,
+        (Some(_), Some(_)) => Action::Conflict,
+        (None, None) => Action::InSync,
+    }
+}
+
+/// What each note came to this run, with the local note it changed (the note itself, or a
+/// conflicted copy); an attempt after a lost manifest write replaces what it decides again
+type Outcomes = BTreeMap<String, (Action, Option<String>)>;
+
+/// Each note counted once, however many attempts it took
+fn tally(outcomes: &Outcomes) -> SyncReport {
+    let mut report = SyncReport::default();
+    for (action, changed) in outcomes.values() {
+        match action {
+            Action::InSync => {}
+            Action::Push | Action::DeleteRemote => report.pushed += 1,
+            Action::Pull => report.pulled += 1,
+            Action::TrashLocal => report.deleted += 1,
+            Action::Conflict => report.conflicts += 1,
+        }
+        report.changed.extend(changed.clone());
+    }
+    report
+}
+
+/// Lay this run's entries over a manifest read again after another device wrote it,
+/// except where that device changed the same note since (those are decided again)
+fn reapply(manifest: &mut Manifest, written: &mut Written) {
+    written.retain(|id, (replaced, _)| manifest.get(id) == replaced.as_ref());
+    for (id, (_, entry)) in written.iter() {
+        manifest.insert(id.clone(), entry.clone());
+    }
+}
+
+/// What the last sync agreed on: note id -> updated_at
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SyncState {
+    base: BTreeMap<String, String>,
+}
+
+impl SyncState {
+    fn path(notes_dir: &Path) -> PathBuf {
+        notes_dir.join("sync_state.json")
+    }
+
+    fn load(notes_dir: &Path) -> Result<Self> {
+        match std::fs::read_to_string(Self::path(notes_dir)) {
+            Ok(json) => serde_json::from_str(&json).context("sync_state.json"),
+            Err(_) => Ok(SyncState::default()),
+        }
+    }
+
+    fn save(&self, notes_dir: &Path) -> Result<()> {
+        std::fs::write(Self::path(notes_dir), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub pushed: usize,
+    pub pulled: usize,
+    pub deleted: usize,     // Notes moved to the trash because another device deleted them
+    pub conflicts: usize,   // Conflicted copies created
+    pub changed: Vec<String>,  // Local notes that were replaced, trashed or created
+}
+
+impl SyncReport {
+    pub fn describe(&self) -> String {
+        let mut text = format!("Synced: {} sent, {} received", self.pushed, self.pulled);
+        if self.deleted > 0 {
+            text.push_str(&format!(", {} deleted", self.deleted));
+        }
+        if self.conflicts > 0 {
+            text.push_str(&format!(", {} conflicted copies to review", self.conflicts));
+        }
+        text
+    }
+}
+
+/// One round of pushing and pulling
+pub fn sync(notes_dir: &Path, store: &mut NoteStore, config: &SyncConfig) -> Result<SyncReport> {
+    if config.url.is_empty() {
+        bail!("no sync url configured (\"sync\" in config.json)");
+    }
+    let remote = Remote::new(config)?;
+    crate::backup::create(notes_dir, "sync")?;
+    let device = config.device_name();
+    let mut state = SyncState::load(notes_dir)?;
+    if config.backend == SyncBackend::WebDav {
+        remote.make_folder("notes/")?;
+    }
+    let mut written = Written::new();
+    let mut outcomes = Outcomes::new();
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        let (mut manifest, etag) = match remote.get_tagged("manifest.json")? {
+            Some((bytes, etag)) => (serde_json::from_slice(&bytes).context("remote manifest.json")?, etag),
+            None => (Manifest::new(), None),
+        };
+        reapply(&mut manifest, &mut written);
+        let mut settled: BTreeMap<String, Option<String>> = BTreeMap::new();
+
+        let local: BTreeMap<String, Note> = store.get_all_notes()?.into_iter().map(|n| (n.id.clone(), n)).collect();
+        let ids: BTreeSet<String> = local.keys().chain(manifest.keys()).chain(state.base.keys()).cloned().collect();
+        for id in ids {
+            let mine = local.get(&id).map(|n| n.updated_at.to_rfc3339());
+            let theirs = manifest.get(&id).filter(|e| !e.deleted).map(|e| e.updated_at.clone());
+            let base = state.base.get(&id).cloned();
+            let mut write = |manifest: &mut Manifest, entry: RemoteEntry| {
+                let replaced = manifest.insert(id.clone(), entry.clone());
+                written.entry(id.clone()).or_insert((replaced, entry.clone())).1 = entry;
+            };
+
+            let action = decide(mine.as_deref(), base.as_deref(), theirs.as_deref());
+            let agreed = match action {
+                Action::InSync => mine.clone(),
+                Action::Push => {
+                    remote.put(&note_path(&id), &serde_json::to_vec(&local[&id])?)?;
+                    write(&mut manifest, RemoteEntry { updated_at: mine.clone().unwrap_or_default(), device: device.clone(), deleted: false });
+                    outcomes.insert(id.clone(), (action, None));
+                    mine.clone()
+                }
+                Action::DeleteRemote => {
+                    remote.delete(&note_path(&id))?;
+                    write(&mut manifest, RemoteEntry { updated_at: Utc::now().to_rfc3339(), device: device.clone(), deleted: true });
+                    outcomes.insert(id.clone(), (action, None));
+                    None
+                }
+                Action::Pull => {
+                    store.put_note(&fetch(&remote, &id)?)?;
+                    outcomes.insert(id.clone(), (action, Some(id.clone())));
+                    theirs.clone()
+                }
+                Action::TrashLocal => {
+                    store.trash_note(&id)?;
+                    outcomes.insert(id.clone(), (action, Some(id.clone())));
+                    None
+                }
+                // Keep ours, add theirs as a copy, then send ours
+                Action::Conflict => {
+                    let other = manifest.get(&id).map(|e| e.device.clone()).unwrap_or_default();
+                    let copy = conflicted_copy(store, fetch(&remote, &id)?, &other)?;
+                    remote.put(&note_path(&id), &serde_json::to_vec(&local[&id])?)?;
+                    write(&mut manifest, RemoteEntry { updated_at: mine.clone().unwrap_or_default(), device: device.clone(), deleted: false });
+                    tracing::warn!(note = %id, copy = %copy.id, "sync conflict");
+                    outcomes.insert(id.clone(), (action, Some(copy.id)));
+                    mine.clone()
+                }
+            };
+            settled.insert(id, agreed);
+        }
+
+        if remote.put_if("manifest.json", &serde_json::to_vec_pretty(&manifest)?, etag.as_deref())? {
+            for (id, agreed) in settled {
+                set_base(&mut state, &id, agreed);
+            }
+            state.save(notes_dir)?;
+            let report = tally(&outcomes);
+            tracing::info!(pushed = report.pushed, pulled = report.pulled, deleted = report.deleted, conflicts = report.conflicts, attempt, "sync");
+            return Ok(report);
+        }
+        tracing::info!(attempt, "remote manifest changed during sync, merging again");
+    }
+    bail!("another device kept changing the remote manifest; sync again in a moment")
+}
+
+fn set_base(state: &mut SyncState, id: &str, updated_at: Option<String>) {
+    match updated_at {
+        Some(updated_at) => state.base.insert(id.to_string(), updated_at),
+        None => state.base.remove(id),
+    };
+}
+
+fn note_path(id: &str) -> String {
+    format!("notes/{}.json", id)
+}
+
+fn fetch(remote: &Remote, id: &str) -> Result<Note> {
+    let bytes = remote.get(&note_path(id))?
+        .ok_or_else(|| anyhow!("note {} is in the remote manifest but not on the server", id))?;
+    serde_json::from_slice(&bytes).with_context(|| format!("remote note {}", id))
+}
+
+/// The other device's version as a new local note, sent on the next sync like any other
+fn conflicted_copy(store: &mut NoteStore, theirs: Note, device: &str) -> Result<Note> {
+    let title = format!("{} (conflicted copy from {}, {})", theirs.title, device, theirs.updated_at.format("%Y-%m-%d %H:%M"));
+    let mut copy = store.create_note(&title, &theirs.content)?;
+    copy.tags = theirs.tags;
+    copy.codes = theirs.codes;
+    store.update_note(&copy)?;
+    Ok(copy)
+}
+
+/// The store, spoken to through curl
+struct Remote<'a> {
+    config: &'a SyncConfig,
+    auth: Option<tempfile::NamedTempFile>,  // curl config with the credentials, kept off the command line
+}
+
+impl<'a> Remote<'a> {
+    fn new(config: &'a SyncConfig) -> Result<Self> {
+        let password = std::env::var("SNYFTER_SYNC_PASSWORD").ok().or_else(|| config.password.clone());
+        let auth = match (&config.user, password) {
+            (Some(user), Some(password)) => {
+                let mut file = tempfile::NamedTempFile::new()?;
+                let quoted = format!("{}:{}", user, password).replace('\\', "\\\\").replace('"', "\\\"");
+                writeln!(file, "user = \"{}\"", quoted)?;
+                Some(file)
+            }
+            _ => None,
+        };
+        Ok(Remote { config, auth })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.config.url.trim_end_matches('/'), path)
+    }
+
+    /// (HTTP status, body, ETag)
+    fn request(&self, method: &str, path: &str, body: Option<&[u8]>, headers: &[String]) -> Result<(u16, Vec<u8>, Option<String>)> {
+        let response_headers = tempfile::NamedTempFile::new()?;
+        let mut command = Command::new("curl");
+        command.args(["-sS", "-X", method, "-w", "\n%{http_code}"]);
+        command.arg("-D").arg(response_headers.path());
+        for header in headers {
+            command.arg("-H").arg(header);
+        }
+        if let Some(auth) = &self.auth {
+            command.arg("-K").arg(auth.path());
+        }
+        if self.config.backend == SyncBackend::S3 {
+            let region = self.config.region.as_deref().unwrap_or("us-east-1");
+            command.arg("--aws-sigv4").arg(format!("aws:amz:{}:s3", region));
+        }
+        if body.is_some() {
+            command.args(["--data-binary", "@-", "-H", "Content-Type: application/json"]);
+        }
+        command.arg(self.url(path))
+            .stdin(if body.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let mut child = command.spawn().map_err(|e| anyhow!("could not run curl: {}", e))?;
+        if let (Some(body), Some(mut stdin)) = (body, child.stdin.take()) {
+            stdin.write_all(body)?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            bail!("{} {}: {}", method, path, String::from_utf8_lossy(&output.stderr).trim());
+        }
+
+        let mut stdout = output.stdout;
+        let split = stdout.iter().rposition(|&b| b == b'\n').unwrap_or(0);
+        let status = String::from_utf8_lossy(&stdout[split..]).trim().parse().unwrap_or(0);
+        stdout.truncate(split);
+        // The last block, after any redirects
+        let etag = std::fs::read_to_string(response_headers.path()).unwrap_or_default().lines()
+            .filter_map(|line| line.split_once(':'))
+            .filter(|(name, _)| name.trim().eq_ignore_ascii_case("etag"))
+            .map(|(_, value)| value.trim().to_string())
+            .last();
+        Ok((status, stdout, etag))
+    }
+
+    fn check(method: &str, path: &str, status: u16) -> Result<()> {
+        match status {
+            200..=299 => Ok(()),
+            401 | 403 => bail!("{} {}: access denied (HTTP {}) - check the sync user and password", method, path, status),
+            _ => bail!("{} {}: HTTP {}", method, path, status),
+        }
+    }
+
+    /// None if the file isn't there
+    fn get(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.get_tagged(path)?.map(|(body, _)| body))
+    }
+
+    /// `get` with the file's ETag, for a conditional `put_if`
+    fn get_tagged(&self, path: &str) -> Result<Option<(Vec<u8>, Option<String>)>> {
+        let (status, body, etag) = self.request("GET", path, None, &[])?;
+        if status == 404 {
+            return Ok(None);
+        }
+        Self::check("GET", path, status)?;
+        Ok(Some((body, etag)))
+    }
+
+    fn put(&self, path: &str, body: &[u8]) -> Result<()> {
+        let (status, _, _) = self.request("PUT", path, Some(body), &[])?;
+        Self::check("PUT", path, status)
+    }
+
+    /// Write only if the file still has `etag` (None: only if it doesn't exist yet);
+    /// false when it has changed since (412, or 409 from S3 for a write in flight)
+    fn put_if(&self, path: &str, body: &[u8], etag: Option<&str>) -> Result<bool> {
+        let condition = match etag {
+            Some(etag) => format!("If-Match: {}", etag),
+            None => "If-None-Match: *".to_string(),
+        };
+        let (status, _, _) = self.request("PUT", path, Some(body), &[condition])?;
+        if status == 412 || (status == 409 && self.config.backend == SyncBackend::S3) {
+            return Ok(false);
+        }
+        Self::check("PUT", path, status)?;
+        Ok(true)
+    }
+
+    fn delete(&self, path: &str) -> Result<()> {
+        let (status, _, _) = self.request("DELETE", path, None, &[])?;
+        if status == 404 {
+            return Ok(());
+        }
+        Self::check("DELETE", path, status)
+    }
+
+    /// WebDAV folders have to exist before files go in (405: it already does)
+    fn make_folder(&self, path: &str) -> Result<()> {
+        let (status, _, _) = self.request("MKCOL", path, None, &[])?;
+        if status == 405 {
+            return Ok(());
+        }
+        Self::check("MKCOL", path, status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: Option<&str> = Some("2026-01-01T00:00:00+00:00");
+    const NEW: Option<&str> = Some("2026-02-01T00:00:00+00:00");
+    const OTHER: Option<&str> = Some("2026-03-01T00:00:00+00:00");
+
+    #[test]
+    fn same_on_both_sides() {
+        assert_eq!(decide(OLD, OLD, OLD), Action::InSync);
+        assert_eq!(decide(NEW, OLD, NEW), Action::InSync);  // Same edit arrived both ways
+        assert_eq!(decide(None, OLD, None), Action::InSync);  // Deleted on both
+        assert_eq!(decide(None, None, None), Action::InSync);
+    }
+
+    #[test]
+    fn changed_here() {
+        assert_eq!(decide(NEW, OLD, OLD), Action::Push);
+        assert_eq!(decide(NEW, None, None), Action::Push);  // New note
+        assert_eq!(decide(None, OLD, OLD), Action::DeleteRemote);
+    }
+
+    #[test]
+    fn changed_there() {
+        assert_eq!(decide(OLD, OLD, NEW), Action::Pull);
+        assert_eq!(decide(None, None, NEW), Action::Pull);  // New on the other device
+        assert_eq!(decide(OLD, OLD, None), Action::TrashLocal);
+    }
+
+    #[test]
+    fn edits_beat_deletions() {
+        assert_eq!(decide(NEW, OLD, None), Action::Push);
+        assert_eq!(decide(None, OLD, NEW), Action::Pull);
+    }
+
+    #[test]
+    fn changed_on_both() {
+        assert_eq!(decide(NEW, OLD, OTHER), Action::Conflict);
+        assert_eq!(decide(NEW, None, OTHER), Action::Conflict);  // Created on both with the same id
+    }
+
+    fn entry(updated_at: &str, device: &str) -> RemoteEntry {
+        RemoteEntry { updated_at: updated_at.to_string(), device: device.to_string(), deleted: false }
+    }
+
+    #[test]
+    fn reapply_keeps_writes_the_other_device_didnt_touch() {
+        let mut written = Written::new();
+        written.insert("a".into(), (Some(entry("1", "x")), entry("2", "me")));
+        written.insert("b".into(), (None, entry("5", "me")));
+        written.insert("c".into(), (Some(entry("1", "x")), entry("2", "me")));
+        let mut manifest = Manifest::new();
+        manifest.insert("a".into(), entry("1", "x"));
+        manifest.insert("c".into(), entry("3", "x"));  // Changed again by the other device
+        manifest.insert("d".into(), entry("4", "x"));
+
+        reapply(&mut manifest, &mut written);
+        assert_eq!(manifest["a"], entry("2", "me"));
+        assert_eq!(manifest["b"], entry("5", "me"));
+        assert_eq!(manifest["c"], entry("3", "x"));
+        assert_eq!(manifest["d"], entry("4", "x"));
+        assert!(!written.contains_key("c"));
+    }
+
+    #[test]
+    fn a_note_changed_there_during_a_retry_still_conflicts() {
+        // Pushed NEW over OLD in the first attempt; the manifest write lost to a device that
+        // wrote OTHER for the same note, so the base must still be OLD
+        let mut written = Written::new();
+        written.insert("a".into(), (Some(entry("OLD", "x")), entry("NEW", "me")));
+        let mut manifest = Manifest::new();
+        manifest.insert("a".into(), entry("OTHER", "x"));
+        reapply(&mut manifest, &mut written);
+        let theirs = manifest["a"].updated_at.as_str();
+        assert_eq!(decide(Some("NEW"), Some("OLD"), Some(theirs)), Action::Conflict);
+    }
+
+    #[test]
+    fn tally_counts_each_note_once() {
+        let mut outcomes = Outcomes::new();
+        outcomes.insert("a".into(), (Action::Push, None));
+        outcomes.insert("b".into(), (Action::Pull, Some("b".into())));
+        outcomes.insert("c".into(), (Action::TrashLocal, Some("c".into())));
+        outcomes.insert("d".into(), (Action::Push, None));
+        // A later attempt decided d again
+        outcomes.insert("d".into(), (Action::Conflict, Some("d copy".into())));
+        let report = tally(&outcomes);
+        assert_eq!((report.pushed, report.pulled, report.deleted, report.conflicts), (1, 1, 1, 1));
+        assert_eq!(report.changed, ["b", "c", "d copy"]);
+    }
+}
@@ -0,0 +1,114 @@
+// Tag suggestions - tags offered for the open note, never added without asking
+//
+// With "tag_suggestions": true in config.json, a couple of seconds after the open note is
+// saved its suggestions show as chips along the bottom of the editor; Alt+T lists them
+// (and works out fresh ones with the option off). Suggestions are the tags of the notes
+// most like it (see similarity.rs) that it doesn't carry yet, weighted by how alike those
+// notes are, then the words that set it apart and that it uses often. `Enter` adds the
+// tag to the note's tag line (or a new one at the end), `d` dismisses it for the session.
+
+use std::collections::HashSet;
+
+use crate::note_store::Note;
+use crate::similarity::Similarity;
+use crate::tags;
+
+/// Most suggestions offered at once
+const MAX_SUGGESTIONS: usize = 5;
+/// Notes whose tags are considered, and how alike they must be
+const SIMILAR_NOTES: usize = 8;
+const MIN_SIMILARITY: f64 = 0.15;
+/// Times a word has to occur in the note before it is offered as a tag
+const MIN_WORD_COUNT: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reason {
+    Similar(usize),  // Carried by this many of the notes most like it
+    Frequent(usize), // A word the note uses this many times
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub tag: String,
+    pub reason: Reason,
+}
+
+impl Suggestion {
+    pub fn describe(&self) -> String {
+        match self.reason {
+            Reason::Similar(1) => "on a similar note".to_string(),
+            Reason::Similar(n) => format!("on {} similar notes", n),
+            Reason::Frequent(n) => format!("word used {} times here", n),
+        }
+    }
+}
+
+/// Tags to offer `note`, best first, leaving out the ones it has and those `dismissed`
+pub fn suggest(note: &Note, notes: &[Note], similarity: &Similarity, dismissed: &HashSet<String>) -> Vec<Suggestion> {
+    let mut taken: HashSet<String> = note.tags.iter().map(|t| t.to_lowercase()).collect();
+    taken.extend(dismissed.iter().map(|t| t.to_lowercase()));
+
+    // Tags of similar notes, scored by the similarity of the notes carrying them
+    let mut scored: Vec<(String, f64, usize)> = Vec::new();
+    for (id, score) in similarity.similar(&note.id, SIMILAR_NOTES) {
+        if score < MIN_SIMILARITY {
+            break;
+        }
+        let Some(other) = notes.iter().find(|n| n.id == id) else {
+            continue;
+        };
+        let mut seen = HashSet::new();
+        for tag in &other.tags {
+            if taken.contains(&tag.to_lowercase()) || !seen.insert(tag) {
+                continue;
+            }
+            match scored.iter_mut().find(|(t, _, _)| t == tag) {
+                Some((_, total, count)) => {
+                    *total += score;
+                    *count += 1;
+                }
+                None => scored.push((tag.clone(), score, 1)),
+            }
+        }
+    }
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let mut suggestions: Vec<Suggestion> = scored.into_iter()
+        .map(|(tag, _, count)| Suggestion { tag, reason: Reason::Similar(count) })
+        .take(MAX_SUGGESTIONS)
+        .collect();
+    taken.extend(suggestions.iter().map(|s| s.tag.to_lowercase()));
+
+    // Then the note's own distinctive, frequent words
+    for (word, count) in similarity.distinctive(&note.id, MAX_SUGGESTIONS * 2) {
+        if suggestions.len() >= MAX_SUGGESTIONS {
+            break;
+        }
+        let Some(tag) = tags::normalize(&word) else {
+            continue;
+        };
+        if count >= MIN_WORD_COUNT && !taken.contains(&tag) {
+            taken.insert(tag.clone());
+            suggestions.push(Suggestion { tag, reason: Reason::Frequent(count) });
+        }
+    }
+    suggestions
+}
+
+/// `content` with `#tag` on the end of its last line if that holds only tags, or else on a
+/// line of its own after the text
+pub fn add_tag(content: &str, tag: &str) -> String {
+    let text = content.trim_end();
+    let rest = &content[text.len()..];
+    let last = text.rsplit('\n').next().unwrap_or("");
+    let tag_line = !last.trim().is_empty() && last.split_whitespace().all(|w| {
+        w.len() > 1 && w.starts_with('#') && !w[1..].starts_with(|c: char| c == '#' || c.is_ascii_digit())
+    });
+    if tag_line {
+        format!("{} #{}{}", text, tag, rest)
+    } else if text.is_empty() {
+        format!("#{}\n", tag)
+    } else {
+        format!("{}\n\n#{}\n", text, tag)
+    }
+}
@@ -0,0 +1,84 @@
+// Tag manager - renaming, merging and removing #tags across every note
+//
+// Ctrl+G # lists the tags with how many notes carry each. `r` renames one (typed in the
+// editor header), `m` merges it into another tag, `d` removes it; each shows the notes
+// that would change before anything is written. The #tag text itself is rewritten in
+// the notes, so the tag list, the filter chips and the search index follow. Only whole
+// words count - `#interview` is left alone when renaming `#inter`. Read-only notes are
+// skipped.
+
+use crate::note_store::Note;
+
+/// A rename, merge (renaming into a tag that exists) or removal (`into` None) waiting for
+/// confirmation
+#[derive(Debug, Clone)]
+pub struct TagChange {
+    pub from: String,
+    pub into: Option<String>,
+    pub note_ids: Vec<String>,  // Notes carrying `from`
+}
+
+impl TagChange {
+    pub fn verb(&self, existing: bool) -> &'static str {
+        match (&self.into, existing) {
+            (None, _) => "Remove",
+            (Some(_), true) => "Merge",
+            (Some(_), false) => "Rename",
+        }
+    }
+}
+
+/// Tags with the number of notes carrying each, most used first (ties by name)
+pub fn usage(notes: &[Note]) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for note in notes {
+        let mut tags: Vec<&String> = note.tags.iter().collect();
+        tags.sort();
+        tags.dedup();
+        for tag in tags {
+            match counts.iter_mut().find(|(t, _)| t == tag) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((tag.clone(), 1)),
+            }
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// A tag as typed: without the leading # and surrounding space; None if it can't be one
+pub fn normalize(text: &str) -> Option<String> {
+    let tag = text.trim().trim_start_matches('#');
+    let valid = !tag.is_empty()
+        && !tag.contains(char::is_whitespace)
+        && !tag.starts_with(|c: char| c.is_ascii_digit() || c == '#');
+    valid.then(|| tag.to_string())
+}
+
+/// `content` with every `#from` word turned into `#into`, or removed with the space after
+/// it; returns the new text and how many were changed
+pub fn rewrite(content: &str, from: &str, into: Option<&str>) -> (String, usize) {
+    let target = format!("#{}", from);
+    let mut out = String::with_capacity(content.len());
+    let mut changed = 0;
+    for piece in content.split_inclusive(char::is_whitespace) {
+        let word = piece.trim_end_matches(char::is_whitespace);
+        let space = &piece[word.len()..];
+        if word != target {
+            out.push_str(piece);
+            continue;
+        }
+        changed += 1;
+        match into {
+            Some(into) => {
+                out.push('#');
+                out.push_str(into);
+                out.push_str(space);
+            }
+            // The line break stays; a space or tab goes with the tag
+            None if space.contains('\n') => out.push_str(space),
+            None => {}
+        }
+    }
+    (out, changed)
+}
@@ -0,0 +1,170 @@
+// Task items across notes - markdown checkboxes collected into one dashboard
+//
+//   - [ ] send consent form to P7 (due: 2024-05-01) #fieldwork
+//   - [x] transcribe interview 3
+//
+// Tasks are read straight from the note text every time the dashboard opens, so
+// there is nothing to keep in sync; toggling one rewrites its checkbox in the note.
+
+use chrono::{Duration, NaiveDate};
+
+use crate::note_store::Note;
+
+#[derive(Debug, Clone)]
+pub struct Task {
+    pub note_id: String,
+    pub note_title: String,
+    pub line: usize,          // Line in the note the task is on
+    pub text: String,         // Without the "- [ ] " prefix
+    pub done: bool,
+    pub due: Option<NaiveDate>,
+    pub tags: Vec<String>,    // The note's tags plus any #tags on the task line
+}
+
+/// Checkbox prefix of a task line: (byte offset of the box character, done)
+fn checkbox(line: &str) -> Option<(usize, bool)> {
+    let indent = line.len() - line.trim_start().len();
+    let rest = &line[indent..];
+    let bullet = rest.strip_prefix("- ").or_else(|| rest.strip_prefix("* "))?;
+    let done = match bullet.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    Some((indent + 2, done))
+}
+
+pub fn parse(note: &Note) -> Vec<Task> {
+    note.content.lines().enumerate()
+        .filter_map(|(i, line)| {
+            let (box_at, done) = checkbox(line)?;
+            let text = line[box_at + 3..].trim().to_string();
+            let mut tags = note.tags.clone();
+            for word in text.split_whitespace() {
+                if let Some(tag) = word.strip_prefix('#').filter(|t| !t.is_empty() && !t.starts_with('#')) {
+                    if !tags.iter().any(|t| t == tag) {
+                        tags.push(tag.to_string());
+                    }
+                }
+            }
+            Some(Task {
+                note_id: note.id.clone(),
+                note_title: note.title.clone(),
+                line: i,
+                due: due_date(&text),
+                text,
+                done,
+                tags,
+            })
+        })
+        .collect()
+}
+
+/// Date from "(due: YYYY-MM-DD)" anywhere in the task text
+fn due_date(text: &str) -> Option<NaiveDate> {
+    let start = text.find("(due:")? + "(due:".len();
+    let end = text[start..].find(')')? + start;
+    NaiveDate::parse_from_str(text[start..end].trim(), "%Y-%m-%d").ok()
+}
+
+/// Flip the checkbox on `line`; None if that line is not a task
+pub fn toggle(content: &str, line: usize) -> Option<String> {
+    let mut out = String::with_capacity(content.len());
+    let mut found = false;
+    for (i, text) in content.split_inclusive('\n').enumerate() {
+        match checkbox(text).filter(|_| i == line) {
+            Some((box_at, done)) => {
+                out.push_str(&text[..box_at]);
+                out.push_str(if done { "[ ]" } else { "[x]" });
+                out.push_str(&text[box_at + 3..]);
+                found = true;
+            }
+            None => out.push_str(text),
+        }
+    }
+    found.then_some(out)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DueFilter {
+    #[default]
+    Any,
+    Overdue,
+    Today,    // Due today or earlier
+    Week,     // Due within the next 7 days (or overdue)
+    Dated,    // Has any due date
+}
+
+impl DueFilter {
+    pub fn next(self) -> Self {
+        match self {
+            DueFilter::Any => DueFilter::Overdue,
+            DueFilter::Overdue => DueFilter::Today,
+            DueFilter::Today => DueFilter::Week,
+            DueFilter::Week => DueFilter::Dated,
+            DueFilter::Dated => DueFilter::Any,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DueFilter::Any => "any date",
+            DueFilter::Overdue => "overdue",
+            DueFilter::Today => "due today",
+            DueFilter::Week => "due this week",
+            DueFilter::Dated => "with a due date",
+        }
+    }
+
+    fn matches(self, due: Option<NaiveDate>, today: NaiveDate) -> bool {
+        match (self, due) {
+            (DueFilter::Any, _) => true,
+            (_, None) => false,
+            (DueFilter::Overdue, Some(d)) => d < today,
+            (DueFilter::Today, Some(d)) => d <= today,
+            (DueFilter::Week, Some(d)) => d <= today + Duration::days(7),
+            (DueFilter::Dated, Some(_)) => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TaskFilter {
+    pub tag: Option<String>,
+    pub due: DueFilter,
+    pub show_done: bool,
+}
+
+impl TaskFilter {
+    pub fn describe(&self) -> String {
+        let mut parts = vec![if self.show_done { "all" } else { "open" }.to_string()];
+        if let Some(tag) = &self.tag {
+            parts.push(format!("#{}", tag));
+        }
+        if self.due != DueFilter::Any {
+            parts.push(self.due.label().to_string());
+        }
+        parts.join(", ")
+    }
+}
+
+/// Every task passing the filter: dated ones first by due date, then the rest by note order
+pub fn collect(notes: &[Note], filter: &TaskFilter, today: NaiveDate) -> Vec<Task> {
+    let mut tasks: Vec<Task> = notes.iter()
+        .flat_map(parse)
+        .filter(|t| filter.show_done || !t.done)
+        .filter(|t| !filter.tag.as_ref().is_some_and(|tag| !t.tags.contains(tag)))
+        .filter(|t| filter.due.matches(t.due, today))
+        .collect();
+    // Stable sort keeps note order (newest first) among tasks without a date
+    tasks.sort_by_key(|t| (t.done, t.due.is_none(), t.due));
+    tasks
+}
+
+/// Tags on any task, for cycling the tag filter
+pub fn tags(notes: &[Note]) -> Vec<String> {
+    let mut tags: Vec<String> = notes.iter().flat_map(parse).flat_map(|t| t.tags).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
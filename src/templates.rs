@@ -0,0 +1,247 @@
+// Built-in note templates for qualitative analysis artifacts
+//
+// A template is a title, front matter fields and a body, all with {{variables}}.
+// The front matter records what the new note belongs to (case_id, code_id), so
+// memos and summaries can be found from their case or code later; the body
+// links back to the case note with a [[wiki link]]. Fields that expand to
+// nothing are left out.
+//
+//   {{date}} {{coder}}                       - today, config "coder" / $USER
+//   {{case}} {{case_id}} {{case_codes}}      - the open note and the codes applied in it
+//   {{code}} {{code_id}} {{code_description}} {{code_example}}
+//   {{citekey}} {{ref_title}} {{ref_authors}} {{ref_short}} {{ref_year}}
+//   {{ref_venue}} {{ref_doi}} {{ref_url}}    - a BibTeX entry (literature notes)
+
+use crate::bibtex::BibEntry;
+use crate::note_store::Note;
+use crate::qda_codes::Code;
+
+pub struct Template {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub needs_case: bool,  // Built from the open note
+    pub needs_code: bool,  // Asks for a code before creating
+    title: &'static str,
+    fields: &'static [(&'static str, &'static str)],
+    body: &'static str,
+}
+
+pub static TEMPLATES: &[Template] = &[
+    Template {
+        name: "Case summary",
+        description: "Summary of the open note as a case, with the codes applied in it",
+        needs_case: true,
+        needs_code: false,
+        title: "Case summary - {{case}}",
+        fields: &[("type", "case-summary"), ("case_id", "{{case_id}}"), ("coder", "{{coder}}"), ("date", "{{date}}")],
+        body: "# Case summary: [[{{case}}]]\n\
+               \n\
+               ## Codes applied\n\
+               {{case_codes}}\n\
+               \n\
+               ## Summary\n\
+               \n\
+               \n\
+               ## Key quotes\n\
+               \n\
+               \n\
+               ## Open questions\n\
+               \n\
+               #case-summary\n",
+    },
+    Template {
+        name: "Analytic memo",
+        description: "Memo on a code, tied to the open note if there is one",
+        needs_case: false,
+        needs_code: true,
+        title: "Memo - {{code}} - {{date}}",
+        fields: &[("type", "memo"), ("code_id", "{{code_id}}"), ("case_id", "{{case_id}}"), ("coder", "{{coder}}"), ("date", "{{date}}")],
+        body: "# Memo: {{code}}\n\
+               \n\
+               Case: [[{{case}}]]\n\
+               \n\
+               ## What I am seeing\n\
+               \n\
+               \n\
+               ## How it relates to other codes\n\
+               \n\
+               \n\
+               ## Next steps\n\
+               \n\
+               #memo\n",
+    },
+    Template {
+        name: "Code definition",
+        description: "Codebook entry: definition, inclusion and exclusion criteria, example",
+        needs_case: false,
+        needs_code: true,
+        title: "Code - {{code}}",
+        fields: &[("type", "code-definition"), ("code_id", "{{code_id}}"), ("coder", "{{coder}}"), ("date", "{{date}}")],
+        body: "# {{code}}\n\
+               \n\
+               ## Definition\n\
+               {{code_description}}\n\
+               \n\
+               ## Use when\n\
+               \n\
+               \n\
+               ## Do not use when\n\
+               \n\
+               \n\
+               ## Example\n\
+               {{code_example}}\n\
+               \n\
+               #codebook\n",
+    },
+    Template {
+        name: "Reflexivity journal",
+        description: "Dated entry on your own position, assumptions and reactions",
+        needs_case: false,
+        needs_code: false,
+        title: "Reflexivity journal - {{date}}",
+        fields: &[("type", "reflexivity"), ("coder", "{{coder}}"), ("date", "{{date}}")],
+        body: "# Reflexivity journal - {{date}}\n\
+               \n\
+               ## What I did today\n\
+               \n\
+               \n\
+               ## Assumptions I noticed\n\
+               \n\
+               \n\
+               ## How this may shape the analysis\n\
+               \n\
+               #reflexivity\n",
+    },
+];
+
+/// Today's note (Ctrl+T) - kept out of the picker since there is one per day
+pub static DAILY: Template = Template {
+    name: "Daily note",
+    description: "One note per day, for fieldwork logs and running notes",
+    needs_case: false,
+    needs_code: false,
+    title: "{{date}}",
+    fields: &[("type", "daily"), ("date", "{{date}}"), ("coder", "{{coder}}")],
+    body: "# {{date}}\n\
+           \n\
+           ## Notes\n\
+           \n\
+           \n\
+           ## Follow up\n\
+           \n\
+           #daily\n",
+};
+
+/// Reading notes on a reference - one per BibTeX entry, created by `snyfter3 import-bib`
+pub static LITERATURE: Template = Template {
+    name: "Literature note",
+    description: "Reading notes on a reference: summary, arguments, quotes",
+    needs_case: false,
+    needs_code: false,
+    title: "{{ref_short}} {{ref_year}} - {{ref_title}}",
+    fields: &[
+        ("type", "literature"), ("citekey", "{{citekey}}"), ("authors", "{{ref_authors}}"),
+        ("year", "{{ref_year}}"), ("venue", "{{ref_venue}}"), ("doi", "{{ref_doi}}"), ("url", "{{ref_url}}"),
+        ("coder", "{{coder}}"), ("date", "{{date}}"),
+    ],
+    body: "# {{ref_title}}\n\
+           \n\
+           {{ref_authors}} ({{ref_year}}). {{ref_venue}}\n\
+           \n\
+           ## Summary\n\
+           \n\
+           \n\
+           ## Key arguments\n\
+           \n\
+           \n\
+           ## Quotes\n\
+           \n\
+           \n\
+           ## Relevance to my research\n\
+           \n\
+           #literature\n",
+};
+
+/// Everything a template can refer to
+pub struct TemplateContext<'a> {
+    pub date: String,
+    pub coder: String,
+    pub case: Option<&'a Note>,
+    pub case_codes: Vec<(String, usize)>,  // Code name and how often it is applied in the case
+    pub code: Option<&'a Code>,
+    pub code_example: Option<String>,      // A segment coded with `code`, quoted in the definition
+    pub reference: Option<&'a BibEntry>,
+}
+
+impl Template {
+    /// Expand into (title, content)
+    pub fn render(&self, ctx: &TemplateContext) -> (String, String) {
+        let case_codes = ctx.case_codes.iter()
+            .map(|(name, count)| format!("- {} ({})", name, count))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let example = ctx.code_example.as_ref()
+            .map(|text| format!("> {}", text.trim().replace('\n', "\n> ")))
+            .unwrap_or_default();
+
+        let reference = ctx.reference;
+        let ref_authors = reference.map(BibEntry::authors).unwrap_or_default();
+        let ref_short = reference.map(BibEntry::short_authors).unwrap_or_default();
+
+        let vars: [(&str, &str); 17] = [
+            ("date", &ctx.date),
+            ("coder", &ctx.coder),
+            ("case", ctx.case.map(|n| n.title.as_str()).unwrap_or("")),
+            ("case_id", ctx.case.map(|n| n.id.as_str()).unwrap_or("")),
+            ("case_codes", &case_codes),
+            ("code", ctx.code.map(|c| c.name.as_str()).unwrap_or("")),
+            ("code_id", ctx.code.map(|c| c.id.as_str()).unwrap_or("")),
+            ("code_description", ctx.code.map(|c| c.description.as_str()).unwrap_or("")),
+            ("code_example", &example),
+            ("citekey", reference.map(|r| r.key.as_str()).unwrap_or("")),
+            ("ref_title", reference.map(|r| if r.title().is_empty() { r.key.as_str() } else { r.title() }).unwrap_or("")),
+            ("ref_authors", &ref_authors),
+            ("ref_short", &ref_short),
+            ("ref_year", reference.map(BibEntry::year).unwrap_or("")),
+            ("ref_venue", reference.map(BibEntry::venue).unwrap_or("")),
+            ("ref_doi", reference.map(BibEntry::doi).unwrap_or("")),
+            ("ref_url", reference.map(BibEntry::url).unwrap_or("")),
+        ];
+
+        let mut content = String::from("---\n");
+        for (key, value) in self.fields {
+            let value = expand(value, &vars);
+            if !value.is_empty() {
+                content.push_str(&format!("{}: {}\n", key, value));
+            }
+        }
+        content.push_str("---\n\n");
+
+        // A body line whose variables are all empty ("Case: [[]]" without a case) goes entirely
+        for line in self.body.lines() {
+            let used: Vec<&str> = vars.iter()
+                .filter(|(name, _)| line.contains(&placeholder(name)))
+                .map(|(_, value)| *value)
+                .collect();
+            if !used.is_empty() && used.iter().all(|value| value.is_empty()) {
+                continue;
+            }
+            content.push_str(&expand(line, &vars));
+            content.push('\n');
+        }
+
+        (expand(self.title, &vars), content)
+    }
+}
+
+fn placeholder(name: &str) -> String {
+    format!("{{{{{}}}}}", name)
+}
+
+fn expand(text: &str, vars: &[(&str, &str)]) -> String {
+    let mut out = text.to_string();
+    for (name, value) in vars {
+        out = out.replace(&placeholder(name), value);
+    }
+    out
+}
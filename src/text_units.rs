@@ -0,0 +1,138 @@
+// Text units - the sentence or paragraph around a char offset
+//
+// Coding is mostly done a sentence or a paragraph at a time rather than on arbitrary
+// character ranges. A paragraph runs between blank lines; a sentence ends at . ! or ?
+// (with any closing quotes or brackets) followed by whitespace, or at the end of its
+// paragraph. Both are trimmed of surrounding whitespace. Offsets are chars, like code
+// applications.
+
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Unit {
+    #[default]
+    Exact,      // The span as it is
+    Sentence,
+    Paragraph,
+}
+
+impl Unit {
+    pub fn next(self) -> Self {
+        match self {
+            Unit::Exact => Unit::Sentence,
+            Unit::Sentence => Unit::Paragraph,
+            Unit::Paragraph => Unit::Exact,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Unit::Exact => "exact match",
+            Unit::Sentence => "sentence",
+            Unit::Paragraph => "paragraph",
+        }
+    }
+
+    /// Grow `start..end` to whole units: from the start of the unit `start` is in to the
+    /// end of the one the last char is in
+    pub fn expand(self, chars: &[char], start: usize, end: usize) -> (usize, usize) {
+        let last = end.saturating_sub(1).max(start);
+        let (from, to) = match self {
+            Unit::Exact => return (start, end),
+            Unit::Sentence => (sentence_at(chars, start).0, sentence_at(chars, last).1),
+            Unit::Paragraph => (paragraph_at(chars, start).0, paragraph_at(chars, last).1),
+        };
+        if from < to { (from, to) } else { (start, end) }
+    }
+}
+
+fn line_start(chars: &[char], i: usize) -> usize {
+    let mut j = i;
+    while j > 0 && chars[j - 1] != '\n' {
+        j -= 1;
+    }
+    j
+}
+
+fn line_end(chars: &[char], i: usize) -> usize {
+    let mut j = i;
+    while j < chars.len() && chars[j] != '\n' {
+        j += 1;
+    }
+    j
+}
+
+fn is_blank(chars: &[char], start: usize, end: usize) -> bool {
+    chars[start..end].iter().all(|c| c.is_whitespace())
+}
+
+/// The paragraph containing `pos`, without its surrounding whitespace (empty on a blank line)
+pub fn paragraph_at(chars: &[char], pos: usize) -> (usize, usize) {
+    let pos = pos.min(chars.len());
+    let (mut start, mut end) = (line_start(chars, pos), line_end(chars, pos));
+    if is_blank(chars, start, end) {
+        return (pos, pos);
+    }
+    while start > 0 {
+        let above = line_start(chars, start - 1);
+        if is_blank(chars, above, start - 1) {
+            break;
+        }
+        start = above;
+    }
+    while end < chars.len() {
+        let below = line_end(chars, end + 1);
+        if is_blank(chars, end + 1, below) {
+            break;
+        }
+        end = below;
+    }
+    trim(chars, start, end)
+}
+
+/// The sentence containing `pos`, without its surrounding whitespace. Between two sentences
+/// it is the next one. Abbreviations like "e.g." end a sentence too.
+pub fn sentence_at(chars: &[char], pos: usize) -> (usize, usize) {
+    let (para_start, para_end) = paragraph_at(chars, pos);
+    if para_start == para_end {
+        return (para_start, para_end);
+    }
+    let mut pos = pos.clamp(para_start, para_end - 1);
+    while pos + 1 < para_end && chars[pos].is_whitespace() {
+        pos += 1;
+    }
+
+    let mut start = pos;
+    while start > para_start && !sentence_ends_at(chars, start) {
+        start -= 1;
+    }
+    let mut end = pos;
+    while end < para_end && !sentence_ends_at(chars, end) {
+        end += 1;
+    }
+    trim(chars, start, end)
+}
+
+/// A sentence ends just before `i`: the char there is whitespace, and right before it comes
+/// a terminator (maybe followed by closing quotes or brackets)
+fn sentence_ends_at(chars: &[char], i: usize) -> bool {
+    if i == 0 || i >= chars.len() || !chars[i].is_whitespace() {
+        return false;
+    }
+    let mut j = i;
+    while j > 0 && matches!(chars[j - 1], '"' | '\'' | ')' | ']' | '”' | '’' | '»') {
+        j -= 1;
+    }
+    j > 0 && matches!(chars[j - 1], '.' | '!' | '?' | '…')
+}
+
+fn trim(chars: &[char], mut start: usize, mut end: usize) -> (usize, usize) {
+    while start < end && chars[start].is_whitespace() {
+        start += 1;
+    }
+    while end > start && chars[end - 1].is_whitespace() {
+        end -= 1;
+    }
+    (start, end)
+}
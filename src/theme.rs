@@ -0,0 +1,285 @@
+// Color themes - every RGB value used by the UI and edit renderer lives here
+
+use crossterm::style::Color;
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    pub fn to_color(self) -> Color {
+        Color::Rgb { r: self.r, g: self.g, b: self.b }
+    }
+
+    /// ANSI escape setting this as the foreground color (for the raw edit renderer)
+    pub fn ansi_fg(self) -> String {
+        format!("\x1b[38;2;{};{};{}m", self.r, self.g, self.b)
+    }
+
+    /// ANSI escape setting this as the background color (for the raw edit renderer)
+    pub fn ansi_bg(self) -> String {
+        format!("\x1b[48;2;{};{};{}m", self.r, self.g, self.b)
+    }
+
+    /// `amount` (0 to 1) of the way from this color to `other`
+    pub fn mix(self, other: Rgb, amount: f32) -> Rgb {
+        let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * amount).round() as u8;
+        Rgb::new(channel(self.r, other.r), channel(self.g, other.g), channel(self.b, other.b))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]  // User themes only need to override the colors they care about
+pub struct Theme {
+    // Top header and bottom status bar
+    pub header_bg: Rgb,
+    pub header_fg: Rgb,
+    pub status_bg: Rgb,
+    pub status_fg: Rgb,
+    pub status_warn_fg: Rgb,   // Status messages by level (info uses status_fg)
+    pub status_error_fg: Rgb,
+
+    // Search bar
+    pub search_bg: Rgb,
+    pub search_fg: Rgb,
+    pub search_focused_bg: Rgb,
+    pub search_focused_fg: Rgb,
+
+    // Pane headers (NOTES / EDITOR)
+    pub pane_header_bg: Rgb,
+    pub pane_header_fg: Rgb,
+    pub pane_header_focused_bg: Rgb,
+    pub pane_header_focused_fg: Rgb,
+
+    // Note list
+    pub list_bg: Rgb,
+    pub list_fg: Rgb,
+    pub list_selected_bg: Rgb,
+    pub list_selected_fg: Rgb,
+    pub list_muted_fg: Rgb,    // Dates, snippets, and tag chips
+
+    // Divider between panes
+    pub divider: Rgb,
+    pub divider_active: Rgb,
+
+    // Editor
+    pub editor_bg: Rgb,
+    pub editor_fg: Rgb,
+    pub editor_placeholder: Rgb,
+    pub cursor_bg: Rgb,
+    pub selection_bg: Rgb,
+    pub selection_fg: Rgb,
+    pub highlight_bg: Rgb,     // Search result highlights
+    pub scrollbar_track: Rgb,
+    pub scrollbar_thumb: Rgb,
+
+    // Overlays (help, reports, pickers)
+    pub overlay_bg: Rgb,
+    pub overlay_fg: Rgb,
+    pub overlay_border: Rgb,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub const BUILTIN_NAMES: &'static [&'static str] = &["dark", "light", "solarized", "high-contrast"];
+
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "solarized" => Some(Self::solarized()),
+            "high-contrast" => Some(Self::high_contrast()),
+            _ => None,
+        }
+    }
+
+    pub fn dark() -> Self {
+        Theme {
+            header_bg: Rgb::new(40, 40, 40),
+            header_fg: Rgb::new(200, 200, 200),
+            status_bg: Rgb::new(40, 40, 40),
+            status_fg: Rgb::new(180, 180, 180),
+            status_warn_fg: Rgb::new(230, 190, 90),
+            status_error_fg: Rgb::new(240, 110, 100),
+
+            search_bg: Rgb::new(35, 35, 35),
+            search_fg: Rgb::new(150, 150, 150),
+            search_focused_bg: Rgb::new(50, 70, 120),
+            search_focused_fg: Rgb::new(255, 255, 255),
+
+            pane_header_bg: Rgb::new(30, 30, 30),
+            pane_header_fg: Rgb::new(150, 150, 150),
+            pane_header_focused_bg: Rgb::new(40, 50, 70),
+            pane_header_focused_fg: Rgb::new(200, 200, 200),
+
+            list_bg: Rgb::new(0, 0, 0),
+            list_fg: Rgb::new(200, 200, 200),
+            list_selected_bg: Rgb::new(60, 60, 100),
+            list_selected_fg: Rgb::new(255, 255, 255),
+            list_muted_fg: Rgb::new(140, 140, 140),
+
+            divider: Rgb::new(60, 60, 60),
+            divider_active: Rgb::new(100, 150, 200),
+
+            editor_bg: Rgb::new(0, 0, 0),
+            editor_fg: Rgb::new(220, 220, 220),
+            editor_placeholder: Rgb::new(100, 100, 100),
+            cursor_bg: Rgb::new(80, 80, 200),
+            selection_bg: Rgb::new(80, 80, 200),
+            selection_fg: Rgb::new(255, 255, 255),
+            highlight_bg: Rgb::new(0, 0, 139),
+            scrollbar_track: Rgb::new(40, 40, 40),
+            scrollbar_thumb: Rgb::new(100, 100, 100),
+
+            overlay_bg: Rgb::new(30, 30, 40),
+            overlay_fg: Rgb::new(220, 220, 220),
+            overlay_border: Rgb::new(100, 150, 200),
+        }
+    }
+
+    pub fn light() -> Self {
+        Theme {
+            header_bg: Rgb::new(215, 215, 215),
+            header_fg: Rgb::new(40, 40, 40),
+            status_bg: Rgb::new(215, 215, 215),
+            status_fg: Rgb::new(60, 60, 60),
+            status_warn_fg: Rgb::new(150, 100, 0),
+            status_error_fg: Rgb::new(190, 30, 30),
+
+            search_bg: Rgb::new(235, 235, 235),
+            search_fg: Rgb::new(90, 90, 90),
+            search_focused_bg: Rgb::new(190, 210, 245),
+            search_focused_fg: Rgb::new(0, 0, 0),
+
+            pane_header_bg: Rgb::new(230, 230, 230),
+            pane_header_fg: Rgb::new(100, 100, 100),
+            pane_header_focused_bg: Rgb::new(200, 215, 240),
+            pane_header_focused_fg: Rgb::new(20, 20, 20),
+
+            list_bg: Rgb::new(250, 250, 250),
+            list_fg: Rgb::new(40, 40, 40),
+            list_selected_bg: Rgb::new(180, 200, 240),
+            list_selected_fg: Rgb::new(0, 0, 0),
+            list_muted_fg: Rgb::new(120, 120, 120),
+
+            divider: Rgb::new(190, 190, 190),
+            divider_active: Rgb::new(70, 120, 200),
+
+            editor_bg: Rgb::new(255, 255, 255),
+            editor_fg: Rgb::new(30, 30, 30),
+            editor_placeholder: Rgb::new(160, 160, 160),
+            cursor_bg: Rgb::new(120, 150, 230),
+            selection_bg: Rgb::new(170, 195, 245),
+            selection_fg: Rgb::new(0, 0, 0),
+            highlight_bg: Rgb::new(250, 230, 140),
+            scrollbar_track: Rgb::new(220, 220, 220),
+            scrollbar_thumb: Rgb::new(150, 150, 150),
+
+            overlay_bg: Rgb::new(245, 245, 240),
+            overlay_fg: Rgb::new(30, 30, 30),
+            overlay_border: Rgb::new(70, 120, 200),
+        }
+    }
+
+    pub fn solarized() -> Self {
+        // Solarized dark palette (base03..base1 plus blue/yellow accents)
+        Theme {
+            header_bg: Rgb::new(7, 54, 66),
+            header_fg: Rgb::new(147, 161, 161),
+            status_bg: Rgb::new(7, 54, 66),
+            status_fg: Rgb::new(131, 148, 150),
+            status_warn_fg: Rgb::new(181, 137, 0),
+            status_error_fg: Rgb::new(220, 50, 47),
+
+            search_bg: Rgb::new(0, 43, 54),
+            search_fg: Rgb::new(88, 110, 117),
+            search_focused_bg: Rgb::new(38, 139, 210),
+            search_focused_fg: Rgb::new(253, 246, 227),
+
+            pane_header_bg: Rgb::new(0, 43, 54),
+            pane_header_fg: Rgb::new(88, 110, 117),
+            pane_header_focused_bg: Rgb::new(7, 54, 66),
+            pane_header_focused_fg: Rgb::new(147, 161, 161),
+
+            list_bg: Rgb::new(0, 43, 54),
+            list_fg: Rgb::new(131, 148, 150),
+            list_selected_bg: Rgb::new(38, 139, 210),
+            list_selected_fg: Rgb::new(253, 246, 227),
+            list_muted_fg: Rgb::new(88, 110, 117),
+
+            divider: Rgb::new(7, 54, 66),
+            divider_active: Rgb::new(42, 161, 152),
+
+            editor_bg: Rgb::new(0, 43, 54),
+            editor_fg: Rgb::new(147, 161, 161),
+            editor_placeholder: Rgb::new(88, 110, 117),
+            cursor_bg: Rgb::new(181, 137, 0),
+            selection_bg: Rgb::new(38, 139, 210),
+            selection_fg: Rgb::new(253, 246, 227),
+            highlight_bg: Rgb::new(108, 113, 196),
+            scrollbar_track: Rgb::new(7, 54, 66),
+            scrollbar_thumb: Rgb::new(88, 110, 117),
+
+            overlay_bg: Rgb::new(7, 54, 66),
+            overlay_fg: Rgb::new(147, 161, 161),
+            overlay_border: Rgb::new(42, 161, 152),
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Theme {
+            header_bg: Rgb::new(0, 0, 0),
+            header_fg: Rgb::new(255, 255, 255),
+            status_bg: Rgb::new(0, 0, 0),
+            status_fg: Rgb::new(255, 255, 0),
+            status_warn_fg: Rgb::new(255, 160, 0),
+            status_error_fg: Rgb::new(255, 60, 60),
+
+            search_bg: Rgb::new(0, 0, 0),
+            search_fg: Rgb::new(255, 255, 255),
+            search_focused_bg: Rgb::new(255, 255, 0),
+            search_focused_fg: Rgb::new(0, 0, 0),
+
+            pane_header_bg: Rgb::new(0, 0, 0),
+            pane_header_fg: Rgb::new(200, 200, 200),
+            pane_header_focused_bg: Rgb::new(255, 255, 255),
+            pane_header_focused_fg: Rgb::new(0, 0, 0),
+
+            list_bg: Rgb::new(0, 0, 0),
+            list_fg: Rgb::new(255, 255, 255),
+            list_selected_bg: Rgb::new(255, 255, 0),
+            list_selected_fg: Rgb::new(0, 0, 0),
+            list_muted_fg: Rgb::new(200, 200, 200),
+
+            divider: Rgb::new(255, 255, 255),
+            divider_active: Rgb::new(255, 255, 0),
+
+            editor_bg: Rgb::new(0, 0, 0),
+            editor_fg: Rgb::new(255, 255, 255),
+            editor_placeholder: Rgb::new(200, 200, 200),
+            cursor_bg: Rgb::new(255, 255, 0),
+            selection_bg: Rgb::new(0, 255, 255),
+            selection_fg: Rgb::new(0, 0, 0),
+            highlight_bg: Rgb::new(255, 0, 255),
+            scrollbar_track: Rgb::new(80, 80, 80),
+            scrollbar_thumb: Rgb::new(255, 255, 255),
+
+            overlay_bg: Rgb::new(0, 0, 0),
+            overlay_fg: Rgb::new(255, 255, 255),
+            overlay_border: Rgb::new(255, 255, 0),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+// Titles that follow the text - nvALT-style, with config "auto_title"
+//
+// A note's title is taken from its first non-empty line (a Markdown heading loses its
+// #s), skipping front matter. It keeps following the text as long as it is still the
+// generated "Note 2025-01-01 13:00" placeholder or exactly what the text gave last
+// time; once renamed by hand (Ctrl+R) a note keeps its title.
+
+use chrono::NaiveDateTime;
+
+use crate::merge::body_start;
+
+const MAX_CHARS: usize = 80;
+
+/// Title for a note's text, None when there is no text yet
+pub fn derive(content: &str) -> Option<String> {
+    let body: String = content.chars().skip(body_start(content)).collect();
+    let line = body.lines()
+        .map(|l| l.trim().trim_start_matches('#').trim())
+        .find(|l| !l.is_empty())?;
+    let title: String = line.chars().take(MAX_CHARS).collect();
+    Some(title.trim_end().to_string())
+}
+
+/// The "Note <date> <time>" title new notes start with
+pub fn is_placeholder(title: &str) -> bool {
+    title.strip_prefix("Note ")
+        .is_some_and(|stamp| NaiveDateTime::parse_from_str(stamp, "%Y-%m-%d %H:%M").is_ok())
+}
+
+/// New title for a note whose text changes from `old` to `new`, if it should follow
+pub fn follow(title: &str, old: &str, new: &str) -> Option<String> {
+    let following = is_placeholder(title) || derive(old).as_deref() == Some(title);
+    derive(new).filter(|derived| following && derived != title)
+}
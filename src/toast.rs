@@ -0,0 +1,21 @@
+// Short-lived notifications drawn above the status bar - they disappear on their own
+
+use std::time::{Duration, Instant};
+
+pub struct Toast {
+    pub message: String,
+    expires_at: Instant,
+}
+
+impl Toast {
+    pub fn new(message: &str, duration: Duration) -> Self {
+        Toast {
+            message: message.to_string(),
+            expires_at: Instant::now() + duration,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.expires_at
+    }
+}
@@ -0,0 +1,132 @@
+// Timestamped transcripts - interview recordings coded by time as well as by text
+//
+//   [00:12:34] Interviewer: And after the move?
+//   [00:12:41] P3: Money got tight, honestly.
+//
+// Timestamps in square brackets ([hh:mm:ss] or [mm:ss], fractions ignored) are
+// anchors: a coded segment runs from the last timestamp before it to the first one
+// after it, which is what the segment export records. Subtitle files (.srt, .vtt)
+// are imported as one timestamped line per cue; plain text transcripts are taken as
+// they are, as long as they contain timestamps.
+
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+use crate::note_store::{Note, NoteStore};
+
+/// Create a note from a transcript file
+pub fn import_transcript(store: &mut NoteStore, path: &Path) -> Result<Note> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let subtitles = path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("srt") || e.eq_ignore_ascii_case("vtt"));
+    let body = if subtitles { from_subtitles(&text) } else { text.trim_end().to_string() + "\n" };
+    if markers(&body).is_empty() {
+        bail!("{} has no timestamps", path.display());
+    }
+
+    let source = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let title = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| source.clone());
+    let content = format!("---\nsource: {}\n---\n\n{}", source, body);
+    let note = store.create_note(&title, &content)?;
+    tracing::info!(file = %path.display(), note = %note.id, "transcript import");
+    Ok(note)
+}
+
+/// One `[hh:mm:ss] text` line per cue of an SRT or WebVTT file
+fn from_subtitles(text: &str) -> String {
+    let mut out = String::new();
+    let text = text.replace("\r\n", "\n");
+    for block in text.split("\n\n") {
+        let mut lines = block.lines().skip_while(|l| !l.contains("-->"));
+        // Blocks without a timing line are headers, cue-less NOTE / STYLE blocks and the like
+        let Some(start) = lines.next().and_then(|t| t.split("-->").next()).and_then(parse_time) else {
+            continue;
+        };
+        let words: Vec<String> = lines.map(strip_cue_tags).filter(|l| !l.is_empty()).collect();
+        if !words.is_empty() {
+            out.push_str(&format!("[{}] {}\n", format_time(start), words.join(" ")));
+        }
+    }
+    out
+}
+
+/// Cue text without markup; a WebVTT voice tag (<v Name>) becomes "Name: "
+fn strip_cue_tags(line: &str) -> String {
+    let mut out = String::new();
+    let mut rest = line.trim();
+    while let Some(open) = rest.find('<') {
+        out.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('>') else {
+            out.push_str(&rest[open..]);
+            rest = "";
+            break;
+        };
+        let tag = &rest[open + 1..open + close];
+        // <v Name> or <v.class Name>
+        if tag.starts_with("v ") || tag.starts_with("v.") {
+            if let Some((_, name)) = tag.split_once(' ') {
+                out.push_str(&format!("{}: ", name.trim()));
+            }
+        }
+        rest = &rest[open + close + 1..];
+    }
+    out.push_str(rest);
+    out.trim().to_string()
+}
+
+/// Seconds from "01:02:03", "02:03" or "00:01:02,500" (SRT) / "00:01:02.500" (VTT)
+pub fn parse_time(text: &str) -> Option<u32> {
+    let text = text.trim();
+    let whole = text.split([',', '.']).next()?;
+    let parts: Vec<&str> = whole.split(':').collect();
+    // Minutes and seconds after the first field are always two digits
+    let digits = |p: &&str| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit());
+    if !(2..=3).contains(&parts.len()) || !parts.iter().all(digits) || parts[1..].iter().any(|p| p.len() != 2) {
+        return None;
+    }
+    let numbers: Vec<u32> = parts.iter().map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    match numbers.as_slice() {
+        [m, s] if *s < 60 => Some(m * 60 + s),
+        [h, m, s] if *m < 60 && *s < 60 => Some(h * 3600 + m * 60 + s),
+        _ => None,
+    }
+}
+
+/// "00:12:34"
+pub fn format_time(seconds: u32) -> String {
+    format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+/// (char offset, seconds) of every [timestamp] in a text, in order
+pub fn markers(text: &str) -> Vec<(usize, u32)> {
+    let mut found = Vec::new();
+    let mut open: Option<(usize, usize)> = None;  // (char offset, byte offset) of the last '['
+    for (offset, (byte, c)) in text.char_indices().enumerate() {
+        match c {
+            '[' => open = Some((offset, byte)),
+            ']' => {
+                if let Some((start, start_byte)) = open.take() {
+                    if let Some(seconds) = parse_time(&text[start_byte + 1..byte]) {
+                        found.push((start, seconds));
+                    }
+                }
+            }
+            '\n' => open = None,
+            _ => {}
+        }
+    }
+    found
+}
+
+/// Last timestamp in a line (the editor footer's position in the recording)
+pub fn last_time(line: &str) -> Option<u32> {
+    markers(line).last().map(|&(_, seconds)| seconds)
+}
+
+/// Time range of the char span start..end given a note's `markers`: from the last timestamp
+/// at or before `start` to the first one after `end` (None at the end of the recording)
+pub fn time_range(markers: &[(usize, u32)], start: usize, end: usize) -> Option<(u32, Option<u32>)> {
+    let from = markers.iter().take_while(|(offset, _)| *offset <= start).last()?.1;
+    let to = markers.iter().find(|(offset, _)| *offset >= end).map(|&(_, seconds)| seconds);
+    Some((from, to))
+}
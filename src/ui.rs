@@ -3,23 +3,95 @@
 use anyhow::Result;
 use crossterm::{
     cursor,
-    style::{self, Color, SetBackgroundColor, SetForegroundColor},
+    style::{self, Attribute, Color, SetAttribute, SetBackgroundColor, SetForegroundColor},
     terminal,
     execute,
 };
+use std::collections::{BTreeMap, HashMap};
 use std::io;
+use std::time::{Duration, Instant};
 use unicode_width::UnicodeWidthStr;
 
 use crate::{App, FocusArea};
+use crate::layout::{Layout, Rect};
+use crate::config::ListDensity;
+use crate::note_store::Note;
+use crate::overlay::Overlay;
+use crate::pdf_import;
+use crate::transcript;
+use crate::query::{self, SearchQuery};
+use crate::images;
+use crate::messages::Level;
+use crate::reading;
+use crate::stats::TextStats;
 use crate::syntax::SyntaxHighlighter;
-use crate::edit_renderer::EditPanelRenderer;
+use crate::edit_renderer::{EditPanelRenderer, Mark, Underline};
+use crate::annotations;
+use crate::theme::Rgb;
+use crate::keymap::{self, Command};
+use crate::tag_suggest::Suggestion;
+
+/// How far a coded segment's tint goes from the editor background toward its code's color
+const CODE_TINT: f32 = 0.35;
 
 pub struct UI {
     syntax_highlighter: SyntaxHighlighter,
     edit_renderer: EditPanelRenderer,
 }
 
+/// Coalesces a burst of resize events (dragging a window edge sends dozens) into one relayout
+pub struct ResizeDebounce {
+    first: Option<Instant>,  // Start of the current burst
+    last: Instant,
+}
+
+impl ResizeDebounce {
+    /// Quiet time after the last resize before laying out again
+    const SETTLE: Duration = Duration::from_millis(80);
+    /// Relayout at least this often while a burst goes on, so the screen never looks frozen
+    const MAX_WAIT: Duration = Duration::from_millis(400);
+
+    pub fn new() -> Self {
+        ResizeDebounce { first: None, last: Instant::now() }
+    }
+
+    pub fn record(&mut self) {
+        let now = Instant::now();
+        self.first.get_or_insert(now);
+        self.last = now;
+    }
+
+    /// A resize is still settling; rendering now would draw a layout about to be thrown away
+    pub fn pending(&self) -> bool {
+        self.first.is_some()
+    }
+
+    /// True once per burst, when it is time to lay out for the new size
+    pub fn settled(&mut self) -> bool {
+        let Some(first) = self.first else {
+            return false;
+        };
+        if self.last.elapsed() >= Self::SETTLE || first.elapsed() >= Self::MAX_WAIT {
+            self.first = None;
+            return true;
+        }
+        false
+    }
+}
+
 impl UI {
+    /// Row of the pane headers: below the title bar, search bar and filter chips
+    pub const PANES_TOP: u16 = 3;
+    const CHIPS_ROW: u16 = 2;
+
+    /// Smallest terminal the layout works in; below it only a placeholder is drawn
+    pub const MIN_WIDTH: u16 = 40;
+    pub const MIN_HEIGHT: u16 = 10;
+
+    pub fn too_small(width: u16, height: u16) -> bool {
+        width < Self::MIN_WIDTH || height < Self::MIN_HEIGHT
+    }
+
     pub fn new() -> Result<Self> {
         Ok(UI {
             syntax_highlighter: SyntaxHighlighter::new()?,
@@ -27,6 +99,17 @@ impl UI {
         })
     }
 
+    /// Current editor viewport scroll (columns, rows) from the last render
+    pub fn editor_scroll(&self) -> (usize, usize) {
+        let (scroll_x, scroll_y) = self.edit_renderer.get_scroll();
+        (scroll_x as usize, scroll_y as usize)
+    }
+
+    /// Scroll the editor so `line` sits near the top (the cursor padding still applies)
+    pub fn scroll_editor_to(&mut self, line: usize) {
+        self.edit_renderer.scroll_to_y(line.min(u16::MAX as usize) as u16);
+    }
+
     /// Handle mouse click in the editor area and convert to document position
     pub fn handle_editor_click(&self, app: &mut App, click_row: usize, click_col: usize) {
         // Get the current scroll offsets from the edit renderer
@@ -49,25 +132,62 @@ impl UI {
             terminal::Clear(terminal::ClearType::All),
             cursor::MoveTo(0, 0),
         )?;
+        images::clear(app.image_protocol)?;
 
-        // Calculate split positions (left-right split)
-        let split_x = (width as f32 * app.split_ratio) as u16;
-        let editor_width = width.saturating_sub(split_x + 1);  // +1 for divider
+        if app.locked {
+            return self.render_locked(app, width, height);
+        }
+        if Self::too_small(width, height) {
+            return self.render_too_small(app, width, height);
+        }
 
-        // Always render header and search bar
-        self.render_header(app, width)?;
-        self.render_search_bar(app, width)?;
+        // The same layout the mouse handler hit-tests against
+        let layout = app.layout(width, height);
+        let editor = layout.editor;
 
-        // Render note list on left (starting at line 3)
-        self.render_note_list(app, split_x, 2, height - 3)?;  // -3 for header, search, and status
+        // Zen mode: only the editor, on the whole screen
+        if layout.list.width == 0 {
+            self.render_editor(app, editor.x, editor.width, editor.y, editor.height)?;
+        } else {
+            // Header, search bar and filter chips
+            self.render_header(app, width)?;
+            self.render_search_bar(app, width)?;
+            self.render_chips(app, width)?;
 
-        // Render divider
-        self.render_divider(split_x, 2, height - 3, app.dragging_divider)?;
+            // Note list on the left, then the divider, the editor and the codes pane
+            let (list, divider) = (layout.list, layout.divider);
+            self.render_note_list(app, list.width, list.y, list.height)?;
+            self.render_divider(app, divider.x, divider.y, divider.height)?;
+            self.render_editor(app, editor.x, editor.width, editor.y, editor.height)?;
+            if layout.has_codes() {
+                let line = layout.codes_divider;
+                execute!(io::stdout(), SetForegroundColor(app.theme.divider.to_color()))?;
+                for y in line.y..line.y + line.height {
+                    execute!(io::stdout(), cursor::MoveTo(line.x, y))?;
+                    print!("│");
+                }
+                self.render_codes_pane(app, layout.codes)?;
+            }
+
+            self.render_status_bar(app, width, height)?;
+
+            if let Some((title, lines)) = app.preview_lines() {
+                self.render_preview(app, &title, &lines, editor)?;
+            }
+            if let Some((column, row, codes)) = &app.code_hover {
+                self.render_code_hover(app, *column, *row, codes, width, height)?;
+            }
+        }
 
-        // Render editor on right
-        self.render_editor(app, split_x + 1, editor_width, 2, height - 3)?;
+        if let Some(ref toast) = app.toast {
+            self.render_toast(app, &toast.message, width, height)?;
+        }
 
-        self.render_status_bar(app, width, height)?;
+        if let Some(ref overlay) = app.overlay {
+            self.render_overlay(app, overlay, width, height)?;
+            execute!(io::stdout(), cursor::Hide)?;
+            return Ok(());
+        }
 
         // Position cursor based on focus area
         match app.focus_area {
@@ -84,12 +204,55 @@ impl UI {
         Ok(())
     }
 
+    /// Lock screen: nothing of the notes, just the passphrase prompt (masked)
+    fn render_locked(&self, app: &App, width: u16, height: u16) -> Result<()> {
+        let lines = [
+            "snyfter3 is locked".to_string(),
+            String::new(),
+            format!("Passphrase: {}", "•".repeat(app.lock_input.chars().count())),
+            if app.lock_failed { "Wrong passphrase".to_string() } else { "Enter: unlock  Ctrl+Q: quit".to_string() },
+        ];
+        Self::render_centered(app, &lines, width, height)
+    }
+
+    /// Centered notice with the current and the needed size
+    fn render_too_small(&self, app: &App, width: u16, height: u16) -> Result<()> {
+        let lines = [
+            "Window too small".to_string(),
+            format!("{}x{}, need {}x{}", width, height, Self::MIN_WIDTH, Self::MIN_HEIGHT),
+        ];
+        Self::render_centered(app, &lines, width, height)
+    }
+
+    /// Lines centered on an otherwise empty screen, clipped to whatever fits
+    fn render_centered(app: &App, lines: &[String], width: u16, height: u16) -> Result<()> {
+        let top = height.saturating_sub(lines.len() as u16) / 2;
+
+        execute!(
+            io::stdout(),
+            cursor::Hide,
+            SetForegroundColor(app.theme.status_fg.to_color()),
+        )?;
+        for (i, line) in lines.iter().enumerate() {
+            let row = top + i as u16;
+            if row >= height {
+                break;
+            }
+            let text = truncate_to_width(line, width as usize);
+            let x = (width as usize).saturating_sub(text.width()) / 2;
+            execute!(io::stdout(), cursor::MoveTo(x as u16, row))?;
+            print!("{}", text);
+        }
+        execute!(io::stdout(), style::ResetColor)?;
+        Ok(())
+    }
+
     fn render_header(&self, app: &App, width: u16) -> Result<()> {
         execute!(
             io::stdout(),
             cursor::MoveTo(0, 0),
-            SetBackgroundColor(Color::Rgb { r: 40, g: 40, b: 40 }),
-            SetForegroundColor(Color::Rgb { r: 200, g: 200, b: 200 }),
+            SetBackgroundColor(app.theme.header_bg.to_color()),
+            SetForegroundColor(app.theme.header_fg.to_color()),
         )?;
 
         let header = format!(" Snyfter3 - {} notes ",
@@ -106,20 +269,36 @@ impl UI {
         Ok(())
     }
 
-    fn render_divider(&self, x: u16, start_y: u16, height: u16, is_dragging: bool) -> Result<()> {
-        let color = if is_dragging {
-            Color::Rgb { r: 100, g: 150, b: 200 }
+    /// Blank columns either side of the text in reading mode
+    const READING_MARGIN: u16 = 2;
+
+    /// Columns the reading mode text wraps at
+    pub fn reading_text_width(editor_width: u16) -> usize {
+        editor_width.saturating_sub(2 * Self::READING_MARGIN).max(1) as usize
+    }
+
+    /// Columns taken by the divider - large UI trades the thin line for a wide grab bar
+    pub fn divider_width(large_ui: bool) -> u16 {
+        if large_ui { 3 } else { 1 }
+    }
+
+    fn render_divider(&self, app: &App, x: u16, start_y: u16, height: u16) -> Result<()> {
+        let color = if app.dragging_divider {
+            app.theme.divider_active.to_color()
         } else {
-            Color::Rgb { r: 60, g: 60, b: 60 }
+            app.theme.divider.to_color()
         };
+        let large = app.config.large_ui;
 
         for y in start_y..start_y + height {
-            execute!(
-                io::stdout(),
-                cursor::MoveTo(x, y),
-                SetForegroundColor(color),
-            )?;
-            print!("│");
+            execute!(io::stdout(), cursor::MoveTo(x, y))?;
+            if large {
+                execute!(io::stdout(), SetBackgroundColor(color))?;
+                print!("{:width$}", "", width = Self::divider_width(true) as usize);
+            } else {
+                execute!(io::stdout(), SetForegroundColor(color))?;
+                print!("│");
+            }
         }
 
         execute!(io::stdout(), style::ResetColor)?;
@@ -133,14 +312,14 @@ impl UI {
             io::stdout(),
             cursor::MoveTo(0, 1),
             SetBackgroundColor(if is_focused {
-                Color::Rgb { r: 50, g: 70, b: 120 }  // Blue background when focused
+                app.theme.search_focused_bg.to_color()
             } else {
-                Color::Rgb { r: 35, g: 35, b: 35 }
+                app.theme.search_bg.to_color()
             }),
             SetForegroundColor(if is_focused {
-                Color::White
+                app.theme.search_focused_fg.to_color()
             } else {
-                Color::Rgb { r: 150, g: 150, b: 150 }
+                app.theme.search_fg.to_color()
             }),
         )?;
 
@@ -160,9 +339,62 @@ impl UI {
         Ok(())
     }
 
+    fn render_chips(&self, app: &App, width: u16) -> Result<()> {
+        execute!(
+            io::stdout(),
+            cursor::MoveTo(0, Self::CHIPS_ROW),
+            SetBackgroundColor(app.theme.search_bg.to_color()),
+        )?;
+        print!("{:width$}", "", width = width as usize);
+
+        for ((label, active), (start, _)) in app.chips.labels().iter().zip(Self::chip_spans(app)) {
+            if start + label.width() as u16 + 2 > width {
+                break;
+            }
+            let (bg, fg) = if *active {
+                (app.theme.list_selected_bg, app.theme.list_selected_fg)
+            } else {
+                (app.theme.search_bg, app.theme.list_muted_fg)
+            };
+            execute!(
+                io::stdout(),
+                cursor::MoveTo(start, Self::CHIPS_ROW),
+                SetBackgroundColor(bg.to_color()),
+                SetForegroundColor(fg.to_color()),
+            )?;
+            print!(" {} ", label);
+        }
+
+        execute!(io::stdout(), style::ResetColor)?;
+        Ok(())
+    }
+
+    /// Column span (start, end exclusive) of each chip on the chips row
+    fn chip_spans(app: &App) -> Vec<(u16, u16)> {
+        let mut x = 1;
+        app.chips.labels().iter()
+            .map(|(label, _)| {
+                let start = x;
+                x += label.width() as u16 + 2;
+                let span = (start, x);
+                x += 1;
+                span
+            })
+            .collect()
+    }
+
+    /// Which chip (0-based) a click on the chips row hit
+    pub fn chip_at(app: &App, row: u16, column: u16) -> Option<usize> {
+        if row != Self::CHIPS_ROW {
+            return None;
+        }
+        Self::chip_spans(app).iter().position(|&(start, end)| column >= start && column < end)
+    }
+
     fn render_note_list(&self, app: &App, width: u16, start_y: u16, height: u16) -> Result<()> {
         // Display search results if searching, otherwise all notes
-        let display_height = height - 1;
+        // (header row on top, footer row at the bottom)
+        let display_height = height.saturating_sub(2);
 
         // Render list header with focus indication
         let is_focused = app.focus_area == FocusArea::NoteList;
@@ -171,69 +403,421 @@ impl UI {
             io::stdout(),
             cursor::MoveTo(0, start_y),
             SetBackgroundColor(if is_focused {
-                Color::Rgb { r: 40, g: 50, b: 70 }  // Darker blue when focused
+                app.theme.pane_header_focused_bg.to_color()
             } else {
-                Color::Rgb { r: 30, g: 30, b: 30 }
+                app.theme.pane_header_bg.to_color()
             }),
             SetForegroundColor(if is_focused {
-                Color::Rgb { r: 200, g: 200, b: 200 }
+                app.theme.pane_header_focused_fg.to_color()
             } else {
-                Color::Rgb { r: 150, g: 150, b: 150 }
+                app.theme.pane_header_fg.to_color()
             }),
         )?;
 
-        print!("{:width$}", " NOTES", width = width as usize);
+        let margin = Self::list_margin(app.config.large_ui);
+        print!("{:width$}", format!("{:margin$}NOTES", "", margin = margin), width = width as usize);
 
-        // Render filtered notes
-        for (i, note) in app.filtered_notes.iter().enumerate() {
-            if i >= display_height as usize {
+        // Render filtered notes - rows can span several lines depending on list density
+        let density = app.config.list_density;
+        let text_width = (width as usize).saturating_sub(margin);
+        let end_y = start_y + 1 + display_height;
+        let mut y = start_y + 1;
+        let search = SearchQuery::parse(&app.search_query);
+        let phrases = search.phrases();
+
+        for (i, note) in app.filtered_notes.iter().enumerate().skip(app.list_scroll) {
+            if y >= end_y {
                 break;
             }
 
-            let y = start_y + 1 + i as u16;
-            execute!(io::stdout(), cursor::MoveTo(0, y))?;
+            let is_selected = i == app.selected_note_index;
+            let bg = if is_selected { app.theme.list_selected_bg } else { app.theme.list_bg };
+            let mut lines = Self::note_row_lines(note, density);
+            // Title characters the search matched (whole phrases, when quoted), shifted past
+            // the mark if there is one
+            let mut matched = query::title_matches(&note.title, &search.text);
+            matched.extend(query::phrase_matches(&note.title, &phrases));
+            matched.sort_unstable();
+            matched.dedup();
+            if let Some(id) = app.zettel_ids.get(&note.id).filter(|_| app.config.zettel_ids) {
+                lines[0] = format!("{} {}", id, lines[0]);
+                let shift = id.chars().count() + 1;
+                matched.iter_mut().for_each(|i| *i += shift);
+            }
+            if app.marked.contains(&note.id) {
+                lines[0] = format!("● {}", lines[0]);
+                matched.iter_mut().for_each(|i| *i += 2);
+            }
+
+            for (line_idx, line) in lines.iter().enumerate() {
+                if y >= end_y {
+                    break;
+                }
+
+                // Title line uses the main color, secondary lines are muted
+                let fg = if is_selected {
+                    app.theme.list_selected_fg
+                } else if line_idx == 0 {
+                    app.theme.list_fg
+                } else {
+                    app.theme.list_muted_fg
+                };
 
-            // Highlight selected note
-            if i == app.selected_note_index {
                 execute!(
                     io::stdout(),
-                    SetBackgroundColor(Color::Rgb { r: 60, g: 60, b: 100 }),
-                    SetForegroundColor(Color::Rgb { r: 255, g: 255, b: 255 }),
+                    cursor::MoveTo(0, y),
+                    SetBackgroundColor(bg.to_color()),
+                    SetForegroundColor(fg.to_color()),
                 )?;
-            } else {
+
+                let text = truncate_to_width(line, text_width.saturating_sub(margin));
+                let padding = text_width.saturating_sub(text.width());
+                // Snippet lines light up the phrases they hold
+                let snippet_matched;
+                let matched = if line_idx == 0 {
+                    &matched
+                } else {
+                    snippet_matched = query::phrase_matches(line, &phrases);
+                    &snippet_matched
+                };
+                if !matched.is_empty() {
+                    // Matches cut off by the truncation don't light up the "..."
+                    let shown = if text == *line { usize::MAX } else { text.chars().count().saturating_sub(3) };
+                    let visible: Vec<usize> = matched.iter().copied().filter(|&i| i < shown).collect();
+                    print!("{:margin$}", "", margin = margin);
+                    Self::print_matched(&text, &visible)?;
+                    print!("{:padding$}", "", padding = padding);
+                } else {
+                    print!("{:margin$}{}{:padding$}", "", text, "", margin = margin, padding = padding);
+                }
+                y += 1;
+            }
+
+            // Large UI separates notes with a blank line instead of packing them
+            if app.config.large_ui && y < end_y {
                 execute!(
                     io::stdout(),
-                    SetBackgroundColor(Color::Black),
-                    SetForegroundColor(Color::Rgb { r: 200, g: 200, b: 200 }),
+                    cursor::MoveTo(0, y),
+                    SetBackgroundColor(app.theme.list_bg.to_color()),
                 )?;
+                print!("{:width$}", "", width = width as usize);
+                y += 1;
             }
-
-            // Format note line
-            let title = if note.title.width() > (width as usize - 4) {
-                format!("{}...", &note.title.chars().take(width as usize - 7).collect::<String>())
-            } else {
-                note.title.clone()
-            };
-
-            print!(" {:<width$}", title, width = width as usize - 1);
         }
 
         // Clear remaining lines
-        for i in app.filtered_notes.len()..display_height as usize {
-            let y = start_y + 1 + i as u16;
+        while y < end_y {
             execute!(
                 io::stdout(),
                 cursor::MoveTo(0, y),
-                SetBackgroundColor(Color::Black),
+                SetBackgroundColor(app.theme.list_bg.to_color()),
             )?;
             print!("{:width$}", "", width = width as usize);
+            y += 1;
+        }
+
+        self.render_list_scrollbar(app, width, start_y + 1, display_height)?;
+        self.render_pane_footer(app, 0, end_y, width, &Self::list_footer(app), is_focused)?;
+
+        execute!(io::stdout(), style::ResetColor)?;
+        Ok(())
+    }
+
+    /// Text with the chars at `matched` (sorted char positions) in bold and underlined
+    fn print_matched(text: &str, matched: &[usize]) -> Result<()> {
+        let mut next = matched.iter().peekable();
+        for (i, ch) in text.chars().enumerate() {
+            if next.peek() == Some(&&i) {
+                next.next();
+                execute!(io::stdout(), SetAttribute(Attribute::Bold), SetAttribute(Attribute::Underlined))?;
+                print!("{}", ch);
+                execute!(io::stdout(), SetAttribute(Attribute::NormalIntensity), SetAttribute(Attribute::NoUnderline))?;
+            } else {
+                print!("{}", ch);
+            }
+        }
+        Ok(())
+    }
+
+    /// Codes pane: the codebook with how often each code is applied in the open note,
+    /// used codes first; the codes under the cursor are marked ▸. The notes related to the
+    /// open one take the bottom of the pane
+    fn render_codes_pane(&self, app: &App, area: Rect) -> Result<()> {
+        execute!(
+            io::stdout(),
+            cursor::MoveTo(area.x, area.y),
+            SetBackgroundColor(app.theme.pane_header_bg.to_color()),
+            SetForegroundColor(app.theme.pane_header_fg.to_color()),
+        )?;
+        print!("{:width$}", truncate_to_width(" CODES", area.width as usize), width = area.width as usize);
+
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        let mut at_cursor = Vec::new();
+        if let Some(note) = &app.selected_note {
+            let applications = app.codes.get_code_applications(&note.id).unwrap_or_default();
+            for code_id in note.codes.iter().map(|s| &s.code_id).chain(applications.iter().map(|a| &a.code_id)) {
+                *counts.entry(code_id.clone()).or_default() += 1;
+            }
+            at_cursor = app.codes_at_cursor(note).into_iter().map(|(id, _)| id).collect();
+        }
+        // The code tree, children indented under their parents
+        let codes = app.codes.tree();
+
+        let body = area.body();
+        let related_rows = Self::related_rows(app, body.height);
+        let text_width = (area.width as usize).saturating_sub(4);
+        for row in 0..body.height - related_rows {
+            execute!(
+                io::stdout(),
+                cursor::MoveTo(area.x, body.y + row),
+                SetBackgroundColor(app.theme.list_bg.to_color()),
+            )?;
+            let Some(&(code, depth)) = codes.get(row as usize) else {
+                print!("{:width$}", "", width = area.width as usize);
+                continue;
+            };
+            let count = counts.get(&code.id).copied().unwrap_or(0);
+            let marker = if at_cursor.contains(&code.id) { "▸" } else { " " };
+            let dragged = app.dragging_code.as_deref() == Some(code.id.as_str());
+            if dragged {
+                execute!(io::stdout(), SetBackgroundColor(app.theme.list_selected_bg.to_color()))?;
+            }
+            execute!(io::stdout(), SetForegroundColor(app.theme.list_fg.to_color()))?;
+            print!("{}", marker);
+            execute!(io::stdout(), SetForegroundColor(app.codes.color_of(&code.id).to_crossterm_color()))?;
+            print!("■ ");
+            let fg = if count > 0 { app.theme.list_fg } else { app.theme.list_muted_fg };
+            execute!(io::stdout(), SetForegroundColor(fg.to_color()))?;
+            let indent = "  ".repeat(depth);
+            let label = if count > 0 { format!("{}{} ({})", indent, code.name, count) } else { format!("{}{}", indent, code.name) };
+            let label = truncate_to_width(&label, text_width);
+            print!("{}{:pad$} ", label, "", pad = text_width.saturating_sub(label.width()));
         }
 
+        if related_rows > 0 {
+            self.render_related(app, Rect { y: body.y + body.height - related_rows, height: related_rows, ..body })?;
+        }
 
+        let used = counts.keys().filter(|id| app.codes.get_code(id).is_some()).count();
+        let footer = if app.selected_note.is_some() {
+            format!("{} of {} used here", used, codes.len())
+        } else {
+            format!("{} codes", codes.len())
+        };
+        self.render_pane_footer(app, area.x, area.y + area.height.saturating_sub(1), area.width, &footer, false)?;
         execute!(io::stdout(), style::ResetColor)?;
         Ok(())
     }
 
+    /// Rows at the bottom of the codes pane for the related notes: a heading and a row per
+    /// note, at most half the pane
+    fn related_rows(app: &App, height: u16) -> u16 {
+        match app.open_related().len() {
+            0 => 0,
+            n => (n as u16 + 1).min(height / 2),
+        }
+    }
+
+    /// "RELATED" and the notes most like the open one, with how alike they are
+    fn render_related(&self, app: &App, area: Rect) -> Result<()> {
+        execute!(
+            io::stdout(),
+            cursor::MoveTo(area.x, area.y),
+            SetBackgroundColor(app.theme.pane_header_bg.to_color()),
+            SetForegroundColor(app.theme.pane_header_fg.to_color()),
+        )?;
+        print!("{:width$}", truncate_to_width(" RELATED", area.width as usize), width = area.width as usize);
+
+        let text_width = (area.width as usize).saturating_sub(2);
+        for (row, (note_id, score)) in (1..area.height).zip(app.open_related()) {
+            let title = app.all_notes.iter().find(|n| n.id == *note_id).map_or("", |n| n.title.as_str());
+            let label = truncate_to_width(&format!("{:>3.0}% {}", score * 100.0, title), text_width);
+            execute!(
+                io::stdout(),
+                cursor::MoveTo(area.x, area.y + row),
+                SetBackgroundColor(app.theme.list_bg.to_color()),
+                SetForegroundColor(app.theme.list_fg.to_color()),
+            )?;
+            print!(" {}{:pad$} ", label, "", pad = text_width.saturating_sub(label.width()));
+        }
+        Ok(())
+    }
+
+    /// Code on a screen row of the codes pane (rows follow the code tree)
+    pub fn code_at_row(app: &App, layout: &Layout, row: u16) -> Option<String> {
+        let body = layout.codes.body();
+        let rows = body.height - Self::related_rows(app, body.height);
+        if !layout.has_codes() || row < body.y || row >= body.y + rows {
+            return None;
+        }
+        app.codes.tree().get((row - body.y) as usize).map(|(code, _)| code.id.clone())
+    }
+
+    /// Related note on a screen row of the codes pane
+    pub fn related_at_row(app: &App, layout: &Layout, row: u16) -> Option<String> {
+        let body = layout.codes.body();
+        let rows = Self::related_rows(app, body.height);
+        let first = body.y + body.height - rows + 1;  // Below the RELATED heading
+        if !layout.has_codes() || rows == 0 || row < first || row >= body.y + body.height {
+            return None;
+        }
+        app.open_related().get((row - first) as usize).map(|(id, _)| id.clone())
+    }
+
+    /// "3/120 · newest first" - position in the list, how many match, and the order
+    fn list_footer(app: &App) -> String {
+        let shown = app.filtered_notes.len();
+        let total = app.all_notes.len();
+        let position = if shown == 0 {
+            "no notes".to_string()
+        } else if shown == total {
+            format!("{}/{}", app.selected_note_index + 1, shown)
+        } else {
+            format!("{}/{} of {}", app.selected_note_index + 1, shown, total)
+        };
+        // Free text is ranked by the fuzzy matcher; otherwise the store order (last edited) stands
+        let order = if SearchQuery::parse(&app.search_query).text.is_empty() {
+            "newest first"
+        } else {
+            "best match"
+        };
+        let filtered = if app.chips.is_active() { " · chips on" } else { "" };
+        format!("{} · {}{}", position, order, filtered)
+    }
+
+    /// One-line footer at the bottom of a pane, in the header's colors so the focused pane
+    /// is marked at both ends
+    fn render_pane_footer(&self, app: &App, x: u16, y: u16, width: u16, text: &str, focused: bool) -> Result<()> {
+        let (bg, fg) = if focused {
+            (app.theme.pane_header_focused_bg, app.theme.pane_header_focused_fg)
+        } else {
+            (app.theme.pane_header_bg, app.theme.pane_header_fg)
+        };
+        execute!(
+            io::stdout(),
+            cursor::MoveTo(x, y),
+            SetBackgroundColor(bg.to_color()),
+            SetForegroundColor(fg.to_color()),
+        )?;
+        let margin = Self::list_margin(app.config.large_ui);
+        let text = truncate_to_width(text, (width as usize).saturating_sub(margin * 2));
+        let padding = (width as usize).saturating_sub(margin + text.width());
+        print!("{:margin$}{}{:padding$}", "", text, "", margin = margin, padding = padding);
+        Ok(())
+    }
+
+    /// Scrollbar in the list's right margin, only when some notes are out of view
+    fn render_list_scrollbar(&self, app: &App, width: u16, top: u16, rows: u16) -> Result<()> {
+        let total = app.filtered_notes.len();
+        let visible = Self::visible_note_count(app, app.list_scroll, rows as usize);
+        if width < 2 || rows == 0 || total <= visible {
+            return Ok(());
+        }
+
+        // Thumb size and position in note units, scaled to the track
+        let track = rows as usize;
+        let thumb = (track * visible / total).max(1);
+        let max_offset = total - visible;
+        let thumb_top = (track - thumb) * app.list_scroll.min(max_offset) / max_offset;
+
+        for row in 0..track {
+            let in_thumb = row >= thumb_top && row < thumb_top + thumb;
+            execute!(
+                io::stdout(),
+                cursor::MoveTo(width - 1, top + row as u16),
+                SetBackgroundColor(app.theme.list_bg.to_color()),
+                SetForegroundColor(if in_thumb { app.theme.list_fg } else { app.theme.list_muted_fg }.to_color()),
+            )?;
+            print!("{}", if in_thumb { "┃" } else { "│" });
+        }
+        Ok(())
+    }
+
+    /// Lines shown for one note in the list; the count is the row height for the current density
+    pub fn note_row_lines(note: &Note, density: ListDensity) -> Vec<String> {
+        let mut lines = vec![note.title.clone()];
+
+        match density {
+            ListDensity::Compact => {}
+            ListDensity::Cozy => {
+                lines.push(note.updated_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string());
+            }
+            ListDensity::Detailed => {
+                // Up to two non-empty content lines as a snippet (skipping a line that repeats the title)
+                let snippet = note.content.lines()
+                    .map(|l| l.trim())
+                    .filter(|l| !l.is_empty() && *l != note.title)
+                    .take(2);
+                for line in snippet {
+                    lines.push(line.to_string());
+                }
+
+                if !note.tags.is_empty() {
+                    let chips: Vec<String> = note.tags.iter().map(|t| format!("[#{}]", t)).collect();
+                    lines.push(chips.join(" "));
+                }
+            }
+        }
+
+        lines
+    }
+
+    fn list_margin(large_ui: bool) -> usize {
+        if large_ui { 2 } else { 1 }
+    }
+
+    /// Map a row inside the list body (0 = first row below the header) to a note index.
+    /// In large UI the separator line below a note counts as part of it (bigger click target).
+    pub fn note_index_at_row(app: &App, row: usize) -> Option<usize> {
+        let mut top = 0;
+        for (i, note) in app.filtered_notes.iter().enumerate().skip(app.list_scroll) {
+            let height = Self::note_height(app, note);
+            if row < top + height {
+                return Some(i);
+            }
+            top += height;
+        }
+        None
+    }
+
+    /// Rows one note occupies in the list, separator included
+    fn note_height(app: &App, note: &Note) -> usize {
+        let separator = if app.config.large_ui { 1 } else { 0 };
+        Self::note_row_lines(note, app.config.list_density).len() + separator
+    }
+
+    /// Rows available to list entries for a given terminal height (header, search bar,
+    /// chips, list header, list footer and status bar take the rest)
+    pub fn list_rows(term_height: u16) -> usize {
+        term_height.saturating_sub(Self::PANES_TOP + 3) as usize
+    }
+
+    /// Number of notes that fit completely when the list starts at `scroll`
+    pub fn visible_note_count(app: &App, scroll: usize, rows: usize) -> usize {
+        let mut used = 0;
+        let mut count = 0;
+        for note in app.filtered_notes.iter().skip(scroll) {
+            used += Self::note_height(app, note);
+            if used > rows {
+                break;
+            }
+            count += 1;
+        }
+        count.max(1)
+    }
+
+    /// Smallest change to `scroll` that brings note `index` fully into view
+    pub fn scroll_to_show(app: &App, index: usize, scroll: usize, rows: usize) -> usize {
+        if index < scroll {
+            return index;
+        }
+        let mut scroll = scroll;
+        while scroll < index && index >= scroll + Self::visible_note_count(app, scroll, rows) {
+            scroll += 1;
+        }
+        scroll
+    }
+
     fn render_editor(&mut self, app: &App, start_x: u16, width: u16, start_y: u16, height: u16) -> Result<()> {
         // Render editor header with focus indication
         let is_focused = app.focus_area == FocusArea::Editor;
@@ -242,29 +826,72 @@ impl UI {
             io::stdout(),
             cursor::MoveTo(start_x, start_y),
             SetBackgroundColor(if is_focused {
-                Color::Rgb { r: 40, g: 50, b: 70 }  // Darker blue when focused
+                app.theme.pane_header_focused_bg.to_color()
             } else {
-                Color::Rgb { r: 30, g: 30, b: 30 }
+                app.theme.pane_header_bg.to_color()
             }),
             SetForegroundColor(if is_focused {
-                Color::Rgb { r: 200, g: 200, b: 200 }
+                app.theme.pane_header_focused_fg.to_color()
             } else {
-                Color::Rgb { r: 150, g: 150, b: 150 }
+                app.theme.pane_header_fg.to_color()
             }),
         )?;
 
-        let editor_header = if let Some(ref note) = app.selected_note {
-            format!(" EDITOR - {} ", note.title)
+        let editor_header = if let Some(title) = &app.title_edit {
+            format!(" TITLE: {}▏ (Enter: save  Esc: cancel) ", title)
+        } else if let Some(edit) = &app.annotation_edit {
+            let hint = if edit.id.is_some() { "Enter: save, empty removes it" } else { "Enter: save" };
+            format!(" COMMENT: {}▏ ({}  Esc: cancel) ", edit.text, hint)
+        } else if let Some(edit) = &app.journal_edit {
+            // Entries run long; keep the end being typed in view
+            let room = (width as usize).saturating_sub(40).max(10);
+            let count = edit.text.chars().count();
+            let shown: String = if count > room {
+                format!("…{}", edit.text.chars().skip(count - room + 1).collect::<String>())
+            } else {
+                edit.text.clone()
+            };
+            format!(" JOURNAL: {}▏ (Enter: save  Esc: cancel) ", shown)
+        } else if let Some((from, name)) = &app.tag_rename {
+            format!(" RENAME #{} TO: #{}▏ (Enter: preview  Esc: back) ", from, name)
+        } else if let Some(edit) = &app.field_edit {
+            format!(" FIELD: {}▏ (key: value  Enter: save, no value removes it  Esc: back) ", edit.text)
+        } else if let Some(pattern) = &app.autocode_pattern {
+            format!(" AUTO-CODE: {}▏ (a phrase, or /regex/  Enter: pick the code  Esc: cancel) ", pattern)
+        } else if let Some(ref note) = app.selected_note {
+            let mode = if app.reading { "READING" } else { "EDITOR" };
+            let lock = if app.read_only.contains(&note.id) { " (READ-ONLY)" } else { "" };
+            format!(" {}{} - {} ", mode, lock, note.title)
         } else {
             " EDITOR - No note selected ".to_string()
         };
 
         print!("{:width$}", editor_header, width = width as usize);
 
+        // Text rows between the pane header and footer
+        let text_height = height.saturating_sub(2);
+
+        if app.reading && app.selected_note.is_some() {
+            let footer = self.render_reading(app, start_x, start_y + 1, width, text_height)?;
+            self.render_pane_footer(app, start_x, start_y + 1 + text_height, width, &footer, is_focused)?;
+            execute!(io::stdout(), style::ResetColor)?;
+            return Ok(());
+        }
+
+        // Tag suggestions take the last text row while there are any
+        let suggestions = app.open_tag_suggestions();
+        let text_height = if suggestions.is_empty() || text_height < 2 {
+            text_height
+        } else {
+            self.render_tag_chips(app, suggestions, start_x, start_y + text_height, width)?;
+            text_height - 1
+        };
+
         // Use the EditPanelRenderer for exact chonker7 rendering
-        if let Some(ref _note) = app.selected_note {
-            // Update renderer size if needed
-            self.edit_renderer.resize(width, height - 1);
+        if let Some(ref note) = app.selected_note {
+            // Update renderer size and colors
+            self.edit_renderer.resize(width, text_height);
+            self.edit_renderer.set_theme(&app.theme);
 
             // Update content from the rope
             self.edit_renderer.update_from_rope(&app.editor.rope);
@@ -276,56 +903,496 @@ impl UI {
             // Make cursor follow viewport
             self.edit_renderer.follow_cursor(cursor_col, cursor_line, 3);
 
-            // Don't pass any selection bounds - we only want block selections and cursor
-            let (sel_start, sel_end) = (None, None);
+            // Linear selection (mouse drag, double/triple click, Shift+arrows); the block
+            // selection takes precedence inside the renderer when both exist
+            let (sel_start, sel_end) = match app.editor.selection_coords() {
+                Some((start, end)) => (Some(start), Some(end)),
+                None => (None, None),
+            };
+
+            // Read-time highlights of the open note, drawn under the text
+            let mut marks: Vec<Mark> = app.highlights.for_note(&note.id).unwrap_or_default().iter()
+                .map(|h| (app.editor.char_coords(h.start_offset), app.editor.char_coords(h.end_offset), h.color.rgb()))
+                .collect();
+
+            // Coded segments tinted in their code's color, under the highlights; hidden codes
+            // (Ctrl+G Y) and unreviewed suggestions are left plain
+            for (code_id, start, end, suggested) in app.coded_spans(note) {
+                if suggested || app.hidden_codes.contains(&code_id) {
+                    continue;
+                }
+                let color = app.codes.color_of(&code_id);
+                let tint = app.theme.editor_bg.mix(Rgb::new(color.r, color.g, color.b), CODE_TINT);
+                marks.push((app.editor.char_coords(start), app.editor.char_coords(end), tint));
+            }
+
+            // Annotations are underlined, and flagged in the gutter on the rows they start on
+            let annotations = app.annotations.for_note(&note.id).unwrap_or_default();
+            let underlines: Vec<Underline> = annotations.iter()
+                .map(|a| (app.editor.char_coords(a.start_offset), app.editor.char_coords(a.end_offset)))
+                .collect();
 
             // Render with cursor and selection using exact chonker7 colors (RGB 80,80,200)
             // Use block selection renderer if block selection is active
             self.edit_renderer.render_with_cursor_and_block_selection(
-                start_x, start_y + 1, width, height - 1,
+                start_x, start_y + 1, width, text_height,
                 (cursor_col, cursor_line),
                 app.editor.block_selection.as_ref(),
                 sel_start,
-                sel_end
+                sel_end,
+                &marks,
+                &underlines,
             )?;
+            self.edit_renderer.draw_scrollbars(start_x, start_y + 1, width, text_height)?;
+            self.render_annotation_gutter(&underlines, start_x, start_y + 1, text_height)?;
+            if app.config.code_labels {
+                self.render_code_labels(app, note, start_x, start_y + 1, width, text_height)?;
+            }
         } else {
             // No note selected - clear the editor area
             execute!(
                 io::stdout(),
-                SetBackgroundColor(Color::Black),
-                SetForegroundColor(Color::Rgb { r: 100, g: 100, b: 100 }),
+                SetBackgroundColor(app.theme.editor_bg.to_color()),
+                SetForegroundColor(app.theme.editor_placeholder.to_color()),
             )?;
 
-            for i in 0..height - 1 {
+            for i in 0..text_height {
                 execute!(io::stdout(), cursor::MoveTo(start_x, start_y + 1 + i))?;
                 print!("{:width$}", "", width = width as usize);
             }
         }
 
+        let footer = Self::editor_footer(app).unwrap_or_default();
+        self.render_pane_footer(app, start_x, start_y + 1 + height.saturating_sub(2), width, &footer, is_focused)?;
+
+        execute!(io::stdout(), style::ResetColor)?;
+        Ok(())
+    }
+    /// "Suggested tags: #a #b" chips along the bottom of the editor, with the key that reviews them
+    fn render_tag_chips(&self, app: &App, suggestions: &[Suggestion], x: u16, y: u16, width: u16) -> Result<()> {
+        execute!(
+            io::stdout(),
+            cursor::MoveTo(x, y),
+            SetBackgroundColor(app.theme.search_bg.to_color()),
+            SetForegroundColor(app.theme.list_muted_fg.to_color()),
+        )?;
+        print!("{:width$}", "", width = width as usize);
+        execute!(io::stdout(), cursor::MoveTo(x, y))?;
+        print!(" Suggested tags:");
+
+        let hint = format!("  {}: add / dismiss ", keymap::keys_for(Command::TagSuggestions).join("/"));
+        let mut column = x + 16;
+        for suggestion in suggestions {
+            let label = format!(" #{} ", suggestion.tag);
+            if column + label.width() as u16 + hint.width() as u16 + 1 > x + width {
+                break;
+            }
+            execute!(
+                io::stdout(),
+                cursor::MoveTo(column + 1, y),
+                SetBackgroundColor(app.theme.list_selected_bg.to_color()),
+                SetForegroundColor(app.theme.list_selected_fg.to_color()),
+            )?;
+            print!("{}", label);
+            column += label.width() as u16 + 1;
+        }
+        if column + hint.width() as u16 <= x + width {
+            execute!(
+                io::stdout(),
+                cursor::MoveTo(column, y),
+                SetBackgroundColor(app.theme.search_bg.to_color()),
+                SetForegroundColor(app.theme.list_muted_fg.to_color()),
+            )?;
+            print!("{}", hint);
+        }
+        Ok(())
+    }
+
+    fn render_overlay(&self, app: &App, overlay: &Overlay, width: u16, height: u16) -> Result<()> {
+        let (x, y, box_width, box_height) = Overlay::frame(width, height);
+        let inner_width = box_width.saturating_sub(2) as usize;
+        let visible = Overlay::visible_rows(height);
+        let theme = &app.theme;
+        let large = app.config.large_ui;
+
+        // Large UI draws the frame as solid bars in the border color instead of thin lines
+        let (frame_bg, frame_fg) = if large {
+            (theme.overlay_border, theme.overlay_bg)
+        } else {
+            (theme.overlay_bg, theme.overlay_border)
+        };
+        let glyph = |thin: &'static str| if large { " " } else { thin };
+
+        let border = |row: u16, left: &str, fill: &str, right: &str, text: &str| -> Result<()> {
+            execute!(
+                io::stdout(),
+                cursor::MoveTo(x, row),
+                SetBackgroundColor(frame_bg.to_color()),
+                SetForegroundColor(frame_fg.to_color()),
+            )?;
+            let text = truncate_to_width(text, inner_width);
+            let fill_count = inner_width.saturating_sub(text.width());
+            print!("{}{}{}{}", left, text, fill.repeat(fill_count), right);
+            Ok(())
+        };
+
+        // Top border with title, bottom border with key hints and position
+        border(y, glyph("┌"), glyph("─"), glyph("┐"), &format!(" {} ", overlay.title))?;
+        let position = if overlay.lines.len() > visible {
+            format!(" {}-{}/{} ", overlay.scroll + 1, (overlay.scroll + visible).min(overlay.lines.len()), overlay.lines.len())
+        } else {
+            String::new()
+        };
+        border(y + box_height - 1, glyph("└"), glyph("─"), glyph("┘"), &format!(" Esc: close  ↑↓/PgUp/PgDn: scroll {}", position))?;
+
+        // Blank spacer row under the title, then the content rows
+        for row in 1..box_height.saturating_sub(1) {
+            let index = (row as usize).checked_sub(2).map(|i| i + overlay.scroll);
+            let line = index.and_then(|i| if (row as usize) < visible + 2 { overlay.lines.get(i) } else { None });
+            let text = truncate_to_width(line.map(|l| l.as_str()).unwrap_or(""), inner_width.saturating_sub(2));
+            let padding = inner_width.saturating_sub(text.width() + 1);
+            let is_selected = line.is_some() && index == overlay.selected;
+            let (text_bg, text_fg) = if is_selected {
+                (theme.list_selected_bg, theme.list_selected_fg)
+            } else {
+                (theme.overlay_bg, theme.overlay_fg)
+            };
+
+            execute!(
+                io::stdout(),
+                cursor::MoveTo(x, y + row),
+                SetBackgroundColor(frame_bg.to_color()),
+                SetForegroundColor(frame_fg.to_color()),
+            )?;
+            print!("{}", glyph("│"));
+            execute!(
+                io::stdout(),
+                SetBackgroundColor(text_bg.to_color()),
+                SetForegroundColor(text_fg.to_color()),
+            )?;
+            print!(" {}{:padding$}", text, "", padding = padding);
+            execute!(
+                io::stdout(),
+                SetBackgroundColor(frame_bg.to_color()),
+                SetForegroundColor(frame_fg.to_color()),
+            )?;
+            print!("{}", glyph("│"));
+        }
+
+        execute!(io::stdout(), style::ResetColor)?;
+        Ok(())
+    }
+
+    /// The column left of the editor (the divider's) doubles as its gutter: ◆ on the rows
+    /// where an annotation starts. Zen mode has no column to spare and goes without.
+    fn render_annotation_gutter(&self, underlines: &[Underline], start_x: u16, start_y: u16, height: u16) -> Result<()> {
+        if start_x == 0 || underlines.is_empty() {
+            return Ok(());
+        }
+        let scroll = self.edit_renderer.scroll_y as usize;
+        execute!(io::stdout(), style::ResetColor, SetForegroundColor(annotations::COLOR.to_color()))?;
+        for ((row, _), _) in underlines {
+            if *row >= scroll && *row < scroll + height as usize {
+                execute!(io::stdout(), cursor::MoveTo(start_x - 1, start_y + (row - scroll) as u16))?;
+                print!("◆");
+            }
+        }
+        execute!(io::stdout(), style::ResetColor)?;
+        Ok(())
+    }
+
+    /// ⟨code⟩ labels at the right end of the rows coded segments start on, in the codes'
+    /// colors (a trailing ? marks a machine suggestion). Left out when they don't fit.
+    fn render_code_labels(&self, app: &App, note: &Note, start_x: u16, start_y: u16, width: u16, height: u16) -> Result<()> {
+        let scroll = self.edit_renderer.scroll_y as usize;
+        let mut rows: BTreeMap<usize, Vec<(String, bool)>> = BTreeMap::new();
+        for (code_id, start, _, suggested) in app.coded_spans(note) {
+            let row = app.editor.char_coords(start).0;
+            if row >= scroll && row < scroll + height as usize {
+                let labels = rows.entry(row).or_default();
+                if !labels.iter().any(|(id, _)| *id == code_id) {
+                    labels.push((code_id, suggested));
+                }
+            }
+        }
+
+        // One column in from the edge, clear of the scrollbar
+        let right = (start_x + width).saturating_sub(1) as usize;
+        execute!(io::stdout(), SetBackgroundColor(app.theme.editor_bg.to_color()))?;
+        for (row, labels) in rows {
+            let labels: Vec<(String, Color)> = labels.iter()
+                .map(|(id, suggested)| {
+                    let name = app.codes.get_code(id).map_or(id.as_str(), |c| c.name.as_str());
+                    let label = format!("⟨{}{}⟩", name, if *suggested { "?" } else { "" });
+                    (label, app.codes.color_of(id).to_crossterm_color())
+                })
+                .collect();
+            let total: usize = labels.iter().map(|(label, _)| label.width()).sum();
+            if total + 4 > width as usize {
+                continue;
+            }
+            execute!(io::stdout(), cursor::MoveTo((right - total) as u16, start_y + (row - scroll) as u16))?;
+            for (label, color) in labels {
+                execute!(io::stdout(), SetForegroundColor(color))?;
+                print!("{}", label);
+            }
+        }
+        execute!(io::stdout(), style::ResetColor)?;
+        Ok(())
+    }
+
+    /// The codes on the text under the mouse, in a small box just below the pointer
+    fn render_code_hover(&self, app: &App, column: u16, row: u16, codes: &[(String, bool)], width: u16, height: u16) -> Result<()> {
+        let lines: Vec<(String, Color)> = codes.iter()
+            .map(|(id, suggested)| {
+                let name = app.codes.get_code(id).map_or(id.as_str(), |c| c.name.as_str());
+                let label = format!("● {}{}", name, if *suggested { " (suggested)" } else { "" });
+                (label, app.codes.color_of(id).to_crossterm_color())
+            })
+            .collect();
+        let inner = lines.iter().map(|(l, _)| l.width()).max().unwrap_or(0).min(40);
+        let (box_width, box_height) = (inner as u16 + 4, lines.len() as u16 + 2);
+        if box_width > width || box_height + 1 > height {
+            return Ok(());
+        }
+        let x = (column + 1).min(width - box_width);
+        // Below the pointer, or above it near the bottom (the status bar stays visible)
+        let y = if row + 1 + box_height < height { row + 1 } else { row.saturating_sub(box_height) };
+
+        let theme = &app.theme;
+        execute!(
+            io::stdout(),
+            SetBackgroundColor(theme.overlay_bg.to_color()),
+            SetForegroundColor(theme.overlay_border.to_color()),
+            cursor::MoveTo(x, y),
+        )?;
+        print!("┌{}┐", "─".repeat(inner + 2));
+        for (i, (label, color)) in lines.iter().enumerate() {
+            execute!(io::stdout(), cursor::MoveTo(x, y + 1 + i as u16), SetForegroundColor(theme.overlay_border.to_color()))?;
+            print!("│ ");
+            execute!(io::stdout(), SetForegroundColor(*color))?;
+            let label = truncate_to_width(label, inner);
+            print!("{}{}", label, " ".repeat(inner - label.width()));
+            execute!(io::stdout(), SetForegroundColor(theme.overlay_border.to_color()))?;
+            print!(" │");
+        }
+        execute!(io::stdout(), cursor::MoveTo(x, y + box_height - 1))?;
+        print!("└{}┘", "─".repeat(inner + 2));
+        execute!(io::stdout(), style::ResetColor)?;
+        Ok(())
+    }
+
+    /// Read-only preview of a list entry, in a box at the top of the editor pane
+    fn render_preview(&self, app: &App, title: &str, lines: &[String], editor: Rect) -> Result<()> {
+        let theme = &app.theme;
+        let box_width = editor.width.saturating_sub(4).min(70);
+        let box_height = (lines.len() as u16 + 2).min(editor.height.saturating_sub(2));
+        if box_width < 12 || box_height < 3 {
+            return Ok(());
+        }
+        let (x, y) = (editor.x + 2, editor.y + 1);
+        let inner_width = box_width as usize - 2;
+
+        execute!(
+            io::stdout(),
+            SetBackgroundColor(theme.overlay_bg.to_color()),
+            SetForegroundColor(theme.overlay_border.to_color()),
+        )?;
+        let title = truncate_to_width(&format!(" {} ", title), inner_width);
+        execute!(io::stdout(), cursor::MoveTo(x, y))?;
+        print!("┌{}{}┐", title, "─".repeat(inner_width.saturating_sub(title.width())));
+        let hint = truncate_to_width(" preview ", inner_width);
+        execute!(io::stdout(), cursor::MoveTo(x, y + box_height - 1))?;
+        print!("└{}{}┘", "─".repeat(inner_width.saturating_sub(hint.width())), hint);
+
+        // The phrases being searched for stand out
+        let search = SearchQuery::parse(&app.search_query);
+        let phrases = search.phrases();
+        for row in 1..box_height - 1 {
+            let line = lines.get(row as usize - 1).map_or("", |l| l.as_str());
+            let text = truncate_to_width(line, inner_width.saturating_sub(2));
+            let padding = inner_width.saturating_sub(text.width() + 1);
+            execute!(io::stdout(), cursor::MoveTo(x, y + row), SetForegroundColor(theme.overlay_border.to_color()))?;
+            print!("│");
+            execute!(io::stdout(), SetForegroundColor(theme.overlay_fg.to_color()))?;
+            print!(" ");
+            Self::print_matched(&text, &query::phrase_matches(&text, &phrases))?;
+            print!("{:padding$}", "", padding = padding);
+            execute!(io::stdout(), SetForegroundColor(theme.overlay_border.to_color()))?;
+            print!("│");
+        }
+
         execute!(io::stdout(), style::ResetColor)?;
         Ok(())
     }
+
+    /// One-line notice right-aligned just above the status bar
+    fn render_toast(&self, app: &App, message: &str, width: u16, height: u16) -> Result<()> {
+        if height < 4 || width < 8 {
+            return Ok(());
+        }
+
+        let pad = if app.config.large_ui { "  " } else { " " };
+        let text = truncate_to_width(message, width as usize - 2 - pad.len() * 2);
+        let label = format!("{}{}{}", pad, text, pad);
+        let x = width.saturating_sub(label.width() as u16 + 1);
+
+        execute!(
+            io::stdout(),
+            cursor::MoveTo(x, height - 2),
+            SetBackgroundColor(app.theme.overlay_border.to_color()),
+            SetForegroundColor(app.theme.overlay_bg.to_color()),
+        )?;
+        print!("{}", label);
+        execute!(io::stdout(), style::ResetColor)?;
+        Ok(())
+    }
+
+    /// Reading mode: the note wrapped between margins, with images drawn where the terminal
+    /// can show them and the whole image is on screen; returns the pane footer text
+    fn render_reading(&self, app: &App, start_x: u16, start_y: u16, width: u16, height: u16) -> Result<String> {
+        let text_width = Self::reading_text_width(width);
+        let rows = reading::layout(&app.editor.get_text(), text_width, app.config.line_spacing, app.image_rows());
+        let scroll = app.reading_scroll.min(rows.len().saturating_sub(height as usize));
+        let mut pictures = Vec::new();
+
+        execute!(
+            io::stdout(),
+            SetBackgroundColor(app.theme.editor_bg.to_color()),
+            SetForegroundColor(app.theme.editor_fg.to_color()),
+        )?;
+        let margin = Self::READING_MARGIN as usize;
+        for i in 0..height {
+            execute!(io::stdout(), cursor::MoveTo(start_x, start_y + i))?;
+            let row = rows.get(scroll + i as usize);
+            let mut text = row.map(|r| r.text.clone()).unwrap_or_default();
+
+            if let Some(src) = row.and_then(|r| r.image.as_deref()) {
+                let problem = match images::resolve(&app.notes_dir, src) {
+                    None => Some("not found"),
+                    Some(path) => {
+                        let problem = images::unsupported(app.image_protocol, &path);
+                        let fits = i as usize + images::IMAGE_ROWS < height as usize;
+                        if problem.is_none() && fits && app.overlay.is_none() {
+                            pictures.push((start_y + i + 1, path));
+                        }
+                        problem
+                    }
+                };
+                if let Some(problem) = problem {
+                    text = truncate_to_width(&format!("{} {}", text, problem), text_width);
+                }
+            }
+            let pad = (width as usize).saturating_sub(margin + text.width());
+            print!("{:margin$}{}{:pad$}", "", text, "", margin = margin, pad = pad);
+        }
+        execute!(io::stdout(), style::ResetColor)?;
+
+        // Drawn last: each image covers the blank rows the layout left under its caption
+        for (y, path) in pictures {
+            execute!(io::stdout(), cursor::MoveTo(start_x + margin as u16, y))?;
+            if let Err(e) = images::draw(app.image_protocol, &path, text_width as u16, images::IMAGE_ROWS as u16) {
+                tracing::warn!(error = %e, path = %path.display(), "inline image failed");
+            }
+        }
+
+        let shown = (scroll + height as usize).min(rows.len());
+        let percent = if rows.is_empty() { 100 } else { shown * 100 / rows.len() };
+        Ok(format!("Reading · {} · {}% · F7: edit", app.config.line_spacing.name(), percent))
+    }
+
+    /// "Ln 12, Col 5 (40%) · 230 words, 1402 chars · Trust, Money?" for the open note.
+    /// Imported PDFs add the page (· p. 3), transcripts the timestamp (· 00:12:34), coded
+    /// projects the coverage (· 42% coded to ln 120). Codes covering the cursor come last;
+    /// a trailing ? marks a machine suggestion.
+    fn editor_footer(app: &App) -> Option<String> {
+        let note = app.selected_note.as_ref()?;
+        let rope = &app.editor.rope;
+        let lines = rope.len_lines().max(1);
+        let line = app.editor.cursor_pos.row + 1;
+        let percent = line * 100 / lines;
+        let stats = TextStats::of(rope.chars());
+
+        let mut footer = format!(
+            "Ln {}, Col {} ({}%) · {} words, {} chars",
+            line,
+            app.editor.cursor_pos.col + 1,
+            percent,
+            stats.words,
+            stats.chars,
+        );
+
+        // Imported PDFs: the page the cursor is on; transcripts: the last timestamp above it
+        let (mut page, mut time) = (None, None);
+        for text in rope.lines().take(line).map(|l| l.to_string()) {
+            page = pdf_import::marker_page(&text).or(page);
+            time = transcript::last_time(&text).or(time);
+        }
+        if let Some(page) = page {
+            footer.push_str(&format!(" · p. {}", page));
+        }
+        if let Some(time) = time {
+            footer.push_str(&format!(" · {}", transcript::format_time(time)));
+        }
+        if let Some(coverage) = app.open_note_coverage(note) {
+            footer.push_str(&format!(" · {}", coverage.label()));
+        }
+
+        let mut codes: Vec<String> = Vec::new();
+        for (code_id, suggested) in app.codes_at_cursor(note) {
+            let name = app.codes.get_code(&code_id).map(|c| c.name.as_str()).unwrap_or(&code_id);
+            let label = if suggested { format!("{}?", name) } else { name.to_string() };
+            if !codes.contains(&label) {
+                codes.push(label);
+            }
+        }
+        if !codes.is_empty() {
+            footer.push_str(&format!(" · {}", codes.join(", ")));
+        }
+
+        Some(footer)
+    }
+
     fn render_status_bar(&self, app: &App, width: u16, height: u16) -> Result<()> {
         execute!(
             io::stdout(),
             cursor::MoveTo(0, height - 1),
-            SetBackgroundColor(Color::Rgb { r: 40, g: 40, b: 40 }),
-            SetForegroundColor(Color::Rgb { r: 180, g: 180, b: 180 }),
+            SetBackgroundColor(app.theme.status_bg.to_color()),
+            SetForegroundColor(app.theme.status_fg.to_color()),
         )?;
 
-        let left_status = format!(" {} ", app.status_message);
+        let pad = if app.config.large_ui { "  " } else { " " };
+        let message = app.messages.visible();
+        let left_status = format!("{}{}{}", pad, message.map_or("", |m| m.text.as_str()), pad);
 
-        let shortcuts = match app.focus_area {
-            FocusArea::SearchBar => "ESC/Enter/↓: Exit Search | Type to filter notes",
-            FocusArea::NoteList => "^Q: Quit | ^N: New | ^L/^F: Search | Enter/→: Edit | ^D: Delete | Tab: Switch Focus",
-            FocusArea::Editor => "ESC/←: Back to List | ^X: Cut | ^C: Copy | ^V: Paste | ^A: Select All | Tab: Switch Focus",
+        // Pane details live in the pane footers; the right side is for background work
+        let selection = app.editor.get_selection().filter(|_| app.focus_area == FocusArea::Editor);
+        let jobs = if app.journal.pending() > 0 {
+            format!("{} edits waiting to save", app.journal.pending())
+        } else if let Some((done, total)) = app.index_job.as_ref().map(|job| job.progress()) {
+            format!("Indexing {}/{} notes", done, total)
+        } else if let Some(text) = selection {
+            let stats = TextStats::of(text.chars());
+            format!("{} words, {} chars selected · Alt+A: actions", stats.words, stats.chars)
+        } else {
+            "F1: Help".to_string()
         };
+        let mut right_status = format!("{}{}{}", pad, jobs, pad);
+        if left_status.width() + right_status.width() > width as usize {
+            right_status.clear();
+        }
 
-        let right_status = format!(" {} ", shortcuts);
-
+        // Warnings and errors stand out; info keeps the bar's own color
+        let fg = match message.map(|m| m.level) {
+            Some(Level::Warn) => app.theme.status_warn_fg,
+            Some(Level::Error) => app.theme.status_error_fg,
+            _ => app.theme.status_fg,
+        };
+        execute!(io::stdout(), SetForegroundColor(fg.to_color()))?;
         print!("{}", left_status);
+        execute!(io::stdout(), SetForegroundColor(app.theme.status_fg.to_color()))?;
 
-        let padding = width as usize - left_status.width() - right_status.width();
+        let padding = (width as usize).saturating_sub(left_status.width() + right_status.width());
         if padding > 0 {
             print!("{:width$}", "", width = padding);
         }
@@ -335,4 +1402,27 @@ impl UI {
         execute!(io::stdout(), style::ResetColor)?;
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Truncate text to a display width, marking the cut with "..."
+fn truncate_to_width(text: &str, max_width: usize) -> String {
+    if text.width() <= max_width {
+        return text.to_string();
+    }
+    if max_width < 3 {
+        return text.chars().take(max_width).collect();
+    }
+
+    let mut result = String::new();
+    let mut used = 0;
+    for ch in text.chars() {
+        let w = unicode_width::UnicodeWidthChar::width(ch).unwrap_or(0);
+        if used + w + 3 > max_width {
+            break;
+        }
+        result.push(ch);
+        used += w;
+    }
+    result.push_str("...");
+    result
+}
@@ -0,0 +1,181 @@
+// Editor undo history - whole-text snapshots, persisted per note under undo/
+//
+// Ropes share structure, so a snapshot per edit group is cheap in memory.
+// Runs of typing (or of deleting) within COALESCE_WINDOW form one step. On
+// disk the history only comes back if the note still has the text it had when
+// the history was written; anything edited elsewhere starts fresh.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use helix_core::Rope;
+use serde::{Serialize, Deserialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const COALESCE_WINDOW: Duration = Duration::from_secs(1);
+const MAX_STEPS: usize = 200;                    // Per direction, per note
+const MAX_BYTES: usize = 2 * 1024 * 1024;        // Snapshot text kept on disk per note
+const MAX_AGE_HOURS: i64 = 24;                   // "Earlier today" - older steps are pruned
+
+/// What an edit did, for grouping keystrokes into undo steps
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EditKind {
+    Typing,
+    Deleting,
+    Other,  // Paste, cut, newline... always its own step
+}
+
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub text: Rope,
+    pub cursor: usize,  // Char position to put the cursor back to
+    pub at: DateTime<Utc>,
+}
+
+impl Snapshot {
+    pub fn new(text: &Rope, cursor: usize) -> Self {
+        Snapshot { text: text.clone(), cursor, at: Utc::now() }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct UndoHistory {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    last_edit: Option<(EditKind, Instant)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredSnapshot {
+    text: String,
+    cursor: usize,
+    at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredHistory {
+    text: String,  // Note content the history leads up to
+    undo: Vec<StoredSnapshot>,
+    redo: Vec<StoredSnapshot>,
+}
+
+impl UndoHistory {
+    /// Remember the state before an edit, merging it into the previous step when it continues a run
+    pub fn record(&mut self, before: Snapshot, kind: EditKind) {
+        let now = Instant::now();
+        let continues = kind != EditKind::Other
+            && self.last_edit.is_some_and(|(last, at)| last == kind && now.duration_since(at) < COALESCE_WINDOW);
+        self.last_edit = Some((kind, now));
+        self.redo.clear();
+        if continues && !self.undo.is_empty() {
+            return;
+        }
+        self.undo.push(before);
+        if self.undo.len() > MAX_STEPS {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Step back; `current` goes onto the redo stack
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        self.last_edit = None;
+        Some(previous)
+    }
+
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        self.last_edit = None;
+        Some(next)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.undo.is_empty() && self.redo.is_empty()
+    }
+
+    /// Load a note's history, or an empty one if there is none or the note changed since
+    pub fn load(notes_dir: &Path, note_id: &str, current_text: &str) -> Self {
+        let stored: Option<StoredHistory> = std::fs::read_to_string(history_path(notes_dir, note_id))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok());
+        let Some(stored) = stored.filter(|s| s.text == current_text) else {
+            return UndoHistory::default();
+        };
+
+        let cutoff = Utc::now() - chrono::Duration::hours(MAX_AGE_HOURS);
+        let restore = |list: Vec<StoredSnapshot>| -> Vec<Snapshot> {
+            list.into_iter()
+                .filter(|s| s.at >= cutoff)
+                .map(|s| Snapshot { text: Rope::from_str(&s.text), cursor: s.cursor, at: s.at })
+                .collect()
+        };
+        UndoHistory {
+            undo: restore(stored.undo),
+            redo: restore(stored.redo),
+            last_edit: None,
+        }
+    }
+
+    /// Write the history for a note, dropping steps past the age, count and size caps (oldest first)
+    pub fn save(&self, notes_dir: &Path, note_id: &str, current_text: &str) -> Result<()> {
+        let path = history_path(notes_dir, note_id);
+        if self.is_empty() {
+            if path.exists() {
+                std::fs::remove_file(&path)?;
+            }
+            return Ok(());
+        }
+
+        let cutoff = Utc::now() - chrono::Duration::hours(MAX_AGE_HOURS);
+        let mut budget = MAX_BYTES;
+        let mut keep = |list: &[Snapshot]| -> Vec<StoredSnapshot> {
+            // Newest steps matter most, so spend the budget from the top of the stack
+            let mut kept: Vec<StoredSnapshot> = list.iter().rev()
+                .filter(|s| s.at >= cutoff)
+                .take(MAX_STEPS)
+                .map_while(|s| {
+                    let text = s.text.to_string();
+                    budget = budget.checked_sub(text.len())?;
+                    Some(StoredSnapshot { text, cursor: s.cursor, at: s.at })
+                })
+                .collect();
+            kept.reverse();
+            kept
+        };
+        let stored = StoredHistory {
+            text: current_text.to_string(),
+            undo: keep(&self.undo),
+            redo: keep(&self.redo),
+        };
+
+        std::fs::create_dir_all(path.parent().unwrap_or(notes_dir))?;
+        std::fs::write(&path, serde_json::to_string(&stored)?)?;
+        Ok(())
+    }
+}
+
+fn history_path(notes_dir: &Path, note_id: &str) -> PathBuf {
+    notes_dir.join("undo").join(format!("{}.json", note_id))
+}
+
+/// Delete history files nobody has touched within MAX_AGE_HOURS; returns how many went
+pub fn prune(notes_dir: &Path) -> Result<usize> {
+    let Ok(entries) = std::fs::read_dir(notes_dir.join("undo")) else {
+        return Ok(0);
+    };
+    let max_age = Duration::from_secs(MAX_AGE_HOURS as u64 * 3600);
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let stale = entry.metadata()
+            .and_then(|m| m.modified())
+            .map(|modified| modified.elapsed().unwrap_or_default() > max_age)
+            .unwrap_or(false);
+        if stale {
+            std::fs::remove_file(entry.path())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
@@ -0,0 +1,150 @@
+// Opt-in local usage counters (never leaves the notes directory)
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::keymap::{self, Command};
+
+// Write to disk every this many events (and on exit)
+const SAVE_EVERY: u32 = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageData {
+    pub since: DateTime<Utc>,
+    pub commands: BTreeMap<String, u64>,  // Command name -> times run
+    pub features: BTreeMap<String, u64>,  // Feature counters (searches, notes created, ...)
+}
+
+impl Default for UsageData {
+    fn default() -> Self {
+        UsageData {
+            since: Utc::now(),
+            commands: BTreeMap::new(),
+            features: BTreeMap::new(),
+        }
+    }
+}
+
+pub struct Usage {
+    path: PathBuf,
+    pub enabled: bool,
+    data: UsageData,
+    unsaved: u32,
+}
+
+impl Usage {
+    pub fn open(notes_dir: &Path, enabled: bool) -> Self {
+        let path = notes_dir.join("usage.json");
+        let data = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        Usage { path, enabled, data, unsaved: 0 }
+    }
+
+    pub fn record_command(&mut self, command: Command) {
+        if self.enabled {
+            *self.data.commands.entry(format!("{:?}", command)).or_insert(0) += 1;
+            self.bump();
+        }
+    }
+
+    pub fn record_feature(&mut self, feature: &str) {
+        if self.enabled {
+            *self.data.features.entry(feature.to_string()).or_insert(0) += 1;
+            self.bump();
+        }
+    }
+
+    fn bump(&mut self) {
+        self.unsaved += 1;
+        if self.unsaved >= SAVE_EVERY {
+            // Counters are a convenience - a failed write shouldn't interrupt editing
+            let _ = self.save();
+        }
+    }
+
+    pub fn save(&mut self) -> Result<()> {
+        if self.unsaved == 0 {
+            return Ok(());
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&self.data)?)?;
+        self.unsaved = 0;
+        Ok(())
+    }
+
+    pub fn reset(&mut self) -> Result<()> {
+        self.data = UsageData::default();
+        self.unsaved = 0;
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Lines for the usage report overlay
+    pub fn report_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if !self.enabled {
+            lines.push("Usage insights are off. Nothing is recorded until you turn them on.".to_string());
+            lines.push("Counts stay in usage.json in your notes directory and are never sent anywhere.".to_string());
+            lines.push(String::new());
+            lines.push("e: enable".to_string());
+            return lines;
+        }
+
+        lines.push(format!(
+            "Since {}  (stored locally in usage.json)",
+            self.data.since.with_timezone(&chrono::Local).format("%Y-%m-%d")
+        ));
+        lines.push(String::new());
+
+        lines.push("── Commands ──".to_string());
+        let commands = keymap::bound_commands();
+        let mut used: Vec<(Command, u64)> = commands.iter()
+            .filter_map(|&c| self.data.commands.get(&format!("{:?}", c)).map(|&n| (c, n)))
+            .collect();
+        used.sort_by(|a, b| b.1.cmp(&a.1));
+        if used.is_empty() {
+            lines.push("  (none yet)".to_string());
+        }
+        for (command, count) in &used {
+            lines.push(format!(
+                "  {:>6}  {:<40} {}",
+                count,
+                command.description(),
+                keymap::keys_for(*command).join(" / ")
+            ));
+        }
+
+        if !self.data.features.is_empty() {
+            lines.push(String::new());
+            lines.push("── Features ──".to_string());
+            let mut features: Vec<_> = self.data.features.iter().collect();
+            features.sort_by(|a, b| b.1.cmp(a.1));
+            for (feature, count) in features {
+                lines.push(format!("  {:>6}  {}", count, feature));
+            }
+        }
+
+        let unused: Vec<Command> = commands.into_iter()
+            .filter(|c| !self.data.commands.contains_key(&format!("{:?}", c)))
+            .collect();
+        if !unused.is_empty() {
+            lines.push(String::new());
+            lines.push("── Never used ──".to_string());
+            for command in unused {
+                lines.push(format!("  {:<48} {}", command.description(), keymap::keys_for(command).join(" / ")));
+            }
+        }
+
+        lines.push(String::new());
+        lines.push("e: disable   r: reset counts".to_string());
+        lines
+    }
+}
@@ -0,0 +1,178 @@
+// Word frequencies and keyword in context (KWIC) across a set of notes
+//
+// Ctrl+G W counts the words of the marked notes (or, with none marked, the notes the
+// list shows): lowercased, front matter and markup left out, and without the stopwords
+// below plus any listed under "stopwords" in config.json. Enter on a word opens its
+// concordance - every occurrence with the text around it, lined up on the word - and
+// Enter on one of those jumps to it in its note.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::front_matter::parse_front_matter;
+use crate::note_store::Note;
+
+/// Characters of context on each side of a concordance hit
+pub const CONTEXT: usize = 32;
+
+/// Common English function words
+const STOPWORDS: &[&str] = &[
+    "a", "about", "above", "after", "again", "against", "all", "also", "am", "an", "and", "any",
+    "are", "aren't", "as", "at", "be", "because", "been", "before", "being", "below", "between",
+    "both", "but", "by", "can", "can't", "could", "couldn't", "did", "didn't", "do", "does",
+    "doesn't", "doing", "don't", "down", "during", "each", "even", "ever", "few", "for", "from",
+    "further", "get", "got", "had", "hadn't", "has", "hasn't", "have", "haven't", "having", "he",
+    "he'd", "he'll", "he's", "her", "here", "hers", "herself", "him", "himself", "his", "how",
+    "i", "i'd", "i'll", "i'm", "i've", "if", "in", "into", "is", "isn't", "it", "it's", "its",
+    "itself", "just", "let's", "like", "me", "more", "most", "much", "my", "myself", "no", "nor",
+    "not", "now", "of", "off", "on", "once", "only", "or", "other", "our", "ours", "ourselves",
+    "out", "over", "own", "really", "said", "same", "she", "she'd", "she'll", "she's", "should",
+    "shouldn't", "so", "some", "such", "than", "that", "that's", "the", "their", "theirs",
+    "them", "themselves", "then", "there", "there's", "these", "they", "they'd", "they'll",
+    "they're", "they've", "this", "those", "through", "to", "too", "under", "until", "up",
+    "very", "was", "wasn't", "we", "we'd", "we'll", "we're", "we've", "well", "were", "weren't",
+    "what", "what's", "when", "where", "which", "while", "who", "whom", "why", "will", "with",
+    "won't", "would", "wouldn't", "yeah", "yes", "you", "you'd", "you'll", "you're", "you've",
+    "your", "yours", "yourself", "yourselves",
+];
+
+#[derive(Debug, Clone)]
+pub struct WordCount {
+    pub word: String,
+    pub count: usize,
+    pub notes: usize,  // Notes it occurs in
+}
+
+/// One occurrence of a word, with its surroundings
+#[derive(Debug, Clone)]
+pub struct Hit {
+    pub note_id: String,
+    pub note_title: String,
+    pub start: usize,  // Char offsets of the word in the note content
+    pub end: usize,
+    pub text: String,  // The word as written
+    pub left: String,
+    pub right: String,
+}
+
+/// Words of a text with their char offsets; apostrophes inside a word are kept ("don't")
+fn tokens(text: &str) -> Vec<(usize, usize, String)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if !chars[i].is_alphanumeric() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < chars.len()
+            && (chars[i].is_alphanumeric()
+                || (matches!(chars[i], '\'' | '’') && chars.get(i + 1).is_some_and(|c| c.is_alphabetic())))
+        {
+            i += 1;
+        }
+        let word: String = chars[start..i].iter().collect::<String>().replace('’', "'").to_lowercase();
+        out.push((start, i, word));
+    }
+    out
+}
+
+/// The text a note's words are taken from: everything after the front matter, and the
+/// char offset it starts at
+fn body(note: &Note) -> (usize, &str) {
+    match parse_front_matter(&note.content) {
+        Some(fm) => (note.content[..fm.body_start].chars().count(), &note.content[fm.body_start..]),
+        None => (0, note.content.as_str()),
+    }
+}
+
+fn counted(word: &str, stopwords: &HashSet<String>) -> bool {
+    word.chars().count() > 1 && word.chars().any(|c| c.is_alphabetic()) && !stopwords.contains(word)
+}
+
+fn stopwords(extra: &[String]) -> HashSet<String> {
+    STOPWORDS.iter().map(|w| w.to_string())
+        .chain(extra.iter().map(|w| w.to_lowercase()))
+        .collect()
+}
+
+/// How often each counted word occurs in one note
+pub fn counts(note: &Note, extra_stopwords: &[String]) -> HashMap<String, usize> {
+    let stopwords = stopwords(extra_stopwords);
+    let mut counts = HashMap::new();
+    for (_, _, word) in tokens(body(note).1) {
+        if counted(&word, &stopwords) {
+            *counts.entry(word).or_default() += 1;
+        }
+    }
+    counts
+}
+
+/// Word counts over `notes`, most frequent first (ties alphabetically)
+pub fn frequencies(notes: &[&Note], extra_stopwords: &[String]) -> Vec<WordCount> {
+    let stopwords = stopwords(extra_stopwords);
+    let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+    for note in notes {
+        let mut seen = HashSet::new();
+        for (_, _, word) in tokens(body(note).1) {
+            if !counted(&word, &stopwords) {
+                continue;
+            }
+            let entry = counts.entry(word.clone()).or_default();
+            entry.0 += 1;
+            if seen.insert(word) {
+                entry.1 += 1;
+            }
+        }
+    }
+    let mut words: Vec<WordCount> = counts.into_iter()
+        .map(|(word, (count, notes))| WordCount { word, count, notes })
+        .collect();
+    words.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.word.cmp(&b.word)));
+    words
+}
+
+/// Every occurrence of `word` in `notes`, in note order, with CONTEXT chars either side
+/// (line breaks shown as spaces)
+pub fn concordance(notes: &[&Note], word: &str) -> Vec<Hit> {
+    let mut hits = Vec::new();
+    for note in notes {
+        let (offset, text) = body(note);
+        let chars: Vec<char> = text.chars().collect();
+        let flat = |from: usize, to: usize| -> String {
+            chars[from..to].iter().map(|&c| if c.is_whitespace() { ' ' } else { c }).collect()
+        };
+        for (start, end, token) in tokens(text) {
+            if token != word {
+                continue;
+            }
+            hits.push(Hit {
+                note_id: note.id.clone(),
+                note_title: note.title.clone(),
+                start: offset + start,
+                end: offset + end,
+                text: chars[start..end].iter().collect(),
+                left: flat(start.saturating_sub(CONTEXT), start),
+                right: flat(end, (end + CONTEXT).min(chars.len())),
+            });
+        }
+    }
+    hits
+}
+
+pub fn frequency_lines(words: &[WordCount]) -> Vec<String> {
+    words.iter()
+        .map(|w| format!("{:>6}  {:<24} in {} note(s)", w.count, w.word, w.notes))
+        .collect()
+}
+
+/// Concordance rows, the word in the same column on every line
+pub fn concordance_lines(hits: &[Hit]) -> Vec<String> {
+    hits.iter()
+        .map(|h| {
+            let title: String = h.note_title.chars().take(20).collect();
+            format!("{:>width$} [{}] {:<width$}  {}", h.left, h.text, h.right, title, width = CONTEXT)
+        })
+        .collect()
+}
+
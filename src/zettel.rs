@@ -0,0 +1,36 @@
+// Zettelkasten IDs - timestamp IDs that links can use instead of titles
+//
+// With "zettel_ids": true in config.json every note gets an ID from the minute it was
+// created, in local time ("202403151030"); a second note from the same minute takes
+// the seconds too ("20240315103042"), then a "-2", "-3"... suffix. IDs are stored with
+// the notes (notes.db) and never change, so `[[202403151030]]` keeps pointing at its
+// note whatever the note is renamed to. The list shows each note's ID before its title
+// and typing an ID (or its start) in the search bar brings up the notes it names.
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Local, Utc};
+
+/// A fresh ID for a note created at `created_at`, not among `taken`
+pub fn generate(created_at: DateTime<Utc>, taken: &HashSet<&str>) -> String {
+    let local = created_at.with_timezone(&Local);
+    let minute = local.format("%Y%m%d%H%M").to_string();
+    if !taken.contains(minute.as_str()) {
+        return minute;
+    }
+    let second = local.format("%Y%m%d%H%M%S").to_string();
+    let mut id = second.clone();
+    let mut n = 1;
+    while taken.contains(id.as_str()) {
+        n += 1;
+        id = format!("{}-{}", second, n);
+    }
+    id
+}
+
+/// Search text that can be (the start of) an ID: at least four digits, nothing else
+pub fn is_id_prefix(text: &str) -> bool {
+    let text = text.trim();
+    text.len() >= 4 && text.chars().all(|c| c.is_ascii_digit() || c == '-')
+        && text.starts_with(|c: char| c.is_ascii_digit())
+}